@@ -153,6 +153,7 @@ impl<B: Backend> RendererState<B> {
                     count: 1,
                     stage_flags: pso::ShaderStageFlags::FRAGMENT,
                     immutable_samplers: false,
+                    flags: pso::DescriptorBindingFlags::empty(),
                 },
                 pso::DescriptorSetLayoutBinding {
                     binding: 1,
@@ -160,6 +161,7 @@ impl<B: Backend> RendererState<B> {
                     count: 1,
                     stage_flags: pso::ShaderStageFlags::FRAGMENT,
                     immutable_samplers: false,
+                    flags: pso::DescriptorBindingFlags::empty(),
                 },
             ],
         );
@@ -177,6 +179,7 @@ impl<B: Backend> RendererState<B> {
                 count: 1,
                 stage_flags: pso::ShaderStageFlags::FRAGMENT,
                 immutable_samplers: false,
+                flags: pso::DescriptorBindingFlags::empty(),
             }],
         );
 
@@ -442,8 +445,12 @@ impl<B: Backend> RendererState<B> {
 
             let submission = Submission {
                 command_buffers: iter::once(&cmd_buffer),
-                wait_semaphores: iter::once((&*image_acquired, pso::PipelineStage::BOTTOM_OF_PIPE)),
-                signal_semaphores: iter::once(&*image_present),
+                wait_semaphores: iter::once((
+                    &*image_acquired,
+                    0,
+                    pso::PipelineStage::BOTTOM_OF_PIPE,
+                )),
+                signal_semaphores: iter::once((&*image_present, 0)),
             };
 
             self.device.borrow_mut().queues.queues[0].submit(submission, Some(framebuffer_fence));
@@ -686,12 +693,13 @@ impl<B: Backend> RenderPassState<B> {
                 inputs: &[],
                 resolves: &[],
                 preserves: &[],
+                view_mask: 0,
             };
 
             device
                 .borrow()
                 .device
-                .create_render_pass(&[attachment], &[subpass], &[])
+                .create_render_pass(&[attachment], &[subpass], &[], &[])
                 .ok()
         };
 
@@ -742,7 +750,9 @@ impl<B: Backend> BufferState<B> {
         {
             let device = &device_ptr.borrow().device;
 
-            buffer = device.create_buffer(upload_size as u64, usage).unwrap();
+            buffer = device
+                .create_buffer(upload_size as u64, usage, m::SparseFlags::empty())
+                .unwrap();
             let mem_req = device.get_buffer_requirements(&buffer);
 
             // A note about performance: Using CPU_VISIBLE memory is convenient because it can be
@@ -821,7 +831,9 @@ impl<B: Backend> BufferState<B> {
         let size: u64;
 
         {
-            buffer = device.create_buffer(upload_size, usage).unwrap();
+            buffer = device
+                .create_buffer(upload_size, usage, m::SparseFlags::empty())
+                .unwrap();
             let mem_reqs = device.get_buffer_requirements(&buffer);
 
             let upload_type = adapter
@@ -939,7 +951,11 @@ impl<B: Backend> DescSetLayout<B> {
         let desc_set_layout = device
             .borrow()
             .device
-            .create_descriptor_set_layout(bindings, &[])
+            .create_descriptor_set_layout(
+                bindings,
+                &[],
+                pso::DescriptorSetLayoutCreateFlags::empty(),
+            )
             .ok();
 
         DescSetLayout {
@@ -1045,6 +1061,7 @@ impl<B: Backend> ImageState<B> {
                 i::Tiling::Optimal,
                 i::Usage::TRANSFER_DST | i::Usage::SAMPLED,
                 i::ViewCapabilities::empty(),
+                m::SparseFlags::empty(),
             )
             .unwrap(); // TODO: usage
         let req = device.get_image_requirements(&image);
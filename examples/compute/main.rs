@@ -87,8 +87,10 @@ fn main() {
                     count: 1,
                     stage_flags: pso::ShaderStageFlags::COMPUTE,
                     immutable_samplers: false,
+                    flags: pso::DescriptorBindingFlags::empty(),
                 }],
                 &[],
+                pso::DescriptorSetLayoutCreateFlags::empty(),
             )
         }
         .expect("Can't create descriptor set layout");
@@ -268,7 +270,9 @@ unsafe fn create_buffer<B: hal::Backend>(
     stride: u64,
     len: u64,
 ) -> (B::Memory, B::Buffer, u64) {
-    let mut buffer = device.create_buffer(stride * len, usage).unwrap();
+    let mut buffer = device
+        .create_buffer(stride * len, usage, memory::SparseFlags::empty())
+        .unwrap();
     let requirements = device.get_buffer_requirements(&buffer);
 
     let ty = memory_types
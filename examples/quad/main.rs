@@ -272,6 +272,7 @@ where
                             count: 1,
                             stage_flags: ShaderStageFlags::FRAGMENT,
                             immutable_samplers: false,
+                            flags: pso::DescriptorBindingFlags::empty(),
                         },
                         pso::DescriptorSetLayoutBinding {
                             binding: 1,
@@ -279,9 +280,11 @@ where
                             count: 1,
                             stage_flags: ShaderStageFlags::FRAGMENT,
                             immutable_samplers: false,
+                            flags: pso::DescriptorBindingFlags::empty(),
                         },
                     ],
                     &[],
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
                 )
             }
             .expect("Can't create descriptor set layout"),
@@ -325,7 +328,14 @@ where
             * non_coherent_alignment;
 
         let mut vertex_buffer = ManuallyDrop::new(
-            unsafe { device.create_buffer(padded_buffer_len, buffer::Usage::VERTEX) }.unwrap(),
+            unsafe {
+                device.create_buffer(
+                    padded_buffer_len,
+                    buffer::Usage::VERTEX,
+                    m::SparseFlags::empty(),
+                )
+            }
+            .unwrap(),
         );
 
         let buffer_req = unsafe { device.get_buffer_requirements(&vertex_buffer) };
@@ -377,8 +387,14 @@ where
             * non_coherent_alignment;
 
         let mut image_upload_buffer = ManuallyDrop::new(
-            unsafe { device.create_buffer(padded_upload_size, buffer::Usage::TRANSFER_SRC) }
-                .unwrap(),
+            unsafe {
+                device.create_buffer(
+                    padded_upload_size,
+                    buffer::Usage::TRANSFER_SRC,
+                    m::SparseFlags::empty(),
+                )
+            }
+            .unwrap(),
         );
         let image_mem_reqs = unsafe { device.get_buffer_requirements(&image_upload_buffer) };
 
@@ -416,6 +432,7 @@ where
                     i::Tiling::Optimal,
                     i::Usage::TRANSFER_DST | i::Usage::SAMPLED,
                     i::ViewCapabilities::empty(),
+                    m::SparseFlags::empty(),
                 )
             }
             .unwrap(),
@@ -583,10 +600,11 @@ where
                 inputs: &[],
                 resolves: &[],
                 preserves: &[],
+                view_mask: 0,
             };
 
             ManuallyDrop::new(
-                unsafe { device.create_render_pass(&[attachment], &[subpass], &[]) }
+                unsafe { device.create_render_pass(&[attachment], &[subpass], &[], &[]) }
                     .expect("Can't create render pass"),
             )
         };
@@ -873,7 +891,7 @@ where
             let submission = Submission {
                 command_buffers: iter::once(&*cmd_buffer),
                 wait_semaphores: None,
-                signal_semaphores: iter::once(&self.submission_complete_semaphores[frame_idx]),
+                signal_semaphores: iter::once((&self.submission_complete_semaphores[frame_idx], 0)),
             };
             self.queue_group.queues[0].submit(
                 submission,
@@ -0,0 +1,805 @@
+use crate::{BufferId, GraphBuilder, ImageId, PassDesc, PassId};
+use gfx_memory::{AllocationError, Allocator, Block, Strategy};
+use gfx_track::{BufferBarrier, CommandTracker, ImageBarrier, ImageState};
+use hal::{
+    command::{ClearValue, CommandBuffer as _, SubpassContents},
+    device::{self, Device as _, OutOfMemory},
+    format,
+    image,
+    memory::{self, Barrier, Dependencies},
+    pass,
+    pso::{self, PipelineStage},
+    Backend,
+};
+
+use std::{collections::HashMap, ops::Range};
+
+/// Error building a graph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// The attachments of a pass don't all have a single mip level and the same extent,
+    /// or are also used otherwise by the pass.
+    InvalidAttachments(PassId),
+    /// Out of either host or device memory.
+    OutOfMemory(OutOfMemory),
+    /// Memory for the transient images couldn't be allocated.
+    Allocation(AllocationError),
+    /// A transient image couldn't be created.
+    ImageCreation(image::CreationError),
+    /// A view of a transient image couldn't be created.
+    ViewCreation(image::ViewCreationError),
+    /// A transient image couldn't be bound to its memory.
+    Bind(device::BindError),
+}
+
+impl From<OutOfMemory> for BuildError {
+    fn from(error: OutOfMemory) -> Self {
+        BuildError::OutOfMemory(error)
+    }
+}
+
+impl From<AllocationError> for BuildError {
+    fn from(error: AllocationError) -> Self {
+        BuildError::Allocation(error)
+    }
+}
+
+impl From<image::CreationError> for BuildError {
+    fn from(error: image::CreationError) -> Self {
+        BuildError::ImageCreation(error)
+    }
+}
+
+impl From<image::ViewCreationError> for BuildError {
+    fn from(error: image::ViewCreationError) -> Self {
+        BuildError::ViewCreation(error)
+    }
+}
+
+impl From<device::BindError> for BuildError {
+    fn from(error: device::BindError) -> Self {
+        BuildError::Bind(error)
+    }
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidAttachments(pass) => write!(
+                fmt,
+                "Failed to build graph: Invalid attachments in pass {:?}",
+                pass
+            ),
+            BuildError::OutOfMemory(err) => write!(fmt, "Failed to build graph: {}", err),
+            BuildError::Allocation(err) => write!(fmt, "Failed to build graph: {}", err),
+            BuildError::ImageCreation(err) => write!(fmt, "Failed to build graph: {}", err),
+            BuildError::ViewCreation(err) => write!(fmt, "Failed to build graph: {}", err),
+            BuildError::Bind(err) => write!(fmt, "Failed to build graph: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::InvalidAttachments(_) => None,
+            BuildError::OutOfMemory(err) => Some(err),
+            BuildError::Allocation(err) => Some(err),
+            BuildError::ImageCreation(err) => Some(err),
+            BuildError::ViewCreation(err) => Some(err),
+            BuildError::Bind(err) => Some(err),
+        }
+    }
+}
+
+/// An image created by the graph.
+#[derive(Debug)]
+pub(crate) struct TransientImage<B: Backend> {
+    image: B::Image,
+    view: Option<B::ImageView>,
+    /// Index of the block of memory, shared with the images aliasing it.
+    pub block: usize,
+}
+
+/// A render pass with a single subpass, wrapping the commands of a pass.
+struct RenderPass<B: Backend> {
+    raw: B::RenderPass,
+    attachments: Vec<ImageId>,
+    clears: Vec<ClearValue>,
+    extent: image::Extent,
+}
+
+/// Barriers to record before a pass, or at the end of the graph.
+pub(crate) struct Step<B: Backend> {
+    pass: Option<PassId>,
+    stages: Range<PipelineStage>,
+    buffers: Vec<BufferBarrier<usize>>,
+    pub images: Vec<ImageBarrier<usize>>,
+    render_pass: Option<RenderPass<B>>,
+}
+
+/// A built graph, ready to be executed.
+pub struct Graph<B: Backend> {
+    pub(crate) images: Vec<Option<TransientImage<B>>>,
+    pub(crate) blocks: Vec<Block<B>>,
+    pub(crate) steps: Vec<Step<B>>,
+    culled: Vec<bool>,
+}
+
+impl<B: Backend> std::fmt::Debug for Graph<B> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("Graph")
+            .field("images", &self.images)
+            .field("blocks", &self.blocks)
+            .field("culled", &self.culled)
+            .finish()
+    }
+}
+
+/// Imported resources bound to a graph for its execution.
+#[derive(Debug)]
+pub struct Bindings<'a, B: Backend> {
+    images: HashMap<ImageId, (Option<&'a B::Image>, &'a B::ImageView)>,
+    buffers: HashMap<BufferId, &'a B::Buffer>,
+}
+
+impl<B: Backend> Default for Bindings<'_, B> {
+    fn default() -> Self {
+        Bindings {
+            images: HashMap::new(),
+            buffers: HashMap::new(),
+        }
+    }
+}
+
+impl<'a, B: Backend> Bindings<'a, B> {
+    /// Creates empty bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds an imported image, along with a view of it.
+    pub fn image(mut self, id: ImageId, image: &'a B::Image, view: &'a B::ImageView) -> Self {
+        self.images.insert(id, (Some(image), view));
+        self
+    }
+
+    /// Binds an imported image through a view only, such as a swapchain image.
+    ///
+    /// This is only possible for images used as attachments by a single pass,
+    /// whose transitions are then all done by the render pass.
+    pub fn image_view(mut self, id: ImageId, view: &'a B::ImageView) -> Self {
+        self.images.insert(id, (None, view));
+        self
+    }
+
+    /// Binds an imported buffer.
+    pub fn buffer(mut self, id: BufferId, buffer: &'a B::Buffer) -> Self {
+        self.buffers.insert(id, buffer);
+        self
+    }
+}
+
+/// Resources of a graph, available to the passes while executing it.
+#[derive(Debug)]
+pub struct Resources<'a, B: Backend> {
+    graph: &'a Graph<B>,
+    bindings: &'a Bindings<'a, B>,
+}
+
+impl<'a, B: Backend> Resources<'a, B> {
+    /// Returns an image used by the pass.
+    pub fn image(&self, id: ImageId) -> &'a B::Image {
+        match self.graph.images[id.0] {
+            Some(ref transient) => &transient.image,
+            None => self
+                .bindings
+                .images
+                .get(&id)
+                .and_then(|&(image, _)| image)
+                .unwrap_or_else(|| panic!("Image {:?} isn't bound", id)),
+        }
+    }
+
+    /// Returns the view of all the subresources of an image used by the pass.
+    pub fn image_view(&self, id: ImageId) -> &'a B::ImageView {
+        match self.graph.images[id.0] {
+            Some(ref transient) => transient.view.as_ref().unwrap(),
+            None => self
+                .bindings
+                .images
+                .get(&id)
+                .map(|&(_, view)| view)
+                .unwrap_or_else(|| panic!("Image {:?} isn't bound", id)),
+        }
+    }
+
+    /// Returns a buffer used by the pass.
+    pub fn buffer(&self, id: BufferId) -> &'a B::Buffer {
+        self.bindings
+            .buffers
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| panic!("Buffer {:?} isn't bound", id))
+    }
+}
+
+/// Memory shared by transient images whose uses don't overlap.
+struct Alias {
+    /// Pass after which the memory is free.
+    end: usize,
+    requirements: memory::Requirements,
+    /// Stages of the uses of the last image placed in the memory.
+    stages: PipelineStage,
+}
+
+impl GraphBuilder {
+    /// Builds the graph, creating its transient images and render passes.
+    ///
+    /// Transient images are allocated from `allocator`.
+    ///
+    /// # Safety
+    ///
+    /// `allocator` must allocate from `device`.
+    pub unsafe fn build<B: Backend>(
+        self,
+        device: &B::Device,
+        allocator: &mut Allocator<B>,
+    ) -> Result<Graph<B>, BuildError> {
+        let mut graph = Graph {
+            images: self.images.iter().map(|_| None).collect(),
+            blocks: Vec::new(),
+            steps: Vec::new(),
+            culled: self.cull(),
+        };
+        match self.compile(device, allocator, &mut graph) {
+            Ok(()) => Ok(graph),
+            Err(err) => {
+                graph.dispose(device, allocator);
+                Err(err)
+            }
+        }
+    }
+
+    /// Finds the passes that don't contribute to any imported resource.
+    fn cull(&self) -> Vec<bool> {
+        let mut needed_images = self
+            .images
+            .iter()
+            .map(|image| image.import.is_some())
+            .collect::<Vec<_>>();
+        let mut needed_buffers = vec![true; self.buffers.len()];
+        let mut culled = vec![true; self.passes.len()];
+
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            let needed = pass
+                .image_uses()
+                .any(|(id, state)| state.writes() && needed_images[id.0])
+                || pass
+                    .buffers
+                    .iter()
+                    .any(|&(id, state)| state.writes() && needed_buffers[id.0]);
+            if !needed {
+                continue;
+            }
+            culled[index] = false;
+            for (id, state) in pass.image_uses() {
+                needed_images[id.0] |= state.reads();
+            }
+            for &(id, state) in &pass.buffers {
+                needed_buffers[id.0] |= state.reads();
+            }
+        }
+
+        culled
+    }
+
+    fn validate(&self, id: PassId, pass: &PassDesc) -> Result<(), BuildError> {
+        let mut extent = None;
+        for (index, attachment) in pass.attachments.iter().enumerate() {
+            let info = &self.images[attachment.image.0].info;
+            let valid = info.mip_levels == 1
+                && *extent.get_or_insert(info.kind.extent()) == info.kind.extent()
+                && pass.attachments[.. index]
+                    .iter()
+                    .all(|other| other.image != attachment.image)
+                && pass
+                    .images
+                    .iter()
+                    .all(|&(image, _, _)| image != attachment.image);
+            if !valid {
+                return Err(BuildError::InvalidAttachments(id));
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn compile<B: Backend>(
+        &self,
+        device: &B::Device,
+        allocator: &mut Allocator<B>,
+        graph: &mut Graph<B>,
+    ) -> Result<(), BuildError> {
+        let passes = self
+            .passes
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| !graph.culled[index])
+            .collect::<Vec<_>>();
+
+        // Passes using each image, and the stages they use it from
+        let mut lifetimes = vec![None::<Range<usize>>; self.images.len()];
+        let mut stages = vec![PipelineStage::empty(); self.images.len()];
+        let mut usages = vec![image::Usage::empty(); self.images.len()];
+        for &(index, pass) in &passes {
+            self.validate(PassId(index), pass)?;
+            for (id, state) in pass.image_uses() {
+                let lifetime = lifetimes[id.0].get_or_insert(index .. index);
+                lifetime.end = index + 1;
+                stages[id.0] |= state.stages;
+                usages[id.0] |= usage(&state);
+            }
+        }
+
+        // Create the transient images, placing them in the memory of the previous
+        // images if they are done with it
+        let mut order = (0 .. self.images.len())
+            .filter(|&id| self.images[id].import.is_none() && lifetimes[id].is_some())
+            .collect::<Vec<_>>();
+        order.sort_by_key(|&id| lifetimes[id].as_ref().unwrap().start);
+
+        let mut aliases = Vec::<Alias>::new();
+        let mut alias_stages = vec![PipelineStage::empty(); self.images.len()];
+        for &id in &order {
+            let info = &self.images[id].info;
+            let lifetime = lifetimes[id].clone().unwrap();
+            let image = device.create_image(
+                info.kind,
+                info.mip_levels,
+                info.format,
+                image::Tiling::Optimal,
+                usages[id],
+                image::ViewCapabilities::empty(),
+                memory::SparseFlags::empty(),
+            )?;
+            let requirements = device.get_image_requirements(&image);
+
+            let free = aliases.iter().position(|alias| {
+                alias.end <= lifetime.start
+                    && alias.requirements.type_mask & requirements.type_mask != 0
+            });
+            let block = match free {
+                Some(index) => {
+                    let alias = &mut aliases[index];
+                    alias_stages[id] = alias.stages;
+                    alias.end = lifetime.end;
+                    alias.stages = stages[id];
+                    alias.requirements = memory::Requirements {
+                        size: alias.requirements.size.max(requirements.size),
+                        alignment: alias.requirements.alignment.max(requirements.alignment),
+                        type_mask: alias.requirements.type_mask & requirements.type_mask,
+                    };
+                    index
+                }
+                None => {
+                    aliases.push(Alias {
+                        end: lifetime.end,
+                        requirements,
+                        stages: stages[id],
+                    });
+                    aliases.len() - 1
+                }
+            };
+            graph.images[id] = Some(TransientImage {
+                image,
+                view: None,
+                block,
+            });
+        }
+
+        for alias in &aliases {
+            let block = allocator.allocate(
+                device,
+                &alias.requirements,
+                memory::Properties::DEVICE_LOCAL,
+                Strategy::General,
+            )?;
+            graph.blocks.push(block);
+        }
+        for &id in &order {
+            let info = &self.images[id].info;
+            let transient = graph.images[id].as_mut().unwrap();
+            let block = &graph.blocks[transient.block];
+            device.bind_image_memory(block.memory(), block.offset(), &mut transient.image)?;
+            transient.view = Some(device.create_image_view(
+                &transient.image,
+                view_kind(info.kind),
+                info.format,
+                format::Swizzle::NO,
+                info.range(),
+            )?);
+        }
+
+        // Track the states of the resources through the passes
+        let mut commands = CommandTracker::new();
+        for (id, desc) in self.images.iter().enumerate() {
+            if let Some(ref states) = desc.import {
+                commands.use_image(id, &desc.info.range(), states.start);
+            }
+        }
+        for (id, desc) in self.buffers.iter().enumerate() {
+            commands.use_buffer(id, desc.states.start);
+        }
+        commands.take_barriers();
+
+        let mut transitioned = vec![false; self.images.len()];
+        for &(index, pass) in &passes {
+            for &id in &order {
+                if lifetimes[id].as_ref().unwrap().start == index {
+                    let state = ImageState {
+                        stages: alias_stages[id],
+                        ..ImageState::UNDEFINED
+                    };
+                    commands.use_image(id, &self.images[id].info.range(), state);
+                }
+            }
+            for attachment in &pass.attachments {
+                let info = &self.images[attachment.image.0].info;
+                commands.use_image(
+                    attachment.image.0,
+                    &info.attachment_range(),
+                    attachment.state,
+                );
+            }
+            for &(id, ref range, state) in &pass.images {
+                commands.use_image(id.0, range, state);
+            }
+            for &(id, state) in &pass.buffers {
+                commands.use_buffer(id.0, state);
+            }
+
+            let barriers = commands.take_barriers();
+            let (attachment_barriers, images) = barriers
+                .images()
+                .iter()
+                .cloned()
+                .partition::<Vec<_>, _>(|barrier| {
+                    pass.attachments
+                        .iter()
+                        .any(|attachment| attachment.image.0 == barrier.key)
+                });
+            let render_pass = if pass.attachments.is_empty() {
+                None
+            } else {
+                let render_pass = self.create_render_pass(
+                    device,
+                    pass,
+                    index,
+                    &lifetimes,
+                    barriers.stages(),
+                    &attachment_barriers,
+                    &mut transitioned,
+                )?;
+                Some(render_pass)
+            };
+            graph.steps.push(Step {
+                pass: Some(PassId(index)),
+                stages: barriers.stages(),
+                buffers: barriers.buffers().to_vec(),
+                images,
+                render_pass,
+            });
+        }
+
+        // Leave the imported resources in their final states
+        for (id, desc) in self.images.iter().enumerate() {
+            match desc.import {
+                Some(ref states) if !transitioned[id] => {
+                    commands.use_image(id, &desc.info.range(), states.end);
+                }
+                _ => {}
+            }
+        }
+        for (id, desc) in self.buffers.iter().enumerate() {
+            commands.use_buffer(id, desc.states.end);
+        }
+        let barriers = commands.take_barriers();
+        graph.steps.push(Step {
+            pass: None,
+            stages: barriers.stages(),
+            buffers: barriers.buffers().to_vec(),
+            images: barriers.images().to_vec(),
+            render_pass: None,
+        });
+
+        Ok(())
+    }
+
+    /// Creates the render pass of a pass, which transitions its attachments from their
+    /// previous states, and to the final states of the imported images it uses last.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn create_render_pass<B: Backend>(
+        &self,
+        device: &B::Device,
+        pass: &PassDesc,
+        index: usize,
+        lifetimes: &[Option<Range<usize>>],
+        stages: Range<PipelineStage>,
+        barriers: &[ImageBarrier<usize>],
+        transitioned: &mut [bool],
+    ) -> Result<RenderPass<B>, OutOfMemory> {
+        let mut attachments = Vec::with_capacity(pass.attachments.len());
+        let mut dependencies = Vec::new();
+        if !barriers.is_empty() {
+            let accesses = barriers.iter().fold(
+                image::Access::empty() .. image::Access::empty(),
+                |accesses, barrier| {
+                    accesses.start | barrier.states.start.0 .. accesses.end | barrier.states.end.0
+                },
+            );
+            dependencies.push(pass::SubpassDependency {
+                passes: None .. Some(0),
+                stages,
+                accesses,
+                flags: Dependencies::empty(),
+            });
+        }
+
+        for attachment in &pass.attachments {
+            let id = attachment.image.0;
+            let desc = &self.images[id];
+            let initial = barriers
+                .iter()
+                .find(|barrier| barrier.key == id)
+                .map_or(attachment.state.layout, |barrier| barrier.states.start.1);
+            let last_use = lifetimes[id].as_ref().unwrap().end == index + 1;
+            let last = match desc.import {
+                Some(ref states) if last_use => {
+                    transitioned[id] = true;
+                    let dst_stages = match states.end.stages {
+                        stages if stages.is_empty() => PipelineStage::BOTTOM_OF_PIPE,
+                        stages => stages,
+                    };
+                    dependencies.push(pass::SubpassDependency {
+                        passes: Some(0) .. None,
+                        stages: attachment.state.stages .. dst_stages,
+                        accesses: attachment.state.access .. states.end.access,
+                        flags: Dependencies::empty(),
+                    });
+                    states.end.layout
+                }
+                _ => attachment.state.layout,
+            };
+            let stencil_ops = if desc
+                .info
+                .format
+                .surface_desc()
+                .aspects
+                .contains(format::Aspects::STENCIL)
+            {
+                attachment.ops
+            } else {
+                pass::AttachmentOps::DONT_CARE
+            };
+            attachments.push(pass::Attachment {
+                format: Some(desc.info.format),
+                samples: desc.info.kind.num_samples(),
+                ops: attachment.ops,
+                stencil_ops,
+                layouts: initial .. last,
+            });
+        }
+
+        let count = pass.attachments.len() - pass.depth_stencil as usize;
+        let colors = (0 .. count)
+            .map(|index| (index, image::Layout::ColorAttachmentOptimal))
+            .collect::<Vec<_>>();
+        let depth_stencil = (count, image::Layout::DepthStencilAttachmentOptimal);
+        let subpass = pass::SubpassDesc {
+            colors: &colors,
+            depth_stencil: if pass.depth_stencil {
+                Some(&depth_stencil)
+            } else {
+                None
+            },
+            inputs: &[],
+            resolves: &[],
+            preserves: &[],
+            view_mask: 0,
+        };
+        let raw = device.create_render_pass(attachments, Some(subpass), dependencies, &[])?;
+
+        Ok(RenderPass {
+            raw,
+            attachments: pass
+                .attachments
+                .iter()
+                .map(|attachment| attachment.image)
+                .collect(),
+            clears: pass
+                .attachments
+                .iter()
+                .map(|attachment| attachment.clear)
+                .collect(),
+            extent: self.images[pass.attachments[0].image.0].info.kind.extent(),
+        })
+    }
+}
+
+impl<B: Backend> Graph<B> {
+    /// Whether a pass was culled, because it doesn't contribute to any imported resource.
+    pub fn is_culled(&self, pass: PassId) -> bool {
+        self.culled[pass.0]
+    }
+
+    /// Returns the render pass of a pass with attachments, to create its pipelines.
+    ///
+    /// Render passes have a single subpass.
+    pub fn render_pass(&self, pass: PassId) -> Option<&B::RenderPass> {
+        self.steps
+            .iter()
+            .find(|step| step.pass == Some(pass))
+            .and_then(|step| step.render_pass.as_ref())
+            .map(|render_pass| &render_pass.raw)
+    }
+
+    /// Records the graph, calling `record` to record the commands of each pass
+    /// that wasn't culled.
+    ///
+    /// Passes with attachments are recorded inside of their render pass.
+    /// Returns the framebuffers created for the render passes, to be destroyed once
+    /// the command buffer has completed.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be recording, outside of a render pass, and all the
+    /// imported resources must be bound in their starting states.
+    pub unsafe fn execute<F>(
+        &self,
+        device: &B::Device,
+        command_buffer: &mut B::CommandBuffer,
+        bindings: &Bindings<'_, B>,
+        mut record: F,
+    ) -> Result<Vec<B::Framebuffer>, OutOfMemory>
+    where
+        F: FnMut(PassId, &mut B::CommandBuffer, &Resources<'_, B>),
+    {
+        let resources = Resources {
+            graph: self,
+            bindings,
+        };
+        let mut framebuffers = Vec::new();
+
+        for step in &self.steps {
+            if !step.buffers.is_empty() || !step.images.is_empty() {
+                let buffers = step.buffers.iter().map(|barrier| {
+                    Barrier::whole_buffer(
+                        resources.buffer(BufferId(barrier.key)),
+                        barrier.states.clone(),
+                    )
+                });
+                let images = step.images.iter().map(|barrier| Barrier::Image {
+                    states: barrier.states.clone(),
+                    target: resources.image(ImageId(barrier.key)),
+                    range: barrier.range.clone(),
+                    families: None,
+                });
+                command_buffer.pipeline_barrier(
+                    step.stages.clone(),
+                    Dependencies::empty(),
+                    buffers.chain(images),
+                );
+            }
+
+            let pass = match step.pass {
+                Some(pass) => pass,
+                None => continue,
+            };
+            let render_pass = match step.render_pass {
+                Some(ref render_pass) => render_pass,
+                None => {
+                    record(pass, command_buffer, &resources);
+                    continue;
+                }
+            };
+            let views = render_pass
+                .attachments
+                .iter()
+                .map(|&id| resources.image_view(id));
+            let framebuffer =
+                match device.create_framebuffer(&render_pass.raw, views, render_pass.extent) {
+                    Ok(framebuffer) => framebuffer,
+                    Err(err) => {
+                        for framebuffer in framebuffers {
+                            device.destroy_framebuffer(framebuffer);
+                        }
+                        return Err(err);
+                    }
+                };
+            let area = pso::Rect {
+                x: 0,
+                y: 0,
+                w: render_pass.extent.width as i16,
+                h: render_pass.extent.height as i16,
+            };
+            command_buffer.begin_render_pass(
+                &render_pass.raw,
+                &framebuffer,
+                area,
+                &render_pass.clears,
+                SubpassContents::Inline,
+            );
+            record(pass, command_buffer, &resources);
+            command_buffer.end_render_pass();
+            framebuffers.push(framebuffer);
+        }
+
+        Ok(framebuffers)
+    }
+
+    /// Destroys the render passes and transient images of the graph.
+    ///
+    /// # Safety
+    ///
+    /// The command buffers executing the graph must have completed, and `allocator`
+    /// must be the one the graph was built with.
+    pub unsafe fn dispose(self, device: &B::Device, allocator: &mut Allocator<B>) {
+        for step in self.steps {
+            if let Some(render_pass) = step.render_pass {
+                device.destroy_render_pass(render_pass.raw);
+            }
+        }
+        for transient in self.images.into_iter().flatten() {
+            if let Some(view) = transient.view {
+                device.destroy_image_view(view);
+            }
+            device.destroy_image(transient.image);
+        }
+        for block in self.blocks {
+            allocator.free(device, block);
+        }
+    }
+}
+
+/// Returns the usage an image needs for a use.
+fn usage(state: &ImageState) -> image::Usage {
+    let access = state.access;
+    let mut usage = image::Usage::empty();
+    if access
+        .intersects(image::Access::COLOR_ATTACHMENT_READ | image::Access::COLOR_ATTACHMENT_WRITE)
+    {
+        usage |= image::Usage::COLOR_ATTACHMENT;
+    }
+    if access.intersects(
+        image::Access::DEPTH_STENCIL_ATTACHMENT_READ
+            | image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    ) {
+        usage |= image::Usage::DEPTH_STENCIL_ATTACHMENT;
+    }
+    if access.contains(image::Access::INPUT_ATTACHMENT_READ) {
+        usage |= image::Usage::INPUT_ATTACHMENT;
+    }
+    if access.intersects(image::Access::SHADER_READ | image::Access::SHADER_WRITE) {
+        usage |= match state.layout {
+            image::Layout::General => image::Usage::STORAGE,
+            _ => image::Usage::SAMPLED,
+        };
+    }
+    if access.contains(image::Access::TRANSFER_READ) {
+        usage |= image::Usage::TRANSFER_SRC;
+    }
+    if access.contains(image::Access::TRANSFER_WRITE) {
+        usage |= image::Usage::TRANSFER_DST;
+    }
+    usage
+}
+
+fn view_kind(kind: image::Kind) -> image::ViewKind {
+    match kind {
+        image::Kind::D1(_, 1) => image::ViewKind::D1,
+        image::Kind::D1(..) => image::ViewKind::D1Array,
+        image::Kind::D2(_, _, 1, _) => image::ViewKind::D2,
+        image::Kind::D2(..) => image::ViewKind::D2Array,
+        image::Kind::D3(..) => image::ViewKind::D3,
+    }
+}
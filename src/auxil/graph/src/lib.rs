@@ -0,0 +1,533 @@
+//! Frame graph.
+//!
+//! A frame is described as a sequence of passes, each declaring the images and buffers
+//! it reads and writes. Building the graph then works out everything that is usually
+//! written by hand around the commands of the passes:
+//!
+//! - passes that don't contribute to any imported resource are culled,
+//! - transient images are created, and those whose uses don't overlap share their memory,
+//! - each pass with attachments gets a render pass, whose attachment layouts and
+//!   subpass dependencies come from the previous and next uses of the images,
+//! - pipeline barriers are inserted between the passes, using `gfx-track`.
+//!
+//! Imported resources are owned by the application, such as the swapchain images,
+//! and are bound to the graph when executing it.
+
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+use hal::{command::ClearValue, format, image, pass::AttachmentOps, pso::PipelineStage};
+
+use std::ops::Range;
+
+pub use crate::graph::{Bindings, BuildError, Graph, Resources};
+pub use gfx_track::{BufferState, ImageState};
+
+mod graph;
+
+/// Identifier of an image of a graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageId(usize);
+
+/// Identifier of a buffer of a graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BufferId(usize);
+
+/// Identifier of a pass of a graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PassId(usize);
+
+/// Description of an image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageInfo {
+    /// Kind and extent of the image.
+    pub kind: image::Kind,
+    /// Number of mip levels.
+    pub mip_levels: image::Level,
+    /// Format of the image.
+    pub format: format::Format,
+}
+
+impl ImageInfo {
+    /// Range of all the subresources of the image.
+    pub fn range(&self) -> image::SubresourceRange {
+        image::SubresourceRange {
+            aspects: self.format.surface_desc().aspects,
+            levels: 0 .. self.mip_levels,
+            layers: 0 .. self.kind.num_layers(),
+        }
+    }
+
+    /// Range of the subresources used as attachment.
+    fn attachment_range(&self) -> image::SubresourceRange {
+        image::SubresourceRange {
+            levels: 0 .. 1,
+            ..self.range()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ImageDesc {
+    pub info: ImageInfo,
+    /// States of imported images at the start and the end of the graph.
+    pub import: Option<Range<ImageState>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct BufferDesc {
+    pub states: Range<BufferState>,
+}
+
+pub(crate) struct AttachmentDesc {
+    pub image: ImageId,
+    pub ops: AttachmentOps,
+    pub clear: ClearValue,
+    pub state: ImageState,
+}
+
+pub(crate) struct PassDesc {
+    pub name: String,
+    /// Color attachments, followed by the depth stencil attachment if any.
+    pub attachments: Vec<AttachmentDesc>,
+    pub depth_stencil: bool,
+    pub images: Vec<(ImageId, image::SubresourceRange, ImageState)>,
+    pub buffers: Vec<(BufferId, BufferState)>,
+}
+
+impl PassDesc {
+    pub fn image_uses(&self) -> impl Iterator<Item = (ImageId, ImageState)> + '_ {
+        self.attachments
+            .iter()
+            .map(|attachment| (attachment.image, attachment.state))
+            .chain(self.images.iter().map(|&(id, _, state)| (id, state)))
+    }
+}
+
+/// Describes the resources and passes of a graph.
+#[derive(Default)]
+pub struct GraphBuilder {
+    images: Vec<ImageDesc>,
+    buffers: Vec<BufferDesc>,
+    passes: Vec<PassDesc>,
+}
+
+impl std::fmt::Debug for GraphBuilder {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("GraphBuilder")
+            .field("images", &self.images)
+            .field("buffers", &self.buffers)
+            .field(
+                "passes",
+                &self
+                    .passes
+                    .iter()
+                    .map(|pass| &pass.name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl GraphBuilder {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a transient image, created and owned by the graph.
+    ///
+    /// The contents of transient images are undefined at the start of the graph,
+    /// and discarded at the end of it.
+    pub fn create_image(&mut self, info: ImageInfo) -> ImageId {
+        self.images.push(ImageDesc { info, import: None });
+        ImageId(self.images.len() - 1)
+    }
+
+    /// Adds an image owned by the application, which must be in `states.start`
+    /// when executing the graph, and is left in `states.end`.
+    pub fn import_image(&mut self, info: ImageInfo, states: Range<ImageState>) -> ImageId {
+        self.images.push(ImageDesc {
+            info,
+            import: Some(states),
+        });
+        ImageId(self.images.len() - 1)
+    }
+
+    /// Adds a buffer owned by the application, which must be in `states.start`
+    /// when executing the graph, and is left in `states.end`.
+    pub fn import_buffer(&mut self, states: Range<BufferState>) -> BufferId {
+        self.buffers.push(BufferDesc { states });
+        BufferId(self.buffers.len() - 1)
+    }
+
+    /// Adds a pass, executed after the previously added ones.
+    pub fn add_pass(&mut self, name: &str) -> PassBuilder<'_> {
+        self.passes.push(PassDesc {
+            name: name.to_string(),
+            attachments: Vec::new(),
+            depth_stencil: false,
+            images: Vec::new(),
+            buffers: Vec::new(),
+        });
+        PassBuilder {
+            id: PassId(self.passes.len() - 1),
+            images: &self.images,
+            pass: self.passes.last_mut().unwrap(),
+        }
+    }
+}
+
+/// Declares the resources used by a pass.
+pub struct PassBuilder<'a> {
+    id: PassId,
+    images: &'a [ImageDesc],
+    pass: &'a mut PassDesc,
+}
+
+impl std::fmt::Debug for PassBuilder<'_> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("PassBuilder")
+            .field("id", &self.id)
+            .field("name", &self.pass.name)
+            .finish()
+    }
+}
+
+impl PassBuilder<'_> {
+    /// Returns the identifier of the pass.
+    pub fn id(&self) -> PassId {
+        self.id
+    }
+
+    /// Renders to `image` as a color attachment.
+    ///
+    /// `clear` is only used if the attachment is cleared when loaded.
+    pub fn color(self, image: ImageId, ops: AttachmentOps, clear: ClearValue) -> Self {
+        let read = match ops.load {
+            hal::pass::AttachmentLoadOp::Load => image::Access::COLOR_ATTACHMENT_READ,
+            _ => image::Access::empty(),
+        };
+        let state = ImageState {
+            access: image::Access::COLOR_ATTACHMENT_WRITE | read,
+            layout: image::Layout::ColorAttachmentOptimal,
+            stages: PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+        };
+        self.attachment(image, ops, clear, state)
+    }
+
+    /// Renders to `image` as the depth stencil attachment.
+    ///
+    /// `clear` is only used if the attachment is cleared when loaded.
+    pub fn depth_stencil(self, image: ImageId, ops: AttachmentOps, clear: ClearValue) -> Self {
+        assert!(
+            !self.pass.depth_stencil,
+            "Pass {:?} already has a depth stencil attachment",
+            self.pass.name
+        );
+        self.pass.depth_stencil = true;
+        let read = match ops.load {
+            hal::pass::AttachmentLoadOp::Load => image::Access::DEPTH_STENCIL_ATTACHMENT_READ,
+            _ => image::Access::empty(),
+        };
+        let state = ImageState {
+            access: image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE | read,
+            layout: image::Layout::DepthStencilAttachmentOptimal,
+            stages: PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS,
+        };
+        self.attachment(image, ops, clear, state)
+    }
+
+    fn attachment(
+        self,
+        image: ImageId,
+        ops: AttachmentOps,
+        clear: ClearValue,
+        state: ImageState,
+    ) -> Self {
+        let attachment = AttachmentDesc {
+            image,
+            ops,
+            clear,
+            state,
+        };
+        // Keep the depth stencil attachment last
+        if self.pass.depth_stencil && state.layout == image::Layout::ColorAttachmentOptimal {
+            let index = self.pass.attachments.len() - 1;
+            self.pass.attachments.insert(index, attachment);
+        } else {
+            self.pass.attachments.push(attachment);
+        }
+        self
+    }
+
+    /// Samples all the subresources of `image` from shaders of the given stages.
+    pub fn sampled(self, image: ImageId, stages: PipelineStage) -> Self {
+        let state = ImageState {
+            access: image::Access::SHADER_READ,
+            layout: image::Layout::ShaderReadOnlyOptimal,
+            stages,
+        };
+        self.image(image, None, state)
+    }
+
+    /// Accesses all the subresources of `image` as a storage image,
+    /// from shaders of the given stages.
+    pub fn storage(self, image: ImageId, access: image::Access, stages: PipelineStage) -> Self {
+        let state = ImageState {
+            access,
+            layout: image::Layout::General,
+            stages,
+        };
+        self.image(image, None, state)
+    }
+
+    /// Copies from all the subresources of `image`.
+    pub fn copy_src(self, image: ImageId) -> Self {
+        let state = ImageState {
+            access: image::Access::TRANSFER_READ,
+            layout: image::Layout::TransferSrcOptimal,
+            stages: PipelineStage::TRANSFER,
+        };
+        self.image(image, None, state)
+    }
+
+    /// Copies to all the subresources of `image`.
+    pub fn copy_dst(self, image: ImageId) -> Self {
+        let state = ImageState {
+            access: image::Access::TRANSFER_WRITE,
+            layout: image::Layout::TransferDstOptimal,
+            stages: PipelineStage::TRANSFER,
+        };
+        self.image(image, None, state)
+    }
+
+    /// Uses the subresources `range` of `image`, or all of them, in the given state.
+    pub fn image(
+        self,
+        image: ImageId,
+        range: Option<image::SubresourceRange>,
+        state: ImageState,
+    ) -> Self {
+        let range = range.unwrap_or_else(|| self.images[image.0].info.range());
+        self.pass.images.push((image, range, state));
+        self
+    }
+
+    /// Uses `buffer` in the given state.
+    pub fn buffer(self, buffer: BufferId, state: BufferState) -> Self {
+        self.pass.buffers.push((buffer, state));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gfx_backend_empty as empty;
+    use gfx_memory::{Allocator, Config};
+    use hal::{
+        adapter::PhysicalDevice as _,
+        command::{ClearColor, Level},
+        device::Device as _,
+        pass::AttachmentLayout,
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        queue::QueueFamily as _,
+        Instance as _,
+    };
+
+    const COLOR: ImageInfo = ImageInfo {
+        kind: image::Kind::D2(64, 64, 1, 1),
+        mip_levels: 1,
+        format: format::Format::Rgba8Unorm,
+    };
+    const CLEAR: ClearValue = ClearValue {
+        color: ClearColor { float32: [0.0; 4] },
+    };
+    const PRESENT: ImageState = ImageState {
+        access: image::Access::empty(),
+        layout: image::Layout::Present,
+        stages: PipelineStage::empty(),
+    };
+
+    fn open() -> (
+        empty::Device,
+        Allocator<empty::Backend>,
+        hal::queue::QueueFamilyId,
+    ) {
+        let instance = empty::Instance::with_config(empty::MockConfig::default());
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let allocator = Allocator::new(
+            &adapter.physical_device.memory_properties(),
+            &adapter.physical_device.limits(),
+            Config::default(),
+        );
+        (gpu.device, allocator, adapter.queue_families[0].id())
+    }
+
+    #[test]
+    fn cull_unused_passes() {
+        let (device, mut allocator, _) = open();
+        let mut builder = GraphBuilder::new();
+        let frame = builder.import_image(COLOR, ImageState::UNDEFINED .. PRESENT);
+        let shadow = builder.create_image(COLOR);
+        let debug = builder.create_image(COLOR);
+        let shadow_pass = builder
+            .add_pass("shadow")
+            .color(shadow, AttachmentOps::INIT, CLEAR)
+            .id();
+        let debug_pass = builder
+            .add_pass("debug")
+            .color(debug, AttachmentOps::INIT, CLEAR)
+            .id();
+        let main_pass = builder
+            .add_pass("main")
+            .sampled(shadow, PipelineStage::FRAGMENT_SHADER)
+            .color(frame, AttachmentOps::INIT, CLEAR)
+            .id();
+
+        let graph = unsafe { builder.build(&device, &mut allocator) }.unwrap();
+        assert!(!graph.is_culled(shadow_pass));
+        assert!(graph.is_culled(debug_pass));
+        assert!(!graph.is_culled(main_pass));
+        assert!(graph.images[shadow.0].is_some());
+        assert!(graph.images[debug.0].is_none());
+        assert!(graph.render_pass(debug_pass).is_none());
+        unsafe { graph.dispose(&device, &mut allocator) };
+    }
+
+    #[test]
+    fn alias_transient_images() {
+        let (device, mut allocator, _) = open();
+        let mut builder = GraphBuilder::new();
+        let frame = builder.import_image(COLOR, ImageState::UNDEFINED .. PRESENT);
+        let images = [
+            builder.create_image(COLOR),
+            builder.create_image(COLOR),
+            builder.create_image(COLOR),
+        ];
+        builder
+            .add_pass("first")
+            .color(images[0], AttachmentOps::INIT, CLEAR);
+        for (index, pair) in images.windows(2).enumerate() {
+            builder
+                .add_pass(&format!("blur {}", index))
+                .sampled(pair[0], PipelineStage::FRAGMENT_SHADER)
+                .color(pair[1], AttachmentOps::INIT, CLEAR);
+        }
+        builder
+            .add_pass("last")
+            .sampled(images[2], PipelineStage::FRAGMENT_SHADER)
+            .color(frame, AttachmentOps::INIT, CLEAR);
+
+        let graph = unsafe { builder.build(&device, &mut allocator) }.unwrap();
+        let block = |id: ImageId| graph.images[id.0].as_ref().unwrap().block;
+        assert_eq!(graph.blocks.len(), 2);
+        assert_eq!(block(images[0]), block(images[2]));
+        assert_ne!(block(images[0]), block(images[1]));
+        unsafe { graph.dispose(&device, &mut allocator) };
+    }
+
+    #[test]
+    fn derive_render_passes() {
+        let (device, mut allocator, family) = open();
+        let mut builder = GraphBuilder::new();
+        let frame = builder.import_image(COLOR, ImageState::UNDEFINED .. PRESENT);
+        let shadow = builder.create_image(COLOR);
+        let shadow_pass = builder
+            .add_pass("shadow")
+            .color(shadow, AttachmentOps::INIT, CLEAR)
+            .id();
+        let main_pass = builder
+            .add_pass("main")
+            .sampled(shadow, PipelineStage::FRAGMENT_SHADER)
+            .color(frame, AttachmentOps::INIT, CLEAR)
+            .id();
+        let graph = unsafe { builder.build(&device, &mut allocator) }.unwrap();
+
+        let layouts = |pass| -> Vec<Range<AttachmentLayout>> {
+            graph
+                .render_pass(pass)
+                .unwrap()
+                .attachments()
+                .iter()
+                .map(|attachment| attachment.layouts.clone())
+                .collect()
+        };
+        assert_eq!(
+            layouts(shadow_pass),
+            vec![image::Layout::Undefined .. image::Layout::ColorAttachmentOptimal]
+        );
+        assert_eq!(
+            layouts(main_pass),
+            vec![image::Layout::Undefined .. image::Layout::Present]
+        );
+        // Only the sampling of the shadow needs a barrier
+        let barriers = graph
+            .steps
+            .iter()
+            .map(|step| step.images.len())
+            .collect::<Vec<_>>();
+        assert_eq!(barriers, vec![0, 1, 0]);
+
+        unsafe {
+            let image = device
+                .create_image(
+                    COLOR.kind,
+                    1,
+                    COLOR.format,
+                    image::Tiling::Optimal,
+                    image::Usage::COLOR_ATTACHMENT,
+                    image::ViewCapabilities::empty(),
+                    hal::memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            let view = device
+                .create_image_view(
+                    &image,
+                    image::ViewKind::D2,
+                    COLOR.format,
+                    format::Swizzle::NO,
+                    COLOR.range(),
+                )
+                .unwrap();
+            let mut pool = device
+                .create_command_pool(family, CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut command_buffer = pool.allocate_one(Level::Primary);
+            let bindings = Bindings::new().image_view(frame, &view);
+
+            let mut recorded = Vec::new();
+            let framebuffers = graph
+                .execute(&device, &mut command_buffer, &bindings, |pass, _, _| {
+                    recorded.push(pass)
+                })
+                .unwrap();
+            assert_eq!(recorded, vec![shadow_pass, main_pass]);
+            assert_eq!(framebuffers.len(), 2);
+
+            for framebuffer in framebuffers {
+                device.destroy_framebuffer(framebuffer);
+            }
+            pool.free(Some(command_buffer));
+            device.destroy_command_pool(pool);
+            device.destroy_image_view(view);
+            device.destroy_image(image);
+            graph.dispose(&device, &mut allocator);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use hal::Backend;
+use range_alloc::RangeAllocator;
+
+use std::{ops::Range, sync::Arc};
+
+/// How the blocks of a chunk are carved.
+#[derive(Debug)]
+pub(crate) enum Kind {
+    /// The chunk backs a single block.
+    Dedicated,
+    /// Blocks are bumped from `offset`, which is reset once they are all freed.
+    Linear { offset: u64, blocks: usize },
+    /// Blocks are the best fit among the free ranges.
+    General(RangeAllocator<u64>),
+}
+
+/// Host address of a mapped chunk.
+#[derive(Debug)]
+pub(crate) struct Mapping(pub *mut u8);
+
+// The pointer is only dereferenced by the users of `Allocator::map`.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+/// A device memory allocation, shared by the blocks carved out of it.
+#[derive(Debug)]
+pub(crate) struct Chunk<B: Backend> {
+    pub memory: Arc<B::Memory>,
+    pub size: u64,
+    pub kind: Kind,
+    pub mapping: Option<Mapping>,
+}
+
+impl<B: Backend> Chunk<B> {
+    /// Carves a block of `size` bytes at an offset multiple of `alignment`.
+    pub fn allocate(&mut self, size: u64, alignment: u64) -> Option<Range<u64>> {
+        match self.kind {
+            Kind::Dedicated => None,
+            Kind::Linear {
+                ref mut offset,
+                ref mut blocks,
+            } => {
+                let start = align_up(*offset, alignment);
+                if start + size > self.size {
+                    return None;
+                }
+                *offset = start + size;
+                *blocks += 1;
+                Some(start .. start + size)
+            }
+            Kind::General(ref mut ranges) => {
+                // Most blocks are naturally aligned, try the exact size first
+                if let Ok(range) = ranges.allocate_range(size) {
+                    if range.start % alignment == 0 {
+                        return Some(range);
+                    }
+                    ranges.free_range(range);
+                }
+                let padded = ranges.allocate_range(size + alignment - 1).ok()?;
+                let start = align_up(padded.start, alignment);
+                if padded.start < start {
+                    ranges.free_range(padded.start .. start);
+                }
+                if start + size < padded.end {
+                    ranges.free_range(start + size .. padded.end);
+                }
+                Some(start .. start + size)
+            }
+        }
+    }
+
+    /// Returns a block to the chunk, and whether the chunk is now unused.
+    pub fn free(&mut self, range: Range<u64>) -> bool {
+        match self.kind {
+            Kind::Dedicated => true,
+            Kind::Linear {
+                ref mut offset,
+                ref mut blocks,
+            } => {
+                *blocks -= 1;
+                if *blocks == 0 {
+                    *offset = 0;
+                }
+                *blocks == 0
+            }
+            Kind::General(ref mut ranges) => {
+                ranges.free_range(range);
+                ranges.is_empty()
+            }
+        }
+    }
+
+    pub fn is_general(&self) -> bool {
+        matches!(self.kind, Kind::General(_))
+    }
+
+    pub fn is_unused(&self) -> bool {
+        match self.kind {
+            Kind::Dedicated => false,
+            Kind::Linear { blocks, .. } => blocks == 0,
+            Kind::General(ref ranges) => ranges.is_empty(),
+        }
+    }
+}
+
+pub(crate) fn align_up(value: u64, alignment: u64) -> u64 {
+    debug_assert!(alignment.is_power_of_two());
+    (value + alignment - 1) & !(alignment - 1)
+}
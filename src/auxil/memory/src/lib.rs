@@ -0,0 +1,740 @@
+//! Device memory sub-allocator.
+//!
+//! Devices only support a limited number of live memory allocations
+//! (`Limits::max_memory_allocation_count`, which can be as low as 4096), so resources
+//! are better placed in blocks carved out of larger chunks of device memory.
+//!
+//! `Allocator` picks a memory type matching the requirements of a resource and the
+//! requested properties, and hands out blocks following one of three strategies:
+//!
+//! - `Strategy::Linear` bumps an offset through shared chunks, which are only reused
+//!   once all their blocks are freed. It suits short-lived data, such as staging buffers.
+//! - `Strategy::General` places blocks in the best fitting free range of shared chunks.
+//! - `Strategy::Dedicated` gives each block a device memory allocation of its own.
+//!
+//! Blocks of shared chunks are aligned and padded to `Limits::buffer_image_granularity`,
+//! so buffers and images can be freely mixed within a chunk.
+
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+use hal::{
+    adapter,
+    device::{self, Device as _, MapError, OutOfMemory},
+    memory::{Properties, Requirements, Segment},
+    Backend,
+    Limits,
+    MemoryTypeId,
+};
+use range_alloc::RangeAllocator;
+
+use std::{ops::Range, sync::Arc};
+
+use crate::chunk::{align_up, Chunk, Kind, Mapping};
+
+mod chunk;
+
+/// Allocation strategy of a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Blocks are allocated one after the other in shared chunks, whose memory is
+    /// reused once all of their blocks are freed.
+    Linear,
+    /// Blocks are placed in the best fitting free range of shared chunks.
+    General,
+    /// Each block has a device memory allocation of its own.
+    Dedicated,
+}
+
+/// Sizes of the chunks shared by blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Size of the chunks used by the linear strategy.
+    pub linear_chunk_size: u64,
+    /// Size of the chunks used by the general strategy.
+    pub general_chunk_size: u64,
+    /// Largest block placed in a chunk by the general strategy.
+    ///
+    /// Larger blocks, as well as linear blocks larger than a chunk, are given a
+    /// dedicated allocation instead.
+    pub general_max_block_size: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            linear_chunk_size: 16 << 20,
+            general_chunk_size: 64 << 20,
+            general_max_block_size: 16 << 20,
+        }
+    }
+}
+
+/// Usage statistics of a memory heap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Size of the heap, in bytes.
+    pub size: u64,
+    /// Device memory allocated from the heap, in bytes.
+    pub allocated: u64,
+    /// Memory used by live blocks, in bytes.
+    pub used: u64,
+    /// Number of live device memory allocations.
+    pub allocations: usize,
+    /// Number of live blocks.
+    pub blocks: usize,
+}
+
+/// Error allocating a block.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllocationError {
+    /// None of the memory types supported by the resource has the requested properties.
+    NoCompatibleMemoryType,
+    /// Out of either host or device memory.
+    OutOfMemory(OutOfMemory),
+    /// The device doesn't support any more memory allocations.
+    TooManyObjects,
+}
+
+impl From<OutOfMemory> for AllocationError {
+    fn from(error: OutOfMemory) -> Self {
+        AllocationError::OutOfMemory(error)
+    }
+}
+
+impl From<device::AllocationError> for AllocationError {
+    fn from(error: device::AllocationError) -> Self {
+        match error {
+            device::AllocationError::OutOfMemory(err) => AllocationError::OutOfMemory(err),
+            device::AllocationError::TooManyObjects => AllocationError::TooManyObjects,
+        }
+    }
+}
+
+impl std::fmt::Display for AllocationError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AllocationError::NoCompatibleMemoryType => {
+                write!(fmt, "Failed to allocate block: No compatible memory type")
+            }
+            AllocationError::OutOfMemory(err) => write!(fmt, "Failed to allocate block: {}", err),
+            AllocationError::TooManyObjects => {
+                write!(fmt, "Failed to allocate block: Too many memory allocations")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AllocationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AllocationError::OutOfMemory(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A range of device memory handed out by an `Allocator`.
+///
+/// Blocks must be returned with `Allocator::free`.
+#[derive(Debug)]
+pub struct Block<B: Backend> {
+    memory: Arc<B::Memory>,
+    memory_type: MemoryTypeId,
+    chunk: usize,
+    range: Range<u64>,
+}
+
+impl<B: Backend> Block<B> {
+    /// The device memory containing the block.
+    pub fn memory(&self) -> &B::Memory {
+        &self.memory
+    }
+
+    /// The memory type of the block.
+    pub fn memory_type(&self) -> MemoryTypeId {
+        self.memory_type
+    }
+
+    /// Offset of the block in its memory, in bytes.
+    pub fn offset(&self) -> u64 {
+        self.range.start
+    }
+
+    /// Size of the block, in bytes.
+    pub fn size(&self) -> u64 {
+        self.range.end - self.range.start
+    }
+
+    /// Range of the block in its memory.
+    pub fn range(&self) -> Range<u64> {
+        self.range.clone()
+    }
+
+    /// Segment of the block in its memory, to flush or invalidate mapped ranges.
+    pub fn segment(&self) -> Segment {
+        Segment {
+            offset: self.range.start,
+            size: Some(self.size()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MemoryType<B: Backend> {
+    properties: Properties,
+    heap_index: usize,
+    /// Live chunks, indexed by the blocks. Freed chunks leave a hole to be reused.
+    chunks: Vec<Option<Chunk<B>>>,
+    /// Chunk the linear strategy currently allocates from.
+    linear_chunk: Option<usize>,
+}
+
+/// Sub-allocator of the device memory of an adapter.
+///
+/// The allocator doesn't own the device, which has to be given to each call.
+/// All blocks must be freed before disposing of the allocator with `dispose`.
+#[derive(Debug)]
+pub struct Allocator<B: Backend> {
+    config: Config,
+    memory_types: Vec<MemoryType<B>>,
+    heaps: Vec<HeapStats>,
+    buffer_image_granularity: u64,
+    max_allocation_count: usize,
+    allocation_count: usize,
+}
+
+impl<B: Backend> Allocator<B> {
+    /// Create an allocator for the memory of an adapter, given its properties and limits.
+    pub fn new(
+        memory_properties: &adapter::MemoryProperties,
+        limits: &Limits,
+        config: Config,
+    ) -> Self {
+        Allocator {
+            config,
+            memory_types: memory_properties
+                .memory_types
+                .iter()
+                .map(|ty| MemoryType {
+                    properties: ty.properties,
+                    heap_index: ty.heap_index,
+                    chunks: Vec::new(),
+                    linear_chunk: None,
+                })
+                .collect(),
+            heaps: memory_properties
+                .memory_heaps
+                .iter()
+                .map(|&size| HeapStats {
+                    size,
+                    ..HeapStats::default()
+                })
+                .collect(),
+            buffer_image_granularity: limits.buffer_image_granularity.max(1),
+            max_allocation_count: limits.max_memory_allocation_count,
+            allocation_count: 0,
+        }
+    }
+
+    /// Usage statistics of the memory heaps, indexed like `MemoryProperties::memory_heaps`.
+    pub fn heap_stats(&self) -> &[HeapStats] {
+        &self.heaps
+    }
+
+    /// Returns the memory types of `type_mask` having all the given properties,
+    /// starting with the ones with the fewest other properties.
+    fn candidates(&self, type_mask: u64, properties: Properties) -> Vec<usize> {
+        let mut candidates = self
+            .memory_types
+            .iter()
+            .enumerate()
+            .filter(|&(i, ty)| type_mask & (1 << i) != 0 && ty.properties.contains(properties))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        // Stable, so that the order of the adapter breaks ties
+        candidates.sort_by_key(|&i| {
+            (self.memory_types[i].properties - properties)
+                .bits()
+                .count_ones()
+        });
+        candidates
+    }
+
+    /// Picks the memory type used for a resource with the given memory type mask,
+    /// and the requested properties.
+    pub fn memory_type(&self, type_mask: u64, properties: Properties) -> Option<MemoryTypeId> {
+        self.candidates(type_mask, properties)
+            .first()
+            .map(|&i| MemoryTypeId(i))
+    }
+
+    /// Allocate a block fulfilling the requirements of a resource, in a memory type
+    /// with all the requested properties.
+    ///
+    /// If a memory type runs out of device memory, the next best one is tried.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the device of the adapter the allocator was created for,
+    /// and the same device must be given to every call.
+    pub unsafe fn allocate(
+        &mut self,
+        device: &B::Device,
+        requirements: &Requirements,
+        properties: Properties,
+        strategy: Strategy,
+    ) -> Result<Block<B>, AllocationError> {
+        let mut result = Err(AllocationError::NoCompatibleMemoryType);
+        for ty in self.candidates(requirements.type_mask, properties) {
+            result = self.allocate_from(device, ty, requirements, strategy);
+            match result {
+                Err(AllocationError::OutOfMemory(OutOfMemory::Device)) => continue,
+                _ => break,
+            }
+        }
+        result
+    }
+
+    unsafe fn allocate_from(
+        &mut self,
+        device: &B::Device,
+        ty: usize,
+        requirements: &Requirements,
+        strategy: Strategy,
+    ) -> Result<Block<B>, AllocationError> {
+        let granularity = self.buffer_image_granularity;
+        let size = align_up(requirements.size, granularity);
+        let alignment = requirements.alignment.max(granularity);
+
+        let chunk_size = match strategy {
+            Strategy::Linear if size <= self.config.linear_chunk_size => {
+                self.config.linear_chunk_size
+            }
+            Strategy::General if size <= self.config.general_max_block_size => {
+                self.config.general_chunk_size
+            }
+            _ => return self.allocate_dedicated(device, ty, requirements.size),
+        };
+
+        let existing = match strategy {
+            Strategy::Linear => self.memory_types[ty].linear_chunk.and_then(|index| {
+                let chunk = self.memory_types[ty].chunks[index].as_mut().unwrap();
+                chunk.allocate(size, alignment).map(|range| (index, range))
+            }),
+            _ => self.memory_types[ty]
+                .chunks
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(index, chunk)| chunk.as_mut().map(|chunk| (index, chunk)))
+                .filter(|(_, chunk)| chunk.is_general())
+                .find_map(|(index, chunk)| {
+                    chunk.allocate(size, alignment).map(|range| (index, range))
+                }),
+        };
+        if let Some((index, range)) = existing {
+            return Ok(self.block(ty, index, range));
+        }
+
+        let kind = match strategy {
+            Strategy::Linear => Kind::Linear {
+                offset: 0,
+                blocks: 0,
+            },
+            _ => Kind::General(RangeAllocator::new(0 .. chunk_size)),
+        };
+        let index = match self.allocate_chunk(device, ty, chunk_size, kind) {
+            Ok(index) => index,
+            // The heap may still have room for the block alone
+            Err(AllocationError::OutOfMemory(_)) => {
+                return self.allocate_dedicated(device, ty, requirements.size)
+            }
+            Err(err) => return Err(err),
+        };
+        if strategy == Strategy::Linear {
+            let memory_type = &mut self.memory_types[ty];
+            if let Some(previous) = memory_type.linear_chunk.replace(index) {
+                // Otherwise, the previous chunk is released along with its last block
+                if memory_type.chunks[previous].as_ref().unwrap().is_unused() {
+                    self.free_chunk(device, ty, previous);
+                }
+            }
+        }
+        let range = self.memory_types[ty].chunks[index]
+            .as_mut()
+            .unwrap()
+            .allocate(size, alignment)
+            .unwrap();
+        Ok(self.block(ty, index, range))
+    }
+
+    unsafe fn allocate_dedicated(
+        &mut self,
+        device: &B::Device,
+        ty: usize,
+        size: u64,
+    ) -> Result<Block<B>, AllocationError> {
+        let index = self.allocate_chunk(device, ty, size, Kind::Dedicated)?;
+        Ok(self.block(ty, index, 0 .. size))
+    }
+
+    unsafe fn allocate_chunk(
+        &mut self,
+        device: &B::Device,
+        ty: usize,
+        size: u64,
+        kind: Kind,
+    ) -> Result<usize, AllocationError> {
+        if self.max_allocation_count != 0 && self.allocation_count >= self.max_allocation_count {
+            return Err(AllocationError::TooManyObjects);
+        }
+        let memory = device.allocate_memory(MemoryTypeId(ty), size)?;
+
+        self.allocation_count += 1;
+        let memory_type = &mut self.memory_types[ty];
+        let heap = &mut self.heaps[memory_type.heap_index];
+        heap.allocated += size;
+        heap.allocations += 1;
+
+        let chunk = Chunk {
+            memory: Arc::new(memory),
+            size,
+            kind,
+            mapping: None,
+        };
+        match memory_type.chunks.iter().position(Option::is_none) {
+            Some(index) => {
+                memory_type.chunks[index] = Some(chunk);
+                Ok(index)
+            }
+            None => {
+                memory_type.chunks.push(Some(chunk));
+                Ok(memory_type.chunks.len() - 1)
+            }
+        }
+    }
+
+    unsafe fn free_chunk(&mut self, device: &B::Device, ty: usize, index: usize) {
+        let memory_type = &mut self.memory_types[ty];
+        let chunk = memory_type.chunks[index].take().unwrap();
+        if memory_type.linear_chunk == Some(index) {
+            memory_type.linear_chunk = None;
+        }
+
+        let heap = &mut self.heaps[memory_type.heap_index];
+        heap.allocated -= chunk.size;
+        heap.allocations -= 1;
+        self.allocation_count -= 1;
+
+        if chunk.mapping.is_some() {
+            device.unmap_memory(&chunk.memory);
+        }
+        let memory = Arc::try_unwrap(chunk.memory)
+            .unwrap_or_else(|_| panic!("Device memory is still used by some blocks"));
+        device.free_memory(memory);
+    }
+
+    fn block(&mut self, ty: usize, index: usize, range: Range<u64>) -> Block<B> {
+        let memory_type = &self.memory_types[ty];
+        let heap = &mut self.heaps[memory_type.heap_index];
+        heap.used += range.end - range.start;
+        heap.blocks += 1;
+
+        Block {
+            memory: Arc::clone(&memory_type.chunks[index].as_ref().unwrap().memory),
+            memory_type: MemoryTypeId(ty),
+            chunk: index,
+            range,
+        }
+    }
+
+    /// Return a block to the allocator.
+    ///
+    /// Dedicated allocations are freed right away, linear chunks once all their
+    /// blocks are freed. A single unused general chunk is kept per memory type.
+    ///
+    /// # Safety
+    ///
+    /// The block must come from this allocator, and must not be in use by the device anymore.
+    pub unsafe fn free(&mut self, device: &B::Device, block: Block<B>) {
+        let Block {
+            memory,
+            memory_type: MemoryTypeId(ty),
+            chunk: index,
+            range,
+        } = block;
+        drop(memory);
+
+        let memory_type = &mut self.memory_types[ty];
+        let heap = &mut self.heaps[memory_type.heap_index];
+        heap.used -= range.end - range.start;
+        heap.blocks -= 1;
+
+        if !memory_type.chunks[index].as_mut().unwrap().free(range) {
+            return;
+        }
+        let release = match memory_type.chunks[index].as_ref().unwrap().kind {
+            Kind::Dedicated => true,
+            Kind::Linear { .. } => memory_type.linear_chunk != Some(index),
+            Kind::General(_) => {
+                memory_type
+                    .chunks
+                    .iter()
+                    .enumerate()
+                    .any(|(i, chunk)| match *chunk {
+                        Some(ref chunk) => i != index && chunk.is_general() && chunk.is_unused(),
+                        None => false,
+                    })
+            }
+        };
+        if release {
+            self.free_chunk(device, ty, index);
+        }
+    }
+
+    /// Map the memory of a block, returning the host address of its start.
+    ///
+    /// The whole memory of a chunk is mapped the first time one of its blocks is,
+    /// and stays mapped until the chunk is freed: blocks are never unmapped individually.
+    ///
+    /// # Safety
+    ///
+    /// The block must come from this allocator, and its memory type must be `CPU_VISIBLE`.
+    /// The returned pointer is only valid until the block is freed.
+    pub unsafe fn map(
+        &mut self,
+        device: &B::Device,
+        block: &Block<B>,
+    ) -> Result<*mut u8, MapError> {
+        let chunk = self.memory_types[block.memory_type.0].chunks[block.chunk]
+            .as_mut()
+            .unwrap();
+        let ptr = match chunk.mapping {
+            Some(Mapping(ptr)) => ptr,
+            None => {
+                let ptr = device.map_memory(&chunk.memory, Segment::ALL)?;
+                chunk.mapping = Some(Mapping(ptr));
+                ptr
+            }
+        };
+        Ok(ptr.offset(block.range.start as isize))
+    }
+
+    /// Free all the device memory of the allocator.
+    ///
+    /// # Safety
+    ///
+    /// None of the memory may be in use by the device anymore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if some blocks were not freed.
+    pub unsafe fn dispose(mut self, device: &B::Device) {
+        for ty in 0 .. self.memory_types.len() {
+            for index in 0 .. self.memory_types[ty].chunks.len() {
+                if self.memory_types[ty].chunks[index].is_some() {
+                    self.free_chunk(device, ty, index);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gfx_backend_empty as empty;
+    use hal::{adapter::PhysicalDevice as _, Instance as _};
+
+    fn open(limits: impl FnOnce(&mut Limits)) -> (empty::Device, Allocator<empty::Backend>) {
+        let mut config = empty::MockConfig::default();
+        limits(&mut config.limits);
+        let instance = empty::Instance::with_config(config);
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let allocator = Allocator::new(
+            &adapter.physical_device.memory_properties(),
+            &adapter.physical_device.limits(),
+            Config {
+                linear_chunk_size: 1 << 12,
+                general_chunk_size: 1 << 16,
+                general_max_block_size: 1 << 14,
+            },
+        );
+        (gpu.device, allocator)
+    }
+
+    fn requirements(size: u64, alignment: u64) -> Requirements {
+        Requirements {
+            size,
+            alignment,
+            type_mask: !0,
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Allocator<empty::Backend>>();
+        foo::<Block<empty::Backend>>();
+    }
+
+    #[test]
+    fn select_memory_type() {
+        let (device, mut allocator) = open(|_| ());
+        assert_eq!(
+            allocator.memory_type(!0, Properties::CPU_VISIBLE),
+            Some(MemoryTypeId(1))
+        );
+        assert_eq!(
+            allocator.memory_type(0b100, Properties::CPU_VISIBLE),
+            Some(MemoryTypeId(2))
+        );
+        assert_eq!(allocator.memory_type(0b110, Properties::DEVICE_LOCAL), None);
+
+        let result = unsafe {
+            allocator.allocate(
+                &device,
+                &Requirements {
+                    type_mask: 0b110,
+                    ..requirements(256, 16)
+                },
+                Properties::DEVICE_LOCAL,
+                Strategy::General,
+            )
+        };
+        assert_eq!(result.err(), Some(AllocationError::NoCompatibleMemoryType));
+        unsafe { allocator.dispose(&device) };
+    }
+
+    #[test]
+    fn general_blocks_share_chunks() {
+        let (device, mut allocator) = open(|limits| limits.buffer_image_granularity = 256);
+        unsafe {
+            let blocks = (0 .. 3)
+                .map(|_| {
+                    allocator
+                        .allocate(
+                            &device,
+                            &requirements(100, 64),
+                            Properties::DEVICE_LOCAL,
+                            Strategy::General,
+                        )
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+            let offsets = blocks.iter().map(Block::offset).collect::<Vec<_>>();
+            assert_eq!(offsets, vec![0, 256, 512]);
+            assert_eq!(
+                allocator.heap_stats()[0],
+                HeapStats {
+                    size: 1 << 30,
+                    allocated: 1 << 16,
+                    used: 3 * 256,
+                    allocations: 1,
+                    blocks: 3,
+                }
+            );
+
+            // Large blocks are given their own allocation
+            let large = allocator
+                .allocate(
+                    &device,
+                    &requirements(1 << 15, 64),
+                    Properties::DEVICE_LOCAL,
+                    Strategy::General,
+                )
+                .unwrap();
+            assert_eq!(allocator.heap_stats()[0].allocations, 2);
+            allocator.free(&device, large);
+            assert_eq!(allocator.heap_stats()[0].allocations, 1);
+
+            // The last general chunk is kept around
+            for block in blocks {
+                allocator.free(&device, block);
+            }
+            assert_eq!(allocator.heap_stats()[0].allocations, 1);
+            assert_eq!(allocator.heap_stats()[0].used, 0);
+            allocator.dispose(&device);
+        }
+    }
+
+    #[test]
+    fn linear_chunks_are_reused() {
+        let (device, mut allocator) = open(|_| ());
+        unsafe {
+            let first = allocator
+                .allocate(
+                    &device,
+                    &requirements(100, 1),
+                    Properties::CPU_VISIBLE,
+                    Strategy::Linear,
+                )
+                .unwrap();
+            let second = allocator
+                .allocate(
+                    &device,
+                    &requirements(100, 64),
+                    Properties::CPU_VISIBLE,
+                    Strategy::Linear,
+                )
+                .unwrap();
+            assert_eq!(second.range(), 128 .. 228);
+            let ptr = allocator.map(&device, &first).unwrap();
+            assert_eq!(allocator.map(&device, &second).unwrap(), ptr.offset(128));
+
+            allocator.free(&device, first);
+            allocator.free(&device, second);
+            let third = allocator
+                .allocate(
+                    &device,
+                    &requirements(100, 64),
+                    Properties::CPU_VISIBLE,
+                    Strategy::Linear,
+                )
+                .unwrap();
+            assert_eq!(third.offset(), 0);
+            assert_eq!(allocator.heap_stats()[1].allocations, 1);
+            allocator.free(&device, third);
+            allocator.dispose(&device);
+        }
+    }
+
+    #[test]
+    fn report_too_many_allocations() {
+        let (device, mut allocator) = open(|limits| limits.max_memory_allocation_count = 1);
+        unsafe {
+            let block = allocator
+                .allocate(
+                    &device,
+                    &requirements(100, 1),
+                    Properties::DEVICE_LOCAL,
+                    Strategy::Dedicated,
+                )
+                .unwrap();
+            assert_eq!(block.size(), 100);
+            let result = allocator.allocate(
+                &device,
+                &requirements(100, 1),
+                Properties::DEVICE_LOCAL,
+                Strategy::General,
+            );
+            assert_eq!(result.err(), Some(AllocationError::TooManyObjects));
+            allocator.free(&device, block);
+            allocator.dispose(&device);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::EntryPoint;
+use hal::pso;
+
+use std::{borrow::Borrow, ops::Range};
+
+/// Error building a pipeline layout from entry points.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutError {
+    /// Two entry points declare the binding with different descriptor types.
+    Conflict {
+        /// Descriptor set of the binding.
+        set: pso::DescriptorSetIndex,
+        /// Binding number within the set.
+        binding: pso::DescriptorBinding,
+    },
+    /// The binding is a runtime sized array, so its descriptor count is unknown.
+    UnsizedArray {
+        /// Descriptor set of the binding.
+        set: pso::DescriptorSetIndex,
+        /// Binding number within the set.
+        binding: pso::DescriptorBinding,
+    },
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::Conflict { set, binding } => write!(
+                fmt,
+                "Failed to build pipeline layout: Conflicting types for set {} binding {}",
+                set, binding
+            ),
+            LayoutError::UnsizedArray { set, binding } => write!(
+                fmt,
+                "Failed to build pipeline layout: Unsized array at set {} binding {}",
+                set, binding
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// Descriptor set layouts and push constant ranges of a pipeline layout.
+#[derive(Clone, Debug)]
+pub struct PipelineLayoutDesc {
+    /// Bindings of each descriptor set, sorted by binding, indexed by set.
+    ///
+    /// Sets below the highest one used that no entry point uses are left empty.
+    pub sets: Vec<Vec<pso::DescriptorSetLayoutBinding>>,
+    /// Push constant ranges, stages using the same range share an entry.
+    pub push_constants: Vec<(pso::ShaderStageFlags, Range<u32>)>,
+}
+
+impl PipelineLayoutDesc {
+    /// Merges the resources of the reflected entry points of a pipeline, such as those
+    /// of a `GraphicsShaderSet` or of a compute shader.
+    ///
+    /// A binding used by several entry points is visible to all their stages. Storage
+    /// buffers and images are only read only if they are in all of the entry points.
+    /// Arrays take the largest count declared.
+    pub fn new<I>(entry_points: I) -> Result<Self, LayoutError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<EntryPoint>,
+    {
+        let mut sets = Vec::<Vec<pso::DescriptorSetLayoutBinding>>::new();
+        let mut stage_ranges = Vec::<(pso::ShaderStageFlags, Range<u32>)>::new();
+
+        for entry in entry_points {
+            let entry = entry.borrow();
+            let stage = pso::ShaderStageFlags::from(entry.stage);
+
+            for binding in &entry.bindings {
+                if binding.count == 0 {
+                    return Err(LayoutError::UnsizedArray {
+                        set: binding.set,
+                        binding: binding.binding,
+                    });
+                }
+                let set = binding.set as usize;
+                if sets.len() <= set {
+                    sets.resize_with(set + 1, Vec::new);
+                }
+                let bindings = &mut sets[set];
+                match bindings.iter_mut().find(|b| b.binding == binding.binding) {
+                    Some(existing) => {
+                        existing.ty =
+                            merge_types(existing.ty, binding.ty).ok_or(LayoutError::Conflict {
+                                set: binding.set,
+                                binding: binding.binding,
+                            })?;
+                        existing.count = existing.count.max(binding.count);
+                        existing.stage_flags |= stage;
+                    }
+                    None => bindings.push(pso::DescriptorSetLayoutBinding {
+                        binding: binding.binding,
+                        ty: binding.ty,
+                        count: binding.count,
+                        stage_flags: stage,
+                        immutable_samplers: false,
+                        flags: pso::DescriptorBindingFlags::empty(),
+                    }),
+                }
+            }
+
+            if let Some(ref range) = entry.push_constants {
+                match stage_ranges.iter_mut().find(|&&mut (s, _)| s == stage) {
+                    Some(&mut (_, ref mut existing)) => {
+                        existing.start = existing.start.min(range.start);
+                        existing.end = existing.end.max(range.end);
+                    }
+                    None => stage_ranges.push((stage, range.clone())),
+                }
+            }
+        }
+
+        for bindings in &mut sets {
+            bindings.sort_by_key(|binding| binding.binding);
+        }
+        let mut push_constants = Vec::<(pso::ShaderStageFlags, Range<u32>)>::new();
+        for (stage, range) in stage_ranges {
+            match push_constants
+                .iter_mut()
+                .find(|&&mut (_, ref r)| *r == range)
+            {
+                Some(&mut (ref mut stages, _)) => *stages |= stage,
+                None => push_constants.push((stage, range)),
+            }
+        }
+
+        Ok(PipelineLayoutDesc {
+            sets,
+            push_constants,
+        })
+    }
+}
+
+/// Returns the type of a binding declared as both `a` and `b`, if compatible.
+fn merge_types(a: pso::DescriptorType, b: pso::DescriptorType) -> Option<pso::DescriptorType> {
+    use hal::pso::{BufferDescriptorType as Bdt, DescriptorType as Dt, ImageDescriptorType as Idt};
+
+    match (a, b) {
+        _ if a == b => Some(a),
+        (
+            Dt::Buffer {
+                ty: Bdt::Storage { read_only: ra },
+                format: fa,
+            },
+            Dt::Buffer {
+                ty: Bdt::Storage { read_only: rb },
+                format: fb,
+            },
+        ) if fa == fb => Some(Dt::Buffer {
+            ty: Bdt::Storage {
+                read_only: ra && rb,
+            },
+            format: fa,
+        }),
+        (
+            Dt::Image {
+                ty: Idt::Storage { read_only: ra },
+            },
+            Dt::Image {
+                ty: Idt::Storage { read_only: rb },
+            },
+        ) => Some(Dt::Image {
+            ty: Idt::Storage {
+                read_only: ra && rb,
+            },
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Binding;
+    use hal::pso::{BufferDescriptorFormat, BufferDescriptorType, DescriptorType};
+
+    #[test]
+    fn layout() {
+        let storage = |read_only| DescriptorType::Buffer {
+            ty: BufferDescriptorType::Storage { read_only },
+            format: BufferDescriptorFormat::Structured {
+                dynamic_offset: false,
+            },
+        };
+        let binding = |set, binding, ty, count| Binding {
+            name: String::new(),
+            set,
+            binding,
+            ty,
+            count,
+        };
+        let entry = |stage, bindings, push_constants| EntryPoint {
+            name: "main".to_string(),
+            stage,
+            bindings,
+            push_constants,
+            inputs: Vec::new(),
+            workgroup_size: None,
+        };
+        let vertex = entry(
+            pso::Stage::Vertex,
+            vec![
+                binding(0, 1, storage(true), 1),
+                binding(2, 0, DescriptorType::Sampler, 2),
+            ],
+            Some(0 .. 16),
+        );
+        let fragment = entry(
+            pso::Stage::Fragment,
+            vec![
+                binding(0, 1, storage(false), 1),
+                binding(0, 0, DescriptorType::Sampler, 1),
+                binding(2, 0, DescriptorType::Sampler, 4),
+            ],
+            Some(0 .. 16),
+        );
+        let geometry = entry(pso::Stage::Geometry, Vec::new(), Some(16 .. 32));
+
+        let desc = PipelineLayoutDesc::new(&[vertex.clone(), fragment, geometry]).unwrap();
+        let sets = desc
+            .sets
+            .iter()
+            .map(|bindings| {
+                bindings
+                    .iter()
+                    .map(|b| (b.binding, b.ty, b.count, b.stage_flags))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let all = pso::ShaderStageFlags::VERTEX | pso::ShaderStageFlags::FRAGMENT;
+        assert_eq!(
+            sets,
+            vec![
+                vec![
+                    (
+                        0,
+                        DescriptorType::Sampler,
+                        1,
+                        pso::ShaderStageFlags::FRAGMENT,
+                    ),
+                    (1, storage(false), 1, all),
+                ],
+                vec![],
+                vec![(0, DescriptorType::Sampler, 4, all)],
+            ]
+        );
+        assert_eq!(
+            desc.push_constants,
+            vec![(all, 0 .. 16), (pso::ShaderStageFlags::GEOMETRY, 16 .. 32),]
+        );
+
+        let conflicting = entry(
+            pso::Stage::Fragment,
+            vec![binding(2, 0, storage(true), 2)],
+            None,
+        );
+        assert_eq!(
+            PipelineLayoutDesc::new(vec![&vertex, &conflicting]).err(),
+            Some(LayoutError::Conflict { set: 2, binding: 0 })
+        );
+    }
+}
@@ -0,0 +1,757 @@
+//! SPIR-V reflection.
+//!
+//! `Module::reflect` lists the entry points of a SPIR-V module along with the resources
+//! each of them uses: descriptor bindings, push constants, vertex inputs and, for compute
+//! shaders, the workgroup size. The specialization constants are listed for the whole
+//! module, as they are shared by its entry points.
+//!
+//! Only the resources statically used by an entry point, or any function it calls, are
+//! reported for it.
+//!
+//! `PipelineLayoutDesc` merges the resources of the entry points of a pipeline into the
+//! descriptor set layout bindings and push constant ranges of its layout.
+
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+pub use crate::layout::{LayoutError, PipelineLayoutDesc};
+
+mod layout;
+mod parse;
+
+use hal::{format::Format, pso};
+use parse::{Id, Type};
+use spirv_headers as spirv;
+
+use std::ops::Range;
+
+/// Error reflecting a SPIR-V module.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReflectError {
+    /// The module doesn't start with a SPIR-V header.
+    InvalidHeader,
+    /// The instruction at the given word offset is malformed.
+    InvalidInstruction(usize),
+    /// The given id is undeclared, or declares something unexpected.
+    InvalidId(u32),
+}
+
+impl std::fmt::Display for ReflectError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReflectError::InvalidHeader => write!(fmt, "Failed to reflect SPIR-V: Invalid header"),
+            ReflectError::InvalidInstruction(offset) => write!(
+                fmt,
+                "Failed to reflect SPIR-V: Invalid instruction at word {}",
+                offset
+            ),
+            ReflectError::InvalidId(id) => {
+                write!(fmt, "Failed to reflect SPIR-V: Invalid id %{}", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+/// Type of a specialization constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScalarType {
+    /// Boolean, set as a 32-bit value.
+    Bool,
+    /// Integer.
+    Int {
+        /// Width in bits.
+        width: u32,
+        /// Whether the integer is signed.
+        signed: bool,
+    },
+    /// Floating point number.
+    Float {
+        /// Width in bits.
+        width: u32,
+    },
+}
+
+/// A specialization constant, set through `pso::Specialization`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecializationConstant {
+    /// Name of the constant, empty if the module has no debug names.
+    pub name: String,
+    /// Id of the constant, matching `pso::SpecializationConstant::id`.
+    pub id: u32,
+    /// Type of the constant.
+    pub ty: ScalarType,
+}
+
+/// A descriptor binding used by an entry point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    /// Name of the variable, or of its block type for anonymous blocks.
+    pub name: String,
+    /// Descriptor set of the binding.
+    pub set: pso::DescriptorSetIndex,
+    /// Binding number within the set.
+    pub binding: pso::DescriptorBinding,
+    /// Type of the descriptors.
+    pub ty: pso::DescriptorType,
+    /// Number of descriptors, 0 for runtime sized arrays.
+    pub count: pso::DescriptorArrayIndex,
+}
+
+/// A vertex input of an entry point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Input {
+    /// Name of the variable.
+    pub name: String,
+    /// Location of the input.
+    pub location: u32,
+    /// Format of the input, as expected by `pso::AttributeDesc`.
+    pub format: Format,
+}
+
+/// An entry point of a module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryPoint {
+    /// Name of the entry point, as passed to `pso::EntryPoint::entry`.
+    pub name: String,
+    /// Pipeline stage of the entry point.
+    pub stage: pso::Stage,
+    /// Descriptor bindings, sorted by set and binding.
+    pub bindings: Vec<Binding>,
+    /// Range of the push constants used, in bytes.
+    pub push_constants: Option<Range<u32>>,
+    /// Vertex inputs sorted by location, empty for stages other than `Stage::Vertex`.
+    ///
+    /// Matrices and arrays take one input per column or element.
+    pub inputs: Vec<Input>,
+    /// Workgroup size of compute entry points.
+    ///
+    /// Sizes set by specialization constants are reported with their default value.
+    pub workgroup_size: Option<[u32; 3]>,
+}
+
+/// Reflection of a SPIR-V module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Module {
+    /// Entry points of the module.
+    ///
+    /// Entry points of execution models without a matching `pso::Stage` are skipped.
+    pub entry_points: Vec<EntryPoint>,
+    /// Specialization constants of the module, sorted by id.
+    pub specialization_constants: Vec<SpecializationConstant>,
+}
+
+impl Module {
+    /// Reflects the SPIR-V module `words`, as returned by `pso::read_spirv`.
+    pub fn reflect(words: &[u32]) -> Result<Self, ReflectError> {
+        let module = parse::Module::parse(words)?;
+
+        let mut entry_points = Vec::new();
+        for entry in &module.entry_points {
+            let stage = match entry.model {
+                spirv::ExecutionModel::Vertex => pso::Stage::Vertex,
+                spirv::ExecutionModel::TessellationControl => pso::Stage::Hull,
+                spirv::ExecutionModel::TessellationEvaluation => pso::Stage::Domain,
+                spirv::ExecutionModel::Geometry => pso::Stage::Geometry,
+                spirv::ExecutionModel::Fragment => pso::Stage::Fragment,
+                spirv::ExecutionModel::GLCompute => pso::Stage::Compute,
+                _ => continue,
+            };
+            let mut uses = module.uses(entry.function);
+            uses.extend(&entry.interface);
+
+            let mut bindings = Vec::new();
+            let mut push_constants = None;
+            let mut inputs = Vec::new();
+            for variable in module.variables.iter().filter(|v| uses.contains(&v.id)) {
+                let decorations = module.decorations(variable.id);
+                match variable.class {
+                    spirv::StorageClass::UniformConstant
+                    | spirv::StorageClass::Uniform
+                    | spirv::StorageClass::StorageBuffer => {
+                        let (set, binding) = match (decorations.set, decorations.binding) {
+                            (Some(set), Some(binding)) => (set, binding),
+                            _ => continue,
+                        };
+                        let (element, count) = array_element(&module, variable.ty)?;
+                        let mut name = module.name(variable.id);
+                        if name.is_empty() {
+                            name = module.name(element);
+                        }
+                        bindings.push(Binding {
+                            name: name.to_string(),
+                            set: set as _,
+                            binding,
+                            ty: descriptor_type(&module, variable, element)?,
+                            count,
+                        });
+                    }
+                    spirv::StorageClass::PushConstant => {
+                        push_constants = Some(block_range(&module, variable.ty)?);
+                    }
+                    spirv::StorageClass::Input if stage == pso::Stage::Vertex => {
+                        if decorations.builtin.is_some() {
+                            continue;
+                        }
+                        if let Some(location) = decorations.location {
+                            let name = module.name(variable.id);
+                            vertex_inputs(&module, name, location, variable.ty, &mut inputs)?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            bindings.sort_by_key(|binding| (binding.set, binding.binding));
+            inputs.sort_by_key(|input| input.location);
+
+            let workgroup_size = if stage == pso::Stage::Compute {
+                workgroup_size(&module, entry)?
+            } else {
+                None
+            };
+
+            entry_points.push(EntryPoint {
+                name: entry.name.clone(),
+                stage,
+                bindings,
+                push_constants,
+                inputs,
+                workgroup_size,
+            });
+        }
+
+        let mut specialization_constants = Vec::new();
+        for (&id, constant) in module.constants.iter().filter(|&(_, c)| c.spec) {
+            let spec_id = match module.decorations(id).spec_id {
+                Some(spec_id) => spec_id,
+                None => continue,
+            };
+            let ty = match *module.ty(constant.ty)? {
+                Type::Bool => ScalarType::Bool,
+                Type::Int { width, signed } => ScalarType::Int { width, signed },
+                Type::Float { width } => ScalarType::Float { width },
+                _ => return Err(ReflectError::InvalidId(constant.ty)),
+            };
+            specialization_constants.push(SpecializationConstant {
+                name: module.name(id).to_string(),
+                id: spec_id,
+                ty,
+            });
+        }
+        specialization_constants.sort_by_key(|constant| constant.id);
+
+        Ok(Module {
+            entry_points,
+            specialization_constants,
+        })
+    }
+
+    /// Returns the entry point named `name`.
+    pub fn entry_point(&self, name: &str) -> Option<&EntryPoint> {
+        self.entry_points.iter().find(|entry| entry.name == name)
+    }
+}
+
+/// Strips the outer array of a descriptor binding, returning its element type and count.
+fn array_element(
+    module: &parse::Module,
+    ty: Id,
+) -> Result<(Id, pso::DescriptorArrayIndex), ReflectError> {
+    match *module.ty(ty)? {
+        Type::Array { element, length } => Ok((element, module.constant(length)? as _)),
+        Type::RuntimeArray { element } => Ok((element, 0)),
+        _ => Ok((ty, 1)),
+    }
+}
+
+fn descriptor_type(
+    module: &parse::Module,
+    variable: &parse::Variable,
+    ty: Id,
+) -> Result<pso::DescriptorType, ReflectError> {
+    use hal::pso::{
+        BufferDescriptorFormat as Bdf,
+        BufferDescriptorType as Bdt,
+        DescriptorType as Dt,
+        ImageDescriptorType as Idt,
+    };
+
+    Ok(match *module.ty(ty)? {
+        Type::Sampler => Dt::Sampler,
+        Type::SampledImage { image } => match *module.ty(image)? {
+            Type::Image {
+                dim: spirv::Dim::DimBuffer,
+                ..
+            } => Dt::Buffer {
+                ty: Bdt::Uniform,
+                format: Bdf::Texel,
+            },
+            _ => Dt::Image {
+                ty: Idt::Sampled { with_sampler: true },
+            },
+        },
+        Type::Image {
+            dim: spirv::Dim::DimSubpassData,
+            ..
+        } => Dt::InputAttachment,
+        Type::Image {
+            dim: spirv::Dim::DimBuffer,
+            sampled,
+        } => Dt::Buffer {
+            ty: if sampled == 2 {
+                Bdt::Storage {
+                    read_only: module.decorations(variable.id).non_writable,
+                }
+            } else {
+                Bdt::Uniform
+            },
+            format: Bdf::Texel,
+        },
+        Type::Image { sampled: 2, .. } => Dt::Image {
+            ty: Idt::Storage {
+                read_only: module.decorations(variable.id).non_writable,
+            },
+        },
+        Type::Image { .. } => Dt::Image {
+            ty: Idt::Sampled {
+                with_sampler: false,
+            },
+        },
+        Type::Struct { ref members } => {
+            let storage = variable.class == spirv::StorageClass::StorageBuffer
+                || module.decorations(ty).buffer_block;
+            Dt::Buffer {
+                ty: if storage {
+                    let read_only = module.decorations(variable.id).non_writable
+                        || (0 .. members.len())
+                            .all(|member| module.member_decorations(ty, member).non_writable);
+                    Bdt::Storage { read_only }
+                } else {
+                    Bdt::Uniform
+                },
+                format: Bdf::Structured {
+                    dynamic_offset: false,
+                },
+            }
+        }
+        _ => return Err(ReflectError::InvalidId(ty)),
+    })
+}
+
+/// Returns the size in bytes of a type with an explicit layout.
+fn size(module: &parse::Module, ty: Id, matrix_stride: Option<u32>) -> Result<u32, ReflectError> {
+    Ok(match *module.ty(ty)? {
+        Type::Bool => 4,
+        Type::Int { width, .. } | Type::Float { width } => width / 8,
+        Type::Vector { component, count } => count * size(module, component, None)?,
+        Type::Matrix { column, count } => match matrix_stride {
+            Some(stride) => count * stride,
+            None => count * size(module, column, None)?,
+        },
+        Type::Array { element, length } => {
+            let count = module.constant(length)?;
+            match module.decorations(ty).array_stride {
+                Some(stride) => count * stride,
+                None => count * size(module, element, matrix_stride)?,
+            }
+        }
+        Type::Struct { .. } => block_range(module, ty)?.end,
+        _ => return Err(ReflectError::InvalidId(ty)),
+    })
+}
+
+/// Returns the range of bytes covered by the members of a block.
+fn block_range(module: &parse::Module, ty: Id) -> Result<Range<u32>, ReflectError> {
+    let members = match *module.ty(ty)? {
+        Type::Struct { ref members } => members,
+        _ => return Err(ReflectError::InvalidId(ty)),
+    };
+    let mut start = None;
+    let mut end = 0;
+    for (i, &member) in members.iter().enumerate() {
+        let decorations = module.member_decorations(ty, i);
+        let offset = decorations.offset.ok_or(ReflectError::InvalidId(ty))?;
+        start = Some(start.map_or(offset, |start: u32| start.min(offset)));
+        end = end.max(offset + size(module, member, decorations.matrix_stride)?);
+    }
+    // Push constant ranges are specified in multiples of 4 bytes
+    Ok(start.unwrap_or(0) .. (end + 3) & !3)
+}
+
+/// Collects the inputs of a vertex input variable, returning the number of locations taken.
+fn vertex_inputs(
+    module: &parse::Module,
+    name: &str,
+    location: u32,
+    ty: Id,
+    inputs: &mut Vec<Input>,
+) -> Result<u32, ReflectError> {
+    let (scalar, count) = match *module.ty(ty)? {
+        Type::Array {
+            element: inner,
+            length,
+        } => {
+            let mut taken = 0;
+            for _ in 0 .. module.constant(length)? {
+                taken += vertex_inputs(module, name, location + taken, inner, inputs)?;
+            }
+            return Ok(taken);
+        }
+        Type::Matrix {
+            column: inner,
+            count,
+        } => {
+            let mut taken = 0;
+            for _ in 0 .. count {
+                taken += vertex_inputs(module, name, location + taken, inner, inputs)?;
+            }
+            return Ok(taken);
+        }
+        Type::Vector { component, count } => (component, count),
+        _ => (ty, 1),
+    };
+    let format = match *module.ty(scalar)? {
+        Type::Float { width } => float_format(width, count),
+        Type::Int { width, signed } => int_format(width, signed, count),
+        _ => None,
+    };
+    let format = format.ok_or(ReflectError::InvalidId(ty))?;
+    inputs.push(Input {
+        name: name.to_string(),
+        location,
+        format,
+    });
+    // 64-bit vectors of three and four components take two locations
+    Ok(if format.surface_desc().bits > 128 {
+        2
+    } else {
+        1
+    })
+}
+
+fn float_format(width: u32, count: u32) -> Option<Format> {
+    use hal::format::Format as F;
+    let formats = match width {
+        16 => [F::R16Sfloat, F::Rg16Sfloat, F::Rgb16Sfloat, F::Rgba16Sfloat],
+        32 => [F::R32Sfloat, F::Rg32Sfloat, F::Rgb32Sfloat, F::Rgba32Sfloat],
+        64 => [F::R64Sfloat, F::Rg64Sfloat, F::Rgb64Sfloat, F::Rgba64Sfloat],
+        _ => return None,
+    };
+    formats.get(count.checked_sub(1)? as usize).cloned()
+}
+
+fn int_format(width: u32, signed: bool, count: u32) -> Option<Format> {
+    use hal::format::Format as F;
+    let formats = match (width, signed) {
+        (8, true) => [F::R8Sint, F::Rg8Sint, F::Rgb8Sint, F::Rgba8Sint],
+        (8, false) => [F::R8Uint, F::Rg8Uint, F::Rgb8Uint, F::Rgba8Uint],
+        (16, true) => [F::R16Sint, F::Rg16Sint, F::Rgb16Sint, F::Rgba16Sint],
+        (16, false) => [F::R16Uint, F::Rg16Uint, F::Rgb16Uint, F::Rgba16Uint],
+        (32, true) => [F::R32Sint, F::Rg32Sint, F::Rgb32Sint, F::Rgba32Sint],
+        (32, false) => [F::R32Uint, F::Rg32Uint, F::Rgb32Uint, F::Rgba32Uint],
+        (64, true) => [F::R64Sint, F::Rg64Sint, F::Rgb64Sint, F::Rgba64Sint],
+        (64, false) => [F::R64Uint, F::Rg64Uint, F::Rgb64Uint, F::Rgba64Uint],
+        _ => return None,
+    };
+    formats.get(count.checked_sub(1)? as usize).cloned()
+}
+
+fn workgroup_size(
+    module: &parse::Module,
+    entry: &parse::EntryPoint,
+) -> Result<Option<[u32; 3]>, ReflectError> {
+    // The `WorkgroupSize` built-in takes precedence over the execution modes
+    let builtin = module
+        .constants
+        .iter()
+        .find(|&(&id, _)| module.decorations(id).builtin == Some(spirv::BuiltIn::WorkgroupSize));
+    if let Some((&id, constant)) = builtin {
+        if constant.constituents.len() != 3 {
+            return Err(ReflectError::InvalidId(id));
+        }
+        let mut size = [0; 3];
+        for (size, &id) in size.iter_mut().zip(&constant.constituents) {
+            *size = module.constant(id)?;
+        }
+        return Ok(Some(size));
+    }
+    if let Some(ids) = entry.local_size_id {
+        let mut size = [0; 3];
+        for (size, &id) in size.iter_mut().zip(&ids) {
+            *size = module.constant(id)?;
+        }
+        return Ok(Some(size));
+    }
+    Ok(entry.local_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::pso::{DescriptorType, ImageDescriptorType};
+
+    fn load(name: &str) -> Vec<u32> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../../examples/quad/data")
+            .join(name);
+        pso::read_spirv(std::fs::File::open(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Module::reflect(&[]), Err(ReflectError::InvalidHeader));
+        let header = [spirv::MAGIC_NUMBER, 0x10000, 0, 8, 0];
+        let truncated: [&[u32]; 5] = [
+            // Word count past the end of the module
+            &[5 << 16],
+            // OpEntryPoint without function nor name
+            &[2 << 16 | spirv::Op::EntryPoint as u32, 0],
+            // OpName without target
+            &[1 << 16 | spirv::Op::Name as u32],
+            // OpTypeStruct without result id
+            &[1 << 16 | spirv::Op::TypeStruct as u32],
+            // OpConstantComposite without result id
+            &[2 << 16 | spirv::Op::ConstantComposite as u32, 1],
+        ];
+        for instruction in truncated.iter() {
+            let words = [&header[..], instruction].concat();
+            assert_eq!(
+                Module::reflect(&words),
+                Err(ReflectError::InvalidInstruction(5))
+            );
+        }
+    }
+
+    #[test]
+    fn quad() {
+        let vertex = Module::reflect(&load("quad.vert.spv")).unwrap();
+        assert_eq!(
+            vertex.specialization_constants,
+            vec![SpecializationConstant {
+                name: "scale".to_string(),
+                id: 0,
+                ty: ScalarType::Float { width: 32 },
+            }]
+        );
+        let entry = vertex.entry_point("main").unwrap();
+        assert_eq!(entry.stage, pso::Stage::Vertex);
+        assert!(entry.bindings.is_empty());
+        let inputs = entry
+            .inputs
+            .iter()
+            .map(|input| (input.location, input.format))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            vec![(0, Format::Rg32Sfloat), (1, Format::Rg32Sfloat)]
+        );
+
+        let fragment = Module::reflect(&load("quad.frag.spv")).unwrap();
+        let entry = fragment.entry_point("main").unwrap();
+        assert_eq!(entry.stage, pso::Stage::Fragment);
+        let bindings = entry
+            .bindings
+            .iter()
+            .map(|binding| (binding.set, binding.binding, binding.ty, binding.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bindings,
+            vec![
+                (
+                    0,
+                    0,
+                    DescriptorType::Image {
+                        ty: ImageDescriptorType::Sampled {
+                            with_sampler: false,
+                        },
+                    },
+                    1,
+                ),
+                (0, 1, DescriptorType::Sampler, 1),
+            ]
+        );
+        assert!(entry.inputs.is_empty());
+    }
+}
+
+#[cfg(all(test, feature = "glsl-to-spirv"))]
+mod glsl_tests {
+    use super::*;
+    use hal::pso::{
+        BufferDescriptorFormat,
+        BufferDescriptorType,
+        DescriptorType,
+        ImageDescriptorType,
+    };
+
+    fn compile(code: &str, ty: glsl_to_spirv::ShaderType) -> Vec<u32> {
+        let file = glsl_to_spirv::compile(code, ty).unwrap();
+        pso::read_spirv(file).unwrap()
+    }
+
+    #[test]
+    fn graphics() {
+        let words = compile(
+            r#"
+            #version 450
+            layout(constant_id = 3) const float scale = 1.0;
+            layout(constant_id = 1) const int count = 4;
+            layout(location = 0) in vec2 a_pos;
+            layout(location = 1) in ivec4 a_index;
+            layout(location = 2) in mat3 a_transform;
+            layout(set = 0, binding = 0) uniform Locals { mat4 transform; };
+            layout(set = 1, binding = 2) uniform sampler2D u_textures[4];
+            layout(set = 1, binding = 1) uniform texture2D u_unused;
+            layout(push_constant) uniform Constants { vec4 offset; uint layer; } constants;
+            layout(location = 0) out vec2 v_uv;
+            void main() {
+                v_uv = texture(u_textures[a_index.x], a_pos).xy * scale * float(count);
+                gl_Position = transform * vec4(a_transform * vec3(a_pos, 1.0), 1.0) + constants.offset;
+            }
+            "#,
+            glsl_to_spirv::ShaderType::Vertex,
+        );
+        let module = Module::reflect(&words).unwrap();
+        assert_eq!(
+            module.specialization_constants,
+            vec![
+                SpecializationConstant {
+                    name: "count".to_string(),
+                    id: 1,
+                    ty: ScalarType::Int {
+                        width: 32,
+                        signed: true,
+                    },
+                },
+                SpecializationConstant {
+                    name: "scale".to_string(),
+                    id: 3,
+                    ty: ScalarType::Float { width: 32 },
+                },
+            ]
+        );
+
+        let entry = module.entry_point("main").unwrap();
+        assert_eq!(entry.stage, pso::Stage::Vertex);
+        assert_eq!(
+            entry.bindings,
+            vec![
+                Binding {
+                    name: "Locals".to_string(),
+                    set: 0,
+                    binding: 0,
+                    ty: DescriptorType::Buffer {
+                        ty: BufferDescriptorType::Uniform,
+                        format: BufferDescriptorFormat::Structured {
+                            dynamic_offset: false,
+                        },
+                    },
+                    count: 1,
+                },
+                Binding {
+                    name: "u_textures".to_string(),
+                    set: 1,
+                    binding: 2,
+                    ty: DescriptorType::Image {
+                        ty: ImageDescriptorType::Sampled { with_sampler: true },
+                    },
+                    count: 4,
+                },
+            ]
+        );
+        assert_eq!(entry.push_constants, Some(0 .. 20));
+        let inputs = entry
+            .inputs
+            .iter()
+            .map(|input| (input.location, input.format))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            vec![
+                (0, Format::Rg32Sfloat),
+                (1, Format::Rgba32Sint),
+                (2, Format::Rgb32Sfloat),
+                (3, Format::Rgb32Sfloat),
+                (4, Format::Rgb32Sfloat),
+            ]
+        );
+        assert_eq!(entry.workgroup_size, None);
+    }
+
+    #[test]
+    fn compute() {
+        let words = compile(
+            r#"
+            #version 450
+            layout(local_size_x = 8, local_size_y = 4) in;
+            layout(set = 0, binding = 0) readonly buffer Input { float values[]; } b_input;
+            layout(set = 0, binding = 1) buffer Output { float values[]; } b_output;
+            layout(set = 0, binding = 2, rgba8) uniform writeonly image2D u_image;
+            layout(set = 0, binding = 3) uniform samplerBuffer u_texels;
+            void main() {
+                uint i = gl_GlobalInvocationID.x;
+                b_output.values[i] = b_input.values[i] + texelFetch(u_texels, int(i)).x;
+                imageStore(u_image, ivec2(gl_GlobalInvocationID.xy), vec4(1.0));
+            }
+            "#,
+            glsl_to_spirv::ShaderType::Compute,
+        );
+        let module = Module::reflect(&words).unwrap();
+        let entry = &module.entry_points[0];
+        assert_eq!(entry.stage, pso::Stage::Compute);
+        let types = entry
+            .bindings
+            .iter()
+            .map(|binding| (binding.binding, binding.ty, binding.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                (
+                    0,
+                    DescriptorType::Buffer {
+                        ty: BufferDescriptorType::Storage { read_only: true },
+                        format: BufferDescriptorFormat::Structured {
+                            dynamic_offset: false,
+                        },
+                    },
+                    1,
+                ),
+                (
+                    1,
+                    DescriptorType::Buffer {
+                        ty: BufferDescriptorType::Storage { read_only: false },
+                        format: BufferDescriptorFormat::Structured {
+                            dynamic_offset: false,
+                        },
+                    },
+                    1,
+                ),
+                (
+                    2,
+                    DescriptorType::Image {
+                        ty: ImageDescriptorType::Storage { read_only: false },
+                    },
+                    1,
+                ),
+                (
+                    3,
+                    DescriptorType::Buffer {
+                        ty: BufferDescriptorType::Uniform,
+                        format: BufferDescriptorFormat::Texel,
+                    },
+                    1,
+                ),
+            ]
+        );
+        assert_eq!(entry.push_constants, None);
+        assert!(entry.inputs.is_empty());
+        assert_eq!(entry.workgroup_size, Some([8, 4, 1]));
+    }
+}
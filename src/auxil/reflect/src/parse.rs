@@ -0,0 +1,441 @@
+//! Parsing of the SPIR-V declarations relevant to reflection.
+
+use crate::ReflectError;
+use num_traits::FromPrimitive;
+use spirv_headers as spirv;
+
+use std::collections::{HashMap, HashSet};
+
+pub type Id = u32;
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Bool,
+    Int {
+        width: u32,
+        signed: bool,
+    },
+    Float {
+        width: u32,
+    },
+    Vector {
+        component: Id,
+        count: u32,
+    },
+    Matrix {
+        column: Id,
+        count: u32,
+    },
+    Image {
+        dim: spirv::Dim,
+        sampled: u32,
+    },
+    Sampler,
+    SampledImage {
+        image: Id,
+    },
+    Array {
+        element: Id,
+        length: Id,
+    },
+    RuntimeArray {
+        element: Id,
+    },
+    Struct {
+        members: Vec<Id>,
+    },
+    Pointer {
+        pointee: Id,
+    },
+    /// Types that never appear in the reflected interfaces.
+    Other,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Decorations {
+    pub builtin: Option<spirv::BuiltIn>,
+    pub location: Option<u32>,
+    pub set: Option<u32>,
+    pub binding: Option<u32>,
+    pub spec_id: Option<u32>,
+    pub offset: Option<u32>,
+    pub array_stride: Option<u32>,
+    pub matrix_stride: Option<u32>,
+    pub buffer_block: bool,
+    pub non_writable: bool,
+}
+
+impl Decorations {
+    fn apply(&mut self, decoration: spirv::Decoration, arguments: &[u32]) {
+        use spirv::Decoration as D;
+        let argument = arguments.first().cloned();
+        match decoration {
+            D::BuiltIn => self.builtin = argument.and_then(spirv::BuiltIn::from_u32),
+            D::Location => self.location = argument,
+            D::DescriptorSet => self.set = argument,
+            D::Binding => self.binding = argument,
+            D::SpecId => self.spec_id = argument,
+            D::Offset => self.offset = argument,
+            D::ArrayStride => self.array_stride = argument,
+            D::MatrixStride => self.matrix_stride = argument,
+            D::BufferBlock => self.buffer_block = true,
+            D::NonWritable => self.non_writable = true,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Constant {
+    pub ty: Id,
+    /// First word of the value of scalar constants.
+    pub value: Option<u32>,
+    pub constituents: Vec<Id>,
+    pub spec: bool,
+}
+
+#[derive(Debug)]
+pub struct EntryPoint {
+    pub model: spirv::ExecutionModel,
+    pub name: String,
+    pub function: Id,
+    pub interface: Vec<Id>,
+    pub local_size: Option<[u32; 3]>,
+    pub local_size_id: Option<[Id; 3]>,
+}
+
+#[derive(Debug)]
+pub struct Variable {
+    pub id: Id,
+    pub class: spirv::StorageClass,
+    /// Type of the variable, behind its pointer type.
+    pub ty: Id,
+}
+
+#[derive(Debug, Default)]
+pub struct Function {
+    /// Ids referenced by the instructions of the function.
+    pub uses: HashSet<Id>,
+    pub calls: Vec<Id>,
+}
+
+/// Declarations of a SPIR-V module.
+#[derive(Debug, Default)]
+pub struct Module {
+    pub types: HashMap<Id, Type>,
+    pub constants: HashMap<Id, Constant>,
+    pub variables: Vec<Variable>,
+    pub functions: HashMap<Id, Function>,
+    pub entry_points: Vec<EntryPoint>,
+    pub names: HashMap<Id, String>,
+    pub decorations: HashMap<Id, Decorations>,
+    pub member_decorations: HashMap<(Id, u32), Decorations>,
+}
+
+fn parse_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        for &byte in &word.to_le_bytes() {
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), i + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+impl Module {
+    pub fn parse(words: &[u32]) -> Result<Self, ReflectError> {
+        use spirv::Op;
+
+        if words.len() < 5 || words[0] != spirv::MAGIC_NUMBER {
+            return Err(ReflectError::InvalidHeader);
+        }
+        let mut module = Module::default();
+        let mut function: Option<(Id, Function)> = None;
+        let mut position = 5;
+
+        while position < words.len() {
+            let offset = position;
+            let count = (words[position] >> 16) as usize;
+            if count == 0 || position + count > words.len() {
+                return Err(ReflectError::InvalidInstruction(offset));
+            }
+            let op = Op::from_u32(words[position] & 0xFFFF);
+            let operands = &words[position + 1 .. position + count];
+            position += count;
+            let operand = |index: usize| {
+                operands
+                    .get(index)
+                    .cloned()
+                    .ok_or(ReflectError::InvalidInstruction(offset))
+            };
+            let operands_from = |index: usize| {
+                operands
+                    .get(index ..)
+                    .ok_or(ReflectError::InvalidInstruction(offset))
+            };
+
+            if let Some((_, ref mut body)) = function {
+                match op {
+                    Some(Op::FunctionEnd) => {
+                        let (id, body) = function.take().unwrap();
+                        module.functions.insert(id, body);
+                    }
+                    Some(Op::FunctionCall) => {
+                        body.calls.push(operand(2)?);
+                        body.uses.extend(operands_from(3)?);
+                    }
+                    // Literal operands are conservatively treated as ids, at worst
+                    // reporting an unused variable
+                    _ => body.uses.extend(operands),
+                }
+                continue;
+            }
+
+            let op = match op {
+                Some(op) => op,
+                None => continue,
+            };
+            match op {
+                Op::EntryPoint => {
+                    let model = spirv::ExecutionModel::from_u32(operand(0)?)
+                        .ok_or(ReflectError::InvalidInstruction(offset))?;
+                    let (name, length) = parse_string(operands_from(2)?);
+                    module.entry_points.push(EntryPoint {
+                        model,
+                        name,
+                        function: operand(1)?,
+                        interface: operands_from(2 + length)?.to_vec(),
+                        local_size: None,
+                        local_size_id: None,
+                    });
+                }
+                Op::ExecutionMode | Op::ExecutionModeId => {
+                    let function = operand(0)?;
+                    let mode = spirv::ExecutionMode::from_u32(operand(1)?);
+                    let size = [operand(2), operand(3), operand(4)];
+                    for entry in module
+                        .entry_points
+                        .iter_mut()
+                        .filter(|entry| entry.function == function)
+                    {
+                        match mode {
+                            Some(spirv::ExecutionMode::LocalSize) => {
+                                entry.local_size =
+                                    Some([size[0].clone()?, size[1].clone()?, size[2].clone()?]);
+                            }
+                            Some(spirv::ExecutionMode::LocalSizeId) => {
+                                entry.local_size_id =
+                                    Some([size[0].clone()?, size[1].clone()?, size[2].clone()?]);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Op::Name => {
+                    let id = operand(0)?;
+                    let (name, _) = parse_string(operands_from(1)?);
+                    module.names.insert(id, name);
+                }
+                Op::Decorate => {
+                    if let Some(decoration) = spirv::Decoration::from_u32(operand(1)?) {
+                        module
+                            .decorations
+                            .entry(operand(0)?)
+                            .or_insert_with(Decorations::default)
+                            .apply(decoration, operands_from(2)?);
+                    }
+                }
+                Op::MemberDecorate => {
+                    if let Some(decoration) = spirv::Decoration::from_u32(operand(2)?) {
+                        module
+                            .member_decorations
+                            .entry((operand(0)?, operand(1)?))
+                            .or_insert_with(Decorations::default)
+                            .apply(decoration, operands_from(3)?);
+                    }
+                }
+                Op::TypeBool => {
+                    module.types.insert(operand(0)?, Type::Bool);
+                }
+                Op::TypeInt => {
+                    let ty = Type::Int {
+                        width: operand(1)?,
+                        signed: operand(2)? != 0,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeFloat => {
+                    let ty = Type::Float { width: operand(1)? };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeVector => {
+                    let ty = Type::Vector {
+                        component: operand(1)?,
+                        count: operand(2)?,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeMatrix => {
+                    let ty = Type::Matrix {
+                        column: operand(1)?,
+                        count: operand(2)?,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeImage => {
+                    let ty = Type::Image {
+                        dim: spirv::Dim::from_u32(operand(2)?)
+                            .ok_or(ReflectError::InvalidInstruction(offset))?,
+                        sampled: operand(6)?,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeSampler => {
+                    module.types.insert(operand(0)?, Type::Sampler);
+                }
+                Op::TypeSampledImage => {
+                    let ty = Type::SampledImage { image: operand(1)? };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeArray => {
+                    let ty = Type::Array {
+                        element: operand(1)?,
+                        length: operand(2)?,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeRuntimeArray => {
+                    let ty = Type::RuntimeArray {
+                        element: operand(1)?,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeStruct => {
+                    let id = operand(0)?;
+                    let ty = Type::Struct {
+                        members: operands_from(1)?.to_vec(),
+                    };
+                    module.types.insert(id, ty);
+                }
+                Op::TypePointer => {
+                    let ty = Type::Pointer {
+                        pointee: operand(2)?,
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                Op::TypeVoid
+                | Op::TypeFunction
+                | Op::TypeOpaque
+                | Op::TypeEvent
+                | Op::TypeDeviceEvent
+                | Op::TypeReserveId
+                | Op::TypeQueue
+                | Op::TypePipe
+                | Op::TypeForwardPointer
+                | Op::TypeAccelerationStructureNV => {
+                    module.types.insert(operand(0)?, Type::Other);
+                }
+                Op::Constant
+                | Op::ConstantTrue
+                | Op::ConstantFalse
+                | Op::ConstantComposite
+                | Op::ConstantNull
+                | Op::SpecConstant
+                | Op::SpecConstantTrue
+                | Op::SpecConstantFalse
+                | Op::SpecConstantComposite => {
+                    let (value, constituents) = match op {
+                        Op::Constant | Op::SpecConstant => (Some(operand(2)?), Vec::new()),
+                        Op::ConstantTrue | Op::SpecConstantTrue => (Some(1), Vec::new()),
+                        Op::ConstantFalse | Op::SpecConstantFalse | Op::ConstantNull => {
+                            (Some(0), Vec::new())
+                        }
+                        _ => (None, operands_from(2)?.to_vec()),
+                    };
+                    let spec = matches!(
+                        op,
+                        Op::SpecConstant
+                            | Op::SpecConstantTrue
+                            | Op::SpecConstantFalse
+                            | Op::SpecConstantComposite
+                    );
+                    let constant = Constant {
+                        ty: operand(0)?,
+                        value,
+                        constituents,
+                        spec,
+                    };
+                    module.constants.insert(operand(1)?, constant);
+                }
+                Op::Variable => {
+                    let class = spirv::StorageClass::from_u32(operand(2)?)
+                        .ok_or(ReflectError::InvalidInstruction(offset))?;
+                    let pointer = operand(0)?;
+                    let ty = match module.types.get(&pointer) {
+                        Some(&Type::Pointer { pointee }) => pointee,
+                        _ => return Err(ReflectError::InvalidId(pointer)),
+                    };
+                    module.variables.push(Variable {
+                        id: operand(1)?,
+                        class,
+                        ty,
+                    });
+                }
+                Op::Function => {
+                    function = Some((operand(1)?, Function::default()));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(module)
+    }
+
+    /// Returns the type declared with `id`.
+    pub fn ty(&self, id: Id) -> Result<&Type, ReflectError> {
+        self.types.get(&id).ok_or(ReflectError::InvalidId(id))
+    }
+
+    /// Returns the value of the scalar constant declared with `id`.
+    pub fn constant(&self, id: Id) -> Result<u32, ReflectError> {
+        self.constants
+            .get(&id)
+            .and_then(|constant| constant.value)
+            .ok_or(ReflectError::InvalidId(id))
+    }
+
+    pub fn decorations(&self, id: Id) -> Decorations {
+        self.decorations.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub fn member_decorations(&self, id: Id, member: usize) -> Decorations {
+        self.member_decorations
+            .get(&(id, member as u32))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn name(&self, id: Id) -> &str {
+        self.names.get(&id).map_or("", String::as_str)
+    }
+
+    /// Collects the ids used by a function and all the functions it calls.
+    pub fn uses(&self, function: Id) -> HashSet<Id> {
+        let mut uses = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![function];
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(function) = self.functions.get(&id) {
+                uses.extend(&function.uses);
+                pending.extend(&function.calls);
+            }
+        }
+        uses
+    }
+}
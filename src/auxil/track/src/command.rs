@@ -0,0 +1,138 @@
+use hal::{format, image};
+
+use std::{collections::HashMap, hash::Hash, mem, ops::Range};
+
+use crate::{
+    subresource::{Subresource, Subresources},
+    Barriers,
+    BufferState,
+    ImageState,
+    State,
+};
+
+/// States of a resource at the start and the end of a command buffer.
+#[derive(Clone, Debug)]
+pub(crate) struct Tracked<S> {
+    /// State expected by the first uses.
+    pub first: S,
+    /// State left by the last uses.
+    pub last: S,
+    /// Whether no barrier was needed since the first use, in which case `first` and
+    /// `last` are the same.
+    pub since_start: bool,
+}
+
+impl<S: State> Tracked<S> {
+    fn new(state: S) -> Self {
+        Tracked {
+            first: state,
+            last: state,
+            since_start: true,
+        }
+    }
+
+    /// Moves to the `next` state, returning the barrier needed if any.
+    fn transition(&mut self, next: S) -> Option<Range<S>> {
+        if self.last.merge(&next) {
+            if self.since_start {
+                self.first = self.last;
+            }
+            None
+        } else {
+            self.since_start = false;
+            Some(mem::replace(&mut self.last, next) .. next)
+        }
+    }
+
+    /// Moves the `current` state of a queue through the uses of the command buffer,
+    /// returning the barrier needed before it if any.
+    pub fn merge_into(&self, current: &mut S) -> Option<Range<S>> {
+        // Reads may only skip the barrier if they aren't followed by another barrier
+        // within the command buffer, since it doesn't wait for the previous reads.
+        if self.since_start && current.merge(&self.first) {
+            None
+        } else {
+            let states = *current .. self.first;
+            *current = self.last;
+            Some(states)
+        }
+    }
+}
+
+/// Tracks the uses of resources by the commands of a command buffer.
+///
+/// Resources are identified by keys of type `K`, shared with the `Tracker` of the queue.
+/// Each use is declared before recording the commands performing it, after which
+/// the barriers returned by `take_barriers` must be recorded.
+#[derive(Debug)]
+pub struct CommandTracker<K> {
+    buffers: HashMap<K, Tracked<BufferState>>,
+    images: HashMap<K, Subresources<(format::Aspects, Tracked<ImageState>)>>,
+    pending: Barriers<K>,
+}
+
+impl<K> Default for CommandTracker<K> {
+    fn default() -> Self {
+        CommandTracker {
+            buffers: HashMap::new(),
+            images: HashMap::new(),
+            pending: Barriers::default(),
+        }
+    }
+}
+
+impl<K: Copy + Hash + Eq> CommandTracker<K> {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets all the uses, to record the command buffer again.
+    pub fn clear(&mut self) {
+        self.buffers.clear();
+        self.images.clear();
+        self.pending = Barriers::default();
+    }
+
+    /// Declares the next use of a buffer.
+    pub fn use_buffer(&mut self, key: K, state: BufferState) {
+        if let Some(tracked) = self.buffers.get_mut(&key) {
+            if let Some(states) = tracked.transition(state) {
+                self.pending.push_buffer(key, states);
+            }
+        } else {
+            self.buffers.insert(key, Tracked::new(state));
+        }
+    }
+
+    /// Declares the next use of the subresources `range` of an image.
+    pub fn use_image(&mut self, key: K, range: &image::SubresourceRange, state: ImageState) {
+        let subresources = self.images.entry(key).or_default();
+        for sub in Subresource::iter(range) {
+            match subresources.get_mut(sub) {
+                Some((aspects, tracked)) => {
+                    *aspects = range.aspects;
+                    if let Some(states) = tracked.transition(state) {
+                        self.pending.push_image(key, range.aspects, sub, states);
+                    }
+                }
+                slot @ None => *slot = Some((range.aspects, Tracked::new(state))),
+            }
+        }
+    }
+
+    /// Takes the barriers needed by the uses declared since the last call.
+    pub fn take_barriers(&mut self) -> Barriers<K> {
+        mem::take(&mut self.pending)
+    }
+
+    pub(crate) fn buffers(&self) -> impl Iterator<Item = (&K, &Tracked<BufferState>)> {
+        self.buffers.iter()
+    }
+
+    pub(crate) fn images(
+        &self,
+    ) -> impl Iterator<Item = (&K, &Subresources<(format::Aspects, Tracked<ImageState>)>)> {
+        self.images.iter()
+    }
+}
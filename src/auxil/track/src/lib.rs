@@ -0,0 +1,556 @@
+//! Resource state and barrier tracking.
+//!
+//! Recording the right `memory::Barrier` between two uses of a resource requires knowing
+//! how it was last accessed, which is easy to get wrong by hand. Instead, every use of a
+//! resource is declared with its intended `BufferState` or `ImageState`, and the trackers
+//! work out the pipeline barriers needed to get there:
+//!
+//! - `CommandTracker` follows the resources used by a single command buffer. Barriers
+//!   between uses within the command buffer are batched in `Barriers`, to be recorded
+//!   before the commands performing the new uses.
+//! - `Tracker` knows the states resources are left in by the submitted command buffers.
+//!   Merging a `CommandTracker` at submission time produces the barriers bringing its
+//!   resources to the states expected by its first commands.
+//!
+//! Consecutive reads of a resource, in the same image layout, don't need any barrier.
+//! Images are tracked per subresource (mip level and array layer).
+
+#![warn(
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+use hal::{
+    buffer,
+    command::CommandBuffer,
+    format,
+    image,
+    memory::{Barrier, Dependencies},
+    pso::PipelineStage,
+    Backend,
+};
+
+use std::{collections::HashMap, hash::Hash, ops::Range};
+
+use crate::subresource::Subresources;
+pub use crate::{command::CommandTracker, subresource::Subresource};
+
+mod command;
+mod subresource;
+
+/// Use of a buffer, or the state it is left in by its last uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferState {
+    /// Accesses performed on the buffer.
+    pub access: buffer::Access,
+    /// Pipeline stages performing the accesses.
+    pub stages: PipelineStage,
+}
+
+impl BufferState {
+    /// State of a buffer that was never used.
+    pub const UNUSED: Self = BufferState {
+        access: buffer::Access::empty(),
+        stages: PipelineStage::empty(),
+    };
+
+    const WRITES: buffer::Access = buffer::Access::from_bits_truncate(
+        buffer::Access::SHADER_WRITE.bits()
+            | buffer::Access::TRANSFER_WRITE.bits()
+            | buffer::Access::HOST_WRITE.bits()
+            | buffer::Access::MEMORY_WRITE.bits()
+            | buffer::Access::TRANSFORM_FEEDBACK_WRITE.bits(),
+    );
+
+    /// Whether the contents of the buffer are read.
+    pub fn reads(&self) -> bool {
+        !(self.access - Self::WRITES).is_empty()
+    }
+
+    /// Whether the contents of the buffer are written.
+    pub fn writes(&self) -> bool {
+        self.access.intersects(Self::WRITES)
+    }
+}
+
+/// Use of an image subresource, or the state it is left in by its last uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ImageState {
+    /// Accesses performed on the image.
+    pub access: image::Access,
+    /// Layout of the image during the accesses.
+    pub layout: image::Layout,
+    /// Pipeline stages performing the accesses.
+    pub stages: PipelineStage,
+}
+
+impl ImageState {
+    /// State of an image that was never used, with undefined contents.
+    pub const UNDEFINED: Self = ImageState {
+        access: image::Access::empty(),
+        layout: image::Layout::Undefined,
+        stages: PipelineStage::empty(),
+    };
+
+    const WRITES: image::Access = image::Access::from_bits_truncate(
+        image::Access::SHADER_WRITE.bits()
+            | image::Access::COLOR_ATTACHMENT_WRITE.bits()
+            | image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE.bits()
+            | image::Access::TRANSFER_WRITE.bits()
+            | image::Access::HOST_WRITE.bits()
+            | image::Access::MEMORY_WRITE.bits(),
+    );
+
+    /// Whether the contents of the image are read.
+    pub fn reads(&self) -> bool {
+        !(self.access - Self::WRITES).is_empty()
+    }
+
+    /// Whether the contents of the image are written.
+    pub fn writes(&self) -> bool {
+        self.access.intersects(Self::WRITES)
+    }
+}
+
+/// State of a tracked resource, which can be transitioned to another state.
+pub(crate) trait State: Copy + PartialEq {
+    /// Merges `next` into this state if no barrier is needed between them,
+    /// which is the case of reads following reads.
+    fn merge(&mut self, next: &Self) -> bool;
+    fn stages(&self) -> PipelineStage;
+}
+
+impl State for BufferState {
+    fn merge(&mut self, next: &Self) -> bool {
+        if self.writes() || next.writes() {
+            return false;
+        }
+        self.access |= next.access;
+        self.stages |= next.stages;
+        true
+    }
+
+    fn stages(&self) -> PipelineStage {
+        self.stages
+    }
+}
+
+impl State for ImageState {
+    fn merge(&mut self, next: &Self) -> bool {
+        if self.layout != next.layout || self.writes() || next.writes() {
+            return false;
+        }
+        self.access |= next.access;
+        self.stages |= next.stages;
+        true
+    }
+
+    fn stages(&self) -> PipelineStage {
+        self.stages
+    }
+}
+
+/// Barrier transitioning a whole buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BufferBarrier<K> {
+    /// Key of the buffer.
+    pub key: K,
+    /// Accesses before and after the barrier.
+    pub states: Range<buffer::State>,
+}
+
+/// Barrier transitioning a range of image subresources.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageBarrier<K> {
+    /// Key of the image.
+    pub key: K,
+    /// Accesses and layouts before and after the barrier.
+    pub states: Range<image::State>,
+    /// Subresources transitioned.
+    pub range: image::SubresourceRange,
+}
+
+/// A batch of barriers, recorded by a single pipeline barrier command.
+#[derive(Clone, Debug)]
+pub struct Barriers<K> {
+    stages: Range<PipelineStage>,
+    buffers: Vec<BufferBarrier<K>>,
+    images: Vec<ImageBarrier<K>>,
+}
+
+impl<K> Default for Barriers<K> {
+    fn default() -> Self {
+        Barriers {
+            stages: PipelineStage::empty() .. PipelineStage::empty(),
+            buffers: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+}
+
+impl<K: Copy + PartialEq> Barriers<K> {
+    /// Whether there is no barrier to record.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty() && self.images.is_empty()
+    }
+
+    /// Stages to wait for, and stages waiting, at the pipeline barrier.
+    pub fn stages(&self) -> Range<PipelineStage> {
+        self.stages.clone()
+    }
+
+    /// Barriers on buffers.
+    pub fn buffers(&self) -> &[BufferBarrier<K>] {
+        &self.buffers
+    }
+
+    /// Barriers on images.
+    pub fn images(&self) -> &[ImageBarrier<K>] {
+        &self.images
+    }
+
+    /// Records the barriers as a single pipeline barrier, if there are any.
+    ///
+    /// The resources are looked up from their keys with `buffer` and `image`.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be recording, outside of a render pass.
+    pub unsafe fn record<'a, B, C>(
+        &self,
+        command_buffer: &mut C,
+        buffer: impl Fn(&K) -> &'a B::Buffer,
+        image: impl Fn(&K) -> &'a B::Image,
+    ) where
+        B: Backend,
+        C: CommandBuffer<B>,
+    {
+        if self.is_empty() {
+            return;
+        }
+        let buffers = self
+            .buffers
+            .iter()
+            .map(|barrier| Barrier::whole_buffer(buffer(&barrier.key), barrier.states.clone()));
+        let images = self.images.iter().map(|barrier| Barrier::Image {
+            states: barrier.states.clone(),
+            target: image(&barrier.key),
+            range: barrier.range.clone(),
+            families: None,
+        });
+        command_buffer.pipeline_barrier(
+            self.stages.clone(),
+            Dependencies::empty(),
+            buffers.chain(images),
+        );
+    }
+
+    fn add_stages<S: State>(&mut self, states: &Range<S>) {
+        // Stage masks can't be empty, resources that were never used wait for nothing
+        self.stages.start |= match states.start.stages() {
+            stages if stages.is_empty() => PipelineStage::TOP_OF_PIPE,
+            stages => stages,
+        };
+        self.stages.end |= match states.end.stages() {
+            stages if stages.is_empty() => PipelineStage::BOTTOM_OF_PIPE,
+            stages => stages,
+        };
+    }
+
+    pub(crate) fn push_buffer(&mut self, key: K, states: Range<BufferState>) {
+        self.add_stages(&states);
+        self.buffers.push(BufferBarrier {
+            key,
+            states: states.start.access .. states.end.access,
+        });
+    }
+
+    /// Adds a barrier on a single subresource, extending the last barrier if possible.
+    ///
+    /// Subresources of an image are expected in order of level, then layer.
+    pub(crate) fn push_image(
+        &mut self,
+        key: K,
+        aspects: format::Aspects,
+        sub: Subresource,
+        states: Range<ImageState>,
+    ) {
+        self.add_stages(&states);
+        let states =
+            (states.start.access, states.start.layout) .. (states.end.access, states.end.layout);
+        let same = |barrier: &ImageBarrier<K>| {
+            barrier.key == key && barrier.states == states && barrier.range.aspects == aspects
+        };
+
+        match self.images.last_mut() {
+            Some(last)
+                if same(last)
+                    && last.range.levels == (sub.level .. sub.level + 1)
+                    && last.range.layers.end == sub.layer =>
+            {
+                last.range.layers.end += 1;
+            }
+            _ => self.images.push(ImageBarrier {
+                key,
+                states: states.clone(),
+                range: image::SubresourceRange {
+                    aspects,
+                    levels: sub.level .. sub.level + 1,
+                    layers: sub.layer .. sub.layer + 1,
+                },
+            }),
+        }
+
+        // Merge the layers of this level with the same layers of the previous level
+        if let [.., previous, last] = &mut self.images[..] {
+            if same(previous)
+                && previous.range.levels.end == sub.level
+                && previous.range.layers == last.range.layers
+            {
+                previous.range.levels.end += 1;
+                self.images.pop();
+            }
+        }
+    }
+}
+
+/// Tracks the states resources are left in by the command buffers submitted to a queue.
+///
+/// Resources are identified by keys of type `K`.
+#[derive(Debug)]
+pub struct Tracker<K> {
+    buffers: HashMap<K, BufferState>,
+    images: HashMap<K, Subresources<(format::Aspects, ImageState)>>,
+}
+
+impl<K> Default for Tracker<K> {
+    fn default() -> Self {
+        Tracker {
+            buffers: HashMap::new(),
+            images: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Copy + Hash + Eq> Tracker<K> {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking a buffer, currently in `state`.
+    ///
+    /// Newly created buffers are in `BufferState::UNUSED`.
+    pub fn register_buffer(&mut self, key: K, state: BufferState) {
+        self.buffers.insert(key, state);
+    }
+
+    /// Starts tracking the subresources `range` of an image, currently in `state`.
+    ///
+    /// Newly created images are in `ImageState::UNDEFINED`.
+    pub fn register_image(&mut self, key: K, range: &image::SubresourceRange, state: ImageState) {
+        let subresources = self.images.entry(key).or_default();
+        for sub in Subresource::iter(range) {
+            *subresources.get_mut(sub) = Some((range.aspects, state));
+        }
+    }
+
+    /// Stops tracking a buffer, typically before destroying it.
+    pub fn unregister_buffer(&mut self, key: &K) {
+        self.buffers.remove(key);
+    }
+
+    /// Stops tracking an image, typically before destroying it.
+    pub fn unregister_image(&mut self, key: &K) {
+        self.images.remove(key);
+    }
+
+    /// Returns the current state of a buffer, if tracked.
+    pub fn buffer_state(&self, key: &K) -> Option<BufferState> {
+        self.buffers.get(key).cloned()
+    }
+
+    /// Returns the current state of an image subresource, if tracked.
+    pub fn image_state(&self, key: &K, sub: Subresource) -> Option<ImageState> {
+        self.images
+            .get(key)
+            .and_then(|subresources| subresources.get(sub))
+            .map(|&(_, state)| state)
+    }
+
+    /// Merges the uses of a command buffer about to be submitted.
+    ///
+    /// Returns the barriers bringing the resources to the states expected by the
+    /// command buffer, to be recorded in a command buffer submitted right before it.
+    /// Resources that aren't tracked yet are assumed to be in the expected states already.
+    /// The tracked states are then updated to the last uses of the command buffer.
+    ///
+    /// Each command buffer of a submission must be merged in order, with the returned
+    /// barriers recorded between it and the previous one.
+    pub fn merge(&mut self, commands: &CommandTracker<K>) -> Barriers<K> {
+        let mut barriers = Barriers::default();
+
+        for (&key, tracked) in commands.buffers() {
+            match self.buffers.get_mut(&key) {
+                Some(current) => {
+                    if let Some(states) = tracked.merge_into(current) {
+                        barriers.push_buffer(key, states);
+                    }
+                }
+                None => {
+                    self.buffers.insert(key, tracked.last);
+                }
+            }
+        }
+
+        for (&key, subresources) in commands.images() {
+            let current = self.images.entry(key).or_default();
+            for (sub, &(aspects, ref tracked)) in subresources.iter() {
+                match current.get_mut(sub) {
+                    Some((current_aspects, current)) => {
+                        *current_aspects = aspects;
+                        if let Some(states) = tracked.merge_into(current) {
+                            barriers.push_image(key, aspects, sub, states);
+                        }
+                    }
+                    slot @ None => *slot = Some((aspects, tracked.last)),
+                }
+            }
+        }
+
+        barriers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READ: BufferState = BufferState {
+        access: buffer::Access::UNIFORM_READ,
+        stages: PipelineStage::VERTEX_SHADER,
+    };
+    const FRAGMENT_READ: BufferState = BufferState {
+        access: buffer::Access::SHADER_READ,
+        stages: PipelineStage::FRAGMENT_SHADER,
+    };
+    const WRITE: BufferState = BufferState {
+        access: buffer::Access::TRANSFER_WRITE,
+        stages: PipelineStage::TRANSFER,
+    };
+    const SAMPLED: ImageState = ImageState {
+        access: image::Access::SHADER_READ,
+        layout: image::Layout::ShaderReadOnlyOptimal,
+        stages: PipelineStage::FRAGMENT_SHADER,
+    };
+    const COPY_DST: ImageState = ImageState {
+        access: image::Access::TRANSFER_WRITE,
+        layout: image::Layout::TransferDstOptimal,
+        stages: PipelineStage::TRANSFER,
+    };
+
+    fn range(levels: Range<image::Level>, layers: Range<image::Layer>) -> image::SubresourceRange {
+        image::SubresourceRange {
+            aspects: format::Aspects::COLOR,
+            levels,
+            layers,
+        }
+    }
+
+    #[test]
+    fn reads_share_a_barrier() {
+        let mut commands = CommandTracker::new();
+        commands.use_buffer(0, WRITE);
+        commands.use_buffer(0, READ);
+        commands.use_buffer(0, FRAGMENT_READ);
+        let barriers = commands.take_barriers();
+        assert_eq!(
+            barriers.stages(),
+            PipelineStage::TRANSFER .. PipelineStage::VERTEX_SHADER
+        );
+        assert_eq!(barriers.buffers().len(), 1);
+
+        commands.use_buffer(0, WRITE);
+        let barriers = commands.take_barriers();
+        assert_eq!(
+            barriers.stages(),
+            PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER
+                .. PipelineStage::TRANSFER
+        );
+        assert_eq!(
+            barriers.buffers(),
+            &[BufferBarrier {
+                key: 0,
+                states: buffer::Access::UNIFORM_READ | buffer::Access::SHADER_READ
+                    .. buffer::Access::TRANSFER_WRITE,
+            }]
+        );
+        assert!(commands.take_barriers().is_empty());
+    }
+
+    #[test]
+    fn image_barriers_cover_ranges() {
+        let mut commands = CommandTracker::new();
+        commands.use_image(0, &range(0 .. 3, 0 .. 2), COPY_DST);
+        commands.use_image(0, &range(0 .. 3, 0 .. 2), SAMPLED);
+        let barriers = commands.take_barriers();
+        assert_eq!(barriers.images().len(), 1);
+        assert_eq!(barriers.images()[0].range, range(0 .. 3, 0 .. 2));
+
+        commands.use_image(0, &range(1 .. 2, 1 .. 2), COPY_DST);
+        commands.take_barriers();
+        commands.use_image(0, &range(0 .. 3, 0 .. 2), SAMPLED);
+        let barriers = commands.take_barriers();
+        let ranges = barriers
+            .images()
+            .iter()
+            .map(|barrier| barrier.range.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![range(1 .. 2, 1 .. 2)]);
+
+        commands.use_image(0, &range(0 .. 3, 0 .. 1), COPY_DST);
+        let barriers = commands.take_barriers();
+        assert_eq!(barriers.images().len(), 1);
+        assert_eq!(barriers.images()[0].range, range(0 .. 3, 0 .. 1));
+    }
+
+    #[test]
+    fn merge_at_submit() {
+        let mut tracker = Tracker::new();
+        tracker.register_buffer(0, BufferState::UNUSED);
+        tracker.register_image(1, &range(0 .. 2, 0 .. 1), ImageState::UNDEFINED);
+
+        let mut commands = CommandTracker::new();
+        commands.use_buffer(0, WRITE);
+        commands.use_buffer(0, READ);
+        commands.use_image(1, &range(0 .. 2, 0 .. 1), COPY_DST);
+        commands.use_image(2, &range(0 .. 1, 0 .. 1), SAMPLED);
+        commands.take_barriers();
+
+        let barriers = tracker.merge(&commands);
+        assert_eq!(
+            barriers.stages(),
+            PipelineStage::TOP_OF_PIPE .. PipelineStage::TRANSFER
+        );
+        assert_eq!(barriers.buffers().len(), 1);
+        assert_eq!(barriers.images().len(), 1);
+        assert_eq!(barriers.images()[0].range, range(0 .. 2, 0 .. 1));
+        assert_eq!(tracker.buffer_state(&0), Some(READ));
+        assert_eq!(
+            tracker.image_state(&2, Subresource { level: 0, layer: 0 }),
+            Some(SAMPLED)
+        );
+
+        // Reads following reads from a previous submission need no barrier
+        let mut commands = CommandTracker::new();
+        commands.use_buffer(0, FRAGMENT_READ);
+        assert!(tracker.merge(&commands).is_empty());
+        assert_eq!(
+            tracker.buffer_state(&0).map(|state| state.stages),
+            Some(PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER)
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use hal::image;
+
+/// A single mip level and array layer of an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subresource {
+    /// Mip level.
+    pub level: image::Level,
+    /// Array layer.
+    pub layer: image::Layer,
+}
+
+impl Subresource {
+    /// Iterates over the subresources of a range, in order of level, then layer.
+    pub fn iter(range: &image::SubresourceRange) -> impl Iterator<Item = Self> {
+        let layers = range.layers.clone();
+        range.levels.clone().flat_map(move |level| {
+            layers
+                .clone()
+                .map(move |layer| Subresource { level, layer })
+        })
+    }
+}
+
+/// Values attached to the subresources of an image, indexed by level, then layer.
+#[derive(Clone, Debug)]
+pub(crate) struct Subresources<T> {
+    levels: Vec<Vec<Option<T>>>,
+}
+
+impl<T> Default for Subresources<T> {
+    fn default() -> Self {
+        Subresources { levels: Vec::new() }
+    }
+}
+
+impl<T> Subresources<T> {
+    pub fn get(&self, sub: Subresource) -> Option<&T> {
+        self.levels
+            .get(sub.level as usize)
+            .and_then(|layers| layers.get(sub.layer as usize))
+            .and_then(Option::as_ref)
+    }
+
+    /// Returns the slot of a subresource, growing the storage as needed.
+    pub fn get_mut(&mut self, sub: Subresource) -> &mut Option<T> {
+        let (level, layer) = (sub.level as usize, sub.layer as usize);
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, Vec::new);
+        }
+        let layers = &mut self.levels[level];
+        if layers.len() <= layer {
+            layers.resize_with(layer + 1, || None);
+        }
+        &mut layers[layer]
+    }
+
+    /// Iterates over the subresources with a value, in order of level, then layer.
+    pub fn iter(&self) -> impl Iterator<Item = (Subresource, &T)> {
+        self.levels.iter().enumerate().flat_map(|(level, layers)| {
+            layers.iter().enumerate().filter_map(move |(layer, value)| {
+                value.as_ref().map(|value| {
+                    let sub = Subresource {
+                        level: level as image::Level,
+                        layer: layer as image::Layer,
+                    };
+                    (sub, value)
+                })
+            })
+        })
+    }
+}
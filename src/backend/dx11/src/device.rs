@@ -2,6 +2,7 @@ use hal::{
     adapter::MemoryProperties,
     buffer,
     device,
+    external,
     format,
     image,
     memory,
@@ -10,7 +11,7 @@ use hal::{
     pso,
     pso::VertexInputRate,
     query,
-    queue::QueueFamilyId,
+    queue::{QueueFamilyId, SemaphoreValue},
     window,
 };
 
@@ -811,6 +812,7 @@ impl device::Device<Backend> for Device {
         attachments: IA,
         subpasses: IS,
         _dependencies: ID,
+        _correlation_masks: &[pass::ViewMask],
     ) -> Result<RenderPass, device::OutOfMemory>
     where
         IA: IntoIterator,
@@ -1027,6 +1029,7 @@ impl device::Device<Backend> for Device {
         &self,
         size: u64,
         usage: buffer::Usage,
+        _sparse: memory::SparseFlags,
     ) -> Result<Buffer, buffer::CreationError> {
         use buffer::Usage;
 
@@ -1095,6 +1098,15 @@ impl device::Device<Backend> for Device {
         })
     }
 
+    unsafe fn create_external_buffer(
+        &self,
+        _size: u64,
+        usage: buffer::Usage,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<Buffer, buffer::CreationError> {
+        Err(buffer::CreationError::UnsupportedUsage { usage })
+    }
+
     unsafe fn get_buffer_requirements(&self, buffer: &Buffer) -> memory::Requirements {
         buffer.requirements
     }
@@ -1324,6 +1336,7 @@ impl device::Device<Backend> for Device {
         _tiling: image::Tiling,
         usage: image::Usage,
         view_caps: image::ViewCapabilities,
+        _sparse: memory::SparseFlags,
     ) -> Result<Image, image::CreationError> {
         use image::Usage;
         //
@@ -1383,10 +1396,41 @@ impl device::Device<Backend> for Device {
         })
     }
 
+    unsafe fn create_external_image(
+        &self,
+        _kind: image::Kind,
+        _mip_levels: image::Level,
+        _format: format::Format,
+        _tiling: image::Tiling,
+        usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<Image, image::CreationError> {
+        Err(image::CreationError::Usage(usage))
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        _kind: image::Kind,
+        format: format::Format,
+        _usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _modifier: external::DrmFormatModifier,
+    ) -> Result<Image, image::CreationError> {
+        Err(image::CreationError::Format(format))
+    }
+
     unsafe fn get_image_requirements(&self, image: &Image) -> memory::Requirements {
         image.requirements
     }
 
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        _image: &Image,
+    ) -> Vec<image::SparseRequirements> {
+        Vec::new()
+    }
+
     unsafe fn get_image_subresource_footprint(
         &self,
         _image: &Image,
@@ -1836,6 +1880,7 @@ impl device::Device<Backend> for Device {
         &self,
         layout_bindings: I,
         _immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<DescriptorSetLayout, device::OutOfMemory>
     where
         I: IntoIterator,
@@ -2078,6 +2123,67 @@ impl device::Device<Backend> for Device {
         Ok(Semaphore)
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: SemaphoreValue,
+    ) -> Result<Semaphore, device::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        _semaphore: &Semaphore,
+    ) -> Result<SemaphoreValue, device::DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        _semaphore: &Semaphore,
+        _value: SemaphoreValue,
+        _timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &Semaphore,
+        _value: SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        unimplemented!()
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        _handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<Semaphore, device::OutOfMemory> {
+        // `OutOfMemory` can't tell that the call is unsupported, so the misuse is logged.
+        error!("EXTERNAL_SEMAPHORE_FD feature is not supported");
+        debug_assert!(
+            self.features.contains(hal::Features::EXTERNAL_SEMAPHORE_FD),
+            "EXTERNAL_SEMAPHORE_FD feature is not enabled"
+        );
+        Err(device::OutOfMemory::Device)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        _semaphore: &Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        _semaphore: &Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+        _fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     fn create_fence(&self, signalled: bool) -> Result<Fence, device::OutOfMemory> {
         Ok(Arc::new(RawFence {
             mutex: Mutex::new(signalled),
@@ -2154,6 +2260,49 @@ impl device::Device<Backend> for Device {
         }
     }
 
+    unsafe fn allocate_exportable_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<Memory, device::AllocationError> {
+        // `AllocationError` can't tell that the call is unsupported, so the misuse is logged.
+        error!("External memory features are not supported");
+        debug_assert!(
+            self.features.intersects(
+                hal::Features::EXTERNAL_MEMORY_FD | hal::Features::EXTERNAL_MEMORY_DMA_BUF
+            ),
+            "External memory features are not enabled"
+        );
+        Err(device::OutOfMemory::Device.into())
+    }
+
+    unsafe fn export_memory(
+        &self,
+        _memory: &Memory,
+        _handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<Memory, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     unsafe fn create_query_pool(
         &self,
         _query_ty: query::Type,
@@ -945,6 +945,10 @@ impl queue::QueueFamily for QueueFamily {
     fn id(&self) -> queue::QueueFamilyId {
         queue::QueueFamilyId(0)
     }
+    fn timestamp_valid_bits(&self) -> u32 {
+        // TODO: timestamp queries are not implemented
+        0
+    }
 }
 
 #[derive(Clone)]
@@ -970,8 +974,8 @@ impl queue::CommandQueue<Backend> for CommandQueue {
         T: 'a + Borrow<CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
         S: 'a + Borrow<Semaphore>,
-        Iw: IntoIterator<Item = (&'a S, pso::PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
     {
         let _scope = debug_scope!(&self.context, "Submit(fence={:?})", fence);
         for cmd_buf in submission.command_buffers {
@@ -1006,6 +1010,21 @@ impl queue::CommandQueue<Backend> for CommandQueue {
         }
     }
 
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        _binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        _fence: Option<&Fence>,
+    ) where
+        S: 'a + Borrow<Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<Item = (&'b Buffer, &'b [memory::SparseBind<&'b Memory>])>,
+        Io: IntoIterator<Item = (&'b Image, &'b [memory::SparseBind<&'b Memory>])>,
+        Ii: IntoIterator<Item = (&'b Image, &'b [memory::SparseImageBind<&'b Memory>])>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn present<'a, W, Is, S, Iw>(
         &mut self,
         swapchains: Is,
@@ -1796,6 +1815,14 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         self.cache.bind_vertex_buffers(&self.context);
     }
 
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, _first_binding: u32, _buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<Buffer>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, _first_viewport: u32, viewports: T)
     where
         T: IntoIterator,
@@ -1976,6 +2003,19 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        _layout: &PipelineLayout,
+        _set: usize,
+        _writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn bind_compute_pipeline(&mut self, pipeline: &ComputePipeline) {
         self.context
             .CSSetShader(pipeline.cs.as_raw(), ptr::null_mut(), 0);
@@ -2071,6 +2111,19 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        _layout: &PipelineLayout,
+        _set: usize,
+        _writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         self.context.Dispatch(count[0], count[1], count[2]);
     }
@@ -2228,6 +2281,60 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!()
     }
 
+    unsafe fn draw_indirect_count(
+        &mut self,
+        _buffer: &Buffer,
+        _offset: buffer::Offset,
+        _count_buffer: &Buffer,
+        _count_buffer_offset: buffer::Offset,
+        _max_draw_count: DrawCount,
+        _stride: u32,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        _buffer: &Buffer,
+        _offset: buffer::Offset,
+        _count_buffer: &Buffer,
+        _count_buffer_offset: buffer::Offset,
+        _max_draw_count: DrawCount,
+        _stride: u32,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        _pool: &QueryPool,
+        _id: query::Id,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn set_event(&mut self, _: &(), _: pso::PipelineStage) {
         unimplemented!()
     }
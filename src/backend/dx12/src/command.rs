@@ -1885,6 +1885,14 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.set_vertex_buffers();
     }
 
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, _first_binding: u32, _buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<r::Buffer>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
     where
         T: IntoIterator,
@@ -2037,6 +2045,19 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.bind_descriptor_heaps();
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        _layout: &r::PipelineLayout,
+        _set: usize,
+        _writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn bind_compute_pipeline(&mut self, pipeline: &r::ComputePipeline) {
         match self.comp_pipeline.pipeline {
             Some((_, signature)) if signature == pipeline.signature => {
@@ -2074,6 +2095,19 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.bind_descriptor_heaps();
     }
 
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        _layout: &r::PipelineLayout,
+        _set: usize,
+        _writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         self.set_compute_bind_point();
         self.raw.dispatch(count);
@@ -2505,6 +2539,82 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &r::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &r::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        assert_eq!(stride, 16);
+        let buffer = buffer.expect_bound();
+        let count_buffer = count_buffer.expect_bound();
+        self.set_graphics_bind_point();
+        self.raw.ExecuteIndirect(
+            self.shared.signatures.draw.as_mut_ptr(),
+            max_draw_count,
+            buffer.resource.as_mut_ptr(),
+            offset,
+            count_buffer.resource.as_mut_ptr(),
+            count_buffer_offset,
+        );
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &r::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &r::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        assert_eq!(stride, 20);
+        let buffer = buffer.expect_bound();
+        let count_buffer = count_buffer.expect_bound();
+        self.set_graphics_bind_point();
+        self.raw.ExecuteIndirect(
+            self.shared.signatures.draw_indexed.as_mut_ptr(),
+            max_draw_count,
+            buffer.resource.as_mut_ptr(),
+            offset,
+            count_buffer.resource.as_mut_ptr(),
+            count_buffer_offset,
+        );
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &r::Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        _pool: &r::QueryPool,
+        _id: query::Id,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn set_event(&mut self, _: &(), _: pso::PipelineStage) {
         unimplemented!()
     }
@@ -32,6 +32,7 @@ use hal::{
     self,
     buffer,
     device as d,
+    external,
     format,
     format::Aspects,
     image,
@@ -42,7 +43,7 @@ use hal::{
     pso,
     pso::VertexInputRate,
     query,
-    queue::{CommandQueue as _, QueueFamilyId},
+    queue::{CommandQueue as _, QueueFamilyId, SemaphoreValue},
     window as w,
 };
 
@@ -1241,6 +1242,7 @@ impl d::Device<B> for Device {
         attachments: IA,
         subpasses: IS,
         dependencies: ID,
+        _correlation_masks: &[pass::ViewMask],
     ) -> Result<r::RenderPass, d::OutOfMemory>
     where
         IA: IntoIterator,
@@ -2103,6 +2105,7 @@ impl d::Device<B> for Device {
         &self,
         mut size: u64,
         usage: buffer::Usage,
+        _sparse: memory::SparseFlags,
     ) -> Result<r::Buffer, buffer::CreationError> {
         if usage.contains(buffer::Usage::UNIFORM) {
             // Constant buffer view sizes need to be aligned.
@@ -2133,6 +2136,15 @@ impl d::Device<B> for Device {
         }))
     }
 
+    unsafe fn create_external_buffer(
+        &self,
+        _size: u64,
+        usage: buffer::Usage,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<r::Buffer, buffer::CreationError> {
+        Err(buffer::CreationError::UnsupportedUsage { usage })
+    }
+
     unsafe fn get_buffer_requirements(&self, buffer: &r::Buffer) -> Requirements {
         match buffer {
             r::Buffer::Unbound(b) => b.requirements,
@@ -2316,6 +2328,7 @@ impl d::Device<B> for Device {
         tiling: image::Tiling,
         usage: image::Usage,
         view_caps: image::ViewCapabilities,
+        _sparse: memory::SparseFlags,
     ) -> Result<r::Image, image::CreationError> {
         assert!(mip_levels <= kind.num_levels());
 
@@ -2401,6 +2414,30 @@ impl d::Device<B> for Device {
         }))
     }
 
+    unsafe fn create_external_image(
+        &self,
+        _kind: image::Kind,
+        _mip_levels: image::Level,
+        _format: format::Format,
+        _tiling: image::Tiling,
+        usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<r::Image, image::CreationError> {
+        Err(image::CreationError::Usage(usage))
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        _kind: image::Kind,
+        format: format::Format,
+        _usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _modifier: external::DrmFormatModifier,
+    ) -> Result<r::Image, image::CreationError> {
+        Err(image::CreationError::Format(format))
+    }
+
     unsafe fn get_image_requirements(&self, image: &r::Image) -> Requirements {
         match image {
             r::Image::Bound(i) => i.requirements,
@@ -2408,6 +2445,13 @@ impl d::Device<B> for Device {
         }
     }
 
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        _image: &r::Image,
+    ) -> Vec<image::SparseRequirements> {
+        Vec::new()
+    }
+
     unsafe fn get_image_subresource_footprint(
         &self,
         image: &r::Image,
@@ -2801,6 +2845,7 @@ impl d::Device<B> for Device {
         &self,
         bindings: I,
         _immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<r::DescriptorSetLayout, d::OutOfMemory>
     where
         I: IntoIterator,
@@ -3223,6 +3268,67 @@ impl d::Device<B> for Device {
         Ok(r::Semaphore { raw: fence.raw })
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: SemaphoreValue,
+    ) -> Result<r::Semaphore, d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        _semaphore: &r::Semaphore,
+    ) -> Result<SemaphoreValue, d::DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        _semaphore: &r::Semaphore,
+        _value: SemaphoreValue,
+        _timeout_ns: u64,
+    ) -> Result<bool, d::OomOrDeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &r::Semaphore,
+        _value: SemaphoreValue,
+    ) -> Result<(), d::OutOfMemory> {
+        unimplemented!()
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        _handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<r::Semaphore, d::OutOfMemory> {
+        // `OutOfMemory` can't tell that the call is unsupported, so the misuse is logged.
+        error!("EXTERNAL_SEMAPHORE_FD feature is not supported");
+        debug_assert!(
+            self.features.contains(hal::Features::EXTERNAL_SEMAPHORE_FD),
+            "EXTERNAL_SEMAPHORE_FD feature is not enabled"
+        );
+        Err(d::OutOfMemory::Device)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        _semaphore: &r::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        _semaphore: &r::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+        _fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     fn create_fence(&self, signalled: bool) -> Result<r::Fence, d::OutOfMemory> {
         Ok(r::Fence {
             raw: self.create_raw_fence(signalled),
@@ -3323,6 +3429,49 @@ impl d::Device<B> for Device {
         }
     }
 
+    unsafe fn allocate_exportable_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<r::Memory, d::AllocationError> {
+        // `AllocationError` can't tell that the call is unsupported, so the misuse is logged.
+        error!("External memory features are not supported");
+        debug_assert!(
+            self.features.intersects(
+                hal::Features::EXTERNAL_MEMORY_FD | hal::Features::EXTERNAL_MEMORY_DMA_BUF
+            ),
+            "External memory features are not enabled"
+        );
+        Err(d::OutOfMemory::Device.into())
+    }
+
+    unsafe fn export_memory(
+        &self,
+        _memory: &r::Memory,
+        _handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<r::Memory, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     unsafe fn create_query_pool(
         &self,
         query_ty: query::Type,
@@ -3332,6 +3481,9 @@ impl d::Device<B> for Device {
             query::Type::Occlusion => native::QueryHeapType::Occlusion,
             query::Type::PipelineStatistics(_) => native::QueryHeapType::PipelineStatistics,
             query::Type::Timestamp => native::QueryHeapType::Timestamp,
+            query::Type::PrimitivesWritten => {
+                return Err(query::CreationError::Unsupported(query_ty))
+            }
         };
 
         let (query_heap, hr) = self.raw.create_query_heap(heap_ty, count, 0);
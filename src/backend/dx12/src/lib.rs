@@ -156,6 +156,13 @@ impl q::QueueFamily for QueueFamily {
             _ => unreachable!(),
         })
     }
+    fn timestamp_valid_bits(&self) -> u32 {
+        match *self {
+            // Copy queues only support timestamps with `CopyQueueTimestampQueriesSupported`
+            QueueFamily::Normal(q::QueueType::Transfer) => 0,
+            _ => 64,
+        }
+    }
 }
 
 impl QueueFamily {
@@ -456,8 +463,8 @@ impl q::CommandQueue<Backend> for CommandQueue {
         T: 'a + Borrow<command::CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
         S: 'a + Borrow<resource::Semaphore>,
-        Iw: IntoIterator<Item = (&'a S, PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>,
+        Iw: IntoIterator<Item = (&'a S, q::SemaphoreValue, PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, q::SemaphoreValue)>,
     {
         // Reset idle fence and event
         // That's safe here due to exclusive access to the queue
@@ -478,6 +485,36 @@ impl q::CommandQueue<Backend> for CommandQueue {
         }
     }
 
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        _binding: q::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        _fence: Option<&resource::Fence>,
+    ) where
+        S: 'a + Borrow<resource::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, q::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, q::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b resource::Buffer,
+                &'b [memory::SparseBind<&'b resource::Memory>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b resource::Image,
+                &'b [memory::SparseBind<&'b resource::Memory>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b resource::Image,
+                &'b [memory::SparseImageBind<&'b resource::Memory>],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
     unsafe fn present<'a, W, Is, S, Iw>(
         &mut self,
         swapchains: Is,
@@ -1068,6 +1105,26 @@ impl hal::Instance<Backend> for Instance {
             //TODO: find a way to get a tighter bound?
             let sample_count_mask = 0x3F;
 
+            // The timestamp frequency can only be queried from a queue
+            let timestamp_period = unsafe {
+                let (queue, hr) = device.create_command_queue(
+                    native::CmdListType::Direct,
+                    native::Priority::Normal,
+                    native::CommandQueueFlags::empty(),
+                    0,
+                );
+                let mut frequency = 0;
+                if winerror::SUCCEEDED(hr) {
+                    queue.GetTimestampFrequency(&mut frequency);
+                    queue.destroy();
+                }
+                if frequency != 0 {
+                    1_000_000_000.0 / frequency as f32
+                } else {
+                    1.0
+                }
+            };
+
             let physical_device = PhysicalDevice {
                 library: Arc::clone(&self.library),
                 adapter,
@@ -1082,6 +1139,7 @@ impl hal::Instance<Backend> for Instance {
                     if depth_bounds_test_supported { Features::DEPTH_BOUNDS } else { Features::empty() } |
                     //logic_op: false, // Optional on feature level 11_0
                     Features::MULTI_DRAW_INDIRECT |
+                    Features::DRAW_INDIRECT_COUNT |
                     Features::FORMAT_BC |
                     Features::INSTANCE_RATE |
                     Features::SAMPLER_MIP_LOD_BIAS |
@@ -1133,6 +1191,7 @@ impl hal::Instance<Backend> for Instance {
                     optimal_buffer_copy_offset_alignment: d3d12::D3D12_TEXTURE_DATA_PLACEMENT_ALIGNMENT as _,
                     optimal_buffer_copy_pitch_alignment: d3d12::D3D12_TEXTURE_DATA_PITCH_ALIGNMENT as _,
                     min_vertex_input_binding_stride_alignment: 1,
+                    timestamp_period,
                     .. Limits::default() //TODO
                 },
                 format_properties: Arc::new(FormatProperties::new(device)),
@@ -0,0 +1,391 @@
+use crate::{native, Backend};
+use hal::{buffer, command, image, memory, pso, query};
+
+use std::borrow::Borrow;
+use std::ops::Range;
+
+/// Mock command buffer, which accepts all the commands and records nothing.
+#[derive(Debug)]
+pub struct CommandBuffer {
+    pub(crate) name: String,
+}
+
+impl CommandBuffer {
+    pub(crate) fn new() -> Self {
+        CommandBuffer {
+            name: String::new(),
+        }
+    }
+
+    /// Returns the debug name of the command buffer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl command::CommandBuffer<Backend> for CommandBuffer {
+    unsafe fn begin(
+        &mut self,
+        _: command::CommandBufferFlags,
+        _: command::CommandBufferInheritanceInfo<Backend>,
+    ) {
+    }
+
+    unsafe fn finish(&mut self) {}
+
+    unsafe fn reset(&mut self, _: bool) {}
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        _: Range<pso::PipelineStage>,
+        _: memory::Dependencies,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Backend>>,
+    {
+    }
+
+    unsafe fn fill_buffer(&mut self, _: &native::Buffer, _: buffer::SubRange, _: u32) {}
+
+    unsafe fn update_buffer(&mut self, _: &native::Buffer, _: buffer::Offset, _: &[u8]) {}
+
+    unsafe fn clear_image<T>(
+        &mut self,
+        _: &native::Image,
+        _: image::Layout,
+        _: command::ClearValue,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, _: T, _: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+    }
+
+    unsafe fn resolve_image<T>(
+        &mut self,
+        _: &native::Image,
+        _: image::Layout,
+        _: &native::Image,
+        _: image::Layout,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageResolve>,
+    {
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        _: &native::Image,
+        _: image::Layout,
+        _: &native::Image,
+        _: image::Layout,
+        _: image::Filter,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageBlit>,
+    {
+    }
+
+    unsafe fn bind_index_buffer(&mut self, _: buffer::IndexBufferView<Backend>) {}
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, _: u32, _: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer>,
+    {
+    }
+
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, _: u32, _: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer>,
+    {
+    }
+
+    unsafe fn set_viewports<T>(&mut self, _: u32, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+    }
+
+    unsafe fn set_scissors<T>(&mut self, _: u32, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+    }
+
+    unsafe fn set_stencil_reference(&mut self, _: pso::Face, _: pso::StencilValue) {}
+
+    unsafe fn set_stencil_read_mask(&mut self, _: pso::Face, _: pso::StencilValue) {}
+
+    unsafe fn set_stencil_write_mask(&mut self, _: pso::Face, _: pso::StencilValue) {}
+
+    unsafe fn set_blend_constants(&mut self, _: pso::ColorValue) {}
+
+    unsafe fn set_depth_bounds(&mut self, _: Range<f32>) {}
+
+    unsafe fn set_line_width(&mut self, _: f32) {}
+
+    unsafe fn set_depth_bias(&mut self, _: pso::DepthBias) {}
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        _: &native::RenderPass,
+        _: &native::Framebuffer,
+        _: pso::Rect,
+        _: T,
+        _: command::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ClearValue>,
+    {
+    }
+
+    unsafe fn next_subpass(&mut self, _: command::SubpassContents) {}
+
+    unsafe fn end_render_pass(&mut self) {}
+
+    unsafe fn bind_graphics_pipeline(&mut self, _: &native::GraphicsPipeline) {}
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        _: &native::PipelineLayout,
+        _: usize,
+        _: I,
+        _: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+    }
+
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        _: &native::PipelineLayout,
+        _: usize,
+        _: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, _: &native::ComputePipeline) {}
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        _: &native::PipelineLayout,
+        _: usize,
+        _: I,
+        _: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        _: &native::PipelineLayout,
+        _: usize,
+        _: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+    }
+
+    unsafe fn dispatch(&mut self, _: hal::WorkGroupCount) {}
+
+    unsafe fn dispatch_indirect(&mut self, _: &native::Buffer, _: buffer::Offset) {}
+
+    unsafe fn copy_buffer<T>(&mut self, _: &native::Buffer, _: &native::Buffer, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferCopy>,
+    {
+    }
+
+    unsafe fn copy_image<T>(
+        &mut self,
+        _: &native::Image,
+        _: image::Layout,
+        _: &native::Image,
+        _: image::Layout,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageCopy>,
+    {
+    }
+
+    unsafe fn copy_buffer_to_image<T>(
+        &mut self,
+        _: &native::Buffer,
+        _: &native::Image,
+        _: image::Layout,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferImageCopy>,
+    {
+    }
+
+    unsafe fn copy_image_to_buffer<T>(
+        &mut self,
+        _: &native::Image,
+        _: image::Layout,
+        _: &native::Buffer,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferImageCopy>,
+    {
+    }
+
+    unsafe fn draw(&mut self, _: Range<hal::VertexCount>, _: Range<hal::InstanceCount>) {}
+
+    unsafe fn draw_indexed(
+        &mut self,
+        _: Range<hal::IndexCount>,
+        _: hal::VertexOffset,
+        _: Range<hal::InstanceCount>,
+    ) {
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: hal::DrawCount,
+        _: u32,
+    ) {
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: hal::DrawCount,
+        _: u32,
+    ) {
+    }
+
+    unsafe fn draw_indirect_count(
+        &mut self,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: hal::DrawCount,
+        _: u32,
+    ) {
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: hal::DrawCount,
+        _: u32,
+    ) {
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: bool,
+    ) {
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        _: &native::QueryPool,
+        _: query::Id,
+        _: bool,
+    ) {
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {}
+
+    unsafe fn begin_transform_feedback(&mut self) {}
+
+    unsafe fn end_transform_feedback(&mut self) {}
+
+    unsafe fn set_event(&mut self, _: &native::Event, _: pso::PipelineStage) {}
+
+    unsafe fn reset_event(&mut self, _: &native::Event, _: pso::PipelineStage) {}
+
+    unsafe fn wait_events<'a, I, J>(&mut self, _: I, _: Range<pso::PipelineStage>, _: J)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::Event>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Backend>>,
+    {
+    }
+
+    unsafe fn begin_query(&mut self, _: query::Query<Backend>, _: query::ControlFlags) {}
+
+    unsafe fn end_query(&mut self, _: query::Query<Backend>) {}
+
+    unsafe fn reset_query_pool(&mut self, _: &native::QueryPool, _: Range<query::Id>) {}
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        _: &native::QueryPool,
+        _: Range<query::Id>,
+        _: &native::Buffer,
+        _: buffer::Offset,
+        _: buffer::Offset,
+        _: query::ResultFlags,
+    ) {
+    }
+
+    unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, _: query::Query<Backend>) {}
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        _: &native::PipelineLayout,
+        _: pso::ShaderStageFlags,
+        _: u32,
+        _: &[u32],
+    ) {
+    }
+
+    unsafe fn push_compute_constants(&mut self, _: &native::PipelineLayout, _: u32, _: &[u32]) {}
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, _: I)
+    where
+        T: 'a + Borrow<CommandBuffer>,
+        I: IntoIterator<Item = &'a T>,
+    {
+    }
+
+    unsafe fn insert_debug_marker(&mut self, _: &str, _: u32) {}
+    unsafe fn begin_debug_marker(&mut self, _: &str, _: u32) {}
+    unsafe fn end_debug_marker(&mut self) {}
+}
@@ -0,0 +1,811 @@
+use crate::{
+    aligned,
+    bound_range,
+    memory_type_mask,
+    native,
+    Backend,
+    CommandBuffer,
+    CommandPool,
+    DescriptorPool,
+    MockConfig,
+    Surface,
+    Swapchain,
+};
+use hal::{buffer, device, external, format, image, memory, pass, pool, pso, query, queue, window};
+
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+const IMAGE_ALIGNMENT: u64 = 256;
+
+/// Mock device.
+///
+/// Keeps track of the memory allocated from each heap, so that allocations
+/// fail like they would on a device with the configured memory properties.
+#[derive(Debug)]
+pub struct Device {
+    config: MockConfig,
+    heap_usage: Mutex<Vec<u64>>,
+    allocation_count: AtomicUsize,
+}
+
+impl Device {
+    pub(crate) fn new(config: MockConfig) -> Self {
+        let heap_usage = vec![0; config.memory_properties.memory_heaps.len()];
+        Device {
+            config,
+            heap_usage: Mutex::new(heap_usage),
+            allocation_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of bytes currently allocated from each memory heap.
+    pub fn heap_usage(&self) -> Vec<u64> {
+        self.heap_usage.lock().unwrap().clone()
+    }
+
+    /// Returns the number of live memory allocations.
+    pub fn allocation_count(&self) -> usize {
+        self.allocation_count.load(Ordering::Acquire)
+    }
+
+    fn image_layout(
+        &self,
+        image: &native::Image,
+    ) -> (u64, impl Fn(image::Level) -> (u64, u64, u64) + '_) {
+        let desc = image.format.surface_desc();
+        let (block_width, block_height) = (desc.dim.0 as u32, desc.dim.1 as u32);
+        let bytes_per_block = (desc.bits / 8) as u64;
+        let kind = image.kind;
+        // Returns the row pitch, depth pitch and size of a mip level.
+        let level_layout = move |level| {
+            let extent = kind.level_extent(level);
+            let row_pitch =
+                ((extent.width + block_width - 1) / block_width) as u64 * bytes_per_block;
+            let depth_pitch =
+                ((extent.height + block_height - 1) / block_height) as u64 * row_pitch;
+            (row_pitch, depth_pitch, depth_pitch * extent.depth as u64)
+        };
+        let layer_size = (0 .. image.mip_levels)
+            .map(|level| level_layout(level).2)
+            .sum::<u64>()
+            * kind.num_samples() as u64;
+        (layer_size, level_layout)
+    }
+}
+
+impl device::Device<Backend> for Device {
+    unsafe fn create_command_pool(
+        &self,
+        _: queue::QueueFamilyId,
+        _: pool::CommandPoolCreateFlags,
+    ) -> Result<CommandPool, device::OutOfMemory> {
+        Ok(CommandPool)
+    }
+
+    unsafe fn destroy_command_pool(&self, _: CommandPool) {}
+
+    unsafe fn allocate_memory(
+        &self,
+        memory_type: hal::MemoryTypeId,
+        size: u64,
+    ) -> Result<native::Memory, device::AllocationError> {
+        let ty = &self.config.memory_properties.memory_types[memory_type.0];
+        {
+            // The allocation count is checked and updated under the heap lock, so that
+            // concurrent allocations can't exceed the limit
+            let mut heap_usage = self.heap_usage.lock().unwrap();
+            let max_count = self.config.limits.max_memory_allocation_count;
+            if max_count != 0 && self.allocation_count() >= max_count {
+                return Err(device::AllocationError::TooManyObjects);
+            }
+            let heap_size = self.config.memory_properties.memory_heaps[ty.heap_index];
+            match heap_usage[ty.heap_index].checked_add(size) {
+                Some(usage) if usage <= heap_size => heap_usage[ty.heap_index] = usage,
+                _ => return Err(device::OutOfMemory::Device.into()),
+            }
+            self.allocation_count.fetch_add(1, Ordering::AcqRel);
+        }
+
+        Ok(native::Memory::new(
+            memory_type,
+            ty.heap_index,
+            ty.properties,
+            size,
+        ))
+    }
+
+    unsafe fn free_memory(&self, memory: native::Memory) {
+        let mut heap_usage = self.heap_usage.lock().unwrap();
+        heap_usage[memory.heap_index] -= memory.size;
+        self.allocation_count.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    unsafe fn allocate_exportable_memory(
+        &self,
+        memory_type: hal::MemoryTypeId,
+        size: u64,
+        _: external::MemoryHandleTypes,
+    ) -> Result<native::Memory, device::AllocationError> {
+        self.allocate_memory(memory_type, size)
+    }
+
+    unsafe fn export_memory(
+        &self,
+        _: &native::Memory,
+        _: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        // The mock device has no file descriptors to hand out
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        _: external::MemoryHandleType,
+        _: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        _: hal::MemoryTypeId,
+        _: u64,
+        _: external::MemoryHandleType,
+        _: external::Fd,
+    ) -> Result<native::Memory, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        _: IS,
+        _: ID,
+        _: &[pass::ViewMask],
+    ) -> Result<native::RenderPass, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        Ok(native::RenderPass {
+            attachments: attachments
+                .into_iter()
+                .map(|attachment| attachment.borrow().clone())
+                .collect(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        _: IS,
+        _: IR,
+    ) -> Result<native::PipelineLayout, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<native::DescriptorSetLayout>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        Ok(native::PipelineLayout)
+    }
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        data: Option<&[u8]>,
+    ) -> Result<native::PipelineCache, device::OutOfMemory> {
+        Ok(native::PipelineCache {
+            data: data.map_or(Vec::new(), |data| data.to_vec()),
+        })
+    }
+
+    unsafe fn get_pipeline_cache_data(
+        &self,
+        cache: &native::PipelineCache,
+    ) -> Result<Vec<u8>, device::OutOfMemory> {
+        Ok(cache.data.clone())
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, _: native::PipelineCache) {}
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        _: &pso::GraphicsPipelineDesc<'a, Backend>,
+        _: Option<&native::PipelineCache>,
+    ) -> Result<native::GraphicsPipeline, pso::CreationError> {
+        Ok(native::GraphicsPipeline)
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        _: &pso::ComputePipelineDesc<'a, Backend>,
+        _: Option<&native::PipelineCache>,
+    ) -> Result<native::ComputePipeline, pso::CreationError> {
+        Ok(native::ComputePipeline)
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        _: &native::PipelineCache,
+        _: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::PipelineCache>,
+    {
+        Ok(())
+    }
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        _: &native::RenderPass,
+        _: I,
+        extent: image::Extent,
+    ) -> Result<native::Framebuffer, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::ImageView>,
+    {
+        Ok(native::Framebuffer {
+            extent,
+            name: String::new(),
+        })
+    }
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv: &[u32],
+    ) -> Result<native::ShaderModule, device::ShaderError> {
+        Ok(native::ShaderModule {
+            spirv: spirv.to_vec(),
+        })
+    }
+
+    unsafe fn create_sampler(
+        &self,
+        _: &image::SamplerDesc,
+    ) -> Result<native::Sampler, device::AllocationError> {
+        Ok(native::Sampler)
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        _: memory::SparseFlags,
+    ) -> Result<native::Buffer, buffer::CreationError> {
+        let limits = &self.config.limits;
+        let mut alignment = 4;
+        if usage.contains(buffer::Usage::UNIFORM) {
+            alignment = alignment.max(limits.min_uniform_buffer_offset_alignment);
+        }
+        if usage.contains(buffer::Usage::STORAGE) {
+            alignment = alignment.max(limits.min_storage_buffer_offset_alignment);
+        }
+        if usage.intersects(buffer::Usage::UNIFORM_TEXEL | buffer::Usage::STORAGE_TEXEL) {
+            alignment = alignment.max(limits.min_texel_buffer_offset_alignment);
+        }
+
+        Ok(native::Buffer {
+            size,
+            usage,
+            requirements: memory::Requirements {
+                size: aligned(size, alignment),
+                alignment,
+                type_mask: memory_type_mask(&self.config.memory_properties),
+            },
+            bound: None,
+            name: String::new(),
+        })
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        _: external::MemoryHandleTypes,
+    ) -> Result<native::Buffer, buffer::CreationError> {
+        self.create_buffer(size, usage, memory::SparseFlags::empty())
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &native::Buffer) -> memory::Requirements {
+        buffer.requirements
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &native::Memory,
+        offset: u64,
+        buffer: &mut native::Buffer,
+    ) -> Result<(), device::BindError> {
+        buffer.bound = Some(bound_range(memory, offset, &buffer.requirements)?);
+        Ok(())
+    }
+
+    unsafe fn create_buffer_view(
+        &self,
+        _: &native::Buffer,
+        _: Option<format::Format>,
+        _: buffer::SubRange,
+    ) -> Result<native::BufferView, buffer::ViewCreationError> {
+        Ok(native::BufferView)
+    }
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        _: image::Tiling,
+        usage: image::Usage,
+        _: image::ViewCapabilities,
+        _: memory::SparseFlags,
+    ) -> Result<native::Image, image::CreationError> {
+        let mut image = native::Image {
+            kind,
+            mip_levels,
+            format,
+            usage,
+            requirements: memory::Requirements {
+                size: 0,
+                alignment: IMAGE_ALIGNMENT.max(self.config.limits.buffer_image_granularity),
+                type_mask: memory_type_mask(&self.config.memory_properties),
+            },
+            bound: None,
+            name: String::new(),
+        };
+        let (layer_size, _) = self.image_layout(&image);
+        image.requirements.size = aligned(
+            layer_size * kind.num_layers() as u64,
+            image.requirements.alignment,
+        );
+        Ok(image)
+    }
+
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        _: external::MemoryHandleTypes,
+    ) -> Result<native::Image, image::CreationError> {
+        self.create_image(
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            memory::SparseFlags::empty(),
+        )
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        _: external::DrmFormatModifier,
+    ) -> Result<native::Image, image::CreationError> {
+        self.create_image(
+            kind,
+            1,
+            format,
+            image::Tiling::Linear,
+            usage,
+            view_caps,
+            memory::SparseFlags::empty(),
+        )
+    }
+
+    unsafe fn get_image_requirements(&self, image: &native::Image) -> memory::Requirements {
+        image.requirements
+    }
+
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        _: &native::Image,
+    ) -> Vec<image::SparseRequirements> {
+        // Sparse residency isn't simulated
+        Vec::new()
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &native::Image,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        let (layer_size, level_layout) = self.image_layout(image);
+        let level_offset = (0 .. subresource.level)
+            .map(|level| level_layout(level).2)
+            .sum::<u64>();
+        let (row_pitch, depth_pitch, level_size) = level_layout(subresource.level);
+        let start = subresource.layer as u64 * layer_size + level_offset;
+
+        image::SubresourceFootprint {
+            slice: start .. start + level_size,
+            row_pitch,
+            array_pitch: layer_size,
+            depth_pitch,
+        }
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &native::Memory,
+        offset: u64,
+        image: &mut native::Image,
+    ) -> Result<(), device::BindError> {
+        image.bound = Some(bound_range(memory, offset, &image.requirements)?);
+        Ok(())
+    }
+
+    unsafe fn create_image_view(
+        &self,
+        _: &native::Image,
+        _: image::ViewKind,
+        format: format::Format,
+        _: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<native::ImageView, image::ViewCreationError> {
+        Ok(native::ImageView { format, range })
+    }
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        max_sets: usize,
+        _: I,
+        _: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        Ok(DescriptorPool {
+            max_sets,
+            allocated: 0,
+        })
+    }
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        _: J,
+        _: pso::DescriptorSetLayoutCreateFlags,
+    ) -> Result<native::DescriptorSetLayout, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<native::Sampler>,
+    {
+        Ok(native::DescriptorSetLayout {
+            bindings: bindings
+                .into_iter()
+                .map(|binding| binding.borrow().clone())
+                .collect(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, _: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Backend, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, _: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Backend>>,
+    {
+    }
+
+    fn create_semaphore(&self) -> Result<native::Semaphore, device::OutOfMemory> {
+        Ok(native::Semaphore::new(0))
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: queue::SemaphoreValue,
+    ) -> Result<native::Semaphore, device::OutOfMemory> {
+        Ok(native::Semaphore::new(initial_value))
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &native::Semaphore,
+    ) -> Result<queue::SemaphoreValue, device::DeviceLost> {
+        Ok(semaphore.value())
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &native::Semaphore,
+        value: queue::SemaphoreValue,
+        _: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        // Submitted work is already complete, so there is nothing to wait for.
+        Ok(semaphore.value() >= value)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &native::Semaphore,
+        value: queue::SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        semaphore.signal(value);
+        Ok(())
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        _: external::SemaphoreHandleTypes,
+    ) -> Result<native::Semaphore, device::OutOfMemory> {
+        self.create_semaphore()
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        _: &native::Semaphore,
+        _: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        _: &native::Semaphore,
+        _: external::SemaphoreHandleType,
+        _: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    fn create_fence(&self, signaled: bool) -> Result<native::Fence, device::OutOfMemory> {
+        Ok(native::Fence::new(signaled))
+    }
+
+    unsafe fn reset_fence(&self, fence: &native::Fence) -> Result<(), device::OutOfMemory> {
+        fence.set(false);
+        Ok(())
+    }
+
+    unsafe fn wait_for_fence(
+        &self,
+        fence: &native::Fence,
+        _: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        // Submitted work is already complete, so there is nothing to wait for.
+        Ok(fence.is_signaled())
+    }
+
+    unsafe fn get_fence_status(&self, fence: &native::Fence) -> Result<bool, device::DeviceLost> {
+        Ok(fence.is_signaled())
+    }
+
+    fn create_event(&self) -> Result<native::Event, device::OutOfMemory> {
+        Ok(native::Event {
+            set: AtomicBool::new(false),
+        })
+    }
+
+    unsafe fn get_event_status(
+        &self,
+        event: &native::Event,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        Ok(event.set.load(Ordering::Acquire))
+    }
+
+    unsafe fn set_event(&self, event: &native::Event) -> Result<(), device::OutOfMemory> {
+        event.set.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    unsafe fn reset_event(&self, event: &native::Event) -> Result<(), device::OutOfMemory> {
+        event.set.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        _: query::Type,
+        count: query::Id,
+    ) -> Result<native::QueryPool, query::CreationError> {
+        Ok(native::QueryPool { count })
+    }
+
+    unsafe fn destroy_query_pool(&self, _: native::QueryPool) {}
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &native::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        assert!(queries.end <= pool.count);
+        // No work is ever executed, so all the results are zero.
+        let result_size = if flags.contains(query::ResultFlags::BITS_64) {
+            8
+        } else {
+            4
+        };
+        let value_count = if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+            2
+        } else {
+            1
+        };
+        for (i, _) in queries.enumerate() {
+            let start = i * stride as usize;
+            let end = start + result_size * value_count;
+            for byte in &mut data[start .. end] {
+                *byte = 0;
+            }
+            if value_count == 2 {
+                // Report the result as available.
+                data[end - result_size] = 1;
+            }
+        }
+        Ok(true)
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &native::Memory,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        let ptr = memory.host_ptr().ok_or(device::MapError::MappingFailed)?;
+        let end = segment.offset + segment.size.unwrap_or(0);
+        if segment.offset > memory.size || end > memory.size {
+            return Err(device::MapError::OutOfBounds);
+        }
+        Ok(ptr.offset(segment.offset as isize))
+    }
+
+    unsafe fn unmap_memory(&self, _: &native::Memory) {}
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory, memory::Segment)>,
+    {
+        Ok(())
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory, memory::Segment)>,
+    {
+        Ok(())
+    }
+
+    unsafe fn destroy_shader_module(&self, _: native::ShaderModule) {}
+
+    unsafe fn destroy_render_pass(&self, _: native::RenderPass) {}
+
+    unsafe fn destroy_pipeline_layout(&self, _: native::PipelineLayout) {}
+
+    unsafe fn destroy_graphics_pipeline(&self, _: native::GraphicsPipeline) {}
+
+    unsafe fn destroy_compute_pipeline(&self, _: native::ComputePipeline) {}
+
+    unsafe fn destroy_framebuffer(&self, _: native::Framebuffer) {}
+
+    unsafe fn destroy_buffer(&self, _: native::Buffer) {}
+
+    unsafe fn destroy_buffer_view(&self, _: native::BufferView) {}
+
+    unsafe fn destroy_image(&self, _: native::Image) {}
+
+    unsafe fn destroy_image_view(&self, _: native::ImageView) {}
+
+    unsafe fn destroy_sampler(&self, _: native::Sampler) {}
+
+    unsafe fn destroy_descriptor_pool(&self, _: DescriptorPool) {}
+
+    unsafe fn destroy_descriptor_set_layout(&self, _: native::DescriptorSetLayout) {}
+
+    unsafe fn destroy_fence(&self, _: native::Fence) {}
+
+    unsafe fn destroy_semaphore(&self, _: native::Semaphore) {}
+
+    unsafe fn destroy_event(&self, _: native::Event) {}
+
+    unsafe fn create_swapchain(
+        &self,
+        _: &mut Surface,
+        config: window::SwapchainConfig,
+        _: Option<Swapchain>,
+    ) -> Result<(Swapchain, Vec<native::Image>), window::CreationError> {
+        let kind = image::Kind::D2(
+            config.extent.width,
+            config.extent.height,
+            config.image_layers,
+            1,
+        );
+        let images = (0 .. config.image_count)
+            .map(|_| {
+                let mut image = self
+                    .create_image(
+                        kind,
+                        1,
+                        config.format,
+                        image::Tiling::Optimal,
+                        config.image_usage,
+                        image::ViewCapabilities::empty(),
+                        memory::SparseFlags::empty(),
+                    )
+                    .unwrap();
+                image.bound = Some(0 .. image.requirements.size);
+                image
+            })
+            .collect();
+        Ok((Swapchain::new(config), images))
+    }
+
+    unsafe fn destroy_swapchain(&self, _: Swapchain) {}
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        Ok(())
+    }
+
+    unsafe fn set_image_name(&self, image: &mut native::Image, name: &str) {
+        image.name = name.to_string();
+    }
+
+    unsafe fn set_buffer_name(&self, buffer: &mut native::Buffer, name: &str) {
+        buffer.name = name.to_string();
+    }
+
+    unsafe fn set_command_buffer_name(&self, command_buffer: &mut CommandBuffer, name: &str) {
+        command_buffer.name = name.to_string();
+    }
+
+    unsafe fn set_semaphore_name(&self, semaphore: &mut native::Semaphore, name: &str) {
+        semaphore.name = name.to_string();
+    }
+
+    unsafe fn set_fence_name(&self, fence: &mut native::Fence, name: &str) {
+        fence.name = name.to_string();
+    }
+
+    unsafe fn set_framebuffer_name(&self, framebuffer: &mut native::Framebuffer, name: &str) {
+        framebuffer.name = name.to_string();
+    }
+
+    unsafe fn set_render_pass_name(&self, render_pass: &mut native::RenderPass, name: &str) {
+        render_pass.name = name.to_string();
+    }
+
+    unsafe fn set_descriptor_set_name(
+        &self,
+        descriptor_set: &mut native::DescriptorSet,
+        name: &str,
+    ) {
+        descriptor_set.name = name.to_string();
+    }
+
+    unsafe fn set_descriptor_set_layout_name(
+        &self,
+        descriptor_set_layout: &mut native::DescriptorSetLayout,
+        name: &str,
+    ) {
+        descriptor_set_layout.name = name.to_string();
+    }
+}
@@ -1,29 +1,36 @@
-//! Dummy backend implementation to test the code for compile errors
-//! outside of the graphics development environment.
+//! Mock backend implementation.
+//!
+//! It exposes a single fake adapter with configurable features, limits and memory
+//! properties. Resources are tracked on the host, command buffers record nothing and
+//! submitted work completes immediately. This allows device setup, resource management
+//! and frame loops to run outside of the graphics development environment, e.g. in tests.
 
 extern crate gfx_hal as hal;
 
 use hal::{
     adapter,
-    buffer,
-    command,
-    device,
+    device::{BindError, CreationError as DeviceCreationError, OutOfMemory},
     format,
     image,
     memory,
-    pass,
     pool,
     pso,
-    query,
     queue,
-    window,
+    window::{Extent2D, PresentError, Suboptimal, SwapImageIndex},
 };
 use std::borrow::Borrow;
 use std::ops::Range;
 
-const DO_NOT_USE_MESSAGE: &str = "You need to enable a native API feature (vulkan/metal/dx11/dx12/gl/wgl) in order to use gfx-rs";
+mod command;
+mod device;
+pub mod native;
+mod window;
 
-/// Dummy backend.
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::window::{Surface, Swapchain};
+
+/// Mock backend.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Backend {}
 impl hal::Backend for Backend {
@@ -38,985 +45,685 @@ impl hal::Backend for Backend {
     type CommandQueue = CommandQueue;
     type CommandBuffer = CommandBuffer;
 
-    type Memory = ();
+    type Memory = native::Memory;
     type CommandPool = CommandPool;
 
-    type ShaderModule = ();
-    type RenderPass = ();
-    type Framebuffer = ();
-
-    type Buffer = ();
-    type BufferView = ();
-    type Image = ();
-    type ImageView = ();
-    type Sampler = ();
-
-    type ComputePipeline = ();
-    type GraphicsPipeline = ();
-    type PipelineCache = ();
-    type PipelineLayout = ();
-    type DescriptorSetLayout = ();
+    type ShaderModule = native::ShaderModule;
+    type RenderPass = native::RenderPass;
+    type Framebuffer = native::Framebuffer;
+
+    type Buffer = native::Buffer;
+    type BufferView = native::BufferView;
+    type Image = native::Image;
+    type ImageView = native::ImageView;
+    type Sampler = native::Sampler;
+
+    type ComputePipeline = native::ComputePipeline;
+    type GraphicsPipeline = native::GraphicsPipeline;
+    type PipelineCache = native::PipelineCache;
+    type PipelineLayout = native::PipelineLayout;
+    type DescriptorSetLayout = native::DescriptorSetLayout;
     type DescriptorPool = DescriptorPool;
-    type DescriptorSet = ();
+    type DescriptorSet = native::DescriptorSet;
+
+    type Fence = native::Fence;
+    type Semaphore = native::Semaphore;
+    type Event = native::Event;
+    type QueryPool = native::QueryPool;
+}
 
-    type Fence = ();
-    type Semaphore = ();
-    type Event = ();
-    type QueryPool = ();
+/// Description of the fake adapter exposed by the mock backend.
+#[derive(Clone, Debug)]
+pub struct MockConfig {
+    /// Information reported for the adapter.
+    pub info: adapter::AdapterInfo,
+    /// Features supported by the adapter.
+    pub features: hal::Features,
+    /// Hints reported by the adapter.
+    pub hints: hal::Hints,
+    /// Limits of the adapter.
+    ///
+    /// `max_memory_allocation_count` is enforced by `allocate_memory` if non-zero.
+    pub limits: hal::Limits,
+    /// Memory types and heaps of the adapter.
+    ///
+    /// Heap sizes are enforced by `allocate_memory`.
+    pub memory_properties: adapter::MemoryProperties,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        const HEAP_SIZE: u64 = 1 << 30;
+
+        MockConfig {
+            info: adapter::AdapterInfo {
+                name: "Mock Adapter".to_owned(),
+                vendor: 0,
+                device: 0,
+                device_type: adapter::DeviceType::VirtualGpu,
+            },
+            features: hal::Features::all(),
+            hints: hal::Hints::all(),
+            limits: hal::Limits {
+                max_image_1d_size: 16384,
+                max_image_2d_size: 16384,
+                max_image_3d_size: 2048,
+                max_image_cube_size: 16384,
+                max_image_array_layers: 2048,
+                max_texel_elements: 1 << 27,
+                max_uniform_buffer_range: 1 << 16,
+                max_storage_buffer_range: 1 << 27,
+                max_push_constants_size: 256,
+                max_memory_allocation_count: 4096,
+                max_sampler_allocation_count: 4000,
+                max_bound_descriptor_sets: 8,
+                max_framebuffer_layers: 2048,
+                max_per_stage_descriptor_samplers: 16,
+                max_per_stage_descriptor_uniform_buffers: 12,
+                max_per_stage_descriptor_storage_buffers: 8,
+                max_per_stage_descriptor_sampled_images: 128,
+                max_per_stage_descriptor_storage_images: 8,
+                max_per_stage_descriptor_input_attachments: 8,
+                max_per_stage_resources: 256,
+                max_descriptor_set_samplers: 96,
+                max_descriptor_set_uniform_buffers: 72,
+                max_descriptor_set_uniform_buffers_dynamic: 8,
+                max_descriptor_set_storage_buffers: 48,
+                max_descriptor_set_storage_buffers_dynamic: 4,
+                max_descriptor_set_sampled_images: 768,
+                max_descriptor_set_storage_images: 48,
+                max_descriptor_set_input_attachments: 8,
+                max_vertex_input_attributes: 16,
+                max_vertex_input_bindings: 16,
+                max_vertex_input_attribute_offset: 2047,
+                max_vertex_input_binding_stride: 2048,
+                max_vertex_output_components: 64,
+                max_patch_size: 32,
+                max_geometry_shader_invocations: 32,
+                max_geometry_input_components: 64,
+                max_geometry_output_components: 64,
+                max_geometry_output_vertices: 256,
+                max_geometry_total_output_components: 1024,
+                max_fragment_input_components: 64,
+                max_fragment_output_attachments: 8,
+                max_fragment_dual_source_attachments: 1,
+                max_fragment_combined_output_resources: 8,
+                max_compute_shared_memory_size: 32768,
+                max_compute_work_group_count: [65535; 3],
+                max_compute_work_group_invocations: 1024,
+                max_compute_work_group_size: [1024, 1024, 64],
+                max_draw_indexed_index_value: !0,
+                max_draw_indirect_count: !0,
+                max_sampler_lod_bias: 16.0,
+                max_sampler_anisotropy: 16.0,
+                max_viewports: 16,
+                max_viewport_dimensions: [16384; 2],
+                max_framebuffer_extent: image::Extent {
+                    width: 16384,
+                    height: 16384,
+                    depth: 2048,
+                },
+                min_memory_map_alignment: 64,
+                buffer_image_granularity: 1,
+                min_texel_buffer_offset_alignment: 16,
+                min_uniform_buffer_offset_alignment: 256,
+                min_storage_buffer_offset_alignment: 16,
+                framebuffer_color_sample_counts: 0b101,
+                framebuffer_depth_sample_counts: 0b101,
+                framebuffer_stencil_sample_counts: 0b101,
+                max_color_attachments: 8,
+                standard_sample_locations: true,
+                optimal_buffer_copy_offset_alignment: 1,
+                optimal_buffer_copy_pitch_alignment: 1,
+                non_coherent_atom_size: 64,
+                min_vertex_input_binding_stride_alignment: 1,
+                timestamp_period: 1.0,
+                max_update_after_bind_descriptors_in_all_pools: 1 << 20,
+                max_per_stage_update_after_bind_resources: 1 << 20,
+                max_push_descriptors: 32,
+                max_multiview_view_count: 6,
+                max_transform_feedback_buffers: 4,
+            },
+            memory_properties: adapter::MemoryProperties {
+                memory_types: vec![
+                    adapter::MemoryType {
+                        properties: memory::Properties::DEVICE_LOCAL,
+                        heap_index: 0,
+                    },
+                    adapter::MemoryType {
+                        properties: memory::Properties::CPU_VISIBLE | memory::Properties::COHERENT,
+                        heap_index: 1,
+                    },
+                    adapter::MemoryType {
+                        properties: memory::Properties::CPU_VISIBLE
+                            | memory::Properties::CPU_CACHED,
+                        heap_index: 1,
+                    },
+                ],
+                memory_heaps: vec![HEAP_SIZE, HEAP_SIZE],
+            },
+        }
+    }
 }
 
-/// Dummy physical device.
+/// Mock physical device.
 #[derive(Debug)]
-pub struct PhysicalDevice;
+pub struct PhysicalDevice {
+    config: MockConfig,
+}
+
 impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
     unsafe fn open(
         &self,
-        _: &[(&QueueFamily, &[queue::QueuePriority])],
-        _: hal::Features,
-    ) -> Result<adapter::Gpu<Backend>, device::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
+        families: &[(&QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Backend>, DeviceCreationError> {
+        if !self.config.features.contains(requested_features) {
+            return Err(DeviceCreationError::MissingFeature);
+        }
+
+        let queue_groups = families
+            .iter()
+            .map(|&(family, priorities)| {
+                let mut group = queue::QueueGroup::new(family.id);
+                for _ in priorities {
+                    group.add_queue(CommandQueue);
+                }
+                group
+            })
+            .collect();
+
+        Ok(adapter::Gpu {
+            device: Device::new(self.config.clone()),
+            queue_groups,
+        })
     }
 
     fn format_properties(&self, _: Option<format::Format>) -> format::Properties {
-        panic!(DO_NOT_USE_MESSAGE)
+        format::Properties {
+            linear_tiling: format::ImageFeature::all(),
+            optimal_tiling: format::ImageFeature::all(),
+            buffer_features: format::BufferFeature::all(),
+        }
     }
 
     fn image_format_properties(
         &self,
         _: format::Format,
-        _dim: u8,
+        dimensions: u8,
         _: image::Tiling,
         _: image::Usage,
         _: image::ViewCapabilities,
     ) -> Option<image::FormatProperties> {
-        panic!(DO_NOT_USE_MESSAGE)
+        let limits = &self.config.limits;
+        let max_extent = match dimensions {
+            1 => image::Extent {
+                width: limits.max_image_1d_size,
+                height: 1,
+                depth: 1,
+            },
+            2 => image::Extent {
+                width: limits.max_image_2d_size,
+                height: limits.max_image_2d_size,
+                depth: 1,
+            },
+            3 => image::Extent {
+                width: limits.max_image_3d_size,
+                height: limits.max_image_3d_size,
+                depth: limits.max_image_3d_size,
+            },
+            _ => return None,
+        };
+
+        Some(image::FormatProperties {
+            max_extent,
+            max_levels: (32 - max_extent.width.max(max_extent.height).leading_zeros())
+                as image::Level,
+            max_layers: limits.max_image_array_layers,
+            sample_count_mask: limits.framebuffer_color_sample_counts,
+            max_resource_size: !0,
+        })
     }
 
     fn memory_properties(&self) -> adapter::MemoryProperties {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.config.memory_properties.clone()
     }
 
     fn features(&self) -> hal::Features {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.config.features
     }
 
     fn hints(&self) -> hal::Hints {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.config.hints
     }
 
     fn limits(&self) -> hal::Limits {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.config.limits
     }
 }
 
-/// Dummy command queue doing nothing.
+/// Mock command queue.
+///
+/// Submissions complete immediately, signalling the semaphores and the fence right away.
 #[derive(Debug)]
 pub struct CommandQueue;
 impl queue::CommandQueue<Backend> for CommandQueue {
     unsafe fn submit<'a, T, Ic, S, Iw, Is>(
         &mut self,
-        _: queue::Submission<Ic, Iw, Is>,
-        _: Option<&()>,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&native::Fence>,
     ) where
         T: 'a + Borrow<CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
-        S: 'a + Borrow<()>,
-        Iw: IntoIterator<Item = (&'a S, pso::PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>,
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
     {
-        panic!(DO_NOT_USE_MESSAGE)
+        for (semaphore, value) in submission.signal_semaphores {
+            semaphore.borrow().signal(value);
+        }
+        if let Some(fence) = fence {
+            fence.set(true);
+        }
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&native::Fence>,
+    ) where
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b native::Buffer,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseImageBind<&'b native::Memory>],
+            ),
+        >,
+    {
+        for (semaphore, value) in binding.signal_semaphores {
+            semaphore.borrow().signal(value);
+        }
+        if let Some(fence) = fence {
+            fence.set(true);
+        }
     }
 
     unsafe fn present<'a, W, Is, S, Iw>(
         &mut self,
         _: Is,
         _: Iw,
-    ) -> Result<Option<window::Suboptimal>, window::PresentError>
+    ) -> Result<Option<Suboptimal>, PresentError>
     where
         W: 'a + Borrow<Swapchain>,
-        Is: IntoIterator<Item = (&'a W, window::SwapImageIndex)>,
-        S: 'a + Borrow<()>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<native::Semaphore>,
         Iw: IntoIterator<Item = &'a S>,
     {
-        panic!(DO_NOT_USE_MESSAGE)
+        Ok(None)
     }
 
     unsafe fn present_surface(
         &mut self,
         _surface: &mut Surface,
-        _image: (),
-        _wait_semaphore: Option<&()>,
-    ) -> Result<Option<window::Suboptimal>, window::PresentError> {
-        panic!(DO_NOT_USE_MESSAGE)
+        _image: native::ImageView,
+        _wait_semaphore: Option<&native::Semaphore>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        Ok(None)
     }
 
-    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        Ok(())
     }
 }
 
-/// Dummy device doing nothing.
+/// Mock queue family, supporting all kinds of operations.
 #[derive(Debug)]
-pub struct Device;
-impl device::Device<Backend> for Device {
-    unsafe fn create_command_pool(
-        &self,
-        _: queue::QueueFamilyId,
-        _: pool::CommandPoolCreateFlags,
-    ) -> Result<CommandPool, device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_command_pool(&self, _: CommandPool) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn allocate_memory(
-        &self,
-        _: hal::MemoryTypeId,
-        _: u64,
-    ) -> Result<(), device::AllocationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_render_pass<'a, IA, IS, ID>(
-        &self,
-        _: IA,
-        _: IS,
-        _: ID,
-    ) -> Result<(), device::OutOfMemory>
-    where
-        IA: IntoIterator,
-        IA::Item: Borrow<pass::Attachment>,
-        IS: IntoIterator,
-        IS::Item: Borrow<pass::SubpassDesc<'a>>,
-        ID: IntoIterator,
-        ID::Item: Borrow<pass::SubpassDependency>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_pipeline_layout<IS, IR>(&self, _: IS, _: IR) -> Result<(), device::OutOfMemory>
-    where
-        IS: IntoIterator,
-        IS::Item: Borrow<()>,
-        IR: IntoIterator,
-        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_pipeline_cache(
-        &self,
-        _data: Option<&[u8]>,
-    ) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_pipeline_cache_data(&self, _cache: &()) -> Result<Vec<u8>, device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_pipeline_cache(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_graphics_pipeline<'a>(
-        &self,
-        _: &pso::GraphicsPipelineDesc<'a, Backend>,
-        _: Option<&()>,
-    ) -> Result<(), pso::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_compute_pipeline<'a>(
-        &self,
-        _: &pso::ComputePipelineDesc<'a, Backend>,
-        _: Option<&()>,
-    ) -> Result<(), pso::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn merge_pipeline_caches<I>(&self, _: &(), _: I) -> Result<(), device::OutOfMemory>
-    where
-        I: IntoIterator,
-        I::Item: Borrow<()>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_framebuffer<I>(
-        &self,
-        _: &(),
-        _: I,
-        _: image::Extent,
-    ) -> Result<(), device::OutOfMemory>
-    where
-        I: IntoIterator,
-        I::Item: Borrow<()>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_shader_module(&self, _: &[u32]) -> Result<(), device::ShaderError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_sampler(&self, _: &image::SamplerDesc) -> Result<(), device::AllocationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn create_buffer(&self, _: u64, _: buffer::Usage) -> Result<(), buffer::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_buffer_requirements(&self, _: &()) -> memory::Requirements {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_buffer_memory(
-        &self,
-        _: &(),
-        _: u64,
-        _: &mut (),
-    ) -> Result<(), device::BindError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_buffer_view(
-        &self,
-        _: &(),
-        _: Option<format::Format>,
-        _: buffer::SubRange,
-    ) -> Result<(), buffer::ViewCreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_image(
-        &self,
-        _: image::Kind,
-        _: image::Level,
-        _: format::Format,
-        _: image::Tiling,
-        _: image::Usage,
-        _: image::ViewCapabilities,
-    ) -> Result<(), image::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_image_requirements(&self, _: &()) -> memory::Requirements {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_image_subresource_footprint(
-        &self,
-        _: &(),
-        _: image::Subresource,
-    ) -> image::SubresourceFootprint {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_image_memory(
-        &self,
-        _: &(),
-        _: u64,
-        _: &mut (),
-    ) -> Result<(), device::BindError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_image_view(
-        &self,
-        _: &(),
-        _: image::ViewKind,
-        _: format::Format,
-        _: format::Swizzle,
-        _: image::SubresourceRange,
-    ) -> Result<(), image::ViewCreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_descriptor_pool<I>(
-        &self,
-        _: usize,
-        _: I,
-        _: pso::DescriptorPoolCreateFlags,
-    ) -> Result<DescriptorPool, device::OutOfMemory>
-    where
-        I: IntoIterator,
-        I::Item: Borrow<pso::DescriptorRangeDesc>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_descriptor_set_layout<I, J>(
-        &self,
-        _: I,
-        _: J,
-    ) -> Result<(), device::OutOfMemory>
-    where
-        I: IntoIterator,
-        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
-        J: IntoIterator,
-        J::Item: Borrow<()>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn write_descriptor_sets<'a, I, J>(&self, _: I)
-    where
-        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Backend, J>>,
-        J: IntoIterator,
-        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn copy_descriptor_sets<'a, I>(&self, _: I)
-    where
-        I: IntoIterator,
-        I::Item: Borrow<pso::DescriptorSetCopy<'a, Backend>>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn create_semaphore(&self) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn create_fence(&self, _: bool) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_fence_status(&self, _: &()) -> Result<bool, device::DeviceLost> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn create_event(&self) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_event_status(&self, _: &()) -> Result<bool, device::OomOrDeviceLost> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_event(&self, _: &()) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn reset_event(&self, _: &()) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_query_pool(&self, _: query::Type, _: u32) -> Result<(), query::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_query_pool(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn get_query_pool_results(
-        &self,
-        _: &(),
-        _: Range<query::Id>,
-        _: &mut [u8],
-        _: buffer::Offset,
-        _: query::ResultFlags,
-    ) -> Result<bool, device::OomOrDeviceLost> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn map_memory(&self, _: &(), _: memory::Segment) -> Result<*mut u8, device::MapError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn unmap_memory(&self, _: &()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
-    where
-        I: IntoIterator,
-        I::Item: Borrow<(&'a (), memory::Segment)>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn invalidate_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
-    where
-        I: IntoIterator,
-        I::Item: Borrow<(&'a (), memory::Segment)>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn free_memory(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_shader_module(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_render_pass(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_pipeline_layout(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_graphics_pipeline(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_compute_pipeline(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_framebuffer(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_buffer(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_buffer_view(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_image(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_image_view(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn destroy_sampler(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_descriptor_pool(&self, _: DescriptorPool) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_descriptor_set_layout(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_fence(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_semaphore(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_event(&self, _: ()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn create_swapchain(
-        &self,
-        _: &mut Surface,
-        _: window::SwapchainConfig,
-        _: Option<Swapchain>,
-    ) -> Result<(Swapchain, Vec<()>), hal::window::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn destroy_swapchain(&self, _: Swapchain) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_image_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_buffer_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_command_buffer_name(&self, _: &mut CommandBuffer, _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_semaphore_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_fence_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_framebuffer_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_render_pass_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_descriptor_set_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_descriptor_set_layout_name(&self, _: &mut (), _: &str) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
+pub struct QueueFamily {
+    id: queue::QueueFamilyId,
 }
-
-#[derive(Debug)]
-pub struct QueueFamily;
 impl queue::QueueFamily for QueueFamily {
     fn queue_type(&self) -> queue::QueueType {
-        panic!(DO_NOT_USE_MESSAGE)
+        queue::QueueType::General
     }
     fn max_queues(&self) -> usize {
-        panic!(DO_NOT_USE_MESSAGE)
+        1
     }
     fn id(&self) -> queue::QueueFamilyId {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.id
+    }
+    fn timestamp_valid_bits(&self) -> u32 {
+        64
     }
 }
 
-/// Dummy raw command pool.
+/// Mock command pool.
 #[derive(Debug)]
 pub struct CommandPool;
 impl pool::CommandPool<Backend> for CommandPool {
-    unsafe fn reset(&mut self, _: bool) {
-        panic!(DO_NOT_USE_MESSAGE)
+    unsafe fn reset(&mut self, _: bool) {}
+
+    unsafe fn allocate_one(&mut self, _: hal::command::Level) -> CommandBuffer {
+        CommandBuffer::new()
     }
 
     unsafe fn free<I>(&mut self, _: I)
     where
         I: IntoIterator<Item = CommandBuffer>,
     {
-        panic!(DO_NOT_USE_MESSAGE)
     }
 }
 
-/// Dummy command buffer, which ignores all the calls.
+/// Mock descriptor pool, keeping track of the number of allocated sets.
 #[derive(Debug)]
-pub struct CommandBuffer;
-impl command::CommandBuffer<Backend> for CommandBuffer {
-    unsafe fn begin(
-        &mut self,
-        _: command::CommandBufferFlags,
-        _: command::CommandBufferInheritanceInfo<Backend>,
-    ) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn finish(&mut self) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn reset(&mut self, _: bool) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn pipeline_barrier<'a, T>(
-        &mut self,
-        _: Range<pso::PipelineStage>,
-        _: memory::Dependencies,
-        _: T,
-    ) where
-        T: IntoIterator,
-        T::Item: Borrow<memory::Barrier<'a, Backend>>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn fill_buffer(&mut self, _: &(), _: buffer::SubRange, _: u32) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn update_buffer(&mut self, _: &(), _: buffer::Offset, _: &[u8]) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn clear_image<T>(&mut self, _: &(), _: image::Layout, _: command::ClearValue, _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<image::SubresourceRange>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn clear_attachments<T, U>(&mut self, _: T, _: U)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<command::AttachmentClear>,
-        U: IntoIterator,
-        U::Item: Borrow<pso::ClearRect>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn resolve_image<T>(&mut self, _: &(), _: image::Layout, _: &(), _: image::Layout, _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<command::ImageResolve>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn blit_image<T>(
-        &mut self,
-        _: &(),
-        _: image::Layout,
-        _: &(),
-        _: image::Layout,
-        _: image::Filter,
-        _: T,
-    ) where
-        T: IntoIterator,
-        T::Item: Borrow<command::ImageBlit>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_index_buffer(&mut self, _: buffer::IndexBufferView<Backend>) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_vertex_buffers<I, T>(&mut self, _: u32, _: I)
-    where
-        I: IntoIterator<Item = (T, buffer::SubRange)>,
-        T: Borrow<()>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_viewports<T>(&mut self, _: u32, _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<pso::Viewport>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_scissors<T>(&mut self, _: u32, _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<pso::Rect>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_stencil_reference(&mut self, _: pso::Face, _: pso::StencilValue) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_stencil_read_mask(&mut self, _: pso::Face, _: pso::StencilValue) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_stencil_write_mask(&mut self, _: pso::Face, _: pso::StencilValue) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_blend_constants(&mut self, _: pso::ColorValue) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_depth_bounds(&mut self, _: Range<f32>) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_line_width(&mut self, _: f32) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_depth_bias(&mut self, _: pso::DepthBias) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn begin_render_pass<T>(
-        &mut self,
-        _: &(),
-        _: &(),
-        _: pso::Rect,
-        _: T,
-        _: command::SubpassContents,
-    ) where
-        T: IntoIterator,
-        T::Item: Borrow<command::ClearValue>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn next_subpass(&mut self, _: command::SubpassContents) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn end_render_pass(&mut self) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_graphics_pipeline(&mut self, _: &()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_graphics_descriptor_sets<I, J>(&mut self, _: &(), _: usize, _: I, _: J)
-    where
-        I: IntoIterator,
-        I::Item: Borrow<()>,
-        J: IntoIterator,
-        J::Item: Borrow<command::DescriptorSetOffset>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_compute_pipeline(&mut self, _: &()) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn bind_compute_descriptor_sets<I, J>(&mut self, _: &(), _: usize, _: I, _: J)
-    where
-        I: IntoIterator,
-        I::Item: Borrow<()>,
-        J: IntoIterator,
-        J::Item: Borrow<command::DescriptorSetOffset>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn dispatch(&mut self, _: hal::WorkGroupCount) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn dispatch_indirect(&mut self, _: &(), _: buffer::Offset) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn copy_buffer<T>(&mut self, _: &(), _: &(), _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<command::BufferCopy>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn copy_image<T>(&mut self, _: &(), _: image::Layout, _: &(), _: image::Layout, _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<command::ImageCopy>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn copy_buffer_to_image<T>(&mut self, _: &(), _: &(), _: image::Layout, _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<command::BufferImageCopy>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn copy_image_to_buffer<T>(&mut self, _: &(), _: image::Layout, _: &(), _: T)
-    where
-        T: IntoIterator,
-        T::Item: Borrow<command::BufferImageCopy>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn draw(&mut self, _: Range<hal::VertexCount>, _: Range<hal::InstanceCount>) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn draw_indexed(
-        &mut self,
-        _: Range<hal::IndexCount>,
-        _: hal::VertexOffset,
-        _: Range<hal::InstanceCount>,
-    ) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn draw_indirect(&mut self, _: &(), _: buffer::Offset, _: hal::DrawCount, _: u32) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn draw_indexed_indirect(
-        &mut self,
-        _: &(),
-        _: buffer::Offset,
-        _: hal::DrawCount,
-        _: u32,
-    ) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn set_event(&mut self, _: &(), _: pso::PipelineStage) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn reset_event(&mut self, _: &(), _: pso::PipelineStage) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn wait_events<'a, I, J>(&mut self, _: I, _: Range<pso::PipelineStage>, _: J)
-    where
-        I: IntoIterator,
-        I::Item: Borrow<()>,
-        J: IntoIterator,
-        J::Item: Borrow<memory::Barrier<'a, Backend>>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn begin_query(&mut self, _: query::Query<Backend>, _: query::ControlFlags) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn end_query(&mut self, _: query::Query<Backend>) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn reset_query_pool(&mut self, _: &(), _: Range<query::Id>) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn copy_query_pool_results(
-        &mut self,
-        _: &(),
-        _: Range<query::Id>,
-        _: &(),
-        _: buffer::Offset,
-        _: buffer::Offset,
-        _: query::ResultFlags,
-    ) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, _: query::Query<Backend>) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn push_graphics_constants(
-        &mut self,
-        _: &(),
-        _: pso::ShaderStageFlags,
-        _: u32,
-        _: &[u32],
-    ) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn push_compute_constants(&mut self, _: &(), _: u32, _: &[u32]) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn execute_commands<'a, T, I>(&mut self, _: I)
-    where
-        T: 'a + Borrow<CommandBuffer>,
-        I: IntoIterator<Item = &'a T>,
-    {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn insert_debug_marker(&mut self, _: &str, _: u32) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn begin_debug_marker(&mut self, _: &str, _: u32) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-    unsafe fn end_debug_marker(&mut self) {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
+pub struct DescriptorPool {
+    max_sets: usize,
+    allocated: usize,
 }
-
-// Dummy descriptor pool.
-#[derive(Debug)]
-pub struct DescriptorPool;
 impl pso::DescriptorPool<Backend> for DescriptorPool {
-    unsafe fn free<I>(&mut self, _descriptor_sets: I)
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &native::DescriptorSetLayout,
+    ) -> Result<native::DescriptorSet, pso::AllocationError> {
+        if self.allocated == self.max_sets {
+            return Err(pso::AllocationError::OutOfPoolMemory);
+        }
+        self.allocated += 1;
+        Ok(native::DescriptorSet {
+            bindings: layout.bindings.clone(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
     where
-        I: IntoIterator<Item = ()>,
+        I: IntoIterator<Item = native::DescriptorSet>,
     {
-        panic!(DO_NOT_USE_MESSAGE)
+        let count = descriptor_sets.into_iter().count();
+        assert!(
+            count <= self.allocated,
+            "Freeing {} descriptor sets, only {} are allocated from the pool",
+            count,
+            self.allocated
+        );
+        self.allocated -= count;
     }
 
     unsafe fn reset(&mut self) {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.allocated = 0;
     }
 }
 
-/// Dummy surface.
+/// Mock instance, exposing a single adapter.
 #[derive(Debug)]
-pub struct Surface;
-impl window::Surface<Backend> for Surface {
-    fn supports_queue_family(&self, _: &QueueFamily) -> bool {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn capabilities(&self, _: &PhysicalDevice) -> window::SurfaceCapabilities {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn supported_formats(&self, _: &PhysicalDevice) -> Option<Vec<format::Format>> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
+pub struct Instance {
+    config: MockConfig,
 }
-impl window::PresentationSurface<Backend> for Surface {
-    type SwapchainImage = ();
 
-    unsafe fn configure_swapchain(
-        &mut self,
-        _: &Device,
-        _: window::SwapchainConfig,
-    ) -> Result<(), window::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    unsafe fn unconfigure_swapchain(&mut self, _: &Device) {
-        panic!(DO_NOT_USE_MESSAGE)
+impl Instance {
+    /// Create an instance whose adapter is described by the given config.
+    pub fn with_config(config: MockConfig) -> Self {
+        Instance { config }
     }
 
-    unsafe fn acquire_image(
-        &mut self,
-        _: u64,
-    ) -> Result<((), Option<window::Suboptimal>), window::AcquireError> {
-        panic!(DO_NOT_USE_MESSAGE)
+    /// Create a surface which is not tied to any window.
+    pub fn create_surface_headless(&self, extent: Extent2D) -> Surface {
+        Surface::new(extent)
     }
 }
 
-/// Dummy swapchain.
-#[derive(Debug)]
-pub struct Swapchain;
-impl window::Swapchain<Backend> for Swapchain {
-    unsafe fn acquire_image(
-        &mut self,
-        _: u64,
-        _: Option<&()>,
-        _: Option<&()>,
-    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-}
-
-#[derive(Debug)]
-pub struct Instance;
-
 impl hal::Instance<Backend> for Instance {
     fn create(_name: &str, _version: u32) -> Result<Self, hal::UnsupportedBackend> {
-        Ok(Instance)
+        Ok(Instance::with_config(MockConfig::default()))
     }
 
     fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Backend>> {
-        vec![]
+        vec![adapter::Adapter {
+            info: self.config.info.clone(),
+            physical_device: PhysicalDevice {
+                config: self.config.clone(),
+            },
+            queue_families: vec![QueueFamily {
+                id: queue::QueueFamilyId(0),
+            }],
+        }]
     }
 
     unsafe fn create_surface(
         &self,
         _: &impl raw_window_handle::HasRawWindowHandle,
     ) -> Result<Surface, hal::window::InitError> {
-        panic!(DO_NOT_USE_MESSAGE)
+        Ok(Surface::new(window::DEFAULT_EXTENT))
     }
 
-    unsafe fn destroy_surface(&self, _surface: Surface) {
-        panic!(DO_NOT_USE_MESSAGE)
+    unsafe fn destroy_surface(&self, _surface: Surface) {}
+}
+
+fn memory_type_mask(properties: &adapter::MemoryProperties) -> u64 {
+    (1 << properties.memory_types.len()) - 1
+}
+
+fn aligned(value: u64, alignment: u64) -> u64 {
+    if alignment == 0 {
+        return value;
+    }
+    (value + alignment - 1) / alignment * alignment
+}
+
+fn bound_range(
+    memory: &native::Memory,
+    offset: u64,
+    requirements: &memory::Requirements,
+) -> Result<Range<u64>, BindError> {
+    if requirements.type_mask & (1 << memory.ty.0) == 0 {
+        return Err(BindError::WrongMemory);
+    }
+    if offset + requirements.size > memory.size {
+        return Err(BindError::OutOfBounds);
+    }
+    Ok(offset .. offset + requirements.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        device::Device as _,
+        external,
+        queue::CommandQueue as _,
+        Instance as _,
+    };
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device>();
+        foo::<native::Memory>();
+    }
+
+    #[test]
+    fn buffer_lifetime() {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let mut buffer = device
+                .create_buffer(100, buffer::Usage::UNIFORM, memory::SparseFlags::empty())
+                .unwrap();
+            let requirements = device.get_buffer_requirements(&buffer);
+            assert_eq!(requirements.size, 256);
+
+            let memory = device
+                .allocate_memory(hal::MemoryTypeId(1), requirements.size)
+                .unwrap();
+            assert_eq!(device.heap_usage(), vec![0, 256]);
+            assert_eq!(
+                device.allocate_memory(hal::MemoryTypeId(1), u64::MAX).err(),
+                Some(hal::device::OutOfMemory::Device.into())
+            );
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            assert_eq!(buffer.bound_range(), Some(0 .. 256));
+
+            let ptr = device.map_memory(&memory, memory::Segment::ALL).unwrap();
+            *ptr = 42;
+            device.unmap_memory(&memory);
+
+            let fence = device.create_fence(false).unwrap();
+            gpu.queue_groups[0].queues[0]
+                .submit_without_semaphores(None::<&CommandBuffer>, Some(&fence));
+            assert!(device.get_fence_status(&fence).unwrap());
+
+            device.destroy_fence(fence);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+            assert_eq!(device.allocation_count(), 0);
+        }
+    }
+
+    #[test]
+    fn external_handles() {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let physical_device = &adapter.physical_device;
+        assert_eq!(
+            physical_device.external_buffer_properties(
+                buffer::Usage::UNIFORM,
+                external::MemoryHandleType::OpaqueFd
+            ),
+            external::ExternalMemoryProperties::default()
+        );
+        assert_eq!(
+            physical_device.external_image_properties(
+                format::Format::Rgba8Unorm,
+                2,
+                image::Tiling::Optimal,
+                image::Usage::SAMPLED,
+                image::ViewCapabilities::empty(),
+                external::MemoryHandleType::DmaBuf
+            ),
+            None
+        );
+        assert_eq!(
+            physical_device.external_semaphore_properties(external::SemaphoreHandleType::SyncFd),
+            external::ExternalSemaphoreProperties::default()
+        );
+        assert!(physical_device
+            .drm_format_modifier_properties(format::Format::Rgba8Unorm)
+            .is_empty());
+
+        let gpu = unsafe {
+            physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::EXTERNAL_MEMORY_FD | hal::Features::EXTERNAL_SEMAPHORE_FD,
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let memory = device
+                .allocate_exportable_memory(
+                    hal::MemoryTypeId(1),
+                    256,
+                    external::MemoryHandleTypes::OPAQUE_FD,
+                )
+                .unwrap();
+            assert_eq!(device.heap_usage(), vec![0, 256]);
+            assert_eq!(
+                device
+                    .export_memory(&memory, external::MemoryHandleType::OpaqueFd)
+                    .err(),
+                Some(external::ExportError::Unsupported)
+            );
+            assert_eq!(
+                device.get_memory_fd_type_mask(external::MemoryHandleType::DmaBuf, 0),
+                Err(external::ImportError::InvalidHandle)
+            );
+            assert_eq!(
+                device
+                    .import_memory(
+                        hal::MemoryTypeId(1),
+                        256,
+                        external::MemoryHandleType::OpaqueFd,
+                        0
+                    )
+                    .err(),
+                Some(external::ImportError::InvalidHandle)
+            );
+            device.free_memory(memory);
+
+            let semaphore = device
+                .create_exportable_semaphore(external::SemaphoreHandleTypes::SYNC_FD)
+                .unwrap();
+            assert_eq!(
+                device.export_semaphore(&semaphore, external::SemaphoreHandleType::SyncFd),
+                Err(external::ExportError::Unsupported)
+            );
+            assert_eq!(
+                device.import_semaphore(&semaphore, external::SemaphoreHandleType::OpaqueFd, 0),
+                Err(external::ImportError::InvalidHandle)
+            );
+            device.destroy_semaphore(semaphore);
+        }
+    }
+
+    #[test]
+    fn timeline_semaphore() {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::TIMELINE_SEMAPHORE,
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let semaphore = device.create_timeline_semaphore(1).unwrap();
+            assert_eq!(device.get_semaphore_value(&semaphore).unwrap(), 1);
+            assert!(!device.wait_for_semaphore_value(&semaphore, 2, 0).unwrap());
+
+            let submission = queue::Submission {
+                command_buffers: None::<&CommandBuffer>,
+                wait_semaphores: Some((&semaphore, 1, pso::PipelineStage::TOP_OF_PIPE)),
+                signal_semaphores: Some((&semaphore, 3)),
+            };
+            gpu.queue_groups[0].queues[0].submit(submission, None);
+            assert!(device.wait_for_semaphore_value(&semaphore, 2, 0).unwrap());
+
+            device.signal_semaphore(&semaphore, 5).unwrap();
+            assert_eq!(device.get_semaphore_value(&semaphore).unwrap(), 5);
+            device.destroy_semaphore(semaphore);
+        }
     }
 }
@@ -0,0 +1,350 @@
+//! Resource handles of the mock backend.
+//!
+//! Handles carry just enough state for the device to answer queries about them,
+//! such as memory requirements, bindings, mappings and fence status.
+
+use hal::{buffer, format, image, memory, pass, pso, MemoryTypeId};
+
+use std::cell::UnsafeCell;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// A block of mock device memory.
+///
+/// CPU-visible memory is backed by host storage, so that mapped pointers
+/// can be written to and read from like on a real device.
+#[derive(Debug)]
+pub struct Memory {
+    pub(crate) ty: MemoryTypeId,
+    pub(crate) heap_index: usize,
+    pub(crate) size: u64,
+    data: Option<UnsafeCell<Box<[u8]>>>,
+}
+
+unsafe impl Send for Memory {}
+unsafe impl Sync for Memory {}
+
+impl Memory {
+    pub(crate) fn new(
+        ty: MemoryTypeId,
+        heap_index: usize,
+        properties: memory::Properties,
+        size: u64,
+    ) -> Self {
+        let data = if properties.contains(memory::Properties::CPU_VISIBLE) {
+            Some(UnsafeCell::new(vec![0; size as usize].into_boxed_slice()))
+        } else {
+            None
+        };
+        Memory {
+            ty,
+            heap_index,
+            size,
+            data,
+        }
+    }
+
+    /// Returns the type this memory was allocated from.
+    pub fn memory_type(&self) -> MemoryTypeId {
+        self.ty
+    }
+
+    /// Returns the size of the allocation in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn host_ptr(&self) -> Option<*mut u8> {
+        self.data
+            .as_ref()
+            .map(|data| unsafe { (*data.get()).as_mut_ptr() })
+    }
+}
+
+/// Mock buffer.
+#[derive(Debug)]
+pub struct Buffer {
+    pub(crate) size: u64,
+    pub(crate) usage: buffer::Usage,
+    pub(crate) requirements: memory::Requirements,
+    pub(crate) bound: Option<Range<u64>>,
+    pub(crate) name: String,
+}
+
+impl Buffer {
+    /// Returns the size of the buffer in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the usage the buffer was created with.
+    pub fn usage(&self) -> buffer::Usage {
+        self.usage
+    }
+
+    /// Returns the memory range this buffer is bound to, if any.
+    pub fn bound_range(&self) -> Option<Range<u64>> {
+        self.bound.clone()
+    }
+
+    /// Returns the debug name of the buffer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock buffer view.
+#[derive(Debug)]
+pub struct BufferView;
+
+/// Mock image.
+#[derive(Debug)]
+pub struct Image {
+    pub(crate) kind: image::Kind,
+    pub(crate) mip_levels: image::Level,
+    pub(crate) format: format::Format,
+    pub(crate) usage: image::Usage,
+    pub(crate) requirements: memory::Requirements,
+    pub(crate) bound: Option<Range<u64>>,
+    pub(crate) name: String,
+}
+
+impl Image {
+    /// Returns the kind of the image.
+    pub fn kind(&self) -> image::Kind {
+        self.kind
+    }
+
+    /// Returns the number of mip levels of the image.
+    pub fn mip_levels(&self) -> image::Level {
+        self.mip_levels
+    }
+
+    /// Returns the format of the image.
+    pub fn format(&self) -> format::Format {
+        self.format
+    }
+
+    /// Returns the usage the image was created with.
+    pub fn usage(&self) -> image::Usage {
+        self.usage
+    }
+
+    /// Returns the memory range this image is bound to, if any.
+    pub fn bound_range(&self) -> Option<Range<u64>> {
+        self.bound.clone()
+    }
+
+    /// Returns the debug name of the image.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock image view.
+#[derive(Clone, Debug)]
+pub struct ImageView {
+    pub(crate) format: format::Format,
+    pub(crate) range: image::SubresourceRange,
+}
+
+impl ImageView {
+    /// Returns the format of the view.
+    pub fn format(&self) -> format::Format {
+        self.format
+    }
+
+    /// Returns the subresource range covered by the view.
+    pub fn range(&self) -> &image::SubresourceRange {
+        &self.range
+    }
+}
+
+/// Mock sampler.
+#[derive(Debug)]
+pub struct Sampler;
+
+/// Mock shader module, keeping the SPIR-V it was created from.
+#[derive(Debug)]
+pub struct ShaderModule {
+    pub(crate) spirv: Vec<u32>,
+}
+
+impl ShaderModule {
+    /// Returns the SPIR-V words of the module.
+    pub fn spirv(&self) -> &[u32] {
+        &self.spirv
+    }
+}
+
+/// Mock render pass.
+#[derive(Debug)]
+pub struct RenderPass {
+    pub(crate) attachments: Vec<pass::Attachment>,
+    pub(crate) name: String,
+}
+
+impl RenderPass {
+    /// Returns the attachments of the render pass.
+    pub fn attachments(&self) -> &[pass::Attachment] {
+        &self.attachments
+    }
+
+    /// Returns the debug name of the render pass.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock framebuffer.
+#[derive(Debug)]
+pub struct Framebuffer {
+    pub(crate) extent: image::Extent,
+    pub(crate) name: String,
+}
+
+impl Framebuffer {
+    /// Returns the extent of the framebuffer.
+    pub fn extent(&self) -> image::Extent {
+        self.extent
+    }
+
+    /// Returns the debug name of the framebuffer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock pipeline cache.
+#[derive(Debug)]
+pub struct PipelineCache {
+    pub(crate) data: Vec<u8>,
+}
+
+/// Mock pipeline layout.
+#[derive(Debug)]
+pub struct PipelineLayout;
+
+/// Mock graphics pipeline.
+#[derive(Debug)]
+pub struct GraphicsPipeline;
+
+/// Mock compute pipeline.
+#[derive(Debug)]
+pub struct ComputePipeline;
+
+/// Mock descriptor set layout.
+#[derive(Debug)]
+pub struct DescriptorSetLayout {
+    pub(crate) bindings: Vec<pso::DescriptorSetLayoutBinding>,
+    pub(crate) name: String,
+}
+
+impl DescriptorSetLayout {
+    /// Returns the bindings of the layout.
+    pub fn bindings(&self) -> &[pso::DescriptorSetLayoutBinding] {
+        &self.bindings
+    }
+
+    /// Returns the debug name of the layout.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock descriptor set.
+#[derive(Debug)]
+pub struct DescriptorSet {
+    pub(crate) bindings: Vec<pso::DescriptorSetLayoutBinding>,
+    pub(crate) name: String,
+}
+
+impl DescriptorSet {
+    /// Returns the bindings of the layout this set was allocated with.
+    pub fn bindings(&self) -> &[pso::DescriptorSetLayoutBinding] {
+        &self.bindings
+    }
+
+    /// Returns the debug name of the set.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock fence.
+///
+/// Submitted work completes immediately, so a fence gets signalled
+/// as soon as the submission it's attached to is made.
+#[derive(Debug)]
+pub struct Fence {
+    signaled: AtomicBool,
+    pub(crate) name: String,
+}
+
+impl Fence {
+    pub(crate) fn new(signaled: bool) -> Self {
+        Fence {
+            signaled: AtomicBool::new(signaled),
+            name: String::new(),
+        }
+    }
+
+    pub(crate) fn set(&self, signaled: bool) {
+        self.signaled.store(signaled, Ordering::Release);
+    }
+
+    /// Returns true if the fence is in signalled state.
+    pub fn is_signaled(&self) -> bool {
+        self.signaled.load(Ordering::Acquire)
+    }
+
+    /// Returns the debug name of the fence.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock semaphore.
+///
+/// Submitted work completes immediately, so the counter of a timeline semaphore
+/// reaches the signalled values as soon as the submission is made.
+#[derive(Debug)]
+pub struct Semaphore {
+    value: AtomicU64,
+    pub(crate) name: String,
+}
+
+impl Semaphore {
+    pub(crate) fn new(value: u64) -> Self {
+        Semaphore {
+            value: AtomicU64::new(value),
+            name: String::new(),
+        }
+    }
+
+    pub(crate) fn signal(&self, value: u64) {
+        self.value.fetch_max(value, Ordering::AcqRel);
+    }
+
+    /// Returns the counter value, which stays at 0 for binary semaphores.
+    pub fn value(&self) -> u64 {
+        self.value.load(Ordering::Acquire)
+    }
+
+    /// Returns the debug name of the semaphore.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Mock event.
+#[derive(Debug)]
+pub struct Event {
+    pub(crate) set: AtomicBool,
+}
+
+/// Mock query pool.
+#[derive(Debug)]
+pub struct QueryPool {
+    pub(crate) count: hal::query::Id,
+}
@@ -0,0 +1,640 @@
+use crate::{device::unwrap_descriptor, Faulty};
+use hal::{
+    buffer,
+    command as com,
+    image,
+    memory,
+    pass,
+    pso,
+    query,
+    DrawCount,
+    IndexCount,
+    InstanceCount,
+    VertexCount,
+    VertexOffset,
+    WorkGroupCount,
+};
+use std::borrow::Borrow;
+use std::ops::Range;
+
+/// Command buffer of the fault injection backend.
+///
+/// Recording can't fail, so the commands are forwarded as is.
+#[derive(Debug)]
+pub struct CommandBuffer<B: hal::Backend> {
+    pub(crate) raw: B::CommandBuffer,
+}
+
+impl<B: hal::Backend> CommandBuffer<B> {
+    /// Returns the wrapped command buffer.
+    pub fn raw(&self) -> &B::CommandBuffer {
+        &self.raw
+    }
+}
+
+fn barriers<'a, B: hal::Backend, T>(barriers: T) -> Vec<memory::Barrier<'a, B>>
+where
+    T: IntoIterator,
+    T::Item: Borrow<memory::Barrier<'a, Faulty<B>>>,
+{
+    barriers
+        .into_iter()
+        .map(|barrier| match *barrier.borrow() {
+            memory::Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+            memory::Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+            memory::Barrier::Buffer {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => memory::Barrier::Buffer {
+                states: states.clone(),
+                target,
+                range: range.clone(),
+                families: families.clone(),
+            },
+            memory::Barrier::Image {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => memory::Barrier::Image {
+                states: states.clone(),
+                target,
+                range: range.clone(),
+                families: families.clone(),
+            },
+        })
+        .collect()
+}
+
+/// Unwraps the descriptors pushed into a command buffer.
+fn push_writes<'a, B: hal::Backend, I, J>(
+    writes: I,
+) -> Vec<pso::DescriptorWrite<Vec<pso::Descriptor<'a, B>>>>
+where
+    I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+    J: IntoIterator,
+    J::Item: Borrow<pso::Descriptor<'a, Faulty<B>>>,
+{
+    writes
+        .into_iter()
+        .map(|write| pso::DescriptorWrite {
+            binding: write.binding,
+            array_offset: write.array_offset,
+            descriptors: write
+                .descriptors
+                .into_iter()
+                .map(|descriptor| unwrap_descriptor(descriptor.borrow()))
+                .collect(),
+        })
+        .collect()
+}
+
+impl<B: hal::Backend> com::CommandBuffer<Faulty<B>> for CommandBuffer<B> {
+    unsafe fn begin(
+        &mut self,
+        flags: com::CommandBufferFlags,
+        inheritance_info: com::CommandBufferInheritanceInfo<Faulty<B>>,
+    ) {
+        let info = com::CommandBufferInheritanceInfo {
+            subpass: inheritance_info
+                .subpass
+                .as_ref()
+                .map(|subpass| pass::Subpass {
+                    index: subpass.index,
+                    main_pass: subpass.main_pass,
+                }),
+            framebuffer: inheritance_info.framebuffer,
+            occlusion_query_enable: inheritance_info.occlusion_query_enable,
+            occlusion_query_flags: inheritance_info.occlusion_query_flags,
+            pipeline_statistics: inheritance_info.pipeline_statistics,
+        };
+        self.raw.begin(flags, info);
+    }
+
+    unsafe fn finish(&mut self) {
+        self.raw.finish();
+    }
+
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources);
+    }
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        dependencies: memory::Dependencies,
+        barriers: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Faulty<B>>>,
+    {
+        let barriers = self::barriers(barriers);
+        self.raw.pipeline_barrier(stages, dependencies, barriers);
+    }
+
+    unsafe fn fill_buffer(&mut self, buffer: &B::Buffer, range: buffer::SubRange, data: u32) {
+        self.raw.fill_buffer(buffer, range, data);
+    }
+
+    unsafe fn update_buffer(&mut self, buffer: &B::Buffer, offset: buffer::Offset, data: &[u8]) {
+        self.raw.update_buffer(buffer, offset, data);
+    }
+
+    unsafe fn clear_image<T>(
+        &mut self,
+        image: &B::Image,
+        layout: image::Layout,
+        value: com::ClearValue,
+        subresource_ranges: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+        self.raw
+            .clear_image(image, layout, value, subresource_ranges);
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<com::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        self.raw.clear_attachments(clears, rects);
+    }
+
+    unsafe fn resolve_image<T>(
+        &mut self,
+        src: &B::Image,
+        src_layout: image::Layout,
+        dst: &B::Image,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageResolve>,
+    {
+        self.raw
+            .resolve_image(src, src_layout, dst, dst_layout, regions);
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        src: &B::Image,
+        src_layout: image::Layout,
+        dst: &B::Image,
+        dst_layout: image::Layout,
+        filter: image::Filter,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageBlit>,
+    {
+        self.raw
+            .blit_image(src, src_layout, dst, dst_layout, filter, regions);
+    }
+
+    unsafe fn bind_index_buffer(&mut self, view: buffer::IndexBufferView<Faulty<B>>) {
+        self.raw.bind_index_buffer(buffer::IndexBufferView {
+            buffer: view.buffer,
+            range: view.range,
+            index_type: view.index_type,
+        });
+    }
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, first_binding: pso::BufferIndex, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<B::Buffer>,
+    {
+        self.raw.bind_vertex_buffers(first_binding, buffers);
+    }
+
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, first_binding: u32, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<B::Buffer>,
+    {
+        self.raw
+            .bind_transform_feedback_buffers(first_binding, buffers);
+    }
+
+    unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        self.raw.set_viewports(first_viewport, viewports);
+    }
+
+    unsafe fn set_scissors<T>(&mut self, first_scissor: u32, rects: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        self.raw.set_scissors(first_scissor, rects);
+    }
+
+    unsafe fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.raw.set_stencil_reference(faces, value);
+    }
+
+    unsafe fn set_stencil_read_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.raw.set_stencil_read_mask(faces, value);
+    }
+
+    unsafe fn set_stencil_write_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.raw.set_stencil_write_mask(faces, value);
+    }
+
+    unsafe fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.raw.set_blend_constants(color);
+    }
+
+    unsafe fn set_depth_bounds(&mut self, bounds: Range<f32>) {
+        self.raw.set_depth_bounds(bounds);
+    }
+
+    unsafe fn set_line_width(&mut self, width: f32) {
+        self.raw.set_line_width(width);
+    }
+
+    unsafe fn set_depth_bias(&mut self, depth_bias: pso::DepthBias) {
+        self.raw.set_depth_bias(depth_bias);
+    }
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        render_pass: &B::RenderPass,
+        framebuffer: &B::Framebuffer,
+        render_area: pso::Rect,
+        clear_values: T,
+        first_subpass: com::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ClearValue>,
+    {
+        self.raw.begin_render_pass(
+            render_pass,
+            framebuffer,
+            render_area,
+            clear_values,
+            first_subpass,
+        );
+    }
+
+    unsafe fn next_subpass(&mut self, contents: com::SubpassContents) {
+        self.raw.next_subpass(contents);
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        self.raw.end_render_pass();
+    }
+
+    unsafe fn bind_graphics_pipeline(&mut self, pipeline: &B::GraphicsPipeline) {
+        self.raw.bind_graphics_pipeline(pipeline);
+    }
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<B::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        self.raw
+            .bind_graphics_descriptor_sets(layout, first_set, sets, offsets);
+    }
+
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Faulty<B>>>,
+    {
+        self.raw
+            .push_graphics_descriptor_set(layout, set, push_writes(writes));
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, pipeline: &B::ComputePipeline) {
+        self.raw.bind_compute_pipeline(pipeline);
+    }
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<B::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        self.raw
+            .bind_compute_descriptor_sets(layout, first_set, sets, offsets);
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Faulty<B>>>,
+    {
+        self.raw
+            .push_compute_descriptor_set(layout, set, push_writes(writes));
+    }
+
+    unsafe fn dispatch(&mut self, count: WorkGroupCount) {
+        self.raw.dispatch(count);
+    }
+
+    unsafe fn dispatch_indirect(&mut self, buffer: &B::Buffer, offset: buffer::Offset) {
+        self.raw.dispatch_indirect(buffer, offset);
+    }
+
+    unsafe fn copy_buffer<T>(&mut self, src: &B::Buffer, dst: &B::Buffer, regions: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferCopy>,
+    {
+        self.raw.copy_buffer(src, dst, regions);
+    }
+
+    unsafe fn copy_image<T>(
+        &mut self,
+        src: &B::Image,
+        src_layout: image::Layout,
+        dst: &B::Image,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageCopy>,
+    {
+        self.raw
+            .copy_image(src, src_layout, dst, dst_layout, regions);
+    }
+
+    unsafe fn copy_buffer_to_image<T>(
+        &mut self,
+        src: &B::Buffer,
+        dst: &B::Image,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        self.raw.copy_buffer_to_image(src, dst, dst_layout, regions);
+    }
+
+    unsafe fn copy_image_to_buffer<T>(
+        &mut self,
+        src: &B::Image,
+        src_layout: image::Layout,
+        dst: &B::Buffer,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        self.raw.copy_image_to_buffer(src, src_layout, dst, regions);
+    }
+
+    unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
+        self.raw.draw(vertices, instances);
+    }
+
+    unsafe fn draw_indexed(
+        &mut self,
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    ) {
+        self.raw.draw_indexed(indices, base_vertex, instances);
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw.draw_indirect(buffer, offset, draw_count, stride);
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw
+            .draw_indexed_indirect(buffer, offset, draw_count, stride);
+    }
+
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &B::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw.draw_indirect_count(
+            buffer,
+            offset,
+            count_buffer,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        );
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &B::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw.draw_indexed_indirect_count(
+            buffer,
+            offset,
+            count_buffer,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        );
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        self.raw
+            .begin_conditional_rendering(buffer, offset, inverted);
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &B::QueryPool,
+        id: query::Id,
+        inverted: bool,
+    ) {
+        self.raw
+            .begin_conditional_rendering_query(pool, id, inverted);
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.raw.end_conditional_rendering();
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        self.raw.begin_transform_feedback();
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        self.raw.end_transform_feedback();
+    }
+
+    unsafe fn set_event(&mut self, event: &B::Event, stages: pso::PipelineStage) {
+        self.raw.set_event(event, stages);
+    }
+
+    unsafe fn reset_event(&mut self, event: &B::Event, stages: pso::PipelineStage) {
+        self.raw.reset_event(event, stages);
+    }
+
+    unsafe fn wait_events<'a, I, J>(
+        &mut self,
+        events: I,
+        stages: Range<pso::PipelineStage>,
+        barriers: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<B::Event>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Faulty<B>>>,
+    {
+        let barriers = self::barriers(barriers);
+        self.raw.wait_events(events, stages, barriers);
+    }
+
+    unsafe fn begin_query(&mut self, query: query::Query<Faulty<B>>, flags: query::ControlFlags) {
+        self.raw.begin_query(
+            query::Query {
+                pool: query.pool,
+                id: query.id,
+            },
+            flags,
+        );
+    }
+
+    unsafe fn end_query(&mut self, query: query::Query<Faulty<B>>) {
+        self.raw.end_query(query::Query {
+            pool: query.pool,
+            id: query.id,
+        });
+    }
+
+    unsafe fn reset_query_pool(&mut self, pool: &B::QueryPool, queries: Range<query::Id>) {
+        self.raw.reset_query_pool(pool, queries);
+    }
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: Range<query::Id>,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) {
+        self.raw
+            .copy_query_pool_results(pool, queries, buffer, offset, stride, flags);
+    }
+
+    unsafe fn write_timestamp(
+        &mut self,
+        stage: pso::PipelineStage,
+        query: query::Query<Faulty<B>>,
+    ) {
+        self.raw.write_timestamp(
+            stage,
+            query::Query {
+                pool: query.pool,
+                id: query.id,
+            },
+        );
+    }
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        layout: &B::PipelineLayout,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.raw
+            .push_graphics_constants(layout, stages, offset, constants);
+    }
+
+    unsafe fn push_compute_constants(
+        &mut self,
+        layout: &B::PipelineLayout,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.raw.push_compute_constants(layout, offset, constants);
+    }
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, cmd_buffers: I)
+    where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let cmd_buffers = cmd_buffers
+            .into_iter()
+            .map(|cmd_buffer| &cmd_buffer.borrow().raw)
+            .collect::<Vec<_>>();
+        self.raw.execute_commands(cmd_buffers.iter());
+    }
+
+    unsafe fn insert_debug_marker(&mut self, name: &str, color: u32) {
+        self.raw.insert_debug_marker(name, color);
+    }
+
+    unsafe fn begin_debug_marker(&mut self, name: &str, color: u32) {
+        self.raw.begin_debug_marker(name, color);
+    }
+
+    unsafe fn end_debug_marker(&mut self) {
+        self.raw.end_debug_marker();
+    }
+}
@@ -0,0 +1,918 @@
+use crate::{
+    command::CommandBuffer,
+    window::{Surface, Swapchain},
+    Call,
+    CommandPool,
+    DescriptorPool,
+    Faulty,
+    Injector,
+};
+use hal::{
+    buffer,
+    device,
+    external,
+    format,
+    image,
+    memory,
+    pass,
+    pool::CommandPoolCreateFlags,
+    pso,
+    query,
+    queue::{QueueFamilyId, SemaphoreValue},
+    window,
+    MemoryTypeId,
+};
+use std::borrow::Borrow;
+use std::iter;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Fault injection device, wrapping a device of the backend `B`.
+#[derive(Debug)]
+pub struct Device<B: hal::Backend> {
+    pub(crate) raw: B::Device,
+    injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> Device<B> {
+    pub(crate) fn new(raw: B::Device, injector: Arc<Injector>) -> Self {
+        Device { raw, injector }
+    }
+
+    /// Returns the wrapped device.
+    pub fn raw(&self) -> &B::Device {
+        &self.raw
+    }
+
+    /// Returns the injector of the instance the device was created from.
+    pub fn injector(&self) -> &Arc<Injector> {
+        &self.injector
+    }
+}
+
+fn entry_point<'a, B: hal::Backend>(
+    entry: &pso::EntryPoint<'a, Faulty<B>>,
+) -> pso::EntryPoint<'a, B> {
+    pso::EntryPoint {
+        entry: entry.entry,
+        module: entry.module,
+        specialization: entry.specialization.clone(),
+    }
+}
+
+// `BasePipeline` is only `Copy` if the pipeline is.
+#[allow(clippy::needless_match)]
+fn base_pipeline<'a, P>(parent: &pso::BasePipeline<'a, P>) -> pso::BasePipeline<'a, P> {
+    match *parent {
+        pso::BasePipeline::Pipeline(pipeline) => pso::BasePipeline::Pipeline(pipeline),
+        pso::BasePipeline::Index(index) => pso::BasePipeline::Index(index),
+        pso::BasePipeline::None => pso::BasePipeline::None,
+    }
+}
+
+/// Unwraps a descriptor of the wrapped backend.
+pub(crate) fn unwrap_descriptor<'a, B: hal::Backend>(
+    descriptor: &pso::Descriptor<'a, Faulty<B>>,
+) -> pso::Descriptor<'a, B> {
+    match *descriptor {
+        pso::Descriptor::Sampler(sampler) => pso::Descriptor::Sampler(sampler),
+        pso::Descriptor::Image(view, layout) => pso::Descriptor::Image(view, layout),
+        pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+            pso::Descriptor::CombinedImageSampler(view, layout, sampler)
+        }
+        pso::Descriptor::Buffer(buffer, ref range) => {
+            pso::Descriptor::Buffer(buffer, range.clone())
+        }
+        pso::Descriptor::TexelBuffer(view) => pso::Descriptor::TexelBuffer(view),
+    }
+}
+
+impl<B: hal::Backend> device::Device<Faulty<B>> for Device<B> {
+    unsafe fn create_command_pool(
+        &self,
+        family: QueueFamilyId,
+        create_flags: CommandPoolCreateFlags,
+    ) -> Result<CommandPool<B>, device::OutOfMemory> {
+        self.injector.inject(Call::CreateCommandPool)?;
+        let raw = self.raw.create_command_pool(family, create_flags)?;
+        Ok(CommandPool { raw })
+    }
+
+    unsafe fn destroy_command_pool(&self, pool: CommandPool<B>) {
+        self.raw.destroy_command_pool(pool.raw)
+    }
+
+    unsafe fn allocate_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+    ) -> Result<B::Memory, device::AllocationError> {
+        self.injector.inject(Call::AllocateMemory)?;
+        self.raw.allocate_memory(memory_type, size)
+    }
+
+    unsafe fn free_memory(&self, memory: B::Memory) {
+        self.raw.free_memory(memory)
+    }
+
+    unsafe fn allocate_exportable_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<B::Memory, device::AllocationError> {
+        self.injector.inject(Call::AllocateExportableMemory)?;
+        self.raw
+            .allocate_exportable_memory(memory_type, size, handle_types)
+    }
+
+    unsafe fn export_memory(
+        &self,
+        memory: &B::Memory,
+        handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.injector.inject(Call::ExportMemory)?;
+        self.raw.export_memory(memory, handle_type)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        self.injector.inject(Call::GetMemoryFdTypeMask)?;
+        self.raw.get_memory_fd_type_mask(handle_type, fd)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<B::Memory, external::ImportError> {
+        self.injector.inject(Call::ImportMemory)?;
+        self.raw.import_memory(memory_type, size, handle_type, fd)
+    }
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        subpasses: IS,
+        dependencies: ID,
+        correlation_masks: &[pass::ViewMask],
+    ) -> Result<B::RenderPass, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        self.injector.inject(Call::CreateRenderPass)?;
+        self.raw
+            .create_render_pass(attachments, subpasses, dependencies, correlation_masks)
+    }
+
+    unsafe fn destroy_render_pass(&self, rp: B::RenderPass) {
+        self.raw.destroy_render_pass(rp)
+    }
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        set_layouts: IS,
+        push_constant: IR,
+    ) -> Result<B::PipelineLayout, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<B::DescriptorSetLayout>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        self.injector.inject(Call::CreatePipelineLayout)?;
+        self.raw.create_pipeline_layout(set_layouts, push_constant)
+    }
+
+    unsafe fn destroy_pipeline_layout(&self, layout: B::PipelineLayout) {
+        self.raw.destroy_pipeline_layout(layout)
+    }
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        data: Option<&[u8]>,
+    ) -> Result<B::PipelineCache, device::OutOfMemory> {
+        self.injector.inject(Call::CreatePipelineCache)?;
+        self.raw.create_pipeline_cache(data)
+    }
+
+    unsafe fn get_pipeline_cache_data(
+        &self,
+        cache: &B::PipelineCache,
+    ) -> Result<Vec<u8>, device::OutOfMemory> {
+        self.injector.inject(Call::GetPipelineCacheData)?;
+        self.raw.get_pipeline_cache_data(cache)
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        target: &B::PipelineCache,
+        sources: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::PipelineCache>,
+    {
+        self.injector.inject(Call::MergePipelineCaches)?;
+        self.raw.merge_pipeline_caches(target, sources)
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, cache: B::PipelineCache) {
+        self.raw.destroy_pipeline_cache(cache)
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        desc: &pso::GraphicsPipelineDesc<'a, Faulty<B>>,
+        cache: Option<&B::PipelineCache>,
+    ) -> Result<B::GraphicsPipeline, pso::CreationError> {
+        self.create_graphics_pipelines(iter::once(desc), cache)
+            .remove(0)
+    }
+
+    /// Each pipeline counts as a call to `CreateGraphicsPipeline`, only the ones which
+    /// don't fail are created.
+    unsafe fn create_graphics_pipelines<'a, I>(
+        &self,
+        descs: I,
+        cache: Option<&B::PipelineCache>,
+    ) -> Vec<Result<B::GraphicsPipeline, pso::CreationError>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::GraphicsPipelineDesc<'a, Faulty<B>>>,
+    {
+        let descs = descs.into_iter().collect::<Vec<_>>();
+        let results = descs
+            .iter()
+            .map(|_| {
+                self.injector
+                    .inject(Call::CreateGraphicsPipeline)
+                    .map_err(pso::CreationError::from)
+            })
+            .collect::<Vec<_>>();
+        let raw_descs = descs
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.is_ok())
+            .map(|(desc, _)| {
+                let desc = desc.borrow();
+                let shaders = &desc.shaders;
+                pso::GraphicsPipelineDesc {
+                    shaders: pso::GraphicsShaderSet {
+                        vertex: entry_point(&shaders.vertex),
+                        hull: shaders.hull.as_ref().map(entry_point),
+                        domain: shaders.domain.as_ref().map(entry_point),
+                        geometry: shaders.geometry.as_ref().map(entry_point),
+                        fragment: shaders.fragment.as_ref().map(entry_point),
+                    },
+                    rasterizer: desc.rasterizer,
+                    vertex_buffers: desc.vertex_buffers.clone(),
+                    attributes: desc.attributes.clone(),
+                    input_assembler: desc.input_assembler.clone(),
+                    blender: desc.blender.clone(),
+                    depth_stencil: desc.depth_stencil,
+                    multisampling: desc.multisampling.clone(),
+                    baked_states: desc.baked_states.clone(),
+                    layout: desc.layout,
+                    subpass: pass::Subpass {
+                        index: desc.subpass.index,
+                        main_pass: desc.subpass.main_pass,
+                    },
+                    flags: desc.flags,
+                    parent: base_pipeline(&desc.parent),
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut pipelines = self
+            .raw
+            .create_graphics_pipelines(&raw_descs, cache)
+            .into_iter();
+        results
+            .into_iter()
+            .map(|result| result.and_then(|()| pipelines.next().unwrap()))
+            .collect()
+    }
+
+    unsafe fn destroy_graphics_pipeline(&self, pipeline: B::GraphicsPipeline) {
+        self.raw.destroy_graphics_pipeline(pipeline)
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        desc: &pso::ComputePipelineDesc<'a, Faulty<B>>,
+        cache: Option<&B::PipelineCache>,
+    ) -> Result<B::ComputePipeline, pso::CreationError> {
+        self.create_compute_pipelines(iter::once(desc), cache)
+            .remove(0)
+    }
+
+    /// Each pipeline counts as a call to `CreateComputePipeline`, only the ones which
+    /// don't fail are created.
+    unsafe fn create_compute_pipelines<'a, I>(
+        &self,
+        descs: I,
+        cache: Option<&B::PipelineCache>,
+    ) -> Vec<Result<B::ComputePipeline, pso::CreationError>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::ComputePipelineDesc<'a, Faulty<B>>>,
+    {
+        let descs = descs.into_iter().collect::<Vec<_>>();
+        let results = descs
+            .iter()
+            .map(|_| {
+                self.injector
+                    .inject(Call::CreateComputePipeline)
+                    .map_err(pso::CreationError::from)
+            })
+            .collect::<Vec<_>>();
+        let raw_descs = descs
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.is_ok())
+            .map(|(desc, _)| {
+                let desc = desc.borrow();
+                pso::ComputePipelineDesc {
+                    shader: entry_point(&desc.shader),
+                    layout: desc.layout,
+                    flags: desc.flags,
+                    parent: base_pipeline(&desc.parent),
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut pipelines = self
+            .raw
+            .create_compute_pipelines(&raw_descs, cache)
+            .into_iter();
+        results
+            .into_iter()
+            .map(|result| result.and_then(|()| pipelines.next().unwrap()))
+            .collect()
+    }
+
+    unsafe fn destroy_compute_pipeline(&self, pipeline: B::ComputePipeline) {
+        self.raw.destroy_compute_pipeline(pipeline)
+    }
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        pass: &B::RenderPass,
+        attachments: I,
+        extent: image::Extent,
+    ) -> Result<B::Framebuffer, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::ImageView>,
+    {
+        self.injector.inject(Call::CreateFramebuffer)?;
+        self.raw.create_framebuffer(pass, attachments, extent)
+    }
+
+    unsafe fn destroy_framebuffer(&self, buf: B::Framebuffer) {
+        self.raw.destroy_framebuffer(buf)
+    }
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv_data: &[u32],
+    ) -> Result<B::ShaderModule, device::ShaderError> {
+        self.injector.inject(Call::CreateShaderModule)?;
+        self.raw.create_shader_module(spirv_data)
+    }
+
+    unsafe fn destroy_shader_module(&self, shader: B::ShaderModule) {
+        self.raw.destroy_shader_module(shader)
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        sparse: memory::SparseFlags,
+    ) -> Result<B::Buffer, buffer::CreationError> {
+        self.injector.inject(Call::CreateBuffer)?;
+        self.raw.create_buffer(size, usage, sparse)
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<B::Buffer, buffer::CreationError> {
+        self.injector.inject(Call::CreateExternalBuffer)?;
+        self.raw.create_external_buffer(size, usage, handle_types)
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &B::Buffer) -> memory::Requirements {
+        self.raw.get_buffer_requirements(buffer)
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &B::Memory,
+        offset: u64,
+        buffer: &mut B::Buffer,
+    ) -> Result<(), device::BindError> {
+        self.injector.inject(Call::BindBufferMemory)?;
+        self.raw.bind_buffer_memory(memory, offset, buffer)
+    }
+
+    unsafe fn destroy_buffer(&self, buffer: B::Buffer) {
+        self.raw.destroy_buffer(buffer)
+    }
+
+    unsafe fn create_buffer_view(
+        &self,
+        buffer: &B::Buffer,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    ) -> Result<B::BufferView, buffer::ViewCreationError> {
+        self.injector.inject(Call::CreateBufferView)?;
+        self.raw.create_buffer_view(buffer, format, range)
+    }
+
+    unsafe fn destroy_buffer_view(&self, view: B::BufferView) {
+        self.raw.destroy_buffer_view(view)
+    }
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
+    ) -> Result<B::Image, image::CreationError> {
+        self.injector.inject(Call::CreateImage)?;
+        self.raw
+            .create_image(kind, mip_levels, format, tiling, usage, view_caps, sparse)
+    }
+
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<B::Image, image::CreationError> {
+        self.injector.inject(Call::CreateExternalImage)?;
+        self.raw.create_external_image(
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            handle_types,
+        )
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        modifier: external::DrmFormatModifier,
+    ) -> Result<B::Image, image::CreationError> {
+        self.injector.inject(Call::CreateDrmFormatModifierImage)?;
+        self.raw
+            .create_drm_format_modifier_image(kind, format, usage, view_caps, modifier)
+    }
+
+    unsafe fn get_image_requirements(&self, image: &B::Image) -> memory::Requirements {
+        self.raw.get_image_requirements(image)
+    }
+
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        image: &B::Image,
+    ) -> Vec<image::SparseRequirements> {
+        self.raw.get_image_sparse_requirements(image)
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &B::Image,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        self.raw.get_image_subresource_footprint(image, subresource)
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &B::Memory,
+        offset: u64,
+        image: &mut B::Image,
+    ) -> Result<(), device::BindError> {
+        self.injector.inject(Call::BindImageMemory)?;
+        self.raw.bind_image_memory(memory, offset, image)
+    }
+
+    unsafe fn destroy_image(&self, image: B::Image) {
+        self.raw.destroy_image(image)
+    }
+
+    unsafe fn create_image_view(
+        &self,
+        image: &B::Image,
+        view_kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<B::ImageView, image::ViewCreationError> {
+        self.injector.inject(Call::CreateImageView)?;
+        self.raw
+            .create_image_view(image, view_kind, format, swizzle, range)
+    }
+
+    unsafe fn destroy_image_view(&self, view: B::ImageView) {
+        self.raw.destroy_image_view(view)
+    }
+
+    unsafe fn create_sampler(
+        &self,
+        desc: &image::SamplerDesc,
+    ) -> Result<B::Sampler, device::AllocationError> {
+        self.injector.inject(Call::CreateSampler)?;
+        self.raw.create_sampler(desc)
+    }
+
+    unsafe fn destroy_sampler(&self, sampler: B::Sampler) {
+        self.raw.destroy_sampler(sampler)
+    }
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        max_sets: usize,
+        descriptor_ranges: I,
+        flags: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool<B>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        self.injector.inject(Call::CreateDescriptorPool)?;
+        let raw = self
+            .raw
+            .create_descriptor_pool(max_sets, descriptor_ranges, flags)?;
+        Ok(DescriptorPool {
+            raw,
+            injector: Arc::clone(&self.injector),
+        })
+    }
+
+    unsafe fn destroy_descriptor_pool(&self, pool: DescriptorPool<B>) {
+        self.raw.destroy_descriptor_pool(pool.raw)
+    }
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        immutable_samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
+    ) -> Result<B::DescriptorSetLayout, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<B::Sampler>,
+    {
+        self.injector.inject(Call::CreateDescriptorSetLayout)?;
+        self.raw
+            .create_descriptor_set_layout(bindings, immutable_samplers, flags)
+    }
+
+    unsafe fn destroy_descriptor_set_layout(&self, layout: B::DescriptorSetLayout) {
+        self.raw.destroy_descriptor_set_layout(layout)
+    }
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, write_iter: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Faulty<B>, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Faulty<B>>>,
+    {
+        let writes = write_iter
+            .into_iter()
+            .map(|write| pso::DescriptorSetWrite {
+                set: write.set,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                descriptors: write
+                    .descriptors
+                    .into_iter()
+                    .map(|descriptor| unwrap_descriptor(descriptor.borrow()))
+                    .collect::<Vec<_>>(),
+            })
+            .collect::<Vec<_>>();
+        self.raw.write_descriptor_sets(writes)
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, copy_iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Faulty<B>>>,
+    {
+        let copies = copy_iter
+            .into_iter()
+            .map(|copy| {
+                let copy = copy.borrow();
+                pso::DescriptorSetCopy {
+                    src_set: copy.src_set,
+                    src_binding: copy.src_binding,
+                    src_array_offset: copy.src_array_offset,
+                    dst_set: copy.dst_set,
+                    dst_binding: copy.dst_binding,
+                    dst_array_offset: copy.dst_array_offset,
+                    count: copy.count,
+                }
+            })
+            .collect::<Vec<_>>();
+        self.raw.copy_descriptor_sets(copies)
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &B::Memory,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        self.injector.inject(Call::MapMemory)?;
+        self.raw.map_memory(memory, segment)
+    }
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, ranges: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a B::Memory, memory::Segment)>,
+    {
+        self.injector.inject(Call::FlushMappedMemoryRanges)?;
+        self.raw.flush_mapped_memory_ranges(ranges)
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(
+        &self,
+        ranges: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a B::Memory, memory::Segment)>,
+    {
+        self.injector.inject(Call::InvalidateMappedMemoryRanges)?;
+        self.raw.invalidate_mapped_memory_ranges(ranges)
+    }
+
+    unsafe fn unmap_memory(&self, memory: &B::Memory) {
+        self.raw.unmap_memory(memory)
+    }
+
+    fn create_semaphore(&self) -> Result<B::Semaphore, device::OutOfMemory> {
+        self.injector.inject(Call::CreateSemaphore)?;
+        self.raw.create_semaphore()
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: SemaphoreValue,
+    ) -> Result<B::Semaphore, device::OutOfMemory> {
+        self.injector.inject(Call::CreateTimelineSemaphore)?;
+        self.raw.create_timeline_semaphore(initial_value)
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &B::Semaphore,
+    ) -> Result<SemaphoreValue, device::DeviceLost> {
+        self.injector.inject(Call::GetSemaphoreValue)?;
+        self.raw.get_semaphore_value(semaphore)
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &B::Semaphore,
+        value: SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.injector.inject(Call::WaitForSemaphoreValue)?;
+        self.raw
+            .wait_for_semaphore_value(semaphore, value, timeout_ns)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        value: SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        self.injector.inject(Call::SignalSemaphore)?;
+        self.raw.signal_semaphore(semaphore, value)
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<B::Semaphore, device::OutOfMemory> {
+        self.injector.inject(Call::CreateExportableSemaphore)?;
+        self.raw.create_exportable_semaphore(handle_types)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.injector.inject(Call::ExportSemaphore)?;
+        self.raw.export_semaphore(semaphore, handle_type)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+        fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        self.injector.inject(Call::ImportSemaphore)?;
+        self.raw.import_semaphore(semaphore, handle_type, fd)
+    }
+
+    unsafe fn destroy_semaphore(&self, semaphore: B::Semaphore) {
+        self.raw.destroy_semaphore(semaphore)
+    }
+
+    fn create_fence(&self, signaled: bool) -> Result<B::Fence, device::OutOfMemory> {
+        self.injector.inject(Call::CreateFence)?;
+        self.raw.create_fence(signaled)
+    }
+
+    unsafe fn reset_fences<I>(&self, fences: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::Fence>,
+    {
+        self.injector.inject(Call::ResetFences)?;
+        self.raw.reset_fences(fences)
+    }
+
+    unsafe fn wait_for_fences<I>(
+        &self,
+        fences: I,
+        wait: device::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::Fence>,
+    {
+        self.injector.inject(Call::WaitForFences)?;
+        self.raw.wait_for_fences(fences, wait, timeout_ns)
+    }
+
+    unsafe fn get_fence_status(&self, fence: &B::Fence) -> Result<bool, device::DeviceLost> {
+        self.injector.inject(Call::GetFenceStatus)?;
+        self.raw.get_fence_status(fence)
+    }
+
+    unsafe fn destroy_fence(&self, fence: B::Fence) {
+        self.raw.destroy_fence(fence)
+    }
+
+    fn create_event(&self) -> Result<B::Event, device::OutOfMemory> {
+        self.injector.inject(Call::CreateEvent)?;
+        self.raw.create_event()
+    }
+
+    unsafe fn destroy_event(&self, event: B::Event) {
+        self.raw.destroy_event(event)
+    }
+
+    unsafe fn get_event_status(&self, event: &B::Event) -> Result<bool, device::OomOrDeviceLost> {
+        self.injector.inject(Call::GetEventStatus)?;
+        self.raw.get_event_status(event)
+    }
+
+    unsafe fn set_event(&self, event: &B::Event) -> Result<(), device::OutOfMemory> {
+        self.injector.inject(Call::SetEvent)?;
+        self.raw.set_event(event)
+    }
+
+    unsafe fn reset_event(&self, event: &B::Event) -> Result<(), device::OutOfMemory> {
+        self.injector.inject(Call::ResetEvent)?;
+        self.raw.reset_event(event)
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<B::QueryPool, query::CreationError> {
+        self.injector.inject(Call::CreateQueryPool)?;
+        self.raw.create_query_pool(ty, count)
+    }
+
+    unsafe fn destroy_query_pool(&self, pool: B::QueryPool) {
+        self.raw.destroy_query_pool(pool)
+    }
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &B::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.injector.inject(Call::GetQueryPoolResults)?;
+        self.raw
+            .get_query_pool_results(pool, queries, data, stride, flags)
+    }
+
+    unsafe fn create_swapchain(
+        &self,
+        surface: &mut Surface<B>,
+        config: window::SwapchainConfig,
+        old_swapchain: Option<Swapchain<B>>,
+    ) -> Result<(Swapchain<B>, Vec<B::Image>), window::CreationError> {
+        let old_swapchain = old_swapchain.map(|swapchain| swapchain.raw);
+        if let Err(fault) = self.injector.inject(Call::CreateSwapchain) {
+            // The old swapchain is retired by the creation, even if it fails.
+            if let Some(old_swapchain) = old_swapchain {
+                self.raw.destroy_swapchain(old_swapchain);
+            }
+            return Err(fault.into());
+        }
+        let (raw, images) = self
+            .raw
+            .create_swapchain(&mut surface.raw, config, old_swapchain)?;
+        let swapchain = Swapchain {
+            raw,
+            injector: Arc::clone(&self.injector),
+        };
+        Ok((swapchain, images))
+    }
+
+    unsafe fn destroy_swapchain(&self, swapchain: Swapchain<B>) {
+        self.raw.destroy_swapchain(swapchain.raw)
+    }
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        self.injector.inject(Call::WaitIdle)?;
+        self.raw.wait_idle()
+    }
+
+    unsafe fn set_image_name(&self, image: &mut B::Image, name: &str) {
+        self.raw.set_image_name(image, name)
+    }
+
+    unsafe fn set_buffer_name(&self, buffer: &mut B::Buffer, name: &str) {
+        self.raw.set_buffer_name(buffer, name)
+    }
+
+    unsafe fn set_command_buffer_name(&self, command_buffer: &mut CommandBuffer<B>, name: &str) {
+        self.raw
+            .set_command_buffer_name(&mut command_buffer.raw, name)
+    }
+
+    unsafe fn set_semaphore_name(&self, semaphore: &mut B::Semaphore, name: &str) {
+        self.raw.set_semaphore_name(semaphore, name)
+    }
+
+    unsafe fn set_fence_name(&self, fence: &mut B::Fence, name: &str) {
+        self.raw.set_fence_name(fence, name)
+    }
+
+    unsafe fn set_framebuffer_name(&self, framebuffer: &mut B::Framebuffer, name: &str) {
+        self.raw.set_framebuffer_name(framebuffer, name)
+    }
+
+    unsafe fn set_render_pass_name(&self, render_pass: &mut B::RenderPass, name: &str) {
+        self.raw.set_render_pass_name(render_pass, name)
+    }
+
+    unsafe fn set_descriptor_set_name(&self, descriptor_set: &mut B::DescriptorSet, name: &str) {
+        self.raw.set_descriptor_set_name(descriptor_set, name)
+    }
+
+    unsafe fn set_descriptor_set_layout_name(
+        &self,
+        descriptor_set_layout: &mut B::DescriptorSetLayout,
+        name: &str,
+    ) {
+        self.raw
+            .set_descriptor_set_layout_name(descriptor_set_layout, name)
+    }
+}
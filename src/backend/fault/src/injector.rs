@@ -0,0 +1,387 @@
+use hal::{
+    buffer,
+    device::{self, DeviceLost, OomOrDeviceLost, OutOfMemory, SurfaceLost},
+    external,
+    image,
+    pso,
+    query,
+    window,
+};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Error returned in place of the result of a call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// `OutOfMemory::Host`.
+    OutOfHostMemory,
+    /// `OutOfMemory::Device`.
+    OutOfDeviceMemory,
+    /// `DeviceLost`, or the `DeviceLost` variant of the error of the call.
+    DeviceLost,
+    /// The `SurfaceLost` variant of the error of the call.
+    SurfaceLost,
+    /// The `OutOfDate` variant of the error of the call.
+    OutOfDate,
+}
+
+impl Fault {
+    fn out_of_memory(self) -> OutOfMemory {
+        match self {
+            Fault::OutOfHostMemory => OutOfMemory::Host,
+            Fault::OutOfDeviceMemory => OutOfMemory::Device,
+            _ => unreachable!("{:?} isn't an out of memory error", self),
+        }
+    }
+}
+
+/// Fallible call which can be failed by the injector.
+///
+/// Calls of the same name on different objects share a variant, e.g. `WaitIdle` covers
+/// both `Device::wait_idle` and `CommandQueue::wait_idle`, and `AcquireImage` both
+/// `PresentationSurface::acquire_image` and `Swapchain::acquire_image`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Call {
+    Open,
+    AllocateMemory,
+    AllocateExportableMemory,
+    ExportMemory,
+    GetMemoryFdTypeMask,
+    ImportMemory,
+    CreateCommandPool,
+    CreateRenderPass,
+    CreatePipelineLayout,
+    CreatePipelineCache,
+    GetPipelineCacheData,
+    MergePipelineCaches,
+    CreateGraphicsPipeline,
+    CreateComputePipeline,
+    CreateFramebuffer,
+    CreateShaderModule,
+    CreateBuffer,
+    CreateExternalBuffer,
+    BindBufferMemory,
+    CreateBufferView,
+    CreateImage,
+    CreateExternalImage,
+    CreateDrmFormatModifierImage,
+    BindImageMemory,
+    CreateImageView,
+    CreateSampler,
+    CreateDescriptorPool,
+    CreateDescriptorSetLayout,
+    AllocateDescriptorSets,
+    MapMemory,
+    FlushMappedMemoryRanges,
+    InvalidateMappedMemoryRanges,
+    CreateSemaphore,
+    CreateTimelineSemaphore,
+    GetSemaphoreValue,
+    WaitForSemaphoreValue,
+    SignalSemaphore,
+    CreateExportableSemaphore,
+    ExportSemaphore,
+    ImportSemaphore,
+    CreateFence,
+    ResetFences,
+    WaitForFences,
+    GetFenceStatus,
+    CreateEvent,
+    GetEventStatus,
+    SetEvent,
+    ResetEvent,
+    CreateQueryPool,
+    GetQueryPoolResults,
+    CreateSwapchain,
+    ConfigureSwapchain,
+    AcquireImage,
+    Present,
+    WaitIdle,
+}
+
+impl Call {
+    /// Returns true if the error type of the call can represent the fault.
+    pub fn can_fail_with(self, fault: Fault) -> bool {
+        match fault {
+            Fault::OutOfHostMemory | Fault::OutOfDeviceMemory => {
+                !matches!(self, Call::GetSemaphoreValue | Call::GetFenceStatus)
+            }
+            Fault::DeviceLost => matches!(
+                self,
+                Call::Open
+                    | Call::GetSemaphoreValue
+                    | Call::WaitForSemaphoreValue
+                    | Call::WaitForFences
+                    | Call::GetFenceStatus
+                    | Call::GetEventStatus
+                    | Call::GetQueryPoolResults
+                    | Call::CreateSwapchain
+                    | Call::ConfigureSwapchain
+                    | Call::AcquireImage
+                    | Call::Present
+            ),
+            Fault::SurfaceLost => matches!(
+                self,
+                Call::CreateSwapchain
+                    | Call::ConfigureSwapchain
+                    | Call::AcquireImage
+                    | Call::Present
+            ),
+            Fault::OutOfDate => matches!(self, Call::AcquireImage | Call::Present),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Trigger {
+    /// Number of calls to let through before failing one.
+    Countdown(usize),
+    /// Probability of failing each call.
+    Random(f32),
+}
+
+#[derive(Debug)]
+struct Rule {
+    call: Call,
+    fault: Fault,
+    trigger: Trigger,
+}
+
+#[derive(Debug)]
+struct State {
+    rules: Vec<Rule>,
+    calls: HashMap<Call, usize>,
+    rng: u64,
+}
+
+/// Returns a uniformly distributed number in `[0, 1)`, using SplitMix64.
+fn random(state: &mut u64) -> f32 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Decides which calls fail, shared by all the objects of an instance.
+///
+/// Rules can be added and removed at any time, which affects the calls made afterwards
+/// on any thread.
+#[derive(Debug)]
+pub struct Injector {
+    state: Mutex<State>,
+}
+
+impl Default for Injector {
+    fn default() -> Self {
+        Injector::new(0)
+    }
+}
+
+impl Injector {
+    /// Create an injector without any rule, seeding the random failures with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Injector {
+            state: Mutex::new(State {
+                rules: Vec::new(),
+                calls: HashMap::new(),
+                rng: seed,
+            }),
+        }
+    }
+
+    fn add_rule(&self, call: Call, fault: Fault, trigger: Trigger) {
+        assert!(
+            call.can_fail_with(fault),
+            "{:?} can't fail with {:?}",
+            call,
+            fault
+        );
+        self.state.lock().unwrap().rules.push(Rule {
+            call,
+            fault,
+            trigger,
+        });
+    }
+
+    /// Fail the `n`th call to `call` made from now on with `fault`, counting from 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `call` can't fail with `fault`, see `Call::can_fail_with`.
+    pub fn fail_nth(&self, call: Call, n: usize, fault: Fault) {
+        self.add_rule(call, fault, Trigger::Countdown(n));
+    }
+
+    /// Fail each call to `call` with `fault` with the given probability, until `clear`.
+    ///
+    /// The failures are deterministic for a given seed and sequence of calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `call` can't fail with `fault`, see `Call::can_fail_with`.
+    pub fn fail_randomly(&self, call: Call, probability: f32, fault: Fault) {
+        self.add_rule(call, fault, Trigger::Random(probability));
+    }
+
+    /// Remove all the rules, letting every call through.
+    pub fn clear(&self) {
+        self.state.lock().unwrap().rules.clear();
+    }
+
+    /// Returns the number of calls to `call` made so far, including the failed ones.
+    pub fn calls(&self, call: Call) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .get(&call)
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Count a call, returning the fault it has to fail with if any.
+    pub(crate) fn inject(&self, call: Call) -> Result<(), Fault> {
+        let mut guard = self.state.lock().unwrap();
+        let State {
+            ref mut rules,
+            ref mut calls,
+            ref mut rng,
+        } = *guard;
+        *calls.entry(call).or_insert(0) += 1;
+
+        let mut fault = None;
+        rules.retain(|rule| {
+            if rule.call != call {
+                return true;
+            }
+            match rule.trigger {
+                Trigger::Countdown(0) => {
+                    fault = fault.or(Some(rule.fault));
+                    false
+                }
+                Trigger::Countdown(_) => true,
+                Trigger::Random(probability) => {
+                    if random(rng) < probability {
+                        fault = fault.or(Some(rule.fault));
+                    }
+                    true
+                }
+            }
+        });
+        for rule in rules.iter_mut().filter(|rule| rule.call == call) {
+            if let Trigger::Countdown(ref mut n) = rule.trigger {
+                *n -= 1;
+            }
+        }
+
+        match fault {
+            Some(fault) => {
+                info!("Failing {:?} with {:?}", call, fault);
+                Err(fault)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// The errors of the calls, converted from the faults allowed by `Call::can_fail_with`.
+
+macro_rules! from_out_of_memory {
+    ($($error:ty),*) => {
+        $(
+            impl From<Fault> for $error {
+                fn from(fault: Fault) -> Self {
+                    fault.out_of_memory().into()
+                }
+            }
+        )*
+    };
+}
+
+from_out_of_memory!(
+    OutOfMemory,
+    device::AllocationError,
+    device::MapError,
+    device::BindError,
+    device::ShaderError,
+    buffer::CreationError,
+    buffer::ViewCreationError,
+    external::ExportError,
+    external::ImportError,
+    image::CreationError,
+    pso::CreationError,
+    query::CreationError
+);
+
+impl From<Fault> for pso::AllocationError {
+    fn from(fault: Fault) -> Self {
+        pso::AllocationError::OutOfMemory(fault.out_of_memory())
+    }
+}
+
+impl From<Fault> for image::ViewCreationError {
+    fn from(fault: Fault) -> Self {
+        image::ViewCreationError::OutOfMemory(fault.out_of_memory())
+    }
+}
+
+impl From<Fault> for DeviceLost {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => DeviceLost,
+            _ => unreachable!("{:?} isn't a device loss", fault),
+        }
+    }
+}
+
+impl From<Fault> for OomOrDeviceLost {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => DeviceLost.into(),
+            _ => fault.out_of_memory().into(),
+        }
+    }
+}
+
+impl From<Fault> for device::CreationError {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => device::CreationError::DeviceLost,
+            _ => device::CreationError::OutOfMemory(fault.out_of_memory()),
+        }
+    }
+}
+
+impl From<Fault> for window::CreationError {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => DeviceLost.into(),
+            Fault::SurfaceLost => SurfaceLost.into(),
+            _ => fault.out_of_memory().into(),
+        }
+    }
+}
+
+impl From<Fault> for window::AcquireError {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => window::AcquireError::DeviceLost(DeviceLost),
+            Fault::SurfaceLost => window::AcquireError::SurfaceLost(SurfaceLost),
+            Fault::OutOfDate => window::AcquireError::OutOfDate,
+            _ => window::AcquireError::OutOfMemory(fault.out_of_memory()),
+        }
+    }
+}
+
+impl From<Fault> for window::PresentError {
+    fn from(fault: Fault) -> Self {
+        match fault {
+            Fault::DeviceLost => window::PresentError::DeviceLost(DeviceLost),
+            Fault::SurfaceLost => window::PresentError::SurfaceLost(SurfaceLost),
+            Fault::OutOfDate => window::PresentError::OutOfDate,
+            _ => window::PresentError::OutOfMemory(fault.out_of_memory()),
+        }
+    }
+}
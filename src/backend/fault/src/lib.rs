@@ -0,0 +1,508 @@
+//! Fault injection backend.
+//!
+//! `Faulty<B>` wraps any backend `B` and returns errors from selected calls instead of
+//! forwarding them, so that the handling of errors which real drivers only produce in
+//! rare circumstances, such as `DeviceLost` or `AcquireError::OutOfDate`, can be tested.
+//!
+//! The failures are configured on the `Injector` shared by an instance and all of the
+//! objects created from it, either for the `n`th following call to a method, or for
+//! random calls with a given seed and probability.
+//!
+//! A failed call isn't forwarded to the wrapped backend, so it has no side effect.
+
+#[macro_use]
+extern crate log;
+
+use hal::{
+    adapter,
+    buffer,
+    command::Level,
+    device::{CreationError as DeviceCreationError, OutOfMemory},
+    external,
+    format,
+    image,
+    memory,
+    pool,
+    pso,
+    queue,
+    window::{PresentError, Suboptimal, SwapImageIndex},
+};
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+mod command;
+mod device;
+mod injector;
+mod window;
+
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::injector::{Call, Fault, Injector};
+pub use crate::window::{Surface, Swapchain};
+
+/// Fault injection backend, wrapping the backend `B`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Faulty<B: hal::Backend>(PhantomData<B>);
+impl<B: hal::Backend> hal::Backend for Faulty<B> {
+    type Instance = Instance<B>;
+    type PhysicalDevice = PhysicalDevice<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type QueueFamily = B::QueueFamily;
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+
+    type Memory = B::Memory;
+    type CommandPool = CommandPool<B>;
+
+    type ShaderModule = B::ShaderModule;
+    type RenderPass = B::RenderPass;
+    type Framebuffer = B::Framebuffer;
+
+    type Buffer = B::Buffer;
+    type BufferView = B::BufferView;
+    type Image = B::Image;
+    type ImageView = B::ImageView;
+    type Sampler = B::Sampler;
+
+    type ComputePipeline = B::ComputePipeline;
+    type GraphicsPipeline = B::GraphicsPipeline;
+    type PipelineCache = B::PipelineCache;
+    type PipelineLayout = B::PipelineLayout;
+    type DescriptorSetLayout = B::DescriptorSetLayout;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = B::DescriptorSet;
+
+    type Fence = B::Fence;
+    type Semaphore = B::Semaphore;
+    type Event = B::Event;
+    type QueryPool = B::QueryPool;
+}
+
+/// Fault injection instance, wrapping an instance of the backend `B`.
+#[derive(Debug)]
+pub struct Instance<B: hal::Backend> {
+    raw: B::Instance,
+    injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> Instance<B> {
+    /// Wrap an instance, failing the calls made to it and its objects as decided by
+    /// the injector.
+    pub fn new(raw: B::Instance, injector: Arc<Injector>) -> Self {
+        Instance { raw, injector }
+    }
+
+    /// Returns the wrapped instance.
+    pub fn raw(&self) -> &B::Instance {
+        &self.raw
+    }
+
+    /// Returns the injector of the instance.
+    pub fn injector(&self) -> &Arc<Injector> {
+        &self.injector
+    }
+}
+
+impl<B: hal::Backend> hal::Instance<Faulty<B>> for Instance<B> {
+    /// Create an instance of `B`, with an injector which doesn't fail any call yet.
+    fn create(name: &str, version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        let raw = B::Instance::create(name, version)?;
+        Ok(Instance::new(raw, Arc::new(Injector::default())))
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Faulty<B>>> {
+        self.raw
+            .enumerate_adapters()
+            .into_iter()
+            .map(|adapter| adapter::Adapter {
+                info: adapter.info,
+                physical_device: PhysicalDevice {
+                    raw: adapter.physical_device,
+                    injector: Arc::clone(&self.injector),
+                },
+                queue_families: adapter.queue_families,
+            })
+            .collect()
+    }
+
+    unsafe fn create_surface(
+        &self,
+        has_handle: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface<B>, hal::window::InitError> {
+        let raw = self.raw.create_surface(has_handle)?;
+        Ok(Surface::new(raw, Arc::clone(&self.injector)))
+    }
+
+    unsafe fn destroy_surface(&self, surface: Surface<B>) {
+        self.raw.destroy_surface(surface.raw)
+    }
+}
+
+/// Fault injection physical device.
+#[derive(Debug)]
+pub struct PhysicalDevice<B: hal::Backend> {
+    raw: B::PhysicalDevice,
+    injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> adapter::PhysicalDevice<Faulty<B>> for PhysicalDevice<B> {
+    unsafe fn open(
+        &self,
+        families: &[(&B::QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Faulty<B>>, DeviceCreationError> {
+        self.injector.inject(Call::Open)?;
+        let gpu = self.raw.open(families, requested_features)?;
+        let queue_groups = gpu
+            .queue_groups
+            .into_iter()
+            .map(|group| {
+                let mut faulty = queue::QueueGroup::new(group.family);
+                for raw in group.queues {
+                    faulty.add_queue(CommandQueue {
+                        raw,
+                        injector: Arc::clone(&self.injector),
+                    });
+                }
+                faulty
+            })
+            .collect();
+
+        Ok(adapter::Gpu {
+            device: Device::new(gpu.device, Arc::clone(&self.injector)),
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        self.raw.format_properties(format)
+    }
+
+    fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        self.raw
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        self.raw.memory_properties()
+    }
+
+    fn features(&self) -> hal::Features {
+        self.raw.features()
+    }
+
+    fn hints(&self) -> hal::Hints {
+        self.raw.hints()
+    }
+
+    fn limits(&self) -> hal::Limits {
+        self.raw.limits()
+    }
+
+    fn is_valid_cache(&self, cache: &[u8]) -> bool {
+        self.raw.is_valid_cache(cache)
+    }
+
+    fn external_buffer_properties(
+        &self,
+        usage: buffer::Usage,
+        handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        self.raw.external_buffer_properties(usage, handle_type)
+    }
+
+    fn external_image_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        self.raw.external_image_properties(
+            format,
+            dimensions,
+            tiling,
+            usage,
+            view_caps,
+            handle_type,
+        )
+    }
+
+    fn external_semaphore_properties(
+        &self,
+        handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        self.raw.external_semaphore_properties(handle_type)
+    }
+
+    fn drm_format_modifier_properties(
+        &self,
+        format: format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        self.raw.drm_format_modifier_properties(format)
+    }
+}
+
+/// Fault injection command queue.
+#[derive(Debug)]
+pub struct CommandQueue<B: hal::Backend> {
+    raw: B::CommandQueue,
+    injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> queue::CommandQueue<Faulty<B>> for CommandQueue<B> {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&B::Fence>,
+    ) where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+    {
+        let command_buffers = submission
+            .command_buffers
+            .into_iter()
+            .map(|cmd_buffer| &cmd_buffer.borrow().raw)
+            .collect::<Vec<_>>();
+        self.raw.submit(
+            queue::Submission {
+                command_buffers,
+                wait_semaphores: submission.wait_semaphores,
+                signal_semaphores: submission.signal_semaphores,
+            },
+            fence,
+        )
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&B::Fence>,
+    ) where
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<Item = (&'b B::Buffer, &'b [memory::SparseBind<&'b B::Memory>])>,
+        Io: IntoIterator<Item = (&'b B::Image, &'b [memory::SparseBind<&'b B::Memory>])>,
+        Ii: IntoIterator<Item = (&'b B::Image, &'b [memory::SparseImageBind<&'b B::Memory>])>,
+    {
+        self.raw.bind_sparse(binding, fence)
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        swapchains: Is,
+        wait_semaphores: Iw,
+    ) -> Result<Option<Suboptimal>, PresentError>
+    where
+        W: 'a + Borrow<Swapchain<B>>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        self.injector.inject(Call::Present)?;
+        let swapchains = swapchains
+            .into_iter()
+            .map(|(swapchain, index)| (&swapchain.borrow().raw, index))
+            .collect::<Vec<_>>();
+        self.raw.present(swapchains, wait_semaphores)
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        surface: &mut Surface<B>,
+        image: crate::window::SwapchainImage<B>,
+        wait_semaphore: Option<&B::Semaphore>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        self.injector.inject(Call::Present)?;
+        self.raw
+            .present_surface(&mut surface.raw, image, wait_semaphore)
+    }
+
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        self.injector.inject(Call::WaitIdle)?;
+        self.raw.wait_idle()
+    }
+}
+
+/// Fault injection command pool.
+///
+/// Allocating command buffers can't fail, so the pool is a plain wrapper.
+#[derive(Debug)]
+pub struct CommandPool<B: hal::Backend> {
+    raw: B::CommandPool,
+}
+
+impl<B: hal::Backend> pool::CommandPool<Faulty<B>> for CommandPool<B> {
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources)
+    }
+
+    unsafe fn allocate_one(&mut self, level: Level) -> CommandBuffer<B> {
+        CommandBuffer {
+            raw: self.raw.allocate_one(level),
+        }
+    }
+
+    unsafe fn allocate<E>(&mut self, num: usize, level: Level, list: &mut E)
+    where
+        E: Extend<CommandBuffer<B>>,
+    {
+        let mut raw = Vec::with_capacity(num);
+        self.raw.allocate(num, level, &mut raw);
+        list.extend(raw.into_iter().map(|raw| CommandBuffer { raw }));
+    }
+
+    unsafe fn free<I>(&mut self, cmd_buffers: I)
+    where
+        I: IntoIterator<Item = CommandBuffer<B>>,
+    {
+        self.raw
+            .free(cmd_buffers.into_iter().map(|cmd_buffer| cmd_buffer.raw))
+    }
+}
+
+/// Fault injection descriptor pool.
+#[derive(Debug)]
+pub struct DescriptorPool<B: hal::Backend> {
+    raw: B::DescriptorPool,
+    injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> pso::DescriptorPool<Faulty<B>> for DescriptorPool<B> {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &B::DescriptorSetLayout,
+    ) -> Result<B::DescriptorSet, pso::AllocationError> {
+        self.injector.inject(Call::AllocateDescriptorSets)?;
+        self.raw.allocate_set(layout)
+    }
+
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &B::DescriptorSetLayout,
+        count: pso::DescriptorArrayIndex,
+    ) -> Result<B::DescriptorSet, pso::AllocationError> {
+        self.injector.inject(Call::AllocateDescriptorSets)?;
+        self.raw.allocate_variable_set(layout, count)
+    }
+
+    unsafe fn allocate<I, E>(
+        &mut self,
+        layouts: I,
+        list: &mut E,
+    ) -> Result<(), pso::AllocationError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::DescriptorSetLayout>,
+        E: Extend<B::DescriptorSet>,
+    {
+        self.injector.inject(Call::AllocateDescriptorSets)?;
+        self.raw.allocate(layouts, list)
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
+    where
+        I: IntoIterator<Item = B::DescriptorSet>,
+    {
+        self.raw.free(descriptor_sets)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.raw.reset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{adapter::PhysicalDevice as _, buffer, device::Device as _, Instance as _};
+
+    fn device() -> Device<gfx_backend_empty::Backend> {
+        let instance = Instance::<gfx_backend_empty::Backend>::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        gpu.device
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device<gfx_backend_empty::Backend>>();
+        foo::<CommandBuffer<gfx_backend_empty::Backend>>();
+        foo::<CommandPool<gfx_backend_empty::Backend>>();
+        foo::<Injector>();
+    }
+
+    #[test]
+    fn fail_nth_call() {
+        let device = device();
+        device
+            .injector()
+            .fail_nth(Call::CreateBuffer, 1, Fault::OutOfDeviceMemory);
+        let results = (0 .. 3)
+            .map(|_| unsafe {
+                device.create_buffer(
+                    256,
+                    buffer::Usage::TRANSFER_DST,
+                    memory::SparseFlags::empty(),
+                )
+            })
+            .map(|result| result.err())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                None,
+                Some(buffer::CreationError::OutOfMemory(OutOfMemory::Device)),
+                None,
+            ]
+        );
+        assert_eq!(device.injector().calls(Call::CreateBuffer), 3);
+    }
+
+    #[test]
+    fn fail_randomly_with_seed() {
+        let failures = |seed| {
+            let injector = Injector::new(seed);
+            injector.fail_randomly(Call::WaitForFences, 0.5, Fault::DeviceLost);
+            (0 .. 64)
+                .map(|_| injector.inject(Call::WaitForFences).is_err())
+                .collect::<Vec<_>>()
+        };
+        let first = failures(7);
+        assert_eq!(first, failures(7));
+        assert!(first.contains(&true) && first.contains(&false));
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsupported_fault() {
+        Injector::default().fail_nth(Call::CreateBuffer, 0, Fault::OutOfDate);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Call, Device, Faulty, Injector, PhysicalDevice};
+use hal::{format, window};
+use std::sync::Arc;
+
+/// Swapchain image of the wrapped backend's surface.
+pub(crate) type SwapchainImage<B> =
+    <<B as hal::Backend>::Surface as window::PresentationSurface<B>>::SwapchainImage;
+
+/// Fault injection surface, wrapping a surface of the backend `B`.
+#[derive(Debug)]
+pub struct Surface<B: hal::Backend> {
+    pub(crate) raw: B::Surface,
+    injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> Surface<B> {
+    /// Wrap a surface created by a backend specific constructor, failing its calls as
+    /// decided by the injector, usually the one of the instance.
+    pub fn new(raw: B::Surface, injector: Arc<Injector>) -> Self {
+        Surface { raw, injector }
+    }
+
+    /// Returns the wrapped surface.
+    pub fn raw(&self) -> &B::Surface {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> window::Surface<Faulty<B>> for Surface<B> {
+    fn supports_queue_family(&self, family: &B::QueueFamily) -> bool {
+        self.raw.supports_queue_family(family)
+    }
+
+    fn capabilities(&self, physical_device: &PhysicalDevice<B>) -> window::SurfaceCapabilities {
+        self.raw.capabilities(&physical_device.raw)
+    }
+
+    fn supported_formats(
+        &self,
+        physical_device: &PhysicalDevice<B>,
+    ) -> Option<Vec<format::Format>> {
+        self.raw.supported_formats(&physical_device.raw)
+    }
+}
+
+impl<B: hal::Backend> window::PresentationSurface<Faulty<B>> for Surface<B> {
+    type SwapchainImage = SwapchainImage<B>;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device<B>,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        self.injector.inject(Call::ConfigureSwapchain)?;
+        self.raw.configure_swapchain(&device.raw, config)
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, device: &Device<B>) {
+        self.raw.unconfigure_swapchain(&device.raw)
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+    ) -> Result<(SwapchainImage<B>, Option<window::Suboptimal>), window::AcquireError> {
+        self.injector.inject(Call::AcquireImage)?;
+        self.raw.acquire_image(timeout_ns)
+    }
+}
+
+/// Fault injection swapchain.
+#[derive(Debug)]
+pub struct Swapchain<B: hal::Backend> {
+    pub(crate) raw: B::Swapchain,
+    pub(crate) injector: Arc<Injector>,
+}
+
+impl<B: hal::Backend> window::Swapchain<Faulty<B>> for Swapchain<B> {
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+        semaphore: Option<&B::Semaphore>,
+        fence: Option<&B::Fence>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        self.injector.inject(Call::AcquireImage)?;
+        self.raw.acquire_image(timeout_ns, semaphore, fence)
+    }
+}
@@ -13,10 +13,12 @@ fn main() {
         ios: { target_os = "ios" },
         linux: { target_os = "linux" },
         // Backends
-        surfman: { all(unix, feature = "surfman", not(ios)) },
+        egl: { all(linux, feature = "egl") },
+        headless: { all(linux, feature = "headless", not(egl)) },
+        surfman: { all(unix, feature = "surfman", not(any(ios, headless, egl))) },
         wgl: { all(windows, feature = "wgl") },
-        glutin: { all(feature = "glutin", not(any(wasm, surfman))) },
-        dummy: { not(any(wasm, glutin, wgl, surfman)) },
+        glutin: { all(feature = "glutin", not(any(wasm, surfman, headless, egl))) },
+        dummy: { not(any(wasm, glutin, wgl, surfman, headless, egl)) },
     }
 
     let target = env::var("TARGET").unwrap();
@@ -41,4 +43,33 @@ fn main() {
         .write_bindings(gl_generator::StructGenerator, &mut file)
         .unwrap();
     }
+
+    if target.contains("linux") && env::var_os("CARGO_FEATURE_EGL").is_some() {
+        let mut file = File::create(&dest.join("egl_sys.rs")).unwrap();
+        Registry::new(
+            Api::Egl,
+            (1, 4),
+            Profile::Core,
+            Fallbacks::All,
+            [
+                "EGL_KHR_create_context",
+                "EGL_KHR_platform_gbm",
+                "EGL_KHR_platform_wayland",
+                "EGL_MESA_platform_surfaceless",
+            ],
+        )
+        .write_bindings(gl_generator::StaticGenerator, &mut file)
+        .unwrap();
+
+        let mut file = File::create(&dest.join("egl_ext_sys.rs")).unwrap();
+        Registry::new(
+            Api::Egl,
+            (1, 4),
+            Profile::Core,
+            Fallbacks::All,
+            ["EGL_EXT_platform_base"],
+        )
+        .write_bindings(gl_generator::StructGenerator, &mut file)
+        .unwrap();
+    }
 }
@@ -7,7 +7,7 @@ use hal::{self, buffer, command, image, memory, pass, pso, query};
 
 use crate::info;
 use crate::pool::{self, BufferMemory};
-use crate::{native as n, Backend, ColorSlot};
+use crate::{device, native as n, Backend, ColorSlot};
 
 use parking_lot::Mutex;
 use std::borrow::Borrow;
@@ -68,6 +68,28 @@ pub enum Command {
         base_vertex: hal::VertexOffset,
         instances: Range<hal::InstanceCount>,
     },
+    DrawIndirect {
+        primitive: u32,
+        /// Index type of indexed draws, `None` for non-indexed ones.
+        index_type: Option<u32>,
+        buffer: n::RawBuffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+        /// Buffer and offset to read the actual draw count from,
+        /// in which case `draw_count` is the maximum.
+        count_buffer: Option<(n::RawBuffer, buffer::Offset)>,
+    },
+    /// Begins transform feedback capturing the given primitive type.
+    BeginTransformFeedback(u32),
+    EndTransformFeedback,
+    /// Begins the query object on the given target.
+    BeginQuery(u32, u32),
+    /// Ends the active query of the given target.
+    EndQuery(u32),
+    /// Begins conditional rendering on the given query object, with the given mode.
+    BeginConditionalRender(u32, u32),
+    EndConditionalRender,
     BindIndexBuffer(n::RawBuffer),
     //BindVertexBuffers(BufferSlice),
     BindUniform {
@@ -461,6 +483,95 @@ impl CommandBuffer {
         }
     }
 
+    /// Records the bind commands of the descriptors of a set.
+    fn bind_descriptor_set(
+        &mut self,
+        drd: &n::DescRemapData,
+        set: pso::DescriptorSetIndex,
+        bindings: &[n::DescSetBindings],
+    ) {
+        for new_binding in bindings {
+            match new_binding {
+                n::DescSetBindings::Buffer {
+                    ty: btype,
+                    binding,
+                    buffer,
+                    offset,
+                    size,
+                } => {
+                    let glow_btype = match btype {
+                        n::BindingTypes::UniformBuffers => glow::UNIFORM_BUFFER,
+                        n::BindingTypes::StorageBuffers => glow::SHADER_STORAGE_BUFFER,
+                        n::BindingTypes::Images => panic!("Wrong desc set binding"),
+                    };
+                    for binding in drd.get_binding(*btype, set, *binding).unwrap() {
+                        self.data.push_cmd(Command::BindBufferRange(
+                            glow_btype,
+                            *binding,
+                            *buffer,
+                            *offset as i32,
+                            *size as i32,
+                        ))
+                    }
+                }
+                n::DescSetBindings::Texture(binding, texture, textype) => {
+                    for binding in drd
+                        .get_binding(n::BindingTypes::Images, set, *binding)
+                        .unwrap()
+                    {
+                        self.data
+                            .push_cmd(Command::BindTexture(*binding, *texture, *textype))
+                    }
+                }
+                n::DescSetBindings::Sampler(binding, sampler) => {
+                    for binding in drd
+                        .get_binding(n::BindingTypes::Images, set, *binding)
+                        .unwrap()
+                    {
+                        self.data.push_cmd(Command::BindSampler(*binding, *sampler))
+                    }
+                }
+                n::DescSetBindings::SamplerDesc(binding, sinfo) => {
+                    let mut all_txts = drd
+                        .get_binding(n::BindingTypes::Images, set, *binding)
+                        .unwrap()
+                        .into_iter()
+                        .flat_map(|binding| {
+                            bindings.iter().filter_map(move |b| {
+                                if let n::DescSetBindings::Texture(b, t, ttype) = b {
+                                    let nbs = drd.get_binding(n::BindingTypes::Images, set, *b)?;
+                                    if nbs.contains(binding) {
+                                        Some((*binding, *t, *ttype))
+                                    } else {
+                                        None
+                                    }
+                                } else {
+                                    None
+                                }
+                            })
+                        })
+                        .collect::<Vec<_>>();
+
+                    // TODO: Check that other samplers aren't using the same
+                    // textures as in `all_txts` unless all the bindings of that
+                    // texture are gonna be unbound or the two samplers have
+                    // identical properties.
+                    all_txts.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+                    all_txts.dedup_by(|a, b| a.1 == b.1);
+
+                    for (binding, txt, textype) in all_txts {
+                        self.data.push_cmd(Command::SetTextureSamplerSettings(
+                            binding,
+                            txt,
+                            textype,
+                            sinfo.clone(),
+                        ))
+                    }
+                }
+            }
+        }
+    }
+
     pub(crate) fn bind_attributes(&mut self, first_instance: u32) {
         let Cache {
             ref attributes,
@@ -499,6 +610,66 @@ impl CommandBuffer {
         }
     }
 
+    fn draw_indirect_impl(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        count_buffer: Option<(&n::Buffer, buffer::Offset)>,
+        draw_count: hal::DrawCount,
+        stride: u32,
+        indexed: bool,
+    ) {
+        // The base instance comes from the indirect buffer, so instanced
+        // attributes can't be offset on the CPU side.
+        self.bind_attributes(0);
+
+        let index_type = if indexed {
+            match self.cache.index_type_range {
+                // GL reads indices relative to the start of the bound buffer.
+                Some((_, ref range)) if range.start != 0 => {
+                    warn!(
+                        "Indexed indirect draws require the index buffer to be bound at offset 0."
+                    );
+                    self.cache.error_state = true;
+                    return;
+                }
+                Some((hal::IndexType::U16, _)) => Some(glow::UNSIGNED_SHORT),
+                Some((hal::IndexType::U32, _)) => Some(glow::UNSIGNED_INT),
+                None => {
+                    warn!("No index type bound. An index buffer needs to be bound before calling `draw_indexed_indirect`.");
+                    self.cache.error_state = true;
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
+        let primitive = match self.cache.primitive {
+            Some(primitive) => primitive,
+            None => {
+                warn!("No primitive bound. An active pipeline needs to be bound before calling `draw_indirect`.");
+                self.cache.error_state = true;
+                return;
+            }
+        };
+
+        let (raw_buffer, range) = buffer.as_bound();
+        let count_buffer = count_buffer.map(|(count_buffer, count_buffer_offset)| {
+            let (raw_count_buffer, count_range) = count_buffer.as_bound();
+            (raw_count_buffer, count_range.start + count_buffer_offset)
+        });
+        self.data.push_cmd(Command::DrawIndirect {
+            primitive,
+            index_type,
+            buffer: raw_buffer,
+            offset: range.start + offset,
+            draw_count,
+            stride,
+            count_buffer,
+        });
+    }
+
     fn begin_subpass(&mut self) {
         let state = self.pass_cache.as_ref().unwrap();
         let subpass = &state.render_pass.subpasses[self.cur_subpass as usize];
@@ -916,6 +1087,24 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, first_binding: u32, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<n::Buffer>,
+    {
+        for (i, (buffer, sub)) in buffers.into_iter().enumerate() {
+            let (raw_buffer, range) = buffer.borrow().as_bound();
+            let range = crate::resolve_sub_range(&sub, range);
+            self.data.push_cmd(Command::BindBufferRange(
+                glow::TRANSFORM_FEEDBACK_BUFFER,
+                first_binding + i as u32,
+                raw_buffer,
+                range.start as i32,
+                (range.end - range.start) as i32,
+            ));
+        }
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
     where
         T: IntoIterator,
@@ -1114,93 +1303,34 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         let drd = &*layout.desc_remap_data.read();
         for desc_set in sets {
             let desc_set = desc_set.borrow();
-            let bindings = desc_set.bindings.lock();
-            for new_binding in &*bindings {
-                match new_binding {
-                    n::DescSetBindings::Buffer {
-                        ty: btype,
-                        binding,
-                        buffer,
-                        offset,
-                        size,
-                    } => {
-                        let glow_btype = match btype {
-                            n::BindingTypes::UniformBuffers => glow::UNIFORM_BUFFER,
-                            n::BindingTypes::StorageBuffers => glow::SHADER_STORAGE_BUFFER,
-                            n::BindingTypes::Images => panic!("Wrong desc set binding"),
-                        };
-                        for binding in drd.get_binding(*btype, set, *binding).unwrap() {
-                            self.data.push_cmd(Command::BindBufferRange(
-                                glow_btype,
-                                *binding,
-                                *buffer,
-                                *offset as i32,
-                                *size as i32,
-                            ))
-                        }
-                    }
-                    n::DescSetBindings::Texture(binding, texture, textype) => {
-                        for binding in drd
-                            .get_binding(n::BindingTypes::Images, set, *binding)
-                            .unwrap()
-                        {
-                            self.data
-                                .push_cmd(Command::BindTexture(*binding, *texture, *textype))
-                        }
-                    }
-                    n::DescSetBindings::Sampler(binding, sampler) => {
-                        for binding in drd
-                            .get_binding(n::BindingTypes::Images, set, *binding)
-                            .unwrap()
-                        {
-                            self.data.push_cmd(Command::BindSampler(*binding, *sampler))
-                        }
-                    }
-                    n::DescSetBindings::SamplerDesc(binding, sinfo) => {
-                        let mut all_txts = drd
-                            .get_binding(n::BindingTypes::Images, set, *binding)
-                            .unwrap()
-                            .into_iter()
-                            .flat_map(|binding| {
-                                bindings.iter().filter_map(move |b| {
-                                    if let n::DescSetBindings::Texture(b, t, ttype) = b {
-                                        let nbs =
-                                            drd.get_binding(n::BindingTypes::Images, set, *b)?;
-                                        if nbs.contains(binding) {
-                                            Some((*binding, *t, *ttype))
-                                        } else {
-                                            None
-                                        }
-                                    } else {
-                                        None
-                                    }
-                                })
-                            })
-                            .collect::<Vec<_>>();
-
-                        // TODO: Check that other samplers aren't using the same
-                        // textures as in `all_txts` unless all the bindings of that
-                        // texture are gonna be unbound or the two samplers have
-                        // identical properties.
-                        all_txts.sort_unstable_by(|a, b| a.1.cmp(&b.1));
-                        all_txts.dedup_by(|a, b| a.1 == b.1);
-
-                        for (binding, txt, textype) in all_txts {
-                            self.data.push_cmd(Command::SetTextureSamplerSettings(
-                                binding,
-                                txt,
-                                textype,
-                                sinfo.clone(),
-                            ))
-                        }
-                    }
-                }
-            }
-
+            self.bind_descriptor_set(drd, set, &desc_set.bindings.lock());
             set += 1;
         }
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        let mut bindings = Vec::new();
+        for write in writes {
+            device::write_descriptors(
+                &layout.set_layouts[set],
+                &mut bindings,
+                write.binding,
+                write.descriptors,
+            );
+        }
+        let drd = &*layout.desc_remap_data.read();
+        self.bind_descriptor_set(drd, set as _, &bindings);
+    }
+
     unsafe fn bind_compute_pipeline(&mut self, pipeline: &n::ComputePipeline) {
         let n::ComputePipeline { program } = *pipeline;
 
@@ -1225,6 +1355,20 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         // TODO
     }
 
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        // GL bindings aren't tied to a pipeline bind point.
+        self.push_graphics_descriptor_set(layout, set, writes)
+    }
+
     unsafe fn dispatch(&mut self, count: hal::WorkGroupCount) {
         self.data.push_cmd(Command::Dispatch(count));
     }
@@ -1466,22 +1610,122 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
 
     unsafe fn draw_indirect(
         &mut self,
-        _buffer: &n::Buffer,
-        _offset: buffer::Offset,
-        _draw_count: hal::DrawCount,
-        _stride: u32,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
     ) {
-        unimplemented!()
+        self.draw_indirect_impl(buffer, offset, None, draw_count, stride, false);
     }
 
     unsafe fn draw_indexed_indirect(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(buffer, offset, None, draw_count, stride, true);
+    }
+
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &n::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(
+            buffer,
+            offset,
+            Some((count_buffer, count_buffer_offset)),
+            max_draw_count,
+            stride,
+            false,
+        );
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &n::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(
+            buffer,
+            offset,
+            Some((count_buffer, count_buffer_offset)),
+            max_draw_count,
+            stride,
+            true,
+        );
+    }
+
+    unsafe fn begin_conditional_rendering(
         &mut self,
         _buffer: &n::Buffer,
         _offset: buffer::Offset,
-        _draw_count: hal::DrawCount,
-        _stride: u32,
+        _inverted: bool,
     ) {
-        unimplemented!()
+        // `glBeginConditionalRender` is driven by a query object instead of a buffer,
+        // see `begin_conditional_rendering_query`.
+        unimplemented!("CONDITIONAL_RENDERING feature is not supported")
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &n::QueryPool,
+        id: query::Id,
+        inverted: bool,
+    ) {
+        let mode = if inverted {
+            glow::QUERY_WAIT_INVERTED
+        } else {
+            glow::QUERY_WAIT
+        };
+        for (_, object) in pool.query(id) {
+            self.data
+                .push_cmd(Command::BeginConditionalRender(object, mode));
+        }
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.data.push_cmd(Command::EndConditionalRender);
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        // Only points, lines and triangles can be captured.
+        let primitive = match self.cache.primitive {
+            Some(glow::POINTS) => glow::POINTS,
+            Some(glow::LINES) | Some(glow::LINE_STRIP) | Some(glow::LINE_LOOP) => glow::LINES,
+            Some(glow::TRIANGLES) | Some(glow::TRIANGLE_STRIP) | Some(glow::TRIANGLE_FAN) => {
+                glow::TRIANGLES
+            }
+            Some(primitive) => {
+                warn!(
+                    "Primitive {:#x} can't be captured with transform feedback.",
+                    primitive
+                );
+                self.cache.error_state = true;
+                return;
+            }
+            None => {
+                warn!("No primitive bound. An active pipeline needs to be bound before calling `begin_transform_feedback`.");
+                self.cache.error_state = true;
+                return;
+            }
+        };
+        self.data
+            .push_cmd(Command::BeginTransformFeedback(primitive));
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        self.data.push_cmd(Command::EndTransformFeedback);
     }
 
     unsafe fn set_event(&mut self, _: &(), _: pso::PipelineStage) {
@@ -1502,32 +1746,38 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         unimplemented!()
     }
 
-    unsafe fn begin_query(&mut self, _query: query::Query<Backend>, _flags: query::ControlFlags) {
-        unimplemented!()
+    unsafe fn begin_query(&mut self, query: query::Query<Backend>, _flags: query::ControlFlags) {
+        for (target, object) in query.pool.query(query.id) {
+            self.data.push_cmd(Command::BeginQuery(target, object));
+        }
     }
 
     unsafe fn copy_query_pool_results(
         &mut self,
-        _pool: &(),
+        _pool: &n::QueryPool,
         _queries: Range<query::Id>,
         _buffer: &n::Buffer,
         _offset: buffer::Offset,
         _stride: buffer::Offset,
         _flags: query::ResultFlags,
     ) {
+        // TODO: needs `GL_QUERY_BUFFER` of GL 4.4
         unimplemented!()
     }
 
-    unsafe fn end_query(&mut self, _query: query::Query<Backend>) {
-        unimplemented!()
+    unsafe fn end_query(&mut self, query: query::Query<Backend>) {
+        for (target, _) in query.pool.query(query.id) {
+            self.data.push_cmd(Command::EndQuery(target));
+        }
     }
 
-    unsafe fn reset_query_pool(&mut self, _pool: &(), _queries: Range<query::Id>) {
-        unimplemented!()
+    unsafe fn reset_query_pool(&mut self, _pool: &n::QueryPool, _queries: Range<query::Id>) {
+        // GL query objects are overwritten by the next `glBeginQuery`.
     }
 
     unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, _: query::Query<Backend>) {
-        unimplemented!()
+        // Timestamp query pools can't be created.
+        unreachable!()
     }
 
     unsafe fn push_graphics_constants(
@@ -1586,3 +1836,93 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
         //TODO
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::command::CommandBuffer as _;
+    use parking_lot::RwLock;
+
+    #[test]
+    fn push_compute_descriptor_set() {
+        let memory = Arc::new(Mutex::new(BufferMemory::Linear(pool::OwnedBuffer::new())));
+        let mut cmd_buffer = CommandBuffer::new(
+            None,
+            hal::Limits::default().into(),
+            Arc::clone(&memory),
+            info::LegacyFeatures::empty(),
+        );
+
+        let set_layout = vec![
+            pso::DescriptorSetLayoutBinding {
+                binding: 0,
+                ty: pso::DescriptorType::Buffer {
+                    ty: pso::BufferDescriptorType::Storage { read_only: false },
+                    format: pso::BufferDescriptorFormat::Structured {
+                        dynamic_offset: false,
+                    },
+                },
+                count: 1,
+                stage_flags: pso::ShaderStageFlags::COMPUTE,
+                immutable_samplers: false,
+                flags: pso::DescriptorBindingFlags::empty(),
+            },
+            pso::DescriptorSetLayoutBinding {
+                binding: 1,
+                ty: pso::DescriptorType::Image {
+                    ty: pso::ImageDescriptorType::Storage { read_only: false },
+                },
+                count: 1,
+                stage_flags: pso::ShaderStageFlags::COMPUTE,
+                immutable_samplers: false,
+                flags: pso::DescriptorBindingFlags::empty(),
+            },
+        ];
+        let mut desc_remap_data = n::DescRemapData::new();
+        desc_remap_data.insert_missing_binding(2, n::BindingTypes::StorageBuffers, 0, 0);
+        desc_remap_data.insert_missing_binding(3, n::BindingTypes::Images, 0, 1);
+        let layout = n::PipelineLayout {
+            desc_remap_data: Arc::new(RwLock::new(desc_remap_data)),
+            set_layouts: vec![set_layout],
+        };
+
+        let buffer = n::Buffer::Bound {
+            buffer: 5,
+            range: 256 .. 512,
+        };
+        let view = n::ImageView::Texture(7, glow::TEXTURE_2D, 0);
+        unsafe {
+            cmd_buffer.push_compute_descriptor_set(
+                &layout,
+                0,
+                vec![
+                    pso::DescriptorWrite {
+                        binding: 0,
+                        array_offset: 0,
+                        descriptors: Some(pso::Descriptor::Buffer(
+                            &buffer,
+                            buffer::SubRange {
+                                offset: 16,
+                                size: Some(64),
+                            },
+                        )),
+                    },
+                    pso::DescriptorWrite {
+                        binding: 1,
+                        array_offset: 0,
+                        descriptors: Some(pso::Descriptor::Image(&view, image::Layout::General)),
+                    },
+                ],
+            );
+        }
+
+        match *memory.lock() {
+            BufferMemory::Linear(ref buffer) => match buffer.commands[..] {
+                [Command::BindBufferRange(glow::SHADER_STORAGE_BUFFER, 2, 5, 272, 64), Command::BindTexture(3, 7, glow::TEXTURE_2D)] =>
+                    {}
+                ref commands => panic!("Unexpected commands: {:?}", commands),
+            },
+            BufferMemory::Individual { .. } => unreachable!(),
+        }
+    }
+}
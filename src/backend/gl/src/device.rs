@@ -14,6 +14,7 @@ use auxil::{spirv_cross_specialize_ast, FastHashMap};
 use hal::{
     buffer,
     device as d,
+    external,
     format::{Format, Swizzle},
     image as i,
     memory,
@@ -171,6 +172,42 @@ impl Device {
         }
     }
 
+    fn bind_target_multiview(
+        gl: &GlContainer,
+        point: u32,
+        attachment: u32,
+        view: &n::ImageView,
+        view_mask: pass::ViewMask,
+    ) {
+        // OVR_multiview2 renders to a contiguous range of array layers
+        let base_view = view_mask.trailing_zeros();
+        let num_views = view_mask.count_ones();
+        assert_eq!(
+            32 - view_mask.leading_zeros(),
+            base_view + num_views,
+            "View mask {:#b} is not contiguous",
+            view_mask
+        );
+        let (texture, level) = match *view {
+            n::ImageView::Texture(texture, _, level) => (texture, level),
+            _ => panic!("Multiview attachments must be array textures"),
+        };
+        let framebuffer_texture_multiview = gl
+            .extra
+            .framebuffer_texture_multiview
+            .expect("GL_OVR_multiview2 is not supported");
+        unsafe {
+            framebuffer_texture_multiview(
+                point,
+                attachment,
+                texture,
+                level as _,
+                base_view as _,
+                num_views as _,
+            );
+        }
+    }
+
     fn parse_spirv(&self, raw_data: &[u32]) -> Result<spirv::Ast<glsl::Target>, d::ShaderError> {
         let module = spirv::Module::from_words(raw_data);
 
@@ -372,6 +409,7 @@ impl Device {
         &self,
         point: &pso::EntryPoint<B>,
         stage: pso::Stage,
+        view_mask: pass::ViewMask,
         desc_remap_data: &mut n::DescRemapData,
         name_binding_map: &mut FastHashMap<String, (n::BindingTypes, pso::DescriptorBinding)>,
     ) -> n::Shader {
@@ -393,7 +431,10 @@ impl Device {
                 );
                 self.set_push_const_layout(&mut ast).unwrap();
 
-                let glsl = self.translate_spirv(&mut ast).unwrap();
+                let mut glsl = self.translate_spirv(&mut ast).unwrap();
+                if stage == pso::Stage::Vertex && view_mask != 0 {
+                    glsl = declare_num_views(&glsl, view_mask.count_ones());
+                }
                 debug!("SPIRV-Cross generated shader:\n{}", glsl);
                 let shader = match self.create_shader_module_from_source(&glsl, stage).unwrap() {
                     n::ShaderModule::Raw(raw) => raw,
@@ -406,6 +447,26 @@ impl Device {
     }
 }
 
+/// Declare the number of views rendered by a multiview vertex shader.
+///
+/// `GL_OVR_multiview2` requires it in the vertex shader, but SPIRV-Cross only
+/// emits the extension, as the view count is given by the render pass.
+fn declare_num_views(glsl: &str, num_views: u32) -> String {
+    let extension = "#extension GL_OVR_multiview2 : require";
+    let layout = format!("layout(num_views = {}) in;", num_views);
+    let mut lines = glsl.lines().collect::<Vec<_>>();
+    if !lines.contains(&extension) {
+        // Extensions directly follow the version directive
+        lines.insert(1, extension);
+    }
+    let position = lines
+        .iter()
+        .rposition(|line| line.starts_with("#extension"))
+        .unwrap();
+    lines.insert(position + 1, &layout);
+    lines.join("\n")
+}
+
 pub(crate) unsafe fn set_sampler_info<SetParamFloat, SetParamFloatVec, SetParamInt>(
     info: &i::SamplerDesc,
     features: &hal::Features,
@@ -460,6 +521,80 @@ pub(crate) unsafe fn set_sampler_info<SetParamFloat, SetParamFloatVec, SetParamI
     }
 }
 
+/// Records descriptors written into a set of the given layout, starting at `binding`.
+pub(crate) fn write_descriptors<'a, J>(
+    layout: &n::DescriptorSetLayout,
+    bindings: &mut Vec<n::DescSetBindings>,
+    binding: pso::DescriptorBinding,
+    descriptors: J,
+) where
+    J: IntoIterator,
+    J::Item: Borrow<pso::Descriptor<'a, B>>,
+{
+    for descriptor in descriptors {
+        match descriptor.borrow() {
+            pso::Descriptor::Buffer(buffer, ref sub) => {
+                let (raw_buffer, buffer_range) = buffer.as_bound();
+                let range = crate::resolve_sub_range(sub, buffer_range);
+
+                let ty = layout[binding as usize].ty;
+                let ty = match ty {
+                    pso::DescriptorType::Buffer { ty, .. } => match ty {
+                        pso::BufferDescriptorType::Uniform => n::BindingTypes::UniformBuffers,
+                        pso::BufferDescriptorType::Storage { .. } => {
+                            n::BindingTypes::StorageBuffers
+                        }
+                    },
+                    _ => panic!("Can't write buffer into descriptor of type {:?}", ty),
+                };
+
+                bindings.push(n::DescSetBindings::Buffer {
+                    ty,
+                    binding,
+                    buffer: raw_buffer,
+                    offset: range.start as i32,
+                    size: (range.end - range.start) as i32,
+                });
+            }
+            pso::Descriptor::CombinedImageSampler(view, _layout, sampler) => {
+                match view {
+                    n::ImageView::Texture(tex, textype, _)
+                    | n::ImageView::TextureLayer(tex, textype, _, _) => {
+                        bindings.push(n::DescSetBindings::Texture(binding, *tex, *textype))
+                    }
+                    n::ImageView::Renderbuffer(_) => unimplemented!(),
+                }
+                match sampler {
+                    n::FatSampler::Sampler(sampler) => {
+                        bindings.push(n::DescSetBindings::Sampler(binding, *sampler))
+                    }
+                    n::FatSampler::Info(info) => {
+                        bindings.push(n::DescSetBindings::SamplerDesc(binding, info.clone()))
+                    }
+                }
+            }
+            pso::Descriptor::Image(view, _layout) => match view {
+                n::ImageView::Texture(tex, textype, _)
+                | n::ImageView::TextureLayer(tex, textype, _, _) => {
+                    bindings.push(n::DescSetBindings::Texture(binding, *tex, *textype))
+                }
+                n::ImageView::Renderbuffer(_) => {
+                    panic!("Texture was created with only render target usage which is invalid.")
+                }
+            },
+            pso::Descriptor::Sampler(sampler) => match sampler {
+                n::FatSampler::Sampler(sampler) => {
+                    bindings.push(n::DescSetBindings::Sampler(binding, *sampler))
+                }
+                n::FatSampler::Info(info) => {
+                    bindings.push(n::DescSetBindings::SamplerDesc(binding, info.clone()))
+                }
+            },
+            pso::Descriptor::TexelBuffer(_view) => unimplemented!(),
+        }
+    }
+}
+
 impl d::Device<B> for Device {
     unsafe fn allocate_memory(
         &self,
@@ -605,6 +740,7 @@ impl d::Device<B> for Device {
         attachments: IA,
         subpasses: IS,
         _dependencies: ID,
+        _correlation_masks: &[pass::ViewMask],
     ) -> Result<n::RenderPass, d::OutOfMemory>
     where
         IA: IntoIterator,
@@ -629,6 +765,7 @@ impl d::Device<B> for Device {
                 n::SubpassDesc {
                     color_attachments,
                     depth_stencil,
+                    view_mask: subpass.view_mask,
                 }
             })
             .collect();
@@ -654,9 +791,13 @@ impl d::Device<B> for Device {
         IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
     {
         let mut drd = n::DescRemapData::new();
+        let set_layouts = layouts
+            .into_iter()
+            .map(|layout| layout.borrow().clone())
+            .collect::<Vec<_>>();
 
-        layouts.into_iter().enumerate().for_each(|(set, layout)| {
-            layout.borrow().iter().for_each(|binding| {
+        set_layouts.iter().enumerate().for_each(|(set, layout)| {
+            layout.iter().for_each(|binding| {
                 // DescriptorType -> Descriptor
                 //
                 // Sampler -> Sampler
@@ -717,6 +858,7 @@ impl d::Device<B> for Device {
 
         Ok(n::PipelineLayout {
             desc_remap_data: Arc::new(RwLock::new(drd)),
+            set_layouts,
         })
     }
 
@@ -779,6 +921,7 @@ impl d::Device<B> for Device {
                         let shader_name = self.compile_shader(
                             point,
                             stage,
+                            subpass.view_mask,
                             &mut desc.layout.desc_remap_data.write(),
                             &mut name_binding_map,
                         );
@@ -927,6 +1070,7 @@ impl d::Device<B> for Device {
             let shader = self.compile_shader(
                 &desc.shader,
                 pso::Stage::Compute,
+                0,
                 &mut desc.layout.desc_remap_data.write(),
                 &mut name_binding_map,
             );
@@ -1009,7 +1153,9 @@ impl d::Device<B> for Device {
                 let color_attachment = glow::COLOR_ATTACHMENT0 + index as u32;
                 assert!(color_attachment <= glow::COLOR_ATTACHMENT31);
 
-                if self.share.private_caps.framebuffer_texture {
+                if subpass.view_mask != 0 {
+                    Self::bind_target_multiview(gl, target, color_attachment, &attachments[color], subpass.view_mask);
+                } else if self.share.private_caps.framebuffer_texture {
                     Self::bind_target(gl, target, color_attachment, &attachments[color]);
                 } else {
                     Self::bind_target_compat(gl, target, color_attachment, &attachments[color]);
@@ -1017,7 +1163,9 @@ impl d::Device<B> for Device {
             }
 
             if let Some(depth_stencil) = subpass.depth_stencil {
-                if self.share.private_caps.framebuffer_texture {
+                if subpass.view_mask != 0 {
+                    Self::bind_target_multiview(gl, target, glow::DEPTH_STENCIL_ATTACHMENT, &attachments[depth_stencil], subpass.view_mask);
+                } else if self.share.private_caps.framebuffer_texture {
                     Self::bind_target(gl, target, glow::DEPTH_STENCIL_ATTACHMENT, &attachments[depth_stencil]);
                 } else {
                     Self::bind_target_compat(gl, target, glow::DEPTH_STENCIL_ATTACHMENT, &attachments[depth_stencil]);
@@ -1099,6 +1247,7 @@ impl d::Device<B> for Device {
         &self,
         size: u64,
         usage: buffer::Usage,
+        _sparse: memory::SparseFlags,
     ) -> Result<n::Buffer, buffer::CreationError> {
         if !self
             .share
@@ -1112,6 +1261,15 @@ impl d::Device<B> for Device {
         Ok(n::Buffer::Unbound { size, usage })
     }
 
+    unsafe fn create_external_buffer(
+        &self,
+        _size: u64,
+        usage: buffer::Usage,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Buffer, buffer::CreationError> {
+        Err(buffer::CreationError::UnsupportedUsage { usage })
+    }
+
     unsafe fn get_buffer_requirements(&self, buffer: &n::Buffer) -> memory::Requirements {
         let (size, usage) = match *buffer {
             n::Buffer::Unbound { size, usage } => (size, usage),
@@ -1292,6 +1450,7 @@ impl d::Device<B> for Device {
         _tiling: i::Tiling,
         usage: i::Usage,
         _view_caps: i::ViewCapabilities,
+        _sparse: memory::SparseFlags,
     ) -> Result<n::Image, i::CreationError> {
         let gl = &self.share.context;
 
@@ -1425,10 +1584,41 @@ impl d::Device<B> for Device {
         })
     }
 
+    unsafe fn create_external_image(
+        &self,
+        _kind: i::Kind,
+        _mip_levels: i::Level,
+        _format: Format,
+        _tiling: i::Tiling,
+        usage: i::Usage,
+        _view_caps: i::ViewCapabilities,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Image, i::CreationError> {
+        Err(i::CreationError::Usage(usage))
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        _kind: i::Kind,
+        format: Format,
+        _usage: i::Usage,
+        _view_caps: i::ViewCapabilities,
+        _modifier: external::DrmFormatModifier,
+    ) -> Result<n::Image, i::CreationError> {
+        Err(i::CreationError::Format(format))
+    }
+
     unsafe fn get_image_requirements(&self, unbound: &n::Image) -> memory::Requirements {
         unbound.requirements
     }
 
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        _image: &n::Image,
+    ) -> Vec<i::SparseRequirements> {
+        Vec::new()
+    }
+
     unsafe fn get_image_subresource_footprint(
         &self,
         _image: &n::Image,
@@ -1498,12 +1688,16 @@ impl d::Device<B> for Device {
         &self,
         _: usize,
         _: I,
-        _: pso::DescriptorPoolCreateFlags,
+        flags: pso::DescriptorPoolCreateFlags,
     ) -> Result<n::DescriptorPool, d::OutOfMemory>
     where
         I: IntoIterator,
         I::Item: Borrow<pso::DescriptorRangeDesc>,
     {
+        if flags.contains(pso::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND) {
+            error!("Update-after-bind descriptor pools are not supported");
+            return Err(d::OutOfMemory::Host);
+        }
         Ok(n::DescriptorPool {})
     }
 
@@ -1511,6 +1705,7 @@ impl d::Device<B> for Device {
         &self,
         layout: I,
         _: J,
+        _: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<n::DescriptorSetLayout, d::OutOfMemory>
     where
         I: IntoIterator,
@@ -1518,8 +1713,19 @@ impl d::Device<B> for Device {
         J: IntoIterator,
         J::Item: Borrow<n::FatSampler>,
     {
-        // Just return it
-        Ok(layout.into_iter().map(|l| l.borrow().clone()).collect())
+        let bindings = layout
+            .into_iter()
+            .map(|l| l.borrow().clone())
+            .collect::<n::DescriptorSetLayout>();
+        // Descriptor indexing is not supported, see `Features`
+        if let Some(binding) = bindings.iter().find(|b| !b.flags.is_empty()) {
+            error!(
+                "Descriptor binding flags {:?} of binding {} are not supported",
+                binding.flags, binding.binding
+            );
+            return Err(d::OutOfMemory::Host);
+        }
+        Ok(bindings)
     }
 
     unsafe fn write_descriptor_sets<'a, I, J>(&self, writes: I)
@@ -1528,74 +1734,14 @@ impl d::Device<B> for Device {
         J: IntoIterator,
         J::Item: Borrow<pso::Descriptor<'a, B>>,
     {
-        for mut write in writes {
-            let set = &mut write.set;
-            let mut bindings = set.bindings.lock();
-            let binding = write.binding;
-
-            for descriptor in write.descriptors {
-                match descriptor.borrow() {
-                    pso::Descriptor::Buffer(buffer, ref sub) => {
-                        let (raw_buffer, buffer_range) = buffer.as_bound();
-                        let range = crate::resolve_sub_range(sub, buffer_range);
-
-                        let ty = set.layout[binding as usize].ty;
-                        let ty = match ty {
-                            pso::DescriptorType::Buffer { ty, .. } => match ty {
-                                pso::BufferDescriptorType::Uniform => {
-                                    n::BindingTypes::UniformBuffers
-                                }
-                                pso::BufferDescriptorType::Storage { .. } => {
-                                    n::BindingTypes::StorageBuffers
-                                }
-                            },
-                            _ => panic!("Can't write buffer into descriptor of type {:?}", ty),
-                        };
-
-                        bindings.push(n::DescSetBindings::Buffer {
-                            ty,
-                            binding,
-                            buffer: raw_buffer,
-                            offset: range.start as i32,
-                            size: (range.end - range.start) as i32,
-                        });
-                    }
-                    pso::Descriptor::CombinedImageSampler(view, _layout, sampler) => {
-                        match view {
-                            n::ImageView::Texture(tex, textype, _)
-                            | n::ImageView::TextureLayer(tex, textype, _, _) => {
-                                bindings.push(n::DescSetBindings::Texture(binding, *tex, *textype))
-                            }
-                            n::ImageView::Renderbuffer(_) => unimplemented!(),
-                        }
-                        match sampler {
-                            n::FatSampler::Sampler(sampler) => {
-                                bindings.push(n::DescSetBindings::Sampler(binding, *sampler))
-                            }
-                            n::FatSampler::Info(info) => bindings
-                                .push(n::DescSetBindings::SamplerDesc(binding, info.clone())),
-                        }
-                    }
-                    pso::Descriptor::Image(view, _layout) => match view {
-                        n::ImageView::Texture(tex, textype, _)
-                        | n::ImageView::TextureLayer(tex, textype, _, _) => {
-                            bindings.push(n::DescSetBindings::Texture(binding, *tex, *textype))
-                        }
-                        n::ImageView::Renderbuffer(_) => panic!(
-                            "Texture was created with only render target usage which is invalid."
-                        ),
-                    },
-                    pso::Descriptor::Sampler(sampler) => match sampler {
-                        n::FatSampler::Sampler(sampler) => {
-                            bindings.push(n::DescSetBindings::Sampler(binding, *sampler))
-                        }
-                        n::FatSampler::Info(info) => {
-                            bindings.push(n::DescSetBindings::SamplerDesc(binding, info.clone()))
-                        }
-                    },
-                    pso::Descriptor::TexelBuffer(_view) => unimplemented!(),
-                }
-            }
+        for write in writes {
+            let set = write.set;
+            write_descriptors(
+                &set.layout,
+                &mut set.bindings.lock(),
+                write.binding,
+                write.descriptors,
+            );
         }
     }
 
@@ -1610,7 +1756,123 @@ impl d::Device<B> for Device {
     }
 
     fn create_semaphore(&self) -> Result<n::Semaphore, d::OutOfMemory> {
-        Ok(n::Semaphore)
+        Ok(n::Semaphore { timeline: None })
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: queue::SemaphoreValue,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        Ok(n::Semaphore {
+            timeline: Some(Mutex::new(n::Timeline {
+                value: initial_value,
+                pending: Vec::new(),
+            })),
+        })
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &n::Semaphore,
+    ) -> Result<queue::SemaphoreValue, d::DeviceLost> {
+        let mut timeline = semaphore
+            .timeline
+            .as_ref()
+            .expect("Not a timeline semaphore")
+            .lock();
+        timeline.update(&self.share.context);
+        Ok(timeline.value)
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &n::Semaphore,
+        value: queue::SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, d::OomOrDeviceLost> {
+        let gl = &self.share.context;
+        let mut timeline = semaphore
+            .timeline
+            .as_ref()
+            .expect("Not a timeline semaphore")
+            .lock();
+        timeline.update(gl);
+        if timeline.value >= value {
+            return Ok(true);
+        }
+        // Signals are submitted in order, so the first one reaching the value completes first.
+        let pending = timeline
+            .pending
+            .iter()
+            .find(|&&(signal, _)| signal >= value);
+        let sync = match pending {
+            Some(&(_, sync)) => sync,
+            None => {
+                warn!(
+                    "Semaphore value {} isn't signalled by any submission, waiting not possible",
+                    value
+                );
+                return Ok(false);
+            }
+        };
+        match gl.client_wait_sync(sync, glow::SYNC_FLUSH_COMMANDS_BIT, timeout_ns as i32) {
+            glow::TIMEOUT_EXPIRED => Ok(false),
+            glow::WAIT_FAILED => {
+                if let Err(err) = self.share.check() {
+                    error!("Error when waiting on semaphore: {:?}", err);
+                }
+                Ok(false)
+            }
+            glow::CONDITION_SATISFIED | glow::ALREADY_SIGNALED => {
+                timeline.update(gl);
+                Ok(true)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &n::Semaphore,
+        value: queue::SemaphoreValue,
+    ) -> Result<(), d::OutOfMemory> {
+        let mut timeline = semaphore
+            .timeline
+            .as_ref()
+            .expect("Not a timeline semaphore")
+            .lock();
+        timeline.value = timeline.value.max(value);
+        Ok(())
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        _handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        // `OutOfMemory` can't tell that the call is unsupported, so the misuse is logged.
+        error!("EXTERNAL_SEMAPHORE_FD feature is not supported");
+        debug_assert!(
+            self.features.contains(hal::Features::EXTERNAL_SEMAPHORE_FD),
+            "EXTERNAL_SEMAPHORE_FD feature is not enabled"
+        );
+        Err(d::OutOfMemory::Device)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+        _fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
     }
 
     fn create_fence(&self, signaled: bool) -> Result<n::Fence, d::OutOfMemory> {
@@ -1749,27 +2011,137 @@ impl d::Device<B> for Device {
         }
     }
 
+    unsafe fn allocate_exportable_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Memory, d::AllocationError> {
+        // `AllocationError` can't tell that the call is unsupported, so the misuse is logged.
+        error!("External memory features are not supported");
+        debug_assert!(
+            self.features.intersects(
+                hal::Features::EXTERNAL_MEMORY_FD | hal::Features::EXTERNAL_MEMORY_DMA_BUF
+            ),
+            "External memory features are not enabled"
+        );
+        Err(d::OutOfMemory::Device.into())
+    }
+
+    unsafe fn export_memory(
+        &self,
+        _memory: &n::Memory,
+        _handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<n::Memory, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     unsafe fn create_query_pool(
         &self,
-        _ty: query::Type,
-        _count: query::Id,
-    ) -> Result<(), query::CreationError> {
-        unimplemented!()
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<n::QueryPool, query::CreationError> {
+        let gl = &self.share.context;
+        if !gl.extra.has_queries() {
+            return Err(query::CreationError::Unsupported(ty));
+        }
+        let targets = match ty {
+            // GLES only has boolean occlusion queries, which are allowed without `PRECISE`.
+            query::Type::Occlusion if self.share.info.version.is_embedded => {
+                vec![glow::ANY_SAMPLES_PASSED]
+            }
+            query::Type::Occlusion => vec![glow::SAMPLES_PASSED],
+            query::Type::PrimitivesWritten
+                if self.features.contains(hal::Features::TRANSFORM_FEEDBACK) =>
+            {
+                vec![
+                    glow::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN,
+                    glow::PRIMITIVES_GENERATED,
+                ]
+            }
+            _ => return Err(query::CreationError::Unsupported(ty)),
+        };
+
+        let gen_queries = gl.extra.gen_queries.unwrap();
+        let mut objects = vec![0; count as usize * targets.len()];
+        gen_queries(objects.len() as i32, objects.as_mut_ptr());
+        if let Err(err) = self.share.check() {
+            panic!("Error creating query pool: {:?}", err);
+        }
+        Ok(n::QueryPool { targets, objects })
     }
 
-    unsafe fn destroy_query_pool(&self, _: ()) {
-        unimplemented!()
+    unsafe fn destroy_query_pool(&self, pool: n::QueryPool) {
+        let delete_queries = self.share.context.extra.delete_queries.unwrap();
+        delete_queries(pool.objects.len() as i32, pool.objects.as_ptr());
     }
 
     unsafe fn get_query_pool_results(
         &self,
-        _pool: &(),
-        _queries: Range<query::Id>,
-        _data: &mut [u8],
-        _stride: buffer::Offset,
-        _flags: query::ResultFlags,
+        pool: &n::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
     ) -> Result<bool, d::OomOrDeviceLost> {
-        unimplemented!()
+        let get_query_object = self.share.context.extra.get_query_object_uiv.unwrap();
+        let size = if flags.contains(query::ResultFlags::BITS_64) {
+            8
+        } else {
+            4
+        };
+        let mut write = |offset: usize, value: u64| {
+            if let Some(bytes) = data.get_mut(offset .. offset + size) {
+                bytes.copy_from_slice(&value.to_le_bytes()[.. size]);
+            }
+        };
+
+        let mut all_available = true;
+        for (i, id) in queries.enumerate() {
+            let offset = i * stride as usize;
+            let mut available = true;
+            if !flags.contains(query::ResultFlags::WAIT) {
+                for (_, object) in pool.query(id) {
+                    let mut value = 0;
+                    get_query_object(object, glow::QUERY_RESULT_AVAILABLE, &mut value);
+                    available &= value != 0;
+                }
+            }
+            all_available &= available;
+
+            // `QUERY_RESULT` blocks until the result is available.
+            for (j, (_, object)) in pool.query(id).enumerate() {
+                if available {
+                    let mut value = 0;
+                    get_query_object(object, glow::QUERY_RESULT, &mut value);
+                    write(offset + j * size, value as u64);
+                } else if flags.contains(query::ResultFlags::PARTIAL) {
+                    write(offset + j * size, 0);
+                }
+            }
+            if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+                write(offset + pool.targets.len() * size, available as u64);
+            }
+        }
+        Ok(all_available)
     }
 
     unsafe fn destroy_shader_module(&self, _: n::ShaderModule) {
@@ -1846,8 +2218,12 @@ impl d::Device<B> for Device {
         }
     }
 
-    unsafe fn destroy_semaphore(&self, _: n::Semaphore) {
-        // Nothing to do
+    unsafe fn destroy_semaphore(&self, semaphore: n::Semaphore) {
+        if let Some(timeline) = semaphore.timeline {
+            for (_, sync) in timeline.into_inner().pending {
+                self.share.context.delete_sync(sync);
+            }
+        }
     }
 
     unsafe fn destroy_event(&self, _event: ()) {
@@ -1872,6 +2248,16 @@ impl d::Device<B> for Device {
             context
         };
 
+        #[cfg(egl)]
+        let context = {
+            use crate::window::egl::PresentContext;
+
+            let context =
+                PresentContext::new(surface, &self.share.instance_context, config.extent)?;
+            context.make_current();
+            context
+        };
+
         let mut fbos = ArrayVec::new();
         let mut images = Vec::new();
 
@@ -1959,8 +2345,8 @@ impl d::Device<B> for Device {
             context: surface.context.clone(),
         };
 
-        // WGL
-        #[cfg(wgl)]
+        // WGL and EGL
+        #[cfg(any(wgl, egl))]
         let swapchain = {
             self.share.instance_context.make_current();
             Swapchain {
@@ -1970,6 +2356,19 @@ impl d::Device<B> for Device {
             }
         };
 
+        // Headless
+        #[cfg(headless)]
+        let swapchain = Swapchain {
+            extent: {
+                let _ = surface;
+                config.extent
+            },
+            format: config.format,
+            fbos,
+            frame: Default::default(),
+            next_image: 0,
+        };
+
         // Dummy
         #[cfg(dummy)]
         let swapchain = Swapchain {
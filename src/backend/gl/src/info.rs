@@ -333,6 +333,9 @@ impl Info {
 
 const IS_WEBGL: bool = cfg!(wasm);
 
+/// `GL_MAX_VIEWS_OVR` of `GL_OVR_multiview`, missing from glow.
+const MAX_VIEWS_OVR: u32 = 0x9631;
+
 /// Load the information pertaining to the driver and the corresponding device
 /// capabilities.
 pub(crate) fn query_all(
@@ -371,6 +374,7 @@ pub(crate) fn query_all(
         framebuffer_color_sample_counts: max_samples_mask,
         non_coherent_atom_size: 1,
         max_color_attachments: get_usize(gl, glow::MAX_COLOR_ATTACHMENTS).unwrap_or(1),
+        max_push_descriptors: 32,
         ..Limits::default()
     };
 
@@ -398,7 +402,10 @@ pub(crate) fn query_all(
         }
     }
 
-    let mut features = Features::NDC_Y_UP;
+    // Timeline semaphores are emulated with fences, and descriptor sets are
+    // bound with plain bind commands, which pushed descriptors map onto directly.
+    let mut features =
+        Features::NDC_Y_UP | Features::TIMELINE_SEMAPHORE | Features::PUSH_DESCRIPTOR;
     let mut legacy = LegacyFeatures::empty();
 
     if info.is_supported(&[
@@ -424,6 +431,42 @@ pub(crate) fn query_all(
     if info.is_supported(&[Core(4, 0), Es(3, 2), Ext("GL_EXT_draw_buffers2")]) && !info.is_webgl() {
         features |= Features::INDEPENDENT_BLENDING;
     }
+    if info.is_supported(&[Ext("GL_OVR_multiview2")])
+        && gl.extra.framebuffer_texture_multiview.is_some()
+    {
+        features |= Features::MULTIVIEW;
+        limits.max_multiview_view_count = get_usize(gl, MAX_VIEWS_OVR).unwrap_or(0);
+    }
+    if info.is_supported(&[Core(4, 6), Ext("GL_ARB_indirect_parameters")])
+        && gl.extra.multi_draw_arrays_indirect_count.is_some()
+        && gl.extra.multi_draw_elements_indirect_count.is_some()
+    {
+        features |= Features::DRAW_INDIRECT_COUNT;
+    }
+    // Captured outputs are declared with the `xfb_*` layout qualifiers.
+    if info.is_supported(&[Core(4, 4), Ext("GL_ARB_enhanced_layouts")])
+        && gl.extra.gen_transform_feedbacks.is_some()
+        && gl.extra.bind_transform_feedback.is_some()
+        && gl.extra.begin_transform_feedback.is_some()
+        && gl.extra.end_transform_feedback.is_some()
+    {
+        features |= Features::TRANSFORM_FEEDBACK;
+        limits.max_transform_feedback_buffers =
+            get_usize(gl, glow::MAX_TRANSFORM_FEEDBACK_BUFFERS).unwrap_or(0);
+    }
+
+    // Desktop GL counts the passed samples, while GLES only reports whether any did.
+    if !info.version.is_embedded && gl.extra.has_queries() {
+        features |= Features::PRECISE_OCCLUSION_QUERY;
+    }
+    // Inverted conditions need the `QUERY_*_INVERTED` modes.
+    if info.is_supported(&[Core(4, 5), Ext("GL_ARB_conditional_render_inverted")])
+        && gl.extra.has_queries()
+        && gl.extra.begin_conditional_render.is_some()
+        && gl.extra.end_conditional_render.is_some()
+    {
+        features |= Features::CONDITIONAL_RENDERING_QUERY;
+    }
 
     // TODO
     if false && info.is_supported(&[Core(4, 3), Es(3, 1)]) {
@@ -52,12 +52,22 @@ pub use crate::window::surfman::{Instance, Surface, Swapchain};
 #[cfg(surfman)]
 surfman::declare_surfman!();
 
+// Headless implementation
+#[cfg(headless)]
+pub use crate::window::headless::{Frame, Instance, Surface, Swapchain};
+
 // WGL implementation
 #[cfg(wgl)]
 use window::wgl::DeviceContext;
 #[cfg(wgl)]
 pub use window::wgl::{Instance, Surface, Swapchain};
 
+// EGL implementation
+#[cfg(egl)]
+use window::egl::DeviceContext;
+#[cfg(egl)]
+pub use window::egl::{Instance, Surface, Swapchain};
+
 // Catch-all dummy implementation
 #[cfg(dummy)]
 pub use window::dummy::{Surface, Swapchain};
@@ -67,14 +77,147 @@ use glow::HasContext;
 
 type ColorSlot = u8;
 
+/// `glFramebufferTextureMultiviewOVR` of `GL_OVR_multiview2`.
+type FramebufferTextureMultiviewOvr =
+    unsafe extern "system" fn(u32, u32, native::Texture, i32, i32, i32);
+/// `glDrawArraysIndirect` of GL 4.0 and GLES 3.1.
+type DrawArraysIndirect = unsafe extern "system" fn(u32, *const std::os::raw::c_void);
+/// `glDrawElementsIndirect` of GL 4.0 and GLES 3.1.
+type DrawElementsIndirect = unsafe extern "system" fn(u32, u32, *const std::os::raw::c_void);
+/// `glMultiDrawArraysIndirectCount` of GL 4.6 and `GL_ARB_indirect_parameters`.
+type MultiDrawArraysIndirectCount =
+    unsafe extern "system" fn(u32, *const std::os::raw::c_void, isize, i32, i32);
+/// `glMultiDrawElementsIndirectCount` of GL 4.6 and `GL_ARB_indirect_parameters`.
+type MultiDrawElementsIndirectCount =
+    unsafe extern "system" fn(u32, u32, *const std::os::raw::c_void, isize, i32, i32);
+/// `glGenTransformFeedbacks` of GL 4.0 and GLES 3.0.
+type GenTransformFeedbacks = unsafe extern "system" fn(i32, *mut u32);
+/// `glBindTransformFeedback` of GL 4.0 and GLES 3.0.
+type BindTransformFeedback = unsafe extern "system" fn(u32, u32);
+/// `glBeginTransformFeedback` of GL 3.0 and GLES 3.0.
+type BeginTransformFeedback = unsafe extern "system" fn(u32);
+/// `glEndTransformFeedback` of GL 3.0 and GLES 3.0.
+type EndTransformFeedback = unsafe extern "system" fn();
+/// `glGenQueries` of GL 1.5 and GLES 3.0.
+type GenQueries = unsafe extern "system" fn(i32, *mut u32);
+/// `glDeleteQueries` of GL 1.5 and GLES 3.0.
+type DeleteQueries = unsafe extern "system" fn(i32, *const u32);
+/// `glBeginQuery` of GL 1.5 and GLES 3.0.
+type BeginQuery = unsafe extern "system" fn(u32, u32);
+/// `glEndQuery` of GL 1.5 and GLES 3.0.
+type EndQuery = unsafe extern "system" fn(u32);
+/// `glGetQueryObjectuiv` of GL 1.5 and GLES 3.0.
+type GetQueryObjectUiv = unsafe extern "system" fn(u32, u32, *mut u32);
+/// `glBeginConditionalRender` of GL 3.0.
+type BeginConditionalRender = unsafe extern "system" fn(u32, u32);
+/// `glEndConditionalRender` of GL 3.0.
+type EndConditionalRender = unsafe extern "system" fn();
+
+/// Entry points which glow doesn't expose, if the driver has them.
+#[derive(Default)]
+pub(crate) struct ExtraFns {
+    framebuffer_texture_multiview: Option<FramebufferTextureMultiviewOvr>,
+    draw_arrays_indirect: Option<DrawArraysIndirect>,
+    draw_elements_indirect: Option<DrawElementsIndirect>,
+    multi_draw_arrays_indirect_count: Option<MultiDrawArraysIndirectCount>,
+    multi_draw_elements_indirect_count: Option<MultiDrawElementsIndirectCount>,
+    gen_transform_feedbacks: Option<GenTransformFeedbacks>,
+    bind_transform_feedback: Option<BindTransformFeedback>,
+    begin_transform_feedback: Option<BeginTransformFeedback>,
+    end_transform_feedback: Option<EndTransformFeedback>,
+    gen_queries: Option<GenQueries>,
+    delete_queries: Option<DeleteQueries>,
+    begin_query: Option<BeginQuery>,
+    end_query: Option<EndQuery>,
+    get_query_object_uiv: Option<GetQueryObjectUiv>,
+    begin_conditional_render: Option<BeginConditionalRender>,
+    end_conditional_render: Option<EndConditionalRender>,
+}
+
+impl ExtraFns {
+    #[cfg(not(any(wasm, dummy)))]
+    fn load<F>(fn_proc: &mut F) -> Self
+    where
+        F: FnMut(&str) -> *const std::os::raw::c_void,
+    {
+        // Takes the first of the given names the driver resolves.
+        fn load_fn<F, T>(fn_proc: &mut F, names: &[&str]) -> Option<T>
+        where
+            F: FnMut(&str) -> *const std::os::raw::c_void,
+        {
+            assert_eq!(
+                std::mem::size_of::<T>(),
+                std::mem::size_of::<*const std::os::raw::c_void>()
+            );
+            names
+                .iter()
+                .map(|name| fn_proc(name))
+                .find(|address| !address.is_null())
+                .map(|address| unsafe { std::mem::transmute_copy(&address) })
+        }
+
+        ExtraFns {
+            framebuffer_texture_multiview: load_fn(fn_proc, &["glFramebufferTextureMultiviewOVR"]),
+            draw_arrays_indirect: load_fn(fn_proc, &["glDrawArraysIndirect"]),
+            draw_elements_indirect: load_fn(fn_proc, &["glDrawElementsIndirect"]),
+            multi_draw_arrays_indirect_count: load_fn(
+                fn_proc,
+                &[
+                    "glMultiDrawArraysIndirectCount",
+                    "glMultiDrawArraysIndirectCountARB",
+                ],
+            ),
+            multi_draw_elements_indirect_count: load_fn(
+                fn_proc,
+                &[
+                    "glMultiDrawElementsIndirectCount",
+                    "glMultiDrawElementsIndirectCountARB",
+                ],
+            ),
+            gen_transform_feedbacks: load_fn(fn_proc, &["glGenTransformFeedbacks"]),
+            bind_transform_feedback: load_fn(fn_proc, &["glBindTransformFeedback"]),
+            begin_transform_feedback: load_fn(fn_proc, &["glBeginTransformFeedback"]),
+            end_transform_feedback: load_fn(fn_proc, &["glEndTransformFeedback"]),
+            gen_queries: load_fn(fn_proc, &["glGenQueries"]),
+            delete_queries: load_fn(fn_proc, &["glDeleteQueries"]),
+            begin_query: load_fn(fn_proc, &["glBeginQuery"]),
+            end_query: load_fn(fn_proc, &["glEndQuery"]),
+            get_query_object_uiv: load_fn(fn_proc, &["glGetQueryObjectuiv"]),
+            begin_conditional_render: load_fn(
+                fn_proc,
+                &["glBeginConditionalRender", "glBeginConditionalRenderNV"],
+            ),
+            end_conditional_render: load_fn(
+                fn_proc,
+                &["glEndConditionalRender", "glEndConditionalRenderNV"],
+            ),
+        }
+    }
+
+    /// Whether query objects can be used.
+    pub(crate) fn has_queries(&self) -> bool {
+        self.gen_queries.is_some()
+            && self.delete_queries.is_some()
+            && self.begin_query.is_some()
+            && self.end_query.is_some()
+            && self.get_query_object_uiv.is_some()
+    }
+}
+
 pub(crate) struct GlContainer {
     context: GlContext,
+    extra: ExtraFns,
 
     #[cfg(surfman)]
     surfman_device: Starc<RwLock<surfman::Device>>,
 
     #[cfg(surfman)]
     surfman_context: Starc<RwLock<surfman::Context>>,
+
+    // Only kept around to destroy the context with the container
+    #[cfg(headless)]
+    #[allow(dead_code)]
+    headless_context: window::headless::HeadlessContext,
 }
 
 impl GlContainer {
@@ -85,32 +228,47 @@ impl GlContainer {
         // I'm not sure if there would be similar concequences with other backends.
     }
 
-    #[cfg(any(glutin, wgl))]
-    fn from_fn_proc<F>(fn_proc: F) -> GlContainer
+    #[cfg(any(glutin, wgl, egl))]
+    fn from_fn_proc<F>(mut fn_proc: F) -> GlContainer
     where
         F: FnMut(&str) -> *const std::os::raw::c_void,
     {
+        let extra = ExtraFns::load(&mut fn_proc);
         let context = glow::Context::from_loader_function(fn_proc);
-        GlContainer { context }
+        GlContainer { context, extra }
     }
 
     #[cfg(surfman)]
     fn from_fn_proc<F>(
-        fn_proc: F,
+        mut fn_proc: F,
         surfman_device: Starc<RwLock<surfman::Device>>,
         surfman_context: Starc<RwLock<surfman::Context>>,
     ) -> GlContainer
     where
         F: FnMut(&str) -> *const std::os::raw::c_void,
     {
+        let extra = ExtraFns::load(&mut fn_proc);
         let context = glow::Context::from_loader_function(fn_proc);
         GlContainer {
             context,
+            extra,
             surfman_device,
             surfman_context,
         }
     }
 
+    #[cfg(headless)]
+    fn from_headless_context(headless_context: window::headless::HeadlessContext) -> GlContainer {
+        let mut fn_proc = |symbol_name: &str| headless_context.get_proc_address(symbol_name);
+        let extra = ExtraFns::load(&mut fn_proc);
+        let context = glow::Context::from_loader_function(fn_proc);
+        GlContainer {
+            context,
+            extra,
+            headless_context,
+        }
+    }
+
     #[cfg(wasm)]
     fn from_canvas(canvas: &web_sys::HtmlCanvasElement) -> GlContainer {
         let context = {
@@ -136,7 +294,10 @@ impl GlContainer {
                 .expect("Cannot convert into WebGL2 context");
             glow::Context::from_webgl2_context(webgl2_context)
         };
-        GlContainer { context }
+        GlContainer {
+            context,
+            extra: ExtraFns::default(),
+        }
     }
 }
 
@@ -207,7 +368,7 @@ impl hal::Backend for Backend {
     type Fence = native::Fence;
     type Semaphore = native::Semaphore;
     type Event = ();
-    type QueryPool = ();
+    type QueryPool = native::QueryPool;
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -450,7 +611,7 @@ unsafe impl<T: ?Sized> Sync for Wstarc<T> {}
 #[derive(Debug)]
 pub struct PhysicalDevice(Starc<Share>);
 
-#[cfg(not(wgl))]
+#[cfg(not(any(wgl, egl)))]
 type DeviceContext = ();
 
 impl PhysicalDevice {
@@ -674,6 +835,15 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
             gl.bind_vertex_array(vao);
         }
 
+        // create the transform feedback object, which holds the bound capture buffers
+        let mut transform_feedback = None;
+        if requested_features.contains(hal::Features::TRANSFORM_FEEDBACK) {
+            let gen_transform_feedbacks = gl.extra.gen_transform_feedbacks.unwrap();
+            let mut name = 0;
+            gen_transform_feedbacks(1, &mut name);
+            transform_feedback = Some(name);
+        }
+
         if let Err(err) = self.0.check() {
             panic!("Error opening adapter: {:?}", err);
         }
@@ -685,7 +855,12 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
                 .map(|&(_family, priorities)| {
                     assert_eq!(priorities.len(), 1);
                     let mut family = q::QueueGroup::new(q::QueueFamilyId(0));
-                    let queue = queue::CommandQueue::new(&self.0, requested_features, vao);
+                    let queue = queue::CommandQueue::new(
+                        &self.0,
+                        requested_features,
+                        vao,
+                        transform_feedback,
+                    );
                     family.add_queue(queue);
                     family
                 })
@@ -765,6 +940,10 @@ impl q::QueueFamily for QueueFamily {
     fn id(&self) -> q::QueueFamilyId {
         q::QueueFamilyId(0)
     }
+    fn timestamp_valid_bits(&self) -> u32 {
+        // TODO: timestamp queries are not implemented
+        0
+    }
 }
 
 #[cfg(dummy)]
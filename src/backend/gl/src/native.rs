@@ -1,3 +1,4 @@
+use glow::HasContext;
 use parking_lot::{Mutex, RwLock};
 use std::cell::Cell;
 use std::ops::Range;
@@ -58,6 +59,25 @@ impl Buffer {
 #[derive(Debug)]
 pub struct BufferView;
 
+#[derive(Debug)]
+pub struct QueryPool {
+    /// Query targets recorded for each query of the pool.
+    pub(crate) targets: Vec<u32>,
+    /// Query objects, one per target for each query.
+    pub(crate) objects: Vec<u32>,
+}
+
+impl QueryPool {
+    /// Returns the query objects backing the query `id`, along with their targets.
+    pub(crate) fn query(&self, id: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let start = id as usize * self.targets.len();
+        self.targets
+            .iter()
+            .cloned()
+            .zip(self.objects[start .. start + self.targets.len()].iter().cloned())
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum FenceInner {
     Idle { signaled: bool },
@@ -279,6 +299,7 @@ pub struct RenderPass {
 pub struct SubpassDesc {
     pub(crate) color_attachments: Vec<usize>,
     pub(crate) depth_stencil: Option<usize>,
+    pub(crate) view_mask: pass::ViewMask,
 }
 
 impl SubpassDesc {
@@ -298,11 +319,42 @@ impl SubpassDesc {
 #[derive(Debug)]
 pub struct PipelineLayout {
     pub(crate) desc_remap_data: Arc<RwLock<DescRemapData>>,
+    pub(crate) set_layouts: Vec<DescriptorSetLayout>,
+}
+
+/// Counter of a timeline semaphore.
+///
+/// GL has no timeline semaphores, so they are emulated with the fences of the
+/// submissions signalling them, each paired with the value it sets once complete.
+#[derive(Debug)]
+pub(crate) struct Timeline {
+    pub(crate) value: u64,
+    pub(crate) pending: Vec<(u64, <GlContext as glow::HasContext>::Fence)>,
+}
+
+impl Timeline {
+    /// Advance the counter past the pending signals which completed.
+    pub(crate) unsafe fn update(&mut self, gl: &GlContext) {
+        let value = &mut self.value;
+        self.pending.retain(|&(signal, sync)| {
+            if gl.get_sync_status(sync) == glow::SIGNALED {
+                *value = (*value).max(signal);
+                gl.delete_sync(sync);
+                false
+            } else {
+                true
+            }
+        });
+    }
 }
 
 #[derive(Debug)]
-// No inter-queue synchronization required for GL.
-pub struct Semaphore;
+// No inter-queue synchronization required for GL, only timeline semaphores carry state.
+pub struct Semaphore {
+    pub(crate) timeline: Option<Mutex<Timeline>>,
+}
+unsafe impl Send for Semaphore {}
+unsafe impl Sync for Semaphore {}
 
 #[derive(Clone, Debug)]
 pub struct AttributeDesc {
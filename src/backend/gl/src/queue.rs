@@ -9,6 +9,9 @@ use crate::{
     Surface, Swapchain,
 };
 
+/// `GL_PARAMETER_BUFFER` of GL 4.6 and `GL_ARB_indirect_parameters`.
+const PARAMETER_BUFFER: u32 = 0x80EE;
+
 // State caching system for command queue.
 //
 // We track the current global state, which is based on
@@ -61,6 +64,7 @@ pub struct CommandQueue {
     pub(crate) share: Starc<Share>,
     features: hal::Features,
     vao: Option<native::VertexArray>,
+    transform_feedback: Option<u32>,
     state: State,
 }
 
@@ -70,11 +74,13 @@ impl CommandQueue {
         share: &Starc<Share>,
         features: hal::Features,
         vao: Option<native::VertexArray>,
+        transform_feedback: Option<u32>,
     ) -> Self {
         CommandQueue {
             share: share.clone(),
             features,
             vao,
+            transform_feedback,
             state: State::new(),
         }
     }
@@ -205,9 +211,10 @@ impl CommandQueue {
 
     fn present_by_copy(&self, swapchain: &Swapchain, index: hal::window::SwapImageIndex) {
         let gl = &self.share.context;
+        #[cfg(not(headless))]
         let extent = swapchain.extent;
 
-        #[cfg(wgl)]
+        #[cfg(any(wgl, egl))]
         swapchain.make_current();
 
         #[cfg(surfman)]
@@ -226,6 +233,13 @@ impl CommandQueue {
             .unwrap()
             .framebuffer_object;
 
+        // There is no window to blit into, read the frame back instead
+        #[cfg(headless)]
+        unsafe {
+            swapchain.read_back(gl, index);
+        }
+
+        #[cfg(not(headless))]
         unsafe {
             gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(swapchain.fbos[index as usize]));
             gl.bind_framebuffer(
@@ -274,7 +288,7 @@ impl CommandQueue {
         #[cfg(glutin)]
         swapchain.context.swap_buffers().unwrap();
 
-        #[cfg(wgl)]
+        #[cfg(any(wgl, egl))]
         swapchain.swap_buffers();
     }
 
@@ -291,6 +305,12 @@ impl CommandQueue {
             self.state.vao = true
         }
 
+        // Bind the transform feedback object
+        if let Some(transform_feedback) = self.transform_feedback {
+            let bind_transform_feedback = gl.extra.bind_transform_feedback.unwrap();
+            unsafe { bind_transform_feedback(glow::TRANSFORM_FEEDBACK, transform_feedback) };
+        }
+
         // Reset indirect draw buffer
         if self
             .share
@@ -467,6 +487,137 @@ impl CommandQueue {
                     error!("Instanced indexed drawing is not supported");
                 }
             }
+            com::Command::DrawIndirect {
+                primitive,
+                index_type,
+                buffer,
+                offset,
+                draw_count,
+                stride,
+                count_buffer,
+            } => {
+                let gl = &self.share.context;
+                unsafe { gl.bind_buffer(glow::DRAW_INDIRECT_BUFFER, Some(buffer)) };
+
+                if let Some((count_buffer, count_buffer_offset)) = count_buffer {
+                    unsafe { gl.bind_buffer(PARAMETER_BUFFER, Some(count_buffer)) };
+                    match index_type {
+                        None => {
+                            let multi_draw_arrays_indirect_count = gl
+                                .extra
+                                .multi_draw_arrays_indirect_count
+                                .expect("DRAW_INDIRECT_COUNT feature is not enabled");
+                            unsafe {
+                                multi_draw_arrays_indirect_count(
+                                    primitive,
+                                    offset as usize as *const _,
+                                    count_buffer_offset as _,
+                                    draw_count as _,
+                                    stride as _,
+                                );
+                            }
+                        }
+                        Some(index_type) => {
+                            let multi_draw_elements_indirect_count = gl
+                                .extra
+                                .multi_draw_elements_indirect_count
+                                .expect("DRAW_INDIRECT_COUNT feature is not enabled");
+                            unsafe {
+                                multi_draw_elements_indirect_count(
+                                    primitive,
+                                    index_type,
+                                    offset as usize as *const _,
+                                    count_buffer_offset as _,
+                                    draw_count as _,
+                                    stride as _,
+                                );
+                            }
+                        }
+                    }
+                    unsafe { gl.bind_buffer(PARAMETER_BUFFER, None) };
+                } else {
+                    // Plain indirect draws only source a single draw each.
+                    let offsets =
+                        (0 .. draw_count).map(|i| offset as usize + i as usize * stride as usize);
+                    match (
+                        index_type,
+                        gl.extra.draw_arrays_indirect,
+                        gl.extra.draw_elements_indirect,
+                    ) {
+                        (None, Some(draw_arrays_indirect), _) => {
+                            for offset in offsets {
+                                unsafe { draw_arrays_indirect(primitive, offset as *const _) };
+                            }
+                        }
+                        (Some(index_type), _, Some(draw_elements_indirect)) => {
+                            for offset in offsets {
+                                unsafe {
+                                    draw_elements_indirect(
+                                        primitive,
+                                        index_type,
+                                        offset as *const _,
+                                    )
+                                };
+                            }
+                        }
+                        _ => error!("Indirect drawing is not supported"),
+                    }
+                }
+            }
+            com::Command::BeginTransformFeedback(primitive) => {
+                let begin_transform_feedback = self
+                    .share
+                    .context
+                    .extra
+                    .begin_transform_feedback
+                    .expect("TRANSFORM_FEEDBACK feature is not enabled");
+                unsafe { begin_transform_feedback(primitive) };
+            }
+            com::Command::EndTransformFeedback => {
+                let end_transform_feedback = self
+                    .share
+                    .context
+                    .extra
+                    .end_transform_feedback
+                    .expect("TRANSFORM_FEEDBACK feature is not enabled");
+                unsafe { end_transform_feedback() };
+            }
+            com::Command::BeginQuery(target, object) => {
+                let begin_query = self
+                    .share
+                    .context
+                    .extra
+                    .begin_query
+                    .expect("Query pools are not supported");
+                unsafe { begin_query(target, object) };
+            }
+            com::Command::EndQuery(target) => {
+                let end_query = self
+                    .share
+                    .context
+                    .extra
+                    .end_query
+                    .expect("Query pools are not supported");
+                unsafe { end_query(target) };
+            }
+            com::Command::BeginConditionalRender(object, mode) => {
+                let begin_conditional_render = self
+                    .share
+                    .context
+                    .extra
+                    .begin_conditional_render
+                    .expect("CONDITIONAL_RENDERING_QUERY feature is not enabled");
+                unsafe { begin_conditional_render(object, mode) };
+            }
+            com::Command::EndConditionalRender => {
+                let end_conditional_render = self
+                    .share
+                    .context
+                    .extra
+                    .end_conditional_render
+                    .expect("CONDITIONAL_RENDERING_QUERY feature is not enabled");
+                unsafe { end_conditional_render() };
+            }
             com::Command::Dispatch(count) => {
                 // Capability support is given by which queue types will be exposed.
                 // If there is no compute support, this pattern should never be reached
@@ -1154,8 +1305,8 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
         T: 'a + Borrow<com::CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
         S: 'a + Borrow<native::Semaphore>,
-        Iw: IntoIterator<Item = (&'a S, hal::pso::PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>,
+        Iw: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue, hal::pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue)>,
     {
         use crate::pool::BufferMemory;
         {
@@ -1181,6 +1332,25 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
             }
         }
 
+        // Commands are executed in submission order, so only the timeline semaphores
+        // need to track when the signalling submission completes.
+        for (semaphore, value) in submit_info.signal_semaphores {
+            if let Some(ref timeline) = semaphore.borrow().timeline {
+                let mut timeline = timeline.lock();
+                if self.share.private_caps.sync {
+                    let sync = self
+                        .share
+                        .context
+                        .fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                        .unwrap();
+                    timeline.pending.push((value, sync));
+                } else {
+                    self.share.context.flush();
+                    timeline.value = timeline.value.max(value);
+                }
+            }
+        }
+
         if let Some(fence) = fence {
             if self.share.private_caps.sync {
                 fence.0.set(native::FenceInner::Pending(Some(
@@ -1196,6 +1366,36 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
         }
     }
 
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        _binding: hal::queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        _fence: Option<&native::Fence>,
+    ) where
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b native::Buffer,
+                &'b [hal::memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [hal::memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [hal::memory::SparseImageBind<&'b native::Memory>],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
     unsafe fn present<'a, W, Is, S, Iw>(
         &mut self,
         swapchains: Is,
@@ -1211,7 +1411,7 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
             self.present_by_copy(swapchain.borrow(), index);
         }
 
-        #[cfg(wgl)]
+        #[cfg(any(wgl, egl))]
         self.share.instance_context.make_current();
 
         Ok(None)
@@ -1229,7 +1429,7 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
             .expect("No swapchain is configured!");
         self.present_by_copy(swapchain, 0);
 
-        #[cfg(wgl)]
+        #[cfg(any(wgl, egl))]
         self.share.instance_context.make_current();
 
         Ok(None)
@@ -0,0 +1,723 @@
+//! EGL-based OpenGL backend for GFX-hal, without any windowing system dependency.
+//!
+//! The instance is created on one of the following EGL platforms:
+//!  - surfaceless (Mesa), for offscreen rendering, with `hal::Instance::create`
+//!  - Wayland, with `Instance::create_from_wayland_display`
+//!  - GBM, for direct rendering on KMS, with `Instance::create_from_gbm_device`
+
+use crate::{conv, device::Device, native, Backend, GlContainer, PhysicalDevice, QueueFamily};
+
+use std::{
+    ffi::{CStr, CString},
+    iter,
+    os::raw::c_void,
+    ptr,
+};
+
+use glow::HasContext;
+use hal::{adapter::Adapter, format as f, image, window};
+
+use arrayvec::ArrayVec;
+use lazy_static::lazy_static;
+
+mod ffi_types {
+    #![allow(non_camel_case_types, dead_code)]
+    use std::os::raw;
+
+    pub type khronos_utime_nanoseconds_t = khronos_uint64_t;
+    pub type khronos_uint64_t = u64;
+    pub type khronos_ssize_t = raw::c_long;
+    pub type EGLint = i32;
+    pub type EGLNativeDisplayType = *const raw::c_void;
+    pub type EGLNativePixmapType = *const raw::c_void;
+    pub type EGLNativeWindowType = *const raw::c_void;
+    pub type NativeDisplayType = EGLNativeDisplayType;
+    pub type NativePixmapType = EGLNativePixmapType;
+    pub type NativeWindowType = EGLNativeWindowType;
+}
+
+pub mod egl_sys {
+    pub use super::ffi_types::*;
+    include!(concat!(env!("OUT_DIR"), "/egl_sys.rs"));
+}
+
+pub mod egl_ext_sys {
+    pub use super::ffi_types::*;
+    include!(concat!(env!("OUT_DIR"), "/egl_ext_sys.rs"));
+}
+
+use self::egl_sys::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLenum, EGLint};
+
+#[link(name = "EGL")]
+extern "C" {}
+
+#[cfg(feature = "egl-wayland")]
+#[link(name = "wayland-egl")]
+extern "C" {
+    fn wl_egl_window_create(surface: *mut c_void, width: i32, height: i32) -> *mut c_void;
+    fn wl_egl_window_resize(window: *mut c_void, width: i32, height: i32, dx: i32, dy: i32);
+    fn wl_egl_window_destroy(window: *mut c_void);
+}
+
+/// `GBM_FORMAT_ARGB8888`, the format expected from GBM surfaces.
+const GBM_FORMAT_ARGB8888: EGLint = 0x3432_5241;
+
+pub(crate) struct Entry {
+    /// Client extensions, not tied to any display.
+    extensions: Vec<String>,
+    pub(crate) egl: egl_ext_sys::Egl,
+}
+
+impl Entry {
+    pub fn new() -> Self {
+        unsafe {
+            // Only available with `EGL_EXT_client_extensions`
+            let extensions = egl_sys::QueryString(egl_sys::NO_DISPLAY, egl_sys::EXTENSIONS as _);
+            let extensions = if extensions.is_null() {
+                Vec::new()
+            } else {
+                CStr::from_ptr(extensions)
+                    .to_string_lossy()
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect()
+            };
+
+            let egl = egl_ext_sys::Egl::load_with(|sym| {
+                let sym = CString::new(sym.as_bytes()).unwrap();
+                egl_sys::GetProcAddress(sym.as_ptr()) as *const _
+            });
+
+            Entry { extensions, egl }
+        }
+    }
+
+    fn supports(&self, extension: &str) -> bool {
+        self.extensions.iter().any(|ext| ext == extension)
+    }
+}
+
+lazy_static! {
+    // Entry function pointers
+    pub(crate) static ref EGL_ENTRY: Entry = Entry::new();
+}
+
+/// EGL platform the instance display was created on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Platform {
+    Surfaceless,
+    #[cfg(feature = "egl-wayland")]
+    Wayland,
+    Gbm,
+}
+
+impl Platform {
+    fn raw(&self) -> EGLenum {
+        match *self {
+            Platform::Surfaceless => egl_sys::PLATFORM_SURFACELESS_MESA,
+            #[cfg(feature = "egl-wayland")]
+            Platform::Wayland => egl_sys::PLATFORM_WAYLAND_KHR,
+            Platform::Gbm => egl_sys::PLATFORM_GBM_KHR,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match *self {
+            Platform::Surfaceless => "EGL_MESA_platform_surfaceless",
+            #[cfg(feature = "egl-wayland")]
+            Platform::Wayland => "EGL_KHR_platform_wayland",
+            Platform::Gbm => "EGL_KHR_platform_gbm",
+        }
+    }
+}
+
+pub struct Instance {
+    pub(crate) ctxt: DeviceContext,
+    platform: Platform,
+    // Only needed to match the display of window handles
+    #[allow(dead_code)]
+    native_display: *mut c_void,
+}
+
+// TODO
+unsafe impl Send for Instance {}
+unsafe impl Sync for Instance {}
+
+impl Instance {
+    /// Create an instance on a `wl_display`, required for presenting to Wayland surfaces.
+    #[cfg(feature = "egl-wayland")]
+    pub unsafe fn create_from_wayland_display(
+        display: *mut c_void,
+    ) -> Result<Self, hal::UnsupportedBackend> {
+        Self::create_on_platform(Platform::Wayland, display)
+    }
+
+    /// Create an instance on a `gbm_device`, required for presenting to GBM surfaces.
+    pub unsafe fn create_from_gbm_device(
+        device: *mut c_void,
+    ) -> Result<Self, hal::UnsupportedBackend> {
+        Self::create_on_platform(Platform::Gbm, device)
+    }
+
+    unsafe fn create_on_platform(
+        platform: Platform,
+        native_display: *mut c_void,
+    ) -> Result<Self, hal::UnsupportedBackend> {
+        let display = if EGL_ENTRY.supports("EGL_EXT_platform_base")
+            && EGL_ENTRY.supports(platform.extension())
+        {
+            EGL_ENTRY
+                .egl
+                .GetPlatformDisplayEXT(platform.raw(), native_display, ptr::null())
+        } else {
+            // Implementations resolve the platform from the native display themselves
+            egl_sys::GetDisplay(native_display as *const _)
+        };
+        if display == egl_sys::NO_DISPLAY {
+            warn!(
+                "Unable to get an EGL display for the {:?} platform",
+                platform
+            );
+            return Err(hal::UnsupportedBackend);
+        }
+
+        let (mut major, mut minor) = (0, 0);
+        if egl_sys::Initialize(display, &mut major, &mut minor) == egl_sys::FALSE {
+            warn!("Unable to initialize the EGL display");
+            return Err(hal::UnsupportedBackend);
+        }
+        info!("EGL version: {}.{}", major, minor);
+
+        let ctxt = DeviceContext::new(display, platform).ok_or(hal::UnsupportedBackend)?;
+        ctxt.make_current();
+
+        Ok(Instance {
+            ctxt,
+            platform,
+            native_display,
+        })
+    }
+
+    /// Create a surface for a `wl_surface`.
+    ///
+    /// The instance must have been created on the display of the surface.
+    #[cfg(feature = "egl-wayland")]
+    pub unsafe fn create_surface_from_wayland(&self, surface: *mut c_void) -> Surface {
+        assert_eq!(self.platform, Platform::Wayland);
+        Surface {
+            window: NativeWindow::Wayland {
+                surface,
+                window: ptr::null_mut(),
+            },
+            swapchain: None,
+            renderbuffer: None,
+        }
+    }
+
+    /// Create a surface for a `gbm_surface` of the given size, using `GBM_FORMAT_ARGB8888`.
+    ///
+    /// The instance must have been created on the device of the surface.
+    pub unsafe fn create_surface_from_gbm(
+        &self,
+        surface: *mut c_void,
+        extent: window::Extent2D,
+    ) -> Surface {
+        assert_eq!(self.platform, Platform::Gbm);
+        Surface {
+            window: NativeWindow::Gbm { surface, extent },
+            swapchain: None,
+            renderbuffer: None,
+        }
+    }
+}
+
+impl hal::Instance<Backend> for Instance {
+    fn create(_name: &str, _version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        unsafe { Self::create_on_platform(Platform::Surfaceless, egl_sys::DEFAULT_DISPLAY as _) }
+    }
+
+    fn enumerate_adapters(&self) -> Vec<Adapter<Backend>> {
+        self.ctxt.make_current();
+        let gl_container = GlContainer::from_fn_proc(|s| unsafe {
+            let sym = CString::new(s.as_bytes()).unwrap();
+            egl_sys::GetProcAddress(sym.as_ptr()) as *const _
+        });
+        let adapter = PhysicalDevice::new_adapter(self.ctxt, gl_container);
+        vec![adapter]
+    }
+
+    unsafe fn create_surface(
+        &self,
+        has_handle: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface, hal::window::InitError> {
+        match has_handle.raw_window_handle() {
+            #[cfg(feature = "egl-wayland")]
+            raw_window_handle::RawWindowHandle::Wayland(handle)
+                if self.platform == Platform::Wayland && handle.display == self.native_display =>
+            {
+                Ok(self.create_surface_from_wayland(handle.surface))
+            }
+            _ => Err(hal::window::InitError::UnsupportedWindowHandle),
+        }
+    }
+
+    unsafe fn destroy_surface(&self, _surface: Surface) {}
+}
+
+#[derive(Debug)]
+enum NativeWindow {
+    #[cfg(feature = "egl-wayland")]
+    Wayland {
+        surface: *mut c_void,
+        /// `wl_egl_window`, created with the first swapchain.
+        window: *mut c_void,
+    },
+    Gbm {
+        surface: *mut c_void,
+        extent: window::Extent2D,
+    },
+}
+
+#[derive(Debug)]
+pub struct Surface {
+    window: NativeWindow,
+    pub(crate) swapchain: Option<Swapchain>,
+    renderbuffer: Option<native::Renderbuffer>,
+}
+
+// TODO
+unsafe impl Send for Surface {}
+unsafe impl Sync for Surface {}
+
+impl Surface {
+    /// Return the native window to create EGL surfaces from, resized to the given extent.
+    fn native_window(&mut self, extent: window::Extent2D) -> *mut c_void {
+        match self.window {
+            #[cfg(feature = "egl-wayland")]
+            NativeWindow::Wayland {
+                surface,
+                ref mut window,
+            } => unsafe {
+                if window.is_null() {
+                    *window = wl_egl_window_create(surface, extent.width as _, extent.height as _);
+                } else {
+                    wl_egl_window_resize(*window, extent.width as _, extent.height as _, 0, 0);
+                }
+                *window
+            },
+            NativeWindow::Gbm { surface, .. } => surface,
+        }
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        // The EGL surface has to go before the window it was created for
+        self.swapchain = None;
+        #[cfg(feature = "egl-wayland")]
+        {
+            if let NativeWindow::Wayland { window, .. } = self.window {
+                if !window.is_null() {
+                    unsafe { wl_egl_window_destroy(window) };
+                }
+            }
+        }
+    }
+}
+
+impl window::Surface<Backend> for Surface {
+    fn supports_queue_family(&self, _queue_family: &QueueFamily) -> bool {
+        true
+    }
+
+    fn capabilities(&self, _physical_device: &PhysicalDevice) -> window::SurfaceCapabilities {
+        let (current_extent, extents) = match self.window {
+            #[cfg(feature = "egl-wayland")]
+            NativeWindow::Wayland { .. } => (
+                None,
+                window::Extent2D {
+                    width: 1,
+                    height: 1,
+                } ..= window::Extent2D {
+                    width: 4096,
+                    height: 4096,
+                },
+            ),
+            NativeWindow::Gbm { extent, .. } => (Some(extent), extent ..= extent),
+        };
+
+        window::SurfaceCapabilities {
+            present_modes: window::PresentMode::FIFO, //TODO
+            composite_alpha_modes: window::CompositeAlphaMode::OPAQUE, //TODO
+            image_count: 2 ..= 2,
+            current_extent,
+            extents,
+            max_image_layers: 1,
+            usage: image::Usage::COLOR_ATTACHMENT | image::Usage::TRANSFER_SRC,
+        }
+    }
+
+    fn supported_formats(&self, _physical_device: &PhysicalDevice) -> Option<Vec<f::Format>> {
+        Some(vec![f::Format::Rgba8Srgb, f::Format::Bgra8Srgb])
+    }
+}
+
+impl window::PresentationSurface<Backend> for Surface {
+    type SwapchainImage = native::ImageView;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        let gl = &device.share.context;
+
+        let context = match self.swapchain.take() {
+            Some(old) => {
+                for fbo in old.fbos {
+                    gl.delete_framebuffer(fbo);
+                }
+                self.native_window(config.extent);
+                old.context
+            }
+            None => PresentContext::new(self, &device.share.instance_context, config.extent)?,
+        };
+        context.make_current();
+
+        if self.renderbuffer.is_none() {
+            self.renderbuffer = Some(gl.create_renderbuffer().unwrap());
+        }
+
+        let desc = conv::describe_format(config.format).unwrap();
+        gl.bind_renderbuffer(glow::RENDERBUFFER, self.renderbuffer);
+        gl.renderbuffer_storage(
+            glow::RENDERBUFFER,
+            desc.tex_internal,
+            config.extent.width as i32,
+            config.extent.height as i32,
+        );
+
+        let fbo = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(fbo));
+        gl.framebuffer_renderbuffer(
+            glow::READ_FRAMEBUFFER,
+            glow::COLOR_ATTACHMENT0,
+            glow::RENDERBUFFER,
+            self.renderbuffer,
+        );
+        self.swapchain = Some(Swapchain {
+            context,
+            extent: config.extent,
+            fbos: iter::once(fbo).collect(),
+        });
+
+        Ok(())
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, device: &Device) {
+        let gl = &device.share.context;
+        if let Some(old) = self.swapchain.take() {
+            for fbo in old.fbos {
+                gl.delete_framebuffer(fbo);
+            }
+        }
+        if let Some(rbo) = self.renderbuffer.take() {
+            gl.delete_renderbuffer(rbo);
+        }
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        _timeout_ns: u64,
+    ) -> Result<(Self::SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
+        let image = native::ImageView::Renderbuffer(self.renderbuffer.unwrap());
+        Ok((image, None))
+    }
+}
+
+#[derive(Debug)]
+pub struct Swapchain {
+    pub(crate) fbos: ArrayVec<[native::RawFrameBuffer; 3]>,
+    pub(crate) context: PresentContext,
+    pub(crate) extent: window::Extent2D,
+}
+impl Swapchain {
+    pub(crate) fn make_current(&self) {
+        self.context.make_current();
+    }
+
+    pub(crate) fn swap_buffers(&self) {
+        self.context.swap_buffers();
+    }
+}
+
+impl window::Swapchain<Backend> for Swapchain {
+    unsafe fn acquire_image(
+        &mut self,
+        _timeout_ns: u64,
+        _semaphore: Option<&native::Semaphore>,
+        _fence: Option<&native::Fence>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        Ok((0, None)) // TODO
+    }
+}
+
+/// Client API of the contexts, desktop OpenGL is preferred over OpenGL ES.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Api {
+    Gl,
+    Gles,
+}
+
+impl Api {
+    fn bind(&self) -> bool {
+        let api = match *self {
+            Api::Gl => egl_sys::OPENGL_API,
+            Api::Gles => egl_sys::OPENGL_ES_API,
+        };
+        unsafe { egl_sys::BindAPI(api) == egl_sys::TRUE }
+    }
+
+    fn renderable_type(&self) -> EGLint {
+        match *self {
+            Api::Gl => egl_sys::OPENGL_BIT as _,
+            Api::Gles => egl_sys::OPENGL_ES3_BIT_KHR as _,
+        }
+    }
+
+    fn context_attributes(&self) -> Vec<EGLint> {
+        match *self {
+            Api::Gl => vec![
+                egl_sys::CONTEXT_MAJOR_VERSION_KHR as _,
+                3,
+                egl_sys::CONTEXT_MINOR_VERSION_KHR as _,
+                3,
+                egl_sys::CONTEXT_OPENGL_PROFILE_MASK_KHR as _,
+                egl_sys::CONTEXT_OPENGL_CORE_PROFILE_BIT_KHR as _,
+                egl_sys::NONE as _,
+            ],
+            Api::Gles => vec![
+                egl_sys::CONTEXT_MAJOR_VERSION_KHR as _,
+                3,
+                egl_sys::NONE as _,
+            ],
+        }
+    }
+}
+
+/// Pick a RGBA8 config for the given surface types.
+///
+/// A visual is required for GBM window surfaces, which only accept matching configs.
+unsafe fn choose_config(
+    display: EGLDisplay,
+    api: Api,
+    surface_type: EGLint,
+    visual: Option<EGLint>,
+) -> Option<EGLConfig> {
+    let attributes = [
+        egl_sys::SURFACE_TYPE as _,
+        surface_type,
+        egl_sys::RENDERABLE_TYPE as _,
+        api.renderable_type(),
+        egl_sys::RED_SIZE as _,
+        8,
+        egl_sys::GREEN_SIZE as _,
+        8,
+        egl_sys::BLUE_SIZE as _,
+        8,
+        egl_sys::ALPHA_SIZE as _,
+        8,
+        egl_sys::NONE as _,
+    ];
+
+    let mut num_configs = 0;
+    let mut configs = [ptr::null(); 64];
+    if egl_sys::ChooseConfig(
+        display,
+        attributes.as_ptr(),
+        configs.as_mut_ptr(),
+        configs.len() as _,
+        &mut num_configs,
+    ) == egl_sys::FALSE
+    {
+        return None;
+    }
+
+    configs[.. num_configs as usize]
+        .iter()
+        .cloned()
+        .find(|&config| match visual {
+            Some(visual) => {
+                let mut id = 0;
+                egl_sys::GetConfigAttrib(display, config, egl_sys::NATIVE_VISUAL_ID as _, &mut id);
+                id == visual
+            }
+            None => true,
+        })
+}
+
+/// Owned context for devices and instances.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct DeviceContext {
+    display: EGLDisplay,
+    api: Api,
+    context: EGLContext,
+
+    /// Pbuffer surface, if the display doesn't support surfaceless contexts.
+    ///
+    /// Not used for actual presentation.
+    surface: EGLSurface,
+}
+
+// TODO
+unsafe impl Send for DeviceContext {}
+unsafe impl Sync for DeviceContext {}
+
+impl DeviceContext {
+    unsafe fn new(display: EGLDisplay, platform: Platform) -> Option<Self> {
+        let extensions = CStr::from_ptr(egl_sys::QueryString(display, egl_sys::EXTENSIONS as _))
+            .to_string_lossy()
+            .into_owned();
+        let surfaceless = extensions
+            .split_whitespace()
+            .any(|ext| ext == "EGL_KHR_surfaceless_context");
+
+        let surface_type = match platform {
+            Platform::Surfaceless => egl_sys::PBUFFER_BIT,
+            _ => egl_sys::WINDOW_BIT,
+        };
+
+        for &api in &[Api::Gl, Api::Gles] {
+            if !api.bind() {
+                continue;
+            }
+            let config = match choose_config(display, api, surface_type as _, None) {
+                Some(config) => config,
+                None => continue,
+            };
+            let context = egl_sys::CreateContext(
+                display,
+                config,
+                egl_sys::NO_CONTEXT,
+                api.context_attributes().as_ptr(),
+            );
+            if context == egl_sys::NO_CONTEXT {
+                continue;
+            }
+
+            let surface = if surfaceless || platform != Platform::Surfaceless {
+                egl_sys::NO_SURFACE
+            } else {
+                let attributes = [
+                    egl_sys::WIDTH as EGLint,
+                    1,
+                    egl_sys::HEIGHT as _,
+                    1,
+                    egl_sys::NONE as _,
+                ];
+                egl_sys::CreatePbufferSurface(display, config, attributes.as_ptr())
+            };
+
+            info!("Created an EGL context for {:?} on {:?}", api, platform);
+            return Some(DeviceContext {
+                display,
+                api,
+                context,
+                surface,
+            });
+        }
+
+        warn!("Unable to create an EGL context");
+        None
+    }
+
+    pub(crate) fn make_current(&self) {
+        unsafe {
+            egl_sys::MakeCurrent(self.display, self.surface, self.surface, self.context);
+        }
+    }
+}
+
+/// Owned context for swapchains which soley is required for presentation.
+#[derive(Debug)]
+pub(crate) struct PresentContext {
+    display: EGLDisplay,
+    /// Owned context, sharing its objects with the device context.
+    context: EGLContext,
+    /// Window surface of the corresponding presentation surface.
+    surface: EGLSurface,
+}
+
+// TODO
+unsafe impl Send for PresentContext {}
+unsafe impl Sync for PresentContext {}
+
+impl PresentContext {
+    pub(crate) fn new(
+        surface: &mut Surface,
+        device_ctxt: &DeviceContext,
+        extent: window::Extent2D,
+    ) -> Result<Self, window::CreationError> {
+        let visual = match surface.window {
+            #[cfg(feature = "egl-wayland")]
+            NativeWindow::Wayland { .. } => None,
+            NativeWindow::Gbm { .. } => Some(GBM_FORMAT_ARGB8888),
+        };
+        let native_window = surface.native_window(extent);
+
+        unsafe {
+            let display = device_ctxt.display;
+            let api = device_ctxt.api;
+            let config = choose_config(display, api, egl_sys::WINDOW_BIT as _, visual)
+                .ok_or(window::CreationError::WindowInUse(hal::device::WindowInUse))?;
+
+            let surface =
+                egl_sys::CreateWindowSurface(display, config, native_window as _, ptr::null());
+            if surface == egl_sys::NO_SURFACE {
+                return Err(window::CreationError::WindowInUse(hal::device::WindowInUse));
+            }
+
+            api.bind();
+            let context = egl_sys::CreateContext(
+                display,
+                config,
+                device_ctxt.context,
+                api.context_attributes().as_ptr(),
+            );
+            if context == egl_sys::NO_CONTEXT {
+                egl_sys::DestroySurface(display, surface);
+                return Err(window::CreationError::WindowInUse(hal::device::WindowInUse));
+            }
+
+            egl_sys::MakeCurrent(display, surface, surface, context);
+
+            Ok(PresentContext {
+                display,
+                context,
+                surface,
+            })
+        }
+    }
+
+    pub(crate) fn make_current(&self) {
+        unsafe {
+            egl_sys::MakeCurrent(self.display, self.surface, self.surface, self.context);
+        }
+    }
+
+    fn swap_buffers(&self) {
+        unsafe {
+            egl_sys::SwapBuffers(self.display, self.surface);
+        }
+    }
+}
+
+impl Drop for PresentContext {
+    fn drop(&mut self) {
+        // Both are only released once they aren't current anymore
+        unsafe {
+            egl_sys::DestroySurface(self.display, self.surface);
+            egl_sys::DestroyContext(self.display, self.context);
+        }
+    }
+}
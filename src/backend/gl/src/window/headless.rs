@@ -0,0 +1,426 @@
+//! Headless OpenGL backend for GFX-hal, built on the surfaceless Mesa platform of
+//! [Surfman](https://github.com/pcwalton/surfman).
+//!
+//! The contexts are not bound to any window surface, so no display server is required.
+//! Presented frames are read back into memory and can be inspected with
+//! [`Surface::last_frame`](struct.Surface.html#method.last_frame).
+
+use crate::{conv, native, Backend as B, Device, GlContainer, PhysicalDevice, QueueFamily};
+use hal::{adapter::Adapter, format as f, image, window};
+
+use arrayvec::ArrayVec;
+use glow::HasContext;
+use parking_lot::Mutex;
+use surfman::platform::unix::generic as sm;
+
+use std::fmt;
+use std::iter;
+
+thread_local! {
+    /// The thread-local surfman connection to the surfaceless platform
+    static SM_CONN: Result<sm::connection::Connection, surfman::Error> =
+        sm::connection::Connection::new();
+}
+
+/// Pixels of a presented frame, read back from the swapchain image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// Size of the frame in pixels.
+    pub extent: window::Extent2D,
+    /// Format of the pixels.
+    pub format: f::Format,
+    /// Tightly packed rows of pixels, from the top row to the bottom one.
+    pub data: Vec<u8>,
+}
+
+/// A surfman device together with a surfaceless context created on it.
+pub(crate) struct HeadlessContext {
+    device: sm::device::Device,
+    context: sm::context::Context,
+}
+
+impl fmt::Debug for HeadlessContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeadlessContext").finish()
+    }
+}
+
+impl HeadlessContext {
+    fn new(adapter: &sm::device::Adapter) -> Result<Self, surfman::Error> {
+        let mut device = SM_CONN.with(|c| {
+            c.as_ref()
+                .expect("Surfaceless platform is not available")
+                .create_device(adapter)
+        })?;
+        let context_descriptor =
+            device.create_context_descriptor(&Instance::get_default_context_attributes())?;
+        let mut context = device.create_context(&context_descriptor)?;
+        if let Err(err) = device.make_context_current(&context) {
+            device.destroy_context(&mut context)?;
+            return Err(err);
+        }
+        Ok(HeadlessContext { device, context })
+    }
+
+    pub(crate) fn get_proc_address(&self, symbol_name: &str) -> *const std::os::raw::c_void {
+        self.device.get_proc_address(&self.context, symbol_name) as *const _
+    }
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        // Contexts must be manually destroyed to prevent a panic
+        self.device
+            .destroy_context(&mut self.context)
+            .expect("TODO");
+    }
+}
+
+pub struct Instance {
+    adapters: Vec<sm::device::Adapter>,
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("adapters", &self.adapters.len())
+            .finish()
+    }
+}
+
+impl Instance {
+    fn get_default_context_attributes() -> surfman::ContextAttributes {
+        surfman::ContextAttributes {
+            version: surfman::GLVersion::new(3, 3),
+            flags: surfman::ContextAttributeFlags::ALPHA,
+        }
+    }
+
+    /// Create a surface that isn't backed by any window.
+    pub fn create_headless_surface(&self) -> Surface {
+        Surface {
+            swapchain: None,
+            renderbuffer: None,
+        }
+    }
+}
+
+impl hal::Instance<B> for Instance {
+    fn create(_: &str, _: u32) -> Result<Self, hal::UnsupportedBackend> {
+        SM_CONN.with(|c| {
+            let connection = match c {
+                Ok(connection) => connection,
+                Err(err) => {
+                    warn!("Unable to connect to the surfaceless platform: {:?}", err);
+                    return Err(hal::UnsupportedBackend);
+                }
+            };
+            // The software adapter is what runs under Mesa's software rasterizer
+            let adapters = vec![
+                connection.create_hardware_adapter(),
+                connection.create_software_adapter(),
+            ]
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+            if adapters.is_empty() {
+                return Err(hal::UnsupportedBackend);
+            }
+            Ok(Instance { adapters })
+        })
+    }
+
+    fn enumerate_adapters(&self) -> Vec<Adapter<B>> {
+        self.adapters
+            .iter()
+            .filter_map(|adapter| match HeadlessContext::new(adapter) {
+                Ok(context) => {
+                    let gl = GlContainer::from_headless_context(context);
+                    Some(PhysicalDevice::new_adapter((), gl))
+                }
+                Err(err) => {
+                    warn!("Unable to create a surfaceless context: {:?}", err);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    unsafe fn create_surface(
+        &self,
+        _: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface, window::InitError> {
+        Err(window::InitError::UnsupportedWindowHandle)
+    }
+
+    unsafe fn destroy_surface(&self, _surface: Surface) {}
+}
+
+#[derive(Debug)]
+pub struct Swapchain {
+    pub(crate) extent: window::Extent2D,
+    pub(crate) format: f::Format,
+    pub(crate) fbos: ArrayVec<[native::RawFrameBuffer; 3]>,
+    pub(crate) frame: Mutex<Option<Frame>>,
+    /// Index of the image returned by the next `acquire_image`.
+    pub(crate) next_image: window::SwapImageIndex,
+}
+
+impl Swapchain {
+    /// Return the pixels of the last presented frame, if any.
+    pub fn last_frame(&self) -> Option<Frame> {
+        self.frame.lock().clone()
+    }
+
+    /// Read the pixels of the given swapchain image back into memory.
+    pub(crate) unsafe fn read_back(&self, gl: &glow::Context, index: window::SwapImageIndex) {
+        let desc = conv::describe_format(self.format).unwrap();
+        let bytes_per_pixel = self.format.surface_desc().bits as usize / 8;
+        let row_size = self.extent.width as usize * bytes_per_pixel;
+        let mut data = vec![0; row_size * self.extent.height as usize];
+
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.fbos[index as usize]));
+        gl.read_pixels(
+            0,
+            0,
+            self.extent.width as _,
+            self.extent.height as _,
+            desc.tex_external,
+            desc.data_type,
+            &mut data,
+        );
+
+        // OpenGL returns the rows from the bottom one up
+        let mut flipped = Vec::with_capacity(data.len());
+        for row in data.chunks(row_size).rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        *self.frame.lock() = Some(Frame {
+            extent: self.extent,
+            format: self.format,
+            data: flipped,
+        });
+    }
+}
+
+impl window::Swapchain<B> for Swapchain {
+    unsafe fn acquire_image(
+        &mut self,
+        _timeout_ns: u64,
+        _semaphore: Option<&native::Semaphore>,
+        _fence: Option<&native::Fence>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        // Presenting reads the image back right away, so the images are handed out
+        // in turn without waiting.
+        let index = self.next_image;
+        self.next_image = (index + 1) % self.fbos.len() as window::SwapImageIndex;
+        Ok((index, None))
+    }
+}
+
+#[derive(Debug)]
+pub struct Surface {
+    pub(crate) swapchain: Option<Swapchain>,
+    renderbuffer: Option<native::Renderbuffer>,
+}
+
+impl Surface {
+    /// Return the pixels of the last frame presented to this surface, if any.
+    pub fn last_frame(&self) -> Option<Frame> {
+        self.swapchain.as_ref().and_then(Swapchain::last_frame)
+    }
+
+    fn swapchain_formats(&self) -> Vec<f::Format> {
+        vec![f::Format::Rgba8Srgb, f::Format::Rgba8Unorm]
+    }
+}
+
+impl window::PresentationSurface<B> for Surface {
+    type SwapchainImage = native::ImageView;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        let gl = &device.share.context;
+
+        if let Some(old) = self.swapchain.take() {
+            for fbo in old.fbos {
+                gl.delete_framebuffer(fbo);
+            }
+        }
+
+        if self.renderbuffer.is_none() {
+            self.renderbuffer = Some(gl.create_renderbuffer().unwrap());
+        }
+
+        let desc = conv::describe_format(config.format).unwrap();
+        gl.bind_renderbuffer(glow::RENDERBUFFER, self.renderbuffer);
+        gl.renderbuffer_storage(
+            glow::RENDERBUFFER,
+            desc.tex_internal,
+            config.extent.width as i32,
+            config.extent.height as i32,
+        );
+
+        let fbo = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(fbo));
+        gl.framebuffer_renderbuffer(
+            glow::READ_FRAMEBUFFER,
+            glow::COLOR_ATTACHMENT0,
+            glow::RENDERBUFFER,
+            self.renderbuffer,
+        );
+        self.swapchain = Some(Swapchain {
+            extent: config.extent,
+            format: config.format,
+            fbos: iter::once(fbo).collect(),
+            frame: Mutex::new(None),
+            next_image: 0,
+        });
+
+        Ok(())
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, device: &Device) {
+        let gl = &device.share.context;
+        if let Some(old) = self.swapchain.take() {
+            for fbo in old.fbos {
+                gl.delete_framebuffer(fbo);
+            }
+        }
+        if let Some(rbo) = self.renderbuffer.take() {
+            gl.delete_renderbuffer(rbo);
+        }
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        _timeout_ns: u64,
+    ) -> Result<(Self::SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
+        let image = native::ImageView::Renderbuffer(self.renderbuffer.unwrap());
+        Ok((image, None))
+    }
+}
+
+impl window::Surface<B> for Surface {
+    fn supports_queue_family(&self, _: &QueueFamily) -> bool {
+        true
+    }
+
+    fn capabilities(&self, _physical_device: &PhysicalDevice) -> window::SurfaceCapabilities {
+        window::SurfaceCapabilities {
+            present_modes: window::PresentMode::FIFO | window::PresentMode::IMMEDIATE,
+            composite_alpha_modes: window::CompositeAlphaMode::OPAQUE,
+            image_count: 1 ..= 2,
+            current_extent: None,
+            extents: window::Extent2D {
+                width: 1,
+                height: 1,
+            } ..= window::Extent2D {
+                width: 4096,
+                height: 4096,
+            },
+            max_image_layers: 1,
+            usage: image::Usage::COLOR_ATTACHMENT | image::Usage::TRANSFER_SRC,
+        }
+    }
+
+    fn supported_formats(&self, _physical_device: &PhysicalDevice) -> Option<Vec<f::Format>> {
+        Some(self.swapchain_formats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        command::{self, CommandBuffer as _},
+        device::Device as _,
+        pool::{CommandPool as _, CommandPoolCreateFlags},
+        queue::{CommandQueue as _, QueueFamily as _},
+        window::Swapchain as _,
+        Instance as _,
+    };
+
+    #[test]
+    fn present_frames() {
+        let instance = match Instance::create("test", 1) {
+            Ok(instance) => instance,
+            // The surfaceless platform isn't available on every machine.
+            Err(hal::UnsupportedBackend) => return,
+        };
+        let adapter = match instance.enumerate_adapters().into_iter().next() {
+            Some(adapter) => adapter,
+            None => return,
+        };
+        let family = adapter
+            .queue_families
+            .iter()
+            .find(|family| family.queue_type().supports_graphics())
+            .unwrap();
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(&[(family, &[1.0])], hal::Features::empty())
+                .unwrap()
+        };
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        let mut surface = instance.create_headless_surface();
+        let extent = window::Extent2D {
+            width: 4,
+            height: 2,
+        };
+        let mut config =
+            window::SwapchainConfig::new(extent.width, extent.height, f::Format::Rgba8Unorm, 2);
+        config.image_usage = image::Usage::COLOR_ATTACHMENT | image::Usage::TRANSFER_DST;
+
+        unsafe {
+            let (mut swapchain, images) =
+                device.create_swapchain(&mut surface, config, None).unwrap();
+            let mut pool = device
+                .create_command_pool(family.id(), CommandPoolCreateFlags::empty())
+                .unwrap();
+
+            let colors = [
+                ([1.0, 0.0, 0.0, 1.0], [0xFFu8, 0, 0, 0xFF]),
+                ([0.0, 0.0, 1.0, 1.0], [0, 0, 0xFF, 0xFF]),
+            ];
+            for (i, &(color, texel)) in colors.iter().enumerate() {
+                // Both images of the swapchain are used in turn.
+                let (index, _) = swapchain.acquire_image(!0, None, None).unwrap();
+                assert_eq!(index, i as window::SwapImageIndex);
+
+                let mut cmd_buffer = pool.allocate_one(command::Level::Primary);
+                cmd_buffer.begin_primary(command::CommandBufferFlags::ONE_TIME_SUBMIT);
+                cmd_buffer.clear_image(
+                    &images[index as usize],
+                    image::Layout::TransferDstOptimal,
+                    command::ClearValue {
+                        color: command::ClearColor { float32: color },
+                    },
+                    iter::once(image::SubresourceRange {
+                        aspects: f::Aspects::COLOR,
+                        levels: 0 .. 1,
+                        layers: 0 .. 1,
+                    }),
+                );
+                cmd_buffer.finish();
+                queue.submit_without_semaphores(iter::once(&cmd_buffer), None);
+                queue
+                    .present_without_semaphores(iter::once((&swapchain, index)))
+                    .unwrap();
+
+                let frame = swapchain.last_frame().unwrap();
+                assert_eq!(frame.extent, extent);
+                assert_eq!(frame.format, f::Format::Rgba8Unorm);
+                assert_eq!(frame.data, texel.repeat(8));
+            }
+        }
+    }
+}
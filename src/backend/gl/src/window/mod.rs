@@ -7,6 +7,12 @@ pub mod glutin;
 #[cfg(surfman)]
 pub mod surfman;
 
+#[cfg(headless)]
+pub mod headless;
+
+#[cfg(egl)]
+pub mod egl;
+
 #[cfg(wgl)]
 pub mod wgl;
 
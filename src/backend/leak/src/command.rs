@@ -0,0 +1,687 @@
+use crate::{device::unwrap_descriptor, native::Handle, Tracked};
+use hal::{
+    buffer,
+    command as com,
+    image,
+    memory,
+    pass,
+    pso,
+    query,
+    DrawCount,
+    IndexCount,
+    InstanceCount,
+    VertexCount,
+    VertexOffset,
+    WorkGroupCount,
+};
+use std::borrow::Borrow;
+use std::ops::Range;
+
+/// Tracked command buffer.
+///
+/// Command buffers are destroyed by being freed, or along with their pool.
+#[derive(Debug)]
+pub struct CommandBuffer<B: hal::Backend> {
+    pub(crate) raw: B::CommandBuffer,
+    pub(crate) id: u64,
+}
+
+impl<B: hal::Backend> CommandBuffer<B> {
+    /// Returns the wrapped command buffer.
+    pub fn raw(&self) -> &B::CommandBuffer {
+        &self.raw
+    }
+}
+
+fn barriers<'a, B: hal::Backend, T>(barriers: T) -> Vec<memory::Barrier<'a, B>>
+where
+    T: IntoIterator,
+    T::Item: Borrow<memory::Barrier<'a, Tracked<B>>>,
+{
+    barriers
+        .into_iter()
+        .map(|barrier| match *barrier.borrow() {
+            memory::Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+            memory::Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+            memory::Barrier::Buffer {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => memory::Barrier::Buffer {
+                states: states.clone(),
+                target: &target.raw,
+                range: range.clone(),
+                families: families.clone(),
+            },
+            memory::Barrier::Image {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => memory::Barrier::Image {
+                states: states.clone(),
+                target: &target.raw,
+                range: range.clone(),
+                families: families.clone(),
+            },
+        })
+        .collect()
+}
+
+/// Unwraps the descriptors pushed into a command buffer.
+fn push_writes<'a, B: hal::Backend, I, J>(
+    writes: I,
+) -> Vec<pso::DescriptorWrite<Vec<pso::Descriptor<'a, B>>>>
+where
+    I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+    J: IntoIterator,
+    J::Item: Borrow<pso::Descriptor<'a, Tracked<B>>>,
+{
+    writes
+        .into_iter()
+        .map(|write| pso::DescriptorWrite {
+            binding: write.binding,
+            array_offset: write.array_offset,
+            descriptors: write
+                .descriptors
+                .into_iter()
+                .map(|descriptor| unwrap_descriptor(descriptor.borrow()))
+                .collect(),
+        })
+        .collect()
+}
+
+impl<B: hal::Backend> com::CommandBuffer<Tracked<B>> for CommandBuffer<B> {
+    unsafe fn begin(
+        &mut self,
+        flags: com::CommandBufferFlags,
+        inheritance_info: com::CommandBufferInheritanceInfo<Tracked<B>>,
+    ) {
+        let info = com::CommandBufferInheritanceInfo {
+            subpass: inheritance_info
+                .subpass
+                .as_ref()
+                .map(|subpass| pass::Subpass {
+                    index: subpass.index,
+                    main_pass: &subpass.main_pass.raw,
+                }),
+            framebuffer: inheritance_info
+                .framebuffer
+                .map(|framebuffer| &framebuffer.raw),
+            occlusion_query_enable: inheritance_info.occlusion_query_enable,
+            occlusion_query_flags: inheritance_info.occlusion_query_flags,
+            pipeline_statistics: inheritance_info.pipeline_statistics,
+        };
+        self.raw.begin(flags, info);
+    }
+
+    unsafe fn finish(&mut self) {
+        self.raw.finish();
+    }
+
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources);
+    }
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        dependencies: memory::Dependencies,
+        barriers: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Tracked<B>>>,
+    {
+        let barriers = self::barriers(barriers);
+        self.raw.pipeline_barrier(stages, dependencies, barriers);
+    }
+
+    unsafe fn fill_buffer(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        range: buffer::SubRange,
+        data: u32,
+    ) {
+        self.raw.fill_buffer(&buffer.raw, range, data);
+    }
+
+    unsafe fn update_buffer(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        data: &[u8],
+    ) {
+        self.raw.update_buffer(&buffer.raw, offset, data);
+    }
+
+    unsafe fn clear_image<T>(
+        &mut self,
+        image: &Handle<B::Image>,
+        layout: image::Layout,
+        value: com::ClearValue,
+        subresource_ranges: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+        self.raw
+            .clear_image(&image.raw, layout, value, subresource_ranges);
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<com::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        self.raw.clear_attachments(clears, rects);
+    }
+
+    unsafe fn resolve_image<T>(
+        &mut self,
+        src: &Handle<B::Image>,
+        src_layout: image::Layout,
+        dst: &Handle<B::Image>,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageResolve>,
+    {
+        self.raw
+            .resolve_image(&src.raw, src_layout, &dst.raw, dst_layout, regions);
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        src: &Handle<B::Image>,
+        src_layout: image::Layout,
+        dst: &Handle<B::Image>,
+        dst_layout: image::Layout,
+        filter: image::Filter,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageBlit>,
+    {
+        self.raw
+            .blit_image(&src.raw, src_layout, &dst.raw, dst_layout, filter, regions);
+    }
+
+    unsafe fn bind_index_buffer(&mut self, view: buffer::IndexBufferView<Tracked<B>>) {
+        self.raw.bind_index_buffer(buffer::IndexBufferView {
+            buffer: &view.buffer.raw,
+            range: view.range,
+            index_type: view.index_type,
+        });
+    }
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, first_binding: pso::BufferIndex, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<Handle<B::Buffer>>,
+    {
+        let buffers = buffers.into_iter().collect::<Vec<_>>();
+        self.raw.bind_vertex_buffers(
+            first_binding,
+            buffers
+                .iter()
+                .map(|(buffer, range)| (&buffer.borrow().raw, range.clone())),
+        );
+    }
+
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, first_binding: u32, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<Handle<B::Buffer>>,
+    {
+        let buffers = buffers.into_iter().collect::<Vec<_>>();
+        self.raw.bind_transform_feedback_buffers(
+            first_binding,
+            buffers
+                .iter()
+                .map(|(buffer, range)| (&buffer.borrow().raw, range.clone())),
+        );
+    }
+
+    unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        self.raw.set_viewports(first_viewport, viewports);
+    }
+
+    unsafe fn set_scissors<T>(&mut self, first_scissor: u32, rects: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        self.raw.set_scissors(first_scissor, rects);
+    }
+
+    unsafe fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.raw.set_stencil_reference(faces, value);
+    }
+
+    unsafe fn set_stencil_read_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.raw.set_stencil_read_mask(faces, value);
+    }
+
+    unsafe fn set_stencil_write_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.raw.set_stencil_write_mask(faces, value);
+    }
+
+    unsafe fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.raw.set_blend_constants(color);
+    }
+
+    unsafe fn set_depth_bounds(&mut self, bounds: Range<f32>) {
+        self.raw.set_depth_bounds(bounds);
+    }
+
+    unsafe fn set_line_width(&mut self, width: f32) {
+        self.raw.set_line_width(width);
+    }
+
+    unsafe fn set_depth_bias(&mut self, depth_bias: pso::DepthBias) {
+        self.raw.set_depth_bias(depth_bias);
+    }
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        render_pass: &Handle<B::RenderPass>,
+        framebuffer: &Handle<B::Framebuffer>,
+        render_area: pso::Rect,
+        clear_values: T,
+        first_subpass: com::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ClearValue>,
+    {
+        self.raw.begin_render_pass(
+            &render_pass.raw,
+            &framebuffer.raw,
+            render_area,
+            clear_values,
+            first_subpass,
+        );
+    }
+
+    unsafe fn next_subpass(&mut self, contents: com::SubpassContents) {
+        self.raw.next_subpass(contents);
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        self.raw.end_render_pass();
+    }
+
+    unsafe fn bind_graphics_pipeline(&mut self, pipeline: &Handle<B::GraphicsPipeline>) {
+        self.raw.bind_graphics_pipeline(&pipeline.raw);
+    }
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::DescriptorSet>>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        let sets = sets.into_iter().collect::<Vec<_>>();
+        self.raw.bind_graphics_descriptor_sets(
+            &layout.raw,
+            first_set,
+            sets.iter().map(|set| &set.borrow().raw),
+            offsets,
+        );
+    }
+
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Tracked<B>>>,
+    {
+        self.raw
+            .push_graphics_descriptor_set(&layout.raw, set, push_writes(writes));
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, pipeline: &Handle<B::ComputePipeline>) {
+        self.raw.bind_compute_pipeline(&pipeline.raw);
+    }
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::DescriptorSet>>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        let sets = sets.into_iter().collect::<Vec<_>>();
+        self.raw.bind_compute_descriptor_sets(
+            &layout.raw,
+            first_set,
+            sets.iter().map(|set| &set.borrow().raw),
+            offsets,
+        );
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Tracked<B>>>,
+    {
+        self.raw
+            .push_compute_descriptor_set(&layout.raw, set, push_writes(writes));
+    }
+
+    unsafe fn dispatch(&mut self, count: WorkGroupCount) {
+        self.raw.dispatch(count);
+    }
+
+    unsafe fn dispatch_indirect(&mut self, buffer: &Handle<B::Buffer>, offset: buffer::Offset) {
+        self.raw.dispatch_indirect(&buffer.raw, offset);
+    }
+
+    unsafe fn copy_buffer<T>(
+        &mut self,
+        src: &Handle<B::Buffer>,
+        dst: &Handle<B::Buffer>,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferCopy>,
+    {
+        self.raw.copy_buffer(&src.raw, &dst.raw, regions);
+    }
+
+    unsafe fn copy_image<T>(
+        &mut self,
+        src: &Handle<B::Image>,
+        src_layout: image::Layout,
+        dst: &Handle<B::Image>,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageCopy>,
+    {
+        self.raw
+            .copy_image(&src.raw, src_layout, &dst.raw, dst_layout, regions);
+    }
+
+    unsafe fn copy_buffer_to_image<T>(
+        &mut self,
+        src: &Handle<B::Buffer>,
+        dst: &Handle<B::Image>,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        self.raw
+            .copy_buffer_to_image(&src.raw, &dst.raw, dst_layout, regions);
+    }
+
+    unsafe fn copy_image_to_buffer<T>(
+        &mut self,
+        src: &Handle<B::Image>,
+        src_layout: image::Layout,
+        dst: &Handle<B::Buffer>,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        self.raw
+            .copy_image_to_buffer(&src.raw, src_layout, &dst.raw, regions);
+    }
+
+    unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
+        self.raw.draw(vertices, instances);
+    }
+
+    unsafe fn draw_indexed(
+        &mut self,
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    ) {
+        self.raw.draw_indexed(indices, base_vertex, instances);
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw
+            .draw_indirect(&buffer.raw, offset, draw_count, stride);
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw
+            .draw_indexed_indirect(&buffer.raw, offset, draw_count, stride);
+    }
+
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        count_buffer: &Handle<B::Buffer>,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw.draw_indirect_count(
+            &buffer.raw,
+            offset,
+            &count_buffer.raw,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        );
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        count_buffer: &Handle<B::Buffer>,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.raw.draw_indexed_indirect_count(
+            &buffer.raw,
+            offset,
+            &count_buffer.raw,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        );
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        self.raw
+            .begin_conditional_rendering(&buffer.raw, offset, inverted);
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &Handle<B::QueryPool>,
+        id: query::Id,
+        inverted: bool,
+    ) {
+        self.raw
+            .begin_conditional_rendering_query(&pool.raw, id, inverted);
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.raw.end_conditional_rendering();
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        self.raw.begin_transform_feedback();
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        self.raw.end_transform_feedback();
+    }
+
+    unsafe fn set_event(&mut self, event: &Handle<B::Event>, stages: pso::PipelineStage) {
+        self.raw.set_event(&event.raw, stages);
+    }
+
+    unsafe fn reset_event(&mut self, event: &Handle<B::Event>, stages: pso::PipelineStage) {
+        self.raw.reset_event(&event.raw, stages);
+    }
+
+    unsafe fn wait_events<'a, I, J>(
+        &mut self,
+        events: I,
+        stages: Range<pso::PipelineStage>,
+        barriers: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::Event>>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Tracked<B>>>,
+    {
+        let events = events.into_iter().collect::<Vec<_>>();
+        let barriers = self::barriers(barriers);
+        self.raw.wait_events(
+            events.iter().map(|event| &event.borrow().raw),
+            stages,
+            barriers,
+        );
+    }
+
+    unsafe fn begin_query(&mut self, query: query::Query<Tracked<B>>, flags: query::ControlFlags) {
+        self.raw.begin_query(
+            query::Query {
+                pool: &query.pool.raw,
+                id: query.id,
+            },
+            flags,
+        );
+    }
+
+    unsafe fn end_query(&mut self, query: query::Query<Tracked<B>>) {
+        self.raw.end_query(query::Query {
+            pool: &query.pool.raw,
+            id: query.id,
+        });
+    }
+
+    unsafe fn reset_query_pool(&mut self, pool: &Handle<B::QueryPool>, queries: Range<query::Id>) {
+        self.raw.reset_query_pool(&pool.raw, queries);
+    }
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &Handle<B::QueryPool>,
+        queries: Range<query::Id>,
+        buffer: &Handle<B::Buffer>,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) {
+        self.raw
+            .copy_query_pool_results(&pool.raw, queries, &buffer.raw, offset, stride, flags);
+    }
+
+    unsafe fn write_timestamp(
+        &mut self,
+        stage: pso::PipelineStage,
+        query: query::Query<Tracked<B>>,
+    ) {
+        self.raw.write_timestamp(
+            stage,
+            query::Query {
+                pool: &query.pool.raw,
+                id: query.id,
+            },
+        );
+    }
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.raw
+            .push_graphics_constants(&layout.raw, stages, offset, constants);
+    }
+
+    unsafe fn push_compute_constants(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.raw
+            .push_compute_constants(&layout.raw, offset, constants);
+    }
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, cmd_buffers: I)
+    where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let cmd_buffers = cmd_buffers
+            .into_iter()
+            .map(|cmd_buffer| &cmd_buffer.borrow().raw)
+            .collect::<Vec<_>>();
+        self.raw.execute_commands(cmd_buffers.iter());
+    }
+
+    unsafe fn insert_debug_marker(&mut self, name: &str, color: u32) {
+        self.raw.insert_debug_marker(name, color);
+    }
+
+    unsafe fn begin_debug_marker(&mut self, name: &str, color: u32) {
+        self.raw.begin_debug_marker(name, color);
+    }
+
+    unsafe fn end_debug_marker(&mut self) {
+        self.raw.end_debug_marker();
+    }
+}
@@ -0,0 +1,997 @@
+use crate::{
+    command::CommandBuffer,
+    native::{self, Handle},
+    tracker::{Leak, ObjectKind, Tracker},
+    window::{Surface, Swapchain},
+    CommandPool,
+    DescriptorPool,
+    Reporter,
+    Tracked,
+};
+use hal::{
+    buffer,
+    device,
+    external,
+    format,
+    image,
+    memory,
+    pass,
+    pool::CommandPoolCreateFlags,
+    pso,
+    query,
+    queue::{QueueFamilyId, SemaphoreValue},
+    window,
+    MemoryTypeId,
+};
+use std::borrow::Borrow;
+use std::iter;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Tracked device, wrapping a device of the backend `B`.
+///
+/// The objects which are still alive when it's dropped are reported as leaks.
+#[derive(Debug)]
+pub struct Device<B: hal::Backend> {
+    pub(crate) raw: B::Device,
+    tracker: Arc<Tracker>,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> Device<B> {
+    pub(crate) fn new(raw: B::Device, reporter: Arc<Reporter>) -> Self {
+        Device {
+            raw,
+            tracker: Arc::new(Tracker::default()),
+            reporter,
+        }
+    }
+
+    /// Returns the wrapped device.
+    pub fn raw(&self) -> &B::Device {
+        &self.raw
+    }
+
+    /// Returns the number of live objects of the given type.
+    pub fn count(&self, kind: ObjectKind) -> usize {
+        self.tracker.count(kind)
+    }
+
+    /// Returns the objects which are alive, in the order of their creation.
+    ///
+    /// Resolving the backtraces is slow, so this is meant to be called when the
+    /// application expects every object to be destroyed, e.g. between two levels.
+    pub fn leaks(&self) -> Vec<Leak> {
+        self.tracker.leaks()
+    }
+
+    fn wrap<T>(&self, raw: T, kind: ObjectKind) -> Handle<T> {
+        Handle {
+            raw,
+            id: self.tracker.add(kind, None),
+        }
+    }
+}
+
+impl<B: hal::Backend> Drop for Device<B> {
+    fn drop(&mut self) {
+        for leak in self.tracker.leaks() {
+            self.reporter.report(&leak);
+        }
+    }
+}
+
+fn entry_point<'a, B: hal::Backend>(
+    entry: &pso::EntryPoint<'a, Tracked<B>>,
+) -> pso::EntryPoint<'a, B> {
+    pso::EntryPoint {
+        entry: entry.entry,
+        module: &entry.module.raw,
+        specialization: entry.specialization.clone(),
+    }
+}
+
+fn base_pipeline<'a, P>(parent: &pso::BasePipeline<'a, Handle<P>>) -> pso::BasePipeline<'a, P> {
+    match *parent {
+        pso::BasePipeline::Pipeline(pipeline) => pso::BasePipeline::Pipeline(&pipeline.raw),
+        pso::BasePipeline::Index(index) => pso::BasePipeline::Index(index),
+        pso::BasePipeline::None => pso::BasePipeline::None,
+    }
+}
+
+/// Unwraps a descriptor of the wrapped backend.
+pub(crate) fn unwrap_descriptor<'a, B: hal::Backend>(
+    descriptor: &pso::Descriptor<'a, Tracked<B>>,
+) -> pso::Descriptor<'a, B> {
+    match *descriptor {
+        pso::Descriptor::Sampler(sampler) => pso::Descriptor::Sampler(&sampler.raw),
+        pso::Descriptor::Image(view, layout) => pso::Descriptor::Image(view.raw(), layout),
+        pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+            pso::Descriptor::CombinedImageSampler(view.raw(), layout, &sampler.raw)
+        }
+        pso::Descriptor::Buffer(buffer, ref range) => {
+            pso::Descriptor::Buffer(&buffer.raw, range.clone())
+        }
+        pso::Descriptor::TexelBuffer(view) => pso::Descriptor::TexelBuffer(&view.raw),
+    }
+}
+
+impl<B: hal::Backend> device::Device<Tracked<B>> for Device<B> {
+    unsafe fn create_command_pool(
+        &self,
+        family: QueueFamilyId,
+        create_flags: CommandPoolCreateFlags,
+    ) -> Result<CommandPool<B>, device::OutOfMemory> {
+        let raw = self.raw.create_command_pool(family, create_flags)?;
+        Ok(CommandPool {
+            raw,
+            id: self.tracker.add(ObjectKind::CommandPool, None),
+            tracker: Arc::clone(&self.tracker),
+        })
+    }
+
+    unsafe fn destroy_command_pool(&self, pool: CommandPool<B>) {
+        self.tracker.remove_children(pool.id);
+        self.tracker.remove(pool.id);
+        self.raw.destroy_command_pool(pool.raw)
+    }
+
+    unsafe fn allocate_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+    ) -> Result<Handle<B::Memory>, device::AllocationError> {
+        let raw = self.raw.allocate_memory(memory_type, size)?;
+        Ok(self.wrap(raw, ObjectKind::Memory))
+    }
+
+    unsafe fn free_memory(&self, memory: Handle<B::Memory>) {
+        self.tracker.remove(memory.id);
+        self.raw.free_memory(memory.raw)
+    }
+
+    unsafe fn allocate_exportable_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<Handle<B::Memory>, device::AllocationError> {
+        let raw = self
+            .raw
+            .allocate_exportable_memory(memory_type, size, handle_types)?;
+        Ok(self.wrap(raw, ObjectKind::Memory))
+    }
+
+    unsafe fn export_memory(
+        &self,
+        memory: &Handle<B::Memory>,
+        handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.raw.export_memory(&memory.raw, handle_type)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        self.raw.get_memory_fd_type_mask(handle_type, fd)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<Handle<B::Memory>, external::ImportError> {
+        let raw = self.raw.import_memory(memory_type, size, handle_type, fd)?;
+        Ok(self.wrap(raw, ObjectKind::Memory))
+    }
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        subpasses: IS,
+        dependencies: ID,
+        correlation_masks: &[pass::ViewMask],
+    ) -> Result<Handle<B::RenderPass>, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        let raw =
+            self.raw
+                .create_render_pass(attachments, subpasses, dependencies, correlation_masks)?;
+        Ok(self.wrap(raw, ObjectKind::RenderPass))
+    }
+
+    unsafe fn destroy_render_pass(&self, rp: Handle<B::RenderPass>) {
+        self.tracker.remove(rp.id);
+        self.raw.destroy_render_pass(rp.raw)
+    }
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        set_layouts: IS,
+        push_constant: IR,
+    ) -> Result<Handle<B::PipelineLayout>, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<Handle<B::DescriptorSetLayout>>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        let set_layouts = set_layouts.into_iter().collect::<Vec<_>>();
+        let raw = self.raw.create_pipeline_layout(
+            set_layouts.iter().map(|layout| &layout.borrow().raw),
+            push_constant,
+        )?;
+        Ok(self.wrap(raw, ObjectKind::PipelineLayout))
+    }
+
+    unsafe fn destroy_pipeline_layout(&self, layout: Handle<B::PipelineLayout>) {
+        self.tracker.remove(layout.id);
+        self.raw.destroy_pipeline_layout(layout.raw)
+    }
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        data: Option<&[u8]>,
+    ) -> Result<Handle<B::PipelineCache>, device::OutOfMemory> {
+        let raw = self.raw.create_pipeline_cache(data)?;
+        Ok(self.wrap(raw, ObjectKind::PipelineCache))
+    }
+
+    unsafe fn get_pipeline_cache_data(
+        &self,
+        cache: &Handle<B::PipelineCache>,
+    ) -> Result<Vec<u8>, device::OutOfMemory> {
+        self.raw.get_pipeline_cache_data(&cache.raw)
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        target: &Handle<B::PipelineCache>,
+        sources: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::PipelineCache>>,
+    {
+        let sources = sources.into_iter().collect::<Vec<_>>();
+        self.raw
+            .merge_pipeline_caches(&target.raw, sources.iter().map(|cache| &cache.borrow().raw))
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, cache: Handle<B::PipelineCache>) {
+        self.tracker.remove(cache.id);
+        self.raw.destroy_pipeline_cache(cache.raw)
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        desc: &pso::GraphicsPipelineDesc<'a, Tracked<B>>,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Result<Handle<B::GraphicsPipeline>, pso::CreationError> {
+        self.create_graphics_pipelines(iter::once(desc), cache)
+            .remove(0)
+    }
+
+    unsafe fn create_graphics_pipelines<'a, I>(
+        &self,
+        descs: I,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Vec<Result<Handle<B::GraphicsPipeline>, pso::CreationError>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::GraphicsPipelineDesc<'a, Tracked<B>>>,
+    {
+        let descs = descs.into_iter().collect::<Vec<_>>();
+        let raw_descs = descs
+            .iter()
+            .map(|desc| {
+                let desc = desc.borrow();
+                let shaders = &desc.shaders;
+                pso::GraphicsPipelineDesc {
+                    shaders: pso::GraphicsShaderSet {
+                        vertex: entry_point(&shaders.vertex),
+                        hull: shaders.hull.as_ref().map(entry_point),
+                        domain: shaders.domain.as_ref().map(entry_point),
+                        geometry: shaders.geometry.as_ref().map(entry_point),
+                        fragment: shaders.fragment.as_ref().map(entry_point),
+                    },
+                    rasterizer: desc.rasterizer,
+                    vertex_buffers: desc.vertex_buffers.clone(),
+                    attributes: desc.attributes.clone(),
+                    input_assembler: desc.input_assembler.clone(),
+                    blender: desc.blender.clone(),
+                    depth_stencil: desc.depth_stencil,
+                    multisampling: desc.multisampling.clone(),
+                    baked_states: desc.baked_states.clone(),
+                    layout: &desc.layout.raw,
+                    subpass: pass::Subpass {
+                        index: desc.subpass.index,
+                        main_pass: &desc.subpass.main_pass.raw,
+                    },
+                    flags: desc.flags,
+                    parent: base_pipeline(&desc.parent),
+                }
+            })
+            .collect::<Vec<_>>();
+        self.raw
+            .create_graphics_pipelines(&raw_descs, cache.map(|cache| &cache.raw))
+            .into_iter()
+            .map(|result| result.map(|raw| self.wrap(raw, ObjectKind::GraphicsPipeline)))
+            .collect()
+    }
+
+    unsafe fn destroy_graphics_pipeline(&self, pipeline: Handle<B::GraphicsPipeline>) {
+        self.tracker.remove(pipeline.id);
+        self.raw.destroy_graphics_pipeline(pipeline.raw)
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        desc: &pso::ComputePipelineDesc<'a, Tracked<B>>,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Result<Handle<B::ComputePipeline>, pso::CreationError> {
+        self.create_compute_pipelines(iter::once(desc), cache)
+            .remove(0)
+    }
+
+    unsafe fn create_compute_pipelines<'a, I>(
+        &self,
+        descs: I,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Vec<Result<Handle<B::ComputePipeline>, pso::CreationError>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::ComputePipelineDesc<'a, Tracked<B>>>,
+    {
+        let descs = descs.into_iter().collect::<Vec<_>>();
+        let raw_descs = descs
+            .iter()
+            .map(|desc| {
+                let desc = desc.borrow();
+                pso::ComputePipelineDesc {
+                    shader: entry_point(&desc.shader),
+                    layout: &desc.layout.raw,
+                    flags: desc.flags,
+                    parent: base_pipeline(&desc.parent),
+                }
+            })
+            .collect::<Vec<_>>();
+        self.raw
+            .create_compute_pipelines(&raw_descs, cache.map(|cache| &cache.raw))
+            .into_iter()
+            .map(|result| result.map(|raw| self.wrap(raw, ObjectKind::ComputePipeline)))
+            .collect()
+    }
+
+    unsafe fn destroy_compute_pipeline(&self, pipeline: Handle<B::ComputePipeline>) {
+        self.tracker.remove(pipeline.id);
+        self.raw.destroy_compute_pipeline(pipeline.raw)
+    }
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        pass: &Handle<B::RenderPass>,
+        attachments: I,
+        extent: image::Extent,
+    ) -> Result<Handle<B::Framebuffer>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::ImageView<B>>,
+    {
+        let attachments = attachments.into_iter().collect::<Vec<_>>();
+        let raw = self.raw.create_framebuffer(
+            &pass.raw,
+            attachments.iter().map(|view| view.borrow().raw()),
+            extent,
+        )?;
+        Ok(self.wrap(raw, ObjectKind::Framebuffer))
+    }
+
+    unsafe fn destroy_framebuffer(&self, buf: Handle<B::Framebuffer>) {
+        self.tracker.remove(buf.id);
+        self.raw.destroy_framebuffer(buf.raw)
+    }
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv_data: &[u32],
+    ) -> Result<Handle<B::ShaderModule>, device::ShaderError> {
+        let raw = self.raw.create_shader_module(spirv_data)?;
+        Ok(self.wrap(raw, ObjectKind::ShaderModule))
+    }
+
+    unsafe fn destroy_shader_module(&self, shader: Handle<B::ShaderModule>) {
+        self.tracker.remove(shader.id);
+        self.raw.destroy_shader_module(shader.raw)
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        sparse: memory::SparseFlags,
+    ) -> Result<Handle<B::Buffer>, buffer::CreationError> {
+        let raw = self.raw.create_buffer(size, usage, sparse)?;
+        Ok(self.wrap(raw, ObjectKind::Buffer))
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<Handle<B::Buffer>, buffer::CreationError> {
+        let raw = self.raw.create_external_buffer(size, usage, handle_types)?;
+        Ok(self.wrap(raw, ObjectKind::Buffer))
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &Handle<B::Buffer>) -> memory::Requirements {
+        self.raw.get_buffer_requirements(&buffer.raw)
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &Handle<B::Memory>,
+        offset: u64,
+        buffer: &mut Handle<B::Buffer>,
+    ) -> Result<(), device::BindError> {
+        self.raw
+            .bind_buffer_memory(&memory.raw, offset, &mut buffer.raw)
+    }
+
+    unsafe fn destroy_buffer(&self, buffer: Handle<B::Buffer>) {
+        self.tracker.remove(buffer.id);
+        self.raw.destroy_buffer(buffer.raw)
+    }
+
+    unsafe fn create_buffer_view(
+        &self,
+        buffer: &Handle<B::Buffer>,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    ) -> Result<Handle<B::BufferView>, buffer::ViewCreationError> {
+        let raw = self.raw.create_buffer_view(&buffer.raw, format, range)?;
+        Ok(self.wrap(raw, ObjectKind::BufferView))
+    }
+
+    unsafe fn destroy_buffer_view(&self, view: Handle<B::BufferView>) {
+        self.tracker.remove(view.id);
+        self.raw.destroy_buffer_view(view.raw)
+    }
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
+    ) -> Result<Handle<B::Image>, image::CreationError> {
+        let raw = self
+            .raw
+            .create_image(kind, mip_levels, format, tiling, usage, view_caps, sparse)?;
+        Ok(self.wrap(raw, ObjectKind::Image))
+    }
+
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<Handle<B::Image>, image::CreationError> {
+        let raw = self.raw.create_external_image(
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            handle_types,
+        )?;
+        Ok(self.wrap(raw, ObjectKind::Image))
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        modifier: external::DrmFormatModifier,
+    ) -> Result<Handle<B::Image>, image::CreationError> {
+        let raw = self
+            .raw
+            .create_drm_format_modifier_image(kind, format, usage, view_caps, modifier)?;
+        Ok(self.wrap(raw, ObjectKind::Image))
+    }
+
+    unsafe fn get_image_requirements(&self, image: &Handle<B::Image>) -> memory::Requirements {
+        self.raw.get_image_requirements(&image.raw)
+    }
+
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        image: &Handle<B::Image>,
+    ) -> Vec<image::SparseRequirements> {
+        self.raw.get_image_sparse_requirements(&image.raw)
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &Handle<B::Image>,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        self.raw
+            .get_image_subresource_footprint(&image.raw, subresource)
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &Handle<B::Memory>,
+        offset: u64,
+        image: &mut Handle<B::Image>,
+    ) -> Result<(), device::BindError> {
+        self.raw
+            .bind_image_memory(&memory.raw, offset, &mut image.raw)
+    }
+
+    unsafe fn destroy_image(&self, image: Handle<B::Image>) {
+        self.tracker.remove(image.id);
+        self.raw.destroy_image(image.raw)
+    }
+
+    unsafe fn create_image_view(
+        &self,
+        image: &Handle<B::Image>,
+        view_kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<native::ImageView<B>, image::ViewCreationError> {
+        let raw = self
+            .raw
+            .create_image_view(&image.raw, view_kind, format, swizzle, range)?;
+        let id = self.tracker.add(ObjectKind::ImageView, None);
+        Ok(native::ImageView {
+            kind: native::ImageViewKind::Owned(raw, id),
+        })
+    }
+
+    unsafe fn destroy_image_view(&self, view: native::ImageView<B>) {
+        match view.kind {
+            native::ImageViewKind::Owned(raw, id) => {
+                self.tracker.remove(id);
+                self.raw.destroy_image_view(raw)
+            }
+            native::ImageViewKind::Swapchain(_) => {
+                error!("Image acquired from a surface can't be destroyed, it has to be presented")
+            }
+        }
+    }
+
+    unsafe fn create_sampler(
+        &self,
+        desc: &image::SamplerDesc,
+    ) -> Result<Handle<B::Sampler>, device::AllocationError> {
+        let raw = self.raw.create_sampler(desc)?;
+        Ok(self.wrap(raw, ObjectKind::Sampler))
+    }
+
+    unsafe fn destroy_sampler(&self, sampler: Handle<B::Sampler>) {
+        self.tracker.remove(sampler.id);
+        self.raw.destroy_sampler(sampler.raw)
+    }
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        max_sets: usize,
+        descriptor_ranges: I,
+        flags: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool<B>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        let raw = self
+            .raw
+            .create_descriptor_pool(max_sets, descriptor_ranges, flags)?;
+        Ok(DescriptorPool {
+            raw,
+            id: self.tracker.add(ObjectKind::DescriptorPool, None),
+            tracker: Arc::clone(&self.tracker),
+        })
+    }
+
+    unsafe fn destroy_descriptor_pool(&self, pool: DescriptorPool<B>) {
+        self.tracker.remove_children(pool.id);
+        self.tracker.remove(pool.id);
+        self.raw.destroy_descriptor_pool(pool.raw)
+    }
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        immutable_samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
+    ) -> Result<Handle<B::DescriptorSetLayout>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<Handle<B::Sampler>>,
+    {
+        let immutable_samplers = immutable_samplers.into_iter().collect::<Vec<_>>();
+        let raw = self.raw.create_descriptor_set_layout(
+            bindings,
+            immutable_samplers
+                .iter()
+                .map(|sampler| &sampler.borrow().raw),
+            flags,
+        )?;
+        Ok(self.wrap(raw, ObjectKind::DescriptorSetLayout))
+    }
+
+    unsafe fn destroy_descriptor_set_layout(&self, layout: Handle<B::DescriptorSetLayout>) {
+        self.tracker.remove(layout.id);
+        self.raw.destroy_descriptor_set_layout(layout.raw)
+    }
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, write_iter: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Tracked<B>, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Tracked<B>>>,
+    {
+        let writes = write_iter
+            .into_iter()
+            .map(|write| pso::DescriptorSetWrite {
+                set: &write.set.raw,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                descriptors: write
+                    .descriptors
+                    .into_iter()
+                    .map(|descriptor| unwrap_descriptor(descriptor.borrow()))
+                    .collect::<Vec<_>>(),
+            })
+            .collect::<Vec<_>>();
+        self.raw.write_descriptor_sets(writes)
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, copy_iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Tracked<B>>>,
+    {
+        let copies = copy_iter
+            .into_iter()
+            .map(|copy| {
+                let copy = copy.borrow();
+                pso::DescriptorSetCopy {
+                    src_set: &copy.src_set.raw,
+                    src_binding: copy.src_binding,
+                    src_array_offset: copy.src_array_offset,
+                    dst_set: &copy.dst_set.raw,
+                    dst_binding: copy.dst_binding,
+                    dst_array_offset: copy.dst_array_offset,
+                    count: copy.count,
+                }
+            })
+            .collect::<Vec<_>>();
+        self.raw.copy_descriptor_sets(copies)
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &Handle<B::Memory>,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        self.raw.map_memory(&memory.raw, segment)
+    }
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, ranges: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a Handle<B::Memory>, memory::Segment)>,
+    {
+        self.raw
+            .flush_mapped_memory_ranges(ranges.into_iter().map(|range| {
+                let (memory, ref segment) = *range.borrow();
+                (&memory.raw, segment.clone())
+            }))
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(
+        &self,
+        ranges: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a Handle<B::Memory>, memory::Segment)>,
+    {
+        self.raw
+            .invalidate_mapped_memory_ranges(ranges.into_iter().map(|range| {
+                let (memory, ref segment) = *range.borrow();
+                (&memory.raw, segment.clone())
+            }))
+    }
+
+    unsafe fn unmap_memory(&self, memory: &Handle<B::Memory>) {
+        self.raw.unmap_memory(&memory.raw)
+    }
+
+    fn create_semaphore(&self) -> Result<Handle<B::Semaphore>, device::OutOfMemory> {
+        let raw = self.raw.create_semaphore()?;
+        Ok(self.wrap(raw, ObjectKind::Semaphore))
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: SemaphoreValue,
+    ) -> Result<Handle<B::Semaphore>, device::OutOfMemory> {
+        let raw = self.raw.create_timeline_semaphore(initial_value)?;
+        Ok(self.wrap(raw, ObjectKind::Semaphore))
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+    ) -> Result<SemaphoreValue, device::DeviceLost> {
+        self.raw.get_semaphore_value(&semaphore.raw)
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        value: SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw
+            .wait_for_semaphore_value(&semaphore.raw, value, timeout_ns)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        value: SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        self.raw.signal_semaphore(&semaphore.raw, value)
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<Handle<B::Semaphore>, device::OutOfMemory> {
+        let raw = self.raw.create_exportable_semaphore(handle_types)?;
+        Ok(self.wrap(raw, ObjectKind::Semaphore))
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.raw.export_semaphore(&semaphore.raw, handle_type)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        handle_type: external::SemaphoreHandleType,
+        fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        self.raw.import_semaphore(&semaphore.raw, handle_type, fd)
+    }
+
+    unsafe fn destroy_semaphore(&self, semaphore: Handle<B::Semaphore>) {
+        self.tracker.remove(semaphore.id);
+        self.raw.destroy_semaphore(semaphore.raw)
+    }
+
+    fn create_fence(&self, signaled: bool) -> Result<Handle<B::Fence>, device::OutOfMemory> {
+        let raw = self.raw.create_fence(signaled)?;
+        Ok(self.wrap(raw, ObjectKind::Fence))
+    }
+
+    unsafe fn reset_fences<I>(&self, fences: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::Fence>>,
+    {
+        let fences = fences.into_iter().collect::<Vec<_>>();
+        self.raw
+            .reset_fences(fences.iter().map(|fence| &fence.borrow().raw))
+    }
+
+    unsafe fn wait_for_fences<I>(
+        &self,
+        fences: I,
+        wait: device::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::Fence>>,
+    {
+        let fences = fences.into_iter().collect::<Vec<_>>();
+        self.raw.wait_for_fences(
+            fences.iter().map(|fence| &fence.borrow().raw),
+            wait,
+            timeout_ns,
+        )
+    }
+
+    unsafe fn get_fence_status(
+        &self,
+        fence: &Handle<B::Fence>,
+    ) -> Result<bool, device::DeviceLost> {
+        self.raw.get_fence_status(&fence.raw)
+    }
+
+    unsafe fn destroy_fence(&self, fence: Handle<B::Fence>) {
+        self.tracker.remove(fence.id);
+        self.raw.destroy_fence(fence.raw)
+    }
+
+    fn create_event(&self) -> Result<Handle<B::Event>, device::OutOfMemory> {
+        let raw = self.raw.create_event()?;
+        Ok(self.wrap(raw, ObjectKind::Event))
+    }
+
+    unsafe fn destroy_event(&self, event: Handle<B::Event>) {
+        self.tracker.remove(event.id);
+        self.raw.destroy_event(event.raw)
+    }
+
+    unsafe fn get_event_status(
+        &self,
+        event: &Handle<B::Event>,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw.get_event_status(&event.raw)
+    }
+
+    unsafe fn set_event(&self, event: &Handle<B::Event>) -> Result<(), device::OutOfMemory> {
+        self.raw.set_event(&event.raw)
+    }
+
+    unsafe fn reset_event(&self, event: &Handle<B::Event>) -> Result<(), device::OutOfMemory> {
+        self.raw.reset_event(&event.raw)
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<Handle<B::QueryPool>, query::CreationError> {
+        let raw = self.raw.create_query_pool(ty, count)?;
+        Ok(self.wrap(raw, ObjectKind::QueryPool))
+    }
+
+    unsafe fn destroy_query_pool(&self, pool: Handle<B::QueryPool>) {
+        self.tracker.remove(pool.id);
+        self.raw.destroy_query_pool(pool.raw)
+    }
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &Handle<B::QueryPool>,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw
+            .get_query_pool_results(&pool.raw, queries, data, stride, flags)
+    }
+
+    unsafe fn create_swapchain(
+        &self,
+        surface: &mut Surface<B>,
+        config: window::SwapchainConfig,
+        old_swapchain: Option<Swapchain<B>>,
+    ) -> Result<(Swapchain<B>, Vec<Handle<B::Image>>), window::CreationError> {
+        // The old swapchain is retired by the creation, even if it fails.
+        let old_swapchain = old_swapchain.map(|swapchain| {
+            self.tracker.remove_children(swapchain.id);
+            self.tracker.remove(swapchain.id);
+            swapchain.raw
+        });
+        let (raw, images) = self
+            .raw
+            .create_swapchain(&mut surface.raw, config, old_swapchain)?;
+        let swapchain = Swapchain {
+            raw,
+            id: self.tracker.add(ObjectKind::Swapchain, None),
+        };
+        let images = images
+            .into_iter()
+            .map(|raw| Handle {
+                raw,
+                id: self.tracker.add(ObjectKind::Image, Some(swapchain.id)),
+            })
+            .collect();
+        Ok((swapchain, images))
+    }
+
+    unsafe fn destroy_swapchain(&self, swapchain: Swapchain<B>) {
+        self.tracker.remove_children(swapchain.id);
+        self.tracker.remove(swapchain.id);
+        self.raw.destroy_swapchain(swapchain.raw)
+    }
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        self.raw.wait_idle()
+    }
+
+    unsafe fn set_image_name(&self, image: &mut Handle<B::Image>, name: &str) {
+        self.tracker.set_name(image.id, name);
+        self.raw.set_image_name(&mut image.raw, name)
+    }
+
+    unsafe fn set_buffer_name(&self, buffer: &mut Handle<B::Buffer>, name: &str) {
+        self.tracker.set_name(buffer.id, name);
+        self.raw.set_buffer_name(&mut buffer.raw, name)
+    }
+
+    unsafe fn set_command_buffer_name(&self, command_buffer: &mut CommandBuffer<B>, name: &str) {
+        self.tracker.set_name(command_buffer.id, name);
+        self.raw
+            .set_command_buffer_name(&mut command_buffer.raw, name)
+    }
+
+    unsafe fn set_semaphore_name(&self, semaphore: &mut Handle<B::Semaphore>, name: &str) {
+        self.tracker.set_name(semaphore.id, name);
+        self.raw.set_semaphore_name(&mut semaphore.raw, name)
+    }
+
+    unsafe fn set_fence_name(&self, fence: &mut Handle<B::Fence>, name: &str) {
+        self.tracker.set_name(fence.id, name);
+        self.raw.set_fence_name(&mut fence.raw, name)
+    }
+
+    unsafe fn set_framebuffer_name(&self, framebuffer: &mut Handle<B::Framebuffer>, name: &str) {
+        self.tracker.set_name(framebuffer.id, name);
+        self.raw.set_framebuffer_name(&mut framebuffer.raw, name)
+    }
+
+    unsafe fn set_render_pass_name(&self, render_pass: &mut Handle<B::RenderPass>, name: &str) {
+        self.tracker.set_name(render_pass.id, name);
+        self.raw.set_render_pass_name(&mut render_pass.raw, name)
+    }
+
+    unsafe fn set_descriptor_set_name(
+        &self,
+        descriptor_set: &mut Handle<B::DescriptorSet>,
+        name: &str,
+    ) {
+        self.tracker.set_name(descriptor_set.id, name);
+        self.raw
+            .set_descriptor_set_name(&mut descriptor_set.raw, name)
+    }
+
+    unsafe fn set_descriptor_set_layout_name(
+        &self,
+        descriptor_set_layout: &mut Handle<B::DescriptorSetLayout>,
+        name: &str,
+    ) {
+        self.tracker.set_name(descriptor_set_layout.id, name);
+        self.raw
+            .set_descriptor_set_layout_name(&mut descriptor_set_layout.raw, name)
+    }
+}
@@ -0,0 +1,652 @@
+//! Resource leak tracking backend.
+//!
+//! `Tracked<B>` wraps any backend `B` and keeps track of the objects created by its
+//! devices, along with the name given by `Device::set_*_name` and the backtrace of
+//! their creation. When a device is dropped, the objects which weren't destroyed are
+//! reported as leaks through a callback. They can also be queried at any time with
+//! `Device::leaks`.
+//!
+//! Command buffers and descriptor sets are destroyed along with their pool, and the
+//! images of a swapchain along with it, so they don't need to be freed explicitly.
+
+#[macro_use]
+extern crate log;
+
+use hal::{
+    adapter,
+    buffer,
+    command::Level,
+    device::{CreationError as DeviceCreationError, OutOfMemory},
+    external,
+    format,
+    image,
+    memory,
+    pool,
+    pso,
+    queue,
+    window::{PresentError, Suboptimal, SwapImageIndex},
+};
+use std::borrow::Borrow;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::native::Handle;
+use crate::tracker::Tracker;
+
+mod command;
+mod device;
+pub mod native;
+mod tracker;
+mod window;
+
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::tracker::{Leak, ObjectKind};
+pub use crate::window::{Surface, Swapchain};
+
+/// Leak tracking backend, wrapping the backend `B`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tracked<B: hal::Backend>(PhantomData<B>);
+impl<B: hal::Backend> hal::Backend for Tracked<B> {
+    type Instance = Instance<B>;
+    type PhysicalDevice = PhysicalDevice<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type QueueFamily = B::QueueFamily;
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+
+    type Memory = Handle<B::Memory>;
+    type CommandPool = CommandPool<B>;
+
+    type ShaderModule = Handle<B::ShaderModule>;
+    type RenderPass = Handle<B::RenderPass>;
+    type Framebuffer = Handle<B::Framebuffer>;
+
+    type Buffer = Handle<B::Buffer>;
+    type BufferView = Handle<B::BufferView>;
+    type Image = Handle<B::Image>;
+    type ImageView = native::ImageView<B>;
+    type Sampler = Handle<B::Sampler>;
+
+    type ComputePipeline = Handle<B::ComputePipeline>;
+    type GraphicsPipeline = Handle<B::GraphicsPipeline>;
+    type PipelineCache = Handle<B::PipelineCache>;
+    type PipelineLayout = Handle<B::PipelineLayout>;
+    type DescriptorSetLayout = Handle<B::DescriptorSetLayout>;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = Handle<B::DescriptorSet>;
+
+    type Fence = Handle<B::Fence>;
+    type Semaphore = Handle<B::Semaphore>;
+    type Event = Handle<B::Event>;
+    type QueryPool = Handle<B::QueryPool>;
+}
+
+/// Callback receiving the leaks of the devices created from an instance.
+pub type Callback = dyn Fn(&Leak) + Send + Sync;
+
+/// Shared destination of leaks.
+pub(crate) struct Reporter {
+    callback: Box<Callback>,
+}
+
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Reporter")
+    }
+}
+
+impl Reporter {
+    pub(crate) fn report(&self, leak: &Leak) {
+        (self.callback)(leak)
+    }
+}
+
+/// Leak tracking instance, wrapping an instance of the backend `B`.
+pub struct Instance<B: hal::Backend> {
+    raw: B::Instance,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> Instance<B> {
+    /// Wrap an instance, reporting the leaks of its devices to the callback.
+    pub fn new<F>(raw: B::Instance, callback: F) -> Self
+    where
+        F: Fn(&Leak) + Send + Sync + 'static,
+    {
+        Instance {
+            raw,
+            reporter: Arc::new(Reporter {
+                callback: Box::new(callback),
+            }),
+        }
+    }
+
+    /// Returns the wrapped instance.
+    pub fn raw(&self) -> &B::Instance {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> hal::Instance<Tracked<B>> for Instance<B> {
+    /// Create an instance of `B`, logging the leaks as errors.
+    fn create(name: &str, version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        let raw = B::Instance::create(name, version)?;
+        Ok(Instance::new(raw, |leak| error!("{}", leak)))
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Tracked<B>>> {
+        self.raw
+            .enumerate_adapters()
+            .into_iter()
+            .map(|adapter| adapter::Adapter {
+                info: adapter.info,
+                physical_device: PhysicalDevice {
+                    raw: adapter.physical_device,
+                    reporter: Arc::clone(&self.reporter),
+                },
+                queue_families: adapter.queue_families,
+            })
+            .collect()
+    }
+
+    unsafe fn create_surface(
+        &self,
+        has_handle: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface<B>, hal::window::InitError> {
+        self.raw.create_surface(has_handle).map(Surface::new)
+    }
+
+    unsafe fn destroy_surface(&self, surface: Surface<B>) {
+        self.raw.destroy_surface(surface.raw)
+    }
+}
+
+/// Leak tracking physical device.
+#[derive(Debug)]
+pub struct PhysicalDevice<B: hal::Backend> {
+    raw: B::PhysicalDevice,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> adapter::PhysicalDevice<Tracked<B>> for PhysicalDevice<B> {
+    unsafe fn open(
+        &self,
+        families: &[(&B::QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Tracked<B>>, DeviceCreationError> {
+        let gpu = self.raw.open(families, requested_features)?;
+        let queue_groups = gpu
+            .queue_groups
+            .into_iter()
+            .map(|group| {
+                let mut tracked = queue::QueueGroup::new(group.family);
+                for raw in group.queues {
+                    tracked.add_queue(CommandQueue { raw });
+                }
+                tracked
+            })
+            .collect();
+
+        Ok(adapter::Gpu {
+            device: Device::new(gpu.device, Arc::clone(&self.reporter)),
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        self.raw.format_properties(format)
+    }
+
+    fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        self.raw
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        self.raw.memory_properties()
+    }
+
+    fn features(&self) -> hal::Features {
+        self.raw.features()
+    }
+
+    fn hints(&self) -> hal::Hints {
+        self.raw.hints()
+    }
+
+    fn limits(&self) -> hal::Limits {
+        self.raw.limits()
+    }
+
+    fn is_valid_cache(&self, cache: &[u8]) -> bool {
+        self.raw.is_valid_cache(cache)
+    }
+
+    fn external_buffer_properties(
+        &self,
+        usage: buffer::Usage,
+        handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        self.raw.external_buffer_properties(usage, handle_type)
+    }
+
+    fn external_image_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        self.raw.external_image_properties(
+            format,
+            dimensions,
+            tiling,
+            usage,
+            view_caps,
+            handle_type,
+        )
+    }
+
+    fn external_semaphore_properties(
+        &self,
+        handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        self.raw.external_semaphore_properties(handle_type)
+    }
+
+    fn drm_format_modifier_properties(
+        &self,
+        format: format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        self.raw.drm_format_modifier_properties(format)
+    }
+}
+
+/// Leak tracking command queue.
+#[derive(Debug)]
+pub struct CommandQueue<B: hal::Backend> {
+    raw: B::CommandQueue,
+}
+
+impl<B: hal::Backend> queue::CommandQueue<Tracked<B>> for CommandQueue<B> {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&Handle<B::Fence>>,
+    ) where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<Handle<B::Semaphore>>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+    {
+        let command_buffers = submission
+            .command_buffers
+            .into_iter()
+            .map(|cmd_buffer| &cmd_buffer.borrow().raw)
+            .collect::<Vec<_>>();
+        let wait_semaphores = submission
+            .wait_semaphores
+            .into_iter()
+            .map(|(semaphore, value, stage)| (&semaphore.borrow().raw, value, stage))
+            .collect::<Vec<_>>();
+        let signal_semaphores = submission
+            .signal_semaphores
+            .into_iter()
+            .map(|(semaphore, value)| (&semaphore.borrow().raw, value))
+            .collect::<Vec<_>>();
+        self.raw.submit(
+            queue::Submission {
+                command_buffers,
+                wait_semaphores,
+                signal_semaphores,
+            },
+            fence.map(|fence| &fence.raw),
+        )
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&Handle<B::Fence>>,
+    ) where
+        S: 'a + Borrow<Handle<B::Semaphore>>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b Handle<B::Buffer>,
+                &'b [memory::SparseBind<&'b Handle<B::Memory>>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b Handle<B::Image>,
+                &'b [memory::SparseBind<&'b Handle<B::Memory>>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b Handle<B::Image>,
+                &'b [memory::SparseImageBind<&'b Handle<B::Memory>>],
+            ),
+        >,
+    {
+        let wait_semaphores = binding
+            .wait_semaphores
+            .into_iter()
+            .map(|(semaphore, value)| (&semaphore.borrow().raw, value))
+            .collect::<Vec<_>>();
+        let signal_semaphores = binding
+            .signal_semaphores
+            .into_iter()
+            .map(|(semaphore, value)| (&semaphore.borrow().raw, value))
+            .collect::<Vec<_>>();
+        let buffer_binds = binding
+            .buffer_binds
+            .into_iter()
+            .map(|(buffer, binds)| (&buffer.raw, binds.iter().map(unwrap_sparse_bind).collect()))
+            .collect::<Vec<(_, Vec<_>)>>();
+        let image_opaque_binds = binding
+            .image_opaque_binds
+            .into_iter()
+            .map(|(image, binds)| (&image.raw, binds.iter().map(unwrap_sparse_bind).collect()))
+            .collect::<Vec<(_, Vec<_>)>>();
+        let image_binds = binding
+            .image_binds
+            .into_iter()
+            .map(|(image, binds)| {
+                let binds = binds
+                    .iter()
+                    .map(|bind| memory::SparseImageBind {
+                        subresource: bind.subresource,
+                        offset: bind.offset,
+                        extent: bind.extent,
+                        memory: bind.memory.map(|(memory, offset)| (&memory.raw, offset)),
+                    })
+                    .collect();
+                (&image.raw, binds)
+            })
+            .collect::<Vec<(_, Vec<_>)>>();
+        self.raw.bind_sparse(
+            queue::SparseBinding {
+                wait_semaphores,
+                signal_semaphores,
+                buffer_binds: buffer_binds
+                    .iter()
+                    .map(|(buffer, binds)| (*buffer, &binds[..])),
+                image_opaque_binds: image_opaque_binds
+                    .iter()
+                    .map(|(image, binds)| (*image, &binds[..])),
+                image_binds: image_binds
+                    .iter()
+                    .map(|(image, binds)| (*image, &binds[..])),
+            },
+            fence.map(|fence| &fence.raw),
+        )
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        swapchains: Is,
+        wait_semaphores: Iw,
+    ) -> Result<Option<Suboptimal>, PresentError>
+    where
+        W: 'a + Borrow<Swapchain<B>>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<Handle<B::Semaphore>>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        let swapchains = swapchains
+            .into_iter()
+            .map(|(swapchain, index)| (&swapchain.borrow().raw, index))
+            .collect::<Vec<_>>();
+        let wait_semaphores = wait_semaphores
+            .into_iter()
+            .map(|semaphore| &semaphore.borrow().raw)
+            .collect::<Vec<_>>();
+        self.raw.present(swapchains, wait_semaphores)
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        surface: &mut Surface<B>,
+        image: native::ImageView<B>,
+        wait_semaphore: Option<&Handle<B::Semaphore>>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        let image = match image.kind {
+            native::ImageViewKind::Swapchain(image) => image,
+            native::ImageViewKind::Owned(..) => panic!("Image view wasn't acquired from a surface"),
+        };
+        self.raw.present_surface(
+            &mut surface.raw,
+            image,
+            wait_semaphore.map(|semaphore| &semaphore.raw),
+        )
+    }
+
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        self.raw.wait_idle()
+    }
+}
+
+fn unwrap_sparse_bind<'a, T>(
+    bind: &memory::SparseBind<&'a Handle<T>>,
+) -> memory::SparseBind<&'a T> {
+    memory::SparseBind {
+        resource_offset: bind.resource_offset,
+        size: bind.size,
+        memory: bind.memory.map(|(memory, offset)| (&memory.raw, offset)),
+    }
+}
+
+/// Leak tracking command pool.
+#[derive(Debug)]
+pub struct CommandPool<B: hal::Backend> {
+    raw: B::CommandPool,
+    id: u64,
+    tracker: Arc<Tracker>,
+}
+
+impl<B: hal::Backend> CommandPool<B> {
+    fn wrap(&self, raw: B::CommandBuffer) -> CommandBuffer<B> {
+        CommandBuffer {
+            raw,
+            id: self.tracker.add(ObjectKind::CommandBuffer, Some(self.id)),
+        }
+    }
+}
+
+impl<B: hal::Backend> pool::CommandPool<Tracked<B>> for CommandPool<B> {
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources)
+    }
+
+    unsafe fn allocate_one(&mut self, level: Level) -> CommandBuffer<B> {
+        let raw = self.raw.allocate_one(level);
+        self.wrap(raw)
+    }
+
+    unsafe fn allocate<E>(&mut self, num: usize, level: Level, list: &mut E)
+    where
+        E: Extend<CommandBuffer<B>>,
+    {
+        let mut raw = Vec::with_capacity(num);
+        self.raw.allocate(num, level, &mut raw);
+        list.extend(raw.into_iter().map(|raw| self.wrap(raw)));
+    }
+
+    unsafe fn free<I>(&mut self, cmd_buffers: I)
+    where
+        I: IntoIterator<Item = CommandBuffer<B>>,
+    {
+        let tracker = &self.tracker;
+        self.raw.free(cmd_buffers.into_iter().map(|cmd_buffer| {
+            tracker.remove(cmd_buffer.id);
+            cmd_buffer.raw
+        }))
+    }
+}
+
+/// Leak tracking descriptor pool.
+#[derive(Debug)]
+pub struct DescriptorPool<B: hal::Backend> {
+    raw: B::DescriptorPool,
+    id: u64,
+    tracker: Arc<Tracker>,
+}
+
+impl<B: hal::Backend> DescriptorPool<B> {
+    fn wrap(&self, raw: B::DescriptorSet) -> Handle<B::DescriptorSet> {
+        Handle {
+            raw,
+            id: self.tracker.add(ObjectKind::DescriptorSet, Some(self.id)),
+        }
+    }
+}
+
+impl<B: hal::Backend> pso::DescriptorPool<Tracked<B>> for DescriptorPool<B> {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &Handle<B::DescriptorSetLayout>,
+    ) -> Result<Handle<B::DescriptorSet>, pso::AllocationError> {
+        let raw = self.raw.allocate_set(&layout.raw)?;
+        Ok(self.wrap(raw))
+    }
+
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &Handle<B::DescriptorSetLayout>,
+        count: pso::DescriptorArrayIndex,
+    ) -> Result<Handle<B::DescriptorSet>, pso::AllocationError> {
+        let raw = self.raw.allocate_variable_set(&layout.raw, count)?;
+        Ok(self.wrap(raw))
+    }
+
+    unsafe fn allocate<I, E>(
+        &mut self,
+        layouts: I,
+        list: &mut E,
+    ) -> Result<(), pso::AllocationError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::DescriptorSetLayout>>,
+        E: Extend<Handle<B::DescriptorSet>>,
+    {
+        let layouts = layouts.into_iter().collect::<Vec<_>>();
+        let mut raw = Vec::with_capacity(layouts.len());
+        let result = self
+            .raw
+            .allocate(layouts.iter().map(|layout| &layout.borrow().raw), &mut raw);
+        list.extend(raw.into_iter().map(|raw| self.wrap(raw)));
+        result
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
+    where
+        I: IntoIterator<Item = Handle<B::DescriptorSet>>,
+    {
+        let tracker = &self.tracker;
+        self.raw.free(descriptor_sets.into_iter().map(|set| {
+            tracker.remove(set.id);
+            set.raw
+        }))
+    }
+
+    unsafe fn reset(&mut self) {
+        self.tracker.remove_children(self.id);
+        self.raw.reset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        device::Device as _,
+        pool::CommandPool as _,
+        Instance as _,
+    };
+    use std::sync::Mutex;
+
+    fn instance() -> (Instance<gfx_backend_empty::Backend>, Arc<Mutex<Vec<Leak>>>) {
+        let leaks = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&leaks);
+        let raw = gfx_backend_empty::Instance::create("test", 1).unwrap();
+        let instance = Instance::new(raw, move |leak| sink.lock().unwrap().push(leak.clone()));
+        (instance, leaks)
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device<gfx_backend_empty::Backend>>();
+        foo::<CommandBuffer<gfx_backend_empty::Backend>>();
+        foo::<CommandPool<gfx_backend_empty::Backend>>();
+    }
+
+    #[test]
+    fn report_leaks_on_drop() {
+        let (instance, leaks) = instance();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = gpu.device;
+
+        unsafe {
+            let mut buffer = device
+                .create_buffer(
+                    256,
+                    buffer::Usage::TRANSFER_DST,
+                    memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            device.set_buffer_name(&mut buffer, "leaked");
+            let fence = device.create_fence(false).unwrap();
+            device.destroy_fence(fence);
+
+            // Command buffers are destroyed along with their pool.
+            let mut pool = device
+                .create_command_pool(
+                    gpu.queue_groups[0].family,
+                    pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let _cmd_buffer = pool.allocate_one(Level::Primary);
+            assert_eq!(device.count(ObjectKind::CommandBuffer), 1);
+            device.destroy_command_pool(pool);
+            assert_eq!(device.count(ObjectKind::CommandBuffer), 0);
+        }
+
+        assert_eq!(device.leaks().len(), 1);
+        drop(device);
+        let leaks = leaks.lock().unwrap();
+        assert_eq!(leaks.len(), 1);
+        assert_eq!(leaks[0].kind, ObjectKind::Buffer);
+        assert_eq!(leaks[0].name, "leaked");
+    }
+}
@@ -0,0 +1,43 @@
+//! Wrappers of the backend resources, identified in the tracker by their id.
+
+use crate::window::SwapchainImage;
+use std::borrow::Borrow;
+
+/// Resource of the wrapped backend, with the id of its entry in the tracker.
+#[derive(Debug)]
+pub struct Handle<T> {
+    pub(crate) raw: T,
+    pub(crate) id: u64,
+}
+
+impl<T> Handle<T> {
+    /// Returns the wrapped resource.
+    pub fn raw(&self) -> &T {
+        &self.raw
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum ImageViewKind<B: hal::Backend> {
+    /// View created by the device, with the id of its entry in the tracker.
+    Owned(B::ImageView, u64),
+    /// Image acquired from a surface, which also serves as its view.
+    /// It's released by presenting it, so it isn't tracked.
+    Swapchain(SwapchainImage<B>),
+}
+
+/// Image view, either created by the device or acquired from a surface.
+#[derive(Debug)]
+pub struct ImageView<B: hal::Backend> {
+    pub(crate) kind: ImageViewKind<B>,
+}
+
+impl<B: hal::Backend> ImageView<B> {
+    /// Returns the wrapped image view.
+    pub fn raw(&self) -> &B::ImageView {
+        match self.kind {
+            ImageViewKind::Owned(ref view, _) => view,
+            ImageViewKind::Swapchain(ref image) => image.borrow(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use backtrace::Backtrace;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Type of a tracked object, one for each resource type of `hal::Backend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    CommandPool,
+    CommandBuffer,
+    Memory,
+    ShaderModule,
+    RenderPass,
+    Framebuffer,
+    Buffer,
+    BufferView,
+    Image,
+    ImageView,
+    Sampler,
+    ComputePipeline,
+    GraphicsPipeline,
+    PipelineCache,
+    PipelineLayout,
+    DescriptorSetLayout,
+    DescriptorPool,
+    DescriptorSet,
+    Fence,
+    Semaphore,
+    Event,
+    QueryPool,
+    Swapchain,
+}
+
+/// Object which is still alive.
+#[derive(Clone, Debug)]
+pub struct Leak {
+    pub kind: ObjectKind,
+    /// Name set with `Device::set_*_name`, empty if none was.
+    pub name: String,
+    /// Backtrace of the creation of the object.
+    pub backtrace: Backtrace,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "unnamed {:?}", self.kind)?;
+        } else {
+            write!(f, "{:?} \"{}\"", self.kind, self.name)?;
+        }
+        write!(f, " was not destroyed, created at:\n{:?}", self.backtrace)
+    }
+}
+
+#[derive(Debug)]
+struct Object {
+    kind: ObjectKind,
+    name: String,
+    /// Pool or swapchain the object is destroyed with.
+    parent: Option<u64>,
+    /// Unresolved backtrace, only resolved when the object is reported.
+    backtrace: Backtrace,
+}
+
+/// Live objects of a device.
+#[derive(Debug, Default)]
+pub(crate) struct Tracker {
+    next_id: AtomicU64,
+    objects: Mutex<HashMap<u64, Object>>,
+}
+
+impl Tracker {
+    /// Start tracking a new object, returning its id.
+    pub(crate) fn add(&self, kind: ObjectKind, parent: Option<u64>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let object = Object {
+            kind,
+            name: String::new(),
+            parent,
+            backtrace: Backtrace::new_unresolved(),
+        };
+        self.objects.lock().unwrap().insert(id, object);
+        id
+    }
+
+    /// Stop tracking a destroyed object.
+    pub(crate) fn remove(&self, id: u64) {
+        self.objects.lock().unwrap().remove(&id);
+    }
+
+    /// Stop tracking the objects destroyed along with `parent`.
+    pub(crate) fn remove_children(&self, parent: u64) {
+        self.objects
+            .lock()
+            .unwrap()
+            .retain(|_, object| object.parent != Some(parent));
+    }
+
+    pub(crate) fn set_name(&self, id: u64, name: &str) {
+        if let Some(object) = self.objects.lock().unwrap().get_mut(&id) {
+            object.name = name.to_owned();
+        }
+    }
+
+    pub(crate) fn count(&self, kind: ObjectKind) -> usize {
+        self.objects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|object| object.kind == kind)
+            .count()
+    }
+
+    /// Returns the live objects, in the order of their creation.
+    pub(crate) fn leaks(&self) -> Vec<Leak> {
+        let objects = self.objects.lock().unwrap();
+        let mut ids = objects.keys().cloned().collect::<Vec<_>>();
+        ids.sort();
+        ids.into_iter()
+            .map(|id| {
+                let object = &objects[&id];
+                let mut backtrace = object.backtrace.clone();
+                backtrace.resolve();
+                Leak {
+                    kind: object.kind,
+                    name: object.name.clone(),
+                    backtrace,
+                }
+            })
+            .collect()
+    }
+}
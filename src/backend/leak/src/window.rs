@@ -0,0 +1,94 @@
+use crate::{native, Device, PhysicalDevice, Tracked};
+use hal::{format, window};
+
+/// Swapchain image of the wrapped backend's surface.
+pub(crate) type SwapchainImage<B> =
+    <<B as hal::Backend>::Surface as window::PresentationSurface<B>>::SwapchainImage;
+
+/// Tracked surface, wrapping a surface of the backend `B`.
+///
+/// Surfaces belong to the instance, so they aren't tracked.
+#[derive(Debug)]
+pub struct Surface<B: hal::Backend> {
+    pub(crate) raw: B::Surface,
+}
+
+impl<B: hal::Backend> Surface<B> {
+    /// Wrap a surface created by a backend specific constructor.
+    pub fn new(raw: B::Surface) -> Self {
+        Surface { raw }
+    }
+
+    /// Returns the wrapped surface.
+    pub fn raw(&self) -> &B::Surface {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> window::Surface<Tracked<B>> for Surface<B> {
+    fn supports_queue_family(&self, family: &B::QueueFamily) -> bool {
+        self.raw.supports_queue_family(family)
+    }
+
+    fn capabilities(&self, physical_device: &PhysicalDevice<B>) -> window::SurfaceCapabilities {
+        self.raw.capabilities(&physical_device.raw)
+    }
+
+    fn supported_formats(
+        &self,
+        physical_device: &PhysicalDevice<B>,
+    ) -> Option<Vec<format::Format>> {
+        self.raw.supported_formats(&physical_device.raw)
+    }
+}
+
+impl<B: hal::Backend> window::PresentationSurface<Tracked<B>> for Surface<B> {
+    type SwapchainImage = native::ImageView<B>;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device<B>,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        self.raw.configure_swapchain(&device.raw, config)
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, device: &Device<B>) {
+        self.raw.unconfigure_swapchain(&device.raw)
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+    ) -> Result<(native::ImageView<B>, Option<window::Suboptimal>), window::AcquireError> {
+        let (image, suboptimal) = self.raw.acquire_image(timeout_ns)?;
+        let view = native::ImageView {
+            kind: native::ImageViewKind::Swapchain(image),
+        };
+        Ok((view, suboptimal))
+    }
+}
+
+/// Tracked swapchain.
+///
+/// Its images are destroyed along with it.
+#[derive(Debug)]
+pub struct Swapchain<B: hal::Backend> {
+    pub(crate) raw: B::Swapchain,
+    pub(crate) id: u64,
+}
+
+impl<B: hal::Backend> window::Swapchain<Tracked<B>> for Swapchain<B> {
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+        semaphore: Option<&native::Handle<B::Semaphore>>,
+        fence: Option<&native::Handle<B::Fence>>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        self.raw.acquire_image(
+            timeout_ns,
+            semaphore.map(|semaphore| &semaphore.raw),
+            fence.map(|fence| &fence.raw),
+        )
+    }
+}
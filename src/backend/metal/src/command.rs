@@ -2086,18 +2086,18 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
         T: 'a + Borrow<CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
         S: 'a + Borrow<native::Semaphore>,
-        Iw: IntoIterator<Item = (&'a S, pso::PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>,
+        Iw: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue)>,
     {
         use smallvec::SmallVec;
 
         debug!("submitting with fence {:?}", fence);
-        self.wait(wait_semaphores.into_iter().map(|(s, _)| s));
+        self.wait(wait_semaphores.into_iter().map(|(s, _, _)| s));
 
         const BLOCK_BUCKET: usize = 4;
         let system_semaphores = signal_semaphores
             .into_iter()
-            .filter_map(|sem| sem.borrow().system.clone())
+            .filter_map(|(sem, _)| sem.borrow().system.clone())
             .collect::<SmallVec<[_; BLOCK_BUCKET]>>();
 
         #[allow(unused_mut)]
@@ -2287,6 +2287,36 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
         }
     }
 
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        _binding: hal::queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        _fence: Option<&native::Fence>,
+    ) where
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, hal::queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b native::Buffer,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseImageBind<&'b native::Memory>],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
     unsafe fn present<'a, W, Is, S, Iw>(
         &mut self,
         swapchains: Is,
@@ -3341,6 +3371,14 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, _first_binding: u32, _buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, vps: T)
     where
         T: IntoIterator,
@@ -3868,6 +3906,19 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        _layout: &native::PipelineLayout,
+        _set: usize,
+        _writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn bind_compute_pipeline(&mut self, pipeline: &native::ComputePipeline) {
         self.state.compute_pso = Some(pipeline.raw.clone());
         self.state.work_group_size = pipeline.work_group_size;
@@ -4004,6 +4055,19 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        _layout: &native::PipelineLayout,
+        _set: usize,
+        _writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        unimplemented!()
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         let mut inner = self.inner.borrow_mut();
         let (mut pre, init) = inner.sink().switch_compute();
@@ -4426,6 +4490,60 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
             .issue_many(commands);
     }
 
+    unsafe fn draw_indirect_count(
+        &mut self,
+        _buffer: &native::Buffer,
+        _offset: buffer::Offset,
+        _count_buffer: &native::Buffer,
+        _count_buffer_offset: buffer::Offset,
+        _max_draw_count: DrawCount,
+        _stride: u32,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        _buffer: &native::Buffer,
+        _offset: buffer::Offset,
+        _count_buffer: &native::Buffer,
+        _count_buffer_offset: buffer::Offset,
+        _max_draw_count: DrawCount,
+        _stride: u32,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        _buffer: &native::Buffer,
+        _offset: buffer::Offset,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        _pool: &native::QueryPool,
+        _id: query::Id,
+        _inverted: bool,
+    ) {
+        unimplemented!()
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
     unsafe fn set_event(&mut self, event: &native::Event, _: pso::PipelineStage) {
         self.inner
             .borrow_mut()
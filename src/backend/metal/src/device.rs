@@ -34,6 +34,7 @@ use hal::{
         OutOfMemory,
         ShaderError,
     },
+    external,
     format,
     image,
     memory,
@@ -43,7 +44,7 @@ use hal::{
     pso,
     pso::VertexInputRate,
     query,
-    queue::{QueueFamilyId, QueueGroup, QueuePriority},
+    queue::{QueueFamilyId, QueueGroup, QueuePriority, SemaphoreValue},
     window,
 };
 use metal::{
@@ -958,6 +959,7 @@ impl hal::device::Device<Backend> for Device {
         attachments: IA,
         subpasses: IS,
         _dependencies: ID,
+        _correlation_masks: &[pass::ViewMask],
     ) -> Result<n::RenderPass, OutOfMemory>
     where
         IA: IntoIterator,
@@ -1876,6 +1878,67 @@ impl hal::device::Device<Backend> for Device {
         })
     }
 
+    fn create_timeline_semaphore(
+        &self,
+        _initial_value: SemaphoreValue,
+    ) -> Result<n::Semaphore, OutOfMemory> {
+        unimplemented!()
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        _semaphore: &n::Semaphore,
+    ) -> Result<SemaphoreValue, DeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        _semaphore: &n::Semaphore,
+        _value: SemaphoreValue,
+        _timeout_ns: u64,
+    ) -> Result<bool, OomOrDeviceLost> {
+        unimplemented!()
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _value: SemaphoreValue,
+    ) -> Result<(), OutOfMemory> {
+        unimplemented!()
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        _handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<n::Semaphore, OutOfMemory> {
+        // `OutOfMemory` can't tell that the call is unsupported, so the misuse is logged.
+        error!("EXTERNAL_SEMAPHORE_FD feature is not supported");
+        debug_assert!(
+            self.features.contains(hal::Features::EXTERNAL_SEMAPHORE_FD),
+            "EXTERNAL_SEMAPHORE_FD feature is not enabled"
+        );
+        Err(OutOfMemory::Device)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        _semaphore: &n::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+        _fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     unsafe fn create_descriptor_pool<I>(
         &self,
         max_sets: usize,
@@ -1934,6 +1997,7 @@ impl hal::device::Device<Backend> for Device {
         &self,
         binding_iter: I,
         immutable_samplers: J,
+        _flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<n::DescriptorSetLayout, OutOfMemory>
     where
         I: IntoIterator,
@@ -2299,10 +2363,54 @@ impl hal::device::Device<Backend> for Device {
         }
     }
 
+    unsafe fn allocate_exportable_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Memory, AllocationError> {
+        // `AllocationError` can't tell that the call is unsupported, so the misuse is logged.
+        error!("External memory features are not supported");
+        debug_assert!(
+            self.features.intersects(
+                hal::Features::EXTERNAL_MEMORY_FD | hal::Features::EXTERNAL_MEMORY_DMA_BUF
+            ),
+            "External memory features are not enabled"
+        );
+        Err(OutOfMemory::Device.into())
+    }
+
+    unsafe fn export_memory(
+        &self,
+        _memory: &n::Memory,
+        _handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<n::Memory, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
     unsafe fn create_buffer(
         &self,
         size: u64,
         usage: buffer::Usage,
+        _sparse: memory::SparseFlags,
     ) -> Result<n::Buffer, buffer::CreationError> {
         debug!("create_buffer of size {} and usage {:?}", size, usage);
         Ok(n::Buffer::Unbound {
@@ -2312,6 +2420,15 @@ impl hal::device::Device<Backend> for Device {
         })
     }
 
+    unsafe fn create_external_buffer(
+        &self,
+        _size: u64,
+        usage: buffer::Usage,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Buffer, buffer::CreationError> {
+        Err(buffer::CreationError::UnsupportedUsage { usage })
+    }
+
     unsafe fn get_buffer_requirements(&self, buffer: &n::Buffer) -> memory::Requirements {
         let (size, usage) = match *buffer {
             n::Buffer::Unbound { size, usage, .. } => (size, usage),
@@ -2501,6 +2618,7 @@ impl hal::device::Device<Backend> for Device {
         tiling: image::Tiling,
         usage: image::Usage,
         view_caps: image::ViewCapabilities,
+        _sparse: memory::SparseFlags,
     ) -> Result<n::Image, image::CreationError> {
         debug!(
             "create_image {:?} with {} mips of {:?} {:?} and usage {:?} with {:?}",
@@ -2602,6 +2720,30 @@ impl hal::device::Device<Backend> for Device {
         })
     }
 
+    unsafe fn create_external_image(
+        &self,
+        _kind: image::Kind,
+        _mip_levels: image::Level,
+        _format: format::Format,
+        _tiling: image::Tiling,
+        usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Image, image::CreationError> {
+        Err(image::CreationError::Usage(usage))
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        _kind: image::Kind,
+        format: format::Format,
+        _usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _modifier: external::DrmFormatModifier,
+    ) -> Result<n::Image, image::CreationError> {
+        Err(image::CreationError::Format(format))
+    }
+
     unsafe fn get_image_requirements(&self, image: &n::Image) -> memory::Requirements {
         let (descriptor, mip_sizes, host_visible) = match image.like {
             n::ImageLike::Unbound {
@@ -2663,6 +2805,13 @@ impl hal::device::Device<Backend> for Device {
         }
     }
 
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        _image: &n::Image,
+    ) -> Vec<image::SparseRequirements> {
+        Vec::new()
+    }
+
     unsafe fn get_image_subresource_footprint(
         &self,
         image: &n::Image,
@@ -2948,7 +3097,7 @@ impl hal::device::Device<Backend> for Device {
                 warn!("Timestamp queries are not really useful yet");
                 Ok(n::QueryPool::Timestamp)
             }
-            query::Type::PipelineStatistics(..) => {
+            query::Type::PipelineStatistics(..) | query::Type::PrimitivesWritten => {
                 Err(query::CreationError::Unsupported(ty))
             }
         }
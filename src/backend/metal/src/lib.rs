@@ -138,6 +138,10 @@ impl hal::queue::QueueFamily for QueueFamily {
     fn id(&self) -> QueueFamilyId {
         QueueFamilyId(0)
     }
+    fn timestamp_valid_bits(&self) -> u32 {
+        // Timestamps are unsupported on Metal
+        0
+    }
 }
 
 #[derive(Debug)]
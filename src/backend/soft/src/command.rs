@@ -0,0 +1,830 @@
+use crate::device::write_descriptors;
+use crate::native::{
+    DescriptorBinding,
+    GraphicsState,
+    ImageView,
+    QueryResults,
+    RawBuffer,
+    RawImage,
+    RenderPassInfo,
+};
+use crate::{native, shader, Backend};
+use hal::{buffer, command, image, memory, pso, query, IndexType};
+use parking_lot::{Mutex, RwLock};
+
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+/// Descriptor set bound to a pipeline, with its dynamic offsets.
+#[derive(Clone, Debug)]
+pub(crate) struct BoundSet {
+    pub(crate) bindings: Arc<RwLock<Vec<DescriptorBinding>>>,
+    pub(crate) offsets: Vec<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BindPoint {
+    Graphics,
+    Compute,
+}
+
+/// Recorded command, holding references to the resources it uses.
+#[derive(Clone, Debug)]
+pub(crate) enum Command {
+    BindGraphicsPipeline(Arc<GraphicsState>),
+    BindComputePipeline(Arc<shader::Program>),
+    BindDescriptorSets {
+        bind_point: BindPoint,
+        first_set: usize,
+        sets: Vec<BoundSet>,
+    },
+    BindIndexBuffer(RawBuffer, IndexType),
+    BindVertexBuffers(u32, Vec<RawBuffer>),
+    SetViewport(pso::Viewport),
+    SetScissor(pso::Rect),
+    SetStencilReference(pso::Face, pso::StencilValue),
+    SetStencilReadMask(pso::Face, pso::StencilValue),
+    SetStencilWriteMask(pso::Face, pso::StencilValue),
+    SetBlendConstants(pso::ColorValue),
+    SetDepthBounds(Range<f32>),
+    SetDepthBias(pso::DepthBias),
+    PushConstants {
+        bind_point: BindPoint,
+        offset: u32,
+        data: Vec<u32>,
+    },
+    BeginRenderPass {
+        pass: Arc<RenderPassInfo>,
+        attachments: Arc<Vec<ImageView>>,
+        area: pso::Rect,
+        clear_values: Vec<command::ClearValue>,
+    },
+    NextSubpass,
+    EndRenderPass,
+    ClearImage {
+        image: RawImage,
+        value: command::ClearValue,
+        ranges: Vec<image::SubresourceRange>,
+    },
+    ClearAttachments {
+        clears: Vec<command::AttachmentClear>,
+        rects: Vec<pso::ClearRect>,
+    },
+    FillBuffer(RawBuffer, u32),
+    UpdateBuffer(RawBuffer, Vec<u8>),
+    CopyBuffer(RawBuffer, RawBuffer, Vec<command::BufferCopy>),
+    CopyImage(RawImage, RawImage, Vec<command::ImageCopy>),
+    CopyBufferToImage(RawBuffer, RawImage, Vec<command::BufferImageCopy>),
+    CopyImageToBuffer(RawImage, RawBuffer, Vec<command::BufferImageCopy>),
+    BlitImage(RawImage, RawImage, image::Filter, Vec<command::ImageBlit>),
+    ResolveImage(RawImage, RawImage, Vec<command::ImageResolve>),
+    Draw {
+        vertices: Range<hal::VertexCount>,
+        instances: Range<hal::InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<hal::IndexCount>,
+        base_vertex: hal::VertexOffset,
+        instances: Range<hal::InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: RawBuffer,
+        /// Buffer holding the number of draws, which is clamped to `count`.
+        count_buffer: Option<RawBuffer>,
+        count: hal::DrawCount,
+        stride: u32,
+        indexed: bool,
+    },
+    Dispatch(hal::WorkGroupCount),
+    DispatchIndirect(RawBuffer),
+    BeginConditionalRendering(RawBuffer, bool),
+    BeginConditionalRenderingQuery(Arc<Mutex<QueryResults>>, query::Id, bool),
+    EndConditionalRendering,
+    SetEvent(Arc<AtomicBool>, bool),
+    BeginQuery(Arc<Mutex<QueryResults>>, query::Id),
+    EndQuery(Arc<Mutex<QueryResults>>, query::Id),
+    ResetQueries(Arc<Mutex<QueryResults>>, Range<query::Id>),
+    WriteTimestamp(Arc<Mutex<QueryResults>>, query::Id),
+    CopyQueryResults {
+        pool: Arc<Mutex<QueryResults>>,
+        queries: Range<query::Id>,
+        buffer: RawBuffer,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    },
+}
+
+/// Command buffer, recording commands to be executed on submission.
+#[derive(Debug)]
+pub struct CommandBuffer {
+    pub(crate) commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    pub(crate) fn new() -> Self {
+        CommandBuffer {
+            commands: Vec::new(),
+        }
+    }
+
+    fn bind_descriptor_sets<I, J>(
+        &mut self,
+        bind_point: BindPoint,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+        let mut offsets = offsets.into_iter().map(|offset| *offset.borrow());
+        let sets = sets
+            .into_iter()
+            .map(|set| {
+                let set = set.borrow();
+                let dynamic_count = set
+                    .bindings
+                    .read()
+                    .iter()
+                    .filter(|binding| binding.is_dynamic())
+                    .map(|binding| binding.descriptors.len())
+                    .sum::<usize>();
+                BoundSet {
+                    bindings: Arc::clone(&set.bindings),
+                    offsets: offsets.by_ref().take(dynamic_count).collect(),
+                }
+            })
+            .collect();
+        self.commands.push(Command::BindDescriptorSets {
+            bind_point,
+            first_set,
+            sets,
+        });
+    }
+
+    fn push_descriptor_set<'a, I, J>(
+        &mut self,
+        bind_point: BindPoint,
+        layout: &native::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        let mut bindings = layout.sets[set].clone();
+        for write in writes {
+            write_descriptors(
+                &mut bindings,
+                write.binding,
+                write.array_offset,
+                write.descriptors,
+            );
+        }
+        self.commands.push(Command::BindDescriptorSets {
+            bind_point,
+            first_set: set,
+            sets: vec![BoundSet {
+                bindings: Arc::new(RwLock::new(bindings)),
+                offsets: Vec::new(),
+            }],
+        });
+    }
+
+    fn draw_indirect_impl(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        count_buffer: Option<(&native::Buffer, buffer::Offset)>,
+        count: hal::DrawCount,
+        stride: u32,
+        indexed: bool,
+    ) {
+        self.commands.push(Command::DrawIndirect {
+            buffer: buffer.raw().range(&buffer::SubRange { offset, size: None }),
+            count_buffer: count_buffer.map(|(count_buffer, offset)| {
+                count_buffer
+                    .raw()
+                    .range(&buffer::SubRange { offset, size: None })
+            }),
+            count,
+            stride,
+            indexed,
+        });
+    }
+}
+
+impl command::CommandBuffer<Backend> for CommandBuffer {
+    unsafe fn begin(
+        &mut self,
+        _: command::CommandBufferFlags,
+        _: command::CommandBufferInheritanceInfo<Backend>,
+    ) {
+        self.commands.clear();
+    }
+
+    unsafe fn finish(&mut self) {}
+
+    unsafe fn reset(&mut self, _: bool) {
+        self.commands.clear();
+    }
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        _: Range<pso::PipelineStage>,
+        _: memory::Dependencies,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Backend>>,
+    {
+        // Commands are executed in order, so barriers have no effect.
+    }
+
+    unsafe fn fill_buffer(&mut self, buffer: &native::Buffer, range: buffer::SubRange, data: u32) {
+        self.commands
+            .push(Command::FillBuffer(buffer.raw().range(&range), data));
+    }
+
+    unsafe fn update_buffer(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        data: &[u8],
+    ) {
+        let range = buffer::SubRange {
+            offset,
+            size: Some(data.len() as buffer::Offset),
+        };
+        self.commands.push(Command::UpdateBuffer(
+            buffer.raw().range(&range),
+            data.to_vec(),
+        ));
+    }
+
+    unsafe fn clear_image<T>(
+        &mut self,
+        image: &native::Image,
+        _: image::Layout,
+        value: command::ClearValue,
+        subresource_ranges: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+        self.commands.push(Command::ClearImage {
+            image: image.raw(),
+            value,
+            ranges: subresource_ranges
+                .into_iter()
+                .map(|range| range.borrow().clone())
+                .collect(),
+        });
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        self.commands.push(Command::ClearAttachments {
+            clears: clears.into_iter().map(|clear| *clear.borrow()).collect(),
+            rects: rects
+                .into_iter()
+                .map(|rect| rect.borrow().clone())
+                .collect(),
+        });
+    }
+
+    unsafe fn resolve_image<T>(
+        &mut self,
+        src: &native::Image,
+        _: image::Layout,
+        dst: &native::Image,
+        _: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageResolve>,
+    {
+        self.commands.push(Command::ResolveImage(
+            src.raw(),
+            dst.raw(),
+            regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        ));
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        src: &native::Image,
+        _: image::Layout,
+        dst: &native::Image,
+        _: image::Layout,
+        filter: image::Filter,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageBlit>,
+    {
+        self.commands.push(Command::BlitImage(
+            src.raw(),
+            dst.raw(),
+            filter,
+            regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        ));
+    }
+
+    unsafe fn bind_index_buffer(&mut self, view: buffer::IndexBufferView<Backend>) {
+        self.commands.push(Command::BindIndexBuffer(
+            view.buffer.raw().range(&view.range),
+            view.index_type,
+        ));
+    }
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, first_binding: pso::BufferIndex, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer>,
+    {
+        let buffers = buffers
+            .into_iter()
+            .map(|(buffer, range)| buffer.borrow().raw().range(&range))
+            .collect();
+        self.commands
+            .push(Command::BindVertexBuffers(first_binding, buffers));
+    }
+
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, _: u32, _: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer>,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        // Only the first viewport is used, as there is no way to select the others.
+        if first_viewport == 0 {
+            if let Some(viewport) = viewports.into_iter().next() {
+                self.commands
+                    .push(Command::SetViewport(viewport.borrow().clone()));
+            }
+        }
+    }
+
+    unsafe fn set_scissors<T>(&mut self, first_scissor: u32, rects: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        if first_scissor == 0 {
+            if let Some(rect) = rects.into_iter().next() {
+                self.commands.push(Command::SetScissor(*rect.borrow()));
+            }
+        }
+    }
+
+    unsafe fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.commands
+            .push(Command::SetStencilReference(faces, value));
+    }
+
+    unsafe fn set_stencil_read_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.commands
+            .push(Command::SetStencilReadMask(faces, value));
+    }
+
+    unsafe fn set_stencil_write_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.commands
+            .push(Command::SetStencilWriteMask(faces, value));
+    }
+
+    unsafe fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.commands.push(Command::SetBlendConstants(color));
+    }
+
+    unsafe fn set_depth_bounds(&mut self, bounds: Range<f32>) {
+        self.commands.push(Command::SetDepthBounds(bounds));
+    }
+
+    unsafe fn set_line_width(&mut self, width: f32) {
+        if width != 1.0 {
+            warn!("Wide lines are not supported");
+        }
+    }
+
+    unsafe fn set_depth_bias(&mut self, depth_bias: pso::DepthBias) {
+        self.commands.push(Command::SetDepthBias(depth_bias));
+    }
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        render_pass: &native::RenderPass,
+        framebuffer: &native::Framebuffer,
+        render_area: pso::Rect,
+        clear_values: T,
+        _: command::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ClearValue>,
+    {
+        self.commands.push(Command::BeginRenderPass {
+            pass: Arc::clone(&render_pass.info),
+            attachments: Arc::clone(&framebuffer.attachments),
+            area: render_area,
+            clear_values: clear_values
+                .into_iter()
+                .map(|value| *value.borrow())
+                .collect(),
+        });
+    }
+
+    unsafe fn next_subpass(&mut self, _: command::SubpassContents) {
+        self.commands.push(Command::NextSubpass);
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        self.commands.push(Command::EndRenderPass);
+    }
+
+    unsafe fn bind_graphics_pipeline(&mut self, pipeline: &native::GraphicsPipeline) {
+        self.commands
+            .push(Command::BindGraphicsPipeline(Arc::clone(&pipeline.state)));
+    }
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        _: &native::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+        self.bind_descriptor_sets(BindPoint::Graphics, first_set, sets, offsets);
+    }
+
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &native::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(BindPoint::Graphics, layout, set, writes);
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, pipeline: &native::ComputePipeline) {
+        self.commands
+            .push(Command::BindComputePipeline(Arc::clone(&pipeline.program)));
+    }
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        _: &native::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+        self.bind_descriptor_sets(BindPoint::Compute, first_set, sets, offsets);
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &native::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(BindPoint::Compute, layout, set, writes);
+    }
+
+    unsafe fn dispatch(&mut self, count: hal::WorkGroupCount) {
+        self.commands.push(Command::Dispatch(count));
+    }
+
+    unsafe fn dispatch_indirect(&mut self, buffer: &native::Buffer, offset: buffer::Offset) {
+        let range = buffer::SubRange {
+            offset,
+            size: Some(12),
+        };
+        self.commands
+            .push(Command::DispatchIndirect(buffer.raw().range(&range)));
+    }
+
+    unsafe fn copy_buffer<T>(&mut self, src: &native::Buffer, dst: &native::Buffer, regions: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferCopy>,
+    {
+        self.commands.push(Command::CopyBuffer(
+            src.raw(),
+            dst.raw(),
+            regions.into_iter().map(|r| *r.borrow()).collect(),
+        ));
+    }
+
+    unsafe fn copy_image<T>(
+        &mut self,
+        src: &native::Image,
+        _: image::Layout,
+        dst: &native::Image,
+        _: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageCopy>,
+    {
+        self.commands.push(Command::CopyImage(
+            src.raw(),
+            dst.raw(),
+            regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        ));
+    }
+
+    unsafe fn copy_buffer_to_image<T>(
+        &mut self,
+        src: &native::Buffer,
+        dst: &native::Image,
+        _: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferImageCopy>,
+    {
+        self.commands.push(Command::CopyBufferToImage(
+            src.raw(),
+            dst.raw(),
+            regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        ));
+    }
+
+    unsafe fn copy_image_to_buffer<T>(
+        &mut self,
+        src: &native::Image,
+        _: image::Layout,
+        dst: &native::Buffer,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferImageCopy>,
+    {
+        self.commands.push(Command::CopyImageToBuffer(
+            src.raw(),
+            dst.raw(),
+            regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        ));
+    }
+
+    unsafe fn draw(
+        &mut self,
+        vertices: Range<hal::VertexCount>,
+        instances: Range<hal::InstanceCount>,
+    ) {
+        self.commands.push(Command::Draw {
+            vertices,
+            instances,
+        });
+    }
+
+    unsafe fn draw_indexed(
+        &mut self,
+        indices: Range<hal::IndexCount>,
+        base_vertex: hal::VertexOffset,
+        instances: Range<hal::InstanceCount>,
+    ) {
+        self.commands.push(Command::DrawIndexed {
+            indices,
+            base_vertex,
+            instances,
+        });
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(buffer, offset, None, draw_count, stride, false);
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(buffer, offset, None, draw_count, stride, true);
+    }
+
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &native::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(
+            buffer,
+            offset,
+            Some((count_buffer, count_buffer_offset)),
+            max_draw_count,
+            stride,
+            false,
+        );
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &native::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.draw_indirect_impl(
+            buffer,
+            offset,
+            Some((count_buffer, count_buffer_offset)),
+            max_draw_count,
+            stride,
+            true,
+        );
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        self.commands.push(Command::BeginConditionalRendering(
+            buffer.raw().range(&buffer::SubRange {
+                offset,
+                size: Some(4),
+            }),
+            inverted,
+        ));
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &native::QueryPool,
+        id: query::Id,
+        inverted: bool,
+    ) {
+        self.commands.push(Command::BeginConditionalRenderingQuery(
+            Arc::clone(&pool.queries),
+            id,
+            inverted,
+        ));
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.commands.push(Command::EndConditionalRendering);
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        unimplemented!()
+    }
+
+    unsafe fn set_event(&mut self, event: &native::Event, _: pso::PipelineStage) {
+        self.commands
+            .push(Command::SetEvent(Arc::clone(&event.set), true));
+    }
+
+    unsafe fn reset_event(&mut self, event: &native::Event, _: pso::PipelineStage) {
+        self.commands
+            .push(Command::SetEvent(Arc::clone(&event.set), false));
+    }
+
+    unsafe fn wait_events<'a, I, J>(&mut self, _: I, _: Range<pso::PipelineStage>, _: J)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::Event>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Backend>>,
+    {
+        // Events are set by earlier commands, which are already executed.
+    }
+
+    unsafe fn begin_query(&mut self, query: query::Query<Backend>, _: query::ControlFlags) {
+        self.commands.push(Command::BeginQuery(
+            Arc::clone(&query.pool.queries),
+            query.id,
+        ));
+    }
+
+    unsafe fn end_query(&mut self, query: query::Query<Backend>) {
+        self.commands
+            .push(Command::EndQuery(Arc::clone(&query.pool.queries), query.id));
+    }
+
+    unsafe fn reset_query_pool(&mut self, pool: &native::QueryPool, queries: Range<query::Id>) {
+        self.commands
+            .push(Command::ResetQueries(Arc::clone(&pool.queries), queries));
+    }
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &native::QueryPool,
+        queries: Range<query::Id>,
+        buffer: &native::Buffer,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) {
+        let range = buffer::SubRange { offset, size: None };
+        self.commands.push(Command::CopyQueryResults {
+            pool: Arc::clone(&pool.queries),
+            queries,
+            buffer: buffer.raw().range(&range),
+            stride,
+            flags,
+        });
+    }
+
+    unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, query: query::Query<Backend>) {
+        self.commands.push(Command::WriteTimestamp(
+            Arc::clone(&query.pool.queries),
+            query.id,
+        ));
+    }
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        _: &native::PipelineLayout,
+        _: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.commands.push(Command::PushConstants {
+            bind_point: BindPoint::Graphics,
+            offset,
+            data: constants.to_vec(),
+        });
+    }
+
+    unsafe fn push_compute_constants(
+        &mut self,
+        _: &native::PipelineLayout,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.commands.push(Command::PushConstants {
+            bind_point: BindPoint::Compute,
+            offset,
+            data: constants.to_vec(),
+        });
+    }
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, buffers: I)
+    where
+        T: 'a + Borrow<CommandBuffer>,
+        I: IntoIterator<Item = &'a T>,
+    {
+        for buffer in buffers {
+            self.commands
+                .extend(buffer.borrow().commands.iter().cloned());
+        }
+    }
+
+    unsafe fn insert_debug_marker(&mut self, _: &str, _: u32) {}
+    unsafe fn begin_debug_marker(&mut self, _: &str, _: u32) {}
+    unsafe fn end_debug_marker(&mut self) {}
+}
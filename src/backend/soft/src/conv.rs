@@ -0,0 +1,435 @@
+//! Conversions between formatted texel data and the values used by shaders.
+//!
+//! Texels are represented by four 32-bit words. Integer formats store the (sign-extended)
+//! integers, all the other formats store the bits of the `f32` values.
+
+use hal::format::{BaseFormat, ChannelType, Format, SurfaceType};
+
+/// Unpacked texel value.
+pub type Texel = [u32; 4];
+
+/// Bit offset and width of a channel inside of a texel.
+type Channel = (u8, u8);
+
+/// Channel layout of a color surface type, in RGBA order.
+fn channels(surface: SurfaceType) -> Option<[Option<Channel>; 4]> {
+    use hal::format::SurfaceType as S;
+    Some(match surface {
+        S::R4_G4 => [Some((4, 4)), Some((0, 4)), None, None],
+        S::R4_G4_B4_A4 => [Some((12, 4)), Some((8, 4)), Some((4, 4)), Some((0, 4))],
+        S::B4_G4_R4_A4 => [Some((4, 4)), Some((8, 4)), Some((12, 4)), Some((0, 4))],
+        S::R5_G6_B5 => [Some((11, 5)), Some((5, 6)), Some((0, 5)), None],
+        S::B5_G6_R5 => [Some((0, 5)), Some((5, 6)), Some((11, 5)), None],
+        S::R5_G5_B5_A1 => [Some((11, 5)), Some((6, 5)), Some((1, 5)), Some((0, 1))],
+        S::B5_G5_R5_A1 => [Some((1, 5)), Some((6, 5)), Some((11, 5)), Some((0, 1))],
+        S::A1_R5_G5_B5 => [Some((10, 5)), Some((5, 5)), Some((0, 5)), Some((15, 1))],
+        S::R8 => [Some((0, 8)), None, None, None],
+        S::R8_G8 => [Some((0, 8)), Some((8, 8)), None, None],
+        S::R8_G8_B8 => [Some((0, 8)), Some((8, 8)), Some((16, 8)), None],
+        S::B8_G8_R8 => [Some((16, 8)), Some((8, 8)), Some((0, 8)), None],
+        S::R8_G8_B8_A8 | S::A8_B8_G8_R8 => {
+            [Some((0, 8)), Some((8, 8)), Some((16, 8)), Some((24, 8))]
+        }
+        S::B8_G8_R8_A8 => [Some((16, 8)), Some((8, 8)), Some((0, 8)), Some((24, 8))],
+        S::A2_R10_G10_B10 => [Some((20, 10)), Some((10, 10)), Some((0, 10)), Some((30, 2))],
+        S::A2_B10_G10_R10 => [Some((0, 10)), Some((10, 10)), Some((20, 10)), Some((30, 2))],
+        S::R16 => [Some((0, 16)), None, None, None],
+        S::R16_G16 => [Some((0, 16)), Some((16, 16)), None, None],
+        S::R16_G16_B16 => [Some((0, 16)), Some((16, 16)), Some((32, 16)), None],
+        S::R16_G16_B16_A16 => [
+            Some((0, 16)),
+            Some((16, 16)),
+            Some((32, 16)),
+            Some((48, 16)),
+        ],
+        S::R32 => [Some((0, 32)), None, None, None],
+        S::R32_G32 => [Some((0, 32)), Some((32, 32)), None, None],
+        S::R32_G32_B32 => [Some((0, 32)), Some((32, 32)), Some((64, 32)), None],
+        S::R32_G32_B32_A32 => [
+            Some((0, 32)),
+            Some((32, 32)),
+            Some((64, 32)),
+            Some((96, 32)),
+        ],
+        S::B10_G11_R11 => [Some((0, 11)), Some((11, 11)), Some((22, 10)), None],
+        _ => return None,
+    })
+}
+
+/// Returns true if texels of the format can be read and written.
+pub fn is_supported(format: Format) -> bool {
+    let BaseFormat(surface, channel) = format.base_format();
+    let desc = surface.desc();
+    if desc.is_compressed() {
+        return false;
+    }
+    if format.is_depth() || format.is_stencil() {
+        return true;
+    }
+    match (surface, channel) {
+        (SurfaceType::B10_G11_R11, ChannelType::Ufloat) => true,
+        (SurfaceType::E5_B9_G9_R9, ChannelType::Ufloat) => true,
+        (SurfaceType::B10_G11_R11, _) | (_, ChannelType::Ufloat) => false,
+        _ => match channels(surface) {
+            Some(channels) => channels.iter().flatten().all(|&(_, bits)| match channel {
+                ChannelType::Sfloat => bits == 16 || bits == 32,
+                ChannelType::Srgb => bits == 8,
+                _ => true,
+            }),
+            None => false,
+        },
+    }
+}
+
+/// Returns true if the format stores integers, which can't be filtered or blended.
+pub fn is_integer(format: Format) -> bool {
+    match format.base_format().1 {
+        ChannelType::Uint | ChannelType::Sint => !(format.is_depth() || format.is_stencil()),
+        _ => false,
+    }
+}
+
+/// Returns true if the format stores normalized fixed-point values.
+pub fn is_normalized(format: Format) -> bool {
+    matches!(
+        format.base_format().1,
+        ChannelType::Unorm | ChannelType::Snorm | ChannelType::Srgb
+    )
+}
+
+/// Returns the size of a texel in bytes.
+pub fn texel_size(format: Format) -> usize {
+    (format.surface_desc().bits / 8) as usize
+}
+
+fn load_bits(bytes: &[u8]) -> u128 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0u128, |bits, &byte| (bits << 8) | byte as u128)
+}
+
+fn store_bits(bits: u128, bytes: &mut [u8]) {
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (bits >> (i * 8)) as u8;
+    }
+}
+
+fn mask(bits: u8) -> u32 {
+    if bits >= 32 {
+        !0
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn sign_extend(value: u32, bits: u8) -> i32 {
+    let shift = 32 - bits as u32;
+    ((value << shift) as i32) >> shift
+}
+
+/// Reads a texel of the format from the given bytes.
+pub fn decode(format: Format, bytes: &[u8]) -> Texel {
+    let BaseFormat(surface, channel) = format.base_format();
+    let bits = load_bits(&bytes[.. texel_size(format)]);
+    if format.is_depth() {
+        return [decode_depth(format, bytes).to_bits(), 0, 0, 1f32.to_bits()];
+    }
+    if format.is_stencil() {
+        return [decode_stencil(format, bytes) as u32, 0, 0, 1];
+    }
+    if surface == SurfaceType::E5_B9_G9_R9 {
+        let bits = bits as u32;
+        let scale = 2f32.powi(((bits >> 27) as i32) - 15 - 9);
+        return [
+            ((bits & 0x1FF) as f32 * scale).to_bits(),
+            (((bits >> 9) & 0x1FF) as f32 * scale).to_bits(),
+            (((bits >> 18) & 0x1FF) as f32 * scale).to_bits(),
+            1f32.to_bits(),
+        ];
+    }
+
+    let one = if is_integer(format) {
+        1
+    } else {
+        1f32.to_bits()
+    };
+    let mut texel = [0, 0, 0, one];
+    let layout = match channels(surface) {
+        Some(layout) => layout,
+        None => return texel,
+    };
+    for (i, channel_layout) in layout.iter().enumerate() {
+        let (offset, width) = match *channel_layout {
+            Some(layout) => layout,
+            None => continue,
+        };
+        let raw = (bits >> offset) as u32 & mask(width);
+        texel[i] = match channel {
+            ChannelType::Unorm => (raw as f32 / mask(width) as f32).to_bits(),
+            ChannelType::Srgb if i < 3 => srgb_to_linear(raw as f32 / 255.0).to_bits(),
+            ChannelType::Srgb => (raw as f32 / 255.0).to_bits(),
+            ChannelType::Snorm => {
+                let max = mask(width - 1) as f32;
+                (sign_extend(raw, width) as f32 / max).max(-1.0).to_bits()
+            }
+            ChannelType::Uint => raw,
+            ChannelType::Sint => sign_extend(raw, width) as u32,
+            ChannelType::Uscaled => (raw as f32).to_bits(),
+            ChannelType::Sscaled => (sign_extend(raw, width) as f32).to_bits(),
+            ChannelType::Sfloat if width == 16 => f16_to_f32(raw as u16).to_bits(),
+            ChannelType::Sfloat => raw,
+            ChannelType::Ufloat => small_float_to_f32(raw, width - 5).to_bits(),
+        };
+    }
+    texel
+}
+
+/// Writes a texel of the format into the given bytes.
+pub fn encode(format: Format, texel: Texel, bytes: &mut [u8]) {
+    let BaseFormat(surface, channel) = format.base_format();
+    let size = texel_size(format);
+    if format.is_depth() {
+        encode_depth(format, f32::from_bits(texel[0]), bytes);
+        return;
+    }
+    if format.is_stencil() {
+        encode_stencil(format, texel[0] as u8, bytes);
+        return;
+    }
+    if surface == SurfaceType::E5_B9_G9_R9 {
+        let bits = encode_rgb9e5([
+            f32::from_bits(texel[0]),
+            f32::from_bits(texel[1]),
+            f32::from_bits(texel[2]),
+        ]);
+        store_bits(bits as u128, &mut bytes[.. size]);
+        return;
+    }
+
+    let layout = match channels(surface) {
+        Some(layout) => layout,
+        None => return,
+    };
+    let mut bits = 0u128;
+    for (i, channel_layout) in layout.iter().enumerate() {
+        let (offset, width) = match *channel_layout {
+            Some(layout) => layout,
+            None => continue,
+        };
+        let value = f32::from_bits(texel[i]);
+        let raw = match channel {
+            ChannelType::Unorm => quantize_unorm(value, width),
+            ChannelType::Srgb if i < 3 => quantize_unorm(linear_to_srgb(value), width),
+            ChannelType::Srgb => quantize_unorm(value, width),
+            ChannelType::Snorm => {
+                let max = mask(width - 1) as f32;
+                (value.clamp(-1.0, 1.0) * max).round() as i32 as u32
+            }
+            ChannelType::Uint => texel[i].min(mask(width)),
+            ChannelType::Sint => {
+                let max = mask(width - 1) as i32;
+                (texel[i] as i32).max(-max - 1).min(max) as u32
+            }
+            ChannelType::Uscaled => value.max(0.0).min(mask(width) as f32) as u32,
+            ChannelType::Sscaled => {
+                let max = mask(width - 1) as f32;
+                value.max(-max - 1.0).min(max) as i32 as u32
+            }
+            ChannelType::Sfloat if width == 16 => f32_to_f16(value) as u32,
+            ChannelType::Sfloat => texel[i],
+            ChannelType::Ufloat => f32_to_small_float(value, width - 5),
+        };
+        bits |= ((raw & mask(width)) as u128) << offset;
+    }
+    store_bits(bits, &mut bytes[.. size]);
+}
+
+fn quantize_unorm(value: f32, bits: u8) -> u32 {
+    let max = mask(bits) as f32;
+    // NaN gets mapped to zero here.
+    (value.clamp(0.0, 1.0) * max + 0.5) as u32
+}
+
+/// Returns the number of bits used to store depth values of the format.
+fn depth_layout(format: Format) -> Option<(u8, bool)> {
+    use hal::format::SurfaceType as S;
+    match format.base_format().0 {
+        S::D16 | S::D16_S8 => Some((16, false)),
+        S::X8D24 | S::D24_S8 => Some((24, false)),
+        S::D32 | S::D32_S8 => Some((32, true)),
+        _ => None,
+    }
+}
+
+/// Reads the depth value of a depth format texel.
+pub fn decode_depth(format: Format, bytes: &[u8]) -> f32 {
+    match depth_layout(format) {
+        Some((_, true)) => f32::from_bits(load_bits(&bytes[.. 4]) as u32),
+        Some((bits, false)) => {
+            let raw = load_bits(&bytes[.. (bits / 8) as usize]) as u32 & mask(bits);
+            raw as f32 / mask(bits) as f32
+        }
+        None => 0.0,
+    }
+}
+
+/// Writes the depth value of a depth format texel, keeping the stencil value.
+pub fn encode_depth(format: Format, depth: f32, bytes: &mut [u8]) {
+    match depth_layout(format) {
+        Some((_, true)) => store_bits(depth.to_bits() as u128, &mut bytes[.. 4]),
+        Some((bits, false)) => {
+            let raw = quantize_unorm(depth, bits);
+            store_bits(raw as u128, &mut bytes[.. (bits / 8) as usize]);
+        }
+        None => {}
+    }
+}
+
+/// Returns the byte offset of the stencil value inside of a texel.
+fn stencil_offset(format: Format) -> Option<usize> {
+    use hal::format::SurfaceType as S;
+    match format.base_format().0 {
+        S::S8 => Some(0),
+        S::D16_S8 => Some(2),
+        S::D24_S8 => Some(3),
+        S::D32_S8 => Some(4),
+        _ => None,
+    }
+}
+
+/// Reads the stencil value of a stencil format texel.
+pub fn decode_stencil(format: Format, bytes: &[u8]) -> u8 {
+    stencil_offset(format).map_or(0, |offset| bytes[offset])
+}
+
+/// Writes the stencil value of a stencil format texel, keeping the depth value.
+pub fn encode_stencil(format: Format, stencil: u8, bytes: &mut [u8]) {
+    if let Some(offset) = stencil_offset(format) {
+        bytes[offset] = stencil;
+    }
+}
+
+/// Returns the format used for copying the given aspect of a depth/stencil format
+/// to and from buffers.
+pub fn aspect_copy_format(format: Format, depth: bool) -> Format {
+    if depth {
+        match depth_layout(format) {
+            Some((16, _)) => Format::D16Unorm,
+            Some((24, _)) => Format::X8D24Unorm,
+            _ => Format::D32Sfloat,
+        }
+    } else {
+        Format::S8Uint
+    }
+}
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half >> 15) as u32) << 31;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // Subnormal half, which is a normal float.
+            let value = mantissa as f32 / (1 << 24) as f32;
+            return if sign != 0 { -value } else { value };
+        }
+        0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+    if exponent == 0xFF {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7C00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let half = mantissa >> shift;
+        let round = (mantissa >> (shift - 1)) & 1;
+        return sign | (half + round) as u16;
+    }
+    let half = ((exponent as u32) << 10) | (mantissa >> 13);
+    let round = (mantissa >> 12) & 1;
+    // Rounding may carry into the exponent, which is still correct.
+    sign | (half + round) as u16
+}
+
+/// Converts an unsigned float with a 5-bit exponent and the given number of mantissa bits.
+fn small_float_to_f32(raw: u32, mantissa_bits: u8) -> f32 {
+    let exponent = (raw >> mantissa_bits) & 0x1F;
+    let mantissa = raw & mask(mantissa_bits);
+    let scale = (1 << mantissa_bits) as f32;
+    match exponent {
+        0 => mantissa as f32 / scale * 2f32.powi(-14),
+        0x1F if mantissa == 0 => f32::INFINITY,
+        0x1F => f32::NAN,
+        _ => (1.0 + mantissa as f32 / scale) * 2f32.powi(exponent as i32 - 15),
+    }
+}
+
+fn f32_to_small_float(value: f32, mantissa_bits: u8) -> u32 {
+    if value.is_nan() {
+        return (0x1F << mantissa_bits) | 1;
+    }
+    if value <= 0.0 {
+        return 0;
+    }
+    let half = f32_to_f16(value) as u32;
+    let shift = 10 - mantissa_bits as u32;
+    if half >= 0x7C00 {
+        return 0x1F << mantissa_bits;
+    }
+    // Truncate the mantissa of the equivalent half float.
+    half >> shift
+}
+
+fn encode_rgb9e5(rgb: [f32; 3]) -> u32 {
+    const MAX: f32 = 65408.0;
+    // NaN is encoded as zero.
+    let clamp = |value: f32| if value > 0.0 { value.min(MAX) } else { 0.0 };
+    let clamped = [clamp(rgb[0]), clamp(rgb[1]), clamp(rgb[2])];
+    let max = clamped[0].max(clamped[1]).max(clamped[2]);
+    let mut exponent = (max.log2().floor() as i32).max(-16) + 1 + 15;
+    let mut scale = 2f32.powi(exponent - 15 - 9);
+    if (max / scale + 0.5).floor() as u32 == 512 {
+        exponent += 1;
+        scale *= 2.0;
+    }
+    let quantize = |value: f32| (value / scale + 0.5).floor() as u32 & 0x1FF;
+    quantize(clamped[0])
+        | (quantize(clamped[1]) << 9)
+        | (quantize(clamped[2]) << 18)
+        | ((exponent as u32) << 27)
+}
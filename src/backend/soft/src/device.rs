@@ -0,0 +1,923 @@
+use crate::native::{Descriptor, DescriptorBinding, GraphicsState, ImageInfo, RawBuffer, RawImage};
+use crate::{
+    aligned,
+    bound_range,
+    conv,
+    limits,
+    memory_properties,
+    memory_type_mask,
+    native,
+    shader,
+    Backend,
+};
+use crate::{CommandBuffer, CommandPool, DescriptorPool, Surface, Swapchain};
+use hal::{buffer, device, external, format, image, memory, pass, pool, pso, query, queue, window};
+use parking_lot::Mutex;
+use spirv_headers as spirv;
+
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const IMAGE_ALIGNMENT: u64 = 256;
+
+fn program(
+    entry: &pso::EntryPoint<Backend>,
+    model: spirv::ExecutionModel,
+) -> Result<shader::Program, pso::CreationError> {
+    let module = &entry.module.module;
+    if !module
+        .entry_points
+        .iter()
+        .any(|ep| ep.name == entry.entry && ep.model == model)
+    {
+        return Err(pso::CreationError::Shader(
+            device::ShaderError::MissingEntryPoint(entry.entry.to_string()),
+        ));
+    }
+    shader::Program::new(module, entry.entry, model, &entry.specialization)
+        .map_err(|err| pso::CreationError::Shader(device::ShaderError::CompilationFailed(err)))
+}
+
+/// Returns the sampler set by the layout for a descriptor, if any.
+fn immutable_sampler(descriptor: &Descriptor) -> Option<image::SamplerDesc> {
+    match *descriptor {
+        Descriptor::Sampler(ref desc) | Descriptor::CombinedImageSampler(_, ref desc) => {
+            Some(desc.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Iterates over the descriptor positions starting at the given binding and array element,
+/// continuing with the next bindings once the array is exhausted.
+fn descriptor_positions(
+    bindings: &[DescriptorBinding],
+    binding: pso::DescriptorBinding,
+    array_offset: pso::DescriptorArrayIndex,
+) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let first = bindings
+        .iter()
+        .position(|b| b.binding == binding)
+        .unwrap_or(bindings.len());
+    bindings
+        .iter()
+        .enumerate()
+        .skip(first)
+        .flat_map(|(index, binding)| (0 .. binding.descriptors.len()).map(move |i| (index, i)))
+        .skip(array_offset)
+}
+
+/// Writes descriptors into the bindings of a set, starting at the given binding and array
+/// element.
+pub(crate) fn write_descriptors<'a, J>(
+    bindings: &mut [DescriptorBinding],
+    binding: pso::DescriptorBinding,
+    array_offset: pso::DescriptorArrayIndex,
+    descriptors: J,
+) where
+    J: IntoIterator,
+    J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+{
+    let positions = descriptor_positions(bindings, binding, array_offset).collect::<Vec<_>>();
+    for ((index, element), descriptor) in positions.into_iter().zip(descriptors) {
+        let binding = &mut bindings[index];
+        let immutable = if binding.immutable_samplers {
+            immutable_sampler(&binding.descriptors[element])
+        } else {
+            None
+        };
+        let value = match *descriptor.borrow() {
+            pso::Descriptor::Sampler(sampler) => {
+                Descriptor::Sampler(immutable.unwrap_or_else(|| sampler.desc.clone()))
+            }
+            pso::Descriptor::Image(view, _) => match immutable {
+                Some(desc) => Descriptor::CombinedImageSampler(view.clone(), desc),
+                None => Descriptor::Image(view.clone()),
+            },
+            pso::Descriptor::CombinedImageSampler(view, _, sampler) => {
+                Descriptor::CombinedImageSampler(
+                    view.clone(),
+                    immutable.unwrap_or_else(|| sampler.desc.clone()),
+                )
+            }
+            pso::Descriptor::Buffer(buffer, ref range) => {
+                Descriptor::Buffer(buffer.raw().range(range))
+            }
+            pso::Descriptor::TexelBuffer(view) => {
+                Descriptor::TexelBuffer(view.raw.clone(), view.format)
+            }
+        };
+        binding.descriptors[element] = value;
+    }
+}
+
+/// Software device.
+#[derive(Debug)]
+pub struct Device {
+    limits: hal::Limits,
+}
+
+impl Device {
+    pub(crate) fn new() -> Self {
+        Device { limits: limits() }
+    }
+
+    /// Creates the images of a swapchain, each backed by its own memory.
+    pub(crate) fn create_swapchain_images(
+        &self,
+        config: &window::SwapchainConfig,
+    ) -> Result<Vec<native::Image>, window::CreationError> {
+        let kind = image::Kind::D2(
+            config.extent.width,
+            config.extent.height,
+            config.image_layers,
+            1,
+        );
+        (0 .. config.image_count)
+            .map(|_| unsafe {
+                let mut image = device::Device::create_image(
+                    self,
+                    kind,
+                    1,
+                    config.format,
+                    image::Tiling::Optimal,
+                    config.image_usage,
+                    image::ViewCapabilities::empty(),
+                    memory::SparseFlags::empty(),
+                )
+                // Only supported formats are exposed by the surface, so failing
+                // here means the image is too large to be allocated.
+                .map_err(|_| device::OutOfMemory::Host)?;
+                let memory = native::Memory::new(hal::MemoryTypeId(0), image.requirements.size);
+                image.raw = Some(RawImage {
+                    block: memory.block,
+                    offset: 0,
+                    info: image.info,
+                });
+                Ok(image)
+            })
+            .collect()
+    }
+}
+
+impl device::Device<Backend> for Device {
+    unsafe fn create_command_pool(
+        &self,
+        _: queue::QueueFamilyId,
+        _: pool::CommandPoolCreateFlags,
+    ) -> Result<CommandPool, device::OutOfMemory> {
+        Ok(CommandPool)
+    }
+
+    unsafe fn destroy_command_pool(&self, _: CommandPool) {}
+
+    unsafe fn allocate_memory(
+        &self,
+        memory_type: hal::MemoryTypeId,
+        size: u64,
+    ) -> Result<native::Memory, device::AllocationError> {
+        Ok(native::Memory::new(memory_type, size))
+    }
+
+    unsafe fn free_memory(&self, _: native::Memory) {}
+
+    unsafe fn allocate_exportable_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Memory, device::AllocationError> {
+        // `AllocationError` can't tell that the call is unsupported, so the misuse is logged.
+        error!("External memory features are not supported");
+        Err(device::OutOfMemory::Device.into())
+    }
+
+    unsafe fn export_memory(
+        &self,
+        _memory: &native::Memory,
+        _handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        _size: u64,
+        _handle_type: external::MemoryHandleType,
+        _fd: external::Fd,
+    ) -> Result<native::Memory, external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        subpasses: IS,
+        _: ID,
+        _: &[pass::ViewMask],
+    ) -> Result<native::RenderPass, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        let subpasses = subpasses
+            .into_iter()
+            .map(|subpass| {
+                let subpass = subpass.borrow();
+                native::Subpass {
+                    colors: subpass.colors.iter().map(|&(id, _)| id).collect(),
+                    depth_stencil: subpass.depth_stencil.map(|&(id, _)| id),
+                }
+            })
+            .collect();
+        Ok(native::RenderPass {
+            info: Arc::new(native::RenderPassInfo {
+                attachments: attachments
+                    .into_iter()
+                    .map(|attachment| attachment.borrow().clone())
+                    .collect(),
+                subpasses,
+            }),
+        })
+    }
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        set_layouts: IS,
+        _: IR,
+    ) -> Result<native::PipelineLayout, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<native::DescriptorSetLayout>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        Ok(native::PipelineLayout {
+            sets: set_layouts
+                .into_iter()
+                .map(|layout| layout.borrow().bindings.clone())
+                .collect(),
+        })
+    }
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        _: Option<&[u8]>,
+    ) -> Result<native::PipelineCache, device::OutOfMemory> {
+        Ok(native::PipelineCache)
+    }
+
+    unsafe fn get_pipeline_cache_data(
+        &self,
+        _: &native::PipelineCache,
+    ) -> Result<Vec<u8>, device::OutOfMemory> {
+        Ok(Vec::new())
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, _: native::PipelineCache) {}
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        desc: &pso::GraphicsPipelineDesc<'a, Backend>,
+        _: Option<&native::PipelineCache>,
+    ) -> Result<native::GraphicsPipeline, pso::CreationError> {
+        let shaders = &desc.shaders;
+        let unsupported = [
+            (&shaders.hull, pso::Stage::Hull),
+            (&shaders.domain, pso::Stage::Domain),
+            (&shaders.geometry, pso::Stage::Geometry),
+        ];
+        for &(entry, stage) in unsupported.iter() {
+            if entry.is_some() {
+                return Err(pso::CreationError::Shader(
+                    device::ShaderError::UnsupportedStage(stage),
+                ));
+            }
+        }
+        if desc.subpass.index as usize >= desc.subpass.main_pass.info.subpasses.len() {
+            return Err(pso::CreationError::InvalidSubpass(desc.subpass.index));
+        }
+
+        let state = GraphicsState {
+            vertex: program(&shaders.vertex, spirv::ExecutionModel::Vertex)?,
+            fragment: match shaders.fragment {
+                Some(ref entry) => Some(program(entry, spirv::ExecutionModel::Fragment)?),
+                None => None,
+            },
+            rasterizer: desc.rasterizer,
+            vertex_buffers: desc.vertex_buffers.clone(),
+            attributes: desc.attributes.clone(),
+            input_assembler: desc.input_assembler.clone(),
+            blender: desc.blender.clone(),
+            depth_stencil: desc.depth_stencil,
+            multisampling: desc.multisampling.clone(),
+            baked_states: desc.baked_states.clone(),
+        };
+        Ok(native::GraphicsPipeline {
+            state: Arc::new(state),
+        })
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        desc: &pso::ComputePipelineDesc<'a, Backend>,
+        _: Option<&native::PipelineCache>,
+    ) -> Result<native::ComputePipeline, pso::CreationError> {
+        Ok(native::ComputePipeline {
+            program: Arc::new(program(&desc.shader, spirv::ExecutionModel::GLCompute)?),
+        })
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        _: &native::PipelineCache,
+        _: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::PipelineCache>,
+    {
+        Ok(())
+    }
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        _: &native::RenderPass,
+        attachments: I,
+        _: image::Extent,
+    ) -> Result<native::Framebuffer, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::ImageView>,
+    {
+        Ok(native::Framebuffer {
+            attachments: Arc::new(
+                attachments
+                    .into_iter()
+                    .map(|view| view.borrow().clone())
+                    .collect(),
+            ),
+        })
+    }
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv: &[u32],
+    ) -> Result<native::ShaderModule, device::ShaderError> {
+        let module =
+            shader::Module::parse(spirv).map_err(device::ShaderError::CompilationFailed)?;
+        Ok(native::ShaderModule {
+            module: Arc::new(module),
+        })
+    }
+
+    unsafe fn create_sampler(
+        &self,
+        desc: &image::SamplerDesc,
+    ) -> Result<native::Sampler, device::AllocationError> {
+        Ok(native::Sampler { desc: desc.clone() })
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        _: memory::SparseFlags,
+    ) -> Result<native::Buffer, buffer::CreationError> {
+        let limits = &self.limits;
+        let mut alignment = 4;
+        if usage.contains(buffer::Usage::UNIFORM) {
+            alignment = alignment.max(limits.min_uniform_buffer_offset_alignment);
+        }
+        if usage.contains(buffer::Usage::STORAGE) {
+            alignment = alignment.max(limits.min_storage_buffer_offset_alignment);
+        }
+        if usage.intersects(buffer::Usage::UNIFORM_TEXEL | buffer::Usage::STORAGE_TEXEL) {
+            alignment = alignment.max(limits.min_texel_buffer_offset_alignment);
+        }
+
+        Ok(native::Buffer {
+            size,
+            requirements: memory::Requirements {
+                size: aligned(size, alignment),
+                alignment,
+                type_mask: memory_type_mask(&memory_properties()),
+            },
+            raw: None,
+        })
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        _size: u64,
+        usage: buffer::Usage,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Buffer, buffer::CreationError> {
+        Err(buffer::CreationError::UnsupportedUsage { usage })
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &native::Buffer) -> memory::Requirements {
+        buffer.requirements
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &native::Memory,
+        offset: u64,
+        buffer: &mut native::Buffer,
+    ) -> Result<(), device::BindError> {
+        bound_range(memory, offset, &buffer.requirements)?;
+        buffer.raw = Some(RawBuffer {
+            block: Arc::clone(&memory.block),
+            offset: offset as usize,
+            size: buffer.size as usize,
+        });
+        Ok(())
+    }
+
+    unsafe fn create_buffer_view(
+        &self,
+        buffer: &native::Buffer,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    ) -> Result<native::BufferView, buffer::ViewCreationError> {
+        match format {
+            Some(format) if conv::is_supported(format) => Ok(native::BufferView {
+                raw: buffer.raw().range(&range),
+                format,
+            }),
+            _ => Err(buffer::ViewCreationError::UnsupportedFormat(format)),
+        }
+    }
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        _: image::Tiling,
+        _: image::Usage,
+        _: image::ViewCapabilities,
+        _: memory::SparseFlags,
+    ) -> Result<native::Image, image::CreationError> {
+        if !conv::is_supported(format) {
+            return Err(image::CreationError::Format(format));
+        }
+        if kind.num_samples() != 1 {
+            return Err(image::CreationError::Samples(kind.num_samples()));
+        }
+        let info = ImageInfo {
+            kind,
+            levels: mip_levels,
+            format,
+        };
+        Ok(native::Image {
+            info,
+            requirements: memory::Requirements {
+                size: aligned(info.size() as u64, IMAGE_ALIGNMENT),
+                alignment: IMAGE_ALIGNMENT,
+                type_mask: memory_type_mask(&memory_properties()),
+            },
+            raw: None,
+        })
+    }
+
+    unsafe fn create_external_image(
+        &self,
+        _kind: image::Kind,
+        _mip_levels: image::Level,
+        _format: format::Format,
+        _tiling: image::Tiling,
+        usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Image, image::CreationError> {
+        Err(image::CreationError::Usage(usage))
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        _kind: image::Kind,
+        format: format::Format,
+        _usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _modifier: external::DrmFormatModifier,
+    ) -> Result<native::Image, image::CreationError> {
+        Err(image::CreationError::Format(format))
+    }
+
+    unsafe fn get_image_requirements(&self, image: &native::Image) -> memory::Requirements {
+        image.requirements
+    }
+
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        _: &native::Image,
+    ) -> Vec<image::SparseRequirements> {
+        Vec::new()
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &native::Image,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        let info = &image.info;
+        let range = info.subresource_range(subresource.level, subresource.layer);
+        let (row_pitch, depth_pitch) = info.pitches(subresource.level);
+        image::SubresourceFootprint {
+            slice: range.start as u64 .. range.end as u64,
+            row_pitch: row_pitch as u64,
+            array_pitch: info.layer_size() as u64,
+            depth_pitch: depth_pitch as u64,
+        }
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &native::Memory,
+        offset: u64,
+        image: &mut native::Image,
+    ) -> Result<(), device::BindError> {
+        bound_range(memory, offset, &image.requirements)?;
+        image.raw = Some(RawImage {
+            block: Arc::clone(&memory.block),
+            offset: offset as usize,
+            info: image.info,
+        });
+        Ok(())
+    }
+
+    unsafe fn create_image_view(
+        &self,
+        image: &native::Image,
+        kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<native::ImageView, image::ViewCreationError> {
+        if range.levels.end > image.info.levels {
+            return Err(image::ViewCreationError::Level(range.levels.start));
+        }
+        if range.layers.end > image.info.kind.num_layers() {
+            return Err(image::ViewCreationError::Layer(
+                image::LayerError::OutOfBounds(range.layers),
+            ));
+        }
+        if format.surface_desc().bits != image.info.format.surface_desc().bits {
+            return Err(image::ViewCreationError::BadFormat(format));
+        }
+        Ok(native::ImageView {
+            image: image.raw(),
+            kind,
+            format,
+            swizzle,
+            range,
+        })
+    }
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        max_sets: usize,
+        _: I,
+        _: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        Ok(DescriptorPool {
+            max_sets,
+            allocated: 0,
+        })
+    }
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        immutable_samplers: J,
+        _: pso::DescriptorSetLayoutCreateFlags,
+    ) -> Result<native::DescriptorSetLayout, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<native::Sampler>,
+    {
+        let mut samplers = immutable_samplers.into_iter();
+        let mut bindings = bindings
+            .into_iter()
+            .map(|binding| {
+                let binding = binding.borrow();
+                let descriptors = (0 .. binding.count)
+                    .map(|_| {
+                        if binding.immutable_samplers {
+                            samplers.next().map_or(Descriptor::Empty, |sampler| {
+                                Descriptor::Sampler(sampler.borrow().desc.clone())
+                            })
+                        } else {
+                            Descriptor::Empty
+                        }
+                    })
+                    .collect();
+                DescriptorBinding {
+                    binding: binding.binding,
+                    ty: binding.ty,
+                    immutable_samplers: binding.immutable_samplers,
+                    descriptors,
+                }
+            })
+            .collect::<Vec<_>>();
+        bindings.sort_by_key(|binding| binding.binding);
+        Ok(native::DescriptorSetLayout { bindings })
+    }
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, writes: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Backend, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        for write in writes {
+            let mut bindings = write.set.bindings.write();
+            write_descriptors(
+                &mut bindings,
+                write.binding,
+                write.array_offset,
+                write.descriptors,
+            );
+        }
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, copies: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Backend>>,
+    {
+        for copy in copies {
+            let copy = copy.borrow();
+            let descriptors = {
+                let bindings = copy.src_set.bindings.read();
+                descriptor_positions(&bindings, copy.src_binding, copy.src_array_offset)
+                    .take(copy.count)
+                    .map(|(index, element)| bindings[index].descriptors[element].clone())
+                    .collect::<Vec<_>>()
+            };
+            let mut bindings = copy.dst_set.bindings.write();
+            let positions =
+                descriptor_positions(&bindings, copy.dst_binding, copy.dst_array_offset)
+                    .collect::<Vec<_>>();
+            for ((index, element), descriptor) in positions.into_iter().zip(descriptors) {
+                bindings[index].descriptors[element] = descriptor;
+            }
+        }
+    }
+
+    fn create_semaphore(&self) -> Result<native::Semaphore, device::OutOfMemory> {
+        Ok(native::Semaphore::new(0))
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: queue::SemaphoreValue,
+    ) -> Result<native::Semaphore, device::OutOfMemory> {
+        Ok(native::Semaphore::new(initial_value))
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &native::Semaphore,
+    ) -> Result<queue::SemaphoreValue, device::DeviceLost> {
+        Ok(semaphore.value())
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &native::Semaphore,
+        value: queue::SemaphoreValue,
+        _: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        // Submissions are executed before `submit` returns.
+        Ok(semaphore.value() >= value)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &native::Semaphore,
+        value: queue::SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        semaphore.signal(value);
+        Ok(())
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        _handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<native::Semaphore, device::OutOfMemory> {
+        // `OutOfMemory` can't tell that the call is unsupported, so the misuse is logged.
+        error!("EXTERNAL_SEMAPHORE_FD feature is not supported");
+        Err(device::OutOfMemory::Device)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        _semaphore: &native::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        Err(external::ExportError::Unsupported)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        _semaphore: &native::Semaphore,
+        _handle_type: external::SemaphoreHandleType,
+        _fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        Err(external::ImportError::InvalidHandle)
+    }
+
+    fn create_fence(&self, signaled: bool) -> Result<native::Fence, device::OutOfMemory> {
+        Ok(native::Fence::new(signaled))
+    }
+
+    unsafe fn reset_fence(&self, fence: &native::Fence) -> Result<(), device::OutOfMemory> {
+        fence.set(false);
+        Ok(())
+    }
+
+    unsafe fn wait_for_fence(
+        &self,
+        fence: &native::Fence,
+        _: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        // Submissions are executed before `submit` returns.
+        Ok(fence.is_signaled())
+    }
+
+    unsafe fn get_fence_status(&self, fence: &native::Fence) -> Result<bool, device::DeviceLost> {
+        Ok(fence.is_signaled())
+    }
+
+    fn create_event(&self) -> Result<native::Event, device::OutOfMemory> {
+        Ok(native::Event {
+            set: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    unsafe fn get_event_status(
+        &self,
+        event: &native::Event,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        Ok(event.set.load(Ordering::Acquire))
+    }
+
+    unsafe fn set_event(&self, event: &native::Event) -> Result<(), device::OutOfMemory> {
+        event.set.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    unsafe fn reset_event(&self, event: &native::Event) -> Result<(), device::OutOfMemory> {
+        event.set.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        _: query::Type,
+        count: query::Id,
+    ) -> Result<native::QueryPool, query::CreationError> {
+        Ok(native::QueryPool {
+            queries: Arc::new(Mutex::new(native::QueryResults {
+                results: vec![None; count as usize],
+            })),
+        })
+    }
+
+    unsafe fn destroy_query_pool(&self, _: native::QueryPool) {}
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &native::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        Ok(pool
+            .queries
+            .lock()
+            .write(queries, data, stride as usize, flags))
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &native::Memory,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        let end = segment.offset + segment.size.unwrap_or(0);
+        if segment.offset > memory.size() || end > memory.size() {
+            return Err(device::MapError::OutOfBounds);
+        }
+        Ok(memory.block.ptr().add(segment.offset as usize))
+    }
+
+    unsafe fn unmap_memory(&self, _: &native::Memory) {}
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory, memory::Segment)>,
+    {
+        Ok(())
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(&self, _: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory, memory::Segment)>,
+    {
+        Ok(())
+    }
+
+    unsafe fn destroy_shader_module(&self, _: native::ShaderModule) {}
+
+    unsafe fn destroy_render_pass(&self, _: native::RenderPass) {}
+
+    unsafe fn destroy_pipeline_layout(&self, _: native::PipelineLayout) {}
+
+    unsafe fn destroy_graphics_pipeline(&self, _: native::GraphicsPipeline) {}
+
+    unsafe fn destroy_compute_pipeline(&self, _: native::ComputePipeline) {}
+
+    unsafe fn destroy_framebuffer(&self, _: native::Framebuffer) {}
+
+    unsafe fn destroy_buffer(&self, _: native::Buffer) {}
+
+    unsafe fn destroy_buffer_view(&self, _: native::BufferView) {}
+
+    unsafe fn destroy_image(&self, _: native::Image) {}
+
+    unsafe fn destroy_image_view(&self, _: native::ImageView) {}
+
+    unsafe fn destroy_sampler(&self, _: native::Sampler) {}
+
+    unsafe fn destroy_descriptor_pool(&self, _: DescriptorPool) {}
+
+    unsafe fn destroy_descriptor_set_layout(&self, _: native::DescriptorSetLayout) {}
+
+    unsafe fn destroy_fence(&self, _: native::Fence) {}
+
+    unsafe fn destroy_semaphore(&self, _: native::Semaphore) {}
+
+    unsafe fn destroy_event(&self, _: native::Event) {}
+
+    unsafe fn create_swapchain(
+        &self,
+        _: &mut Surface,
+        config: window::SwapchainConfig,
+        _: Option<Swapchain>,
+    ) -> Result<(Swapchain, Vec<native::Image>), window::CreationError> {
+        let images = self.create_swapchain_images(&config)?;
+        let swapchain = Swapchain::new(config, images.iter().map(native::Image::raw).collect());
+        Ok((swapchain, images))
+    }
+
+    unsafe fn destroy_swapchain(&self, _: Swapchain) {}
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        Ok(())
+    }
+
+    unsafe fn set_image_name(&self, _: &mut native::Image, _: &str) {}
+
+    unsafe fn set_buffer_name(&self, _: &mut native::Buffer, _: &str) {}
+
+    unsafe fn set_command_buffer_name(&self, _: &mut CommandBuffer, _: &str) {}
+
+    unsafe fn set_semaphore_name(&self, _: &mut native::Semaphore, _: &str) {}
+
+    unsafe fn set_fence_name(&self, _: &mut native::Fence, _: &str) {}
+
+    unsafe fn set_framebuffer_name(&self, _: &mut native::Framebuffer, _: &str) {}
+
+    unsafe fn set_render_pass_name(&self, _: &mut native::RenderPass, _: &str) {}
+
+    unsafe fn set_descriptor_set_name(&self, _: &mut native::DescriptorSet, _: &str) {}
+
+    unsafe fn set_descriptor_set_layout_name(&self, _: &mut native::DescriptorSetLayout, _: &str) {}
+}
@@ -0,0 +1,948 @@
+//! Execution of recorded commands on the host.
+
+use crate::command::{BindPoint, BoundSet, Command};
+use crate::conv::{self, Texel};
+use crate::native::{GraphicsState, ImageView, QueryResults, RawBuffer, RawImage, RenderPassInfo};
+use crate::raster::Renderer;
+use crate::sample::{self, Bindings};
+use crate::shader::{Invocation, Program, Status, Value};
+use hal::{command, format, image, pso, query, IndexType};
+use parking_lot::Mutex;
+use spirv_headers as spirv;
+
+use std::ops::Range;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{iter, ptr};
+
+/// Size of the push constant storage, in bytes.
+pub(crate) const PUSH_CONSTANTS_SIZE: usize = 256;
+
+/// Pipeline state set by commands.
+#[derive(Debug)]
+pub(crate) struct DynamicState {
+    pub(crate) viewport: Option<pso::Viewport>,
+    pub(crate) scissor: Option<pso::Rect>,
+    pub(crate) stencil_reference: pso::Sided<pso::StencilValue>,
+    pub(crate) stencil_read_mask: pso::Sided<pso::StencilValue>,
+    pub(crate) stencil_write_mask: pso::Sided<pso::StencilValue>,
+    pub(crate) blend_constants: pso::ColorValue,
+    pub(crate) depth_bounds: Range<f32>,
+    pub(crate) depth_bias: pso::DepthBias,
+}
+
+impl Default for DynamicState {
+    fn default() -> Self {
+        DynamicState {
+            viewport: None,
+            scissor: None,
+            stencil_reference: pso::Sided::new(0),
+            stencil_read_mask: pso::Sided::new(!0),
+            stencil_write_mask: pso::Sided::new(!0),
+            blend_constants: [0.0; 4],
+            depth_bounds: 0.0 .. 1.0,
+            depth_bias: pso::DepthBias::default(),
+        }
+    }
+}
+
+/// Render pass instance being executed.
+#[derive(Debug)]
+pub(crate) struct PassState {
+    pub(crate) info: Arc<RenderPassInfo>,
+    pub(crate) attachments: Arc<Vec<ImageView>>,
+    pub(crate) area: pso::Rect,
+    pub(crate) subpass: usize,
+}
+
+#[derive(Debug)]
+struct ActiveQuery {
+    pool: Arc<Mutex<QueryResults>>,
+    id: query::Id,
+    samples_passed: u64,
+}
+
+fn set_sided(
+    sided: &mut pso::Sided<pso::StencilValue>,
+    faces: pso::Face,
+    value: pso::StencilValue,
+) {
+    if faces.contains(pso::Face::FRONT) {
+        sided.front = value;
+    }
+    if faces.contains(pso::Face::BACK) {
+        sided.back = value;
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    if let Some(slice) = bytes.get(offset .. offset + 4) {
+        word.copy_from_slice(slice);
+    }
+    u32::from_le_bytes(word)
+}
+
+fn offset_coords(offset: image::Offset, x: u32, y: u32, z: u32) -> [i32; 3] {
+    [
+        offset.x + x as i32,
+        offset.y + y as i32,
+        offset.z + z as i32,
+    ]
+}
+
+/// Returns the texel used to clear the given aspects.
+fn clear_texel(value: &command::ClearValue, aspects: format::Aspects) -> Texel {
+    if aspects.contains(format::Aspects::COLOR) {
+        unsafe { value.color.uint32 }
+    } else {
+        let depth_stencil = unsafe { value.depth_stencil };
+        if aspects == format::Aspects::STENCIL {
+            [depth_stencil.stencil, 0, 0, 0]
+        } else {
+            [depth_stencil.depth.to_bits(), depth_stencil.stencil, 0, 0]
+        }
+    }
+}
+
+/// Writes a texel to a box of a subresource.
+#[allow(clippy::too_many_arguments)]
+fn fill(
+    image: &RawImage,
+    format: format::Format,
+    aspects: format::Aspects,
+    level: image::Level,
+    layer: image::Layer,
+    origin: [i32; 3],
+    extent: image::Extent,
+    texel: Texel,
+) {
+    for z in 0 .. extent.depth {
+        for y in 0 .. extent.height {
+            for x in 0 .. extent.width {
+                let coords = [
+                    origin[0] + x as i32,
+                    origin[1] + y as i32,
+                    origin[2] + z as i32,
+                ];
+                sample::write_texel(image, format, aspects, level, layer, coords, texel);
+            }
+        }
+    }
+}
+
+/// Clears a rectangle of the layers of an attachment.
+fn clear_view(
+    view: &ImageView,
+    aspects: format::Aspects,
+    rect: pso::Rect,
+    layers: Range<image::Layer>,
+    texel: Texel,
+) {
+    let extent = image::Extent {
+        width: rect.w.max(0) as u32,
+        height: rect.h.max(0) as u32,
+        depth: 1,
+    };
+    for layer in layers {
+        fill(
+            &view.image,
+            view.format,
+            aspects,
+            view.range.levels.start,
+            view.range.layers.start + layer,
+            [rect.x as i32, rect.y as i32, 0],
+            extent,
+            texel,
+        );
+    }
+}
+
+/// Copies the given aspects of a texel between images of compatible formats.
+fn copy_texel(
+    src: &RawImage,
+    src_subresource: (image::Level, image::Layer),
+    src_coords: [i32; 3],
+    dst: &RawImage,
+    dst_subresource: (image::Level, image::Layer),
+    dst_coords: [i32; 3],
+    aspects: format::Aspects,
+) {
+    let format = src.info.format;
+    if aspects != format.surface_desc().aspects {
+        // Only one aspect of a combined depth/stencil format is copied.
+        let (level, layer) = src_subresource;
+        if let Some(texel) = sample::read_texel(src, format, aspects, level, layer, src_coords) {
+            let (level, layer) = dst_subresource;
+            sample::write_texel(
+                dst,
+                dst.info.format,
+                aspects,
+                level,
+                layer,
+                dst_coords,
+                texel,
+            );
+        }
+        return;
+    }
+    let src_ptr = src.texel_ptr(src_subresource.0, src_subresource.1, src_coords);
+    let dst_ptr = dst.texel_ptr(dst_subresource.0, dst_subresource.1, dst_coords);
+    if let (Some(src_ptr), Some(dst_ptr)) = (src_ptr, dst_ptr) {
+        let size = src.info.texel_size().min(dst.info.texel_size());
+        unsafe { ptr::copy(src_ptr, dst_ptr, size) };
+    }
+}
+
+fn copy_image(
+    src: &RawImage,
+    src_subresource: &image::SubresourceLayers,
+    src_offset: image::Offset,
+    dst: &RawImage,
+    dst_subresource: &image::SubresourceLayers,
+    dst_offset: image::Offset,
+    extent: image::Extent,
+) {
+    let layers = src_subresource
+        .layers
+        .clone()
+        .zip(dst_subresource.layers.clone());
+    for (src_layer, dst_layer) in layers {
+        for z in 0 .. extent.depth {
+            for y in 0 .. extent.height {
+                for x in 0 .. extent.width {
+                    copy_texel(
+                        src,
+                        (src_subresource.level, src_layer),
+                        offset_coords(src_offset, x, y, z),
+                        dst,
+                        (dst_subresource.level, dst_layer),
+                        offset_coords(dst_offset, x, y, z),
+                        src_subresource.aspects,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Copies a region between a buffer and an image, in the direction given by `to_image`.
+fn copy_buffer_image(
+    buffer: &RawBuffer,
+    image: &RawImage,
+    region: &command::BufferImageCopy,
+    to_image: bool,
+) {
+    let aspects = region.image_layers.aspects;
+    let level = region.image_layers.level;
+    let format = image.info.format;
+    let buffer_format = if format.is_depth() || format.is_stencil() {
+        conv::aspect_copy_format(format, aspects.contains(format::Aspects::DEPTH))
+    } else {
+        format
+    };
+    let texel_size = conv::texel_size(buffer_format);
+    let extent = region.image_extent;
+    let row_length = match region.buffer_width {
+        0 => extent.width,
+        width => width,
+    } as usize;
+    let image_height = match region.buffer_height {
+        0 => extent.height,
+        height => height,
+    } as usize;
+    let data = unsafe { buffer.bytes_mut() };
+
+    for (i, layer) in region.image_layers.layers.clone().enumerate() {
+        for z in 0 .. extent.depth {
+            for y in 0 .. extent.height {
+                for x in 0 .. extent.width {
+                    let index = ((i * extent.depth as usize + z as usize) * image_height
+                        + y as usize)
+                        * row_length
+                        + x as usize;
+                    let offset = region.buffer_offset as usize + index * texel_size;
+                    let bytes = match data.get_mut(offset .. offset + texel_size) {
+                        Some(bytes) => bytes,
+                        None => continue,
+                    };
+                    let coords = offset_coords(region.image_offset, x, y, z);
+                    if buffer_format == format {
+                        if let Some(texel) = unsafe { image.texel(level, layer, coords) } {
+                            if to_image {
+                                texel.copy_from_slice(bytes);
+                            } else {
+                                bytes.copy_from_slice(texel);
+                            }
+                        }
+                    } else if to_image {
+                        let texel = conv::decode(buffer_format, bytes);
+                        sample::write_texel(image, format, aspects, level, layer, coords, texel);
+                    } else if let Some(texel) =
+                        sample::read_texel(image, format, aspects, level, layer, coords)
+                    {
+                        conv::encode(buffer_format, texel, bytes);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn blit_image(src: &RawImage, dst: &RawImage, filter: image::Filter, region: &command::ImageBlit) {
+    let aspects = region.src_subresource.aspects;
+    let (src_format, dst_format) = (src.info.format, dst.info.format);
+    let src_level = region.src_subresource.level;
+    let src_extent = src.info.level_extent(src_level);
+    let src_size = [
+        src_extent.width as i32,
+        src_extent.height as i32,
+        src_extent.depth as i32,
+    ];
+    let (s0, s1) = (&region.src_bounds.start, &region.src_bounds.end);
+    let (d0, d1) = (&region.dst_bounds.start, &region.dst_bounds.end);
+    let src_start = [s0.x as f32, s0.y as f32, s0.z as f32];
+    let src_delta = [
+        (s1.x - s0.x) as f32,
+        (s1.y - s0.y) as f32,
+        (s1.z - s0.z) as f32,
+    ];
+    let dst_start = [d0.x as f32, d0.y as f32, d0.z as f32];
+    let dst_delta = [
+        (d1.x - d0.x) as f32,
+        (d1.y - d0.y) as f32,
+        (d1.z - d0.z) as f32,
+    ];
+    // Integer and depth/stencil formats are never filtered.
+    let linear = filter == image::Filter::Linear
+        && !conv::is_integer(src_format)
+        && aspects == format::Aspects::COLOR;
+
+    let layers = region
+        .src_subresource
+        .layers
+        .clone()
+        .zip(region.dst_subresource.layers.clone());
+    for (src_layer, dst_layer) in layers {
+        let read = |coords: [i32; 3]| {
+            let mut clamped = [0; 3];
+            for i in 0 .. 3 {
+                clamped[i] = coords[i].max(0).min(src_size[i] - 1);
+            }
+            sample::read_texel(src, src_format, aspects, src_level, src_layer, clamped)
+                .unwrap_or([0; 4])
+        };
+        for z in d0.z.min(d1.z) .. d0.z.max(d1.z) {
+            for y in d0.y.min(d1.y) .. d0.y.max(d1.y) {
+                for x in d0.x.min(d1.x) .. d0.x.max(d1.x) {
+                    let dst_coords = [x, y, z];
+                    let mut position = [0.0; 3];
+                    for i in 0 .. 3 {
+                        let t = (dst_coords[i] as f32 + 0.5 - dst_start[i]) / dst_delta[i];
+                        position[i] = src_start[i] + t * src_delta[i];
+                    }
+                    let texel = if linear {
+                        let base = [
+                            (position[0] - 0.5).floor(),
+                            (position[1] - 0.5).floor(),
+                            (position[2] - 0.5).floor(),
+                        ];
+                        let mut result = [0.0; 4];
+                        for corner in 0 .. 8 {
+                            let mut weight = 1.0;
+                            let mut coords = [0; 3];
+                            for i in 0 .. 3 {
+                                let fraction = position[i] - 0.5 - base[i];
+                                let high = corner >> i & 1 == 1;
+                                weight *= if high { fraction } else { 1.0 - fraction };
+                                coords[i] = base[i] as i32 + high as i32;
+                            }
+                            if weight == 0.0 {
+                                continue;
+                            }
+                            let texel = read(coords);
+                            for (r, t) in result.iter_mut().zip(texel.iter()) {
+                                *r += weight * f32::from_bits(*t);
+                            }
+                        }
+                        [
+                            result[0].to_bits(),
+                            result[1].to_bits(),
+                            result[2].to_bits(),
+                            result[3].to_bits(),
+                        ]
+                    } else {
+                        read([
+                            position[0].floor() as i32,
+                            position[1].floor() as i32,
+                            position[2].floor() as i32,
+                        ])
+                    };
+                    sample::write_texel(
+                        dst,
+                        dst_format,
+                        aspects,
+                        region.dst_subresource.level,
+                        dst_layer,
+                        dst_coords,
+                        texel,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+/// Executes the commands of a command buffer.
+///
+/// No state is kept between command buffers.
+#[derive(Debug)]
+pub(crate) struct Executor {
+    graphics_pipeline: Option<Arc<GraphicsState>>,
+    compute_pipeline: Option<Arc<Program>>,
+    graphics_sets: Vec<Option<BoundSet>>,
+    compute_sets: Vec<Option<BoundSet>>,
+    graphics_constants: Vec<u8>,
+    compute_constants: Vec<u8>,
+    index_buffer: Option<(RawBuffer, IndexType)>,
+    vertex_buffers: Vec<Option<RawBuffer>>,
+    dynamic: DynamicState,
+    pass: Option<PassState>,
+    queries: Vec<ActiveQuery>,
+    /// Set while the predicate of conditional rendering is false.
+    discarding: bool,
+}
+
+impl Executor {
+    pub(crate) fn new() -> Self {
+        Executor {
+            graphics_pipeline: None,
+            compute_pipeline: None,
+            graphics_sets: Vec::new(),
+            compute_sets: Vec::new(),
+            graphics_constants: vec![0; PUSH_CONSTANTS_SIZE],
+            compute_constants: vec![0; PUSH_CONSTANTS_SIZE],
+            index_buffer: None,
+            vertex_buffers: Vec::new(),
+            dynamic: DynamicState::default(),
+            pass: None,
+            queries: Vec::new(),
+            discarding: false,
+        }
+    }
+
+    pub(crate) fn execute(&mut self, commands: &[Command]) {
+        for command in commands {
+            self.command(command);
+        }
+    }
+
+    fn command(&mut self, command: &Command) {
+        if self.discarding {
+            match *command {
+                Command::ClearAttachments { .. }
+                | Command::Draw { .. }
+                | Command::DrawIndexed { .. }
+                | Command::DrawIndirect { .. }
+                | Command::Dispatch(_)
+                | Command::DispatchIndirect(_) => return,
+                _ => {}
+            }
+        }
+
+        match *command {
+            Command::BindGraphicsPipeline(ref state) => {
+                self.graphics_pipeline = Some(Arc::clone(state));
+            }
+            Command::BindComputePipeline(ref program) => {
+                self.compute_pipeline = Some(Arc::clone(program));
+            }
+            Command::BindDescriptorSets {
+                bind_point,
+                first_set,
+                ref sets,
+            } => {
+                let bound = match bind_point {
+                    BindPoint::Graphics => &mut self.graphics_sets,
+                    BindPoint::Compute => &mut self.compute_sets,
+                };
+                if bound.len() < first_set + sets.len() {
+                    bound.resize(first_set + sets.len(), None);
+                }
+                for (slot, set) in bound[first_set ..].iter_mut().zip(sets) {
+                    *slot = Some(set.clone());
+                }
+            }
+            Command::BindIndexBuffer(ref buffer, index_type) => {
+                self.index_buffer = Some((buffer.clone(), index_type));
+            }
+            Command::BindVertexBuffers(first, ref buffers) => {
+                let first = first as usize;
+                if self.vertex_buffers.len() < first + buffers.len() {
+                    self.vertex_buffers.resize(first + buffers.len(), None);
+                }
+                for (slot, buffer) in self.vertex_buffers[first ..].iter_mut().zip(buffers) {
+                    *slot = Some(buffer.clone());
+                }
+            }
+            Command::SetViewport(ref viewport) => {
+                self.dynamic.viewport = Some(viewport.clone());
+            }
+            Command::SetScissor(rect) => {
+                self.dynamic.scissor = Some(rect);
+            }
+            Command::SetStencilReference(faces, value) => {
+                set_sided(&mut self.dynamic.stencil_reference, faces, value);
+            }
+            Command::SetStencilReadMask(faces, value) => {
+                set_sided(&mut self.dynamic.stencil_read_mask, faces, value);
+            }
+            Command::SetStencilWriteMask(faces, value) => {
+                set_sided(&mut self.dynamic.stencil_write_mask, faces, value);
+            }
+            Command::SetBlendConstants(color) => {
+                self.dynamic.blend_constants = color;
+            }
+            Command::SetDepthBounds(ref bounds) => {
+                self.dynamic.depth_bounds = bounds.clone();
+            }
+            Command::SetDepthBias(bias) => {
+                self.dynamic.depth_bias = bias;
+            }
+            Command::PushConstants {
+                bind_point,
+                offset,
+                ref data,
+            } => {
+                let constants = match bind_point {
+                    BindPoint::Graphics => &mut self.graphics_constants,
+                    BindPoint::Compute => &mut self.compute_constants,
+                };
+                let bytes = data.iter().flat_map(|word| word.to_le_bytes().to_vec());
+                for (byte, value) in constants[offset as usize ..].iter_mut().zip(bytes) {
+                    *byte = value;
+                }
+            }
+            Command::BeginRenderPass {
+                ref pass,
+                ref attachments,
+                area,
+                ref clear_values,
+            } => {
+                self.begin_render_pass(pass, attachments, area, clear_values);
+            }
+            Command::NextSubpass => {
+                if let Some(ref mut pass) = self.pass {
+                    pass.subpass += 1;
+                }
+            }
+            Command::EndRenderPass => {
+                // Attachments are single sampled, so there is nothing to resolve.
+                self.pass = None;
+            }
+            Command::ClearImage {
+                ref image,
+                ref value,
+                ref ranges,
+            } => {
+                for range in ranges {
+                    let texel = clear_texel(value, range.aspects);
+                    for level in range.levels.clone() {
+                        for layer in range.layers.clone() {
+                            fill(
+                                image,
+                                image.info.format,
+                                range.aspects,
+                                level,
+                                layer,
+                                [0; 3],
+                                image.info.level_extent(level),
+                                texel,
+                            );
+                        }
+                    }
+                }
+            }
+            Command::ClearAttachments {
+                ref clears,
+                ref rects,
+            } => self.clear_attachments(clears, rects),
+            Command::FillBuffer(ref buffer, data) => {
+                for word in unsafe { buffer.bytes_mut() }.chunks_exact_mut(4) {
+                    word.copy_from_slice(&data.to_le_bytes());
+                }
+            }
+            Command::UpdateBuffer(ref buffer, ref data) => {
+                let bytes = unsafe { buffer.bytes_mut() };
+                let size = bytes.len().min(data.len());
+                bytes[.. size].copy_from_slice(&data[.. size]);
+            }
+            Command::CopyBuffer(ref src, ref dst, ref regions) => {
+                for region in regions {
+                    let (src_offset, dst_offset) = (region.src as usize, region.dst as usize);
+                    let size = (region.size as usize)
+                        .min(src.size.saturating_sub(src_offset))
+                        .min(dst.size.saturating_sub(dst_offset));
+                    unsafe {
+                        ptr::copy(src.ptr().add(src_offset), dst.ptr().add(dst_offset), size);
+                    }
+                }
+            }
+            Command::CopyImage(ref src, ref dst, ref regions) => {
+                for region in regions {
+                    copy_image(
+                        src,
+                        &region.src_subresource,
+                        region.src_offset,
+                        dst,
+                        &region.dst_subresource,
+                        region.dst_offset,
+                        region.extent,
+                    );
+                }
+            }
+            Command::CopyBufferToImage(ref src, ref dst, ref regions) => {
+                for region in regions {
+                    copy_buffer_image(src, dst, region, true);
+                }
+            }
+            Command::CopyImageToBuffer(ref src, ref dst, ref regions) => {
+                for region in regions {
+                    copy_buffer_image(dst, src, region, false);
+                }
+            }
+            Command::BlitImage(ref src, ref dst, filter, ref regions) => {
+                for region in regions {
+                    blit_image(src, dst, filter, region);
+                }
+            }
+            Command::ResolveImage(ref src, ref dst, ref regions) => {
+                for region in regions {
+                    copy_image(
+                        src,
+                        &region.src_subresource,
+                        region.src_offset,
+                        dst,
+                        &region.dst_subresource,
+                        region.dst_offset,
+                        region.extent,
+                    );
+                }
+            }
+            Command::Draw {
+                ref vertices,
+                ref instances,
+            } => {
+                let indices = vertices.clone().map(Some).collect::<Vec<_>>();
+                self.draw(&indices, instances.clone());
+            }
+            Command::DrawIndexed {
+                ref indices,
+                base_vertex,
+                ref instances,
+            } => {
+                let indices = self.read_indices(indices.clone(), base_vertex);
+                self.draw(&indices, instances.clone());
+            }
+            Command::DrawIndirect {
+                ref buffer,
+                ref count_buffer,
+                count,
+                stride,
+                indexed,
+            } => {
+                let count = match *count_buffer {
+                    Some(ref count_buffer) => {
+                        count.min(read_u32(unsafe { count_buffer.bytes() }, 0))
+                    }
+                    None => count,
+                };
+                let bytes = unsafe { buffer.bytes() };
+                for i in 0 .. count as usize {
+                    let offset = i * stride as usize;
+                    let word = |index: usize| read_u32(bytes, offset + index * 4);
+                    if indexed {
+                        let indices =
+                            self.read_indices(word(2) .. word(2) + word(0), word(3) as i32);
+                        self.draw(&indices, word(4) .. word(4) + word(1));
+                    } else {
+                        let indices = (word(2) .. word(2) + word(0)).map(Some).collect::<Vec<_>>();
+                        self.draw(&indices, word(3) .. word(3) + word(1));
+                    }
+                }
+            }
+            Command::Dispatch(count) => self.dispatch(count),
+            Command::DispatchIndirect(ref buffer) => {
+                let bytes = unsafe { buffer.bytes() };
+                self.dispatch([read_u32(bytes, 0), read_u32(bytes, 4), read_u32(bytes, 8)]);
+            }
+            Command::BeginConditionalRendering(ref buffer, inverted) => {
+                self.discarding = (read_u32(unsafe { buffer.bytes() }, 0) == 0) != inverted;
+            }
+            Command::BeginConditionalRenderingQuery(ref pool, id, inverted) => {
+                // Queries are resolved as commands execute, so an unavailable result
+                // belongs to a query that never ran.
+                let result = pool
+                    .lock()
+                    .results
+                    .get(id as usize)
+                    .cloned()
+                    .and_then(|r| r);
+                self.discarding = (result.unwrap_or(0) == 0) != inverted;
+            }
+            Command::EndConditionalRendering => self.discarding = false,
+            Command::SetEvent(ref event, value) => {
+                event.store(value, Ordering::Release);
+            }
+            Command::BeginQuery(ref pool, id) => {
+                if let Some(result) = pool.lock().results.get_mut(id as usize) {
+                    *result = None;
+                }
+                self.queries.push(ActiveQuery {
+                    pool: Arc::clone(pool),
+                    id,
+                    samples_passed: 0,
+                });
+            }
+            Command::EndQuery(ref pool, id) => {
+                let position = self
+                    .queries
+                    .iter()
+                    .position(|query| Arc::ptr_eq(&query.pool, pool) && query.id == id);
+                if let Some(position) = position {
+                    let query = self.queries.swap_remove(position);
+                    if let Some(result) = pool.lock().results.get_mut(id as usize) {
+                        *result = Some(query.samples_passed);
+                    }
+                }
+            }
+            Command::ResetQueries(ref pool, ref queries) => {
+                let mut pool = pool.lock();
+                let end = (queries.end as usize).min(pool.results.len());
+                let start = (queries.start as usize).min(end);
+                for result in &mut pool.results[start .. end] {
+                    *result = None;
+                }
+            }
+            Command::WriteTimestamp(ref pool, id) => {
+                if let Some(result) = pool.lock().results.get_mut(id as usize) {
+                    *result = Some(timestamp());
+                }
+            }
+            Command::CopyQueryResults {
+                ref pool,
+                ref queries,
+                ref buffer,
+                stride,
+                flags,
+            } => {
+                let data = unsafe { buffer.bytes_mut() };
+                pool.lock()
+                    .write(queries.clone(), data, stride as usize, flags);
+            }
+        }
+    }
+
+    fn begin_render_pass(
+        &mut self,
+        info: &Arc<RenderPassInfo>,
+        attachments: &Arc<Vec<ImageView>>,
+        area: pso::Rect,
+        clear_values: &[command::ClearValue],
+    ) {
+        for (i, (attachment, view)) in info.attachments.iter().zip(attachments.iter()).enumerate() {
+            let value = match clear_values.get(i) {
+                Some(value) => value,
+                None => continue,
+            };
+            let format = view.format;
+            let mut aspects = format::Aspects::empty();
+            if format.is_color() && attachment.ops.load == hal::pass::AttachmentLoadOp::Clear {
+                aspects |= format::Aspects::COLOR;
+            }
+            if format.is_depth() && attachment.ops.load == hal::pass::AttachmentLoadOp::Clear {
+                aspects |= format::Aspects::DEPTH;
+            }
+            if format.is_stencil()
+                && attachment.stencil_ops.load == hal::pass::AttachmentLoadOp::Clear
+            {
+                aspects |= format::Aspects::STENCIL;
+            }
+            if !aspects.is_empty() {
+                let layers = 0 .. view.range.layers.end - view.range.layers.start;
+                clear_view(view, aspects, area, layers, clear_texel(value, aspects));
+            }
+        }
+        self.pass = Some(PassState {
+            info: Arc::clone(info),
+            attachments: Arc::clone(attachments),
+            area,
+            subpass: 0,
+        });
+    }
+
+    fn clear_attachments(&self, clears: &[command::AttachmentClear], rects: &[pso::ClearRect]) {
+        let pass = match self.pass {
+            Some(ref pass) => pass,
+            None => return,
+        };
+        let subpass = &pass.info.subpasses[pass.subpass];
+        for clear in clears {
+            let (attachment, aspects, texel) = match *clear {
+                command::AttachmentClear::Color { index, value } => (
+                    subpass.colors.get(index).cloned(),
+                    format::Aspects::COLOR,
+                    unsafe { value.uint32 },
+                ),
+                command::AttachmentClear::DepthStencil { depth, stencil } => {
+                    let mut aspects = format::Aspects::empty();
+                    if depth.is_some() {
+                        aspects |= format::Aspects::DEPTH;
+                    }
+                    if stencil.is_some() {
+                        aspects |= format::Aspects::STENCIL;
+                    }
+                    let value = command::ClearValue {
+                        depth_stencil: command::ClearDepthStencil {
+                            depth: depth.unwrap_or(0.0),
+                            stencil: stencil.unwrap_or(0),
+                        },
+                    };
+                    (subpass.depth_stencil, aspects, clear_texel(&value, aspects))
+                }
+            };
+            if let Some(view) = attachment.map(|index| &pass.attachments[index]) {
+                for rect in rects {
+                    clear_view(view, aspects, rect.rect, rect.layers.clone(), texel);
+                }
+            }
+        }
+    }
+
+    /// Reads indices from the bound index buffer, with `None` marking primitive restarts.
+    fn read_indices(&self, range: Range<u32>, base_vertex: hal::VertexOffset) -> Vec<Option<u32>> {
+        let (buffer, index_type) = match self.index_buffer {
+            Some((ref buffer, index_type)) => (buffer, index_type),
+            None => return Vec::new(),
+        };
+        let restart = matches!(
+            self.graphics_pipeline,
+            Some(ref state) if state.input_assembler.restart_index.is_some()
+        );
+        let bytes = unsafe { buffer.bytes() };
+        range
+            .map(|i| {
+                let (index, restart_index) = match index_type {
+                    IndexType::U16 => {
+                        let offset = i as usize * 2;
+                        let index = bytes
+                            .get(offset .. offset + 2)
+                            .map_or(0, |b| u16::from_le_bytes([b[0], b[1]]) as u32);
+                        (index, 0xFFFF)
+                    }
+                    IndexType::U32 => (read_u32(bytes, i as usize * 4), !0),
+                };
+                if restart && index == restart_index {
+                    None
+                } else {
+                    Some((index as i64 + base_vertex as i64) as u32)
+                }
+            })
+            .collect()
+    }
+
+    fn draw(&mut self, indices: &[Option<u32>], instances: Range<hal::InstanceCount>) {
+        let state = match self.graphics_pipeline {
+            Some(ref state) => Arc::clone(state),
+            None => return,
+        };
+        let pass = match self.pass {
+            Some(ref pass) => pass,
+            None => return,
+        };
+        let bindings = Bindings::new(&self.graphics_sets, &self.graphics_constants);
+        let mut renderer =
+            match Renderer::new(&state, &self.dynamic, pass, &self.vertex_buffers, bindings) {
+                Some(renderer) => renderer,
+                None => {
+                    warn!("Draw without a viewport is skipped");
+                    return;
+                }
+            };
+        for instance in instances.clone() {
+            renderer.draw(indices, instance, instances.start);
+        }
+        let samples_passed = renderer.samples_passed();
+        for query in &mut self.queries {
+            query.samples_passed += samples_passed;
+        }
+    }
+
+    fn dispatch(&mut self, count: hal::WorkGroupCount) {
+        let program = match self.compute_pipeline {
+            Some(ref program) => Arc::clone(program),
+            None => return,
+        };
+        let bindings = Bindings::new(&self.compute_sets, &self.compute_constants);
+        let [lx, ly, lz] = program.local_size;
+        let mut invocations = iter::repeat_with(|| Invocation::new(&program))
+            .take((lx * ly * lz) as usize)
+            .collect::<Vec<_>>();
+        let mut finished = vec![false; invocations.len()];
+        let mut shared = Vec::new();
+
+        for wz in 0 .. count[2] {
+            for wy in 0 .. count[1] {
+                for wx in 0 .. count[0] {
+                    shared.clone_from(&program.shared);
+                    for (index, invocation) in invocations.iter_mut().enumerate() {
+                        let index = index as u32;
+                        let local = [index % lx, index / lx % ly, index / (lx * ly)];
+                        invocation.reset();
+                        invocation.set_builtin(
+                            spirv::BuiltIn::NumWorkgroups,
+                            Value::from_scalars(&count),
+                        );
+                        invocation.set_builtin(
+                            spirv::BuiltIn::WorkgroupId,
+                            Value::from_scalars(&[wx, wy, wz]),
+                        );
+                        invocation.set_builtin(
+                            spirv::BuiltIn::LocalInvocationId,
+                            Value::from_scalars(&local),
+                        );
+                        invocation.set_builtin(
+                            spirv::BuiltIn::GlobalInvocationId,
+                            Value::from_scalars(&[
+                                wx * lx + local[0],
+                                wy * ly + local[1],
+                                wz * lz + local[2],
+                            ]),
+                        );
+                        invocation.set_builtin(
+                            spirv::BuiltIn::LocalInvocationIndex,
+                            Value::Scalar(index),
+                        );
+                    }
+
+                    // Invocations run in turns from one control barrier to the next.
+                    finished.fill(false);
+                    while finished.iter().any(|done| !done) {
+                        for (invocation, done) in invocations.iter_mut().zip(&mut finished) {
+                            if !*done {
+                                *done = invocation.run(&mut shared, &bindings) != Status::Barrier;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1251 @@
+//! Software rasterizer backend implementation.
+//!
+//! It exposes a single CPU adapter which executes command buffers on the host:
+//! SPIR-V shaders are interpreted, triangles, lines and points are rasterized with
+//! a fixed subpixel precision, and all resources live in host memory. Rendering is
+//! slow but deterministic, which makes the backend suitable for reference testing
+//! and for running applications where no GPU is available.
+
+#[macro_use]
+extern crate log;
+
+use hal::{
+    adapter,
+    device::{BindError, CreationError as DeviceCreationError, OutOfMemory},
+    format,
+    image,
+    memory,
+    pool,
+    pso,
+    queue,
+    window::{Extent2D, PresentError, Suboptimal, SwapImageIndex},
+};
+use std::borrow::Borrow;
+use std::ops::Range;
+
+mod command;
+mod conv;
+mod device;
+mod execute;
+pub mod native;
+mod raster;
+mod sample;
+mod shader;
+mod window;
+
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::window::{Surface, Swapchain};
+
+/// Software backend.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {}
+impl hal::Backend for Backend {
+    type Instance = Instance;
+    type PhysicalDevice = PhysicalDevice;
+    type Device = Device;
+
+    type Surface = Surface;
+    type Swapchain = Swapchain;
+
+    type QueueFamily = QueueFamily;
+    type CommandQueue = CommandQueue;
+    type CommandBuffer = CommandBuffer;
+
+    type Memory = native::Memory;
+    type CommandPool = CommandPool;
+
+    type ShaderModule = native::ShaderModule;
+    type RenderPass = native::RenderPass;
+    type Framebuffer = native::Framebuffer;
+
+    type Buffer = native::Buffer;
+    type BufferView = native::BufferView;
+    type Image = native::Image;
+    type ImageView = native::ImageView;
+    type Sampler = native::Sampler;
+
+    type ComputePipeline = native::ComputePipeline;
+    type GraphicsPipeline = native::GraphicsPipeline;
+    type PipelineCache = native::PipelineCache;
+    type PipelineLayout = native::PipelineLayout;
+    type DescriptorSetLayout = native::DescriptorSetLayout;
+    type DescriptorPool = DescriptorPool;
+    type DescriptorSet = native::DescriptorSet;
+
+    type Fence = native::Fence;
+    type Semaphore = native::Semaphore;
+    type Event = native::Event;
+    type QueryPool = native::QueryPool;
+}
+
+fn features() -> hal::Features {
+    hal::Features::ROBUST_BUFFER_ACCESS
+        | hal::Features::FULL_DRAW_INDEX_U32
+        | hal::Features::IMAGE_CUBE_ARRAY
+        | hal::Features::INDEPENDENT_BLENDING
+        | hal::Features::DUAL_SRC_BLENDING
+        | hal::Features::MULTI_DRAW_INDIRECT
+        | hal::Features::DRAW_INDIRECT_FIRST_INSTANCE
+        | hal::Features::DEPTH_CLAMP
+        | hal::Features::DEPTH_BIAS_CLAMP
+        | hal::Features::NON_FILL_POLYGON_MODE
+        | hal::Features::DEPTH_BOUNDS
+        | hal::Features::POINT_SIZE
+        | hal::Features::ALPHA_TO_ONE
+        | hal::Features::PRECISE_OCCLUSION_QUERY
+        | hal::Features::VERTEX_STORES_AND_ATOMICS
+        | hal::Features::FRAGMENT_STORES_AND_ATOMICS
+        | hal::Features::SHADER_UNIFORM_BUFFER_ARRAY_DYNAMIC_INDEXING
+        | hal::Features::SHADER_SAMPLED_IMAGE_ARRAY_DYNAMIC_INDEXING
+        | hal::Features::SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING
+        | hal::Features::SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING
+        | hal::Features::SAMPLER_MIRROR_CLAMP_EDGE
+        | hal::Features::SEPARATE_STENCIL_REF_VALUES
+        | hal::Features::INSTANCE_RATE
+        | hal::Features::SAMPLER_MIP_LOD_BIAS
+        | hal::Features::TIMELINE_SEMAPHORE
+        | hal::Features::PUSH_DESCRIPTOR
+        | hal::Features::DRAW_INDIRECT_COUNT
+        | hal::Features::CONDITIONAL_RENDERING
+        | hal::Features::CONDITIONAL_RENDERING_QUERY
+}
+
+fn limits() -> hal::Limits {
+    hal::Limits {
+        max_image_1d_size: 16384,
+        max_image_2d_size: 16384,
+        max_image_3d_size: 2048,
+        max_image_cube_size: 16384,
+        max_image_array_layers: 2048,
+        max_texel_elements: 1 << 27,
+        max_uniform_buffer_range: 1 << 16,
+        max_storage_buffer_range: 1 << 27,
+        max_push_constants_size: execute::PUSH_CONSTANTS_SIZE,
+        max_memory_allocation_count: 4096,
+        max_sampler_allocation_count: 4000,
+        max_bound_descriptor_sets: 8,
+        max_framebuffer_layers: 2048,
+        max_per_stage_descriptor_samplers: 16,
+        max_per_stage_descriptor_uniform_buffers: 12,
+        max_per_stage_descriptor_storage_buffers: 8,
+        max_per_stage_descriptor_sampled_images: 128,
+        max_per_stage_descriptor_storage_images: 8,
+        max_per_stage_descriptor_input_attachments: 8,
+        max_per_stage_resources: 256,
+        max_descriptor_set_samplers: 96,
+        max_descriptor_set_uniform_buffers: 72,
+        max_descriptor_set_uniform_buffers_dynamic: 8,
+        max_descriptor_set_storage_buffers: 48,
+        max_descriptor_set_storage_buffers_dynamic: 4,
+        max_descriptor_set_sampled_images: 768,
+        max_descriptor_set_storage_images: 48,
+        max_descriptor_set_input_attachments: 8,
+        max_vertex_input_attributes: 16,
+        max_vertex_input_bindings: 16,
+        max_vertex_input_attribute_offset: 2047,
+        max_vertex_input_binding_stride: 2048,
+        max_vertex_output_components: 64,
+        max_patch_size: 0,
+        max_geometry_shader_invocations: 0,
+        max_geometry_input_components: 0,
+        max_geometry_output_components: 0,
+        max_geometry_output_vertices: 0,
+        max_geometry_total_output_components: 0,
+        max_fragment_input_components: 64,
+        max_fragment_output_attachments: 8,
+        max_fragment_dual_source_attachments: 1,
+        max_fragment_combined_output_resources: 8,
+        max_compute_shared_memory_size: 32768,
+        max_compute_work_group_count: [65535; 3],
+        max_compute_work_group_invocations: 1024,
+        max_compute_work_group_size: [1024, 1024, 64],
+        max_draw_indexed_index_value: !0,
+        max_draw_indirect_count: !0,
+        max_sampler_lod_bias: 16.0,
+        max_sampler_anisotropy: 1.0,
+        max_viewports: 1,
+        max_viewport_dimensions: [16384; 2],
+        max_framebuffer_extent: image::Extent {
+            width: 16384,
+            height: 16384,
+            depth: 2048,
+        },
+        min_memory_map_alignment: 64,
+        buffer_image_granularity: 1,
+        min_texel_buffer_offset_alignment: 16,
+        min_uniform_buffer_offset_alignment: 256,
+        min_storage_buffer_offset_alignment: 16,
+        framebuffer_color_sample_counts: 0b1,
+        framebuffer_depth_sample_counts: 0b1,
+        framebuffer_stencil_sample_counts: 0b1,
+        max_color_attachments: 8,
+        standard_sample_locations: true,
+        optimal_buffer_copy_offset_alignment: 1,
+        optimal_buffer_copy_pitch_alignment: 1,
+        non_coherent_atom_size: 1,
+        min_vertex_input_binding_stride_alignment: 1,
+        // Timestamps are read from the system clock in nanoseconds.
+        timestamp_period: 1.0,
+        max_update_after_bind_descriptors_in_all_pools: 0,
+        max_per_stage_update_after_bind_resources: 0,
+        max_push_descriptors: 32,
+        max_multiview_view_count: 0,
+        max_transform_feedback_buffers: 0,
+    }
+}
+
+fn memory_properties() -> adapter::MemoryProperties {
+    adapter::MemoryProperties {
+        memory_types: vec![
+            adapter::MemoryType {
+                properties: memory::Properties::DEVICE_LOCAL
+                    | memory::Properties::CPU_VISIBLE
+                    | memory::Properties::COHERENT,
+                heap_index: 0,
+            },
+            adapter::MemoryType {
+                properties: memory::Properties::DEVICE_LOCAL
+                    | memory::Properties::CPU_VISIBLE
+                    | memory::Properties::CPU_CACHED,
+                heap_index: 0,
+            },
+        ],
+        memory_heaps: vec![1 << 32],
+    }
+}
+
+/// Software physical device.
+#[derive(Debug)]
+pub struct PhysicalDevice;
+
+impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
+    unsafe fn open(
+        &self,
+        families: &[(&QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Backend>, DeviceCreationError> {
+        if !features().contains(requested_features) {
+            return Err(DeviceCreationError::MissingFeature);
+        }
+
+        let queue_groups = families
+            .iter()
+            .map(|&(family, priorities)| {
+                let mut group = queue::QueueGroup::new(family.id);
+                for _ in priorities {
+                    group.add_queue(CommandQueue);
+                }
+                group
+            })
+            .collect();
+
+        Ok(adapter::Gpu {
+            device: Device::new(),
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        match format {
+            Some(format) if conv::is_supported(format) => {
+                let mut image = format::ImageFeature::SAMPLED
+                    | format::ImageFeature::STORAGE
+                    | format::ImageFeature::BLIT_SRC
+                    | format::ImageFeature::BLIT_DST;
+                if format.is_depth() || format.is_stencil() {
+                    image |= format::ImageFeature::DEPTH_STENCIL_ATTACHMENT;
+                } else {
+                    image |= format::ImageFeature::COLOR_ATTACHMENT;
+                    if !conv::is_integer(format) {
+                        image |= format::ImageFeature::SAMPLED_LINEAR
+                            | format::ImageFeature::COLOR_ATTACHMENT_BLEND;
+                    }
+                }
+                format::Properties {
+                    linear_tiling: image,
+                    optimal_tiling: image,
+                    buffer_features: format::BufferFeature::all(),
+                }
+            }
+            _ => format::Properties::default(),
+        }
+    }
+
+    fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        _: image::Tiling,
+        _: image::Usage,
+        _: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        if !conv::is_supported(format) {
+            return None;
+        }
+        let limits = limits();
+        let max_extent = match dimensions {
+            1 => image::Extent {
+                width: limits.max_image_1d_size,
+                height: 1,
+                depth: 1,
+            },
+            2 => image::Extent {
+                width: limits.max_image_2d_size,
+                height: limits.max_image_2d_size,
+                depth: 1,
+            },
+            3 => image::Extent {
+                width: limits.max_image_3d_size,
+                height: limits.max_image_3d_size,
+                depth: limits.max_image_3d_size,
+            },
+            _ => return None,
+        };
+
+        Some(image::FormatProperties {
+            max_extent,
+            max_levels: (32 - max_extent.width.max(max_extent.height).leading_zeros())
+                as image::Level,
+            max_layers: limits.max_image_array_layers,
+            sample_count_mask: limits.framebuffer_color_sample_counts,
+            max_resource_size: !0,
+        })
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        memory_properties()
+    }
+
+    fn features(&self) -> hal::Features {
+        features()
+    }
+
+    fn hints(&self) -> hal::Hints {
+        hal::Hints::BASE_VERTEX_INSTANCE_DRAWING
+    }
+
+    fn limits(&self) -> hal::Limits {
+        limits()
+    }
+}
+
+/// Software command queue.
+///
+/// Submitted command buffers are executed on the calling thread, so the fence
+/// is signalled by the time `submit` returns.
+#[derive(Debug)]
+pub struct CommandQueue;
+impl queue::CommandQueue<Backend> for CommandQueue {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&native::Fence>,
+    ) where
+        T: 'a + Borrow<CommandBuffer>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+    {
+        for command_buffer in submission.command_buffers {
+            execute::Executor::new().execute(&command_buffer.borrow().commands);
+        }
+        for (semaphore, value) in submission.signal_semaphores {
+            semaphore.borrow().signal(value);
+        }
+        if let Some(fence) = fence {
+            fence.set(true);
+        }
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        _: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        _: Option<&native::Fence>,
+    ) where
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b native::Buffer,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseImageBind<&'b native::Memory>],
+            ),
+        >,
+    {
+        unimplemented!()
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        _: Is,
+        _: Iw,
+    ) -> Result<Option<Suboptimal>, PresentError>
+    where
+        W: 'a + Borrow<Swapchain>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        Ok(None)
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        _surface: &mut Surface,
+        _image: native::ImageView,
+        _wait_semaphore: Option<&native::Semaphore>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        Ok(None)
+    }
+
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        Ok(())
+    }
+}
+
+/// Software queue family, supporting all kinds of operations.
+#[derive(Debug)]
+pub struct QueueFamily {
+    id: queue::QueueFamilyId,
+}
+impl queue::QueueFamily for QueueFamily {
+    fn queue_type(&self) -> queue::QueueType {
+        queue::QueueType::General
+    }
+    fn max_queues(&self) -> usize {
+        1
+    }
+    fn id(&self) -> queue::QueueFamilyId {
+        self.id
+    }
+    fn timestamp_valid_bits(&self) -> u32 {
+        64
+    }
+}
+
+/// Software command pool.
+#[derive(Debug)]
+pub struct CommandPool;
+impl pool::CommandPool<Backend> for CommandPool {
+    unsafe fn reset(&mut self, _: bool) {}
+
+    unsafe fn allocate_one(&mut self, _: hal::command::Level) -> CommandBuffer {
+        CommandBuffer::new()
+    }
+
+    unsafe fn free<I>(&mut self, _: I)
+    where
+        I: IntoIterator<Item = CommandBuffer>,
+    {
+    }
+}
+
+/// Software descriptor pool, keeping track of the number of allocated sets.
+#[derive(Debug)]
+pub struct DescriptorPool {
+    max_sets: usize,
+    allocated: usize,
+}
+impl pso::DescriptorPool<Backend> for DescriptorPool {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &native::DescriptorSetLayout,
+    ) -> Result<native::DescriptorSet, pso::AllocationError> {
+        if self.allocated == self.max_sets {
+            return Err(pso::AllocationError::OutOfPoolMemory);
+        }
+        self.allocated += 1;
+        Ok(native::DescriptorSet {
+            bindings: std::sync::Arc::new(parking_lot::RwLock::new(layout.bindings.clone())),
+        })
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
+    where
+        I: IntoIterator<Item = native::DescriptorSet>,
+    {
+        self.allocated -= descriptor_sets.into_iter().count();
+    }
+
+    unsafe fn reset(&mut self) {
+        self.allocated = 0;
+    }
+}
+
+/// Software instance, exposing a single adapter.
+#[derive(Debug)]
+pub struct Instance;
+
+impl Instance {
+    /// Create a surface which is not tied to any window.
+    pub fn create_surface_headless(&self, extent: Extent2D) -> Surface {
+        Surface::new(extent)
+    }
+}
+
+impl hal::Instance<Backend> for Instance {
+    fn create(_name: &str, _version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        Ok(Instance)
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Backend>> {
+        vec![adapter::Adapter {
+            info: adapter::AdapterInfo {
+                name: "Software Rasterizer".to_owned(),
+                vendor: 0,
+                device: 0,
+                device_type: adapter::DeviceType::Cpu,
+            },
+            physical_device: PhysicalDevice,
+            queue_families: vec![QueueFamily {
+                id: queue::QueueFamilyId(0),
+            }],
+        }]
+    }
+
+    unsafe fn create_surface(
+        &self,
+        _: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface, hal::window::InitError> {
+        Ok(Surface::new(window::DEFAULT_EXTENT))
+    }
+
+    unsafe fn destroy_surface(&self, _surface: Surface) {}
+}
+
+fn memory_type_mask(properties: &adapter::MemoryProperties) -> u64 {
+    (1 << properties.memory_types.len()) - 1
+}
+
+/// Rounds `value` up to `alignment`, which must be a power of two.
+fn aligned(value: u64, alignment: u64) -> u64 {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+fn bound_range(
+    memory: &native::Memory,
+    offset: u64,
+    requirements: &memory::Requirements,
+) -> Result<Range<u64>, BindError> {
+    if requirements.type_mask & (1 << memory.ty.0) == 0 {
+        return Err(BindError::WrongMemory);
+    }
+    if offset + requirements.size > memory.size() {
+        return Err(BindError::OutOfBounds);
+    }
+    Ok(offset .. offset + requirements.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        command::{self, CommandBuffer as _},
+        device::Device as _,
+        pass,
+        pso::DescriptorPool as _,
+        query,
+        queue::{CommandQueue as _, QueueFamily as _},
+        Instance as _,
+    };
+
+    const RED: [u8; 4] = [0xFF, 0, 0, 0xFF];
+    const GREEN: [u8; 4] = [0, 0xFF, 0, 0xFF];
+    const BLUE: [u8; 4] = [0, 0, 0xFF, 0xFF];
+    const WHITE: [u8; 4] = [0xFF; 4];
+
+    /// Positions and texture coordinates of the quad example vertices, scaled by
+    /// half in the vertex shader. The first quad covers the 2x2 texels at the center
+    /// of the target and maps the texture on them, while the second one covers the
+    /// whole target with the last texel of the texture.
+    #[rustfmt::skip]
+    const VERTICES: [[f32; 4]; 12] = [
+        [-1.0, -1.0, 0.0, 0.0], [1.0, -1.0, 1.0, 0.0], [1.0, 1.0, 1.0, 1.0],
+        [-1.0, -1.0, 0.0, 0.0], [1.0, 1.0, 1.0, 1.0], [-1.0, 1.0, 0.0, 1.0],
+        [-2.0, -2.0, 0.75, 0.75], [2.0, -2.0, 0.75, 0.75], [2.0, 2.0, 0.75, 0.75],
+        [-2.0, -2.0, 0.75, 0.75], [2.0, 2.0, 0.75, 0.75], [-2.0, 2.0, 0.75, 0.75],
+    ];
+
+    fn load_spirv(name: &str) -> Vec<u32> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../../examples/quad/data")
+            .join(name);
+        pso::read_spirv(std::fs::File::open(path).unwrap()).unwrap()
+    }
+
+    unsafe fn create_image(
+        device: &Device,
+        size: u32,
+        format: format::Format,
+        usage: image::Usage,
+    ) -> (native::Memory, native::Image, native::ImageView) {
+        let mut image = device
+            .create_image(
+                image::Kind::D2(size, size, 1, 1),
+                1,
+                format,
+                image::Tiling::Optimal,
+                usage,
+                image::ViewCapabilities::empty(),
+                memory::SparseFlags::empty(),
+            )
+            .unwrap();
+        let requirements = device.get_image_requirements(&image);
+        let memory = device
+            .allocate_memory(hal::MemoryTypeId(0), requirements.size)
+            .unwrap();
+        device.bind_image_memory(&memory, 0, &mut image).unwrap();
+        let view = device
+            .create_image_view(
+                &image,
+                image::ViewKind::D2,
+                format,
+                format::Swizzle::NO,
+                image::SubresourceRange {
+                    aspects: format.surface_desc().aspects,
+                    levels: 0 .. 1,
+                    layers: 0 .. 1,
+                },
+            )
+            .unwrap();
+        (memory, image, view)
+    }
+
+    /// Draws ranges of `VERTICES` with the shaders of the quad example into a 4x4
+    /// target, sampling a 2x2 texture of red, green, blue and white texels with
+    /// nearest filtering, and returns the texels of the target.
+    ///
+    /// Each draw is given the depth of its fragments, which are tested against a depth
+    /// attachment cleared to 1.0.
+    ///
+    /// With a `condition`, its vertices are first drawn in another pass within an
+    /// occlusion query, and the draws are conditionally rendered on the query result.
+    fn render_quad(
+        clear_color: [f32; 4],
+        blend: Option<pso::BlendState>,
+        depth: Option<pso::DepthTest>,
+        draws: &[(Range<u32>, f32)],
+        condition: Option<(Range<u32>, bool)>,
+    ) -> Vec<[u8; 4]> {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+        let color_format = format::Format::Rgba8Unorm;
+        let depth_format = format::Format::D32Sfloat;
+
+        unsafe {
+            // The texture, the vertices and the rendered texels share a buffer.
+            let usage =
+                buffer::Usage::TRANSFER_SRC | buffer::Usage::TRANSFER_DST | buffer::Usage::VERTEX;
+            let mut staging = device
+                .create_buffer(512, usage, memory::SparseFlags::empty())
+                .unwrap();
+            let staging_memory = device.allocate_memory(hal::MemoryTypeId(0), 512).unwrap();
+            device
+                .bind_buffer_memory(&staging_memory, 0, &mut staging)
+                .unwrap();
+            let ptr = device
+                .map_memory(&staging_memory, memory::Segment::ALL)
+                .unwrap();
+            let data = std::slice::from_raw_parts_mut(ptr, 512);
+            for (i, texel) in [RED, GREEN, BLUE, WHITE].iter().enumerate() {
+                data[i * 4 .. i * 4 + 4].copy_from_slice(texel);
+            }
+            for (i, value) in VERTICES.iter().flatten().enumerate() {
+                data[16 + i * 4 .. 20 + i * 4].copy_from_slice(&value.to_le_bytes());
+            }
+
+            let (_texture_memory, texture, texture_view) = create_image(
+                device,
+                2,
+                color_format,
+                image::Usage::TRANSFER_DST | image::Usage::SAMPLED,
+            );
+            let (_target_memory, target, target_view) = create_image(
+                device,
+                4,
+                color_format,
+                image::Usage::COLOR_ATTACHMENT | image::Usage::TRANSFER_SRC,
+            );
+            let (_depth_memory, _depth, depth_view) = create_image(
+                device,
+                4,
+                depth_format,
+                image::Usage::DEPTH_STENCIL_ATTACHMENT,
+            );
+            let sampler = device
+                .create_sampler(&image::SamplerDesc::new(
+                    image::Filter::Nearest,
+                    image::WrapMode::Clamp,
+                ))
+                .unwrap();
+
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    &[
+                        pso::DescriptorSetLayoutBinding {
+                            binding: 0,
+                            ty: pso::DescriptorType::Image {
+                                ty: pso::ImageDescriptorType::Sampled {
+                                    with_sampler: false,
+                                },
+                            },
+                            count: 1,
+                            stage_flags: pso::ShaderStageFlags::FRAGMENT,
+                            immutable_samplers: false,
+                            flags: pso::DescriptorBindingFlags::empty(),
+                        },
+                        pso::DescriptorSetLayoutBinding {
+                            binding: 1,
+                            ty: pso::DescriptorType::Sampler,
+                            count: 1,
+                            stage_flags: pso::ShaderStageFlags::FRAGMENT,
+                            immutable_samplers: false,
+                            flags: pso::DescriptorBindingFlags::empty(),
+                        },
+                    ],
+                    &[],
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
+                )
+                .unwrap();
+            let mut pool = device
+                .create_descriptor_pool(
+                    1,
+                    vec![
+                        pso::DescriptorRangeDesc {
+                            ty: pso::DescriptorType::Image {
+                                ty: pso::ImageDescriptorType::Sampled {
+                                    with_sampler: false,
+                                },
+                            },
+                            count: 1,
+                        },
+                        pso::DescriptorRangeDesc {
+                            ty: pso::DescriptorType::Sampler,
+                            count: 1,
+                        },
+                    ],
+                    pso::DescriptorPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let set = pool.allocate_set(&set_layout).unwrap();
+            device.write_descriptor_sets(vec![
+                pso::DescriptorSetWrite {
+                    set: &set,
+                    binding: 0,
+                    array_offset: 0,
+                    descriptors: Some(pso::Descriptor::Image(
+                        &texture_view,
+                        image::Layout::ShaderReadOnlyOptimal,
+                    )),
+                },
+                pso::DescriptorSetWrite {
+                    set: &set,
+                    binding: 1,
+                    array_offset: 0,
+                    descriptors: Some(pso::Descriptor::Sampler(&sampler)),
+                },
+            ]);
+
+            let render_pass = device
+                .create_render_pass(
+                    &[
+                        pass::Attachment {
+                            format: Some(color_format),
+                            samples: 1,
+                            ops: pass::AttachmentOps::new(
+                                pass::AttachmentLoadOp::Clear,
+                                pass::AttachmentStoreOp::Store,
+                            ),
+                            stencil_ops: pass::AttachmentOps::DONT_CARE,
+                            layouts: image::Layout::Undefined .. image::Layout::TransferSrcOptimal,
+                        },
+                        pass::Attachment {
+                            format: Some(depth_format),
+                            samples: 1,
+                            ops: pass::AttachmentOps::new(
+                                pass::AttachmentLoadOp::Clear,
+                                pass::AttachmentStoreOp::DontCare,
+                            ),
+                            stencil_ops: pass::AttachmentOps::DONT_CARE,
+                            layouts: image::Layout::Undefined
+                                .. image::Layout::DepthStencilAttachmentOptimal,
+                        },
+                    ],
+                    &[pass::SubpassDesc {
+                        colors: &[(0, image::Layout::ColorAttachmentOptimal)],
+                        depth_stencil: Some(&(1, image::Layout::DepthStencilAttachmentOptimal)),
+                        inputs: &[],
+                        resolves: &[],
+                        preserves: &[],
+                        view_mask: 0,
+                    }],
+                    &[],
+                    &[],
+                )
+                .unwrap();
+            let extent = image::Extent {
+                width: 4,
+                height: 4,
+                depth: 1,
+            };
+            let framebuffer = device
+                .create_framebuffer(&render_pass, vec![&target_view, &depth_view], extent)
+                .unwrap();
+
+            let layout = device
+                .create_pipeline_layout(Some(&set_layout), &[])
+                .unwrap();
+            let vs_module = device
+                .create_shader_module(&load_spirv("quad.vert.spv"))
+                .unwrap();
+            let fs_module = device
+                .create_shader_module(&load_spirv("quad.frag.spv"))
+                .unwrap();
+            let mut desc = pso::GraphicsPipelineDesc::new(
+                pso::GraphicsShaderSet {
+                    vertex: pso::EntryPoint {
+                        entry: "main",
+                        module: &vs_module,
+                        specialization: hal::spec_const_list![0.5f32],
+                    },
+                    hull: None,
+                    domain: None,
+                    geometry: None,
+                    fragment: Some(pso::EntryPoint {
+                        entry: "main",
+                        module: &fs_module,
+                        specialization: pso::Specialization::default(),
+                    }),
+                },
+                pso::Primitive::TriangleList,
+                pso::Rasterizer::FILL,
+                &layout,
+                pass::Subpass {
+                    index: 0,
+                    main_pass: &render_pass,
+                },
+            );
+            desc.blender.targets.push(pso::ColorBlendDesc {
+                mask: pso::ColorMask::ALL,
+                blend,
+            });
+            desc.depth_stencil.depth = depth;
+            desc.vertex_buffers.push(pso::VertexBufferDesc {
+                binding: 0,
+                stride: 16,
+                rate: pso::VertexInputRate::Vertex,
+            });
+            for location in 0 .. 2 {
+                desc.attributes.push(pso::AttributeDesc {
+                    location,
+                    binding: 0,
+                    element: pso::Element {
+                        format: format::Format::Rg32Sfloat,
+                        offset: location * 8,
+                    },
+                });
+            }
+            let pipeline = device.create_graphics_pipeline(&desc, None).unwrap();
+
+            let rect = pso::Rect {
+                x: 0,
+                y: 0,
+                w: 4,
+                h: 4,
+            };
+            let layers = image::SubresourceLayers {
+                aspects: format::Aspects::COLOR,
+                level: 0,
+                layers: 0 .. 1,
+            };
+            let mut cmd = CommandBuffer::new();
+            cmd.pipeline_barrier(
+                pso::PipelineStage::TOP_OF_PIPE .. pso::PipelineStage::TRANSFER,
+                memory::Dependencies::empty(),
+                Some(memory::Barrier::Image {
+                    states: (image::Access::empty(), image::Layout::Undefined)
+                        .. (
+                            image::Access::TRANSFER_WRITE,
+                            image::Layout::TransferDstOptimal,
+                        ),
+                    target: &texture,
+                    families: None,
+                    range: image::SubresourceRange {
+                        aspects: format::Aspects::COLOR,
+                        levels: 0 .. 1,
+                        layers: 0 .. 1,
+                    },
+                }),
+            );
+            cmd.copy_buffer_to_image(
+                &staging,
+                &texture,
+                image::Layout::TransferDstOptimal,
+                Some(command::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: 2,
+                    buffer_height: 2,
+                    image_layers: layers.clone(),
+                    image_offset: image::Offset::ZERO,
+                    image_extent: image::Extent {
+                        width: 2,
+                        height: 2,
+                        depth: 1,
+                    },
+                }),
+            );
+            cmd.pipeline_barrier(
+                pso::PipelineStage::TRANSFER .. pso::PipelineStage::FRAGMENT_SHADER,
+                memory::Dependencies::empty(),
+                Some(memory::Barrier::Image {
+                    states: (
+                        image::Access::TRANSFER_WRITE,
+                        image::Layout::TransferDstOptimal,
+                    )
+                        .. (
+                            image::Access::SHADER_READ,
+                            image::Layout::ShaderReadOnlyOptimal,
+                        ),
+                    target: &texture,
+                    families: None,
+                    range: image::SubresourceRange {
+                        aspects: format::Aspects::COLOR,
+                        levels: 0 .. 1,
+                        layers: 0 .. 1,
+                    },
+                }),
+            );
+
+            let begin_pass = |cmd: &mut CommandBuffer| {
+                cmd.begin_render_pass(
+                    &render_pass,
+                    &framebuffer,
+                    rect,
+                    vec![
+                        command::ClearValue {
+                            color: command::ClearColor {
+                                float32: clear_color,
+                            },
+                        },
+                        command::ClearValue {
+                            depth_stencil: command::ClearDepthStencil {
+                                depth: 1.0,
+                                stencil: 0,
+                            },
+                        },
+                    ],
+                    command::SubpassContents::Inline,
+                );
+                cmd.bind_graphics_pipeline(&pipeline);
+                cmd.bind_graphics_descriptor_sets(&layout, 0, Some(&set), &[]);
+                cmd.bind_vertex_buffers(
+                    0,
+                    Some((
+                        &staging,
+                        buffer::SubRange {
+                            offset: 16,
+                            size: None,
+                        },
+                    )),
+                );
+                cmd.set_scissors(0, Some(rect));
+            };
+            let query_pool = device.create_query_pool(query::Type::Occlusion, 1).unwrap();
+            if let Some((ref vertices, inverted)) = condition {
+                begin_pass(&mut cmd);
+                cmd.set_viewports(
+                    0,
+                    Some(pso::Viewport {
+                        rect,
+                        depth: 0.0 .. 1.0,
+                    }),
+                );
+                cmd.begin_query(
+                    query::Query {
+                        pool: &query_pool,
+                        id: 0,
+                    },
+                    query::ControlFlags::PRECISE,
+                );
+                cmd.draw(vertices.clone(), 0 .. 1);
+                cmd.end_query(query::Query {
+                    pool: &query_pool,
+                    id: 0,
+                });
+                cmd.end_render_pass();
+                cmd.begin_conditional_rendering_query(&query_pool, 0, inverted);
+            }
+            begin_pass(&mut cmd);
+            for &(ref vertices, depth) in draws {
+                cmd.set_viewports(
+                    0,
+                    Some(pso::Viewport {
+                        rect,
+                        depth: depth .. depth,
+                    }),
+                );
+                cmd.draw(vertices.clone(), 0 .. 1);
+            }
+            cmd.end_render_pass();
+            if condition.is_some() {
+                cmd.end_conditional_rendering();
+            }
+            cmd.copy_image_to_buffer(
+                &target,
+                image::Layout::TransferSrcOptimal,
+                &staging,
+                Some(command::BufferImageCopy {
+                    buffer_offset: 256,
+                    buffer_width: 4,
+                    buffer_height: 4,
+                    image_layers: layers,
+                    image_offset: image::Offset::ZERO,
+                    image_extent: extent,
+                }),
+            );
+
+            let fence = device.create_fence(false).unwrap();
+            gpu.queue_groups[0].queues[0].submit_without_semaphores(Some(&cmd), Some(&fence));
+            assert!(device.get_fence_status(&fence).unwrap());
+
+            let data = std::slice::from_raw_parts(ptr.add(256), 64);
+            let texels = data
+                .chunks(4)
+                .map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+                .collect();
+            device.unmap_memory(&staging_memory);
+            texels
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device>();
+        foo::<native::Memory>();
+        foo::<native::Image>();
+        foo::<CommandBuffer>();
+    }
+
+    #[test]
+    fn fill_and_copy_buffer() {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let usage = buffer::Usage::TRANSFER_SRC | buffer::Usage::TRANSFER_DST;
+            let sparse = memory::SparseFlags::empty();
+            let mut src = device.create_buffer(16, usage, sparse).unwrap();
+            let mut dst = device.create_buffer(16, usage, sparse).unwrap();
+            let memory = device.allocate_memory(hal::MemoryTypeId(0), 32).unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut src).unwrap();
+            device.bind_buffer_memory(&memory, 16, &mut dst).unwrap();
+
+            let mut cmd = CommandBuffer::new();
+            cmd.fill_buffer(&src, buffer::SubRange::WHOLE, 0x0403_0201);
+            cmd.copy_buffer(
+                &src,
+                &dst,
+                Some(hal::command::BufferCopy {
+                    src: 4,
+                    dst: 0,
+                    size: 8,
+                }),
+            );
+
+            let fence = device.create_fence(false).unwrap();
+            gpu.queue_groups[0].queues[0].submit_without_semaphores(Some(&cmd), Some(&fence));
+            assert!(device.get_fence_status(&fence).unwrap());
+
+            let ptr = device.map_memory(&memory, memory::Segment::ALL).unwrap();
+            let data = std::slice::from_raw_parts(ptr, 32);
+            assert_eq!(&data[16 .. 24], &[1, 2, 3, 4, 1, 2, 3, 4]);
+            assert_eq!(&data[24 ..], &[0; 8]);
+            device.unmap_memory(&memory);
+        }
+    }
+
+    #[test]
+    fn profiler_scopes() {
+        let instance = Instance::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let mut profiler = hal::query::Profiler::<Backend>::new(
+                device,
+                2,
+                adapter.physical_device.limits().timestamp_period,
+                adapter.queue_families[0].timestamp_valid_bits(),
+            )
+            .unwrap();
+            let mut cmd = CommandBuffer::new();
+            profiler.reset(&mut cmd);
+            profiler.begin_scope(&mut cmd, "frame");
+            profiler.begin_scope(&mut cmd, "fill");
+            profiler.end_scope(&mut cmd);
+            profiler.end_scope(&mut cmd);
+            gpu.queue_groups[0].queues[0].submit_without_semaphores(Some(&cmd), None);
+
+            let timings = profiler.resolve(device).unwrap();
+            assert_eq!(timings.len(), 2);
+            assert_eq!((timings[0].name.as_str(), timings[0].depth), ("frame", 0));
+            assert_eq!((timings[1].name.as_str(), timings[1].depth), ("fill", 1));
+            assert!(timings[1].duration_ns <= timings[0].duration_ns);
+            profiler.destroy(device);
+        }
+    }
+
+    #[test]
+    fn draw_quad() {
+        let clear = [0, 0, 0, 0];
+        let texels = render_quad([0.0; 4], None, None, &[(0 .. 6, 0.0)], None);
+        #[rustfmt::skip]
+        assert_eq!(texels, vec![
+            clear, clear, clear, clear,
+            clear, RED, GREEN, clear,
+            clear, BLUE, WHITE, clear,
+            clear, clear, clear, clear,
+        ]);
+    }
+
+    #[test]
+    fn depth_test() {
+        // The full screen quad is drawn behind the center one.
+        let depth = pso::DepthTest {
+            fun: pso::Comparison::Less,
+            write: true,
+        };
+        let texels = render_quad(
+            [0.0; 4],
+            None,
+            Some(depth),
+            &[(0 .. 6, 0.25), (6 .. 12, 0.5)],
+            None,
+        );
+        #[rustfmt::skip]
+        assert_eq!(texels, vec![
+            WHITE, WHITE, WHITE, WHITE,
+            WHITE, RED, GREEN, WHITE,
+            WHITE, BLUE, WHITE, WHITE,
+            WHITE, WHITE, WHITE, WHITE,
+        ]);
+
+        // Without writes, the depth of the full screen quad passes everywhere.
+        let depth = pso::DepthTest {
+            fun: pso::Comparison::Less,
+            write: false,
+        };
+        let texels = render_quad(
+            [0.0; 4],
+            None,
+            Some(depth),
+            &[(0 .. 6, 0.25), (6 .. 12, 0.5)],
+            None,
+        );
+        assert_eq!(texels, vec![WHITE; 16]);
+    }
+
+    #[test]
+    fn blend() {
+        let clear = [0, 0, 0x80, 0];
+        let texels = render_quad(
+            [0.0, 0.0, 0.5, 0.0],
+            Some(pso::BlendState::ADD),
+            None,
+            &[(0 .. 6, 0.0)],
+            None,
+        );
+        #[rustfmt::skip]
+        assert_eq!(texels, vec![
+            clear, clear, clear, clear,
+            clear, [0xFF, 0, 0x80, 0xFF], [0, 0xFF, 0x80, 0xFF], clear,
+            clear, BLUE, WHITE, clear,
+            clear, clear, clear, clear,
+        ]);
+    }
+
+    #[test]
+    fn conditional_rendering_query() {
+        let clear = [0, 0, 0, 0];
+        // The query draw covers the center of the target, so some samples pass.
+        let texels = render_quad(
+            [0.0; 4],
+            None,
+            None,
+            &[(6 .. 12, 0.0)],
+            Some((0 .. 6, false)),
+        );
+        assert_eq!(texels, vec![WHITE; 16]);
+        let texels = render_quad(
+            [0.0; 4],
+            None,
+            None,
+            &[(6 .. 12, 0.0)],
+            Some((0 .. 6, true)),
+        );
+        assert_eq!(texels, vec![clear; 16]);
+        // Nothing is drawn within the query, so no sample passes.
+        let texels = render_quad(
+            [0.0; 4],
+            None,
+            None,
+            &[(6 .. 12, 0.0)],
+            Some((0 .. 0, false)),
+        );
+        assert_eq!(texels, vec![clear; 16]);
+        let texels = render_quad(
+            [0.0; 4],
+            None,
+            None,
+            &[(6 .. 12, 0.0)],
+            Some((0 .. 0, true)),
+        );
+        assert_eq!(texels, vec![WHITE; 16]);
+    }
+}
@@ -0,0 +1,493 @@
+//! Resource handles of the software backend.
+//!
+//! Resources that are referenced by recorded commands are reference counted,
+//! so that command buffers can outlive the handles they were recorded with.
+
+use crate::{conv, shader};
+use hal::{buffer, format, image, memory, pass, pso, query, MemoryTypeId};
+use parking_lot::{Mutex, RwLock};
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::{fmt, ptr, slice};
+
+/// Host storage of a memory allocation.
+///
+/// The contents are accessed through raw pointers, since they can be written
+/// by the host and by executed commands at the same time.
+pub(crate) struct Block {
+    ptr: *mut u8,
+    len: usize,
+}
+
+unsafe impl Send for Block {}
+unsafe impl Sync for Block {}
+
+impl fmt::Debug for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Block").field(&self.len).finish()
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                self.ptr, self.len,
+            )));
+        }
+    }
+}
+
+impl Block {
+    fn new(size: usize) -> Self {
+        let data = Box::into_raw(vec![0u8; size].into_boxed_slice());
+        Block {
+            ptr: data as *mut u8,
+            len: size,
+        }
+    }
+
+    pub(crate) fn ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Device memory, backed by host memory.
+#[derive(Debug)]
+pub struct Memory {
+    pub(crate) block: Arc<Block>,
+    pub(crate) ty: MemoryTypeId,
+}
+
+impl Memory {
+    pub(crate) fn new(ty: MemoryTypeId, size: u64) -> Self {
+        Memory {
+            block: Arc::new(Block::new(size as usize)),
+            ty,
+        }
+    }
+
+    /// Returns the size of the allocation in bytes.
+    pub fn size(&self) -> u64 {
+        self.block.len() as u64
+    }
+}
+
+/// Range of memory a buffer is bound to.
+#[derive(Clone, Debug)]
+pub(crate) struct RawBuffer {
+    pub(crate) block: Arc<Block>,
+    pub(crate) offset: usize,
+    pub(crate) size: usize,
+}
+
+impl RawBuffer {
+    /// Returns a sub-range of the buffer, clamped to its size.
+    pub(crate) fn range(&self, range: &buffer::SubRange) -> RawBuffer {
+        let offset = (range.offset as usize).min(self.size);
+        let size = range.size.map_or(self.size - offset, |size| {
+            (size as usize).min(self.size - offset)
+        });
+        RawBuffer {
+            block: Arc::clone(&self.block),
+            offset: self.offset + offset,
+            size,
+        }
+    }
+
+    pub(crate) fn ptr(&self) -> *mut u8 {
+        unsafe { self.block.ptr().add(self.offset) }
+    }
+
+    /// Returns the contents of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The memory must not be written while the slice is alive.
+    pub(crate) unsafe fn bytes(&self) -> &[u8] {
+        slice::from_raw_parts(self.ptr(), self.size)
+    }
+
+    /// Returns the mutable contents of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The memory must not be accessed otherwise while the slice is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn bytes_mut(&self) -> &mut [u8] {
+        slice::from_raw_parts_mut(self.ptr(), self.size)
+    }
+}
+
+#[derive(Debug)]
+pub struct Buffer {
+    pub(crate) size: u64,
+    pub(crate) requirements: memory::Requirements,
+    pub(crate) raw: Option<RawBuffer>,
+}
+
+impl Buffer {
+    pub(crate) fn raw(&self) -> RawBuffer {
+        self.raw.clone().expect("Buffer is not bound to memory")
+    }
+}
+
+#[derive(Debug)]
+pub struct BufferView {
+    pub(crate) raw: RawBuffer,
+    pub(crate) format: format::Format,
+}
+
+/// Properties that define the memory layout of an image.
+///
+/// Images are stored linearly, one array layer after the other,
+/// with all the mip levels of a layer stored together.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ImageInfo {
+    pub(crate) kind: image::Kind,
+    pub(crate) levels: image::Level,
+    pub(crate) format: format::Format,
+}
+
+impl ImageInfo {
+    pub(crate) fn texel_size(&self) -> usize {
+        conv::texel_size(self.format)
+    }
+
+    pub(crate) fn level_extent(&self, level: image::Level) -> image::Extent {
+        self.kind.level_extent(level)
+    }
+
+    /// Returns the row pitch and the depth pitch of a mip level.
+    pub(crate) fn pitches(&self, level: image::Level) -> (usize, usize) {
+        let extent = self.level_extent(level);
+        let row_pitch = extent.width as usize * self.texel_size();
+        (row_pitch, row_pitch * extent.height as usize)
+    }
+
+    fn level_size(&self, level: image::Level) -> usize {
+        self.pitches(level).1 * self.level_extent(level).depth as usize
+    }
+
+    pub(crate) fn layer_size(&self) -> usize {
+        (0 .. self.levels).map(|level| self.level_size(level)).sum()
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.layer_size() * self.kind.num_layers() as usize
+    }
+
+    /// Returns the byte offset of the first texel of a subresource.
+    pub(crate) fn subresource_offset(&self, level: image::Level, layer: image::Layer) -> usize {
+        let level_offset = (0 .. level)
+            .map(|level| self.level_size(level))
+            .sum::<usize>();
+        layer as usize * self.layer_size() + level_offset
+    }
+
+    /// Returns the byte range of a subresource.
+    pub(crate) fn subresource_range(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+    ) -> Range<usize> {
+        let offset = self.subresource_offset(level, layer);
+        offset .. offset + self.level_size(level)
+    }
+}
+
+/// Memory an image is bound to.
+#[derive(Clone, Debug)]
+pub(crate) struct RawImage {
+    pub(crate) block: Arc<Block>,
+    pub(crate) offset: usize,
+    pub(crate) info: ImageInfo,
+}
+
+impl RawImage {
+    /// Returns a pointer to a texel, if it lies inside of the image.
+    pub(crate) fn texel_ptr(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        [x, y, z]: [i32; 3],
+    ) -> Option<*mut u8> {
+        let extent = self.info.level_extent(level);
+        if level >= self.info.levels
+            || layer >= self.info.kind.num_layers()
+            || x < 0
+            || y < 0
+            || z < 0
+            || x as u32 >= extent.width
+            || y as u32 >= extent.height
+            || z as u32 >= extent.depth
+        {
+            return None;
+        }
+        let (row_pitch, depth_pitch) = self.info.pitches(level);
+        let offset = self.offset
+            + self.info.subresource_offset(level, layer)
+            + z as usize * depth_pitch
+            + y as usize * row_pitch
+            + x as usize * self.info.texel_size();
+        Some(unsafe { self.block.ptr().add(offset) })
+    }
+
+    /// Returns the bytes of a texel, if it lies inside of the image.
+    ///
+    /// # Safety
+    ///
+    /// The texel must not be accessed otherwise while the slice is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn texel(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coords: [i32; 3],
+    ) -> Option<&mut [u8]> {
+        let size = self.info.texel_size();
+        self.texel_ptr(level, layer, coords)
+            .map(|ptr| slice::from_raw_parts_mut(ptr, size))
+    }
+}
+
+#[derive(Debug)]
+pub struct Image {
+    pub(crate) info: ImageInfo,
+    pub(crate) requirements: memory::Requirements,
+    pub(crate) raw: Option<RawImage>,
+}
+
+impl Image {
+    pub(crate) fn raw(&self) -> RawImage {
+        self.raw.clone().expect("Image is not bound to memory")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ImageView {
+    pub(crate) image: RawImage,
+    pub(crate) kind: image::ViewKind,
+    pub(crate) format: format::Format,
+    pub(crate) swizzle: format::Swizzle,
+    pub(crate) range: image::SubresourceRange,
+}
+
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    pub(crate) desc: image::SamplerDesc,
+}
+
+#[derive(Debug)]
+pub struct ShaderModule {
+    pub(crate) module: Arc<shader::Module>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Subpass {
+    pub(crate) colors: Vec<usize>,
+    pub(crate) depth_stencil: Option<usize>,
+}
+
+#[derive(Debug)]
+pub(crate) struct RenderPassInfo {
+    pub(crate) attachments: Vec<pass::Attachment>,
+    pub(crate) subpasses: Vec<Subpass>,
+}
+
+#[derive(Debug)]
+pub struct RenderPass {
+    pub(crate) info: Arc<RenderPassInfo>,
+}
+
+#[derive(Debug)]
+pub struct Framebuffer {
+    pub(crate) attachments: Arc<Vec<ImageView>>,
+}
+
+#[derive(Debug)]
+pub struct PipelineCache;
+
+#[derive(Debug)]
+pub struct PipelineLayout {
+    /// Initial contents of each set, used to build the pushed descriptor sets.
+    pub(crate) sets: Vec<Vec<DescriptorBinding>>,
+}
+
+/// Fixed-function state of a graphics pipeline.
+#[derive(Debug)]
+pub(crate) struct GraphicsState {
+    pub(crate) vertex: shader::Program,
+    pub(crate) fragment: Option<shader::Program>,
+    pub(crate) rasterizer: pso::Rasterizer,
+    pub(crate) vertex_buffers: Vec<pso::VertexBufferDesc>,
+    pub(crate) attributes: Vec<pso::AttributeDesc>,
+    pub(crate) input_assembler: pso::InputAssemblerDesc,
+    pub(crate) blender: pso::BlendDesc,
+    pub(crate) depth_stencil: pso::DepthStencilDesc,
+    pub(crate) multisampling: Option<pso::Multisampling>,
+    pub(crate) baked_states: pso::BakedStates,
+}
+
+#[derive(Debug)]
+pub struct GraphicsPipeline {
+    pub(crate) state: Arc<GraphicsState>,
+}
+
+#[derive(Debug)]
+pub struct ComputePipeline {
+    pub(crate) program: Arc<shader::Program>,
+}
+
+#[derive(Debug)]
+pub struct DescriptorSetLayout {
+    /// Initial contents of the sets allocated with the layout, sorted by binding number.
+    pub(crate) bindings: Vec<DescriptorBinding>,
+}
+
+/// Descriptor stored in a set.
+#[derive(Clone, Debug)]
+pub(crate) enum Descriptor {
+    Empty,
+    Sampler(image::SamplerDesc),
+    Image(ImageView),
+    CombinedImageSampler(ImageView, image::SamplerDesc),
+    Buffer(RawBuffer),
+    TexelBuffer(RawBuffer, format::Format),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DescriptorBinding {
+    pub(crate) binding: pso::DescriptorBinding,
+    pub(crate) ty: pso::DescriptorType,
+    /// True if the samplers are set by the layout, and can't be written.
+    pub(crate) immutable_samplers: bool,
+    pub(crate) descriptors: Vec<Descriptor>,
+}
+
+impl DescriptorBinding {
+    /// Returns true if the binding takes a dynamic offset.
+    pub(crate) fn is_dynamic(&self) -> bool {
+        matches!(
+            self.ty,
+            pso::DescriptorType::Buffer {
+                format: pso::BufferDescriptorFormat::Structured {
+                    dynamic_offset: true,
+                },
+                ..
+            }
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct DescriptorSet {
+    /// Bindings sorted by binding number.
+    pub(crate) bindings: Arc<RwLock<Vec<DescriptorBinding>>>,
+}
+
+#[derive(Debug)]
+pub struct Fence {
+    signaled: AtomicBool,
+}
+
+impl Fence {
+    pub(crate) fn new(signaled: bool) -> Self {
+        Fence {
+            signaled: AtomicBool::new(signaled),
+        }
+    }
+
+    pub(crate) fn set(&self, signaled: bool) {
+        self.signaled.store(signaled, Ordering::Release);
+    }
+
+    pub(crate) fn is_signaled(&self) -> bool {
+        self.signaled.load(Ordering::Acquire)
+    }
+}
+
+/// Semaphore, which has nothing to synchronize since submissions are executed in order.
+///
+/// Timeline semaphores only keep their counter, set when the signalling submission is made.
+#[derive(Debug)]
+pub struct Semaphore {
+    value: AtomicU64,
+}
+
+impl Semaphore {
+    pub(crate) fn new(value: u64) -> Self {
+        Semaphore {
+            value: AtomicU64::new(value),
+        }
+    }
+
+    pub(crate) fn signal(&self, value: u64) {
+        self.value.fetch_max(value, Ordering::AcqRel);
+    }
+
+    pub(crate) fn value(&self) -> u64 {
+        self.value.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug)]
+pub struct Event {
+    pub(crate) set: Arc<AtomicBool>,
+}
+
+#[derive(Debug)]
+pub(crate) struct QueryResults {
+    /// Result of each query, if available.
+    pub(crate) results: Vec<Option<u64>>,
+}
+
+impl QueryResults {
+    /// Writes the results of a range of queries into `data`, laid out with the given stride.
+    ///
+    /// Returns false if some of the results were not available.
+    pub(crate) fn write(
+        &self,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: usize,
+        flags: query::ResultFlags,
+    ) -> bool {
+        let size = if flags.contains(query::ResultFlags::BITS_64) {
+            8
+        } else {
+            4
+        };
+        let mut write = |offset: usize, value: u64| {
+            if let Some(bytes) = data.get_mut(offset .. offset + size) {
+                bytes.copy_from_slice(&value.to_le_bytes()[.. size]);
+            }
+        };
+
+        let mut available = true;
+        for (i, id) in queries.enumerate() {
+            let result = self.results.get(id as usize).cloned().and_then(|r| r);
+            available &= result.is_some();
+            match result {
+                Some(value) => write(i * stride, value),
+                None if flags.contains(query::ResultFlags::PARTIAL) => write(i * stride, 0),
+                None => {}
+            }
+            if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+                write(i * stride + size, result.is_some() as u64);
+            }
+        }
+        available
+    }
+}
+
+#[derive(Debug)]
+pub struct QueryPool {
+    pub(crate) queries: Arc<Mutex<QueryResults>>,
+}
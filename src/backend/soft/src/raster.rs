@@ -0,0 +1,1042 @@
+//! Graphics pipeline: vertex processing, clipping, rasterization and the per-fragment operations.
+//!
+//! Rasterization follows the Vulkan rules for a single sample per pixel: sample positions are
+//! at the pixel centers, coverage uses the top-left rule, and vertex attributes are interpolated
+//! with perspective correction unless they are decorated otherwise.
+
+use crate::conv::{self, Texel};
+use crate::execute::{DynamicState, PassState};
+use crate::native::{GraphicsState, ImageView, RawBuffer};
+use crate::sample::{self, Bindings};
+use crate::shader::{Interpolation, Invocation, Status, Value};
+use auxil::FastHashMap;
+use hal::{format, pso};
+use spirv_headers as spirv;
+
+/// Size of the guard band, relative to the viewport, that primitives are clipped to.
+///
+/// Fragments outside of the viewport are discarded after rasterization, so the guard band
+/// only needs to keep the framebuffer coordinates in a range with enough precision.
+const GUARD_BAND: f32 = 4.0;
+/// Smallest clip space `w` of the vertices that are rasterized.
+const MIN_W: f32 = 1.0e-6;
+/// Number of subpixel steps that framebuffer coordinates are snapped to.
+const SUBPIXEL_STEPS: f64 = 256.0;
+/// Largest supported point size.
+pub(crate) const MAX_POINT_SIZE: f32 = 64.0;
+
+/// Shaded vertex.
+#[derive(Clone, Debug)]
+struct Vertex {
+    /// Position in clip space.
+    position: [f32; 4],
+    point_size: f32,
+    /// Outputs read by the fragment shader, in the order of `Renderer::varyings`.
+    varyings: Vec<Texel>,
+}
+
+impl Vertex {
+    /// Interpolates between two vertices in clip space.
+    fn lerp(&self, other: &Vertex, t: f32) -> Vertex {
+        let mut position = [0.0; 4];
+        for (p, (a, b)) in position
+            .iter_mut()
+            .zip(self.position.iter().zip(other.position.iter()))
+        {
+            *p = a + (b - a) * t;
+        }
+        let varyings = self
+            .varyings
+            .iter()
+            .zip(other.varyings.iter())
+            .map(|(a, b)| {
+                let mut texel = [0; 4];
+                for i in 0 .. 4 {
+                    let (a, b) = (f32::from_bits(a[i]), f32::from_bits(b[i]));
+                    texel[i] = (a + (b - a) * t).to_bits();
+                }
+                texel
+            })
+            .collect();
+        Vertex {
+            position,
+            point_size: self.point_size,
+            varyings,
+        }
+    }
+}
+
+/// Vertex position in framebuffer coordinates.
+#[derive(Clone, Copy, Debug)]
+struct Position {
+    x: f64,
+    y: f64,
+    z: f32,
+    /// Reciprocal of the clip space `w`.
+    inv_w: f32,
+}
+
+/// Pixel rectangle, with exclusive upper bounds.
+#[derive(Clone, Copy, Debug)]
+struct Bounds {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+impl Bounds {
+    fn from_rect(rect: pso::Rect) -> Self {
+        let (x, y, w, h) = (rect.x as i32, rect.y as i32, rect.w as i32, rect.h as i32);
+        Bounds {
+            x0: x.min(x + w),
+            y0: y.min(y + h),
+            x1: x.max(x + w),
+            y1: y.max(y + h),
+        }
+    }
+
+    fn intersect(&self, other: &Bounds) -> Self {
+        Bounds {
+            x0: self.x0.max(other.x0),
+            y0: self.y0.max(other.y0),
+            x1: self.x1.min(other.x1),
+            y1: self.y1.min(other.y1),
+        }
+    }
+
+    /// Returns the pixels whose centers lie inside of the given box.
+    fn covering(&self, x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        let first = |v: f64| (v - 0.5).ceil() as i32;
+        let last = |v: f64| (v - 0.5).floor() as i32 + 1;
+        Bounds {
+            x0: first(x0).max(self.x0),
+            y0: first(y0).max(self.y0),
+            x1: last(x1).min(self.x1),
+            y1: last(y1).min(self.y1),
+        }
+    }
+}
+
+/// Fragment produced by rasterization.
+#[derive(Clone, Copy, Debug)]
+struct Fragment {
+    x: i32,
+    y: i32,
+    depth: f32,
+    /// Interpolated reciprocal of the clip space `w`.
+    inv_w: f32,
+    front_facing: bool,
+    point_coord: [f32; 2],
+}
+
+/// Evaluates the edge function of the edge going from `a` to `b` at the given point.
+fn edge(a: &Position, b: &Position, x: f64, y: f64) -> f64 {
+    (x - a.x) * (b.y - a.y) - (y - a.y) * (b.x - a.x)
+}
+
+/// Returns true if a sample on an edge is covered by the primitive, according to the
+/// top-left rule. `sign` is the orientation of the primitive.
+fn covers_edge(a: &Position, b: &Position, sign: f64) -> bool {
+    let dx = (b.x - a.x) * sign;
+    let dy = (b.y - a.y) * sign;
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+fn stencil_op(op: pso::StencilOp, value: u32, reference: u32) -> u32 {
+    match op {
+        pso::StencilOp::Keep => value,
+        pso::StencilOp::Zero => 0,
+        pso::StencilOp::Replace => reference,
+        pso::StencilOp::IncrementClamp => (value + 1).min(0xFF),
+        pso::StencilOp::DecrementClamp => value.saturating_sub(1),
+        pso::StencilOp::Invert => !value & 0xFF,
+        pso::StencilOp::IncrementWrap => (value + 1) & 0xFF,
+        pso::StencilOp::DecrementWrap => value.wrapping_sub(1) & 0xFF,
+    }
+}
+
+fn blend_factor(
+    factor: pso::Factor,
+    channel: usize,
+    src: &[f32; 4],
+    src1: &[f32; 4],
+    dst: &[f32; 4],
+    constant: &pso::ColorValue,
+) -> f32 {
+    use hal::pso::Factor as F;
+    match factor {
+        F::Zero => 0.0,
+        F::One => 1.0,
+        F::SrcColor => src[channel],
+        F::OneMinusSrcColor => 1.0 - src[channel],
+        F::DstColor => dst[channel],
+        F::OneMinusDstColor => 1.0 - dst[channel],
+        F::SrcAlpha => src[3],
+        F::OneMinusSrcAlpha => 1.0 - src[3],
+        F::DstAlpha => dst[3],
+        F::OneMinusDstAlpha => 1.0 - dst[3],
+        F::ConstColor => constant[channel],
+        F::OneMinusConstColor => 1.0 - constant[channel],
+        F::ConstAlpha => constant[3],
+        F::OneMinusConstAlpha => 1.0 - constant[3],
+        F::SrcAlphaSaturate if channel == 3 => 1.0,
+        F::SrcAlphaSaturate => src[3].min(1.0 - dst[3]),
+        F::Src1Color => src1[channel],
+        F::OneMinusSrc1Color => 1.0 - src1[channel],
+        F::Src1Alpha => src1[3],
+        F::OneMinusSrc1Alpha => 1.0 - src1[3],
+    }
+}
+
+fn blend_op(
+    op: pso::BlendOp,
+    channel: usize,
+    src: &[f32; 4],
+    src1: &[f32; 4],
+    dst: &[f32; 4],
+    constant: &pso::ColorValue,
+) -> f32 {
+    let factor = |f| blend_factor(f, channel, src, src1, dst, constant);
+    let (s, d) = (src[channel], dst[channel]);
+    match op {
+        pso::BlendOp::Add { src, dst } => s * factor(src) + d * factor(dst),
+        pso::BlendOp::Sub { src, dst } => s * factor(src) - d * factor(dst),
+        pso::BlendOp::RevSub { src, dst } => d * factor(dst) - s * factor(src),
+        pso::BlendOp::Min => s.min(d),
+        pso::BlendOp::Max => s.max(d),
+    }
+}
+
+fn floats(texel: Texel) -> [f32; 4] {
+    [
+        f32::from_bits(texel[0]),
+        f32::from_bits(texel[1]),
+        f32::from_bits(texel[2]),
+        f32::from_bits(texel[3]),
+    ]
+}
+
+/// Minimum resolvable difference of a depth format, used for the depth bias.
+fn depth_resolution(format: format::Format, max_depth: f32) -> f32 {
+    match format.base_format().0 {
+        format::SurfaceType::D16 | format::SurfaceType::D16_S8 => 1.0 / 65536.0,
+        format::SurfaceType::D24_S8 | format::SurfaceType::X8D24 => 1.0 / 16_777_216.0,
+        _ => {
+            let exponent = ((max_depth.to_bits() >> 23) & 0xFF) as i32 - 127;
+            2f32.powi(exponent - 23)
+        }
+    }
+}
+
+/// Draws primitives with a graphics pipeline inside of a subpass.
+pub(crate) struct Renderer<'a> {
+    state: &'a GraphicsState,
+    dynamic: &'a DynamicState,
+    vertex_buffers: &'a [Option<RawBuffer>],
+    bindings: Bindings<'a>,
+    vertex: Invocation<'a>,
+    fragment: Option<Invocation<'a>>,
+    /// Fragment shader input locations, with their interpolation.
+    varyings: Vec<(u32, Interpolation)>,
+    viewport: pso::Viewport,
+    /// Pixels that may be written: the render area inside of the scissor.
+    bounds: Bounds,
+    colors: Vec<&'a ImageView>,
+    depth_stencil: Option<&'a ImageView>,
+    /// Number of samples that passed the depth and stencil tests.
+    samples_passed: u64,
+}
+
+impl<'a> Renderer<'a> {
+    /// Creates a renderer, or returns `None` if no viewport is set.
+    pub(crate) fn new(
+        state: &'a GraphicsState,
+        dynamic: &'a DynamicState,
+        pass: &'a PassState,
+        vertex_buffers: &'a [Option<RawBuffer>],
+        bindings: Bindings<'a>,
+    ) -> Option<Self> {
+        let viewport = state
+            .baked_states
+            .viewport
+            .clone()
+            .or_else(|| dynamic.viewport.clone())?;
+        let scissor = state
+            .baked_states
+            .scissor
+            .or(dynamic.scissor)
+            .unwrap_or(pass.area);
+        let subpass = &pass.info.subpasses[pass.subpass];
+
+        let mut varyings = Vec::<(u32, Interpolation)>::new();
+        if let Some(ref fragment) = state.fragment {
+            for slot in &fragment.inputs {
+                match varyings.iter_mut().find(|v| v.0 == slot.location) {
+                    Some(varying) if slot.interpolation == Interpolation::Flat => {
+                        varying.1 = Interpolation::Flat;
+                    }
+                    Some(_) => {}
+                    None => varyings.push((slot.location, slot.interpolation)),
+                }
+            }
+        }
+
+        Some(Renderer {
+            state,
+            dynamic,
+            vertex_buffers,
+            bindings,
+            vertex: Invocation::new(&state.vertex),
+            fragment: state.fragment.as_ref().map(Invocation::new),
+            varyings,
+            viewport,
+            bounds: Bounds::from_rect(pass.area).intersect(&Bounds::from_rect(scissor)),
+            colors: subpass
+                .colors
+                .iter()
+                .map(|&index| &pass.attachments[index])
+                .collect(),
+            depth_stencil: subpass.depth_stencil.map(|index| &pass.attachments[index]),
+            samples_passed: 0,
+        })
+    }
+
+    /// Returns the number of samples that passed the depth and stencil tests so far.
+    pub(crate) fn samples_passed(&self) -> u64 {
+        self.samples_passed
+    }
+
+    /// Draws one instance of the primitives formed by the given vertex indices.
+    ///
+    /// `None` restarts the primitive assembly.
+    pub(crate) fn draw(&mut self, indices: &[Option<u32>], instance: u32, first_instance: u32) {
+        let mut cache = FastHashMap::default();
+        let mut vertices = Vec::new();
+
+        for run in indices.split(Option::is_none) {
+            let run = run.iter().map(|index| index.unwrap()).collect::<Vec<_>>();
+            let primitives: Vec<Vec<u32>> = match self.state.input_assembler.primitive {
+                pso::Primitive::PointList => run.iter().map(|&i| vec![i]).collect(),
+                pso::Primitive::LineList => run.chunks_exact(2).map(|c| c.to_vec()).collect(),
+                pso::Primitive::LineStrip => run.windows(2).map(|w| w.to_vec()).collect(),
+                pso::Primitive::TriangleList => run.chunks_exact(3).map(|c| c.to_vec()).collect(),
+                pso::Primitive::TriangleStrip => run
+                    .windows(3)
+                    .enumerate()
+                    .map(|(i, w)| {
+                        if i % 2 == 0 {
+                            w.to_vec()
+                        } else {
+                            vec![w[0], w[2], w[1]]
+                        }
+                    })
+                    .collect(),
+                pso::Primitive::PatchList(_) => {
+                    warn!("Patch lists are not supported");
+                    return;
+                }
+            };
+
+            for primitive in primitives {
+                let shaded = primitive
+                    .iter()
+                    .map(|&index| {
+                        *cache.entry(index).or_insert_with(|| {
+                            vertices.push(self.shade_vertex(index, instance, first_instance));
+                            vertices.len() - 1
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                match shaded[..] {
+                    [a] => self.point(&vertices[a], 0.0),
+                    [a, b] => self.line(&vertices[a], &vertices[b], &vertices[a].varyings, 0.0),
+                    [a, b, c] => self.triangle([&vertices[a], &vertices[b], &vertices[c]]),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    fn shade_vertex(&mut self, index: u32, instance: u32, first_instance: u32) -> Vertex {
+        self.vertex.reset();
+        for attribute in &self.state.attributes {
+            let desc = self
+                .state
+                .vertex_buffers
+                .iter()
+                .find(|desc| desc.binding == attribute.binding);
+            let buffer = self
+                .vertex_buffers
+                .get(attribute.binding as usize)
+                .and_then(Option::as_ref);
+            let (desc, buffer) = match (desc, buffer) {
+                (Some(desc), Some(buffer)) => (desc, buffer),
+                _ => continue,
+            };
+            let element = match desc.rate {
+                pso::VertexInputRate::Vertex => index,
+                pso::VertexInputRate::Instance(0) => first_instance,
+                pso::VertexInputRate::Instance(divisor) => {
+                    first_instance + (instance - first_instance) / divisor as u32
+                }
+            };
+            let format = attribute.element.format;
+            let offset =
+                element as usize * desc.stride as usize + attribute.element.offset as usize;
+            let bytes = unsafe { buffer.bytes() };
+            // Out of bounds fetches return zero, as required by robust buffer access.
+            let texel = match bytes.get(offset .. offset + conv::texel_size(format)) {
+                Some(bytes) => conv::decode(format, bytes),
+                None => [0; 4],
+            };
+            self.vertex.set_input(attribute.location, texel);
+        }
+        self.vertex
+            .set_builtin(spirv::BuiltIn::VertexIndex, Value::Scalar(index));
+        self.vertex
+            .set_builtin(spirv::BuiltIn::InstanceIndex, Value::Scalar(instance));
+        self.vertex.run(&mut [], &self.bindings);
+
+        let position = match self.vertex.builtin(spirv::BuiltIn::Position) {
+            Some(value) => floats(value.to_texel()),
+            None => [0.0, 0.0, 0.0, 1.0],
+        };
+        let point_size = self
+            .vertex
+            .builtin(spirv::BuiltIn::PointSize)
+            .map_or(1.0, Value::float);
+        let vertex = &self.vertex;
+        Vertex {
+            position,
+            point_size,
+            varyings: self
+                .varyings
+                .iter()
+                .map(|&(location, _)| vertex.output(location, 0).unwrap_or([0; 4]))
+                .collect(),
+        }
+    }
+
+    /// Returns the signed distances of a clip space position to the clipping planes.
+    fn plane_distances(&self, p: &[f32; 4]) -> [f32; 7] {
+        let (x, y, z, w) = (p[0], p[1], p[2], p[3]);
+        let clamped = self.state.rasterizer.depth_clamping;
+        [
+            w - MIN_W,
+            w * GUARD_BAND - x,
+            w * GUARD_BAND + x,
+            w * GUARD_BAND - y,
+            w * GUARD_BAND + y,
+            if clamped { 1.0 } else { z },
+            if clamped { 1.0 } else { w - z },
+        ]
+    }
+
+    /// Clips a polygon against the clip volume.
+    fn clip(&self, mut polygon: Vec<Vertex>) -> Vec<Vertex> {
+        for plane in 0 .. 7 {
+            if polygon.is_empty() {
+                break;
+            }
+            let distances = polygon
+                .iter()
+                .map(|v| self.plane_distances(&v.position)[plane])
+                .collect::<Vec<_>>();
+            if distances.iter().all(|&d| d >= 0.0) {
+                continue;
+            }
+            // A line is only split once per plane, since it has no closing edge.
+            let edges = if polygon.len() == 2 { 1 } else { polygon.len() };
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+            for i in 0 .. edges {
+                let j = (i + 1) % polygon.len();
+                let (da, db) = (distances[i], distances[j]);
+                if da >= 0.0 {
+                    clipped.push(polygon[i].clone());
+                }
+                if (da >= 0.0) != (db >= 0.0) {
+                    clipped.push(polygon[i].lerp(&polygon[j], da / (da - db)));
+                }
+            }
+            if polygon.len() == 2 && distances[1] >= 0.0 {
+                clipped.push(polygon[1].clone());
+            }
+            polygon = clipped;
+        }
+        polygon
+    }
+
+    /// Transforms a clip space position to framebuffer coordinates.
+    fn project(&self, p: &[f32; 4]) -> Position {
+        let inv_w = 1.0 / p[3];
+        let rect = &self.viewport.rect;
+        let (w, h) = (rect.w as f32, rect.h as f32);
+        let x = w * 0.5 * p[0] * inv_w + rect.x as f32 + w * 0.5;
+        let y = h * 0.5 * p[1] * inv_w + rect.y as f32 + h * 0.5;
+        let depth = &self.viewport.depth;
+        let z = depth.start + (depth.end - depth.start) * p[2] * inv_w;
+        let snap = |v: f32| (v as f64 * SUBPIXEL_STEPS).round() / SUBPIXEL_STEPS;
+        Position {
+            x: snap(x),
+            y: snap(y),
+            z,
+            inv_w,
+        }
+    }
+
+    fn viewport_bounds(&self) -> Bounds {
+        self.bounds
+            .intersect(&Bounds::from_rect(self.viewport.rect))
+    }
+
+    fn depth_bias(&self) -> Option<pso::DepthBias> {
+        match self.state.rasterizer.depth_bias {
+            Some(pso::State::Static(bias)) => Some(bias),
+            Some(pso::State::Dynamic) => Some(self.dynamic.depth_bias),
+            None => None,
+        }
+    }
+
+    fn triangle(&mut self, vertices: [&Vertex; 3]) {
+        let flat = vertices[0].varyings.clone();
+        let polygon = self.clip(vertices.iter().map(|&v| v.clone()).collect());
+        if polygon.len() < 3 {
+            return;
+        }
+        let positions = polygon
+            .iter()
+            .map(|v| self.project(&v.position))
+            .collect::<Vec<_>>();
+
+        let area = positions
+            .iter()
+            .zip(positions.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum::<f64>()
+            * -0.5;
+        if area == 0.0 {
+            return;
+        }
+        let front_facing =
+            (area > 0.0) == (self.state.rasterizer.front_face == pso::FrontFace::CounterClockwise);
+        let face = if front_facing {
+            pso::Face::FRONT
+        } else {
+            pso::Face::BACK
+        };
+        if self.state.rasterizer.cull_face.contains(face) {
+            return;
+        }
+
+        let bias = match self.depth_bias() {
+            Some(bias) => {
+                let (p0, p1, p2) = (&positions[0], &positions[1], &positions[2]);
+                let det = (p1.x - p0.x) * (p2.y - p0.y) - (p2.x - p0.x) * (p1.y - p0.y);
+                let (dz1, dz2) = ((p1.z - p0.z) as f64, (p2.z - p0.z) as f64);
+                let dzdx = (dz1 * (p2.y - p0.y) - dz2 * (p1.y - p0.y)) / det;
+                let dzdy = ((p1.x - p0.x) * dz2 - (p2.x - p0.x) * dz1) / det;
+                let slope = dzdx.abs().max(dzdy.abs()) as f32;
+                let max_depth = positions.iter().fold(0f32, |m, p| m.max(p.z.abs()));
+                let resolution = self
+                    .depth_stencil
+                    .map_or(0.0, |view| depth_resolution(view.format, max_depth));
+                let value = slope * bias.slope_factor + resolution * bias.const_factor;
+                if bias.clamp > 0.0 {
+                    value.min(bias.clamp)
+                } else if bias.clamp < 0.0 {
+                    value.max(bias.clamp)
+                } else {
+                    value
+                }
+            }
+            None => 0.0,
+        };
+
+        match self.state.rasterizer.polygon_mode {
+            pso::PolygonMode::Fill => {
+                for i in 1 .. polygon.len() - 1 {
+                    self.fill(
+                        [&positions[0], &positions[i], &positions[i + 1]],
+                        [&polygon[0], &polygon[i], &polygon[i + 1]],
+                        &flat,
+                        front_facing,
+                        bias,
+                    );
+                }
+            }
+            pso::PolygonMode::Line => {
+                for i in 0 .. 3 {
+                    self.line(vertices[i], vertices[(i + 1) % 3], &flat, bias);
+                }
+            }
+            pso::PolygonMode::Point => {
+                for vertex in vertices.iter() {
+                    self.point(vertex, bias);
+                }
+            }
+        }
+    }
+
+    /// Rasterizes a clipped triangle.
+    fn fill(
+        &mut self,
+        p: [&Position; 3],
+        v: [&Vertex; 3],
+        flat: &[Texel],
+        front_facing: bool,
+        bias: f32,
+    ) {
+        let area = edge(p[0], p[1], p[2].x, p[2].y);
+        if area == 0.0 {
+            return;
+        }
+        let sign = area.signum();
+        let bounds = self.viewport_bounds().covering(
+            p.iter().fold(f64::MAX, |m, p| m.min(p.x)),
+            p.iter().fold(f64::MAX, |m, p| m.min(p.y)),
+            p.iter().fold(f64::MIN, |m, p| m.max(p.x)),
+            p.iter().fold(f64::MIN, |m, p| m.max(p.y)),
+        );
+        let edges = [(1, 2), (2, 0), (0, 1)];
+
+        for y in bounds.y0 .. bounds.y1 {
+            for x in bounds.x0 .. bounds.x1 {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                let mut weights = [0.0; 3];
+                let mut inside = true;
+                for (weight, &(a, b)) in weights.iter_mut().zip(edges.iter()) {
+                    let e = edge(p[a], p[b], px, py) * sign;
+                    if e < 0.0 || (e == 0.0 && !covers_edge(p[a], p[b], sign)) {
+                        inside = false;
+                        break;
+                    }
+                    *weight = (e / (area * sign)) as f32;
+                }
+                if !inside {
+                    continue;
+                }
+
+                let depth = weights
+                    .iter()
+                    .zip(p.iter())
+                    .map(|(w, p)| w * p.z)
+                    .sum::<f32>();
+                let inv_w = weights
+                    .iter()
+                    .zip(p.iter())
+                    .map(|(w, p)| w * p.inv_w)
+                    .sum::<f32>();
+                let mut perspective = [0.0; 3];
+                for i in 0 .. 3 {
+                    perspective[i] = weights[i] * p[i].inv_w / inv_w;
+                }
+                let inputs = self.interpolate(&v, &weights, &perspective, flat);
+                self.fragment(
+                    Fragment {
+                        x,
+                        y,
+                        depth: depth + bias,
+                        inv_w,
+                        front_facing,
+                        point_coord: [0.0; 2],
+                    },
+                    &inputs,
+                );
+            }
+        }
+    }
+
+    /// Interpolates the varyings of a primitive with the given weights.
+    fn interpolate(
+        &self,
+        vertices: &[&Vertex],
+        linear: &[f32],
+        perspective: &[f32],
+        flat: &[Texel],
+    ) -> Vec<Texel> {
+        self.varyings
+            .iter()
+            .enumerate()
+            .map(|(i, &(_, interpolation))| {
+                let weights = match interpolation {
+                    Interpolation::Flat => return flat[i],
+                    Interpolation::Linear => linear,
+                    Interpolation::Perspective => perspective,
+                };
+                let mut texel = [0; 4];
+                for (c, t) in texel.iter_mut().enumerate() {
+                    *t = vertices
+                        .iter()
+                        .zip(weights.iter())
+                        .map(|(v, w)| w * f32::from_bits(v.varyings[i][c]))
+                        .sum::<f32>()
+                        .to_bits();
+                }
+                texel
+            })
+            .collect()
+    }
+
+    fn line(&mut self, a: &Vertex, b: &Vertex, flat: &[Texel], bias: f32) {
+        let clipped = self.clip(vec![a.clone(), b.clone()]);
+        if clipped.len() != 2 {
+            return;
+        }
+        let (pa, pb) = (
+            self.project(&clipped[0].position),
+            self.project(&clipped[1].position),
+        );
+        let (dx, dy) = (pb.x - pa.x, pb.y - pa.y);
+        let x_major = dx.abs() >= dy.abs();
+        let (start, end) = if x_major { (pa.x, pb.x) } else { (pa.y, pb.y) };
+        let bounds = self.viewport_bounds();
+        let vertices = [&clipped[0], &clipped[1]];
+
+        // Walks the pixel centers along the major axis, excluding the last endpoint.
+        let first = (start.min(end) - 0.5).ceil() as i32;
+        let last = (start.max(end) - 0.5).ceil() as i32;
+        for major in first .. last {
+            let center = major as f64 + 0.5;
+            if (start < end && center < start) || (start > end && center <= end) {
+                continue;
+            }
+            let t = (center - start) / (end - start);
+            let minor = if x_major {
+                pa.y + t * dy
+            } else {
+                pa.x + t * dx
+            }
+            .floor() as i32;
+            let (x, y) = if x_major {
+                (major, minor)
+            } else {
+                (minor, major)
+            };
+            if x < bounds.x0 || x >= bounds.x1 || y < bounds.y0 || y >= bounds.y1 {
+                continue;
+            }
+
+            let t = t as f32;
+            let weights = [1.0 - t, t];
+            let inv_w = weights[0] * pa.inv_w + weights[1] * pb.inv_w;
+            let perspective = [weights[0] * pa.inv_w / inv_w, weights[1] * pb.inv_w / inv_w];
+            let inputs = self.interpolate(&vertices, &weights, &perspective, flat);
+            self.fragment(
+                Fragment {
+                    x,
+                    y,
+                    depth: weights[0] * pa.z + weights[1] * pb.z + bias,
+                    inv_w,
+                    front_facing: true,
+                    point_coord: [0.0; 2],
+                },
+                &inputs,
+            );
+        }
+    }
+
+    fn point(&mut self, vertex: &Vertex, bias: f32) {
+        let distances = self.plane_distances(&vertex.position);
+        let [x, y, _, w] = vertex.position;
+        if distances.iter().any(|&d| d < 0.0) || x.abs() > w || y.abs() > w {
+            return;
+        }
+        let p = self.project(&vertex.position);
+        let size = vertex.point_size.clamp(1.0, MAX_POINT_SIZE) as f64;
+        let bounds = self.bounds.covering(
+            p.x - size / 2.0,
+            p.y - size / 2.0,
+            p.x + size / 2.0,
+            p.y + size / 2.0,
+        );
+        for y in bounds.y0 .. bounds.y1 {
+            for x in bounds.x0 .. bounds.x1 {
+                let point_coord = [
+                    (0.5 + (x as f64 + 0.5 - p.x) / size) as f32,
+                    (0.5 + (y as f64 + 0.5 - p.y) / size) as f32,
+                ];
+                self.fragment(
+                    Fragment {
+                        x,
+                        y,
+                        depth: p.z + bias,
+                        inv_w: p.inv_w,
+                        front_facing: true,
+                        point_coord,
+                    },
+                    &vertex.varyings,
+                );
+            }
+        }
+    }
+
+    /// Runs the per-fragment operations for a covered pixel.
+    fn fragment(&mut self, fragment: Fragment, inputs: &[Texel]) {
+        if let Some(ref multisampling) = self.state.multisampling {
+            if multisampling.sample_mask & 1 == 0 {
+                return;
+            }
+        }
+        let mut depth = fragment.depth;
+        if self.state.rasterizer.depth_clamping {
+            let range = &self.viewport.depth;
+            depth = depth
+                .max(range.start.min(range.end))
+                .min(range.start.max(range.end));
+        }
+
+        let early_tests = self
+            .state
+            .fragment
+            .as_ref()
+            .map(|program| program.early_fragment_tests)
+            .unwrap_or(true);
+        if early_tests && !self.depth_stencil_test(&fragment, depth) {
+            return;
+        }
+
+        let mut outputs = Vec::new();
+        let mut src1 = [0.0; 4];
+        if let Some(ref mut invocation) = self.fragment {
+            invocation.reset();
+            for (&(location, _), &texel) in self.varyings.iter().zip(inputs) {
+                invocation.set_input(location, texel);
+            }
+            invocation.set_builtin(
+                spirv::BuiltIn::FragCoord,
+                Value::from_floats(&[
+                    fragment.x as f32 + 0.5,
+                    fragment.y as f32 + 0.5,
+                    depth,
+                    fragment.inv_w,
+                ]),
+            );
+            invocation.set_builtin(
+                spirv::BuiltIn::FrontFacing,
+                Value::Scalar(fragment.front_facing as u32),
+            );
+            invocation.set_builtin(
+                spirv::BuiltIn::PointCoord,
+                Value::from_floats(&fragment.point_coord),
+            );
+            invocation.set_builtin(spirv::BuiltIn::SampleId, Value::Scalar(0));
+            invocation.set_builtin(
+                spirv::BuiltIn::SamplePosition,
+                Value::from_floats(&[0.5, 0.5]),
+            );
+            if invocation.run(&mut [], &self.bindings) == Status::Killed {
+                return;
+            }
+
+            if invocation.program().writes_depth() {
+                if let Some(value) = invocation.builtin(spirv::BuiltIn::FragDepth) {
+                    depth = value.float();
+                }
+            }
+            outputs = (0 .. self.colors.len() as u32)
+                .map(|location| invocation.output(location, 0))
+                .collect();
+            if let Some(texel) = invocation.output(0, 1) {
+                src1 = floats(texel);
+            }
+        }
+
+        if let Some(ref multisampling) = self.state.multisampling {
+            if let Some(Some(ref mut texel)) = outputs.first_mut() {
+                if multisampling.alpha_coverage && f32::from_bits(texel[3]) < 0.5 {
+                    return;
+                }
+            }
+            if multisampling.alpha_to_one {
+                for texel in outputs.iter_mut().flatten() {
+                    texel[3] = 1f32.to_bits();
+                }
+            }
+        }
+
+        if !early_tests && !self.depth_stencil_test(&fragment, depth) {
+            return;
+        }
+        self.samples_passed += 1;
+
+        for (i, output) in outputs.into_iter().enumerate() {
+            if let Some(texel) = output {
+                self.write_color(i, self.colors[i], fragment.x, fragment.y, texel, &src1);
+            }
+        }
+    }
+
+    /// Runs the depth bounds, stencil and depth tests, updating the attachment.
+    fn depth_stencil_test(&self, fragment: &Fragment, depth: f32) -> bool {
+        let view = match self.depth_stencil {
+            Some(view) => view,
+            None => return true,
+        };
+        let desc = &self.state.depth_stencil;
+        let format = view.format;
+        let bytes = match unsafe {
+            view.image.texel(
+                view.range.levels.start,
+                view.range.layers.start,
+                [fragment.x, fragment.y, 0],
+            )
+        } {
+            Some(bytes) => bytes,
+            None => return false,
+        };
+
+        let stored_depth = if format.is_depth() {
+            conv::decode_depth(format, bytes)
+        } else {
+            0.0
+        };
+        if desc.depth_bounds && format.is_depth() {
+            let bounds = self
+                .state
+                .baked_states
+                .depth_bounds
+                .clone()
+                .unwrap_or_else(|| self.dynamic.depth_bounds.clone());
+            if stored_depth < bounds.start || stored_depth > bounds.end {
+                return false;
+            }
+        }
+        let depth_passed = match desc.depth {
+            Some(ref test) if format.is_depth() => sample::compare(test.fun, depth, stored_depth),
+            _ => true,
+        };
+
+        if let Some(ref stencil) = desc.stencil {
+            if format.is_stencil() {
+                let side = |values: pso::StencilValues, dynamic: &pso::Sided<u32>| {
+                    let values = match values {
+                        pso::State::Static(values) => values,
+                        pso::State::Dynamic => *dynamic,
+                    };
+                    if fragment.front_facing {
+                        values.front
+                    } else {
+                        values.back
+                    }
+                };
+                let face = if fragment.front_facing {
+                    &stencil.faces.front
+                } else {
+                    &stencil.faces.back
+                };
+                let reference = side(stencil.reference_values, &self.dynamic.stencil_reference);
+                let read_mask = side(stencil.read_masks, &self.dynamic.stencil_read_mask);
+                let write_mask = side(stencil.write_masks, &self.dynamic.stencil_write_mask);
+
+                let stored = conv::decode_stencil(format, bytes) as u32;
+                let stencil_passed =
+                    sample::compare(face.fun, reference & read_mask, stored & read_mask);
+                let op = if !stencil_passed {
+                    face.op_fail
+                } else if !depth_passed {
+                    face.op_depth_fail
+                } else {
+                    face.op_pass
+                };
+                let value = stencil_op(op, stored, reference);
+                let value = (stored & !write_mask) | (value & write_mask);
+                conv::encode_stencil(format, value as u8, bytes);
+                if !stencil_passed {
+                    return false;
+                }
+            }
+        }
+
+        if !depth_passed {
+            return false;
+        }
+        if let Some(ref test) = desc.depth {
+            if test.write && format.is_depth() {
+                conv::encode_depth(format, depth, bytes);
+            }
+        }
+        true
+    }
+
+    fn write_color(
+        &self,
+        index: usize,
+        view: &ImageView,
+        x: i32,
+        y: i32,
+        texel: Texel,
+        src1: &[f32; 4],
+    ) {
+        let target = match self.state.blender.targets.get(index) {
+            Some(target) if !target.mask.is_empty() => target,
+            _ => return,
+        };
+        let (level, layer) = (view.range.levels.start, view.range.layers.start);
+        let coords = [x, y, 0];
+        let dst = match sample::read_texel(
+            &view.image,
+            view.format,
+            format::Aspects::COLOR,
+            level,
+            layer,
+            coords,
+        ) {
+            Some(dst) => dst,
+            None => return,
+        };
+
+        let mut result = texel;
+        if let Some(blend) = target.blend {
+            if !conv::is_integer(view.format) {
+                let clamp = |v: [f32; 4]| -> [f32; 4] {
+                    if !conv::is_normalized(view.format) {
+                        return v;
+                    }
+                    let min = if view.format.base_format().1 == format::ChannelType::Snorm {
+                        -1.0
+                    } else {
+                        0.0
+                    };
+                    [
+                        v[0].max(min).min(1.0),
+                        v[1].max(min).min(1.0),
+                        v[2].max(min).min(1.0),
+                        v[3].max(min).min(1.0),
+                    ]
+                };
+                let src = clamp(floats(texel));
+                let src1 = clamp(*src1);
+                let constant = clamp(
+                    self.state
+                        .baked_states
+                        .blend_color
+                        .unwrap_or(self.dynamic.blend_constants),
+                );
+                let dst = floats(dst);
+                for (c, value) in result.iter_mut().enumerate() {
+                    let op = if c == 3 { blend.alpha } else { blend.color };
+                    *value = blend_op(op, c, &src, &src1, &dst, &constant).to_bits();
+                }
+            }
+        }
+
+        for c in 0 .. 4 {
+            if !target
+                .mask
+                .contains(pso::ColorMask::from_bits_truncate(1 << c))
+            {
+                result[c] = dst[c];
+            }
+        }
+        sample::write_texel(
+            &view.image,
+            view.format,
+            format::Aspects::COLOR,
+            level,
+            layer,
+            coords,
+            result,
+        );
+    }
+}
@@ -0,0 +1,595 @@
+//! Access to the descriptors from shaders: buffers, texel fetches and image sampling.
+
+use crate::command::BoundSet;
+use crate::conv::{self, Texel};
+use crate::native::{Descriptor, DescriptorBinding, ImageView, RawBuffer, RawImage};
+use crate::shader::{self, Lod};
+use hal::{format, image, pso};
+use parking_lot::RwLockReadGuard;
+
+const ONE: u32 = 0x3F80_0000;
+
+/// Reads a texel of an image, decoding the given aspect.
+pub(crate) fn read_texel(
+    image: &RawImage,
+    format: format::Format,
+    aspects: format::Aspects,
+    level: image::Level,
+    layer: image::Layer,
+    coords: [i32; 3],
+) -> Option<Texel> {
+    let bytes = unsafe { image.texel(level, layer, coords)? };
+    Some(if aspects == format::Aspects::STENCIL {
+        [conv::decode_stencil(format, bytes) as u32, 0, 0, 1]
+    } else {
+        conv::decode(format, bytes)
+    })
+}
+
+/// Writes the given aspects of a texel of an image.
+pub(crate) fn write_texel(
+    image: &RawImage,
+    format: format::Format,
+    aspects: format::Aspects,
+    level: image::Level,
+    layer: image::Layer,
+    coords: [i32; 3],
+    texel: Texel,
+) {
+    let bytes = match unsafe { image.texel(level, layer, coords) } {
+        Some(bytes) => bytes,
+        None => return,
+    };
+    if format.is_depth() || format.is_stencil() {
+        if aspects.contains(format::Aspects::DEPTH) {
+            conv::encode_depth(format, f32::from_bits(texel[0]), bytes);
+        }
+        if aspects.contains(format::Aspects::STENCIL) {
+            let stencil = if aspects == format::Aspects::STENCIL {
+                texel[0]
+            } else {
+                texel[1]
+            };
+            conv::encode_stencil(format, stencil as u8, bytes);
+        }
+    } else {
+        conv::encode(format, texel, bytes);
+    }
+}
+
+fn one(format: format::Format) -> u32 {
+    if conv::is_integer(format) {
+        1
+    } else {
+        ONE
+    }
+}
+
+fn swizzle(texel: Texel, swizzle: format::Swizzle, format: format::Format) -> Texel {
+    let component = |c: format::Component| match c {
+        format::Component::Zero => 0,
+        format::Component::One => one(format),
+        format::Component::R => texel[0],
+        format::Component::G => texel[1],
+        format::Component::B => texel[2],
+        format::Component::A => texel[3],
+    };
+    [
+        component(swizzle.0),
+        component(swizzle.1),
+        component(swizzle.2),
+        component(swizzle.3),
+    ]
+}
+
+/// Reads a texel of a view, given a level and layer relative to the view.
+fn load(view: &ImageView, level: u32, layer: u32, coords: [i32; 3]) -> Option<Texel> {
+    let texel = read_texel(
+        &view.image,
+        view.format,
+        view.range.aspects,
+        view.range.levels.start + level as image::Level,
+        view.range.layers.start + layer as image::Layer,
+        coords,
+    )?;
+    Some(swizzle(texel, view.swizzle, view.format))
+}
+
+fn level_count(view: &ImageView) -> u32 {
+    (view.range.levels.end - view.range.levels.start) as u32
+}
+
+fn layer_count(view: &ImageView) -> u32 {
+    (view.range.layers.end - view.range.layers.start) as u32
+}
+
+/// Returns the extent of a level of the view.
+fn extent(view: &ImageView, level: u32) -> [i32; 3] {
+    let extent = view
+        .image
+        .info
+        .level_extent(view.range.levels.start + level as image::Level);
+    [
+        extent.width as i32,
+        extent.height as i32,
+        extent.depth as i32,
+    ]
+}
+
+fn dimensions(kind: image::ViewKind) -> usize {
+    match kind {
+        image::ViewKind::D1 | image::ViewKind::D1Array => 1,
+        image::ViewKind::D3 => 3,
+        _ => 2,
+    }
+}
+
+/// Splits integer coordinates of a view into texel coordinates and an array layer.
+fn split_coords(kind: image::ViewKind, coords: [i32; 4]) -> ([i32; 3], i32) {
+    match kind {
+        image::ViewKind::D1 => ([coords[0], 0, 0], 0),
+        image::ViewKind::D1Array => ([coords[0], 0, 0], coords[1]),
+        image::ViewKind::D2 => ([coords[0], coords[1], 0], 0),
+        image::ViewKind::D3 => ([coords[0], coords[1], coords[2]], 0),
+        image::ViewKind::D2Array | image::ViewKind::Cube | image::ViewKind::CubeArray => {
+            ([coords[0], coords[1], 0], coords[2])
+        }
+    }
+}
+
+/// Selects the face of a cube map, returning the face and the coordinates on it.
+fn cube_face(direction: [f32; 3]) -> (u32, f32, f32) {
+    let [x, y, z] = direction;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let (face, sc, tc, ma) = if ax >= ay && ax >= az {
+        if x >= 0.0 {
+            (0, -z, -y, ax)
+        } else {
+            (1, z, -y, ax)
+        }
+    } else if ay >= az {
+        if y >= 0.0 {
+            (2, x, z, ay)
+        } else {
+            (3, x, -z, ay)
+        }
+    } else if z >= 0.0 {
+        (4, x, -y, az)
+    } else {
+        (5, -x, -y, az)
+    };
+    if ma == 0.0 {
+        return (face, 0.5, 0.5);
+    }
+    (face, 0.5 * (sc / ma + 1.0), 0.5 * (tc / ma + 1.0))
+}
+
+/// Applies a wrap mode to a texel coordinate. Returns `None` for border texels.
+fn wrap(coord: i32, size: i32, mode: image::WrapMode) -> Option<i32> {
+    match mode {
+        image::WrapMode::Tile => Some(coord.rem_euclid(size)),
+        image::WrapMode::Mirror => {
+            let t = coord.rem_euclid(2 * size);
+            Some(if t < size { t } else { 2 * size - 1 - t })
+        }
+        image::WrapMode::Clamp => Some(coord.max(0).min(size - 1)),
+        image::WrapMode::Border => {
+            if coord >= 0 && coord < size {
+                Some(coord)
+            } else {
+                None
+            }
+        }
+        image::WrapMode::MirrorClamp => {
+            let mirrored = if coord < 0 { -1 - coord } else { coord };
+            Some(mirrored.min(size - 1))
+        }
+    }
+}
+
+/// Evaluates `reference OP value`.
+pub(crate) fn compare<T: PartialOrd>(fun: pso::Comparison, reference: T, value: T) -> bool {
+    match fun {
+        pso::Comparison::Never => false,
+        pso::Comparison::Less => reference < value,
+        pso::Comparison::Equal => reference == value,
+        pso::Comparison::LessEqual => reference <= value,
+        pso::Comparison::Greater => reference > value,
+        pso::Comparison::NotEqual => reference != value,
+        pso::Comparison::GreaterEqual => reference >= value,
+        pso::Comparison::Always => true,
+    }
+}
+
+struct Sampling<'a> {
+    view: &'a ImageView,
+    desc: &'a image::SamplerDesc,
+    layer: u32,
+    reference: Option<f32>,
+    offset: [i32; 3],
+    /// Cube maps are always clamped to their edges.
+    cube: bool,
+}
+
+impl<'a> Sampling<'a> {
+    /// Returns the filterable value of a texel, after wrapping.
+    fn texel(&self, level: u32, mut coords: [i32; 3], size: [i32; 3]) -> [f32; 4] {
+        let modes = [
+            self.desc.wrap_mode.0,
+            self.desc.wrap_mode.1,
+            self.desc.wrap_mode.2,
+        ];
+        let dims = dimensions(self.view.kind);
+        let mut border = false;
+        for i in 0 .. dims {
+            let mode = if self.cube {
+                image::WrapMode::Clamp
+            } else {
+                modes[i]
+            };
+            match wrap(coords[i], size[i], mode) {
+                Some(c) => coords[i] = c,
+                None => border = true,
+            }
+        }
+        let texel = if border {
+            None
+        } else {
+            load(self.view, level, self.layer, coords)
+        };
+        let integer = conv::is_integer(self.view.format);
+        let values = match texel {
+            Some(texel) if integer => [
+                texel[0] as f32,
+                texel[1] as f32,
+                texel[2] as f32,
+                texel[3] as f32,
+            ],
+            Some(texel) => [
+                f32::from_bits(texel[0]),
+                f32::from_bits(texel[1]),
+                f32::from_bits(texel[2]),
+                f32::from_bits(texel[3]),
+            ],
+            None => self.desc.border.into(),
+        };
+        match (self.reference, self.desc.comparison) {
+            (Some(reference), Some(fun)) => {
+                let result = compare(fun, reference, values[0]) as u32 as f32;
+                [result, 0.0, 0.0, 1.0]
+            }
+            _ => values,
+        }
+    }
+
+    /// Filters the texels of a mip level around the given coordinates.
+    fn level(&self, level: u32, coords: [f32; 3], filter: image::Filter) -> [f32; 4] {
+        let size = extent(self.view, level);
+        let dims = dimensions(self.view.kind);
+        let mut scaled = [0.0; 3];
+        for i in 0 .. dims {
+            scaled[i] = if self.desc.normalized {
+                coords[i] * size[i] as f32
+            } else {
+                coords[i]
+            };
+        }
+
+        let integer = conv::is_integer(self.view.format);
+        if filter == image::Filter::Nearest || integer {
+            let mut texel = [0; 3];
+            for i in 0 .. dims {
+                texel[i] = scaled[i].floor() as i32 + self.offset[i];
+            }
+            return self.texel(level, texel, size);
+        }
+
+        let mut base = [0; 3];
+        let mut weights = [0.0; 3];
+        for i in 0 .. dims {
+            let c = scaled[i] - 0.5;
+            base[i] = c.floor() as i32 + self.offset[i];
+            weights[i] = c - c.floor();
+        }
+        let mut result = [0.0; 4];
+        for corner in 0 .. 1 << dims {
+            let mut texel = base;
+            let mut weight = 1.0;
+            for i in 0 .. dims {
+                if corner & (1 << i) != 0 {
+                    texel[i] += 1;
+                    weight *= weights[i];
+                } else {
+                    weight *= 1.0 - weights[i];
+                }
+            }
+            if weight == 0.0 {
+                continue;
+            }
+            let value = self.texel(level, texel, size);
+            for (r, v) in result.iter_mut().zip(value.iter()) {
+                *r += weight * v;
+            }
+        }
+        result
+    }
+}
+
+/// Samples an image view, returning the raw texel.
+pub(crate) fn sample(
+    view: &ImageView,
+    desc: &image::SamplerDesc,
+    coords: [f32; 4],
+    lod: Lod,
+    reference: Option<f32>,
+    offset: [i32; 3],
+) -> Texel {
+    let mut uvw = [coords[0], coords[1], coords[2]];
+    let mut cube = false;
+    let layer = match view.kind {
+        image::ViewKind::D1 | image::ViewKind::D2 | image::ViewKind::D3 => 0.0,
+        image::ViewKind::D1Array => coords[1],
+        image::ViewKind::D2Array => coords[2],
+        image::ViewKind::Cube | image::ViewKind::CubeArray => {
+            let (face, s, t) = cube_face(uvw);
+            uvw = [s, t, 0.0];
+            cube = true;
+            let array_layer = if view.kind == image::ViewKind::CubeArray {
+                coords[3].round().max(0.0)
+            } else {
+                0.0
+            };
+            array_layer * 6.0 + face as f32
+        }
+    };
+    let layer = (layer.round().max(0.0) as u32).min(layer_count(view).saturating_sub(1));
+
+    let size = extent(view, 0);
+    let lambda = match lod {
+        Lod::Level(level) => level,
+        Lod::Bias(bias) => bias,
+        Lod::Gradient(dx, dy) => {
+            let dims = dimensions(view.kind);
+            let scale = |d: [f32; 3]| {
+                (0 .. dims)
+                    .map(|i| (d[i] * size[i] as f32).powi(2))
+                    .sum::<f32>()
+                    .sqrt()
+            };
+            scale(dx).max(scale(dy)).log2()
+        }
+    } + desc.lod_bias.0;
+    let lambda = lambda.max(desc.lod_range.start.0).min(desc.lod_range.end.0);
+
+    let sampling = Sampling {
+        view,
+        desc,
+        layer,
+        reference: reference.map(|r| r.clamp(0.0, 1.0)),
+        offset,
+        cube,
+    };
+    let max_level = level_count(view).saturating_sub(1);
+    let value = if lambda <= 0.0 || !desc.normalized {
+        sampling.level(0, uvw, desc.mag_filter)
+    } else {
+        let filter = desc.min_filter;
+        match desc.mip_filter {
+            image::Filter::Nearest => {
+                let level = if lambda <= 0.5 {
+                    0
+                } else {
+                    ((lambda + 0.5).ceil() - 1.0) as u32
+                };
+                sampling.level(level.min(max_level), uvw, filter)
+            }
+            image::Filter::Linear => {
+                let low = (lambda.floor() as u32).min(max_level);
+                let high = (low + 1).min(max_level);
+                let fraction = lambda - lambda.floor();
+                let a = sampling.level(low, uvw, filter);
+                if high == low || fraction == 0.0 {
+                    a
+                } else {
+                    let b = sampling.level(high, uvw, filter);
+                    let mut mixed = [0.0; 4];
+                    for i in 0 .. 4 {
+                        mixed[i] = a[i] * (1.0 - fraction) + b[i] * fraction;
+                    }
+                    mixed
+                }
+            }
+        }
+    };
+
+    if conv::is_integer(view.format) && sampling.reference.is_none() {
+        let mut texel = [0; 4];
+        for (t, v) in texel.iter_mut().zip(value.iter()) {
+            *t = *v as i32 as u32;
+        }
+        texel
+    } else {
+        [
+            value[0].to_bits(),
+            value[1].to_bits(),
+            value[2].to_bits(),
+            value[3].to_bits(),
+        ]
+    }
+}
+
+/// Locked bindings of a descriptor set, along with its dynamic offsets.
+type LockedSet<'a> = (RwLockReadGuard<'a, Vec<DescriptorBinding>>, &'a [u32]);
+
+/// Descriptors and push constants visible to the shaders of a pipeline.
+pub(crate) struct Bindings<'a> {
+    sets: Vec<Option<LockedSet<'a>>>,
+    push_constants: &'a [u8],
+}
+
+impl<'a> Bindings<'a> {
+    pub(crate) fn new(sets: &'a [Option<BoundSet>], push_constants: &'a [u8]) -> Self {
+        Bindings {
+            sets: sets
+                .iter()
+                .map(|set| {
+                    set.as_ref()
+                        .map(|set| (set.bindings.read(), &set.offsets[..]))
+                })
+                .collect(),
+            push_constants,
+        }
+    }
+
+    /// Returns a descriptor, along with its dynamic offset.
+    fn descriptor(&self, resource: shader::Resource) -> Option<(&Descriptor, u32)> {
+        let (ref bindings, offsets) = *self.sets.get(resource.set as usize)?.as_ref()?;
+        let mut dynamic_index = 0;
+        for binding in bindings.iter() {
+            if binding.binding == resource.binding {
+                let descriptor = binding.descriptors.get(resource.element as usize)?;
+                let offset = if binding.is_dynamic() {
+                    offsets
+                        .get(dynamic_index + resource.element as usize)
+                        .cloned()
+                        .unwrap_or(0)
+                } else {
+                    0
+                };
+                return Some((descriptor, offset));
+            }
+            if binding.is_dynamic() {
+                dynamic_index += binding.descriptors.len();
+            }
+        }
+        None
+    }
+
+    fn view(&self, resource: shader::Resource) -> Option<&ImageView> {
+        match self.descriptor(resource)?.0 {
+            Descriptor::Image(ref view) | Descriptor::CombinedImageSampler(ref view, _) => {
+                Some(view)
+            }
+            _ => None,
+        }
+    }
+
+    fn texel_buffer(&self, resource: shader::Resource) -> Option<(&RawBuffer, format::Format)> {
+        match self.descriptor(resource)?.0 {
+            Descriptor::TexelBuffer(ref buffer, format) => Some((buffer, *format)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> shader::Resources for Bindings<'a> {
+    fn buffer(&self, resource: shader::Resource) -> Option<(*mut u8, usize)> {
+        match self.descriptor(resource)? {
+            (Descriptor::Buffer(buffer), offset) => {
+                let start = (buffer.offset + offset as usize).min(buffer.block.len());
+                let size = buffer.size.min(buffer.block.len() - start);
+                Some((unsafe { buffer.block.ptr().add(start) }, size))
+            }
+            _ => None,
+        }
+    }
+
+    fn push_constants(&self) -> &[u8] {
+        self.push_constants
+    }
+
+    fn sample(
+        &self,
+        image: shader::Resource,
+        sampler: shader::Resource,
+        coords: [f32; 4],
+        lod: Lod,
+        reference: Option<f32>,
+        offset: [i32; 3],
+    ) -> Texel {
+        let view = match self.view(image) {
+            Some(view) => view,
+            None => return [0; 4],
+        };
+        let desc = match self.descriptor(sampler) {
+            Some((&Descriptor::Sampler(ref desc), _))
+            | Some((&Descriptor::CombinedImageSampler(_, ref desc), _)) => desc,
+            _ => return [0; 4],
+        };
+        sample(view, desc, coords, lod, reference, offset)
+    }
+
+    fn fetch(&self, image: shader::Resource, coords: [i32; 4], level: u32) -> Texel {
+        if let Some((buffer, format)) = self.texel_buffer(image) {
+            let size = conv::texel_size(format);
+            let start = coords[0].max(0) as usize * size;
+            let bytes = unsafe { buffer.bytes() };
+            return match bytes.get(start .. start + size) {
+                Some(bytes) if coords[0] >= 0 => conv::decode(format, bytes),
+                _ => [0; 4],
+            };
+        }
+        let view = match self.view(image) {
+            Some(view) => view,
+            None => return [0; 4],
+        };
+        let (texel, layer) = split_coords(view.kind, coords);
+        if layer < 0 || layer as u32 >= layer_count(view) || level >= level_count(view) {
+            return [0; 4];
+        }
+        load(view, level, layer as u32, texel).unwrap_or([0; 4])
+    }
+
+    fn write(&self, image: shader::Resource, coords: [i32; 4], texel: Texel) {
+        if let Some((buffer, format)) = self.texel_buffer(image) {
+            let size = conv::texel_size(format);
+            let start = coords[0].max(0) as usize * size;
+            let bytes = unsafe { buffer.bytes_mut() };
+            if let Some(bytes) = bytes.get_mut(start .. start + size) {
+                if coords[0] >= 0 {
+                    conv::encode(format, texel, bytes);
+                }
+            }
+            return;
+        }
+        if let Some(view) = self.view(image) {
+            let (coords, layer) = split_coords(view.kind, coords);
+            if layer >= 0 && (layer as u32) < layer_count(view) {
+                write_texel(
+                    &view.image,
+                    view.format,
+                    view.range.aspects,
+                    view.range.levels.start,
+                    view.range.layers.start + layer as image::Layer,
+                    coords,
+                    texel,
+                );
+            }
+        }
+    }
+
+    fn query_size(&self, image: shader::Resource, level: u32) -> [u32; 4] {
+        if let Some((buffer, format)) = self.texel_buffer(image) {
+            return [(buffer.size / conv::texel_size(format)) as u32, 0, 0, 0];
+        }
+        let view = match self.view(image) {
+            Some(view) => view,
+            None => return [0; 4],
+        };
+        let [w, h, d] = extent(view, level);
+        let (w, h, d) = (w as u32, h as u32, d as u32);
+        let layers = layer_count(view);
+        match view.kind {
+            image::ViewKind::D1 => [w, 0, 0, 0],
+            image::ViewKind::D1Array => [w, layers, 0, 0],
+            image::ViewKind::D2 | image::ViewKind::Cube => [w, h, 0, 0],
+            image::ViewKind::D2Array => [w, h, layers, 0],
+            image::ViewKind::CubeArray => [w, h, layers / 6, 0],
+            image::ViewKind::D3 => [w, h, d, 0],
+        }
+    }
+
+    fn query_levels(&self, image: shader::Resource) -> u32 {
+        self.view(image).map_or(0, level_count)
+    }
+}
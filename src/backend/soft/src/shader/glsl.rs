@@ -0,0 +1,314 @@
+//! Instructions of the `GLSL.std.450` extended instruction set.
+
+use super::ops::{dot, map1, map2, map3};
+use super::Value;
+use crate::conv;
+
+// Instructions that need access to the invocation state.
+pub const MODF: u32 = 35;
+pub const FREXP: u32 = 51;
+pub const INTERPOLATE_AT_CENTROID: u32 = 76;
+pub const INTERPOLATE_AT_SAMPLE: u32 = 77;
+pub const INTERPOLATE_AT_OFFSET: u32 = 78;
+
+fn f(bits: u32) -> f32 {
+    f32::from_bits(bits)
+}
+
+fn b(value: f32) -> u32 {
+    value.to_bits()
+}
+
+fn i(bits: u32) -> i32 {
+    bits as i32
+}
+
+fn mapf(a: &Value, fun: &dyn Fn(f32) -> f32) -> Value {
+    map1(a, &|x| b(fun(f(x))))
+}
+
+fn mapf2(a: &Value, c: &Value, fun: &dyn Fn(f32, f32) -> f32) -> Value {
+    map2(a, c, &|x, y| b(fun(f(x), f(y))))
+}
+
+fn mapf3(a: &Value, c: &Value, d: &Value, fun: &dyn Fn(f32, f32, f32) -> f32) -> Value {
+    map3(a, c, d, &|x, y, z| b(fun(f(x), f(y), f(z))))
+}
+
+fn floats(value: &Value) -> Vec<f32> {
+    value.components().iter().map(Value::float).collect()
+}
+
+fn length(value: &Value) -> f32 {
+    dot(value, value).sqrt()
+}
+
+fn round_even(x: f32) -> f32 {
+    let rounded = x.round();
+    if (x - x.trunc()).abs() == 0.5 && rounded % 2.0 != 0.0 {
+        rounded - x.signum()
+    } else {
+        rounded
+    }
+}
+
+fn determinant(columns: &[Vec<f32>]) -> f32 {
+    match columns.len() {
+        1 => columns[0][0],
+        2 => columns[0][0] * columns[1][1] - columns[1][0] * columns[0][1],
+        n => (0 .. n)
+            .map(|j| {
+                let minor = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|&(c, _)| c != j)
+                    .map(|(_, column)| column[1 ..].to_vec())
+                    .collect::<Vec<_>>();
+                let sign = if j % 2 == 0 { 1.0 } else { -1.0 };
+                sign * columns[j][0] * determinant(&minor)
+            })
+            .sum(),
+    }
+}
+
+fn inverse(columns: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let n = columns.len();
+    let det = determinant(columns);
+    // inverse[c][r] = cofactor(r, c) / det
+    (0 .. n)
+        .map(|c| {
+            (0 .. n)
+                .map(|r| {
+                    let minor = columns
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != c)
+                        .map(|(_, column)| {
+                            column
+                                .iter()
+                                .enumerate()
+                                .filter(|&(i, _)| i != r)
+                                .map(|(_, &v)| v)
+                                .collect()
+                        })
+                        .collect::<Vec<Vec<f32>>>();
+                    let sign = if (r + c) % 2 == 0 { 1.0 } else { -1.0 };
+                    let minor_det = if n == 1 { 1.0 } else { determinant(&minor) };
+                    sign * minor_det / det
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn pack(values: &[f32], bits: u32, fun: &dyn Fn(f32) -> u32) -> u32 {
+    values
+        .iter()
+        .enumerate()
+        .fold(0, |packed, (i, &v)| packed | (fun(v) << (i as u32 * bits)))
+}
+
+fn unpack(packed: u32, count: u32, fun: &dyn Fn(u32) -> f32) -> Value {
+    let bits = 32 / count;
+    let mask = (1u32 << bits) - 1;
+    let values = (0 .. count)
+        .map(|i| fun((packed >> (i * bits)) & mask))
+        .collect::<Vec<_>>();
+    Value::from_floats(&values)
+}
+
+/// Evaluates an instruction that only depends on its operands.
+pub fn evaluate(instruction: u32, args: &[&Value]) -> Option<Value> {
+    let arg = |index: usize| args.get(index).cloned().unwrap_or(&Value::Undefined);
+    let value = match instruction {
+        1 => mapf(arg(0), &f32::round),
+        2 => mapf(arg(0), &round_even),
+        3 => mapf(arg(0), &f32::trunc),
+        4 => mapf(arg(0), &f32::abs),
+        5 => map1(arg(0), &|x| i(x).wrapping_abs() as u32),
+        6 => mapf(arg(0), &|x| {
+            if x > 0.0 {
+                1.0
+            } else if x < 0.0 {
+                -1.0
+            } else {
+                0.0
+            }
+        }),
+        7 => map1(arg(0), &|x| i(x).signum() as u32),
+        8 => mapf(arg(0), &f32::floor),
+        9 => mapf(arg(0), &f32::ceil),
+        10 => mapf(arg(0), &|x| x - x.floor()),
+        11 => mapf(arg(0), &f32::to_radians),
+        12 => mapf(arg(0), &f32::to_degrees),
+        13 => mapf(arg(0), &f32::sin),
+        14 => mapf(arg(0), &f32::cos),
+        15 => mapf(arg(0), &f32::tan),
+        16 => mapf(arg(0), &f32::asin),
+        17 => mapf(arg(0), &f32::acos),
+        18 => mapf(arg(0), &f32::atan),
+        19 => mapf(arg(0), &f32::sinh),
+        20 => mapf(arg(0), &f32::cosh),
+        21 => mapf(arg(0), &f32::tanh),
+        22 => mapf(arg(0), &f32::asinh),
+        23 => mapf(arg(0), &f32::acosh),
+        24 => mapf(arg(0), &f32::atanh),
+        25 => mapf2(arg(0), arg(1), &f32::atan2),
+        26 => mapf2(arg(0), arg(1), &f32::powf),
+        27 => mapf(arg(0), &f32::exp),
+        28 => mapf(arg(0), &f32::ln),
+        29 => mapf(arg(0), &f32::exp2),
+        30 => mapf(arg(0), &f32::log2),
+        31 => mapf(arg(0), &f32::sqrt),
+        32 => mapf(arg(0), &|x| 1.0 / x.sqrt()),
+        33 => {
+            let columns = arg(0).components().iter().map(floats).collect::<Vec<_>>();
+            Value::Scalar(b(determinant(&columns)))
+        }
+        34 => {
+            let columns = arg(0).components().iter().map(floats).collect::<Vec<_>>();
+            Value::Composite(
+                inverse(&columns)
+                    .iter()
+                    .map(|column| Value::from_floats(column))
+                    .collect(),
+            )
+        }
+        36 => Value::Composite(vec![mapf(arg(0), &f32::fract), mapf(arg(0), &f32::trunc)]),
+        37 | 79 => mapf2(arg(0), arg(1), &f32::min),
+        38 => map2(arg(0), arg(1), &u32::min),
+        39 => map2(arg(0), arg(1), &|x, y| i(x).min(i(y)) as u32),
+        40 | 80 => mapf2(arg(0), arg(1), &f32::max),
+        41 => map2(arg(0), arg(1), &u32::max),
+        42 => map2(arg(0), arg(1), &|x, y| i(x).max(i(y)) as u32),
+        43 | 81 => mapf3(arg(0), arg(1), arg(2), &|x, lo, hi| x.max(lo).min(hi)),
+        44 => map3(arg(0), arg(1), arg(2), &|x, lo, hi| x.max(lo).min(hi)),
+        45 => map3(arg(0), arg(1), arg(2), &|x, lo, hi| {
+            i(x).max(i(lo)).min(i(hi)) as u32
+        }),
+        46 => mapf3(arg(0), arg(1), arg(2), &|x, y, a| x * (1.0 - a) + y * a),
+        48 => mapf2(arg(0), arg(1), &|edge, x| if x < edge { 0.0 } else { 1.0 }),
+        49 => mapf3(arg(0), arg(1), arg(2), &|e0, e1, x| {
+            let t = ((x - e0) / (e1 - e0)).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        }),
+        50 => mapf3(arg(0), arg(1), arg(2), &|a, b, c| a.mul_add(b, c)),
+        52 => Value::Composite(vec![
+            mapf(arg(0), &|x| frexp(x).0),
+            map1(arg(0), &|x| frexp(f(x)).1 as u32),
+        ]),
+        53 => map2(arg(0), arg(1), &|x, e| b(f(x) * 2f32.powi(i(e)))),
+        54 => Value::Scalar(pack(&floats(arg(0)), 8, &|v| {
+            ((v.clamp(-1.0, 1.0) * 127.0).round() as i32 as u32) & 0xFF
+        })),
+        55 => Value::Scalar(pack(&floats(arg(0)), 8, &|v| {
+            (v.clamp(0.0, 1.0) * 255.0).round() as u32
+        })),
+        56 => Value::Scalar(pack(&floats(arg(0)), 16, &|v| {
+            ((v.clamp(-1.0, 1.0) * 32767.0).round() as i32 as u32) & 0xFFFF
+        })),
+        57 => Value::Scalar(pack(&floats(arg(0)), 16, &|v| {
+            (v.clamp(0.0, 1.0) * 65535.0).round() as u32
+        })),
+        58 => Value::Scalar(pack(&floats(arg(0)), 16, &|v| conv::f32_to_f16(v) as u32)),
+        60 => unpack(arg(0).scalar(), 2, &|v| {
+            (v as u16 as i16 as f32 / 32767.0).max(-1.0)
+        }),
+        61 => unpack(arg(0).scalar(), 2, &|v| v as f32 / 65535.0),
+        62 => unpack(arg(0).scalar(), 2, &|v| conv::f16_to_f32(v as u16)),
+        63 => unpack(arg(0).scalar(), 4, &|v| {
+            (v as u8 as i8 as f32 / 127.0).max(-1.0)
+        }),
+        64 => unpack(arg(0).scalar(), 4, &|v| v as f32 / 255.0),
+        66 => Value::Scalar(b(length(arg(0)))),
+        67 => {
+            let difference = mapf2(arg(0), arg(1), &|x, y| x - y);
+            Value::Scalar(b(length(&difference)))
+        }
+        68 => {
+            let (x, y) = (floats(arg(0)), floats(arg(1)));
+            if x.len() < 3 || y.len() < 3 {
+                return None;
+            }
+            Value::from_floats(&[
+                x[1] * y[2] - y[1] * x[2],
+                x[2] * y[0] - y[2] * x[0],
+                x[0] * y[1] - y[0] * x[1],
+            ])
+        }
+        69 => {
+            let length = length(arg(0));
+            mapf(arg(0), &|x| x / length)
+        }
+        70 => {
+            let (n, incident, reference) = (arg(0), arg(1), arg(2));
+            if dot(reference, incident) < 0.0 {
+                n.clone()
+            } else {
+                mapf(n, &|x| -x)
+            }
+        }
+        71 => {
+            let (incident, normal) = (arg(0), arg(1));
+            let factor = 2.0 * dot(normal, incident);
+            mapf2(incident, normal, &|i, n| i - factor * n)
+        }
+        72 => {
+            let (incident, normal, eta) = (arg(0), arg(1), arg(2).float());
+            let d = dot(normal, incident);
+            let k = 1.0 - eta * eta * (1.0 - d * d);
+            if k < 0.0 {
+                mapf(incident, &|_| 0.0)
+            } else {
+                let factor = eta * d + k.sqrt();
+                mapf2(incident, normal, &|i, n| eta * i - factor * n)
+            }
+        }
+        73 => map1(arg(0), &|x| {
+            if x == 0 {
+                !0
+            } else {
+                x.trailing_zeros()
+            }
+        }),
+        74 => map1(arg(0), &|x| {
+            let x = if i(x) < 0 { !x } else { x };
+            if x == 0 {
+                !0
+            } else {
+                31 - x.leading_zeros()
+            }
+        }),
+        75 => map1(arg(0), &|x| {
+            if x == 0 {
+                !0
+            } else {
+                31 - x.leading_zeros()
+            }
+        }),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Splits a float into a mantissa in `[0.5, 1)` and an exponent.
+pub fn frexp(x: f32) -> (f32, i32) {
+    if x == 0.0 || !x.is_finite() {
+        return (x, 0);
+    }
+    let exponent = x.abs().log2().floor() as i32 + 1;
+    let mantissa = x / 2f32.powi(exponent);
+    // Correct rounding errors of the logarithm.
+    if mantissa.abs() >= 1.0 {
+        (mantissa / 2.0, exponent + 1)
+    } else if mantissa.abs() < 0.5 {
+        (mantissa * 2.0, exponent - 1)
+    } else {
+        (mantissa, exponent)
+    }
+}
+
+/// Returns true if the instruction is implemented.
+pub fn is_supported(instruction: u32) -> bool {
+    matches!(instruction, 1 ..= 46 | 48 ..= 58 | 60 ..= 64 | 66 ..= 81)
+}
@@ -0,0 +1,971 @@
+//! Execution of the function bodies.
+
+use super::{
+    glsl,
+    ops::{self, extract, insert},
+    Function,
+    Id,
+    Instruction,
+    Layout,
+    Pointer,
+    Program,
+    Resource,
+    Root,
+    Type,
+    Value,
+};
+use crate::conv::Texel;
+use spirv_headers as spirv;
+
+/// State of an invocation after it stopped running.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Finished,
+    /// The invocation waits for the rest of its workgroup.
+    Barrier,
+    /// The fragment was discarded.
+    Killed,
+}
+
+/// Level of detail selection of an image sampling.
+#[derive(Clone, Copy, Debug)]
+pub enum Lod {
+    Level(f32),
+    /// Implicit level of detail, with a bias.
+    Bias(f32),
+    /// Explicit derivatives of the coordinates.
+    Gradient([f32; 3], [f32; 3]),
+}
+
+/// Access to the descriptors bound to an invocation.
+pub trait Resources {
+    /// Returns the memory range of a buffer descriptor.
+    fn buffer(&self, resource: Resource) -> Option<(*mut u8, usize)>;
+    fn push_constants(&self) -> &[u8];
+    /// Samples an image view with a sampler. The coordinates include the array layer.
+    fn sample(
+        &self,
+        image: Resource,
+        sampler: Resource,
+        coords: [f32; 4],
+        lod: Lod,
+        reference: Option<f32>,
+        offset: [i32; 3],
+    ) -> Texel;
+    /// Reads a single texel of an image view.
+    fn fetch(&self, image: Resource, coords: [i32; 4], level: u32) -> Texel;
+    fn write(&self, image: Resource, coords: [i32; 4], texel: Texel);
+    /// Returns the size of a level of an image view, followed by its layer count.
+    fn query_size(&self, image: Resource, level: u32) -> [u32; 4];
+    fn query_levels(&self, image: Resource) -> u32;
+}
+
+/// Returns true if the instruction may appear in a function body.
+pub fn is_supported(op: spirv::Op) -> bool {
+    use spirv::Op;
+    match op {
+        Op::Label
+        | Op::Branch
+        | Op::BranchConditional
+        | Op::Switch
+        | Op::Phi
+        | Op::Return
+        | Op::ReturnValue
+        | Op::FunctionCall
+        | Op::Kill
+        | Op::Unreachable
+        | Op::LoopMerge
+        | Op::SelectionMerge
+        | Op::ControlBarrier
+        | Op::MemoryBarrier
+        | Op::Variable
+        | Op::Undef
+        | Op::Load
+        | Op::Store
+        | Op::CopyMemory
+        | Op::AccessChain
+        | Op::InBoundsAccessChain
+        | Op::ArrayLength
+        | Op::AtomicLoad
+        | Op::AtomicStore
+        | Op::AtomicExchange
+        | Op::AtomicCompareExchange
+        | Op::AtomicIIncrement
+        | Op::AtomicIDecrement
+        | Op::AtomicIAdd
+        | Op::AtomicISub
+        | Op::AtomicSMin
+        | Op::AtomicUMin
+        | Op::AtomicSMax
+        | Op::AtomicUMax
+        | Op::AtomicAnd
+        | Op::AtomicOr
+        | Op::AtomicXor
+        | Op::ExtInst
+        | Op::SampledImage
+        | Op::Image
+        | Op::ImageSampleImplicitLod
+        | Op::ImageSampleExplicitLod
+        | Op::ImageSampleDrefImplicitLod
+        | Op::ImageSampleDrefExplicitLod
+        | Op::ImageSampleProjImplicitLod
+        | Op::ImageSampleProjExplicitLod
+        | Op::ImageSampleProjDrefImplicitLod
+        | Op::ImageSampleProjDrefExplicitLod
+        | Op::ImageFetch
+        | Op::ImageRead
+        | Op::ImageWrite
+        | Op::ImageQuerySize
+        | Op::ImageQuerySizeLod
+        | Op::ImageQueryLevels
+        | Op::ImageQuerySamples => true,
+        op => ops::is_pure(op),
+    }
+}
+
+/// Returns true if the `GLSL.std.450` instruction is supported.
+pub fn is_supported_glsl(instruction: u32) -> bool {
+    match instruction {
+        glsl::MODF
+        | glsl::FREXP
+        | glsl::INTERPOLATE_AT_CENTROID
+        | glsl::INTERPOLATE_AT_SAMPLE
+        | glsl::INTERPOLATE_AT_OFFSET => true,
+        other => glsl::is_supported(other),
+    }
+}
+
+fn read_word(bytes: &[u8], offset: u32) -> u32 {
+    let offset = offset as usize;
+    bytes
+        .get(offset .. offset.saturating_add(4))
+        .map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn write_word(bytes: &mut [u8], offset: u32, word: u32) {
+    let offset = offset as usize;
+    if let Some(b) = bytes.get_mut(offset .. offset.saturating_add(4)) {
+        b.copy_from_slice(&word.to_le_bytes());
+    }
+}
+
+/// Stores a scalar into a value, growing its composites as needed.
+fn place(target: &mut Value, path: &[u32], scalar: u32) {
+    match path.split_first() {
+        None => *target = Value::Scalar(scalar),
+        Some((&index, rest)) => {
+            if let Value::Undefined = *target {
+                *target = Value::Composite(Vec::new());
+            }
+            if let Value::Composite(ref mut components) = *target {
+                if components.len() <= index as usize {
+                    components.resize(index as usize + 1, Value::Undefined);
+                }
+                place(&mut components[index as usize], rest, scalar);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ImageOperands {
+    bias: Option<f32>,
+    lod: Option<u32>,
+    grad: Option<([f32; 3], [f32; 3])>,
+    offset: [i32; 3],
+}
+
+struct Frame<'p> {
+    function: &'p Function,
+    pc: usize,
+    label: Id,
+    prev_label: Id,
+    /// Id receiving the return value in the calling function.
+    result: Option<Id>,
+}
+
+/// Execution state of a program.
+pub struct Invocation<'p> {
+    program: &'p Program,
+    values: Vec<Value>,
+    storage: Vec<Value>,
+    frames: Vec<Frame<'p>>,
+    frag_coord: [f32; 4],
+}
+
+impl<'p> Invocation<'p> {
+    pub fn new(program: &'p Program) -> Self {
+        let mut invocation = Invocation {
+            program,
+            values: vec![Value::Undefined; program.module.bound],
+            storage: Vec::new(),
+            frames: Vec::new(),
+            frag_coord: [0.0; 4],
+        };
+        invocation.reset();
+        invocation
+    }
+
+    /// Restores the initial state, so the invocation can run again.
+    pub fn reset(&mut self) {
+        self.storage.clone_from(&self.program.storage);
+        self.frames.clear();
+        if let Some(function) = self.program.module.functions.get(&self.program.function) {
+            self.frames.push(Frame {
+                function,
+                pc: 0,
+                label: 0,
+                prev_label: 0,
+                result: None,
+            });
+        }
+    }
+
+    pub fn program(&self) -> &'p Program {
+        self.program
+    }
+
+    /// Sets the components of an input location.
+    pub fn set_input(&mut self, location: u32, texel: Texel) {
+        for slot in self
+            .program
+            .inputs
+            .iter()
+            .filter(|s| s.location == location)
+        {
+            let value = if slot.components == 1 {
+                Value::Scalar(texel[0])
+            } else {
+                Value::from_scalars(&texel[.. slot.components.min(4) as usize])
+            };
+            insert(&mut self.storage[slot.var as usize], &slot.path, value);
+        }
+    }
+
+    /// Returns the components written to an output location.
+    pub fn output(&self, location: u32, index: u32) -> Option<Texel> {
+        self.program
+            .outputs
+            .iter()
+            .find(|s| s.location == location && s.index == index)
+            .and_then(|slot| extract(&self.storage[slot.var as usize], &slot.path))
+            .map(Value::to_texel)
+    }
+
+    pub fn set_builtin(&mut self, builtin: spirv::BuiltIn, value: Value) {
+        if builtin == spirv::BuiltIn::FragCoord {
+            for (c, component) in self.frag_coord.iter_mut().zip(value.components()) {
+                *c = component.float();
+            }
+        }
+        for slot in &self.program.builtins {
+            if slot.input && slot.builtin == builtin {
+                insert(
+                    &mut self.storage[slot.var as usize],
+                    &slot.path,
+                    value.clone(),
+                );
+            }
+        }
+    }
+
+    /// Returns the value written to an output built-in.
+    pub fn builtin(&self, builtin: spirv::BuiltIn) -> Option<&Value> {
+        self.program
+            .builtins
+            .iter()
+            .find(|slot| !slot.input && slot.builtin == builtin)
+            .and_then(|slot| extract(&self.storage[slot.var as usize], &slot.path))
+    }
+
+    /// Runs until the entry point returns, the fragment is discarded or a
+    /// control barrier is reached.
+    pub fn run(&mut self, shared: &mut [Value], resources: &dyn Resources) -> Status {
+        loop {
+            let inst = match self.frames.last_mut() {
+                Some(frame) => match frame.function.body.get(frame.pc) {
+                    Some(inst) => {
+                        frame.pc += 1;
+                        inst
+                    }
+                    None => return Status::Finished,
+                },
+                None => return Status::Finished,
+            };
+            if let Some(status) = self.step(inst, shared, resources) {
+                return status;
+            }
+        }
+    }
+
+    fn get(&self, id: Id) -> &Value {
+        match self.values[id as usize] {
+            Value::Undefined => &self.program.constants[id as usize],
+            ref value => value,
+        }
+    }
+
+    fn set(&mut self, id: Id, value: Value) {
+        self.values[id as usize] = value;
+    }
+
+    fn pointer(&self, id: Id) -> Option<Pointer> {
+        match *self.get(id) {
+            Value::Pointer(ref pointer) => Some(pointer.clone()),
+            _ => None,
+        }
+    }
+
+    fn jump(&mut self, label: Id) {
+        let frame = self.frames.last_mut().unwrap();
+        frame.pc = frame.function.labels.get(&label).cloned().unwrap_or(!0);
+    }
+
+    fn step(
+        &mut self,
+        inst: &'p Instruction,
+        shared: &mut [Value],
+        resources: &dyn Resources,
+    ) -> Option<Status> {
+        use spirv::Op;
+
+        let operands = &inst.operands[..];
+        let module = &*self.program.module;
+        match inst.op {
+            Op::Label => {
+                let frame = self.frames.last_mut().unwrap();
+                frame.prev_label = frame.label;
+                frame.label = operands[0];
+            }
+            Op::Branch => self.jump(operands[0]),
+            Op::BranchConditional => {
+                let target = if self.get(operands[0]).scalar() != 0 {
+                    operands[1]
+                } else {
+                    operands[2]
+                };
+                self.jump(target);
+            }
+            Op::Switch => {
+                let selector = self.get(operands[0]).scalar();
+                let target = operands[2 ..]
+                    .chunks(2)
+                    .find(|case| case[0] == selector)
+                    .map_or(operands[1], |case| case[1]);
+                self.jump(target);
+            }
+            Op::Phi => {
+                let prev_label = self.frames.last().unwrap().prev_label;
+                if let Some(pair) = operands[2 ..].chunks(2).find(|pair| pair[1] == prev_label) {
+                    let value = self.get(pair[0]).clone();
+                    self.set(operands[1], value);
+                }
+            }
+            Op::Return | Op::ReturnValue => {
+                let value = if inst.op == Op::ReturnValue {
+                    Some(self.get(operands[0]).clone())
+                } else {
+                    None
+                };
+                let frame = self.frames.pop().unwrap();
+                if self.frames.is_empty() {
+                    return Some(Status::Finished);
+                }
+                if let (Some(result), Some(value)) = (frame.result, value) {
+                    self.set(result, value);
+                }
+            }
+            Op::FunctionCall => {
+                let function = &module.functions[&operands[2]];
+                for (&param, &arg) in function.params.iter().zip(&operands[3 ..]) {
+                    let value = self.get(arg).clone();
+                    self.set(param, value);
+                }
+                self.frames.push(Frame {
+                    function,
+                    pc: 0,
+                    label: 0,
+                    prev_label: 0,
+                    result: Some(operands[1]),
+                });
+            }
+            Op::Kill => return Some(Status::Killed),
+            Op::Unreachable => return Some(Status::Finished),
+            Op::ControlBarrier => return Some(Status::Barrier),
+            Op::LoopMerge | Op::SelectionMerge | Op::MemoryBarrier => {}
+            Op::Variable => {
+                let (ty, id) = (operands[0], operands[1]);
+                let pointee = match *module.ty(ty) {
+                    Type::Pointer { pointee, .. } => pointee,
+                    _ => 0,
+                };
+                self.storage[id as usize] = match operands.get(3) {
+                    Some(&init) => self.get(init).clone(),
+                    None => self.program.zero(pointee),
+                };
+                self.set(
+                    id,
+                    Value::Pointer(Pointer {
+                        root: Root::Private(id),
+                        path: Vec::new(),
+                        offset: 0,
+                        layout: Layout::default(),
+                        ty: pointee,
+                    }),
+                );
+            }
+            Op::Undef => {
+                let value = self.program.zero(operands[0]);
+                self.set(operands[1], value);
+            }
+            Op::Load | Op::AtomicLoad => {
+                if let Some(pointer) = self.pointer(operands[2]) {
+                    let value = self.load(&pointer, shared, resources);
+                    self.set(operands[1], value);
+                }
+            }
+            Op::Store | Op::AtomicStore => {
+                let value_id = if inst.op == Op::Store {
+                    operands[1]
+                } else {
+                    operands[3]
+                };
+                if let Some(pointer) = self.pointer(operands[0]) {
+                    let value = self.get(value_id).clone();
+                    self.store(&pointer, &value, shared, resources);
+                }
+            }
+            Op::CopyMemory => {
+                if let (Some(target), Some(source)) =
+                    (self.pointer(operands[0]), self.pointer(operands[1]))
+                {
+                    let value = self.load(&source, shared, resources);
+                    self.store(&target, &value, shared, resources);
+                }
+            }
+            Op::AccessChain | Op::InBoundsAccessChain => {
+                if let Some(base) = self.pointer(operands[2]) {
+                    let pointer = self.access_chain(base, &operands[3 ..]);
+                    self.set(operands[1], Value::Pointer(pointer));
+                }
+            }
+            Op::ArrayLength => {
+                let length = match self.pointer(operands[2]) {
+                    Some(Pointer {
+                        root: Root::Buffer(resource),
+                        offset,
+                        ty,
+                        ..
+                    }) => {
+                        let member = operands[3];
+                        let member_offset = module
+                            .member_decorations(ty, member)
+                            .and_then(|d| d.offset)
+                            .unwrap_or(0);
+                        let array = match *module.ty(ty) {
+                            Type::Struct { ref members } => members[member as usize],
+                            _ => 0,
+                        };
+                        let size = resources.buffer(resource).map_or(0, |(_, size)| size);
+                        (size as u32).saturating_sub(offset + member_offset)
+                            / self.array_stride(array)
+                    }
+                    _ => 0,
+                };
+                self.set(operands[1], Value::Scalar(length));
+            }
+            Op::AtomicExchange
+            | Op::AtomicCompareExchange
+            | Op::AtomicIIncrement
+            | Op::AtomicIDecrement
+            | Op::AtomicIAdd
+            | Op::AtomicISub
+            | Op::AtomicSMin
+            | Op::AtomicUMin
+            | Op::AtomicSMax
+            | Op::AtomicUMax
+            | Op::AtomicAnd
+            | Op::AtomicOr
+            | Op::AtomicXor => {
+                let pointer = self.pointer(operands[2])?;
+                let old = self.load(&pointer, shared, resources).scalar();
+                let operand =
+                    |index: usize| operands.get(index).map_or(0, |&id| self.get(id).scalar());
+                let new = match inst.op {
+                    Op::AtomicExchange => operand(5),
+                    Op::AtomicCompareExchange => {
+                        if old == operand(7) {
+                            operand(6)
+                        } else {
+                            old
+                        }
+                    }
+                    Op::AtomicIIncrement => old.wrapping_add(1),
+                    Op::AtomicIDecrement => old.wrapping_sub(1),
+                    Op::AtomicIAdd => old.wrapping_add(operand(5)),
+                    Op::AtomicISub => old.wrapping_sub(operand(5)),
+                    Op::AtomicSMin => (old as i32).min(operand(5) as i32) as u32,
+                    Op::AtomicUMin => old.min(operand(5)),
+                    Op::AtomicSMax => (old as i32).max(operand(5) as i32) as u32,
+                    Op::AtomicUMax => old.max(operand(5)),
+                    Op::AtomicAnd => old & operand(5),
+                    Op::AtomicOr => old | operand(5),
+                    _ => old ^ operand(5),
+                };
+                self.store(&pointer, &Value::Scalar(new), shared, resources);
+                self.set(operands[1], Value::Scalar(old));
+            }
+            Op::ExtInst => self.ext_inst(operands, shared, resources),
+            Op::SampledImage => {
+                let value = match (self.get(operands[2]), self.get(operands[3])) {
+                    (&Value::Image(image), &Value::Sampler(sampler))
+                    | (&Value::SampledImage(image, _), &Value::Sampler(sampler)) => {
+                        Value::SampledImage(image, sampler)
+                    }
+                    _ => Value::Undefined,
+                };
+                self.set(operands[1], value);
+            }
+            Op::Image => {
+                let value = match *self.get(operands[2]) {
+                    Value::SampledImage(image, _) => Value::Image(image),
+                    ref other => other.clone(),
+                };
+                self.set(operands[1], value);
+            }
+            Op::ImageSampleImplicitLod
+            | Op::ImageSampleExplicitLod
+            | Op::ImageSampleDrefImplicitLod
+            | Op::ImageSampleDrefExplicitLod
+            | Op::ImageSampleProjImplicitLod
+            | Op::ImageSampleProjExplicitLod
+            | Op::ImageSampleProjDrefImplicitLod
+            | Op::ImageSampleProjDrefExplicitLod => self.sample(inst.op, operands, resources),
+            Op::ImageFetch | Op::ImageRead => {
+                let image = self.image(operands[2])?;
+                let image_operands = self.image_operands(&operands[4 ..]);
+                let mut coords = [0i32; 4];
+                for (c, component) in coords.iter_mut().zip(self.get(operands[3]).components()) {
+                    *c = component.scalar() as i32;
+                }
+                if self
+                    .program
+                    .subpass_inputs
+                    .contains(&(image.set, image.binding))
+                {
+                    coords[0] += self.frag_coord[0] as i32;
+                    coords[1] += self.frag_coord[1] as i32;
+                }
+                for (c, offset) in coords.iter_mut().zip(&image_operands.offset) {
+                    *c += offset;
+                }
+                let texel = resources.fetch(image, coords, image_operands.lod.unwrap_or(0));
+                let value = self.shape(operands[0], texel);
+                self.set(operands[1], value);
+            }
+            Op::ImageWrite => {
+                if let Some(image) = self.image(operands[0]) {
+                    let mut coords = [0i32; 4];
+                    for (c, component) in coords.iter_mut().zip(self.get(operands[1]).components())
+                    {
+                        *c = component.scalar() as i32;
+                    }
+                    resources.write(image, coords, self.get(operands[2]).to_texel());
+                }
+            }
+            Op::ImageQuerySize | Op::ImageQuerySizeLod | Op::ImageQueryLevels => {
+                let image = self.image(operands[2])?;
+                let value = match inst.op {
+                    Op::ImageQueryLevels => Value::Scalar(resources.query_levels(image)),
+                    _ => {
+                        let level = operands.get(3).map_or(0, |&id| self.get(id).scalar());
+                        self.shape(operands[0], resources.query_size(image, level))
+                    }
+                };
+                self.set(operands[1], value);
+            }
+            Op::ImageQuerySamples => self.set(operands[1], Value::Scalar(1)),
+            op => {
+                let value =
+                    ops::evaluate(module, op, operands[0], &operands[2 ..], &|id| self.get(id))
+                        .unwrap_or(Value::Undefined);
+                self.set(operands[1], value);
+            }
+        }
+        None
+    }
+
+    fn ext_inst(&mut self, operands: &[u32], shared: &mut [Value], resources: &dyn Resources) {
+        let (id, instruction) = (operands[1], operands[3]);
+        let args = &operands[4 ..];
+        match instruction {
+            glsl::MODF | glsl::FREXP => {
+                let x = self.get(args[0]).clone();
+                let parts = if instruction == glsl::MODF {
+                    glsl::evaluate(36, &[&x])
+                } else {
+                    glsl::evaluate(52, &[&x])
+                };
+                if let Some(Value::Composite(mut parts)) = parts {
+                    if let Some(pointer) = self.pointer(args[1]) {
+                        self.store(&pointer, &parts[1], shared, resources);
+                    }
+                    self.set(id, parts.swap_remove(0));
+                }
+            }
+            glsl::INTERPOLATE_AT_CENTROID
+            | glsl::INTERPOLATE_AT_SAMPLE
+            | glsl::INTERPOLATE_AT_OFFSET => {
+                // Fragments are only shaded at their center.
+                if let Some(pointer) = self.pointer(args[0]) {
+                    let value = self.load(&pointer, shared, resources);
+                    self.set(id, value);
+                }
+            }
+            _ => {
+                let values = args.iter().map(|&arg| self.get(arg)).collect::<Vec<_>>();
+                let value = glsl::evaluate(instruction, &values).unwrap_or(Value::Undefined);
+                self.set(id, value);
+            }
+        }
+    }
+
+    fn image(&self, id: Id) -> Option<Resource> {
+        match *self.get(id) {
+            Value::Image(image) | Value::SampledImage(image, _) => Some(image),
+            _ => None,
+        }
+    }
+
+    fn image_operands(&self, operands: &[u32]) -> ImageOperands {
+        let mut result = ImageOperands::default();
+        let mask = match operands.first() {
+            Some(&mask) => mask,
+            None => return result,
+        };
+        let mut args = operands[1 ..].iter().map(|&id| self.get(id));
+        let floats = |value: Option<&Value>| {
+            let mut array = [0.0; 3];
+            if let Some(value) = value {
+                for (a, component) in array.iter_mut().zip(value.components()) {
+                    *a = component.float();
+                }
+            }
+            array
+        };
+        for bit in 0 .. 8 {
+            match mask & (1 << bit) {
+                0 => {}
+                0x1 => result.bias = args.next().map(Value::float),
+                0x2 => result.lod = args.next().map(Value::scalar),
+                0x4 => {
+                    let dx = floats(args.next());
+                    let dy = floats(args.next());
+                    result.grad = Some((dx, dy));
+                }
+                0x8 | 0x10 => {
+                    if let Some(offset) = args.next() {
+                        for (o, component) in result.offset.iter_mut().zip(offset.components()) {
+                            *o = component.scalar() as i32;
+                        }
+                    }
+                }
+                _ => {
+                    args.next();
+                }
+            }
+        }
+        result
+    }
+
+    fn sample(&mut self, op: spirv::Op, operands: &[u32], resources: &dyn Resources) {
+        use spirv::Op;
+
+        let (image, sampler) = match *self.get(operands[2]) {
+            Value::SampledImage(image, sampler) => (image, sampler),
+            _ => return,
+        };
+        let (dref, projected) = match op {
+            Op::ImageSampleDrefImplicitLod | Op::ImageSampleDrefExplicitLod => (true, false),
+            Op::ImageSampleProjImplicitLod | Op::ImageSampleProjExplicitLod => (false, true),
+            Op::ImageSampleProjDrefImplicitLod | Op::ImageSampleProjDrefExplicitLod => (true, true),
+            _ => (false, false),
+        };
+        let components = self.get(operands[3]).components();
+        let mut coords = [0.0; 4];
+        for (c, component) in coords.iter_mut().zip(components) {
+            *c = component.float();
+        }
+        let mut reference = if dref {
+            Some(self.get(operands[4]).float())
+        } else {
+            None
+        };
+        if projected {
+            let q = components.last().map_or(1.0, Value::float);
+            for c in coords.iter_mut().take(components.len() - 1) {
+                *c /= q;
+            }
+            reference = reference.map(|r| r / q);
+        }
+        let image_operands = self.image_operands(&operands[if dref { 5 } else { 4 } ..]);
+        let lod = match (image_operands.lod, image_operands.grad) {
+            (Some(lod), _) => Lod::Level(f32::from_bits(lod)),
+            (None, Some((dx, dy))) => Lod::Gradient(dx, dy),
+            (None, None) => Lod::Bias(image_operands.bias.unwrap_or(0.0)),
+        };
+        let texel = resources.sample(
+            image,
+            sampler,
+            coords,
+            lod,
+            reference,
+            image_operands.offset,
+        );
+        let value = if dref {
+            Value::Scalar(texel[0])
+        } else {
+            self.shape(operands[0], texel)
+        };
+        self.set(operands[1], value);
+    }
+
+    /// Converts a texel to a value of the given scalar or vector type.
+    fn shape(&self, ty: Id, texel: Texel) -> Value {
+        match *self.program.module.ty(ty) {
+            Type::Vector { count, .. } => Value::from_scalars(&texel[.. count.min(4) as usize]),
+            _ => Value::Scalar(texel[0]),
+        }
+    }
+
+    fn array_stride(&self, ty: Id) -> u32 {
+        self.program
+            .module
+            .decorations(ty)
+            .and_then(|d| d.array_stride)
+            .unwrap_or(16)
+            .max(1)
+    }
+
+    fn access_chain(&self, mut pointer: Pointer, indices: &[Id]) -> Pointer {
+        let module = &self.program.module;
+        for &index in indices {
+            let index = self.get(index).scalar();
+            if let Root::BufferArray(resource) = pointer.root {
+                pointer.root = Root::Buffer(Resource {
+                    element: resource.element + index,
+                    ..resource
+                });
+                pointer.ty = match *module.ty(pointer.ty) {
+                    Type::Array { element, .. } | Type::RuntimeArray { element } => element,
+                    _ => pointer.ty,
+                };
+                continue;
+            }
+            match *module.ty(pointer.ty) {
+                Type::Struct { ref members } => {
+                    let decorations = module.member_decorations(pointer.ty, index);
+                    let offset = decorations.and_then(|d| d.offset).unwrap_or(0);
+                    pointer.offset = pointer.offset.wrapping_add(offset);
+                    pointer.layout = Layout {
+                        matrix_stride: decorations
+                            .and_then(|d| d.matrix_stride)
+                            .unwrap_or(pointer.layout.matrix_stride),
+                        row_major: decorations.map(|d| d.row_major).unwrap_or(false),
+                        component_stride: 4,
+                    };
+                    pointer.ty = members.get(index as usize).cloned().unwrap_or(0);
+                }
+                Type::Array { element, .. } | Type::RuntimeArray { element } => {
+                    let stride = self.array_stride(pointer.ty);
+                    pointer.offset = pointer.offset.wrapping_add(index.wrapping_mul(stride));
+                    pointer.ty = element;
+                }
+                Type::Matrix { column, .. } => {
+                    let layout = &mut pointer.layout;
+                    let (column_stride, component_stride) = if layout.row_major {
+                        (4, layout.matrix_stride)
+                    } else {
+                        (layout.matrix_stride, 4)
+                    };
+                    pointer.offset = pointer
+                        .offset
+                        .wrapping_add(index.wrapping_mul(column_stride));
+                    layout.component_stride = component_stride;
+                    pointer.ty = column;
+                }
+                Type::Vector { component, .. } => {
+                    let stride = pointer.layout.component_stride;
+                    pointer.offset = pointer.offset.wrapping_add(index.wrapping_mul(stride));
+                    pointer.ty = component;
+                }
+                _ => {}
+            }
+            pointer.path.push(index);
+        }
+        pointer
+    }
+
+    fn load(&self, pointer: &Pointer, shared: &[Value], resources: &dyn Resources) -> Value {
+        let value = match pointer.root {
+            Root::Private(id) => extract(&self.storage[id as usize], &pointer.path).cloned(),
+            Root::Shared(id) => shared
+                .get(id as usize)
+                .and_then(|value| extract(value, &pointer.path))
+                .cloned(),
+            Root::Buffer(resource) => resources.buffer(resource).map(|(data, size)| {
+                let bytes = unsafe { std::slice::from_raw_parts(data, size) };
+                self.read_memory(bytes, pointer.offset, pointer.layout, pointer.ty)
+            }),
+            Root::PushConstants => Some(self.read_memory(
+                resources.push_constants(),
+                pointer.offset,
+                pointer.layout,
+                pointer.ty,
+            )),
+            Root::BufferArray(_) => None,
+        };
+        value.unwrap_or_else(|| self.program.zero(pointer.ty))
+    }
+
+    fn store(
+        &mut self,
+        pointer: &Pointer,
+        value: &Value,
+        shared: &mut [Value],
+        resources: &dyn Resources,
+    ) {
+        match pointer.root {
+            Root::Private(id) => {
+                insert(&mut self.storage[id as usize], &pointer.path, value.clone())
+            }
+            Root::Shared(id) => {
+                if let Some(target) = shared.get_mut(id as usize) {
+                    insert(target, &pointer.path, value.clone());
+                }
+            }
+            Root::Buffer(resource) => {
+                if let Some((data, size)) = resources.buffer(resource) {
+                    let bytes = unsafe { std::slice::from_raw_parts_mut(data, size) };
+                    self.write_memory(bytes, pointer.offset, pointer.layout, pointer.ty, value);
+                }
+            }
+            Root::PushConstants | Root::BufferArray(_) => {}
+        }
+    }
+
+    /// Visits the scalars of a value of the given type, laid out in memory.
+    fn visit_memory(
+        &self,
+        size: usize,
+        offset: u32,
+        layout: Layout,
+        ty: Id,
+        path: &mut Vec<u32>,
+        fun: &mut dyn FnMut(&[u32], u32),
+    ) {
+        let module = &self.program.module;
+        match *module.ty(ty) {
+            Type::Vector { component, count } => {
+                for i in 0 .. count {
+                    path.push(i);
+                    let offset = offset.wrapping_add(i * layout.component_stride);
+                    self.visit_memory(size, offset, layout, component, path, fun);
+                    path.pop();
+                }
+            }
+            Type::Matrix { column, count } => {
+                let (column_stride, component_stride) = if layout.row_major {
+                    (4, layout.matrix_stride)
+                } else {
+                    (layout.matrix_stride, 4)
+                };
+                let column_layout = Layout {
+                    component_stride,
+                    ..layout
+                };
+                for i in 0 .. count {
+                    path.push(i);
+                    let offset = offset.wrapping_add(i * column_stride);
+                    self.visit_memory(size, offset, column_layout, column, path, fun);
+                    path.pop();
+                }
+            }
+            Type::Array { element, length } => {
+                let stride = self.array_stride(ty);
+                let length = self.program.constants[length as usize].scalar();
+                let element_layout = Layout {
+                    component_stride: 4,
+                    ..layout
+                };
+                for i in 0 .. length {
+                    path.push(i);
+                    let offset = offset.wrapping_add(i.wrapping_mul(stride));
+                    self.visit_memory(size, offset, element_layout, element, path, fun);
+                    path.pop();
+                }
+            }
+            Type::RuntimeArray { element } => {
+                let stride = self.array_stride(ty);
+                let length = (size as u32).saturating_sub(offset) / stride;
+                let element_layout = Layout {
+                    component_stride: 4,
+                    ..layout
+                };
+                for i in 0 .. length {
+                    path.push(i);
+                    let offset = offset.wrapping_add(i * stride);
+                    self.visit_memory(size, offset, element_layout, element, path, fun);
+                    path.pop();
+                }
+            }
+            Type::Struct { ref members } => {
+                for (i, &member) in members.iter().enumerate() {
+                    let decorations = module.member_decorations(ty, i as u32);
+                    let member_offset = decorations.and_then(|d| d.offset).unwrap_or(0);
+                    let member_layout = Layout {
+                        matrix_stride: decorations
+                            .and_then(|d| d.matrix_stride)
+                            .unwrap_or(layout.matrix_stride),
+                        row_major: decorations.map(|d| d.row_major).unwrap_or(false),
+                        component_stride: 4,
+                    };
+                    path.push(i as u32);
+                    let offset = offset.wrapping_add(member_offset);
+                    self.visit_memory(size, offset, member_layout, member, path, fun);
+                    path.pop();
+                }
+            }
+            _ => fun(path, offset),
+        }
+    }
+
+    fn read_memory(&self, bytes: &[u8], offset: u32, layout: Layout, ty: Id) -> Value {
+        let mut value = Value::Undefined;
+        self.visit_memory(
+            bytes.len(),
+            offset,
+            layout,
+            ty,
+            &mut Vec::new(),
+            &mut |path, offset| place(&mut value, path, read_word(bytes, offset)),
+        );
+        value
+    }
+
+    fn write_memory(&self, bytes: &mut [u8], offset: u32, layout: Layout, ty: Id, value: &Value) {
+        let size = bytes.len();
+        self.visit_memory(
+            size,
+            offset,
+            layout,
+            ty,
+            &mut Vec::new(),
+            &mut |path, offset| {
+                if let Some(scalar) = extract(value, path) {
+                    write_word(bytes, offset, scalar.scalar());
+                }
+            },
+        );
+    }
+}
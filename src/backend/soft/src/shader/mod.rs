@@ -0,0 +1,929 @@
+//! SPIR-V shader parsing and execution.
+//!
+//! Shader modules are parsed once into types, constants, variables and functions.
+//! Pipelines specialize an entry point into a `Program`, which resolves the constants
+//! and describes the shader interface, and execute it with `Invocation`s.
+
+mod glsl;
+mod interp;
+mod ops;
+
+pub use self::interp::{Invocation, Lod, Resources, Status};
+
+use auxil::FastHashMap;
+use hal::pso;
+use num_traits::FromPrimitive;
+use spirv_headers as spirv;
+
+use std::sync::Arc;
+
+pub type Id = u32;
+
+/// Instruction of a function body or a constant declaration.
+#[derive(Clone, Debug)]
+pub struct Instruction {
+    pub op: spirv::Op,
+    pub operands: Vec<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Void,
+    Bool,
+    Int,
+    Float,
+    Vector { component: Id, count: u32 },
+    Matrix { column: Id, count: u32 },
+    Array { element: Id, length: Id },
+    RuntimeArray { element: Id },
+    Struct { members: Vec<Id> },
+    Pointer { pointee: Id },
+    Function,
+    Image { dim: spirv::Dim },
+    Sampler,
+    SampledImage,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Decorations {
+    pub builtin: Option<spirv::BuiltIn>,
+    pub location: Option<u32>,
+    pub index: Option<u32>,
+    pub set: Option<u32>,
+    pub binding: Option<u32>,
+    pub spec_id: Option<u32>,
+    pub offset: Option<u32>,
+    pub array_stride: Option<u32>,
+    pub matrix_stride: Option<u32>,
+    pub row_major: bool,
+    pub flat: bool,
+    pub no_perspective: bool,
+}
+
+impl Decorations {
+    fn apply(&mut self, decoration: spirv::Decoration, arguments: &[u32]) {
+        use spirv::Decoration as D;
+        let argument = arguments.first().cloned();
+        match decoration {
+            D::BuiltIn => self.builtin = argument.and_then(spirv::BuiltIn::from_u32),
+            D::Location => self.location = argument,
+            D::Index => self.index = argument,
+            D::DescriptorSet => self.set = argument,
+            D::Binding => self.binding = argument,
+            D::SpecId => self.spec_id = argument,
+            D::Offset => self.offset = argument,
+            D::ArrayStride => self.array_stride = argument,
+            D::MatrixStride => self.matrix_stride = argument,
+            D::RowMajor => self.row_major = true,
+            D::Flat => self.flat = true,
+            D::NoPerspective => self.no_perspective = true,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Function {
+    pub params: Vec<Id>,
+    pub body: Vec<Instruction>,
+    /// Position of each label in the body.
+    pub labels: FastHashMap<Id, usize>,
+}
+
+#[derive(Debug)]
+pub struct EntryPoint {
+    pub model: spirv::ExecutionModel,
+    pub name: String,
+    pub function: Id,
+    pub interface: Vec<Id>,
+    pub local_size: [u32; 3],
+    pub early_fragment_tests: bool,
+}
+
+#[derive(Debug)]
+pub struct Variable {
+    pub id: Id,
+    pub class: spirv::StorageClass,
+    pub pointee: Id,
+    pub initializer: Option<Id>,
+}
+
+/// Parsed SPIR-V module.
+#[derive(Debug)]
+pub struct Module {
+    pub bound: usize,
+    pub types: Vec<Option<Type>>,
+    /// Constant declarations, in order.
+    pub constants: Vec<Instruction>,
+    pub variables: Vec<Variable>,
+    pub functions: FastHashMap<Id, Function>,
+    pub entry_points: Vec<EntryPoint>,
+    pub decorations: FastHashMap<Id, Decorations>,
+    pub member_decorations: FastHashMap<(Id, u32), Decorations>,
+    pub glsl_std: Option<Id>,
+}
+
+fn parse_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        for &byte in &word.to_le_bytes() {
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), i + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+impl Module {
+    pub fn parse(words: &[u32]) -> Result<Self, String> {
+        use spirv::Op;
+
+        if words.len() < 5 || words[0] != spirv::MAGIC_NUMBER {
+            return Err("Invalid SPIR-V header".into());
+        }
+        let bound = words[3] as usize;
+        let mut module = Module {
+            bound,
+            types: vec![None; bound],
+            constants: Vec::new(),
+            variables: Vec::new(),
+            functions: FastHashMap::default(),
+            entry_points: Vec::new(),
+            decorations: FastHashMap::default(),
+            member_decorations: FastHashMap::default(),
+            glsl_std: None,
+        };
+
+        let mut function: Option<(Id, Function)> = None;
+        let mut position = 5;
+        while position < words.len() {
+            let count = (words[position] >> 16) as usize;
+            let opcode = words[position] & 0xFFFF;
+            if count == 0 || position + count > words.len() {
+                return Err("Truncated SPIR-V instruction".into());
+            }
+            let operands = &words[position + 1 .. position + count];
+            position += count;
+
+            let op = Op::from_u32(opcode).ok_or(format!("Unknown SPIR-V opcode {}", opcode))?;
+            let operand = |index: usize| {
+                operands
+                    .get(index)
+                    .cloned()
+                    .ok_or(format!("Missing operands of {:?}", op))
+            };
+            for &id in Self::result_id(op, operands).iter() {
+                if id as usize >= bound {
+                    return Err(format!("Id {} is out of bounds", id));
+                }
+            }
+
+            if let Some((_, ref mut body)) = function {
+                match op {
+                    Op::FunctionParameter => body.params.push(operand(1)?),
+                    Op::FunctionEnd => {
+                        let (id, body) = function.take().unwrap();
+                        module.functions.insert(id, body);
+                    }
+                    Op::Line | Op::NoLine | Op::Nop => {}
+                    _ if interp::is_supported(op) => {
+                        if op == Op::Label {
+                            body.labels.insert(operand(0)?, body.body.len());
+                        }
+                        if op == Op::ExtInst {
+                            if Some(operand(2)?) != module.glsl_std {
+                                return Err("Unsupported extended instruction set".into());
+                            }
+                            if !interp::is_supported_glsl(operand(3)?) {
+                                return Err(format!(
+                                    "Unsupported GLSL.std.450 instruction {}",
+                                    operands[3]
+                                ));
+                            }
+                        }
+                        body.body.push(Instruction {
+                            op,
+                            operands: operands.to_vec(),
+                        });
+                    }
+                    _ => return Err(format!("Unsupported SPIR-V instruction {:?}", op)),
+                }
+                continue;
+            }
+
+            match op {
+                Op::Nop
+                | Op::Capability
+                | Op::Extension
+                | Op::MemoryModel
+                | Op::Source
+                | Op::SourceContinued
+                | Op::SourceExtension
+                | Op::String
+                | Op::Name
+                | Op::MemberName
+                | Op::ModuleProcessed
+                | Op::Line
+                | Op::NoLine => {}
+                Op::ExtInstImport => {
+                    let (name, _) = parse_string(&operands[1 ..]);
+                    if name != "GLSL.std.450" {
+                        return Err(format!("Unsupported extended instruction set {}", name));
+                    }
+                    module.glsl_std = Some(operand(0)?);
+                }
+                Op::EntryPoint => {
+                    let model = spirv::ExecutionModel::from_u32(operand(0)?)
+                        .ok_or("Invalid execution model")?;
+                    let (name, length) = parse_string(&operands[2 ..]);
+                    module.entry_points.push(EntryPoint {
+                        model,
+                        name,
+                        function: operand(1)?,
+                        interface: operands[2 + length ..].to_vec(),
+                        local_size: [1; 3],
+                        early_fragment_tests: false,
+                    });
+                }
+                Op::ExecutionMode => {
+                    let function = operand(0)?;
+                    let mode = spirv::ExecutionMode::from_u32(operand(1)?);
+                    for entry in module
+                        .entry_points
+                        .iter_mut()
+                        .filter(|entry| entry.function == function)
+                    {
+                        match mode {
+                            Some(spirv::ExecutionMode::LocalSize) => {
+                                entry.local_size = [operand(2)?, operand(3)?, operand(4)?];
+                            }
+                            Some(spirv::ExecutionMode::EarlyFragmentTests) => {
+                                entry.early_fragment_tests = true
+                            }
+                            Some(spirv::ExecutionMode::OriginLowerLeft) => {
+                                return Err("Lower left origin is not supported".into())
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Op::Decorate => {
+                    if let Some(decoration) = spirv::Decoration::from_u32(operand(1)?) {
+                        module
+                            .decorations
+                            .entry(operand(0)?)
+                            .or_default()
+                            .apply(decoration, &operands[2 ..]);
+                    }
+                }
+                Op::MemberDecorate => {
+                    if let Some(decoration) = spirv::Decoration::from_u32(operand(2)?) {
+                        module
+                            .member_decorations
+                            .entry((operand(0)?, operand(1)?))
+                            .or_default()
+                            .apply(decoration, &operands[3 ..]);
+                    }
+                }
+                Op::TypeVoid => module.types[operand(0)? as usize] = Some(Type::Void),
+                Op::TypeBool => module.types[operand(0)? as usize] = Some(Type::Bool),
+                Op::TypeInt | Op::TypeFloat => {
+                    if operand(1)? != 32 {
+                        return Err(format!("Unsupported {}-bit type", operands[1]));
+                    }
+                    module.types[operand(0)? as usize] = Some(if op == Op::TypeInt {
+                        Type::Int
+                    } else {
+                        Type::Float
+                    });
+                }
+                Op::TypeVector => {
+                    module.types[operand(0)? as usize] = Some(Type::Vector {
+                        component: operand(1)?,
+                        count: operand(2)?,
+                    })
+                }
+                Op::TypeMatrix => {
+                    module.types[operand(0)? as usize] = Some(Type::Matrix {
+                        column: operand(1)?,
+                        count: operand(2)?,
+                    })
+                }
+                Op::TypeImage => {
+                    let dim = spirv::Dim::from_u32(operand(2)?).ok_or("Invalid image dimension")?;
+                    if operand(5)? != 0 {
+                        return Err("Multisampled images are not supported".into());
+                    }
+                    module.types[operand(0)? as usize] = Some(Type::Image { dim });
+                }
+                Op::TypeSampler => module.types[operand(0)? as usize] = Some(Type::Sampler),
+                Op::TypeSampledImage => {
+                    module.types[operand(0)? as usize] = Some(Type::SampledImage)
+                }
+                Op::TypeArray => {
+                    module.types[operand(0)? as usize] = Some(Type::Array {
+                        element: operand(1)?,
+                        length: operand(2)?,
+                    })
+                }
+                Op::TypeRuntimeArray => {
+                    module.types[operand(0)? as usize] = Some(Type::RuntimeArray {
+                        element: operand(1)?,
+                    })
+                }
+                Op::TypeStruct => {
+                    module.types[operand(0)? as usize] = Some(Type::Struct {
+                        members: operands[1 ..].to_vec(),
+                    })
+                }
+                Op::TypePointer => {
+                    module.types[operand(0)? as usize] = Some(Type::Pointer {
+                        pointee: operand(2)?,
+                    })
+                }
+                Op::TypeFunction => module.types[operand(0)? as usize] = Some(Type::Function),
+                Op::ConstantTrue
+                | Op::ConstantFalse
+                | Op::Constant
+                | Op::ConstantComposite
+                | Op::ConstantNull
+                | Op::SpecConstantTrue
+                | Op::SpecConstantFalse
+                | Op::SpecConstant
+                | Op::SpecConstantComposite
+                | Op::Undef => module.constants.push(Instruction {
+                    op,
+                    operands: operands.to_vec(),
+                }),
+                Op::SpecConstantOp => {
+                    let inner = Op::from_u32(operand(2)?);
+                    if !inner.map(ops::is_pure).unwrap_or(false) {
+                        return Err(format!(
+                            "Unsupported specialization constant op {:?}",
+                            inner
+                        ));
+                    }
+                    module.constants.push(Instruction {
+                        op,
+                        operands: operands.to_vec(),
+                    });
+                }
+                Op::Variable => {
+                    let class = spirv::StorageClass::from_u32(operand(2)?)
+                        .ok_or("Invalid storage class")?;
+                    let pointee = match module.types[operand(0)? as usize] {
+                        Some(Type::Pointer { pointee, .. }) => pointee,
+                        _ => return Err("Variable type is not a pointer".into()),
+                    };
+                    module.variables.push(Variable {
+                        id: operand(1)?,
+                        class,
+                        pointee,
+                        initializer: operands.get(3).cloned(),
+                    });
+                }
+                Op::Function => {
+                    function = Some((operand(1)?, Function::default()));
+                }
+                _ => return Err(format!("Unsupported SPIR-V instruction {:?}", op)),
+            }
+        }
+
+        if function.is_some() {
+            return Err("Unterminated function".into());
+        }
+        Ok(module)
+    }
+
+    /// Returns the result id of an instruction, if it has one.
+    fn result_id(op: spirv::Op, operands: &[u32]) -> Option<Id> {
+        use spirv::Op;
+        match op {
+            Op::Label
+            | Op::ExtInstImport
+            | Op::TypeVoid
+            | Op::TypeBool
+            | Op::TypeInt
+            | Op::TypeFloat
+            | Op::TypeVector
+            | Op::TypeMatrix
+            | Op::TypeImage
+            | Op::TypeSampler
+            | Op::TypeSampledImage
+            | Op::TypeArray
+            | Op::TypeRuntimeArray
+            | Op::TypeStruct
+            | Op::TypePointer
+            | Op::TypeFunction => operands.first().cloned(),
+            Op::Decorate
+            | Op::MemberDecorate
+            | Op::EntryPoint
+            | Op::ExecutionMode
+            | Op::Store
+            | Op::CopyMemory
+            | Op::Branch
+            | Op::BranchConditional
+            | Op::Switch
+            | Op::Return
+            | Op::ReturnValue
+            | Op::Kill
+            | Op::Unreachable
+            | Op::LoopMerge
+            | Op::SelectionMerge
+            | Op::ControlBarrier
+            | Op::MemoryBarrier
+            | Op::ImageWrite
+            | Op::AtomicStore
+            | Op::Capability
+            | Op::Extension
+            | Op::MemoryModel
+            | Op::Source
+            | Op::SourceContinued
+            | Op::SourceExtension
+            | Op::Name
+            | Op::MemberName
+            | Op::String
+            | Op::ModuleProcessed
+            | Op::Line
+            | Op::NoLine
+            | Op::Nop
+            | Op::FunctionEnd => None,
+            _ => operands.get(1).cloned(),
+        }
+    }
+
+    pub fn ty(&self, id: Id) -> &Type {
+        self.types
+            .get(id as usize)
+            .and_then(Option::as_ref)
+            .unwrap_or(&Type::Void)
+    }
+
+    pub fn decorations(&self, id: Id) -> Option<&Decorations> {
+        self.decorations.get(&id)
+    }
+
+    pub fn member_decorations(&self, id: Id, member: u32) -> Option<&Decorations> {
+        self.member_decorations.get(&(id, member))
+    }
+}
+
+/// Descriptor array element bound to a shader resource variable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Resource {
+    pub set: u32,
+    pub binding: u32,
+    pub element: u32,
+}
+
+/// Memory layout of a value in a buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    pub matrix_stride: u32,
+    pub row_major: bool,
+    /// Distance between the components of a vector.
+    pub component_stride: u32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout {
+            matrix_stride: 16,
+            row_major: false,
+            component_stride: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Root {
+    /// Variable private to the invocation.
+    Private(Id),
+    /// Variable shared by the workgroup.
+    Shared(Id),
+    /// Buffer descriptor.
+    Buffer(Resource),
+    /// Array of buffer descriptors, not indexed yet.
+    BufferArray(Resource),
+    PushConstants,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pointer {
+    pub root: Root,
+    /// Indices into the composite value of private and shared variables.
+    pub path: Vec<u32>,
+    /// Byte offset into buffers.
+    pub offset: u32,
+    pub layout: Layout,
+    /// Type of the pointed value.
+    pub ty: Id,
+}
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Undefined,
+    /// Boolean, 32-bit integer or the bits of a 32-bit float.
+    Scalar(u32),
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+    Image(Resource),
+    Sampler(Resource),
+    SampledImage(Resource, Resource),
+}
+
+impl Value {
+    pub fn scalar(&self) -> u32 {
+        match *self {
+            Value::Scalar(value) => value,
+            _ => 0,
+        }
+    }
+
+    pub fn float(&self) -> f32 {
+        f32::from_bits(self.scalar())
+    }
+
+    pub fn components(&self) -> &[Value] {
+        match *self {
+            Value::Composite(ref components) => components,
+            ref value => std::slice::from_ref(value),
+        }
+    }
+
+    pub fn from_floats(values: &[f32]) -> Self {
+        Value::Composite(values.iter().map(|v| Value::Scalar(v.to_bits())).collect())
+    }
+
+    pub fn from_scalars(values: &[u32]) -> Self {
+        Value::Composite(values.iter().map(|&v| Value::Scalar(v)).collect())
+    }
+
+    /// Returns the first four scalar components of the value.
+    pub fn to_texel(&self) -> [u32; 4] {
+        let mut texel = [0; 4];
+        for (t, component) in texel.iter_mut().zip(self.components()) {
+            *t = component.scalar();
+        }
+        texel
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Perspective,
+    Linear,
+    Flat,
+}
+
+/// Location of the shader interface, which holds up to four components.
+#[derive(Clone, Debug)]
+pub struct Slot {
+    pub location: u32,
+    pub index: u32,
+    pub var: Id,
+    pub path: Vec<u32>,
+    pub components: u32,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuiltinSlot {
+    pub builtin: spirv::BuiltIn,
+    pub input: bool,
+    pub var: Id,
+    pub path: Vec<u32>,
+}
+
+/// Entry point of a module, specialized for a pipeline.
+#[derive(Debug)]
+pub struct Program {
+    pub module: Arc<Module>,
+    pub function: Id,
+    /// Values of the constants and of the pointers to global variables.
+    pub constants: Vec<Value>,
+    /// Initial contents of the private variables.
+    pub storage: Vec<Value>,
+    /// Initial contents of the workgroup variables.
+    pub shared: Vec<Value>,
+    pub inputs: Vec<Slot>,
+    pub outputs: Vec<Slot>,
+    pub builtins: Vec<BuiltinSlot>,
+    /// Descriptor set and binding of the input attachments.
+    pub subpass_inputs: Vec<(u32, u32)>,
+    pub local_size: [u32; 3],
+    pub early_fragment_tests: bool,
+}
+
+impl Program {
+    pub fn new(
+        module: &Arc<Module>,
+        name: &str,
+        model: spirv::ExecutionModel,
+        specialization: &pso::Specialization,
+    ) -> Result<Self, String> {
+        let entry = module
+            .entry_points
+            .iter()
+            .find(|entry| entry.name == name && entry.model == model)
+            .ok_or(format!("Entry point {} not found", name))?;
+
+        let mut program = Program {
+            module: Arc::clone(module),
+            function: entry.function,
+            constants: vec![Value::Undefined; module.bound],
+            storage: vec![Value::Undefined; module.bound],
+            shared: vec![Value::Undefined; module.bound],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            builtins: Vec::new(),
+            subpass_inputs: Vec::new(),
+            local_size: entry.local_size,
+            early_fragment_tests: entry.early_fragment_tests,
+        };
+        program.evaluate_constants(specialization)?;
+        program.init_variables(entry)?;
+
+        for (&id, decorations) in &module.decorations {
+            if decorations.builtin == Some(spirv::BuiltIn::WorkgroupSize) {
+                let size = program.constants[id as usize].to_texel();
+                program.local_size = [size[0], size[1], size[2]];
+            }
+        }
+        Ok(program)
+    }
+
+    fn evaluate_constants(&mut self, specialization: &pso::Specialization) -> Result<(), String> {
+        use spirv::Op;
+        let module = Arc::clone(&self.module);
+
+        for inst in &module.constants {
+            let (ty, id) = (inst.operands[0], inst.operands[1] as usize);
+            let spec_value = || {
+                let spec_id = module.decorations(id as Id).and_then(|d| d.spec_id)?;
+                let constant = specialization.constants.iter().find(|c| c.id == spec_id)?;
+                let data = &specialization.data
+                    [constant.range.start as usize .. constant.range.end as usize];
+                Some(data.iter().rev().fold(0u32, |u, &b| (u << 8) | b as u32))
+            };
+            let value = match inst.op {
+                Op::ConstantTrue => Value::Scalar(1),
+                Op::ConstantFalse | Op::ConstantNull | Op::Undef => self.zero(ty),
+                Op::Constant => Value::Scalar(inst.operands[2]),
+                Op::SpecConstantTrue | Op::SpecConstantFalse => {
+                    let default = (inst.op == Op::SpecConstantTrue) as u32;
+                    Value::Scalar(spec_value().map_or(default, |v| (v != 0) as u32))
+                }
+                Op::SpecConstant => Value::Scalar(spec_value().unwrap_or(inst.operands[2])),
+                Op::ConstantComposite | Op::SpecConstantComposite => Value::Composite(
+                    inst.operands[2 ..]
+                        .iter()
+                        .map(|&c| self.constants[c as usize].clone())
+                        .collect(),
+                ),
+                Op::SpecConstantOp => {
+                    let op = Op::from_u32(inst.operands[2]).unwrap();
+                    let constants = &self.constants;
+                    ops::evaluate(&module, op, ty, &inst.operands[3 ..], &|id| {
+                        &constants[id as usize]
+                    })
+                    .ok_or(format!("Failed to evaluate specialization constant {}", id))?
+                }
+                _ => unreachable!(),
+            };
+            self.constants[id] = value;
+        }
+        Ok(())
+    }
+
+    fn init_variables(&mut self, entry: &EntryPoint) -> Result<(), String> {
+        use spirv::StorageClass as Sc;
+        let module = Arc::clone(&self.module);
+
+        for var in &module.variables {
+            let id = var.id as usize;
+            let decorations = module.decorations(var.id).cloned().unwrap_or_default();
+            let resource = Resource {
+                set: decorations.set.unwrap_or(0),
+                binding: decorations.binding.unwrap_or(0),
+                element: 0,
+            };
+            let image_ty = match *module.ty(var.pointee) {
+                Type::Array { element, .. } | Type::RuntimeArray { element } => element,
+                _ => var.pointee,
+            };
+            if let Type::Image {
+                dim: spirv::Dim::DimSubpassData,
+            } = *module.ty(image_ty)
+            {
+                self.subpass_inputs.push((resource.set, resource.binding));
+            }
+            let root = match var.class {
+                Sc::Input | Sc::Output | Sc::Private | Sc::UniformConstant => {
+                    self.storage[id] = match var.initializer {
+                        Some(init) => self.constants[init as usize].clone(),
+                        None if var.class == Sc::UniformConstant => {
+                            self.resource_handle(var.pointee, resource)
+                        }
+                        None => self.zero(var.pointee),
+                    };
+                    Root::Private(var.id)
+                }
+                Sc::Workgroup => {
+                    self.shared[id] = self.zero(var.pointee);
+                    Root::Shared(var.id)
+                }
+                Sc::Uniform | Sc::StorageBuffer => match *module.ty(var.pointee) {
+                    Type::Array { .. } | Type::RuntimeArray { .. } => Root::BufferArray(resource),
+                    _ => Root::Buffer(resource),
+                },
+                Sc::PushConstant => Root::PushConstants,
+                class => return Err(format!("Unsupported storage class {:?}", class)),
+            };
+            self.constants[id] = Value::Pointer(Pointer {
+                root,
+                path: Vec::new(),
+                offset: 0,
+                layout: Layout::default(),
+                ty: var.pointee,
+            });
+
+            let is_interface = entry.interface.is_empty() || entry.interface.contains(&var.id);
+            if is_interface && (var.class == Sc::Input || var.class == Sc::Output) {
+                self.collect_slots(
+                    var.id,
+                    var.class == Sc::Input,
+                    var.pointee,
+                    &decorations,
+                    decorations.location,
+                    &mut Vec::new(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the value of a sampler or image variable.
+    fn resource_handle(&self, ty: Id, resource: Resource) -> Value {
+        match *self.module.ty(ty) {
+            Type::Image { .. } => Value::Image(resource),
+            Type::Sampler => Value::Sampler(resource),
+            Type::SampledImage => Value::SampledImage(resource, resource),
+            Type::Array { element, length } => Value::Composite(
+                (0 .. self.constants[length as usize].scalar())
+                    .map(|i| {
+                        self.resource_handle(
+                            element,
+                            Resource {
+                                element: resource.element + i,
+                                ..resource
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => Value::Undefined,
+        }
+    }
+
+    /// Collects the locations and built-ins of an interface variable.
+    ///
+    /// Returns the number of locations used by the given type.
+    fn collect_slots(
+        &mut self,
+        var: Id,
+        input: bool,
+        ty: Id,
+        decorations: &Decorations,
+        location: Option<u32>,
+        path: &mut Vec<u32>,
+    ) -> u32 {
+        if let Some(builtin) = decorations.builtin {
+            self.builtins.push(BuiltinSlot {
+                builtin,
+                input,
+                var,
+                path: path.clone(),
+            });
+            return 0;
+        }
+        let interpolation = if decorations.flat {
+            Interpolation::Flat
+        } else if decorations.no_perspective {
+            Interpolation::Linear
+        } else {
+            Interpolation::Perspective
+        };
+
+        let module = Arc::clone(&self.module);
+        match *module.ty(ty) {
+            Type::Struct { ref members } => {
+                let mut next = location;
+                let mut total = 0;
+                for (i, &member) in members.iter().enumerate() {
+                    let mut member_decorations = module
+                        .member_decorations(ty, i as u32)
+                        .cloned()
+                        .unwrap_or_default();
+                    member_decorations.flat |= decorations.flat;
+                    member_decorations.no_perspective |= decorations.no_perspective;
+                    if let Some(location) = member_decorations.location {
+                        next = Some(location);
+                    }
+                    path.push(i as u32);
+                    let count =
+                        self.collect_slots(var, input, member, &member_decorations, next, path);
+                    path.pop();
+                    next = next.map(|location| location + count);
+                    total += count;
+                }
+                total
+            }
+            Type::Array { element, length } => {
+                let length = self.constants[length as usize].scalar();
+                let mut total = 0;
+                for i in 0 .. length {
+                    path.push(i);
+                    total += self.collect_slots(
+                        var,
+                        input,
+                        element,
+                        decorations,
+                        location.map(|location| location + total),
+                        path,
+                    );
+                    path.pop();
+                }
+                total
+            }
+            Type::Matrix { column, count } => {
+                for i in 0 .. count {
+                    path.push(i);
+                    self.collect_slots(
+                        var,
+                        input,
+                        column,
+                        decorations,
+                        location.map(|location| location + i),
+                        path,
+                    );
+                    path.pop();
+                }
+                count
+            }
+            ref other => {
+                let components = match *other {
+                    Type::Vector { count, .. } => count,
+                    _ => 1,
+                };
+                if let Some(location) = location {
+                    let slot = Slot {
+                        location,
+                        index: decorations.index.unwrap_or(0),
+                        var,
+                        path: path.clone(),
+                        components,
+                        interpolation,
+                    };
+                    if input {
+                        self.inputs.push(slot);
+                    } else {
+                        self.outputs.push(slot);
+                    }
+                }
+                1
+            }
+        }
+    }
+
+    /// Returns the zero value of a type.
+    pub fn zero(&self, ty: Id) -> Value {
+        match *self.module.ty(ty) {
+            Type::Bool | Type::Int | Type::Float => Value::Scalar(0),
+            Type::Vector { component, count }
+            | Type::Matrix {
+                column: component,
+                count,
+            } => Value::Composite((0 .. count).map(|_| self.zero(component)).collect()),
+            Type::Array { element, length } => Value::Composite(
+                (0 .. self.constants[length as usize].scalar())
+                    .map(|_| self.zero(element))
+                    .collect(),
+            ),
+            Type::RuntimeArray { .. } => Value::Composite(Vec::new()),
+            Type::Struct { ref members } => {
+                Value::Composite(members.iter().map(|&member| self.zero(member)).collect())
+            }
+            _ => Value::Undefined,
+        }
+    }
+
+    /// Returns true if the program writes the fragment depth.
+    pub fn writes_depth(&self) -> bool {
+        self.builtins
+            .iter()
+            .any(|slot| !slot.input && slot.builtin == spirv::BuiltIn::FragDepth)
+    }
+}
@@ -0,0 +1,512 @@
+//! Evaluation of the instructions that don't depend on the invocation state.
+
+use super::{Id, Module, Type, Value};
+use spirv_headers as spirv;
+
+fn f(bits: u32) -> f32 {
+    f32::from_bits(bits)
+}
+
+fn b(value: f32) -> u32 {
+    value.to_bits()
+}
+
+fn i(bits: u32) -> i32 {
+    bits as i32
+}
+
+/// Applies a function to every scalar of a value.
+pub fn map1(a: &Value, fun: &dyn Fn(u32) -> u32) -> Value {
+    match *a {
+        Value::Composite(ref components) => {
+            Value::Composite(components.iter().map(|c| map1(c, fun)).collect())
+        }
+        ref value => Value::Scalar(fun(value.scalar())),
+    }
+}
+
+/// Applies a function to every pair of scalars of two values of the same shape.
+///
+/// A scalar second operand is used for every component of the first one.
+pub fn map2(a: &Value, b: &Value, fun: &dyn Fn(u32, u32) -> u32) -> Value {
+    match (a, b) {
+        (Value::Composite(x), Value::Composite(y)) => Value::Composite(
+            x.iter()
+                .zip(y.iter())
+                .map(|(x, y)| map2(x, y, fun))
+                .collect(),
+        ),
+        (Value::Composite(x), y) => Value::Composite(x.iter().map(|x| map2(x, y, fun)).collect()),
+        (x, y) => Value::Scalar(fun(x.scalar(), y.scalar())),
+    }
+}
+
+pub fn map3(a: &Value, b: &Value, c: &Value, fun: &dyn Fn(u32, u32, u32) -> u32) -> Value {
+    match *a {
+        Value::Composite(ref x) => {
+            let at = |v: &Value, index: usize| match *v {
+                Value::Composite(ref components) => components[index].clone(),
+                ref scalar => scalar.clone(),
+            };
+            Value::Composite(
+                x.iter()
+                    .enumerate()
+                    .map(|(index, x)| map3(x, &at(b, index), &at(c, index), fun))
+                    .collect(),
+            )
+        }
+        ref x => Value::Scalar(fun(x.scalar(), b.scalar(), c.scalar())),
+    }
+}
+
+pub fn dot(a: &Value, b: &Value) -> f32 {
+    a.components()
+        .iter()
+        .zip(b.components())
+        .map(|(x, y)| x.float() * y.float())
+        .sum()
+}
+
+fn scale(vector: &Value, factor: f32) -> Value {
+    map1(vector, &|x| b(f(x) * factor))
+}
+
+fn transpose(matrix: &Value) -> Value {
+    let columns = matrix.components();
+    let rows = columns.first().map_or(0, |c| c.components().len());
+    Value::Composite(
+        (0 .. rows)
+            .map(|row| {
+                Value::Composite(
+                    columns
+                        .iter()
+                        .map(|column| column.components()[row].clone())
+                        .collect(),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn matrix_times_vector(matrix: &Value, vector: &Value) -> Value {
+    let columns = matrix.components();
+    let rows = columns.first().map_or(0, |c| c.components().len());
+    let result = (0 .. rows)
+        .map(|row| {
+            columns
+                .iter()
+                .zip(vector.components())
+                .map(|(column, v)| column.components()[row].float() * v.float())
+                .sum::<f32>()
+        })
+        .collect::<Vec<_>>();
+    Value::from_floats(&result)
+}
+
+/// Replaces the part of a composite value at the given path.
+pub fn insert(target: &mut Value, path: &[u32], object: Value) {
+    match path.split_first() {
+        None => *target = object,
+        Some((&index, rest)) => {
+            if let Value::Composite(ref mut components) = *target {
+                if let Some(component) = components.get_mut(index as usize) {
+                    insert(component, rest, object);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the part of a composite value at the given path.
+pub fn extract<'a>(value: &'a Value, path: &[u32]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, &index| match *value {
+        Value::Composite(ref components) => components.get(index as usize),
+        _ => None,
+    })
+}
+
+fn bool_op(value: bool) -> u32 {
+    value as u32
+}
+
+fn bit_field_extract(base: u32, offset: u32, count: u32, signed: bool) -> u32 {
+    if count == 0 {
+        return 0;
+    }
+    let shifted = base.checked_shr(offset).unwrap_or(0);
+    let mask = if count >= 32 { !0 } else { (1 << count) - 1 };
+    let value = shifted & mask;
+    if signed && count < 32 && value & (1 << (count - 1)) != 0 {
+        value | !mask
+    } else {
+        value
+    }
+}
+
+/// Returns true if the instruction can be evaluated by `evaluate`.
+pub fn is_pure(op: spirv::Op) -> bool {
+    use spirv::Op;
+    matches!(
+        op,
+        Op::SNegate
+            | Op::FNegate
+            | Op::IAdd
+            | Op::FAdd
+            | Op::ISub
+            | Op::FSub
+            | Op::IMul
+            | Op::FMul
+            | Op::UDiv
+            | Op::SDiv
+            | Op::FDiv
+            | Op::UMod
+            | Op::SRem
+            | Op::SMod
+            | Op::FRem
+            | Op::FMod
+            | Op::VectorTimesScalar
+            | Op::MatrixTimesScalar
+            | Op::VectorTimesMatrix
+            | Op::MatrixTimesVector
+            | Op::MatrixTimesMatrix
+            | Op::OuterProduct
+            | Op::Dot
+            | Op::Transpose
+            | Op::IAddCarry
+            | Op::ISubBorrow
+            | Op::UMulExtended
+            | Op::SMulExtended
+            | Op::ShiftRightLogical
+            | Op::ShiftRightArithmetic
+            | Op::ShiftLeftLogical
+            | Op::BitwiseOr
+            | Op::BitwiseXor
+            | Op::BitwiseAnd
+            | Op::Not
+            | Op::BitFieldInsert
+            | Op::BitFieldSExtract
+            | Op::BitFieldUExtract
+            | Op::BitReverse
+            | Op::BitCount
+            | Op::Any
+            | Op::All
+            | Op::IsNan
+            | Op::IsInf
+            | Op::LogicalEqual
+            | Op::LogicalNotEqual
+            | Op::LogicalOr
+            | Op::LogicalAnd
+            | Op::LogicalNot
+            | Op::Select
+            | Op::IEqual
+            | Op::INotEqual
+            | Op::UGreaterThan
+            | Op::SGreaterThan
+            | Op::UGreaterThanEqual
+            | Op::SGreaterThanEqual
+            | Op::ULessThan
+            | Op::SLessThan
+            | Op::ULessThanEqual
+            | Op::SLessThanEqual
+            | Op::FOrdEqual
+            | Op::FUnordEqual
+            | Op::FOrdNotEqual
+            | Op::FUnordNotEqual
+            | Op::FOrdLessThan
+            | Op::FUnordLessThan
+            | Op::FOrdGreaterThan
+            | Op::FUnordGreaterThan
+            | Op::FOrdLessThanEqual
+            | Op::FUnordLessThanEqual
+            | Op::FOrdGreaterThanEqual
+            | Op::FUnordGreaterThanEqual
+            | Op::ConvertFToU
+            | Op::ConvertFToS
+            | Op::ConvertSToF
+            | Op::ConvertUToF
+            | Op::UConvert
+            | Op::SConvert
+            | Op::FConvert
+            | Op::QuantizeToF16
+            | Op::Bitcast
+            | Op::VectorExtractDynamic
+            | Op::VectorInsertDynamic
+            | Op::VectorShuffle
+            | Op::CompositeConstruct
+            | Op::CompositeExtract
+            | Op::CompositeInsert
+            | Op::CopyObject
+            | Op::DPdx
+            | Op::DPdy
+            | Op::Fwidth
+            | Op::DPdxFine
+            | Op::DPdyFine
+            | Op::FwidthFine
+            | Op::DPdxCoarse
+            | Op::DPdyCoarse
+            | Op::FwidthCoarse
+    )
+}
+
+/// Evaluates a pure instruction, given its operands following the result id.
+pub fn evaluate<'a>(
+    module: &Module,
+    op: spirv::Op,
+    ty: Id,
+    operands: &[u32],
+    get: &dyn Fn(Id) -> &'a Value,
+) -> Option<Value> {
+    use spirv::Op;
+
+    let arg = |index: usize| get(operands[index]);
+    let value = match op {
+        Op::SNegate => map1(arg(0), &|x| i(x).wrapping_neg() as u32),
+        Op::FNegate => map1(arg(0), &|x| b(-f(x))),
+        Op::IAdd => map2(arg(0), arg(1), &|x, y| x.wrapping_add(y)),
+        Op::FAdd => map2(arg(0), arg(1), &|x, y| b(f(x) + f(y))),
+        Op::ISub => map2(arg(0), arg(1), &|x, y| x.wrapping_sub(y)),
+        Op::FSub => map2(arg(0), arg(1), &|x, y| b(f(x) - f(y))),
+        Op::IMul => map2(arg(0), arg(1), &|x, y| x.wrapping_mul(y)),
+        Op::FMul => map2(arg(0), arg(1), &|x, y| b(f(x) * f(y))),
+        Op::UDiv => map2(arg(0), arg(1), &|x, y| x.checked_div(y).unwrap_or(0)),
+        Op::SDiv => map2(arg(0), arg(1), &|x, y| {
+            i(x).checked_div(i(y)).unwrap_or(0) as u32
+        }),
+        Op::FDiv => map2(arg(0), arg(1), &|x, y| b(f(x) / f(y))),
+        Op::UMod => map2(arg(0), arg(1), &|x, y| x.checked_rem(y).unwrap_or(0)),
+        Op::SRem => map2(arg(0), arg(1), &|x, y| {
+            i(x).checked_rem(i(y)).unwrap_or(0) as u32
+        }),
+        Op::SMod => map2(arg(0), arg(1), &|x, y| {
+            let rem = i(x).checked_rem(i(y)).unwrap_or(0);
+            if rem != 0 && (rem < 0) != (i(y) < 0) {
+                (rem + i(y)) as u32
+            } else {
+                rem as u32
+            }
+        }),
+        Op::FRem => map2(arg(0), arg(1), &|x, y| b(f(x) % f(y))),
+        Op::FMod => map2(arg(0), arg(1), &|x, y| {
+            b(f(x) - f(y) * (f(x) / f(y)).floor())
+        }),
+        Op::VectorTimesScalar => scale(arg(0), arg(1).float()),
+        Op::MatrixTimesScalar => {
+            let factor = arg(1).float();
+            map1(arg(0), &|x| b(f(x) * factor))
+        }
+        Op::VectorTimesMatrix => {
+            let vector = arg(0);
+            let result = arg(1)
+                .components()
+                .iter()
+                .map(|column| dot(vector, column))
+                .collect::<Vec<_>>();
+            Value::from_floats(&result)
+        }
+        Op::MatrixTimesVector => matrix_times_vector(arg(0), arg(1)),
+        Op::MatrixTimesMatrix => {
+            let left = arg(0);
+            Value::Composite(
+                arg(1)
+                    .components()
+                    .iter()
+                    .map(|column| matrix_times_vector(left, column))
+                    .collect(),
+            )
+        }
+        Op::OuterProduct => {
+            let left = arg(0);
+            Value::Composite(
+                arg(1)
+                    .components()
+                    .iter()
+                    .map(|factor| scale(left, factor.float()))
+                    .collect(),
+            )
+        }
+        Op::Dot => Value::Scalar(b(dot(arg(0), arg(1)))),
+        Op::Transpose => transpose(arg(0)),
+        Op::IAddCarry | Op::ISubBorrow | Op::UMulExtended | Op::SMulExtended => {
+            let fun: &dyn Fn(u32, u32) -> (u32, u32) = match op {
+                Op::IAddCarry => &|x, y| {
+                    let (sum, carry) = x.overflowing_add(y);
+                    (sum, carry as u32)
+                },
+                Op::ISubBorrow => &|x, y| {
+                    let (difference, borrow) = x.overflowing_sub(y);
+                    (difference, borrow as u32)
+                },
+                Op::UMulExtended => &|x, y| {
+                    let product = x as u64 * y as u64;
+                    (product as u32, (product >> 32) as u32)
+                },
+                _ => &|x, y| {
+                    let product = i(x) as i64 * i(y) as i64;
+                    (product as u32, (product >> 32) as u32)
+                },
+            };
+            Value::Composite(vec![
+                map2(arg(0), arg(1), &|x, y| fun(x, y).0),
+                map2(arg(0), arg(1), &|x, y| fun(x, y).1),
+            ])
+        }
+        Op::ShiftRightLogical => map2(arg(0), arg(1), &|x, y| x.checked_shr(y).unwrap_or(0)),
+        Op::ShiftRightArithmetic => map2(arg(0), arg(1), &|x, y| (i(x) >> y.min(31)) as u32),
+        Op::ShiftLeftLogical => map2(arg(0), arg(1), &|x, y| x.checked_shl(y).unwrap_or(0)),
+        Op::BitwiseOr | Op::LogicalOr => map2(arg(0), arg(1), &|x, y| x | y),
+        Op::BitwiseXor => map2(arg(0), arg(1), &|x, y| x ^ y),
+        Op::BitwiseAnd | Op::LogicalAnd => map2(arg(0), arg(1), &|x, y| x & y),
+        Op::Not => map1(arg(0), &|x| !x),
+        Op::BitFieldInsert => {
+            let (offset, count) = (arg(2).scalar(), arg(3).scalar());
+            let mask = if count >= 32 {
+                !0
+            } else {
+                ((1u32 << count) - 1).checked_shl(offset).unwrap_or(0)
+            };
+            map2(arg(0), arg(1), &|base, insert| {
+                (base & !mask) | (insert.checked_shl(offset).unwrap_or(0) & mask)
+            })
+        }
+        Op::BitFieldSExtract | Op::BitFieldUExtract => {
+            let (offset, count) = (arg(1).scalar(), arg(2).scalar());
+            let signed = op == Op::BitFieldSExtract;
+            map1(arg(0), &|x| bit_field_extract(x, offset, count, signed))
+        }
+        Op::BitReverse => map1(arg(0), &|x| x.reverse_bits()),
+        Op::BitCount => map1(arg(0), &|x| x.count_ones()),
+        Op::Any => Value::Scalar(bool_op(arg(0).components().iter().any(|c| c.scalar() != 0))),
+        Op::All => Value::Scalar(bool_op(arg(0).components().iter().all(|c| c.scalar() != 0))),
+        Op::IsNan => map1(arg(0), &|x| bool_op(f(x).is_nan())),
+        Op::IsInf => map1(arg(0), &|x| bool_op(f(x).is_infinite())),
+        Op::LogicalEqual => map2(arg(0), arg(1), &|x, y| bool_op(x == y)),
+        Op::LogicalNotEqual => map2(arg(0), arg(1), &|x, y| bool_op(x != y)),
+        Op::LogicalNot => map1(arg(0), &|x| bool_op(x == 0)),
+        Op::Select => match *arg(0) {
+            Value::Composite(_) => map3(arg(0), arg(1), arg(2), &|c, x, y| {
+                if c != 0 {
+                    x
+                } else {
+                    y
+                }
+            }),
+            ref condition => {
+                if condition.scalar() != 0 {
+                    arg(1).clone()
+                } else {
+                    arg(2).clone()
+                }
+            }
+        },
+        Op::IEqual => map2(arg(0), arg(1), &|x, y| bool_op(x == y)),
+        Op::INotEqual => map2(arg(0), arg(1), &|x, y| bool_op(x != y)),
+        Op::UGreaterThan => map2(arg(0), arg(1), &|x, y| bool_op(x > y)),
+        Op::SGreaterThan => map2(arg(0), arg(1), &|x, y| bool_op(i(x) > i(y))),
+        Op::UGreaterThanEqual => map2(arg(0), arg(1), &|x, y| bool_op(x >= y)),
+        Op::SGreaterThanEqual => map2(arg(0), arg(1), &|x, y| bool_op(i(x) >= i(y))),
+        Op::ULessThan => map2(arg(0), arg(1), &|x, y| bool_op(x < y)),
+        Op::SLessThan => map2(arg(0), arg(1), &|x, y| bool_op(i(x) < i(y))),
+        Op::ULessThanEqual => map2(arg(0), arg(1), &|x, y| bool_op(x <= y)),
+        Op::SLessThanEqual => map2(arg(0), arg(1), &|x, y| bool_op(i(x) <= i(y))),
+        Op::FOrdEqual => map2(arg(0), arg(1), &|x, y| bool_op(f(x) == f(y))),
+        Op::FUnordEqual => map2(arg(0), arg(1), &|x, y| {
+            bool_op(!(f(x) != f(y)) || f(x).is_nan() || f(y).is_nan())
+        }),
+        Op::FOrdNotEqual => map2(arg(0), arg(1), &|x, y| {
+            bool_op(f(x) != f(y) && !f(x).is_nan() && !f(y).is_nan())
+        }),
+        Op::FUnordNotEqual => map2(arg(0), arg(1), &|x, y| bool_op(f(x) != f(y))),
+        Op::FOrdLessThan => map2(arg(0), arg(1), &|x, y| bool_op(f(x) < f(y))),
+        Op::FUnordLessThan => map2(arg(0), arg(1), &|x, y| {
+            bool_op(f(x) < f(y) || f(x).is_nan() || f(y).is_nan())
+        }),
+        Op::FOrdGreaterThan => map2(arg(0), arg(1), &|x, y| bool_op(f(x) > f(y))),
+        Op::FUnordGreaterThan => map2(arg(0), arg(1), &|x, y| {
+            bool_op(f(x) > f(y) || f(x).is_nan() || f(y).is_nan())
+        }),
+        Op::FOrdLessThanEqual => map2(arg(0), arg(1), &|x, y| bool_op(f(x) <= f(y))),
+        Op::FUnordLessThanEqual => map2(arg(0), arg(1), &|x, y| {
+            bool_op(f(x) <= f(y) || f(x).is_nan() || f(y).is_nan())
+        }),
+        Op::FOrdGreaterThanEqual => map2(arg(0), arg(1), &|x, y| bool_op(f(x) >= f(y))),
+        Op::FUnordGreaterThanEqual => map2(arg(0), arg(1), &|x, y| {
+            bool_op(f(x) >= f(y) || f(x).is_nan() || f(y).is_nan())
+        }),
+        Op::ConvertFToU => map1(arg(0), &|x| f(x) as u32),
+        Op::ConvertFToS => map1(arg(0), &|x| f(x) as i32 as u32),
+        Op::ConvertSToF => map1(arg(0), &|x| b(i(x) as f32)),
+        Op::ConvertUToF => map1(arg(0), &|x| b(x as f32)),
+        Op::UConvert | Op::SConvert | Op::FConvert | Op::Bitcast | Op::CopyObject => arg(0).clone(),
+        Op::QuantizeToF16 => map1(arg(0), &|x| {
+            b(crate::conv::f16_to_f32(crate::conv::f32_to_f16(f(x))))
+        }),
+        Op::VectorExtractDynamic => {
+            let index = arg(1).scalar() as usize;
+            arg(0)
+                .components()
+                .get(index)
+                .cloned()
+                .unwrap_or(Value::Scalar(0))
+        }
+        Op::VectorInsertDynamic => {
+            let mut vector = arg(0).clone();
+            if let Value::Composite(ref mut components) = vector {
+                if let Some(component) = components.get_mut(arg(2).scalar() as usize) {
+                    *component = arg(1).clone();
+                }
+            }
+            vector
+        }
+        Op::VectorShuffle => {
+            let first = arg(0).components();
+            let second = arg(1).components();
+            Value::Composite(
+                operands[2 ..]
+                    .iter()
+                    .map(|&index| {
+                        let index = index as usize;
+                        if index < first.len() {
+                            first[index].clone()
+                        } else {
+                            second
+                                .get(index - first.len())
+                                .cloned()
+                                .unwrap_or(Value::Scalar(0))
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        Op::CompositeConstruct => match *module.ty(ty) {
+            Type::Vector { .. } => Value::Composite(
+                operands
+                    .iter()
+                    .flat_map(|&id| get(id).components().iter().cloned())
+                    .collect(),
+            ),
+            _ => Value::Composite(operands.iter().map(|&id| get(id).clone()).collect()),
+        },
+        Op::CompositeExtract => extract(arg(0), &operands[1 ..])
+            .cloned()
+            .unwrap_or(Value::Undefined),
+        Op::CompositeInsert => {
+            let mut composite = arg(1).clone();
+            insert(&mut composite, &operands[2 ..], arg(0).clone());
+            composite
+        }
+        Op::DPdx
+        | Op::DPdy
+        | Op::Fwidth
+        | Op::DPdxFine
+        | Op::DPdyFine
+        | Op::FwidthFine
+        | Op::DPdxCoarse
+        | Op::DPdyCoarse
+        | Op::FwidthCoarse => {
+            // There are no helper invocations to compute the derivatives from.
+            map1(arg(0), &|_| 0)
+        }
+        _ => return None,
+    };
+    Some(value)
+}
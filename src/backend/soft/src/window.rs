@@ -0,0 +1,156 @@
+use crate::native::{self, RawImage};
+use crate::{Backend, Device, PhysicalDevice, QueueFamily};
+use hal::{format, image, window};
+
+/// Extent of surfaces that aren't created with an explicit one.
+pub(crate) const DEFAULT_EXTENT: window::Extent2D = window::Extent2D {
+    width: 640,
+    height: 480,
+};
+
+const MAX_IMAGE_COUNT: window::SwapImageIndex = 8;
+
+/// Software surface, presenting to nowhere.
+///
+/// Rendered images stay in host memory and are discarded on present.
+#[derive(Debug)]
+pub struct Surface {
+    extent: window::Extent2D,
+    swapchain: Option<Swapchain>,
+}
+
+impl Surface {
+    pub(crate) fn new(extent: window::Extent2D) -> Self {
+        Surface {
+            extent,
+            swapchain: None,
+        }
+    }
+}
+
+impl window::Surface<Backend> for Surface {
+    fn supports_queue_family(&self, _: &QueueFamily) -> bool {
+        true
+    }
+
+    fn capabilities(&self, _: &PhysicalDevice) -> window::SurfaceCapabilities {
+        window::SurfaceCapabilities {
+            image_count: 1 ..= MAX_IMAGE_COUNT,
+            current_extent: Some(self.extent),
+            extents: window::Extent2D {
+                width: 1,
+                height: 1,
+            } ..= window::Extent2D {
+                width: 16384,
+                height: 16384,
+            },
+            max_image_layers: 1,
+            usage: image::Usage::COLOR_ATTACHMENT
+                | image::Usage::TRANSFER_SRC
+                | image::Usage::TRANSFER_DST
+                | image::Usage::SAMPLED,
+            present_modes: window::PresentMode::FIFO
+                | window::PresentMode::MAILBOX
+                | window::PresentMode::IMMEDIATE,
+            composite_alpha_modes: window::CompositeAlphaMode::OPAQUE,
+        }
+    }
+
+    fn supported_formats(&self, _: &PhysicalDevice) -> Option<Vec<format::Format>> {
+        Some(vec![
+            format::Format::Bgra8Srgb,
+            format::Format::Bgra8Unorm,
+            format::Format::Rgba8Srgb,
+            format::Format::Rgba8Unorm,
+        ])
+    }
+}
+
+impl window::PresentationSurface<Backend> for Surface {
+    type SwapchainImage = native::ImageView;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        let images = device
+            .create_swapchain_images(&config)?
+            .iter()
+            .map(native::Image::raw)
+            .collect();
+        self.extent = config.extent;
+        self.swapchain = Some(Swapchain::new(config, images));
+        Ok(())
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, _: &Device) {
+        self.swapchain = None;
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        _: u64,
+    ) -> Result<(native::ImageView, Option<window::Suboptimal>), window::AcquireError> {
+        let swapchain = self
+            .swapchain
+            .as_mut()
+            .ok_or(window::AcquireError::OutOfDate)?;
+        let index = swapchain.next_index();
+        let layers = swapchain.config.image_layers;
+        let view = native::ImageView {
+            image: swapchain.images[index as usize].clone(),
+            kind: if layers > 1 {
+                image::ViewKind::D2Array
+            } else {
+                image::ViewKind::D2
+            },
+            format: swapchain.config.format,
+            swizzle: format::Swizzle::NO,
+            range: image::SubresourceRange {
+                aspects: format::Aspects::COLOR,
+                levels: 0 .. 1,
+                layers: 0 .. layers,
+            },
+        };
+        Ok((view, None))
+    }
+}
+
+/// Software swapchain, cycling through its images in order.
+#[derive(Debug)]
+pub struct Swapchain {
+    pub(crate) config: window::SwapchainConfig,
+    images: Vec<RawImage>,
+    next: window::SwapImageIndex,
+}
+
+impl Swapchain {
+    pub(crate) fn new(config: window::SwapchainConfig, images: Vec<RawImage>) -> Self {
+        Swapchain {
+            config,
+            images,
+            next: 0,
+        }
+    }
+
+    fn next_index(&mut self) -> window::SwapImageIndex {
+        let index = self.next;
+        self.next = (index + 1) % self.config.image_count;
+        index
+    }
+}
+
+impl window::Swapchain<Backend> for Swapchain {
+    unsafe fn acquire_image(
+        &mut self,
+        _: u64,
+        _: Option<&native::Semaphore>,
+        fence: Option<&native::Fence>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        if let Some(fence) = fence {
+            fence.set(true);
+        }
+        Ok((self.next_index(), None))
+    }
+}
@@ -0,0 +1,612 @@
+//! Statistics backend.
+//!
+//! `Counted<B>` wraps any backend `B` and counts the work recorded into its command
+//! buffers: draws, dispatches, binds, barriers, render passes and copied bytes.
+//! The counters of the command buffers are summed up when they are submitted, and can
+//! be read from the `CommandQueue` for the last submission and the last frame, which
+//! ends with each presentation.
+
+use hal::{
+    adapter,
+    buffer,
+    command::Level,
+    device::{CreationError as DeviceCreationError, OutOfMemory},
+    external,
+    format,
+    image,
+    memory,
+    pool,
+    pso,
+    queue,
+    window::{PresentError, Suboptimal, SwapImageIndex},
+};
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+mod command;
+mod device;
+mod stats;
+mod window;
+
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::stats::Stats;
+pub use crate::window::{Surface, Swapchain};
+
+/// Statistics backend, wrapping the backend `B`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Counted<B: hal::Backend>(PhantomData<B>);
+impl<B: hal::Backend> hal::Backend for Counted<B> {
+    type Instance = Instance<B>;
+    type PhysicalDevice = PhysicalDevice<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type QueueFamily = B::QueueFamily;
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+
+    type Memory = B::Memory;
+    type CommandPool = CommandPool<B>;
+
+    type ShaderModule = B::ShaderModule;
+    type RenderPass = B::RenderPass;
+    type Framebuffer = B::Framebuffer;
+
+    type Buffer = B::Buffer;
+    type BufferView = B::BufferView;
+    type Image = Image<B>;
+    type ImageView = B::ImageView;
+    type Sampler = B::Sampler;
+
+    type ComputePipeline = B::ComputePipeline;
+    type GraphicsPipeline = B::GraphicsPipeline;
+    type PipelineCache = B::PipelineCache;
+    type PipelineLayout = B::PipelineLayout;
+    type DescriptorSetLayout = B::DescriptorSetLayout;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = B::DescriptorSet;
+
+    type Fence = B::Fence;
+    type Semaphore = B::Semaphore;
+    type Event = B::Event;
+    type QueryPool = B::QueryPool;
+}
+
+/// Statistics instance, wrapping an instance of the backend `B`.
+#[derive(Debug)]
+pub struct Instance<B: hal::Backend> {
+    raw: B::Instance,
+}
+
+impl<B: hal::Backend> Instance<B> {
+    /// Wrap an instance.
+    pub fn new(raw: B::Instance) -> Self {
+        Instance { raw }
+    }
+
+    /// Returns the wrapped instance.
+    pub fn raw(&self) -> &B::Instance {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> hal::Instance<Counted<B>> for Instance<B> {
+    fn create(name: &str, version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        B::Instance::create(name, version).map(Instance::new)
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Counted<B>>> {
+        self.raw
+            .enumerate_adapters()
+            .into_iter()
+            .map(|adapter| adapter::Adapter {
+                info: adapter.info,
+                physical_device: PhysicalDevice {
+                    raw: adapter.physical_device,
+                },
+                queue_families: adapter.queue_families,
+            })
+            .collect()
+    }
+
+    unsafe fn create_surface(
+        &self,
+        has_handle: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface<B>, hal::window::InitError> {
+        self.raw.create_surface(has_handle).map(Surface::new)
+    }
+
+    unsafe fn destroy_surface(&self, surface: Surface<B>) {
+        self.raw.destroy_surface(surface.raw)
+    }
+}
+
+/// Statistics physical device.
+#[derive(Debug)]
+pub struct PhysicalDevice<B: hal::Backend> {
+    raw: B::PhysicalDevice,
+}
+
+impl<B: hal::Backend> adapter::PhysicalDevice<Counted<B>> for PhysicalDevice<B> {
+    unsafe fn open(
+        &self,
+        families: &[(&B::QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Counted<B>>, DeviceCreationError> {
+        let gpu = self.raw.open(families, requested_features)?;
+        let queue_groups = gpu
+            .queue_groups
+            .into_iter()
+            .map(|group| {
+                let mut counted = queue::QueueGroup::new(group.family);
+                for raw in group.queues {
+                    counted.add_queue(CommandQueue::new(raw));
+                }
+                counted
+            })
+            .collect();
+
+        Ok(adapter::Gpu {
+            device: Device::new(gpu.device),
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        self.raw.format_properties(format)
+    }
+
+    fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        self.raw
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        self.raw.memory_properties()
+    }
+
+    fn features(&self) -> hal::Features {
+        self.raw.features()
+    }
+
+    fn hints(&self) -> hal::Hints {
+        self.raw.hints()
+    }
+
+    fn limits(&self) -> hal::Limits {
+        self.raw.limits()
+    }
+
+    fn is_valid_cache(&self, cache: &[u8]) -> bool {
+        self.raw.is_valid_cache(cache)
+    }
+
+    fn external_buffer_properties(
+        &self,
+        usage: buffer::Usage,
+        handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        self.raw.external_buffer_properties(usage, handle_type)
+    }
+
+    fn external_image_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        self.raw.external_image_properties(
+            format,
+            dimensions,
+            tiling,
+            usage,
+            view_caps,
+            handle_type,
+        )
+    }
+
+    fn external_semaphore_properties(
+        &self,
+        handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        self.raw.external_semaphore_properties(handle_type)
+    }
+
+    fn drm_format_modifier_properties(
+        &self,
+        format: format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        self.raw.drm_format_modifier_properties(format)
+    }
+}
+
+/// Statistics command queue.
+///
+/// It keeps the statistics of the last submission, and those of the frames made of
+/// the submissions between two presentations.
+#[derive(Debug)]
+pub struct CommandQueue<B: hal::Backend> {
+    raw: B::CommandQueue,
+    last_submission: Stats,
+    frame: Stats,
+    last_frame: Stats,
+}
+
+impl<B: hal::Backend> CommandQueue<B> {
+    fn new(raw: B::CommandQueue) -> Self {
+        CommandQueue {
+            raw,
+            last_submission: Stats::default(),
+            frame: Stats::default(),
+            last_frame: Stats::default(),
+        }
+    }
+
+    /// Returns the wrapped command queue.
+    pub fn raw(&self) -> &B::CommandQueue {
+        &self.raw
+    }
+
+    /// Returns the statistics of the command buffers of the last submission.
+    pub fn last_submission(&self) -> &Stats {
+        &self.last_submission
+    }
+
+    /// Returns the statistics of the submissions made since the last presentation.
+    pub fn current_frame(&self) -> &Stats {
+        &self.frame
+    }
+
+    /// Returns the statistics of the submissions made between the last two presentations.
+    pub fn last_frame(&self) -> &Stats {
+        &self.last_frame
+    }
+
+    fn end_frame(&mut self) {
+        self.last_frame = self.frame;
+        self.frame = Stats::default();
+    }
+}
+
+impl<B: hal::Backend> queue::CommandQueue<Counted<B>> for CommandQueue<B> {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&B::Fence>,
+    ) where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+    {
+        let mut stats = Stats::default();
+        let command_buffers = submission
+            .command_buffers
+            .into_iter()
+            .map(|cmd_buffer| {
+                let cmd_buffer = cmd_buffer.borrow();
+                stats += cmd_buffer.stats;
+                stats.command_buffers += 1;
+                &cmd_buffer.raw
+            })
+            .collect::<Vec<_>>();
+        self.last_submission = stats;
+        self.frame += stats;
+        self.raw.submit(
+            queue::Submission {
+                command_buffers,
+                wait_semaphores: submission.wait_semaphores,
+                signal_semaphores: submission.signal_semaphores,
+            },
+            fence,
+        )
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&B::Fence>,
+    ) where
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<Item = (&'b B::Buffer, &'b [memory::SparseBind<&'b B::Memory>])>,
+        Io: IntoIterator<Item = (&'b Image<B>, &'b [memory::SparseBind<&'b B::Memory>])>,
+        Ii: IntoIterator<Item = (&'b Image<B>, &'b [memory::SparseImageBind<&'b B::Memory>])>,
+    {
+        self.raw.bind_sparse(
+            queue::SparseBinding {
+                wait_semaphores: binding.wait_semaphores,
+                signal_semaphores: binding.signal_semaphores,
+                buffer_binds: binding.buffer_binds,
+                image_opaque_binds: binding
+                    .image_opaque_binds
+                    .into_iter()
+                    .map(|(image, binds)| (&image.raw, binds)),
+                image_binds: binding
+                    .image_binds
+                    .into_iter()
+                    .map(|(image, binds)| (&image.raw, binds)),
+            },
+            fence,
+        )
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        swapchains: Is,
+        wait_semaphores: Iw,
+    ) -> Result<Option<Suboptimal>, PresentError>
+    where
+        W: 'a + Borrow<Swapchain<B>>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        let swapchains = swapchains
+            .into_iter()
+            .map(|(swapchain, index)| (&swapchain.borrow().raw, index))
+            .collect::<Vec<_>>();
+        self.end_frame();
+        self.raw.present(swapchains, wait_semaphores)
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        surface: &mut Surface<B>,
+        image: crate::window::SwapchainImage<B>,
+        wait_semaphore: Option<&B::Semaphore>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        self.end_frame();
+        self.raw
+            .present_surface(&mut surface.raw, image, wait_semaphore)
+    }
+
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        self.raw.wait_idle()
+    }
+}
+
+/// Statistics command pool.
+#[derive(Debug)]
+pub struct CommandPool<B: hal::Backend> {
+    raw: B::CommandPool,
+}
+
+impl<B: hal::Backend> pool::CommandPool<Counted<B>> for CommandPool<B> {
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources)
+    }
+
+    unsafe fn allocate_one(&mut self, level: Level) -> CommandBuffer<B> {
+        CommandBuffer::new(self.raw.allocate_one(level))
+    }
+
+    unsafe fn allocate<E>(&mut self, num: usize, level: Level, list: &mut E)
+    where
+        E: Extend<CommandBuffer<B>>,
+    {
+        let mut raw = Vec::with_capacity(num);
+        self.raw.allocate(num, level, &mut raw);
+        list.extend(raw.into_iter().map(CommandBuffer::new));
+    }
+
+    unsafe fn free<I>(&mut self, cmd_buffers: I)
+    where
+        I: IntoIterator<Item = CommandBuffer<B>>,
+    {
+        self.raw
+            .free(cmd_buffers.into_iter().map(|cmd_buffer| cmd_buffer.raw))
+    }
+}
+
+/// Statistics image, with its format to compute the size of copies.
+#[derive(Debug)]
+pub struct Image<B: hal::Backend> {
+    raw: B::Image,
+    format: format::Format,
+}
+
+impl<B: hal::Backend> Image<B> {
+    /// Returns the wrapped image.
+    pub fn raw(&self) -> &B::Image {
+        &self.raw
+    }
+}
+
+/// Statistics descriptor pool.
+#[derive(Debug)]
+pub struct DescriptorPool<B: hal::Backend> {
+    raw: B::DescriptorPool,
+}
+
+impl<B: hal::Backend> pso::DescriptorPool<Counted<B>> for DescriptorPool<B> {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &B::DescriptorSetLayout,
+    ) -> Result<B::DescriptorSet, pso::AllocationError> {
+        self.raw.allocate_set(layout)
+    }
+
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &B::DescriptorSetLayout,
+        count: pso::DescriptorArrayIndex,
+    ) -> Result<B::DescriptorSet, pso::AllocationError> {
+        self.raw.allocate_variable_set(layout, count)
+    }
+
+    unsafe fn allocate<I, E>(
+        &mut self,
+        layouts: I,
+        list: &mut E,
+    ) -> Result<(), pso::AllocationError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::DescriptorSetLayout>,
+        E: Extend<B::DescriptorSet>,
+    {
+        self.raw.allocate(layouts, list)
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
+    where
+        I: IntoIterator<Item = B::DescriptorSet>,
+    {
+        self.raw.free(descriptor_sets)
+    }
+
+    unsafe fn reset(&mut self) {
+        self.raw.reset()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        command::{self as com, CommandBuffer as _},
+        device::Device as _,
+        memory,
+        pool::CommandPool as _,
+        queue::CommandQueue as _,
+        Instance as _,
+    };
+    use std::iter;
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device<gfx_backend_empty::Backend>>();
+        foo::<CommandBuffer<gfx_backend_empty::Backend>>();
+        foo::<CommandPool<gfx_backend_empty::Backend>>();
+        foo::<CommandQueue<gfx_backend_empty::Backend>>();
+    }
+
+    #[test]
+    fn count_submission() {
+        let instance = Instance::<gfx_backend_empty::Backend>::create("test", 1).unwrap();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+        let family = gpu.queue_groups[0].family;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        unsafe {
+            let buffer = device
+                .create_buffer(
+                    256,
+                    buffer::Usage::TRANSFER_SRC | buffer::Usage::TRANSFER_DST,
+                    memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            let image = device
+                .create_image(
+                    image::Kind::D2(4, 4, 1, 1),
+                    1,
+                    format::Format::Rgba8Unorm,
+                    image::Tiling::Optimal,
+                    image::Usage::TRANSFER_DST,
+                    image::ViewCapabilities::empty(),
+                    memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            let mut pool = device
+                .create_command_pool(family, pool::CommandPoolCreateFlags::empty())
+                .unwrap();
+            let mut cmd_buffer = pool.allocate_one(Level::Primary);
+
+            cmd_buffer.begin_primary(com::CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buffer.pipeline_barrier(
+                pso::PipelineStage::TOP_OF_PIPE .. pso::PipelineStage::TRANSFER,
+                memory::Dependencies::empty(),
+                &[
+                    memory::Barrier::AllBuffers(
+                        buffer::Access::empty() .. buffer::Access::TRANSFER_WRITE,
+                    ),
+                    memory::Barrier::AllImages(
+                        image::Access::empty() .. image::Access::TRANSFER_WRITE,
+                    ),
+                ],
+            );
+            cmd_buffer.copy_buffer(
+                &buffer,
+                &buffer,
+                iter::once(com::BufferCopy {
+                    src: 0,
+                    dst: 128,
+                    size: 100,
+                }),
+            );
+            cmd_buffer.copy_buffer_to_image(
+                &buffer,
+                &image,
+                image::Layout::TransferDstOptimal,
+                iter::once(com::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: 4,
+                    buffer_height: 4,
+                    image_layers: image::SubresourceLayers {
+                        aspects: format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0 .. 1,
+                    },
+                    image_offset: image::Offset::ZERO,
+                    image_extent: image::Extent {
+                        width: 4,
+                        height: 4,
+                        depth: 1,
+                    },
+                }),
+            );
+            cmd_buffer.dispatch([1, 1, 1]);
+            cmd_buffer.finish();
+
+            queue.submit_without_semaphores(iter::once(&cmd_buffer), None);
+            let expected = Stats {
+                command_buffers: 1,
+                dispatches: 1,
+                barriers: 2,
+                bytes_copied: 100 + 4 * 4 * 4,
+                ..Stats::default()
+            };
+            assert_eq!(*queue.last_submission(), expected);
+
+            queue.submit_without_semaphores(iter::once(&cmd_buffer), None);
+            assert_eq!(queue.current_frame().command_buffers, 2);
+            assert_eq!(
+                queue.current_frame().bytes_copied,
+                2 * expected.bytes_copied
+            );
+
+            device.destroy_command_pool(pool);
+            device.destroy_image(image);
+            device.destroy_buffer(buffer);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use hal::{format, image};
+use std::ops::{AddAssign, Range};
+
+/// Counters of the work recorded into command buffers.
+///
+/// Commands recorded into secondary command buffers are counted where the secondary
+/// buffers are executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Stats {
+    /// Number of primary command buffers submitted.
+    pub command_buffers: usize,
+    /// Number of draw calls, including the indirect ones.
+    pub draws: usize,
+    /// Number of dispatch calls, including the indirect ones.
+    pub dispatches: usize,
+    /// Number of graphics and compute pipelines bound.
+    pub pipeline_binds: usize,
+    /// Number of descriptor sets bound, counting each set of a bind call and each pushed set.
+    pub descriptor_set_binds: usize,
+    /// Number of memory barriers, from pipeline barriers and event waits.
+    pub barriers: usize,
+    /// Number of render passes begun.
+    pub render_passes: usize,
+    /// Number of bytes written by buffer updates and copies.
+    pub bytes_copied: u64,
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.command_buffers += other.command_buffers;
+        self.draws += other.draws;
+        self.dispatches += other.dispatches;
+        self.pipeline_binds += other.pipeline_binds;
+        self.descriptor_set_binds += other.descriptor_set_binds;
+        self.barriers += other.barriers;
+        self.render_passes += other.render_passes;
+        self.bytes_copied += other.bytes_copied;
+    }
+}
+
+/// Returns the size of a copied region of an image of the given format.
+pub(crate) fn image_copy_size(
+    format: format::Format,
+    extent: image::Extent,
+    layers: &Range<image::Layer>,
+) -> u64 {
+    let desc = format.surface_desc();
+    let (block_width, block_height) = (desc.dim.0 as u32, desc.dim.1 as u32);
+    let blocks_wide = (extent.width + block_width - 1) / block_width;
+    let blocks_high = (extent.height + block_height - 1) / block_height;
+    let num_layers = (layers.end - layers.start) as u64;
+    blocks_wide as u64 * blocks_high as u64 * extent.depth as u64 * num_layers * desc.bits as u64
+        / 8
+}
@@ -0,0 +1,81 @@
+use crate::{Counted, Device, PhysicalDevice};
+use hal::{format, window};
+
+/// Swapchain image of the wrapped backend's surface.
+pub(crate) type SwapchainImage<B> =
+    <<B as hal::Backend>::Surface as window::PresentationSurface<B>>::SwapchainImage;
+
+/// Statistics surface, wrapping a surface of the backend `B`.
+#[derive(Debug)]
+pub struct Surface<B: hal::Backend> {
+    pub(crate) raw: B::Surface,
+}
+
+impl<B: hal::Backend> Surface<B> {
+    /// Wrap a surface created by a backend specific constructor.
+    pub fn new(raw: B::Surface) -> Self {
+        Surface { raw }
+    }
+
+    /// Returns the wrapped surface.
+    pub fn raw(&self) -> &B::Surface {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> window::Surface<Counted<B>> for Surface<B> {
+    fn supports_queue_family(&self, family: &B::QueueFamily) -> bool {
+        self.raw.supports_queue_family(family)
+    }
+
+    fn capabilities(&self, physical_device: &PhysicalDevice<B>) -> window::SurfaceCapabilities {
+        self.raw.capabilities(&physical_device.raw)
+    }
+
+    fn supported_formats(
+        &self,
+        physical_device: &PhysicalDevice<B>,
+    ) -> Option<Vec<format::Format>> {
+        self.raw.supported_formats(&physical_device.raw)
+    }
+}
+
+impl<B: hal::Backend> window::PresentationSurface<Counted<B>> for Surface<B> {
+    type SwapchainImage = SwapchainImage<B>;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device<B>,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        self.raw.configure_swapchain(&device.raw, config)
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, device: &Device<B>) {
+        self.raw.unconfigure_swapchain(&device.raw)
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+    ) -> Result<(SwapchainImage<B>, Option<window::Suboptimal>), window::AcquireError> {
+        self.raw.acquire_image(timeout_ns)
+    }
+}
+
+/// Statistics swapchain.
+#[derive(Debug)]
+pub struct Swapchain<B: hal::Backend> {
+    pub(crate) raw: B::Swapchain,
+}
+
+impl<B: hal::Backend> window::Swapchain<Counted<B>> for Swapchain<B> {
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+        semaphore: Option<&B::Semaphore>,
+        fence: Option<&B::Fence>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        self.raw.acquire_image(timeout_ns, semaphore, fence)
+    }
+}
@@ -0,0 +1,75 @@
+use gfx_backend_trace::replay::Replayer;
+use hal::Instance as _;
+use std::fs::File;
+use std::path::Path;
+
+#[allow(dead_code)]
+fn replay<B: hal::Backend>(path: &Path, name: &str) -> usize {
+    let instance = match B::Instance::create("replay", 1) {
+        Ok(instance) => instance,
+        Err(_) => {
+            println!("{}: unsupported", name);
+            return 0;
+        }
+    };
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("{}: failed to open {}: {}", name, path.display(), e);
+            return 1;
+        }
+    };
+    let mut replayer = Replayer::<B>::new(&instance);
+    match unsafe { replayer.replay(file) } {
+        Ok(count) => {
+            println!("{}: replayed {} calls", name, count);
+            0
+        }
+        Err(e) => {
+            println!("{}: {}", name, e);
+            1
+        }
+    }
+}
+
+fn main() {
+    use std::{env, process};
+
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            println!("Call with the argument of the trace path");
+            return;
+        }
+    };
+    let path = Path::new(&path);
+    #[allow(unused_mut)]
+    let mut num_failures = 0;
+
+    #[cfg(feature = "vulkan")]
+    {
+        num_failures += replay::<gfx_backend_vulkan::Backend>(path, "Vulkan");
+    }
+    #[cfg(feature = "dx12")]
+    {
+        num_failures += replay::<gfx_backend_dx12::Backend>(path, "DX12");
+    }
+    #[cfg(feature = "dx11")]
+    {
+        num_failures += replay::<gfx_backend_dx11::Backend>(path, "DX11");
+    }
+    #[cfg(feature = "metal")]
+    {
+        num_failures += replay::<gfx_backend_metal::Backend>(path, "Metal");
+    }
+    #[cfg(feature = "gl")]
+    {
+        num_failures += replay::<gfx_backend_gl::Backend>(path, "GL");
+    }
+    #[cfg(feature = "soft")]
+    {
+        num_failures += replay::<gfx_backend_soft::Backend>(path, "Soft");
+    }
+    let _ = path;
+    process::exit(num_failures as _);
+}
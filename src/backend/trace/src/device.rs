@@ -0,0 +1,1411 @@
+use crate::{
+    command::CommandBuffer,
+    format::{self as f, Call, Id},
+    native::{self, Handle},
+    recorder::{segment_size, Recorder},
+    window::{Surface, Swapchain},
+    CommandPool,
+    DescriptorPool,
+    Traced,
+};
+use hal::{
+    buffer,
+    device,
+    external,
+    format,
+    image,
+    memory,
+    pass,
+    pool::CommandPoolCreateFlags,
+    pso,
+    query,
+    queue::{QueueFamilyId, SemaphoreValue},
+    window,
+    MemoryTypeId,
+};
+use std::borrow::Borrow;
+use std::iter;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Trace device, wrapping a device of the backend `B`.
+#[derive(Debug)]
+pub struct Device<B: hal::Backend> {
+    pub(crate) raw: B::Device,
+    recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> Device<B> {
+    pub(crate) fn new(raw: B::Device, recorder: Arc<Recorder>) -> Self {
+        Device { raw, recorder }
+    }
+
+    /// Returns the wrapped device.
+    pub fn raw(&self) -> &B::Device {
+        &self.raw
+    }
+
+    fn wrap<T>(&self, raw: T) -> Handle<T> {
+        Handle {
+            raw,
+            id: self.recorder.id(),
+        }
+    }
+
+    fn set_name(&self, kind: f::ObjectKind, object: Id, name: &str) {
+        self.recorder.record(Call::SetName {
+            kind,
+            object,
+            name: name.to_owned(),
+        });
+    }
+}
+
+fn entry_point<'a, B: hal::Backend>(
+    entry: &pso::EntryPoint<'a, Traced<B>>,
+) -> pso::EntryPoint<'a, B> {
+    pso::EntryPoint {
+        entry: entry.entry,
+        module: &entry.module.raw,
+        specialization: entry.specialization.clone(),
+    }
+}
+
+fn record_entry_point<B: hal::Backend>(entry: &pso::EntryPoint<Traced<B>>) -> f::EntryPoint {
+    f::EntryPoint {
+        entry: entry.entry.to_owned(),
+        module: entry.module.id,
+        specialization: f::Specialization {
+            constants: entry.specialization.constants.to_vec(),
+            data: entry.specialization.data.to_vec(),
+        },
+    }
+}
+
+/// Unwraps a descriptor, returning it along with its recorded version.
+pub(crate) fn unwrap_descriptor<'a, B: hal::Backend>(
+    descriptor: &pso::Descriptor<'a, Traced<B>>,
+) -> (pso::Descriptor<'a, B>, f::Descriptor) {
+    match *descriptor {
+        pso::Descriptor::Sampler(sampler) => (
+            pso::Descriptor::Sampler(&sampler.raw),
+            f::Descriptor::Sampler(sampler.id),
+        ),
+        pso::Descriptor::Image(view, layout) => (
+            pso::Descriptor::Image(view.raw(), layout),
+            f::Descriptor::Image(view.id, layout),
+        ),
+        pso::Descriptor::CombinedImageSampler(view, layout, sampler) => (
+            pso::Descriptor::CombinedImageSampler(view.raw(), layout, &sampler.raw),
+            f::Descriptor::CombinedImageSampler(view.id, layout, sampler.id),
+        ),
+        pso::Descriptor::Buffer(buffer, ref range) => (
+            pso::Descriptor::Buffer(&buffer.raw, range.clone()),
+            f::Descriptor::Buffer(buffer.id, range.clone()),
+        ),
+        pso::Descriptor::TexelBuffer(view) => (
+            pso::Descriptor::TexelBuffer(&view.raw),
+            f::Descriptor::TexelBuffer(view.id),
+        ),
+    }
+}
+
+fn base_pipeline<'a, P>(parent: &pso::BasePipeline<'a, Handle<P>>) -> pso::BasePipeline<'a, P> {
+    match *parent {
+        pso::BasePipeline::Pipeline(pipeline) => pso::BasePipeline::Pipeline(&pipeline.raw),
+        pso::BasePipeline::Index(index) => pso::BasePipeline::Index(index),
+        pso::BasePipeline::None => pso::BasePipeline::None,
+    }
+}
+
+fn record_base_pipeline<P>(parent: &pso::BasePipeline<Handle<P>>) -> f::BasePipeline {
+    match *parent {
+        pso::BasePipeline::Pipeline(pipeline) => f::BasePipeline::Pipeline(pipeline.id),
+        pso::BasePipeline::Index(index) => f::BasePipeline::Index(index),
+        pso::BasePipeline::None => f::BasePipeline::None,
+    }
+}
+
+impl<B: hal::Backend> device::Device<Traced<B>> for Device<B> {
+    unsafe fn create_command_pool(
+        &self,
+        family: QueueFamilyId,
+        create_flags: CommandPoolCreateFlags,
+    ) -> Result<CommandPool<B>, device::OutOfMemory> {
+        let raw = self.raw.create_command_pool(family, create_flags)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::CreateCommandPool {
+            pool: id,
+            family,
+            flags: create_flags,
+        });
+        Ok(CommandPool {
+            raw,
+            id,
+            recorder: Arc::clone(&self.recorder),
+        })
+    }
+
+    unsafe fn destroy_command_pool(&self, pool: CommandPool<B>) {
+        self.recorder
+            .record(Call::DestroyCommandPool { pool: pool.id });
+        self.raw.destroy_command_pool(pool.raw)
+    }
+
+    unsafe fn allocate_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+    ) -> Result<native::Memory<B>, device::AllocationError> {
+        let raw = self.raw.allocate_memory(memory_type, size)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::AllocateMemory {
+            memory: id,
+            memory_type,
+            size,
+        });
+        Ok(native::Memory { raw, id, size })
+    }
+
+    unsafe fn free_memory(&self, memory: native::Memory<B>) {
+        self.recorder.unmap(memory.id);
+        self.recorder.record(Call::FreeMemory { memory: memory.id });
+        self.raw.free_memory(memory.raw)
+    }
+
+    unsafe fn allocate_exportable_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Memory<B>, device::AllocationError> {
+        let raw = self
+            .raw
+            .allocate_exportable_memory(memory_type, size, handle_types)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::AllocateMemory {
+            memory: id,
+            memory_type,
+            size,
+        });
+        Ok(native::Memory { raw, id, size })
+    }
+
+    unsafe fn export_memory(
+        &self,
+        memory: &native::Memory<B>,
+        handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.raw.export_memory(&memory.raw, handle_type)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        self.raw.get_memory_fd_type_mask(handle_type, fd)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<native::Memory<B>, external::ImportError> {
+        let raw = self.raw.import_memory(memory_type, size, handle_type, fd)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::AllocateMemory {
+            memory: id,
+            memory_type,
+            size,
+        });
+        Ok(native::Memory { raw, id, size })
+    }
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        subpasses: IS,
+        dependencies: ID,
+        correlation_masks: &[pass::ViewMask],
+    ) -> Result<Handle<B::RenderPass>, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        let attachments = attachments
+            .into_iter()
+            .map(|attachment| attachment.borrow().clone())
+            .collect::<Vec<_>>();
+        let subpasses = subpasses.into_iter().collect::<Vec<_>>();
+        let dependencies = dependencies
+            .into_iter()
+            .map(|dependency| dependency.borrow().clone())
+            .collect::<Vec<_>>();
+
+        let raw = self.raw.create_render_pass(
+            &attachments,
+            subpasses.iter().map(Borrow::borrow),
+            &dependencies,
+            correlation_masks,
+        )?;
+        let render_pass = self.wrap(raw);
+        self.recorder.record(Call::CreateRenderPass {
+            render_pass: render_pass.id,
+            attachments,
+            subpasses: subpasses
+                .iter()
+                .map(|subpass| {
+                    let subpass = subpass.borrow();
+                    f::SubpassDesc {
+                        colors: subpass.colors.to_vec(),
+                        depth_stencil: subpass.depth_stencil.cloned(),
+                        inputs: subpass.inputs.to_vec(),
+                        resolves: subpass.resolves.to_vec(),
+                        preserves: subpass.preserves.to_vec(),
+                        view_mask: subpass.view_mask,
+                    }
+                })
+                .collect(),
+            dependencies,
+            correlation_masks: correlation_masks.to_vec(),
+        });
+        Ok(render_pass)
+    }
+
+    unsafe fn destroy_render_pass(&self, rp: Handle<B::RenderPass>) {
+        self.recorder
+            .record(Call::DestroyRenderPass { render_pass: rp.id });
+        self.raw.destroy_render_pass(rp.raw)
+    }
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        set_layouts: IS,
+        push_constant: IR,
+    ) -> Result<Handle<B::PipelineLayout>, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<Handle<B::DescriptorSetLayout>>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        let set_layouts = set_layouts.into_iter().collect::<Vec<_>>();
+        let push_constants = push_constant
+            .into_iter()
+            .map(|range| range.borrow().clone())
+            .collect::<Vec<_>>();
+        let raw = self.raw.create_pipeline_layout(
+            set_layouts.iter().map(|layout| &layout.borrow().raw),
+            &push_constants,
+        )?;
+        let layout = self.wrap(raw);
+        self.recorder.record(Call::CreatePipelineLayout {
+            layout: layout.id,
+            set_layouts: set_layouts
+                .iter()
+                .map(|layout| layout.borrow().id)
+                .collect(),
+            push_constants,
+        });
+        Ok(layout)
+    }
+
+    unsafe fn destroy_pipeline_layout(&self, layout: Handle<B::PipelineLayout>) {
+        self.recorder
+            .record(Call::DestroyPipelineLayout { layout: layout.id });
+        self.raw.destroy_pipeline_layout(layout.raw)
+    }
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        data: Option<&[u8]>,
+    ) -> Result<Handle<B::PipelineCache>, device::OutOfMemory> {
+        let cache = self.wrap(self.raw.create_pipeline_cache(data)?);
+        self.recorder.record(Call::CreatePipelineCache {
+            cache: cache.id,
+            data: data.map(<[u8]>::to_vec),
+        });
+        Ok(cache)
+    }
+
+    unsafe fn get_pipeline_cache_data(
+        &self,
+        cache: &Handle<B::PipelineCache>,
+    ) -> Result<Vec<u8>, device::OutOfMemory> {
+        self.raw.get_pipeline_cache_data(&cache.raw)
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        target: &Handle<B::PipelineCache>,
+        sources: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::PipelineCache>>,
+    {
+        let sources = sources.into_iter().collect::<Vec<_>>();
+        self.raw
+            .merge_pipeline_caches(&target.raw, sources.iter().map(|cache| &cache.borrow().raw))?;
+        self.recorder.record(Call::MergePipelineCaches {
+            target: target.id,
+            sources: sources.iter().map(|cache| cache.borrow().id).collect(),
+        });
+        Ok(())
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, cache: Handle<B::PipelineCache>) {
+        self.recorder
+            .record(Call::DestroyPipelineCache { cache: cache.id });
+        self.raw.destroy_pipeline_cache(cache.raw)
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        desc: &pso::GraphicsPipelineDesc<'a, Traced<B>>,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Result<Handle<B::GraphicsPipeline>, pso::CreationError> {
+        self.create_graphics_pipelines(iter::once(desc), cache)
+            .remove(0)
+    }
+
+    unsafe fn create_graphics_pipelines<'a, I>(
+        &self,
+        descs: I,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Vec<Result<Handle<B::GraphicsPipeline>, pso::CreationError>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::GraphicsPipelineDesc<'a, Traced<B>>>,
+    {
+        let descs = descs.into_iter().collect::<Vec<_>>();
+        let raw_descs = descs
+            .iter()
+            .map(|desc| {
+                let desc = desc.borrow();
+                let shaders = &desc.shaders;
+                pso::GraphicsPipelineDesc {
+                    shaders: pso::GraphicsShaderSet {
+                        vertex: entry_point(&shaders.vertex),
+                        hull: shaders.hull.as_ref().map(entry_point),
+                        domain: shaders.domain.as_ref().map(entry_point),
+                        geometry: shaders.geometry.as_ref().map(entry_point),
+                        fragment: shaders.fragment.as_ref().map(entry_point),
+                    },
+                    rasterizer: desc.rasterizer,
+                    vertex_buffers: desc.vertex_buffers.clone(),
+                    attributes: desc.attributes.clone(),
+                    input_assembler: desc.input_assembler.clone(),
+                    blender: desc.blender.clone(),
+                    depth_stencil: desc.depth_stencil,
+                    multisampling: desc.multisampling.clone(),
+                    baked_states: desc.baked_states.clone(),
+                    layout: &desc.layout.raw,
+                    subpass: pass::Subpass {
+                        index: desc.subpass.index,
+                        main_pass: &desc.subpass.main_pass.raw,
+                    },
+                    flags: desc.flags,
+                    parent: base_pipeline(&desc.parent),
+                }
+            })
+            .collect::<Vec<_>>();
+        let pipelines = self
+            .raw
+            .create_graphics_pipelines(&raw_descs, cache.map(|cache| &cache.raw))
+            .into_iter()
+            .map(|result| result.map(|raw| self.wrap(raw)))
+            .collect::<Vec<_>>();
+
+        self.recorder.record(Call::CreateGraphicsPipelines {
+            pipelines: pipelines
+                .iter()
+                .map(|result| result.as_ref().ok().map(|pipeline| pipeline.id))
+                .collect(),
+            descs: descs
+                .iter()
+                .map(|desc| {
+                    let desc = desc.borrow();
+                    let shaders = &desc.shaders;
+                    f::GraphicsPipelineDesc {
+                        shaders: f::GraphicsShaderSet {
+                            vertex: record_entry_point(&shaders.vertex),
+                            hull: shaders.hull.as_ref().map(record_entry_point),
+                            domain: shaders.domain.as_ref().map(record_entry_point),
+                            geometry: shaders.geometry.as_ref().map(record_entry_point),
+                            fragment: shaders.fragment.as_ref().map(record_entry_point),
+                        },
+                        rasterizer: desc.rasterizer,
+                        vertex_buffers: desc.vertex_buffers.clone(),
+                        attributes: desc.attributes.clone(),
+                        input_assembler: desc.input_assembler.clone(),
+                        blender: desc.blender.clone(),
+                        depth_stencil: desc.depth_stencil,
+                        multisampling: desc.multisampling.clone(),
+                        baked_states: desc.baked_states.clone(),
+                        layout: desc.layout.id,
+                        subpass: (desc.subpass.main_pass.id, desc.subpass.index),
+                        flags: desc.flags,
+                        parent: record_base_pipeline(&desc.parent),
+                    }
+                })
+                .collect(),
+            cache: cache.map(|cache| cache.id),
+        });
+        pipelines
+    }
+
+    unsafe fn destroy_graphics_pipeline(&self, pipeline: Handle<B::GraphicsPipeline>) {
+        self.recorder.record(Call::DestroyGraphicsPipeline {
+            pipeline: pipeline.id,
+        });
+        self.raw.destroy_graphics_pipeline(pipeline.raw)
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        desc: &pso::ComputePipelineDesc<'a, Traced<B>>,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Result<Handle<B::ComputePipeline>, pso::CreationError> {
+        self.create_compute_pipelines(iter::once(desc), cache)
+            .remove(0)
+    }
+
+    unsafe fn create_compute_pipelines<'a, I>(
+        &self,
+        descs: I,
+        cache: Option<&Handle<B::PipelineCache>>,
+    ) -> Vec<Result<Handle<B::ComputePipeline>, pso::CreationError>>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::ComputePipelineDesc<'a, Traced<B>>>,
+    {
+        let descs = descs.into_iter().collect::<Vec<_>>();
+        let raw_descs = descs
+            .iter()
+            .map(|desc| {
+                let desc = desc.borrow();
+                pso::ComputePipelineDesc {
+                    shader: entry_point(&desc.shader),
+                    layout: &desc.layout.raw,
+                    flags: desc.flags,
+                    parent: base_pipeline(&desc.parent),
+                }
+            })
+            .collect::<Vec<_>>();
+        let pipelines = self
+            .raw
+            .create_compute_pipelines(&raw_descs, cache.map(|cache| &cache.raw))
+            .into_iter()
+            .map(|result| result.map(|raw| self.wrap(raw)))
+            .collect::<Vec<_>>();
+
+        self.recorder.record(Call::CreateComputePipelines {
+            pipelines: pipelines
+                .iter()
+                .map(|result| result.as_ref().ok().map(|pipeline| pipeline.id))
+                .collect(),
+            descs: descs
+                .iter()
+                .map(|desc| {
+                    let desc = desc.borrow();
+                    f::ComputePipelineDesc {
+                        shader: record_entry_point(&desc.shader),
+                        layout: desc.layout.id,
+                        flags: desc.flags,
+                        parent: record_base_pipeline(&desc.parent),
+                    }
+                })
+                .collect(),
+            cache: cache.map(|cache| cache.id),
+        });
+        pipelines
+    }
+
+    unsafe fn destroy_compute_pipeline(&self, pipeline: Handle<B::ComputePipeline>) {
+        self.recorder.record(Call::DestroyComputePipeline {
+            pipeline: pipeline.id,
+        });
+        self.raw.destroy_compute_pipeline(pipeline.raw)
+    }
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        pass: &Handle<B::RenderPass>,
+        attachments: I,
+        extent: image::Extent,
+    ) -> Result<Handle<B::Framebuffer>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::ImageView<B>>,
+    {
+        let attachments = attachments.into_iter().collect::<Vec<_>>();
+        let raw = self.raw.create_framebuffer(
+            &pass.raw,
+            attachments.iter().map(|view| view.borrow().raw()),
+            extent,
+        )?;
+        let framebuffer = self.wrap(raw);
+        self.recorder.record(Call::CreateFramebuffer {
+            framebuffer: framebuffer.id,
+            render_pass: pass.id,
+            attachments: attachments.iter().map(|view| view.borrow().id).collect(),
+            extent,
+        });
+        Ok(framebuffer)
+    }
+
+    unsafe fn destroy_framebuffer(&self, buf: Handle<B::Framebuffer>) {
+        self.recorder.record(Call::DestroyFramebuffer {
+            framebuffer: buf.id,
+        });
+        self.raw.destroy_framebuffer(buf.raw)
+    }
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv_data: &[u32],
+    ) -> Result<Handle<B::ShaderModule>, device::ShaderError> {
+        let module = self.wrap(self.raw.create_shader_module(spirv_data)?);
+        self.recorder.record(Call::CreateShaderModule {
+            module: module.id,
+            spirv: spirv_data.to_vec(),
+        });
+        Ok(module)
+    }
+
+    unsafe fn destroy_shader_module(&self, shader: Handle<B::ShaderModule>) {
+        self.recorder
+            .record(Call::DestroyShaderModule { module: shader.id });
+        self.raw.destroy_shader_module(shader.raw)
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        sparse: memory::SparseFlags,
+    ) -> Result<Handle<B::Buffer>, buffer::CreationError> {
+        let buffer = self.wrap(self.raw.create_buffer(size, usage, sparse)?);
+        self.recorder.record(Call::CreateBuffer {
+            buffer: buffer.id,
+            size,
+            usage,
+            sparse,
+        });
+        Ok(buffer)
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<Handle<B::Buffer>, buffer::CreationError> {
+        let buffer = self.wrap(self.raw.create_external_buffer(size, usage, handle_types)?);
+        self.recorder.record(Call::CreateBuffer {
+            buffer: buffer.id,
+            size,
+            usage,
+            sparse: memory::SparseFlags::empty(),
+        });
+        Ok(buffer)
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &Handle<B::Buffer>) -> memory::Requirements {
+        self.raw.get_buffer_requirements(&buffer.raw)
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &native::Memory<B>,
+        offset: u64,
+        buffer: &mut Handle<B::Buffer>,
+    ) -> Result<(), device::BindError> {
+        self.raw
+            .bind_buffer_memory(&memory.raw, offset, &mut buffer.raw)?;
+        self.recorder.record(Call::BindBufferMemory {
+            memory: memory.id,
+            offset,
+            buffer: buffer.id,
+        });
+        Ok(())
+    }
+
+    unsafe fn destroy_buffer(&self, buffer: Handle<B::Buffer>) {
+        self.recorder
+            .record(Call::DestroyBuffer { buffer: buffer.id });
+        self.raw.destroy_buffer(buffer.raw)
+    }
+
+    unsafe fn create_buffer_view(
+        &self,
+        buffer: &Handle<B::Buffer>,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    ) -> Result<Handle<B::BufferView>, buffer::ViewCreationError> {
+        let raw = self
+            .raw
+            .create_buffer_view(&buffer.raw, format, range.clone())?;
+        let view = self.wrap(raw);
+        self.recorder.record(Call::CreateBufferView {
+            view: view.id,
+            buffer: buffer.id,
+            format,
+            range,
+        });
+        Ok(view)
+    }
+
+    unsafe fn destroy_buffer_view(&self, view: Handle<B::BufferView>) {
+        self.recorder
+            .record(Call::DestroyBufferView { view: view.id });
+        self.raw.destroy_buffer_view(view.raw)
+    }
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
+    ) -> Result<Handle<B::Image>, image::CreationError> {
+        let raw = self
+            .raw
+            .create_image(kind, mip_levels, format, tiling, usage, view_caps, sparse)?;
+        let image = self.wrap(raw);
+        self.recorder.record(Call::CreateImage {
+            image: image.id,
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            sparse,
+        });
+        Ok(image)
+    }
+
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<Handle<B::Image>, image::CreationError> {
+        let raw = self.raw.create_external_image(
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            handle_types,
+        )?;
+        let image = self.wrap(raw);
+        self.recorder.record(Call::CreateImage {
+            image: image.id,
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            sparse: memory::SparseFlags::empty(),
+        });
+        Ok(image)
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        modifier: external::DrmFormatModifier,
+    ) -> Result<Handle<B::Image>, image::CreationError> {
+        let raw = self
+            .raw
+            .create_drm_format_modifier_image(kind, format, usage, view_caps, modifier)?;
+        let image = self.wrap(raw);
+        // The explicit layout only matters to the imported memory, which isn't replayed
+        self.recorder.record(Call::CreateImage {
+            image: image.id,
+            kind,
+            mip_levels: 1,
+            format,
+            tiling: image::Tiling::Linear,
+            usage,
+            view_caps,
+            sparse: memory::SparseFlags::empty(),
+        });
+        Ok(image)
+    }
+
+    unsafe fn get_image_requirements(&self, image: &Handle<B::Image>) -> memory::Requirements {
+        self.raw.get_image_requirements(&image.raw)
+    }
+
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        image: &Handle<B::Image>,
+    ) -> Vec<image::SparseRequirements> {
+        self.raw.get_image_sparse_requirements(&image.raw)
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &Handle<B::Image>,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        self.raw
+            .get_image_subresource_footprint(&image.raw, subresource)
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &native::Memory<B>,
+        offset: u64,
+        image: &mut Handle<B::Image>,
+    ) -> Result<(), device::BindError> {
+        self.raw
+            .bind_image_memory(&memory.raw, offset, &mut image.raw)?;
+        self.recorder.record(Call::BindImageMemory {
+            memory: memory.id,
+            offset,
+            image: image.id,
+        });
+        Ok(())
+    }
+
+    unsafe fn destroy_image(&self, image: Handle<B::Image>) {
+        self.recorder.record(Call::DestroyImage { image: image.id });
+        self.raw.destroy_image(image.raw)
+    }
+
+    unsafe fn create_image_view(
+        &self,
+        image: &Handle<B::Image>,
+        view_kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<native::ImageView<B>, image::ViewCreationError> {
+        let raw =
+            self.raw
+                .create_image_view(&image.raw, view_kind, format, swizzle, range.clone())?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::CreateImageView {
+            view: id,
+            image: image.id,
+            view_kind,
+            format,
+            swizzle,
+            range,
+        });
+        Ok(native::ImageView {
+            kind: native::ImageViewKind::Owned(raw),
+            id,
+        })
+    }
+
+    unsafe fn destroy_image_view(&self, view: native::ImageView<B>) {
+        self.recorder
+            .record(Call::DestroyImageView { view: view.id });
+        match view.kind {
+            native::ImageViewKind::Owned(raw) => self.raw.destroy_image_view(raw),
+            native::ImageViewKind::Swapchain(_) => {
+                error!("Image acquired from a surface can't be destroyed, it has to be presented")
+            }
+        }
+    }
+
+    unsafe fn create_sampler(
+        &self,
+        desc: &image::SamplerDesc,
+    ) -> Result<Handle<B::Sampler>, device::AllocationError> {
+        let sampler = self.wrap(self.raw.create_sampler(desc)?);
+        self.recorder.record(Call::CreateSampler {
+            sampler: sampler.id,
+            desc: desc.clone(),
+        });
+        Ok(sampler)
+    }
+
+    unsafe fn destroy_sampler(&self, sampler: Handle<B::Sampler>) {
+        self.recorder.record(Call::DestroySampler {
+            sampler: sampler.id,
+        });
+        self.raw.destroy_sampler(sampler.raw)
+    }
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        max_sets: usize,
+        descriptor_ranges: I,
+        flags: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool<B>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        let ranges = descriptor_ranges
+            .into_iter()
+            .map(|range| *range.borrow())
+            .collect::<Vec<_>>();
+        let raw = self.raw.create_descriptor_pool(max_sets, &ranges, flags)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::CreateDescriptorPool {
+            pool: id,
+            max_sets,
+            ranges,
+            flags,
+        });
+        Ok(DescriptorPool {
+            raw,
+            id,
+            recorder: Arc::clone(&self.recorder),
+        })
+    }
+
+    unsafe fn destroy_descriptor_pool(&self, pool: DescriptorPool<B>) {
+        self.recorder
+            .record(Call::DestroyDescriptorPool { pool: pool.id });
+        self.raw.destroy_descriptor_pool(pool.raw)
+    }
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        immutable_samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
+    ) -> Result<Handle<B::DescriptorSetLayout>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<Handle<B::Sampler>>,
+    {
+        let bindings = bindings
+            .into_iter()
+            .map(|binding| binding.borrow().clone())
+            .collect::<Vec<_>>();
+        let immutable_samplers = immutable_samplers.into_iter().collect::<Vec<_>>();
+        let raw = self.raw.create_descriptor_set_layout(
+            &bindings,
+            immutable_samplers
+                .iter()
+                .map(|sampler| &sampler.borrow().raw),
+            flags,
+        )?;
+        let layout = self.wrap(raw);
+        self.recorder.record(Call::CreateDescriptorSetLayout {
+            layout: layout.id,
+            bindings,
+            immutable_samplers: immutable_samplers
+                .iter()
+                .map(|sampler| sampler.borrow().id)
+                .collect(),
+            flags,
+        });
+        Ok(layout)
+    }
+
+    unsafe fn destroy_descriptor_set_layout(&self, layout: Handle<B::DescriptorSetLayout>) {
+        self.recorder
+            .record(Call::DestroyDescriptorSetLayout { layout: layout.id });
+        self.raw.destroy_descriptor_set_layout(layout.raw)
+    }
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, write_iter: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Traced<B>, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Traced<B>>>,
+    {
+        let mut writes = Vec::new();
+        let mut recorded = Vec::new();
+        for write in write_iter {
+            let mut descriptors = Vec::new();
+            let mut recorded_descriptors = Vec::new();
+            for descriptor in write.descriptors {
+                let (raw, desc) = unwrap_descriptor(descriptor.borrow());
+                descriptors.push(raw);
+                recorded_descriptors.push(desc);
+            }
+            recorded.push(f::DescriptorSetWrite {
+                set: write.set.id,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                descriptors: recorded_descriptors,
+            });
+            writes.push(pso::DescriptorSetWrite {
+                set: &write.set.raw,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                descriptors,
+            });
+        }
+        self.raw.write_descriptor_sets(writes);
+        self.recorder.record(Call::WriteDescriptorSets(recorded));
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, copy_iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Traced<B>>>,
+    {
+        let mut copies = Vec::new();
+        let mut recorded = Vec::new();
+        for copy in copy_iter {
+            let copy = copy.borrow();
+            let (src_set, dst_set) = (copy.src_set, copy.dst_set);
+            copies.push(pso::DescriptorSetCopy {
+                src_set: &src_set.raw,
+                src_binding: copy.src_binding,
+                src_array_offset: copy.src_array_offset,
+                dst_set: &dst_set.raw,
+                dst_binding: copy.dst_binding,
+                dst_array_offset: copy.dst_array_offset,
+                count: copy.count,
+            });
+            recorded.push(f::DescriptorSetCopy {
+                src_set: src_set.id,
+                src_binding: copy.src_binding,
+                src_array_offset: copy.src_array_offset,
+                dst_set: dst_set.id,
+                dst_binding: copy.dst_binding,
+                dst_array_offset: copy.dst_array_offset,
+                count: copy.count,
+            });
+        }
+        self.raw.copy_descriptor_sets(copies);
+        self.recorder.record(Call::CopyDescriptorSets(recorded));
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &native::Memory<B>,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        let ptr = self.raw.map_memory(&memory.raw, segment.clone())?;
+        let size = segment_size(&segment, memory.size);
+        self.recorder.record(Call::MapMemory {
+            memory: memory.id,
+            segment: segment.clone(),
+        });
+        self.recorder.map(memory.id, ptr, segment.offset, size);
+        Ok(ptr)
+    }
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, ranges: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory<B>, memory::Segment)>,
+    {
+        let ranges = ranges
+            .into_iter()
+            .map(|range| {
+                let (memory, ref segment) = *range.borrow();
+                (memory, segment.clone())
+            })
+            .collect::<Vec<_>>();
+        self.recorder
+            .capture(ranges.iter().map(|&(memory, _)| memory.id));
+        self.recorder.record(Call::FlushMappedMemoryRanges(
+            ranges
+                .iter()
+                .map(|&(memory, ref segment)| (memory.id, segment.clone()))
+                .collect(),
+        ));
+        self.raw.flush_mapped_memory_ranges(
+            ranges
+                .iter()
+                .map(|&(memory, ref segment)| (&memory.raw, segment.clone())),
+        )
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(
+        &self,
+        ranges: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory<B>, memory::Segment)>,
+    {
+        let ranges = ranges
+            .into_iter()
+            .map(|range| {
+                let (memory, ref segment) = *range.borrow();
+                (memory, segment.clone())
+            })
+            .collect::<Vec<_>>();
+        self.raw.invalidate_mapped_memory_ranges(
+            ranges
+                .iter()
+                .map(|&(memory, ref segment)| (&memory.raw, segment.clone())),
+        )?;
+        self.recorder.record(Call::InvalidateMappedMemoryRanges(
+            ranges
+                .iter()
+                .map(|&(memory, ref segment)| (memory.id, segment.clone()))
+                .collect(),
+        ));
+        self.recorder
+            .invalidate(ranges.iter().map(|&(memory, _)| memory.id));
+        Ok(())
+    }
+
+    unsafe fn unmap_memory(&self, memory: &native::Memory<B>) {
+        self.recorder.unmap(memory.id);
+        self.recorder
+            .record(Call::UnmapMemory { memory: memory.id });
+        self.raw.unmap_memory(&memory.raw)
+    }
+
+    fn create_semaphore(&self) -> Result<Handle<B::Semaphore>, device::OutOfMemory> {
+        let semaphore = self.wrap(self.raw.create_semaphore()?);
+        self.recorder.record(Call::CreateSemaphore {
+            semaphore: semaphore.id,
+        });
+        Ok(semaphore)
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: SemaphoreValue,
+    ) -> Result<Handle<B::Semaphore>, device::OutOfMemory> {
+        let semaphore = self.wrap(self.raw.create_timeline_semaphore(initial_value)?);
+        self.recorder.record(Call::CreateTimelineSemaphore {
+            semaphore: semaphore.id,
+            initial_value,
+        });
+        Ok(semaphore)
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+    ) -> Result<SemaphoreValue, device::DeviceLost> {
+        let value = self.raw.get_semaphore_value(&semaphore.raw)?;
+        self.recorder.record(Call::WaitForSemaphoreValue {
+            semaphore: semaphore.id,
+            value,
+        });
+        Ok(value)
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        value: SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        let done = self
+            .raw
+            .wait_for_semaphore_value(&semaphore.raw, value, timeout_ns)?;
+        // Only the waits which succeeded synchronize with the device.
+        if done {
+            self.recorder.record(Call::WaitForSemaphoreValue {
+                semaphore: semaphore.id,
+                value,
+            });
+        }
+        Ok(done)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        value: SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        self.recorder.record(Call::SignalSemaphore {
+            semaphore: semaphore.id,
+            value,
+        });
+        self.raw.signal_semaphore(&semaphore.raw, value)
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<Handle<B::Semaphore>, device::OutOfMemory> {
+        let semaphore = self.wrap(self.raw.create_exportable_semaphore(handle_types)?);
+        self.recorder.record(Call::CreateSemaphore {
+            semaphore: semaphore.id,
+        });
+        Ok(semaphore)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.raw.export_semaphore(&semaphore.raw, handle_type)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        semaphore: &Handle<B::Semaphore>,
+        handle_type: external::SemaphoreHandleType,
+        fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        self.raw.import_semaphore(&semaphore.raw, handle_type, fd)
+    }
+
+    unsafe fn destroy_semaphore(&self, semaphore: Handle<B::Semaphore>) {
+        self.recorder.record(Call::DestroySemaphore {
+            semaphore: semaphore.id,
+        });
+        self.raw.destroy_semaphore(semaphore.raw)
+    }
+
+    fn create_fence(&self, signaled: bool) -> Result<Handle<B::Fence>, device::OutOfMemory> {
+        let fence = self.wrap(self.raw.create_fence(signaled)?);
+        self.recorder.record(Call::CreateFence {
+            fence: fence.id,
+            signaled,
+        });
+        Ok(fence)
+    }
+
+    unsafe fn reset_fences<I>(&self, fences: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::Fence>>,
+    {
+        let fences = fences.into_iter().collect::<Vec<_>>();
+        self.raw
+            .reset_fences(fences.iter().map(|fence| &fence.borrow().raw))?;
+        self.recorder.record(Call::ResetFences(
+            fences.iter().map(|fence| fence.borrow().id).collect(),
+        ));
+        Ok(())
+    }
+
+    unsafe fn wait_for_fences<I>(
+        &self,
+        fences: I,
+        wait: device::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::Fence>>,
+    {
+        let fences = fences.into_iter().collect::<Vec<_>>();
+        let done = self.raw.wait_for_fences(
+            fences.iter().map(|fence| &fence.borrow().raw),
+            wait.clone(),
+            timeout_ns,
+        )?;
+        // Only the waits which succeeded synchronize with the device.
+        if done {
+            self.recorder.record(Call::WaitForFences {
+                fences: fences.iter().map(|fence| fence.borrow().id).collect(),
+                wait,
+            });
+        }
+        Ok(done)
+    }
+
+    unsafe fn get_fence_status(
+        &self,
+        fence: &Handle<B::Fence>,
+    ) -> Result<bool, device::DeviceLost> {
+        let signaled = self.raw.get_fence_status(&fence.raw)?;
+        if signaled {
+            self.recorder.record(Call::WaitForFences {
+                fences: vec![fence.id],
+                wait: device::WaitFor::All,
+            });
+        }
+        Ok(signaled)
+    }
+
+    unsafe fn destroy_fence(&self, fence: Handle<B::Fence>) {
+        self.recorder.record(Call::DestroyFence { fence: fence.id });
+        self.raw.destroy_fence(fence.raw)
+    }
+
+    fn create_event(&self) -> Result<Handle<B::Event>, device::OutOfMemory> {
+        let event = self.wrap(self.raw.create_event()?);
+        self.recorder.record(Call::CreateEvent { event: event.id });
+        Ok(event)
+    }
+
+    unsafe fn destroy_event(&self, event: Handle<B::Event>) {
+        self.recorder.record(Call::DestroyEvent { event: event.id });
+        self.raw.destroy_event(event.raw)
+    }
+
+    unsafe fn get_event_status(
+        &self,
+        event: &Handle<B::Event>,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw.get_event_status(&event.raw)
+    }
+
+    unsafe fn set_event(&self, event: &Handle<B::Event>) -> Result<(), device::OutOfMemory> {
+        self.raw.set_event(&event.raw)?;
+        self.recorder.record(Call::SetEvent { event: event.id });
+        Ok(())
+    }
+
+    unsafe fn reset_event(&self, event: &Handle<B::Event>) -> Result<(), device::OutOfMemory> {
+        self.raw.reset_event(&event.raw)?;
+        self.recorder.record(Call::ResetEvent { event: event.id });
+        Ok(())
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<Handle<B::QueryPool>, query::CreationError> {
+        let pool = self.wrap(self.raw.create_query_pool(ty, count)?);
+        self.recorder.record(Call::CreateQueryPool {
+            pool: pool.id,
+            ty,
+            count,
+        });
+        Ok(pool)
+    }
+
+    unsafe fn destroy_query_pool(&self, pool: Handle<B::QueryPool>) {
+        self.recorder
+            .record(Call::DestroyQueryPool { pool: pool.id });
+        self.raw.destroy_query_pool(pool.raw)
+    }
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &Handle<B::QueryPool>,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw
+            .get_query_pool_results(&pool.raw, queries, data, stride, flags)
+    }
+
+    unsafe fn create_swapchain(
+        &self,
+        surface: &mut Surface<B>,
+        config: window::SwapchainConfig,
+        old_swapchain: Option<Swapchain<B>>,
+    ) -> Result<(Swapchain<B>, Vec<Handle<B::Image>>), window::CreationError> {
+        let old_id = old_swapchain.as_ref().map(|swapchain| swapchain.id);
+        let (raw, images) = self.raw.create_swapchain(
+            &mut surface.raw,
+            config.clone(),
+            old_swapchain.map(|swapchain| swapchain.raw),
+        )?;
+        let swapchain = Swapchain {
+            raw,
+            id: self.recorder.id(),
+            recorder: Arc::clone(&self.recorder),
+        };
+        let images = images
+            .into_iter()
+            .map(|raw| self.wrap(raw))
+            .collect::<Vec<_>>();
+        self.recorder.record(Call::CreateSwapchain {
+            swapchain: swapchain.id,
+            surface: surface.id,
+            config,
+            old_swapchain: old_id,
+            images: images.iter().map(|image| image.id).collect(),
+        });
+        Ok((swapchain, images))
+    }
+
+    unsafe fn destroy_swapchain(&self, swapchain: Swapchain<B>) {
+        self.recorder.record(Call::DestroySwapchain {
+            swapchain: swapchain.id,
+        });
+        self.raw.destroy_swapchain(swapchain.raw)
+    }
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        self.raw.wait_idle()?;
+        self.recorder.record(Call::WaitIdle);
+        Ok(())
+    }
+
+    unsafe fn set_image_name(&self, image: &mut Handle<B::Image>, name: &str) {
+        self.set_name(f::ObjectKind::Image, image.id, name);
+        self.raw.set_image_name(&mut image.raw, name)
+    }
+
+    unsafe fn set_buffer_name(&self, buffer: &mut Handle<B::Buffer>, name: &str) {
+        self.set_name(f::ObjectKind::Buffer, buffer.id, name);
+        self.raw.set_buffer_name(&mut buffer.raw, name)
+    }
+
+    unsafe fn set_command_buffer_name(&self, command_buffer: &mut CommandBuffer<B>, name: &str) {
+        self.set_name(f::ObjectKind::CommandBuffer, command_buffer.id, name);
+        self.raw
+            .set_command_buffer_name(&mut command_buffer.raw, name)
+    }
+
+    unsafe fn set_semaphore_name(&self, semaphore: &mut Handle<B::Semaphore>, name: &str) {
+        self.set_name(f::ObjectKind::Semaphore, semaphore.id, name);
+        self.raw.set_semaphore_name(&mut semaphore.raw, name)
+    }
+
+    unsafe fn set_fence_name(&self, fence: &mut Handle<B::Fence>, name: &str) {
+        self.set_name(f::ObjectKind::Fence, fence.id, name);
+        self.raw.set_fence_name(&mut fence.raw, name)
+    }
+
+    unsafe fn set_framebuffer_name(&self, framebuffer: &mut Handle<B::Framebuffer>, name: &str) {
+        self.set_name(f::ObjectKind::Framebuffer, framebuffer.id, name);
+        self.raw.set_framebuffer_name(&mut framebuffer.raw, name)
+    }
+
+    unsafe fn set_render_pass_name(&self, render_pass: &mut Handle<B::RenderPass>, name: &str) {
+        self.set_name(f::ObjectKind::RenderPass, render_pass.id, name);
+        self.raw.set_render_pass_name(&mut render_pass.raw, name)
+    }
+
+    unsafe fn set_descriptor_set_name(
+        &self,
+        descriptor_set: &mut Handle<B::DescriptorSet>,
+        name: &str,
+    ) {
+        self.set_name(f::ObjectKind::DescriptorSet, descriptor_set.id, name);
+        self.raw
+            .set_descriptor_set_name(&mut descriptor_set.raw, name)
+    }
+
+    unsafe fn set_descriptor_set_layout_name(
+        &self,
+        descriptor_set_layout: &mut Handle<B::DescriptorSetLayout>,
+        name: &str,
+    ) {
+        self.set_name(
+            f::ObjectKind::DescriptorSetLayout,
+            descriptor_set_layout.id,
+            name,
+        );
+        self.raw
+            .set_descriptor_set_layout_name(&mut descriptor_set_layout.raw, name)
+    }
+}
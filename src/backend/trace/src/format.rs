@@ -0,0 +1,902 @@
+//! Trace file format.
+//!
+//! A trace starts with a `Header`, followed by a sequence of `Call`s, all of them encoded
+//! with `bincode`. Objects are referred to by an `Id`, assigned by the recorder when they
+//! are created, so a trace can be replayed on a different machine and backend.
+//!
+//! Types of `hal` that can't be serialized, typically because they borrow other objects,
+//! are mirrored here with the borrows replaced by ids.
+
+use hal::{
+    adapter::MemoryProperties,
+    buffer,
+    command as com,
+    device::WaitFor,
+    format,
+    image,
+    memory,
+    pass,
+    pool::CommandPoolCreateFlags,
+    pso,
+    query,
+    queue::{QueueFamilyId, QueuePriority, QueueType, SemaphoreValue},
+    window,
+    DrawCount,
+    Features,
+    IndexCount,
+    IndexType,
+    InstanceCount,
+    MemoryTypeId,
+    VertexCount,
+    VertexOffset,
+    WorkGroupCount,
+};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Magic bytes starting every trace.
+pub const MAGIC: [u8; 4] = *b"GFXT";
+/// Version of the format, bumped on every incompatible change.
+pub const VERSION: u32 = 9;
+
+/// Header of a trace.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Header {
+    pub magic: [u8; 4],
+    pub version: u32,
+}
+
+impl Header {
+    /// Header of traces written with the current version of the format.
+    pub const CURRENT: Self = Header {
+        magic: MAGIC,
+        version: VERSION,
+    };
+}
+
+/// Identifier of a recorded object.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct Id(pub u32);
+
+/// Kind of an object named with `Device::set_*_name`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ObjectKind {
+    Image,
+    Buffer,
+    CommandBuffer,
+    Semaphore,
+    Fence,
+    Framebuffer,
+    RenderPass,
+    DescriptorSet,
+    DescriptorSetLayout,
+}
+
+/// Queue family opened on the device.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueueFamily {
+    pub id: QueueFamilyId,
+    pub ty: QueueType,
+    pub priorities: Vec<QueuePriority>,
+    /// Ids of the created queues, one per priority.
+    pub queues: Vec<Id>,
+}
+
+/// Owned version of `pass::SubpassDesc`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubpassDesc {
+    pub colors: Vec<pass::AttachmentRef>,
+    pub depth_stencil: Option<pass::AttachmentRef>,
+    pub inputs: Vec<pass::AttachmentRef>,
+    pub resolves: Vec<pass::AttachmentRef>,
+    pub preserves: Vec<pass::AttachmentId>,
+    pub view_mask: pass::ViewMask,
+}
+
+/// Owned version of `pso::Specialization`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Specialization {
+    pub constants: Vec<pso::SpecializationConstant>,
+    pub data: Vec<u8>,
+}
+
+/// Version of `pso::EntryPoint` referring to the shader module by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EntryPoint {
+    pub entry: String,
+    pub module: Id,
+    pub specialization: Specialization,
+}
+
+/// Version of `pso::BasePipeline` referring to the parent by id.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum BasePipeline {
+    Pipeline(Id),
+    Index(usize),
+    None,
+}
+
+/// Version of `pso::GraphicsShaderSet` referring to the shader modules by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphicsShaderSet {
+    pub vertex: EntryPoint,
+    pub hull: Option<EntryPoint>,
+    pub domain: Option<EntryPoint>,
+    pub geometry: Option<EntryPoint>,
+    pub fragment: Option<EntryPoint>,
+}
+
+/// Version of `pso::GraphicsPipelineDesc` referring to the objects by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphicsPipelineDesc {
+    pub shaders: GraphicsShaderSet,
+    pub rasterizer: pso::Rasterizer,
+    pub vertex_buffers: Vec<pso::VertexBufferDesc>,
+    pub attributes: Vec<pso::AttributeDesc>,
+    pub input_assembler: pso::InputAssemblerDesc,
+    pub blender: pso::BlendDesc,
+    pub depth_stencil: pso::DepthStencilDesc,
+    pub multisampling: Option<pso::Multisampling>,
+    pub baked_states: pso::BakedStates,
+    pub layout: Id,
+    /// Render pass and index of the subpass.
+    pub subpass: (Id, pass::SubpassId),
+    pub flags: pso::PipelineCreationFlags,
+    pub parent: BasePipeline,
+}
+
+/// Version of `pso::ComputePipelineDesc` referring to the objects by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComputePipelineDesc {
+    pub shader: EntryPoint,
+    pub layout: Id,
+    pub flags: pso::PipelineCreationFlags,
+    pub parent: BasePipeline,
+}
+
+/// Version of `pso::Descriptor` referring to the objects by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Descriptor {
+    Sampler(Id),
+    Image(Id, image::Layout),
+    CombinedImageSampler(Id, image::Layout, Id),
+    Buffer(Id, buffer::SubRange),
+    TexelBuffer(Id),
+}
+
+/// Version of `pso::DescriptorSetWrite` referring to the objects by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DescriptorSetWrite {
+    pub set: Id,
+    pub binding: pso::DescriptorBinding,
+    pub array_offset: pso::DescriptorArrayIndex,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Version of `pso::DescriptorWrite` referring to the objects by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DescriptorWrite {
+    pub binding: pso::DescriptorBinding,
+    pub array_offset: pso::DescriptorArrayIndex,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Version of `pso::DescriptorSetCopy` referring to the sets by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DescriptorSetCopy {
+    pub src_set: Id,
+    pub src_binding: pso::DescriptorBinding,
+    pub src_array_offset: pso::DescriptorArrayIndex,
+    pub dst_set: Id,
+    pub dst_binding: pso::DescriptorBinding,
+    pub dst_array_offset: pso::DescriptorArrayIndex,
+    pub count: usize,
+}
+
+/// Version of `memory::Barrier` referring to the resources by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Barrier {
+    AllBuffers(Range<buffer::Access>),
+    AllImages(Range<image::Access>),
+    Buffer {
+        states: Range<buffer::State>,
+        target: Id,
+        range: buffer::SubRange,
+        families: Option<Range<QueueFamilyId>>,
+    },
+    Image {
+        states: Range<image::State>,
+        target: Id,
+        range: image::SubresourceRange,
+        families: Option<Range<QueueFamilyId>>,
+    },
+}
+
+/// Bits of a `command::ClearValue`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ClearValue(pub [u32; 4]);
+
+impl From<com::ClearValue> for ClearValue {
+    fn from(value: com::ClearValue) -> Self {
+        // All the variants are plain old data, overlapping the color bits.
+        ClearValue(unsafe { value.color.uint32 })
+    }
+}
+
+impl From<ClearValue> for com::ClearValue {
+    fn from(value: ClearValue) -> Self {
+        com::ClearValue {
+            color: com::ClearColor { uint32: value.0 },
+        }
+    }
+}
+
+/// Serializable version of `command::AttachmentClear`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum AttachmentClear {
+    Color {
+        index: usize,
+        value: [u32; 4],
+    },
+    DepthStencil {
+        depth: Option<pso::DepthValue>,
+        stencil: Option<pso::StencilValue>,
+    },
+}
+
+impl From<com::AttachmentClear> for AttachmentClear {
+    fn from(clear: com::AttachmentClear) -> Self {
+        match clear {
+            com::AttachmentClear::Color { index, value } => AttachmentClear::Color {
+                index,
+                value: unsafe { value.uint32 },
+            },
+            com::AttachmentClear::DepthStencil { depth, stencil } => {
+                AttachmentClear::DepthStencil { depth, stencil }
+            }
+        }
+    }
+}
+
+impl From<AttachmentClear> for com::AttachmentClear {
+    fn from(clear: AttachmentClear) -> Self {
+        match clear {
+            AttachmentClear::Color { index, value } => com::AttachmentClear::Color {
+                index,
+                value: com::ClearColor { uint32: value },
+            },
+            AttachmentClear::DepthStencil { depth, stencil } => {
+                com::AttachmentClear::DepthStencil { depth, stencil }
+            }
+        }
+    }
+}
+
+/// Version of `command::CommandBufferInheritanceInfo` referring to the objects by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InheritanceInfo {
+    /// Render pass and index of the subpass.
+    pub subpass: Option<(Id, pass::SubpassId)>,
+    pub framebuffer: Option<Id>,
+    pub occlusion_query_enable: bool,
+    pub occlusion_query_flags: query::ControlFlags,
+    pub pipeline_statistics: query::PipelineStatistic,
+}
+
+/// Command recorded into a command buffer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Command {
+    Begin {
+        flags: com::CommandBufferFlags,
+        inheritance: InheritanceInfo,
+    },
+    Finish,
+    Reset {
+        release_resources: bool,
+    },
+    PipelineBarrier {
+        stages: Range<pso::PipelineStage>,
+        dependencies: memory::Dependencies,
+        barriers: Vec<Barrier>,
+    },
+    FillBuffer {
+        buffer: Id,
+        range: buffer::SubRange,
+        data: u32,
+    },
+    UpdateBuffer {
+        buffer: Id,
+        offset: buffer::Offset,
+        data: Vec<u8>,
+    },
+    ClearImage {
+        image: Id,
+        layout: image::Layout,
+        value: ClearValue,
+        subresource_ranges: Vec<image::SubresourceRange>,
+    },
+    ClearAttachments {
+        clears: Vec<AttachmentClear>,
+        rects: Vec<pso::ClearRect>,
+    },
+    ResolveImage {
+        src: Id,
+        src_layout: image::Layout,
+        dst: Id,
+        dst_layout: image::Layout,
+        regions: Vec<com::ImageResolve>,
+    },
+    BlitImage {
+        src: Id,
+        src_layout: image::Layout,
+        dst: Id,
+        dst_layout: image::Layout,
+        filter: image::Filter,
+        regions: Vec<com::ImageBlit>,
+    },
+    BindIndexBuffer {
+        buffer: Id,
+        range: buffer::SubRange,
+        index_type: IndexType,
+    },
+    BindVertexBuffers {
+        first_binding: pso::BufferIndex,
+        buffers: Vec<(Id, buffer::SubRange)>,
+    },
+    BindTransformFeedbackBuffers {
+        first_binding: u32,
+        buffers: Vec<(Id, buffer::SubRange)>,
+    },
+    SetViewports {
+        first_viewport: u32,
+        viewports: Vec<pso::Viewport>,
+    },
+    SetScissors {
+        first_scissor: u32,
+        rects: Vec<pso::Rect>,
+    },
+    SetStencilReference {
+        faces: pso::Face,
+        value: pso::StencilValue,
+    },
+    SetStencilReadMask {
+        faces: pso::Face,
+        value: pso::StencilValue,
+    },
+    SetStencilWriteMask {
+        faces: pso::Face,
+        value: pso::StencilValue,
+    },
+    SetBlendConstants(pso::ColorValue),
+    SetDepthBounds(Range<f32>),
+    SetLineWidth(f32),
+    SetDepthBias(pso::DepthBias),
+    BeginRenderPass {
+        render_pass: Id,
+        framebuffer: Id,
+        render_area: pso::Rect,
+        clear_values: Vec<ClearValue>,
+        first_subpass: com::SubpassContents,
+    },
+    NextSubpass(com::SubpassContents),
+    EndRenderPass,
+    BindGraphicsPipeline(Id),
+    BindGraphicsDescriptorSets {
+        layout: Id,
+        first_set: usize,
+        sets: Vec<Id>,
+        offsets: Vec<com::DescriptorSetOffset>,
+    },
+    PushGraphicsDescriptorSet {
+        layout: Id,
+        set: usize,
+        writes: Vec<DescriptorWrite>,
+    },
+    BindComputePipeline(Id),
+    BindComputeDescriptorSets {
+        layout: Id,
+        first_set: usize,
+        sets: Vec<Id>,
+        offsets: Vec<com::DescriptorSetOffset>,
+    },
+    PushComputeDescriptorSet {
+        layout: Id,
+        set: usize,
+        writes: Vec<DescriptorWrite>,
+    },
+    Dispatch(WorkGroupCount),
+    DispatchIndirect {
+        buffer: Id,
+        offset: buffer::Offset,
+    },
+    CopyBuffer {
+        src: Id,
+        dst: Id,
+        regions: Vec<com::BufferCopy>,
+    },
+    CopyImage {
+        src: Id,
+        src_layout: image::Layout,
+        dst: Id,
+        dst_layout: image::Layout,
+        regions: Vec<com::ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: Id,
+        dst: Id,
+        dst_layout: image::Layout,
+        regions: Vec<com::BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: Id,
+        src_layout: image::Layout,
+        dst: Id,
+        regions: Vec<com::BufferImageCopy>,
+    },
+    Draw {
+        vertices: Range<VertexCount>,
+        instances: Range<InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: Id,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    },
+    DrawIndexedIndirect {
+        buffer: Id,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    },
+    DrawIndirectCount {
+        buffer: Id,
+        offset: buffer::Offset,
+        count_buffer: Id,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    },
+    DrawIndexedIndirectCount {
+        buffer: Id,
+        offset: buffer::Offset,
+        count_buffer: Id,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    },
+    BeginConditionalRendering {
+        buffer: Id,
+        offset: buffer::Offset,
+        inverted: bool,
+    },
+    BeginConditionalRenderingQuery {
+        pool: Id,
+        id: query::Id,
+        inverted: bool,
+    },
+    EndConditionalRendering,
+    BeginTransformFeedback,
+    EndTransformFeedback,
+    SetEvent {
+        event: Id,
+        stages: pso::PipelineStage,
+    },
+    ResetEvent {
+        event: Id,
+        stages: pso::PipelineStage,
+    },
+    WaitEvents {
+        events: Vec<Id>,
+        stages: Range<pso::PipelineStage>,
+        barriers: Vec<Barrier>,
+    },
+    BeginQuery {
+        pool: Id,
+        id: query::Id,
+        flags: query::ControlFlags,
+    },
+    EndQuery {
+        pool: Id,
+        id: query::Id,
+    },
+    ResetQueryPool {
+        pool: Id,
+        queries: Range<query::Id>,
+    },
+    CopyQueryPoolResults {
+        pool: Id,
+        queries: Range<query::Id>,
+        buffer: Id,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    },
+    WriteTimestamp {
+        stage: pso::PipelineStage,
+        pool: Id,
+        id: query::Id,
+    },
+    PushGraphicsConstants {
+        layout: Id,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: Vec<u32>,
+    },
+    PushComputeConstants {
+        layout: Id,
+        offset: u32,
+        constants: Vec<u32>,
+    },
+    ExecuteCommands(Vec<Id>),
+    InsertDebugMarker {
+        name: String,
+        color: u32,
+    },
+    BeginDebugMarker {
+        name: String,
+        color: u32,
+    },
+    EndDebugMarker,
+}
+
+/// Recorded call.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Call {
+    // Instance and physical device
+    CreateSurface {
+        surface: Id,
+    },
+    DestroySurface {
+        surface: Id,
+    },
+    Open {
+        adapter: usize,
+        families: Vec<QueueFamily>,
+        features: Features,
+        /// Memory properties of the recording device, for mapping the memory types.
+        memory_properties: MemoryProperties,
+    },
+
+    // Device
+    AllocateMemory {
+        memory: Id,
+        memory_type: MemoryTypeId,
+        size: u64,
+    },
+    FreeMemory {
+        memory: Id,
+    },
+    CreateCommandPool {
+        pool: Id,
+        family: QueueFamilyId,
+        flags: CommandPoolCreateFlags,
+    },
+    DestroyCommandPool {
+        pool: Id,
+    },
+    CreateRenderPass {
+        render_pass: Id,
+        attachments: Vec<pass::Attachment>,
+        subpasses: Vec<SubpassDesc>,
+        dependencies: Vec<pass::SubpassDependency>,
+        correlation_masks: Vec<pass::ViewMask>,
+    },
+    DestroyRenderPass {
+        render_pass: Id,
+    },
+    CreatePipelineLayout {
+        layout: Id,
+        set_layouts: Vec<Id>,
+        push_constants: Vec<(pso::ShaderStageFlags, Range<u32>)>,
+    },
+    DestroyPipelineLayout {
+        layout: Id,
+    },
+    CreatePipelineCache {
+        cache: Id,
+        data: Option<Vec<u8>>,
+    },
+    MergePipelineCaches {
+        target: Id,
+        sources: Vec<Id>,
+    },
+    DestroyPipelineCache {
+        cache: Id,
+    },
+    /// Creation of a batch of graphics pipelines, with the ids of the successful ones.
+    CreateGraphicsPipelines {
+        pipelines: Vec<Option<Id>>,
+        descs: Vec<GraphicsPipelineDesc>,
+        cache: Option<Id>,
+    },
+    DestroyGraphicsPipeline {
+        pipeline: Id,
+    },
+    /// Creation of a batch of compute pipelines, with the ids of the successful ones.
+    CreateComputePipelines {
+        pipelines: Vec<Option<Id>>,
+        descs: Vec<ComputePipelineDesc>,
+        cache: Option<Id>,
+    },
+    DestroyComputePipeline {
+        pipeline: Id,
+    },
+    CreateFramebuffer {
+        framebuffer: Id,
+        render_pass: Id,
+        attachments: Vec<Id>,
+        extent: image::Extent,
+    },
+    DestroyFramebuffer {
+        framebuffer: Id,
+    },
+    CreateShaderModule {
+        module: Id,
+        spirv: Vec<u32>,
+    },
+    DestroyShaderModule {
+        module: Id,
+    },
+    CreateBuffer {
+        buffer: Id,
+        size: u64,
+        usage: buffer::Usage,
+        sparse: memory::SparseFlags,
+    },
+    BindBufferMemory {
+        memory: Id,
+        offset: u64,
+        buffer: Id,
+    },
+    DestroyBuffer {
+        buffer: Id,
+    },
+    CreateBufferView {
+        view: Id,
+        buffer: Id,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    },
+    DestroyBufferView {
+        view: Id,
+    },
+    CreateImage {
+        image: Id,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
+    },
+    BindImageMemory {
+        memory: Id,
+        offset: u64,
+        image: Id,
+    },
+    DestroyImage {
+        image: Id,
+    },
+    CreateImageView {
+        view: Id,
+        image: Id,
+        view_kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    },
+    DestroyImageView {
+        view: Id,
+    },
+    CreateSampler {
+        sampler: Id,
+        desc: image::SamplerDesc,
+    },
+    DestroySampler {
+        sampler: Id,
+    },
+    CreateDescriptorPool {
+        pool: Id,
+        max_sets: usize,
+        ranges: Vec<pso::DescriptorRangeDesc>,
+        flags: pso::DescriptorPoolCreateFlags,
+    },
+    DestroyDescriptorPool {
+        pool: Id,
+    },
+    CreateDescriptorSetLayout {
+        layout: Id,
+        bindings: Vec<pso::DescriptorSetLayoutBinding>,
+        immutable_samplers: Vec<Id>,
+        flags: pso::DescriptorSetLayoutCreateFlags,
+    },
+    DestroyDescriptorSetLayout {
+        layout: Id,
+    },
+    WriteDescriptorSets(Vec<DescriptorSetWrite>),
+    CopyDescriptorSets(Vec<DescriptorSetCopy>),
+    MapMemory {
+        memory: Id,
+        segment: memory::Segment,
+    },
+    /// Contents written by the host into mapped memory.
+    WriteMemory {
+        memory: Id,
+        /// Offset from the start of the memory object.
+        offset: u64,
+        data: Vec<u8>,
+    },
+    FlushMappedMemoryRanges(Vec<(Id, memory::Segment)>),
+    InvalidateMappedMemoryRanges(Vec<(Id, memory::Segment)>),
+    UnmapMemory {
+        memory: Id,
+    },
+    CreateSemaphore {
+        semaphore: Id,
+    },
+    CreateTimelineSemaphore {
+        semaphore: Id,
+        initial_value: SemaphoreValue,
+    },
+    /// Successful wait for a semaphore value, or a value query returning it.
+    WaitForSemaphoreValue {
+        semaphore: Id,
+        value: SemaphoreValue,
+    },
+    SignalSemaphore {
+        semaphore: Id,
+        value: SemaphoreValue,
+    },
+    DestroySemaphore {
+        semaphore: Id,
+    },
+    CreateFence {
+        fence: Id,
+        signaled: bool,
+    },
+    ResetFences(Vec<Id>),
+    /// Successful wait for fences, or a fence status query returning signaled.
+    WaitForFences {
+        fences: Vec<Id>,
+        wait: WaitFor,
+    },
+    DestroyFence {
+        fence: Id,
+    },
+    CreateEvent {
+        event: Id,
+    },
+    DestroyEvent {
+        event: Id,
+    },
+    SetEvent {
+        event: Id,
+    },
+    ResetEvent {
+        event: Id,
+    },
+    CreateQueryPool {
+        pool: Id,
+        ty: query::Type,
+        count: query::Id,
+    },
+    DestroyQueryPool {
+        pool: Id,
+    },
+    CreateSwapchain {
+        swapchain: Id,
+        surface: Id,
+        config: window::SwapchainConfig,
+        old_swapchain: Option<Id>,
+        images: Vec<Id>,
+    },
+    DestroySwapchain {
+        swapchain: Id,
+    },
+    WaitIdle,
+    SetName {
+        kind: ObjectKind,
+        object: Id,
+        name: String,
+    },
+
+    // Pools
+    ResetCommandPool {
+        pool: Id,
+        release_resources: bool,
+    },
+    AllocateCommandBuffers {
+        pool: Id,
+        level: com::Level,
+        cmd_buffers: Vec<Id>,
+    },
+    FreeCommandBuffers {
+        pool: Id,
+        cmd_buffers: Vec<Id>,
+    },
+    /// Allocation of descriptor sets, with the ids of the allocated ones.
+    AllocateDescriptorSets {
+        pool: Id,
+        layouts: Vec<Id>,
+        sets: Vec<Id>,
+    },
+    /// Allocation of a descriptor set with a variable descriptor count.
+    AllocateVariableDescriptorSet {
+        pool: Id,
+        layout: Id,
+        count: pso::DescriptorArrayIndex,
+        set: Id,
+    },
+    FreeDescriptorSets {
+        pool: Id,
+        sets: Vec<Id>,
+    },
+    ResetDescriptorPool {
+        pool: Id,
+    },
+
+    // Command buffers
+    Record {
+        cmd_buffer: Id,
+        command: Command,
+    },
+
+    // Queues
+    Submit {
+        queue: Id,
+        command_buffers: Vec<Id>,
+        wait_semaphores: Vec<(Id, SemaphoreValue, pso::PipelineStage)>,
+        signal_semaphores: Vec<(Id, SemaphoreValue)>,
+        fence: Option<Id>,
+    },
+    BindSparse {
+        queue: Id,
+        wait_semaphores: Vec<(Id, SemaphoreValue)>,
+        signal_semaphores: Vec<(Id, SemaphoreValue)>,
+        buffer_binds: Vec<(Id, Vec<memory::SparseBind<Id>>)>,
+        image_opaque_binds: Vec<(Id, Vec<memory::SparseBind<Id>>)>,
+        image_binds: Vec<(Id, Vec<memory::SparseImageBind<Id>>)>,
+        fence: Option<Id>,
+    },
+    Present {
+        queue: Id,
+        swapchains: Vec<(Id, window::SwapImageIndex)>,
+        wait_semaphores: Vec<Id>,
+    },
+    PresentSurface {
+        queue: Id,
+        surface: Id,
+        image: Id,
+        wait_semaphore: Option<Id>,
+    },
+    QueueWaitIdle {
+        queue: Id,
+    },
+
+    // Presentation
+    ConfigureSwapchain {
+        surface: Id,
+        config: window::SwapchainConfig,
+    },
+    UnconfigureSwapchain {
+        surface: Id,
+    },
+    /// Image acquired from a surface, as a view with the given id.
+    AcquireImage {
+        surface: Id,
+        image: Id,
+    },
+    AcquireSwapchainImage {
+        swapchain: Id,
+        index: window::SwapImageIndex,
+        semaphore: Option<Id>,
+        fence: Option<Id>,
+    },
+}
@@ -0,0 +1,858 @@
+//! Capture and replay backend.
+//!
+//! `Traced<B>` wraps any backend `B` and records every call made to its devices, queues
+//! and command buffers into a trace, before forwarding it to `B`. Resources are referred
+//! to by ids, and the contents written by the host into mapped memory are captured when
+//! they become visible to the device: at flushes, unmaps and submissions.
+//!
+//! The trace can be replayed on any backend with `replay::Replayer`, or with the `replay`
+//! binary, in order to reproduce bugs or benchmark a frame without the application.
+//!
+//! External memory and semaphores are recorded as regular objects: their handles, and
+//! the contents other devices or processes write through them, can't be captured.
+
+#[macro_use]
+extern crate log;
+
+use hal::{
+    adapter,
+    buffer,
+    command::Level,
+    device::{CreationError as DeviceCreationError, OutOfMemory},
+    external,
+    image,
+    memory,
+    pool,
+    pso,
+    queue::{self, QueueFamily as _},
+    window::{PresentError, Suboptimal, SwapImageIndex},
+};
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::format::{Call, Id};
+use crate::native::Handle;
+use crate::recorder::Recorder;
+
+mod command;
+mod device;
+pub mod format;
+pub mod native;
+mod recorder;
+pub mod replay;
+mod window;
+
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::window::{Surface, Swapchain};
+
+/// Environment variable with the path of the trace written by `Instance::create`.
+pub const TRACE_PATH_VAR: &str = "GFX_TRACE";
+/// Path of the trace written by `Instance::create` if `GFX_TRACE` isn't set.
+pub const DEFAULT_TRACE_PATH: &str = "trace.gfx";
+
+/// Trace backend, wrapping the backend `B`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Traced<B: hal::Backend>(PhantomData<B>);
+impl<B: hal::Backend> hal::Backend for Traced<B> {
+    type Instance = Instance<B>;
+    type PhysicalDevice = PhysicalDevice<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type QueueFamily = B::QueueFamily;
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+
+    type Memory = native::Memory<B>;
+    type CommandPool = CommandPool<B>;
+
+    type ShaderModule = Handle<B::ShaderModule>;
+    type RenderPass = Handle<B::RenderPass>;
+    type Framebuffer = Handle<B::Framebuffer>;
+
+    type Buffer = Handle<B::Buffer>;
+    type BufferView = Handle<B::BufferView>;
+    type Image = Handle<B::Image>;
+    type ImageView = native::ImageView<B>;
+    type Sampler = Handle<B::Sampler>;
+
+    type ComputePipeline = Handle<B::ComputePipeline>;
+    type GraphicsPipeline = Handle<B::GraphicsPipeline>;
+    type PipelineCache = Handle<B::PipelineCache>;
+    type PipelineLayout = Handle<B::PipelineLayout>;
+    type DescriptorSetLayout = Handle<B::DescriptorSetLayout>;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = Handle<B::DescriptorSet>;
+
+    type Fence = Handle<B::Fence>;
+    type Semaphore = Handle<B::Semaphore>;
+    type Event = Handle<B::Event>;
+    type QueryPool = Handle<B::QueryPool>;
+}
+
+/// Trace instance, wrapping an instance of the backend `B`.
+pub struct Instance<B: hal::Backend> {
+    raw: B::Instance,
+    recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> Instance<B> {
+    /// Wrap an instance, writing the trace to a new file at `path`.
+    pub fn new<P: AsRef<Path>>(raw: B::Instance, path: P) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Instance::from_writer(raw, file))
+    }
+
+    /// Wrap an instance, writing the trace to `writer`.
+    ///
+    /// The trace is written in chunks, and flushed at every submission and presentation.
+    pub fn from_writer<W: Write + Send + 'static>(raw: B::Instance, writer: W) -> Self {
+        Instance {
+            raw,
+            recorder: Arc::new(Recorder::new(Box::new(writer))),
+        }
+    }
+
+    /// Returns the wrapped instance.
+    pub fn raw(&self) -> &B::Instance {
+        &self.raw
+    }
+
+    /// Wrap a surface created by a backend specific constructor.
+    pub fn wrap_surface(&self, raw: B::Surface) -> Surface<B> {
+        let id = self.recorder.id();
+        self.recorder.record(Call::CreateSurface { surface: id });
+        Surface {
+            raw,
+            id,
+            recorder: Arc::clone(&self.recorder),
+        }
+    }
+}
+
+impl<B: hal::Backend> hal::Instance<Traced<B>> for Instance<B> {
+    /// Create an instance of `B`, writing the trace to the path in the `GFX_TRACE`
+    /// environment variable, or to `trace.gfx` in the working directory.
+    fn create(name: &str, version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        let raw = B::Instance::create(name, version)?;
+        let path = std::env::var(TRACE_PATH_VAR).unwrap_or_else(|_| DEFAULT_TRACE_PATH.into());
+        Instance::new(raw, &path).map_err(|e| {
+            error!("Failed to create the trace file {:?}: {}", path, e);
+            hal::UnsupportedBackend
+        })
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Traced<B>>> {
+        self.raw
+            .enumerate_adapters()
+            .into_iter()
+            .enumerate()
+            .map(|(index, adapter)| adapter::Adapter {
+                info: adapter.info,
+                physical_device: PhysicalDevice {
+                    raw: adapter.physical_device,
+                    index,
+                    recorder: Arc::clone(&self.recorder),
+                },
+                queue_families: adapter.queue_families,
+            })
+            .collect()
+    }
+
+    unsafe fn create_surface(
+        &self,
+        has_handle: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface<B>, hal::window::InitError> {
+        let raw = self.raw.create_surface(has_handle)?;
+        Ok(self.wrap_surface(raw))
+    }
+
+    unsafe fn destroy_surface(&self, surface: Surface<B>) {
+        self.recorder.record(Call::DestroySurface {
+            surface: surface.id,
+        });
+        self.raw.destroy_surface(surface.raw)
+    }
+}
+
+/// Trace physical device.
+#[derive(Debug)]
+pub struct PhysicalDevice<B: hal::Backend> {
+    raw: B::PhysicalDevice,
+    /// Index of the adapter, used to pick the same one on replay.
+    index: usize,
+    recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> adapter::PhysicalDevice<Traced<B>> for PhysicalDevice<B> {
+    unsafe fn open(
+        &self,
+        families: &[(&B::QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Traced<B>>, DeviceCreationError> {
+        let gpu = self.raw.open(families, requested_features)?;
+        let mut recorded_families = Vec::new();
+        let queue_groups = gpu
+            .queue_groups
+            .into_iter()
+            .map(|group| {
+                let &(family, priorities) = families
+                    .iter()
+                    .find(|&&(family, _)| family.id() == group.family)
+                    .unwrap();
+                let mut traced = queue::QueueGroup::<Traced<B>>::new(group.family);
+                for raw in group.queues {
+                    traced.add_queue(CommandQueue {
+                        raw,
+                        id: self.recorder.id(),
+                        recorder: Arc::clone(&self.recorder),
+                    });
+                }
+                recorded_families.push(format::QueueFamily {
+                    id: group.family,
+                    ty: family.queue_type(),
+                    priorities: priorities.to_vec(),
+                    queues: traced.queues.iter().map(|queue| queue.id).collect(),
+                });
+                traced
+            })
+            .collect();
+
+        self.recorder.record(Call::Open {
+            adapter: self.index,
+            families: recorded_families,
+            features: requested_features,
+            memory_properties: self.raw.memory_properties(),
+        });
+        Ok(adapter::Gpu {
+            device: Device::new(gpu.device, Arc::clone(&self.recorder)),
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<hal::format::Format>) -> hal::format::Properties {
+        self.raw.format_properties(format)
+    }
+
+    fn image_format_properties(
+        &self,
+        format: hal::format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        self.raw
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        self.raw.memory_properties()
+    }
+
+    fn features(&self) -> hal::Features {
+        self.raw.features()
+    }
+
+    fn hints(&self) -> hal::Hints {
+        self.raw.hints()
+    }
+
+    fn limits(&self) -> hal::Limits {
+        self.raw.limits()
+    }
+
+    fn is_valid_cache(&self, cache: &[u8]) -> bool {
+        self.raw.is_valid_cache(cache)
+    }
+
+    fn external_buffer_properties(
+        &self,
+        usage: buffer::Usage,
+        handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        self.raw.external_buffer_properties(usage, handle_type)
+    }
+
+    fn external_image_properties(
+        &self,
+        format: hal::format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        self.raw.external_image_properties(
+            format,
+            dimensions,
+            tiling,
+            usage,
+            view_caps,
+            handle_type,
+        )
+    }
+
+    fn external_semaphore_properties(
+        &self,
+        handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        self.raw.external_semaphore_properties(handle_type)
+    }
+
+    fn drm_format_modifier_properties(
+        &self,
+        format: hal::format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        self.raw.drm_format_modifier_properties(format)
+    }
+}
+
+/// Trace command queue.
+///
+/// Captures the host writes to mapped memory before each submission.
+#[derive(Debug)]
+pub struct CommandQueue<B: hal::Backend> {
+    raw: B::CommandQueue,
+    id: Id,
+    recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> queue::CommandQueue<Traced<B>> for CommandQueue<B> {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&Handle<B::Fence>>,
+    ) where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<Handle<B::Semaphore>>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+    {
+        let command_buffers = submission
+            .command_buffers
+            .into_iter()
+            .map(Borrow::borrow)
+            .collect::<Vec<&CommandBuffer<B>>>();
+        let wait_semaphores = submission
+            .wait_semaphores
+            .into_iter()
+            .map(|(semaphore, value, stage)| (semaphore.borrow(), value, stage))
+            .collect::<Vec<(&Handle<B::Semaphore>, _, _)>>();
+        let signal_semaphores = submission
+            .signal_semaphores
+            .into_iter()
+            .map(|(semaphore, value)| (semaphore.borrow(), value))
+            .collect::<Vec<(&Handle<B::Semaphore>, _)>>();
+
+        self.recorder.capture_all();
+        self.recorder.record(Call::Submit {
+            queue: self.id,
+            command_buffers: command_buffers
+                .iter()
+                .map(|cmd_buffer| cmd_buffer.id)
+                .collect(),
+            wait_semaphores: wait_semaphores
+                .iter()
+                .map(|&(semaphore, value, stage)| (semaphore.id, value, stage))
+                .collect(),
+            signal_semaphores: signal_semaphores
+                .iter()
+                .map(|&(semaphore, value)| (semaphore.id, value))
+                .collect(),
+            fence: fence.map(|fence| fence.id),
+        });
+        self.recorder.flush();
+
+        self.raw.submit(
+            queue::Submission {
+                command_buffers: command_buffers.iter().map(|cmd_buffer| &cmd_buffer.raw),
+                wait_semaphores: wait_semaphores
+                    .iter()
+                    .map(|&(semaphore, value, stage)| (&semaphore.raw, value, stage)),
+                signal_semaphores: signal_semaphores
+                    .iter()
+                    .map(|&(semaphore, value)| (&semaphore.raw, value)),
+            },
+            fence.map(|fence| &fence.raw),
+        )
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&Handle<B::Fence>>,
+    ) where
+        S: 'a + Borrow<Handle<B::Semaphore>>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b Handle<B::Buffer>,
+                &'b [memory::SparseBind<&'b native::Memory<B>>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b Handle<B::Image>,
+                &'b [memory::SparseBind<&'b native::Memory<B>>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b Handle<B::Image>,
+                &'b [memory::SparseImageBind<&'b native::Memory<B>>],
+            ),
+        >,
+    {
+        let wait_semaphores = binding
+            .wait_semaphores
+            .into_iter()
+            .map(|(semaphore, value)| (semaphore.borrow(), value))
+            .collect::<Vec<(&Handle<B::Semaphore>, _)>>();
+        let signal_semaphores = binding
+            .signal_semaphores
+            .into_iter()
+            .map(|(semaphore, value)| (semaphore.borrow(), value))
+            .collect::<Vec<(&Handle<B::Semaphore>, _)>>();
+        let buffer_binds = binding.buffer_binds.into_iter().collect::<Vec<_>>();
+        let image_opaque_binds = binding.image_opaque_binds.into_iter().collect::<Vec<_>>();
+        let image_binds = binding.image_binds.into_iter().collect::<Vec<_>>();
+
+        self.recorder.record(Call::BindSparse {
+            queue: self.id,
+            wait_semaphores: wait_semaphores
+                .iter()
+                .map(|&(semaphore, value)| (semaphore.id, value))
+                .collect(),
+            signal_semaphores: signal_semaphores
+                .iter()
+                .map(|&(semaphore, value)| (semaphore.id, value))
+                .collect(),
+            buffer_binds: buffer_binds
+                .iter()
+                .map(|&(buffer, binds)| (buffer.id, map_sparse_binds(binds, |memory| memory.id)))
+                .collect(),
+            image_opaque_binds: image_opaque_binds
+                .iter()
+                .map(|&(image, binds)| (image.id, map_sparse_binds(binds, |memory| memory.id)))
+                .collect(),
+            image_binds: image_binds
+                .iter()
+                .map(|&(image, binds)| {
+                    (image.id, map_sparse_image_binds(binds, |memory| memory.id))
+                })
+                .collect(),
+            fence: fence.map(|fence| fence.id),
+        });
+        self.recorder.flush();
+
+        let buffer_binds = buffer_binds
+            .into_iter()
+            .map(|(buffer, binds)| (&buffer.raw, map_sparse_binds(binds, |memory| &memory.raw)))
+            .collect::<Vec<_>>();
+        let image_opaque_binds = image_opaque_binds
+            .into_iter()
+            .map(|(image, binds)| (&image.raw, map_sparse_binds(binds, |memory| &memory.raw)))
+            .collect::<Vec<_>>();
+        let image_binds = image_binds
+            .into_iter()
+            .map(|(image, binds)| {
+                (
+                    &image.raw,
+                    map_sparse_image_binds(binds, |memory| &memory.raw),
+                )
+            })
+            .collect::<Vec<_>>();
+        self.raw.bind_sparse(
+            queue::SparseBinding {
+                wait_semaphores: wait_semaphores
+                    .iter()
+                    .map(|&(semaphore, value)| (&semaphore.raw, value)),
+                signal_semaphores: signal_semaphores
+                    .iter()
+                    .map(|&(semaphore, value)| (&semaphore.raw, value)),
+                buffer_binds: buffer_binds
+                    .iter()
+                    .map(|(buffer, binds)| (*buffer, &binds[..])),
+                image_opaque_binds: image_opaque_binds
+                    .iter()
+                    .map(|(image, binds)| (*image, &binds[..])),
+                image_binds: image_binds
+                    .iter()
+                    .map(|(image, binds)| (*image, &binds[..])),
+            },
+            fence.map(|fence| &fence.raw),
+        )
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        swapchains: Is,
+        wait_semaphores: Iw,
+    ) -> Result<Option<Suboptimal>, PresentError>
+    where
+        W: 'a + Borrow<Swapchain<B>>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<Handle<B::Semaphore>>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        let swapchains = swapchains
+            .into_iter()
+            .map(|(swapchain, index)| (swapchain.borrow(), index))
+            .collect::<Vec<(&Swapchain<B>, _)>>();
+        let wait_semaphores = wait_semaphores
+            .into_iter()
+            .map(Borrow::borrow)
+            .collect::<Vec<&Handle<B::Semaphore>>>();
+        self.recorder.record(Call::Present {
+            queue: self.id,
+            swapchains: swapchains
+                .iter()
+                .map(|&(swapchain, index)| (swapchain.id, index))
+                .collect(),
+            wait_semaphores: wait_semaphores
+                .iter()
+                .map(|semaphore| semaphore.id)
+                .collect(),
+        });
+        self.recorder.flush();
+        self.raw.present(
+            swapchains
+                .iter()
+                .map(|&(swapchain, index)| (&swapchain.raw, index)),
+            wait_semaphores.iter().map(|semaphore| &semaphore.raw),
+        )
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        surface: &mut Surface<B>,
+        image: native::ImageView<B>,
+        wait_semaphore: Option<&Handle<B::Semaphore>>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        self.recorder.record(Call::PresentSurface {
+            queue: self.id,
+            surface: surface.id,
+            image: image.id,
+            wait_semaphore: wait_semaphore.map(|semaphore| semaphore.id),
+        });
+        self.recorder.flush();
+        let image = match image.kind {
+            native::ImageViewKind::Swapchain(image) => image,
+            native::ImageViewKind::Owned(_) => panic!("Image view wasn't acquired from a surface"),
+        };
+        self.raw.present_surface(
+            &mut surface.raw,
+            image,
+            wait_semaphore.map(|semaphore| &semaphore.raw),
+        )
+    }
+
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        self.raw.wait_idle()?;
+        self.recorder.record(Call::QueueWaitIdle { queue: self.id });
+        Ok(())
+    }
+}
+
+/// Maps the memory of sparse binds, e.g. to its id or to the wrapped memory.
+fn map_sparse_binds<M: Copy, N>(
+    binds: &[memory::SparseBind<M>],
+    map: impl Fn(M) -> N,
+) -> Vec<memory::SparseBind<N>> {
+    binds
+        .iter()
+        .map(|bind| memory::SparseBind {
+            resource_offset: bind.resource_offset,
+            size: bind.size,
+            memory: bind.memory.map(|(memory, offset)| (map(memory), offset)),
+        })
+        .collect()
+}
+
+/// Maps the memory of sparse image binds, e.g. to its id or to the wrapped memory.
+fn map_sparse_image_binds<M: Copy, N>(
+    binds: &[memory::SparseImageBind<M>],
+    map: impl Fn(M) -> N,
+) -> Vec<memory::SparseImageBind<N>> {
+    binds
+        .iter()
+        .map(|bind| memory::SparseImageBind {
+            subresource: bind.subresource,
+            offset: bind.offset,
+            extent: bind.extent,
+            memory: bind.memory.map(|(memory, offset)| (map(memory), offset)),
+        })
+        .collect()
+}
+
+/// Trace command pool.
+#[derive(Debug)]
+pub struct CommandPool<B: hal::Backend> {
+    raw: B::CommandPool,
+    id: Id,
+    recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> pool::CommandPool<Traced<B>> for CommandPool<B> {
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources);
+        self.recorder.record(Call::ResetCommandPool {
+            pool: self.id,
+            release_resources,
+        });
+    }
+
+    unsafe fn allocate_one(&mut self, level: Level) -> CommandBuffer<B> {
+        let raw = self.raw.allocate_one(level);
+        let id = self.recorder.id();
+        self.recorder.record(Call::AllocateCommandBuffers {
+            pool: self.id,
+            level,
+            cmd_buffers: vec![id],
+        });
+        CommandBuffer::new(raw, id, Arc::clone(&self.recorder))
+    }
+
+    unsafe fn allocate<E>(&mut self, num: usize, level: Level, list: &mut E)
+    where
+        E: Extend<CommandBuffer<B>>,
+    {
+        let mut raw = Vec::with_capacity(num);
+        self.raw.allocate(num, level, &mut raw);
+        let cmd_buffers = raw
+            .into_iter()
+            .map(|raw| CommandBuffer::new(raw, self.recorder.id(), Arc::clone(&self.recorder)))
+            .collect::<Vec<_>>();
+        self.recorder.record(Call::AllocateCommandBuffers {
+            pool: self.id,
+            level,
+            cmd_buffers: cmd_buffers.iter().map(|cmd_buffer| cmd_buffer.id).collect(),
+        });
+        list.extend(cmd_buffers);
+    }
+
+    unsafe fn free<I>(&mut self, cmd_buffers: I)
+    where
+        I: IntoIterator<Item = CommandBuffer<B>>,
+    {
+        let cmd_buffers = cmd_buffers.into_iter().collect::<Vec<_>>();
+        self.recorder.record(Call::FreeCommandBuffers {
+            pool: self.id,
+            cmd_buffers: cmd_buffers.iter().map(|cmd_buffer| cmd_buffer.id).collect(),
+        });
+        self.raw
+            .free(cmd_buffers.into_iter().map(|cmd_buffer| cmd_buffer.raw))
+    }
+}
+
+/// Trace descriptor pool.
+#[derive(Debug)]
+pub struct DescriptorPool<B: hal::Backend> {
+    raw: B::DescriptorPool,
+    id: Id,
+    recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> pso::DescriptorPool<Traced<B>> for DescriptorPool<B> {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &Handle<B::DescriptorSetLayout>,
+    ) -> Result<Handle<B::DescriptorSet>, pso::AllocationError> {
+        let raw = self.raw.allocate_set(&layout.raw)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::AllocateDescriptorSets {
+            pool: self.id,
+            layouts: vec![layout.id],
+            sets: vec![id],
+        });
+        Ok(Handle { raw, id })
+    }
+
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &Handle<B::DescriptorSetLayout>,
+        count: pso::DescriptorArrayIndex,
+    ) -> Result<Handle<B::DescriptorSet>, pso::AllocationError> {
+        let raw = self.raw.allocate_variable_set(&layout.raw, count)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::AllocateVariableDescriptorSet {
+            pool: self.id,
+            layout: layout.id,
+            count,
+            set: id,
+        });
+        Ok(Handle { raw, id })
+    }
+
+    unsafe fn allocate<I, E>(
+        &mut self,
+        layouts: I,
+        list: &mut E,
+    ) -> Result<(), pso::AllocationError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Handle<B::DescriptorSetLayout>>,
+        E: Extend<Handle<B::DescriptorSet>>,
+    {
+        let layouts = layouts.into_iter().collect::<Vec<_>>();
+        let mut raw = Vec::with_capacity(layouts.len());
+        let result = self
+            .raw
+            .allocate(layouts.iter().map(|layout| &layout.borrow().raw), &mut raw);
+        let sets = raw
+            .into_iter()
+            .map(|raw| Handle {
+                raw,
+                id: self.recorder.id(),
+            })
+            .collect::<Vec<_>>();
+        // Only the leading layouts got a set if the allocation failed midway.
+        self.recorder.record(Call::AllocateDescriptorSets {
+            pool: self.id,
+            layouts: layouts
+                .iter()
+                .take(sets.len())
+                .map(|layout| layout.borrow().id)
+                .collect(),
+            sets: sets.iter().map(|set| set.id).collect(),
+        });
+        list.extend(sets);
+        result
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
+    where
+        I: IntoIterator<Item = Handle<B::DescriptorSet>>,
+    {
+        let sets = descriptor_sets.into_iter().collect::<Vec<_>>();
+        self.recorder.record(Call::FreeDescriptorSets {
+            pool: self.id,
+            sets: sets.iter().map(|set| set.id).collect(),
+        });
+        self.raw.free(sets.into_iter().map(|set| set.raw))
+    }
+
+    unsafe fn reset(&mut self) {
+        self.raw.reset();
+        self.recorder
+            .record(Call::ResetDescriptorPool { pool: self.id });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        command::CommandBuffer as _,
+        device::Device as _,
+        memory,
+        pool::CommandPool as _,
+        queue::CommandQueue as _,
+        Instance as _,
+    };
+    use std::iter;
+    use std::sync::Mutex;
+
+    /// Writer sharing the written trace with the test.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(data)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device<gfx_backend_empty::Backend>>();
+        foo::<CommandBuffer<gfx_backend_empty::Backend>>();
+        foo::<native::Memory<gfx_backend_empty::Backend>>();
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let trace = Shared::default();
+        let raw = gfx_backend_empty::Instance::create("test", 1).unwrap();
+        let instance = Instance::<gfx_backend_empty::Backend>::from_writer(raw, trace.clone());
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let mut buffer = device
+                .create_buffer(
+                    256,
+                    buffer::Usage::TRANSFER_DST,
+                    memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            device.set_buffer_name(&mut buffer, "target");
+            let requirements = device.get_buffer_requirements(&buffer);
+            let memory = device
+                .allocate_memory(hal::MemoryTypeId(1), requirements.size)
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+            let ptr = device.map_memory(&memory, memory::Segment::ALL).unwrap();
+            *ptr.offset(4) = 42;
+            device.unmap_memory(&memory);
+
+            let mut pool = device
+                .create_command_pool(
+                    gpu.queue_groups[0].family,
+                    pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let mut cmd_buffer = pool.allocate_one(Level::Primary);
+            cmd_buffer.begin_primary(hal::command::CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd_buffer.fill_buffer(&buffer, buffer::SubRange::WHOLE, 0);
+            cmd_buffer.finish();
+
+            let fence = device.create_fence(false).unwrap();
+            gpu.queue_groups[0].queues[0]
+                .submit_without_semaphores(iter::once(&cmd_buffer), Some(&fence));
+            device.wait_for_fence(&fence, !0).unwrap();
+
+            device.destroy_fence(fence);
+            pool.free(iter::once(cmd_buffer));
+            device.destroy_command_pool(pool);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+        drop(gpu);
+        drop(adapter);
+        drop(instance);
+
+        let trace = trace.0.lock().unwrap().clone();
+        let raw = gfx_backend_empty::Instance::create("test", 1).unwrap();
+        let mut replayer = replay::Replayer::<gfx_backend_empty::Backend>::new(&raw);
+        let count = unsafe { replayer.replay(trace.as_slice()) }.unwrap();
+        assert!(count > 0);
+        assert_eq!(replayer.device().unwrap().allocation_count(), 0);
+    }
+}
@@ -0,0 +1,73 @@
+//! Wrappers of the backend resources, identified in the trace by their id.
+
+use crate::format::Id;
+use crate::window::SwapchainImage;
+use std::borrow::Borrow;
+
+/// Resource of the wrapped backend, with the id referring to it in the trace.
+#[derive(Debug)]
+pub struct Handle<T> {
+    pub(crate) raw: T,
+    pub(crate) id: Id,
+}
+
+impl<T> Handle<T> {
+    /// Returns the wrapped resource.
+    pub fn raw(&self) -> &T {
+        &self.raw
+    }
+
+    /// Returns the id of the resource in the trace.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
+
+/// Memory allocation.
+#[derive(Debug)]
+pub struct Memory<B: hal::Backend> {
+    pub(crate) raw: B::Memory,
+    pub(crate) id: Id,
+    pub(crate) size: u64,
+}
+
+impl<B: hal::Backend> Memory<B> {
+    /// Returns the wrapped memory.
+    pub fn raw(&self) -> &B::Memory {
+        &self.raw
+    }
+
+    /// Returns the id of the memory in the trace.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum ImageViewKind<B: hal::Backend> {
+    Owned(B::ImageView),
+    /// Image acquired from a surface, which also serves as its view.
+    Swapchain(SwapchainImage<B>),
+}
+
+/// Image view, either created by the device or acquired from a surface.
+#[derive(Debug)]
+pub struct ImageView<B: hal::Backend> {
+    pub(crate) kind: ImageViewKind<B>,
+    pub(crate) id: Id,
+}
+
+impl<B: hal::Backend> ImageView<B> {
+    /// Returns the wrapped image view.
+    pub fn raw(&self) -> &B::ImageView {
+        match self.kind {
+            ImageViewKind::Owned(ref view) => view,
+            ImageViewKind::Swapchain(ref image) => image.borrow(),
+        }
+    }
+
+    /// Returns the id of the image view in the trace.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
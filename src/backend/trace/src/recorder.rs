@@ -0,0 +1,174 @@
+use crate::format::{Call, Header, Id};
+use hal::memory;
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+use std::slice;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+/// Mapped range of a memory object.
+#[derive(Debug)]
+struct Mapping {
+    /// Host address of the start of the range, stored as an integer to keep the recorder `Send`.
+    ptr: usize,
+    /// Offset of the range in the memory object.
+    offset: u64,
+    /// Contents of the range as last seen by the recorder.
+    shadow: Vec<u8>,
+}
+
+impl Mapping {
+    unsafe fn contents(&self) -> &[u8] {
+        slice::from_raw_parts(self.ptr as *const u8, self.shadow.len())
+    }
+
+    /// Returns the host writes since the last capture, updating the shadow copy.
+    unsafe fn capture(&mut self, memory: Id) -> Option<Call> {
+        let contents = self.contents();
+        let start = contents
+            .iter()
+            .zip(&self.shadow)
+            .position(|(a, b)| a != b)?;
+        let end = contents
+            .iter()
+            .zip(&self.shadow)
+            .rposition(|(a, b)| a != b)
+            .unwrap()
+            + 1;
+        let data = contents[start .. end].to_vec();
+        self.shadow[start .. end].copy_from_slice(&data);
+        Some(Call::WriteMemory {
+            memory,
+            offset: self.offset + start as u64,
+            data,
+        })
+    }
+}
+
+/// Destination of the recorded calls, shared by all the objects of an instance.
+pub(crate) struct Recorder {
+    /// `None` after a write error, which stops the recording.
+    writer: Mutex<Option<BufWriter<Box<dyn Write + Send>>>>,
+    next_id: AtomicU32,
+    /// Mapped memory objects, locked before the writer.
+    mappings: Mutex<HashMap<Id, Mapping>>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Recorder")
+    }
+}
+
+impl Recorder {
+    pub(crate) fn new(writer: Box<dyn Write + Send>) -> Self {
+        let recorder = Recorder {
+            writer: Mutex::new(Some(BufWriter::new(writer))),
+            next_id: AtomicU32::new(0),
+            mappings: Mutex::new(HashMap::new()),
+        };
+        recorder.write(&Header::CURRENT);
+        recorder
+    }
+
+    /// Returns a new object id.
+    pub(crate) fn id(&self) -> Id {
+        Id(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn write<T: serde::Serialize>(&self, value: &T) {
+        let mut guard = self.writer.lock().unwrap();
+        if let Some(ref mut writer) = *guard {
+            if let Err(e) = bincode::serialize_into(writer, value) {
+                error!("Failed to write the trace, stopping the recording: {}", e);
+                *guard = None;
+            }
+        }
+    }
+
+    pub(crate) fn record(&self, call: Call) {
+        self.write(&call);
+    }
+
+    /// Writes the buffered calls out, so that the trace is complete up to this point.
+    pub(crate) fn flush(&self) {
+        let mut guard = self.writer.lock().unwrap();
+        if let Some(ref mut writer) = *guard {
+            if let Err(e) = writer.flush() {
+                error!("Failed to write the trace, stopping the recording: {}", e);
+                *guard = None;
+            }
+        }
+    }
+
+    /// Starts tracking the host writes to a mapped range.
+    pub(crate) unsafe fn map(&self, memory: Id, ptr: *mut u8, offset: u64, size: u64) {
+        let mapping = Mapping {
+            ptr: ptr as usize,
+            offset,
+            shadow: slice::from_raw_parts(ptr as *const u8, size as usize).to_vec(),
+        };
+        self.mappings.lock().unwrap().insert(memory, mapping);
+    }
+
+    /// Captures the pending host writes to a memory, and stops tracking it.
+    pub(crate) unsafe fn unmap(&self, memory: Id) {
+        let mut mappings = self.mappings.lock().unwrap();
+        if let Some(mut mapping) = mappings.remove(&memory) {
+            if let Some(call) = mapping.capture(memory) {
+                self.record(call);
+            }
+        }
+    }
+
+    /// Captures the pending host writes to the given memories.
+    pub(crate) unsafe fn capture<I>(&self, memories: I)
+    where
+        I: IntoIterator<Item = Id>,
+    {
+        let mut mappings = self.mappings.lock().unwrap();
+        for memory in memories {
+            if let Some(call) = mappings
+                .get_mut(&memory)
+                .and_then(|mapping| mapping.capture(memory))
+            {
+                self.record(call);
+            }
+        }
+    }
+
+    /// Captures the pending host writes to all the mapped memories.
+    ///
+    /// Coherent memory doesn't need to be flushed, so this is done before every submission.
+    pub(crate) unsafe fn capture_all(&self) {
+        let mut mappings = self.mappings.lock().unwrap();
+        let mut ids = mappings.keys().cloned().collect::<Vec<_>>();
+        ids.sort();
+        for memory in ids {
+            if let Some(call) = mappings.get_mut(&memory).unwrap().capture(memory) {
+                self.record(call);
+            }
+        }
+    }
+
+    /// Refreshes the shadow copies after the device wrote to the given memories.
+    pub(crate) unsafe fn invalidate<I>(&self, memories: I)
+    where
+        I: IntoIterator<Item = Id>,
+    {
+        let mut mappings = self.mappings.lock().unwrap();
+        for memory in memories {
+            if let Some(mapping) = mappings.get_mut(&memory) {
+                let contents = mapping.contents().to_vec();
+                mapping.shadow = contents;
+            }
+        }
+    }
+}
+
+/// Returns the size of a segment of a memory object with the given size.
+pub(crate) fn segment_size(segment: &memory::Segment, memory_size: u64) -> u64 {
+    segment
+        .size
+        .unwrap_or_else(|| memory_size.saturating_sub(segment.offset))
+}
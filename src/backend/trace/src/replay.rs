@@ -0,0 +1,1788 @@
+//! Replay of traces on any backend.
+//!
+//! The recorded objects are recreated on the device of the replaying backend, and the
+//! recorded ids are mapped to them. Memory types and queue families are matched by
+//! their properties, so a trace can be replayed on a different adapter than the one
+//! it was recorded on, as long as it provides the same capabilities.
+//!
+//! Presentation is emulated with offscreen images: surfaces and swapchains are backed
+//! by images of the configured size and format, and presenting only waits for the
+//! given semaphores.
+
+use crate::format::{self as f, Call, Command, Header, Id, MAGIC, VERSION};
+use hal::{
+    adapter::{Adapter, MemoryProperties, PhysicalDevice as _},
+    buffer,
+    command::{CommandBuffer as _, CommandBufferInheritanceInfo},
+    device::Device as _,
+    format,
+    image,
+    memory,
+    pass,
+    pool::CommandPool as _,
+    pso::{self, DescriptorPool as _},
+    queue::{CommandQueue as _, QueueFamily as _, QueueFamilyId, SparseBinding, Submission},
+    window,
+    Instance as _,
+    MemoryTypeId,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read};
+use std::{error, fmt, iter};
+
+/// Error replaying a trace.
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read the trace.
+    Io(io::Error),
+    /// Failed to decode a call.
+    Decode(bincode::Error),
+    /// The trace doesn't start with a valid header.
+    InvalidHeader,
+    /// The trace was written with an unsupported version of the format.
+    UnsupportedVersion(u32),
+    /// The call refers to an object which doesn't exist.
+    MissingObject(Id),
+    /// Host writes are replayed into memory which isn't mapped.
+    NotMapped(Id),
+    /// The call requires a device, but none was opened.
+    NoDevice,
+    /// The backend doesn't expose any adapter.
+    NoAdapter,
+    /// No queue family of the adapter matches a recorded one.
+    NoQueueFamily(QueueFamilyId),
+    /// No memory type of the adapter has the properties of a recorded one.
+    NoMemoryType(memory::Properties),
+    /// A call failed on the replaying backend.
+    Failed { call: &'static str, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "failed to read the trace: {}", e),
+            Error::Decode(ref e) => write!(f, "failed to decode the trace: {}", e),
+            Error::InvalidHeader => write!(f, "not a trace"),
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "trace version {} is not supported, expected {}",
+                version, VERSION
+            ),
+            Error::MissingObject(id) => write!(f, "object {} doesn't exist", id.0),
+            Error::NotMapped(id) => write!(f, "memory {} is not mapped", id.0),
+            Error::NoDevice => write!(f, "no device is open"),
+            Error::NoAdapter => write!(f, "no adapter is available"),
+            Error::NoQueueFamily(id) => write!(f, "no queue family matches family {}", id.0),
+            Error::NoMemoryType(properties) => {
+                write!(f, "no memory type has the properties {:?}", properties)
+            }
+            Error::Failed { call, ref message } => write!(f, "{} failed: {}", call, message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::Decode(e)
+    }
+}
+
+fn failed<E: fmt::Debug>(call: &'static str) -> impl FnOnce(E) -> Error {
+    move |e| Error::Failed {
+        call,
+        message: format!("{:?}", e),
+    }
+}
+
+fn get<T>(objects: &HashMap<Id, T>, id: Id) -> Result<&T, Error> {
+    objects.get(&id).ok_or(Error::MissingObject(id))
+}
+
+fn get_mut<T>(objects: &mut HashMap<Id, T>, id: Id) -> Result<&mut T, Error> {
+    objects.get_mut(&id).ok_or(Error::MissingObject(id))
+}
+
+fn take<T>(objects: &mut HashMap<Id, T>, id: Id) -> Result<T, Error> {
+    objects.remove(&id).ok_or(Error::MissingObject(id))
+}
+
+/// Reads and checks the header of a trace.
+pub fn read_header<R: Read>(reader: R) -> Result<Header, Error> {
+    let header: Header = bincode::deserialize_from(reader)?;
+    if header.magic != MAGIC {
+        return Err(Error::InvalidHeader);
+    }
+    if header.version != VERSION {
+        return Err(Error::UnsupportedVersion(header.version));
+    }
+    Ok(header)
+}
+
+/// Offscreen image standing in for a presentable one.
+#[derive(Debug)]
+struct Offscreen<B: hal::Backend> {
+    image: B::Image,
+    memory: B::Memory,
+    config: window::SwapchainConfig,
+}
+
+/// Replays traces on the backend `B`.
+///
+/// A single device can be opened by the trace.
+#[derive(Debug)]
+pub struct Replayer<B: hal::Backend> {
+    adapters: Vec<Adapter<B>>,
+    device: Option<B::Device>,
+    memory_properties: MemoryProperties,
+    /// Replaying memory type of each recorded one.
+    memory_types: Vec<MemoryTypeId>,
+    /// Replaying queue family of each recorded one.
+    families: HashMap<QueueFamilyId, QueueFamilyId>,
+
+    queues: HashMap<Id, B::CommandQueue>,
+    command_pools: HashMap<Id, B::CommandPool>,
+    command_buffers: HashMap<Id, B::CommandBuffer>,
+    memories: HashMap<Id, B::Memory>,
+    /// Mapped memories, with the host address and the offset of the mapped segment.
+    mapped: HashMap<Id, (*mut u8, u64)>,
+    render_passes: HashMap<Id, B::RenderPass>,
+    pipeline_layouts: HashMap<Id, B::PipelineLayout>,
+    pipeline_caches: HashMap<Id, B::PipelineCache>,
+    graphics_pipelines: HashMap<Id, B::GraphicsPipeline>,
+    compute_pipelines: HashMap<Id, B::ComputePipeline>,
+    framebuffers: HashMap<Id, B::Framebuffer>,
+    shader_modules: HashMap<Id, B::ShaderModule>,
+    buffers: HashMap<Id, B::Buffer>,
+    buffer_views: HashMap<Id, B::BufferView>,
+    images: HashMap<Id, B::Image>,
+    image_views: HashMap<Id, B::ImageView>,
+    samplers: HashMap<Id, B::Sampler>,
+    descriptor_pools: HashMap<Id, B::DescriptorPool>,
+    descriptor_set_layouts: HashMap<Id, B::DescriptorSetLayout>,
+    descriptor_sets: HashMap<Id, B::DescriptorSet>,
+    semaphores: HashMap<Id, B::Semaphore>,
+    fences: HashMap<Id, B::Fence>,
+    events: HashMap<Id, B::Event>,
+    query_pools: HashMap<Id, B::QueryPool>,
+
+    /// Offscreen image of each surface, once configured.
+    surfaces: HashMap<Id, Option<Offscreen<B>>>,
+    /// Offscreen images of each swapchain, by image id.
+    swapchains: HashMap<Id, Vec<(Id, B::Memory)>>,
+}
+
+impl<B: hal::Backend> Replayer<B> {
+    /// Create a replayer using the adapters of `instance`.
+    pub fn new(instance: &B::Instance) -> Self {
+        Replayer {
+            adapters: instance.enumerate_adapters(),
+            device: None,
+            memory_properties: MemoryProperties {
+                memory_types: Vec::new(),
+                memory_heaps: Vec::new(),
+            },
+            memory_types: Vec::new(),
+            families: HashMap::new(),
+            queues: HashMap::new(),
+            command_pools: HashMap::new(),
+            command_buffers: HashMap::new(),
+            memories: HashMap::new(),
+            mapped: HashMap::new(),
+            render_passes: HashMap::new(),
+            pipeline_layouts: HashMap::new(),
+            pipeline_caches: HashMap::new(),
+            graphics_pipelines: HashMap::new(),
+            compute_pipelines: HashMap::new(),
+            framebuffers: HashMap::new(),
+            shader_modules: HashMap::new(),
+            buffers: HashMap::new(),
+            buffer_views: HashMap::new(),
+            images: HashMap::new(),
+            image_views: HashMap::new(),
+            samplers: HashMap::new(),
+            descriptor_pools: HashMap::new(),
+            descriptor_set_layouts: HashMap::new(),
+            descriptor_sets: HashMap::new(),
+            semaphores: HashMap::new(),
+            fences: HashMap::new(),
+            events: HashMap::new(),
+            query_pools: HashMap::new(),
+            surfaces: HashMap::new(),
+            swapchains: HashMap::new(),
+        }
+    }
+
+    /// Returns the device opened by the trace.
+    pub fn device(&self) -> Option<&B::Device> {
+        self.device.as_ref()
+    }
+
+    /// Replays a whole trace, returning the number of replayed calls.
+    ///
+    /// # Safety
+    ///
+    /// The trace must have been recorded from a valid usage of the API, see `execute`.
+    pub unsafe fn replay<R: Read>(&mut self, reader: R) -> Result<usize, Error> {
+        let mut reader = BufReader::new(reader);
+        read_header(&mut reader)?;
+        let mut count = 0;
+        while !reader.fill_buf()?.is_empty() {
+            let call = bincode::deserialize_from(&mut reader)?;
+            self.execute(call)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn memory_type(&self, recorded: MemoryTypeId) -> Result<MemoryTypeId, Error> {
+        self.memory_types
+            .get(recorded.0)
+            .cloned()
+            .ok_or(Error::NoMemoryType(memory::Properties::empty()))
+    }
+
+    fn family(&self, recorded: QueueFamilyId) -> QueueFamilyId {
+        self.families.get(&recorded).cloned().unwrap_or(recorded)
+    }
+
+    unsafe fn open(
+        &mut self,
+        adapter: usize,
+        families: Vec<f::QueueFamily>,
+        features: hal::Features,
+        recorded_properties: MemoryProperties,
+    ) -> Result<(), Error> {
+        let adapter = match self.adapters.get(adapter) {
+            Some(adapter) => adapter,
+            None => self.adapters.first().ok_or(Error::NoAdapter)?,
+        };
+
+        let mut requests = Vec::new();
+        for family in &families {
+            let replaying = adapter
+                .queue_families
+                .iter()
+                .filter(|qf| {
+                    qf.queue_type() == family.ty && qf.max_queues() >= family.priorities.len()
+                })
+                .min_by_key(|qf| qf.id() != family.id)
+                .ok_or(Error::NoQueueFamily(family.id))?;
+            self.families.insert(family.id, replaying.id());
+            requests.push((replaying, family.priorities.as_slice()));
+        }
+
+        self.memory_properties = adapter.physical_device.memory_properties();
+        self.memory_types = recorded_properties
+            .memory_types
+            .iter()
+            .map(|recorded| {
+                let types = &self.memory_properties.memory_types;
+                types
+                    .iter()
+                    .position(|ty| ty.properties == recorded.properties)
+                    .or_else(|| {
+                        types
+                            .iter()
+                            .position(|ty| ty.properties.contains(recorded.properties))
+                    })
+                    .map(MemoryTypeId)
+                    .ok_or(Error::NoMemoryType(recorded.properties))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut gpu = adapter
+            .physical_device
+            .open(&requests, features)
+            .map_err(failed("PhysicalDevice::open"))?;
+        for family in families {
+            let replaying = self.families[&family.id];
+            let group = gpu
+                .queue_groups
+                .iter_mut()
+                .find(|group| group.family == replaying)
+                .ok_or(Error::NoQueueFamily(family.id))?;
+            for id in family.queues {
+                if group.queues.is_empty() {
+                    return Err(Error::NoQueueFamily(family.id));
+                }
+                self.queues.insert(id, group.queues.remove(0));
+            }
+        }
+        self.device = Some(gpu.device);
+        Ok(())
+    }
+
+    /// Creates an image standing in for a presentable one.
+    unsafe fn create_offscreen(
+        &self,
+        config: &window::SwapchainConfig,
+    ) -> Result<(B::Image, B::Memory), Error> {
+        let device = self.device.as_ref().ok_or(Error::NoDevice)?;
+        let mut image = device
+            .create_image(
+                image::Kind::D2(config.extent.width, config.extent.height, 1, 1),
+                1,
+                config.format,
+                image::Tiling::Optimal,
+                config.image_usage,
+                image::ViewCapabilities::empty(),
+                memory::SparseFlags::empty(),
+            )
+            .map_err(failed("Device::create_image"))?;
+        let requirements = device.get_image_requirements(&image);
+        let types = &self.memory_properties.memory_types;
+        let allowed = |&(id, _): &(usize, _)| requirements.type_mask & (1 << id) != 0;
+        let memory_type = types
+            .iter()
+            .enumerate()
+            .filter(allowed)
+            .find(|(_, ty)| ty.properties.contains(memory::Properties::DEVICE_LOCAL))
+            .or_else(|| types.iter().enumerate().find(allowed))
+            .map(|(id, _)| MemoryTypeId(id))
+            .ok_or(Error::NoMemoryType(memory::Properties::DEVICE_LOCAL))?;
+        let memory = device
+            .allocate_memory(memory_type, requirements.size)
+            .map_err(failed("Device::allocate_memory"))?;
+        device
+            .bind_image_memory(&memory, 0, &mut image)
+            .map_err(failed("Device::bind_image_memory"))?;
+        Ok((image, memory))
+    }
+
+    unsafe fn destroy_offscreen(&self, image: B::Image, memory: B::Memory) -> Result<(), Error> {
+        let device = self.device.as_ref().ok_or(Error::NoDevice)?;
+        device.destroy_image(image);
+        device.free_memory(memory);
+        Ok(())
+    }
+
+    unsafe fn destroy_swapchain(&mut self, swapchain: Id) -> Result<(), Error> {
+        for (image, memory) in take(&mut self.swapchains, swapchain)? {
+            let image = take(&mut self.images, image)?;
+            self.destroy_offscreen(image, memory)?;
+        }
+        Ok(())
+    }
+
+    /// Waits for and signals semaphores and fences, without executing any command.
+    unsafe fn sync(
+        &mut self,
+        queue: Option<Id>,
+        wait_semaphores: &[Id],
+        signal_semaphores: &[Id],
+        fence: Option<Id>,
+    ) -> Result<(), Error> {
+        if wait_semaphores.is_empty() && signal_semaphores.is_empty() && fence.is_none() {
+            return Ok(());
+        }
+        let queue_id = match queue {
+            Some(id) => id,
+            None => *self.queues.keys().min().ok_or(Error::NoDevice)?,
+        };
+        let semaphores = &self.semaphores;
+        let wait_semaphores = wait_semaphores
+            .iter()
+            .map(|&id| Ok((get(semaphores, id)?, 0, pso::PipelineStage::BOTTOM_OF_PIPE)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let signal_semaphores = signal_semaphores
+            .iter()
+            .map(|&id| Ok((get(semaphores, id)?, 0)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let fence = match fence {
+            Some(id) => Some(get(&self.fences, id)?),
+            None => None,
+        };
+        get_mut(&mut self.queues, queue_id)?.submit(
+            Submission {
+                command_buffers: iter::empty::<&B::CommandBuffer>(),
+                wait_semaphores,
+                signal_semaphores,
+            },
+            fence,
+        );
+        Ok(())
+    }
+
+    fn entry_point<'a>(
+        &'a self,
+        entry: &'a f::EntryPoint,
+    ) -> Result<pso::EntryPoint<'a, B>, Error> {
+        Ok(pso::EntryPoint {
+            entry: &entry.entry,
+            module: get(&self.shader_modules, entry.module)?,
+            specialization: pso::Specialization {
+                constants: Cow::Borrowed(&entry.specialization.constants),
+                data: Cow::Borrowed(&entry.specialization.data),
+            },
+        })
+    }
+
+    fn optional_entry_point<'a>(
+        &'a self,
+        entry: &'a Option<f::EntryPoint>,
+    ) -> Result<Option<pso::EntryPoint<'a, B>>, Error> {
+        entry
+            .as_ref()
+            .map(|entry| self.entry_point(entry))
+            .transpose()
+    }
+
+    fn base_pipeline<'a, P>(
+        pipelines: &'a HashMap<Id, P>,
+        parent: f::BasePipeline,
+    ) -> Result<pso::BasePipeline<'a, P>, Error> {
+        Ok(match parent {
+            f::BasePipeline::Pipeline(id) => pso::BasePipeline::Pipeline(get(pipelines, id)?),
+            f::BasePipeline::Index(index) => pso::BasePipeline::Index(index),
+            f::BasePipeline::None => pso::BasePipeline::None,
+        })
+    }
+
+    fn barrier<'a>(&'a self, barrier: &f::Barrier) -> Result<memory::Barrier<'a, B>, Error> {
+        let families = |families: &Option<std::ops::Range<QueueFamilyId>>| {
+            families
+                .as_ref()
+                .map(|range| self.family(range.start) .. self.family(range.end))
+        };
+        Ok(match *barrier {
+            f::Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+            f::Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+            f::Barrier::Buffer {
+                ref states,
+                target,
+                ref range,
+                families: ref fam,
+            } => memory::Barrier::Buffer {
+                states: states.clone(),
+                target: get(&self.buffers, target)?,
+                range: range.clone(),
+                families: families(fam),
+            },
+            f::Barrier::Image {
+                ref states,
+                target,
+                ref range,
+                families: ref fam,
+            } => memory::Barrier::Image {
+                states: states.clone(),
+                target: get(&self.images, target)?,
+                range: range.clone(),
+                families: families(fam),
+            },
+        })
+    }
+
+    fn barriers<'a>(
+        &'a self,
+        barriers: &[f::Barrier],
+    ) -> Result<Vec<memory::Barrier<'a, B>>, Error> {
+        barriers
+            .iter()
+            .map(|barrier| self.barrier(barrier))
+            .collect()
+    }
+
+    fn descriptor<'a>(
+        &'a self,
+        descriptor: &f::Descriptor,
+    ) -> Result<pso::Descriptor<'a, B>, Error> {
+        Ok(match *descriptor {
+            f::Descriptor::Sampler(sampler) => {
+                pso::Descriptor::Sampler(get(&self.samplers, sampler)?)
+            }
+            f::Descriptor::Image(view, layout) => {
+                pso::Descriptor::Image(get(&self.image_views, view)?, layout)
+            }
+            f::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+                pso::Descriptor::CombinedImageSampler(
+                    get(&self.image_views, view)?,
+                    layout,
+                    get(&self.samplers, sampler)?,
+                )
+            }
+            f::Descriptor::Buffer(buffer, ref range) => {
+                pso::Descriptor::Buffer(get(&self.buffers, buffer)?, range.clone())
+            }
+            f::Descriptor::TexelBuffer(view) => {
+                pso::Descriptor::TexelBuffer(get(&self.buffer_views, view)?)
+            }
+        })
+    }
+
+    fn push_writes<'a>(
+        &'a self,
+        writes: &[f::DescriptorWrite],
+    ) -> Result<Vec<pso::DescriptorWrite<Vec<pso::Descriptor<'a, B>>>>, Error> {
+        writes
+            .iter()
+            .map(|write| {
+                Ok(pso::DescriptorWrite {
+                    binding: write.binding,
+                    array_offset: write.array_offset,
+                    descriptors: write
+                        .descriptors
+                        .iter()
+                        .map(|descriptor| self.descriptor(descriptor))
+                        .collect::<Result<Vec<_>, Error>>()?,
+                })
+            })
+            .collect()
+    }
+
+    fn sparse_binds<'a>(
+        memories: &'a HashMap<Id, B::Memory>,
+        binds: &[memory::SparseBind<Id>],
+    ) -> Result<Vec<memory::SparseBind<&'a B::Memory>>, Error> {
+        binds
+            .iter()
+            .map(|bind| {
+                Ok(memory::SparseBind {
+                    resource_offset: bind.resource_offset,
+                    size: bind.size,
+                    memory: match bind.memory {
+                        Some((id, offset)) => Some((get(memories, id)?, offset)),
+                        None => None,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn sparse_image_binds<'a>(
+        memories: &'a HashMap<Id, B::Memory>,
+        binds: &[memory::SparseImageBind<Id>],
+    ) -> Result<Vec<memory::SparseImageBind<&'a B::Memory>>, Error> {
+        binds
+            .iter()
+            .map(|bind| {
+                Ok(memory::SparseImageBind {
+                    subresource: bind.subresource,
+                    offset: bind.offset,
+                    extent: bind.extent,
+                    memory: match bind.memory {
+                        Some((id, offset)) => Some((get(memories, id)?, offset)),
+                        None => None,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn ids<'a, T>(objects: &'a HashMap<Id, T>, ids: &[Id]) -> Result<Vec<&'a T>, Error> {
+        ids.iter().map(|&id| get(objects, id)).collect()
+    }
+
+    /// Replays a single call.
+    ///
+    /// # Safety
+    ///
+    /// The call must be valid in the state left by the previous ones, as it was when recorded.
+    /// Traces recorded from an invalid usage of the API are replayed as is.
+    pub unsafe fn execute(&mut self, call: Call) -> Result<(), Error> {
+        if let Call::Open {
+            adapter,
+            families,
+            features,
+            memory_properties,
+        } = call
+        {
+            return self.open(adapter, families, features, memory_properties);
+        }
+        if let Call::Record {
+            cmd_buffer,
+            command,
+        } = call
+        {
+            return self.record(cmd_buffer, command);
+        }
+
+        let device = self.device.as_ref().ok_or(Error::NoDevice)?;
+        match call {
+            Call::Open { .. } | Call::Record { .. } => unreachable!(),
+            Call::CreateSurface { surface } => {
+                self.surfaces.insert(surface, None);
+            }
+            Call::DestroySurface { surface } => {
+                if let Some(offscreen) = take(&mut self.surfaces, surface)? {
+                    self.destroy_offscreen(offscreen.image, offscreen.memory)?;
+                }
+            }
+            Call::AllocateMemory {
+                memory,
+                memory_type,
+                size,
+            } => {
+                let raw = device
+                    .allocate_memory(self.memory_type(memory_type)?, size)
+                    .map_err(failed("Device::allocate_memory"))?;
+                self.memories.insert(memory, raw);
+            }
+            Call::FreeMemory { memory } => {
+                self.mapped.remove(&memory);
+                device.free_memory(take(&mut self.memories, memory)?);
+            }
+            Call::CreateCommandPool {
+                pool,
+                family,
+                flags,
+            } => {
+                let raw = device
+                    .create_command_pool(self.family(family), flags)
+                    .map_err(failed("Device::create_command_pool"))?;
+                self.command_pools.insert(pool, raw);
+            }
+            Call::DestroyCommandPool { pool } => {
+                device.destroy_command_pool(take(&mut self.command_pools, pool)?);
+            }
+            Call::CreateRenderPass {
+                render_pass,
+                attachments,
+                subpasses,
+                dependencies,
+                correlation_masks,
+            } => {
+                let subpasses = subpasses.iter().map(|subpass| pass::SubpassDesc {
+                    colors: &subpass.colors,
+                    depth_stencil: subpass.depth_stencil.as_ref(),
+                    inputs: &subpass.inputs,
+                    resolves: &subpass.resolves,
+                    preserves: &subpass.preserves,
+                    view_mask: subpass.view_mask,
+                });
+                let raw = device
+                    .create_render_pass(&attachments, subpasses, &dependencies, &correlation_masks)
+                    .map_err(failed("Device::create_render_pass"))?;
+                self.render_passes.insert(render_pass, raw);
+            }
+            Call::DestroyRenderPass { render_pass } => {
+                device.destroy_render_pass(take(&mut self.render_passes, render_pass)?);
+            }
+            Call::CreatePipelineLayout {
+                layout,
+                set_layouts,
+                push_constants,
+            } => {
+                let raw = device
+                    .create_pipeline_layout(
+                        Self::ids(&self.descriptor_set_layouts, &set_layouts)?,
+                        &push_constants,
+                    )
+                    .map_err(failed("Device::create_pipeline_layout"))?;
+                self.pipeline_layouts.insert(layout, raw);
+            }
+            Call::DestroyPipelineLayout { layout } => {
+                device.destroy_pipeline_layout(take(&mut self.pipeline_layouts, layout)?);
+            }
+            Call::CreatePipelineCache { cache, data } => {
+                let raw = device
+                    .create_pipeline_cache(data.as_deref())
+                    .map_err(failed("Device::create_pipeline_cache"))?;
+                self.pipeline_caches.insert(cache, raw);
+            }
+            Call::MergePipelineCaches { target, sources } => {
+                device
+                    .merge_pipeline_caches(
+                        get(&self.pipeline_caches, target)?,
+                        Self::ids(&self.pipeline_caches, &sources)?,
+                    )
+                    .map_err(failed("Device::merge_pipeline_caches"))?;
+            }
+            Call::DestroyPipelineCache { cache } => {
+                device.destroy_pipeline_cache(take(&mut self.pipeline_caches, cache)?);
+            }
+            Call::CreateGraphicsPipelines {
+                pipelines,
+                descs,
+                cache,
+            } => {
+                let raw_descs = descs
+                    .iter()
+                    .map(|desc| {
+                        let shaders = &desc.shaders;
+                        Ok(pso::GraphicsPipelineDesc {
+                            shaders: pso::GraphicsShaderSet {
+                                vertex: self.entry_point(&shaders.vertex)?,
+                                hull: self.optional_entry_point(&shaders.hull)?,
+                                domain: self.optional_entry_point(&shaders.domain)?,
+                                geometry: self.optional_entry_point(&shaders.geometry)?,
+                                fragment: self.optional_entry_point(&shaders.fragment)?,
+                            },
+                            rasterizer: desc.rasterizer,
+                            vertex_buffers: desc.vertex_buffers.clone(),
+                            attributes: desc.attributes.clone(),
+                            input_assembler: desc.input_assembler.clone(),
+                            blender: desc.blender.clone(),
+                            depth_stencil: desc.depth_stencil,
+                            multisampling: desc.multisampling.clone(),
+                            baked_states: desc.baked_states.clone(),
+                            layout: get(&self.pipeline_layouts, desc.layout)?,
+                            subpass: pass::Subpass {
+                                index: desc.subpass.1,
+                                main_pass: get(&self.render_passes, desc.subpass.0)?,
+                            },
+                            flags: desc.flags,
+                            parent: Self::base_pipeline(&self.graphics_pipelines, desc.parent)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let cache = match cache {
+                    Some(id) => Some(get(&self.pipeline_caches, id)?),
+                    None => None,
+                };
+                let results = device.create_graphics_pipelines(&raw_descs, cache);
+                drop(raw_descs);
+                for (id, result) in pipelines.into_iter().zip(results) {
+                    match (id, result) {
+                        (Some(id), Ok(raw)) => {
+                            self.graphics_pipelines.insert(id, raw);
+                        }
+                        (Some(_), Err(e)) => {
+                            return Err(failed("Device::create_graphics_pipelines")(e))
+                        }
+                        (None, Ok(raw)) => device.destroy_graphics_pipeline(raw),
+                        (None, Err(_)) => {}
+                    }
+                }
+            }
+            Call::DestroyGraphicsPipeline { pipeline } => {
+                device.destroy_graphics_pipeline(take(&mut self.graphics_pipelines, pipeline)?);
+            }
+            Call::CreateComputePipelines {
+                pipelines,
+                descs,
+                cache,
+            } => {
+                let raw_descs = descs
+                    .iter()
+                    .map(|desc| {
+                        Ok(pso::ComputePipelineDesc {
+                            shader: self.entry_point(&desc.shader)?,
+                            layout: get(&self.pipeline_layouts, desc.layout)?,
+                            flags: desc.flags,
+                            parent: Self::base_pipeline(&self.compute_pipelines, desc.parent)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let cache = match cache {
+                    Some(id) => Some(get(&self.pipeline_caches, id)?),
+                    None => None,
+                };
+                let results = device.create_compute_pipelines(&raw_descs, cache);
+                drop(raw_descs);
+                for (id, result) in pipelines.into_iter().zip(results) {
+                    match (id, result) {
+                        (Some(id), Ok(raw)) => {
+                            self.compute_pipelines.insert(id, raw);
+                        }
+                        (Some(_), Err(e)) => {
+                            return Err(failed("Device::create_compute_pipelines")(e))
+                        }
+                        (None, Ok(raw)) => device.destroy_compute_pipeline(raw),
+                        (None, Err(_)) => {}
+                    }
+                }
+            }
+            Call::DestroyComputePipeline { pipeline } => {
+                device.destroy_compute_pipeline(take(&mut self.compute_pipelines, pipeline)?);
+            }
+            Call::CreateFramebuffer {
+                framebuffer,
+                render_pass,
+                attachments,
+                extent,
+            } => {
+                let raw = device
+                    .create_framebuffer(
+                        get(&self.render_passes, render_pass)?,
+                        Self::ids(&self.image_views, &attachments)?,
+                        extent,
+                    )
+                    .map_err(failed("Device::create_framebuffer"))?;
+                self.framebuffers.insert(framebuffer, raw);
+            }
+            Call::DestroyFramebuffer { framebuffer } => {
+                device.destroy_framebuffer(take(&mut self.framebuffers, framebuffer)?);
+            }
+            Call::CreateShaderModule { module, spirv } => {
+                let raw = device
+                    .create_shader_module(&spirv)
+                    .map_err(failed("Device::create_shader_module"))?;
+                self.shader_modules.insert(module, raw);
+            }
+            Call::DestroyShaderModule { module } => {
+                device.destroy_shader_module(take(&mut self.shader_modules, module)?);
+            }
+            Call::CreateBuffer {
+                buffer,
+                size,
+                usage,
+                sparse,
+            } => {
+                let raw = device
+                    .create_buffer(size, usage, sparse)
+                    .map_err(failed("Device::create_buffer"))?;
+                self.buffers.insert(buffer, raw);
+            }
+            Call::BindBufferMemory {
+                memory,
+                offset,
+                buffer,
+            } => {
+                device
+                    .bind_buffer_memory(
+                        get(&self.memories, memory)?,
+                        offset,
+                        get_mut(&mut self.buffers, buffer)?,
+                    )
+                    .map_err(failed("Device::bind_buffer_memory"))?;
+            }
+            Call::DestroyBuffer { buffer } => {
+                device.destroy_buffer(take(&mut self.buffers, buffer)?);
+            }
+            Call::CreateBufferView {
+                view,
+                buffer,
+                format,
+                range,
+            } => {
+                let raw = device
+                    .create_buffer_view(get(&self.buffers, buffer)?, format, range)
+                    .map_err(failed("Device::create_buffer_view"))?;
+                self.buffer_views.insert(view, raw);
+            }
+            Call::DestroyBufferView { view } => {
+                device.destroy_buffer_view(take(&mut self.buffer_views, view)?);
+            }
+            Call::CreateImage {
+                image,
+                kind,
+                mip_levels,
+                format,
+                tiling,
+                usage,
+                view_caps,
+                sparse,
+            } => {
+                let raw = device
+                    .create_image(kind, mip_levels, format, tiling, usage, view_caps, sparse)
+                    .map_err(failed("Device::create_image"))?;
+                self.images.insert(image, raw);
+            }
+            Call::BindImageMemory {
+                memory,
+                offset,
+                image,
+            } => {
+                device
+                    .bind_image_memory(
+                        get(&self.memories, memory)?,
+                        offset,
+                        get_mut(&mut self.images, image)?,
+                    )
+                    .map_err(failed("Device::bind_image_memory"))?;
+            }
+            Call::DestroyImage { image } => {
+                device.destroy_image(take(&mut self.images, image)?);
+            }
+            Call::CreateImageView {
+                view,
+                image,
+                view_kind,
+                format,
+                swizzle,
+                range,
+            } => {
+                let raw = device
+                    .create_image_view(get(&self.images, image)?, view_kind, format, swizzle, range)
+                    .map_err(failed("Device::create_image_view"))?;
+                self.image_views.insert(view, raw);
+            }
+            Call::DestroyImageView { view } => {
+                device.destroy_image_view(take(&mut self.image_views, view)?);
+            }
+            Call::CreateSampler { sampler, desc } => {
+                let raw = device
+                    .create_sampler(&desc)
+                    .map_err(failed("Device::create_sampler"))?;
+                self.samplers.insert(sampler, raw);
+            }
+            Call::DestroySampler { sampler } => {
+                device.destroy_sampler(take(&mut self.samplers, sampler)?);
+            }
+            Call::CreateDescriptorPool {
+                pool,
+                max_sets,
+                ranges,
+                flags,
+            } => {
+                let raw = device
+                    .create_descriptor_pool(max_sets, &ranges, flags)
+                    .map_err(failed("Device::create_descriptor_pool"))?;
+                self.descriptor_pools.insert(pool, raw);
+            }
+            Call::DestroyDescriptorPool { pool } => {
+                device.destroy_descriptor_pool(take(&mut self.descriptor_pools, pool)?);
+            }
+            Call::CreateDescriptorSetLayout {
+                layout,
+                bindings,
+                immutable_samplers,
+                flags,
+            } => {
+                let raw = device
+                    .create_descriptor_set_layout(
+                        &bindings,
+                        Self::ids(&self.samplers, &immutable_samplers)?,
+                        flags,
+                    )
+                    .map_err(failed("Device::create_descriptor_set_layout"))?;
+                self.descriptor_set_layouts.insert(layout, raw);
+            }
+            Call::DestroyDescriptorSetLayout { layout } => {
+                device
+                    .destroy_descriptor_set_layout(take(&mut self.descriptor_set_layouts, layout)?);
+            }
+            Call::WriteDescriptorSets(writes) => {
+                let writes = writes
+                    .iter()
+                    .map(|write| {
+                        let descriptors = write
+                            .descriptors
+                            .iter()
+                            .map(|descriptor| self.descriptor(descriptor))
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        Ok(pso::DescriptorSetWrite {
+                            set: get(&self.descriptor_sets, write.set)?,
+                            binding: write.binding,
+                            array_offset: write.array_offset,
+                            descriptors,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                device.write_descriptor_sets(writes);
+            }
+            Call::CopyDescriptorSets(copies) => {
+                let copies = copies
+                    .iter()
+                    .map(|copy| {
+                        Ok(pso::DescriptorSetCopy {
+                            src_set: get(&self.descriptor_sets, copy.src_set)?,
+                            src_binding: copy.src_binding,
+                            src_array_offset: copy.src_array_offset,
+                            dst_set: get(&self.descriptor_sets, copy.dst_set)?,
+                            dst_binding: copy.dst_binding,
+                            dst_array_offset: copy.dst_array_offset,
+                            count: copy.count,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                device.copy_descriptor_sets(copies);
+            }
+            Call::MapMemory { memory, segment } => {
+                let offset = segment.offset;
+                let ptr = device
+                    .map_memory(get(&self.memories, memory)?, segment)
+                    .map_err(failed("Device::map_memory"))?;
+                self.mapped.insert(memory, (ptr, offset));
+            }
+            Call::WriteMemory {
+                memory,
+                offset,
+                data,
+            } => {
+                let &(ptr, mapped_offset) =
+                    self.mapped.get(&memory).ok_or(Error::NotMapped(memory))?;
+                let dst = ptr.offset((offset - mapped_offset) as isize);
+                std::ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len());
+            }
+            Call::FlushMappedMemoryRanges(ranges) => {
+                let ranges = ranges
+                    .into_iter()
+                    .map(|(memory, segment)| Ok((get(&self.memories, memory)?, segment)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                device
+                    .flush_mapped_memory_ranges(ranges)
+                    .map_err(failed("Device::flush_mapped_memory_ranges"))?;
+            }
+            Call::InvalidateMappedMemoryRanges(ranges) => {
+                let ranges = ranges
+                    .into_iter()
+                    .map(|(memory, segment)| Ok((get(&self.memories, memory)?, segment)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                device
+                    .invalidate_mapped_memory_ranges(ranges)
+                    .map_err(failed("Device::invalidate_mapped_memory_ranges"))?;
+            }
+            Call::UnmapMemory { memory } => {
+                self.mapped.remove(&memory);
+                device.unmap_memory(get(&self.memories, memory)?);
+            }
+            Call::CreateSemaphore { semaphore } => {
+                let raw = device
+                    .create_semaphore()
+                    .map_err(failed("Device::create_semaphore"))?;
+                self.semaphores.insert(semaphore, raw);
+            }
+            Call::CreateTimelineSemaphore {
+                semaphore,
+                initial_value,
+            } => {
+                let raw = device
+                    .create_timeline_semaphore(initial_value)
+                    .map_err(failed("Device::create_timeline_semaphore"))?;
+                self.semaphores.insert(semaphore, raw);
+            }
+            Call::WaitForSemaphoreValue { semaphore, value } => {
+                device
+                    .wait_for_semaphore_value(get(&self.semaphores, semaphore)?, value, !0)
+                    .map_err(failed("Device::wait_for_semaphore_value"))?;
+            }
+            Call::SignalSemaphore { semaphore, value } => {
+                device
+                    .signal_semaphore(get(&self.semaphores, semaphore)?, value)
+                    .map_err(failed("Device::signal_semaphore"))?;
+            }
+            Call::DestroySemaphore { semaphore } => {
+                device.destroy_semaphore(take(&mut self.semaphores, semaphore)?);
+            }
+            Call::CreateFence { fence, signaled } => {
+                let raw = device
+                    .create_fence(signaled)
+                    .map_err(failed("Device::create_fence"))?;
+                self.fences.insert(fence, raw);
+            }
+            Call::ResetFences(fences) => {
+                device
+                    .reset_fences(Self::ids(&self.fences, &fences)?)
+                    .map_err(failed("Device::reset_fences"))?;
+            }
+            Call::WaitForFences { fences, wait } => {
+                device
+                    .wait_for_fences(Self::ids(&self.fences, &fences)?, wait, !0)
+                    .map_err(failed("Device::wait_for_fences"))?;
+            }
+            Call::DestroyFence { fence } => {
+                device.destroy_fence(take(&mut self.fences, fence)?);
+            }
+            Call::CreateEvent { event } => {
+                let raw = device
+                    .create_event()
+                    .map_err(failed("Device::create_event"))?;
+                self.events.insert(event, raw);
+            }
+            Call::DestroyEvent { event } => {
+                device.destroy_event(take(&mut self.events, event)?);
+            }
+            Call::SetEvent { event } => {
+                device
+                    .set_event(get(&self.events, event)?)
+                    .map_err(failed("Device::set_event"))?;
+            }
+            Call::ResetEvent { event } => {
+                device
+                    .reset_event(get(&self.events, event)?)
+                    .map_err(failed("Device::reset_event"))?;
+            }
+            Call::CreateQueryPool { pool, ty, count } => {
+                let raw = device
+                    .create_query_pool(ty, count)
+                    .map_err(failed("Device::create_query_pool"))?;
+                self.query_pools.insert(pool, raw);
+            }
+            Call::DestroyQueryPool { pool } => {
+                device.destroy_query_pool(take(&mut self.query_pools, pool)?);
+            }
+            Call::CreateSwapchain {
+                swapchain,
+                surface: _,
+                config,
+                old_swapchain,
+                images,
+            } => {
+                if let Some(old) = old_swapchain {
+                    self.destroy_swapchain(old)?;
+                }
+                let mut offscreen = Vec::with_capacity(images.len());
+                for id in images {
+                    let (image, memory) = self.create_offscreen(&config)?;
+                    self.images.insert(id, image);
+                    offscreen.push((id, memory));
+                }
+                self.swapchains.insert(swapchain, offscreen);
+            }
+            Call::DestroySwapchain { swapchain } => {
+                self.destroy_swapchain(swapchain)?;
+            }
+            Call::WaitIdle => {
+                device.wait_idle().map_err(failed("Device::wait_idle"))?;
+            }
+            Call::SetName { kind, object, name } => match kind {
+                f::ObjectKind::Image => {
+                    device.set_image_name(get_mut(&mut self.images, object)?, &name)
+                }
+                f::ObjectKind::Buffer => {
+                    device.set_buffer_name(get_mut(&mut self.buffers, object)?, &name)
+                }
+                f::ObjectKind::CommandBuffer => device
+                    .set_command_buffer_name(get_mut(&mut self.command_buffers, object)?, &name),
+                f::ObjectKind::Semaphore => {
+                    device.set_semaphore_name(get_mut(&mut self.semaphores, object)?, &name)
+                }
+                f::ObjectKind::Fence => {
+                    device.set_fence_name(get_mut(&mut self.fences, object)?, &name)
+                }
+                f::ObjectKind::Framebuffer => {
+                    device.set_framebuffer_name(get_mut(&mut self.framebuffers, object)?, &name)
+                }
+                f::ObjectKind::RenderPass => {
+                    device.set_render_pass_name(get_mut(&mut self.render_passes, object)?, &name)
+                }
+                f::ObjectKind::DescriptorSet => device
+                    .set_descriptor_set_name(get_mut(&mut self.descriptor_sets, object)?, &name),
+                f::ObjectKind::DescriptorSetLayout => device.set_descriptor_set_layout_name(
+                    get_mut(&mut self.descriptor_set_layouts, object)?,
+                    &name,
+                ),
+            },
+            Call::ResetCommandPool {
+                pool,
+                release_resources,
+            } => {
+                get_mut(&mut self.command_pools, pool)?.reset(release_resources);
+            }
+            Call::AllocateCommandBuffers {
+                pool,
+                level,
+                cmd_buffers,
+            } => {
+                let pool = get_mut(&mut self.command_pools, pool)?;
+                for id in cmd_buffers {
+                    self.command_buffers.insert(id, pool.allocate_one(level));
+                }
+            }
+            Call::FreeCommandBuffers { pool, cmd_buffers } => {
+                let cmd_buffers = cmd_buffers
+                    .into_iter()
+                    .map(|id| take(&mut self.command_buffers, id))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                get_mut(&mut self.command_pools, pool)?.free(cmd_buffers);
+            }
+            Call::AllocateDescriptorSets {
+                pool,
+                layouts,
+                sets,
+            } => {
+                let pool = get_mut(&mut self.descriptor_pools, pool)?;
+                for (id, layout) in sets.into_iter().zip(layouts) {
+                    let set = pool
+                        .allocate_set(get(&self.descriptor_set_layouts, layout)?)
+                        .map_err(failed("DescriptorPool::allocate_set"))?;
+                    self.descriptor_sets.insert(id, set);
+                }
+            }
+            Call::AllocateVariableDescriptorSet {
+                pool,
+                layout,
+                count,
+                set,
+            } => {
+                let pool = get_mut(&mut self.descriptor_pools, pool)?;
+                let raw = pool
+                    .allocate_variable_set(get(&self.descriptor_set_layouts, layout)?, count)
+                    .map_err(failed("DescriptorPool::allocate_variable_set"))?;
+                self.descriptor_sets.insert(set, raw);
+            }
+            Call::FreeDescriptorSets { pool, sets } => {
+                let sets = sets
+                    .into_iter()
+                    .map(|id| take(&mut self.descriptor_sets, id))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                get_mut(&mut self.descriptor_pools, pool)?.free(sets);
+            }
+            Call::ResetDescriptorPool { pool } => {
+                get_mut(&mut self.descriptor_pools, pool)?.reset();
+            }
+            Call::Submit {
+                queue,
+                command_buffers,
+                wait_semaphores,
+                signal_semaphores,
+                fence,
+            } => {
+                let semaphores = &self.semaphores;
+                let wait_semaphores = wait_semaphores
+                    .into_iter()
+                    .map(|(id, value, stage)| Ok((get(semaphores, id)?, value, stage)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let signal_semaphores = signal_semaphores
+                    .into_iter()
+                    .map(|(id, value)| Ok((get(semaphores, id)?, value)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let fence = match fence {
+                    Some(id) => Some(get(&self.fences, id)?),
+                    None => None,
+                };
+                get_mut(&mut self.queues, queue)?.submit(
+                    Submission {
+                        command_buffers: Self::ids(&self.command_buffers, &command_buffers)?,
+                        wait_semaphores,
+                        signal_semaphores,
+                    },
+                    fence,
+                );
+            }
+            Call::BindSparse {
+                queue,
+                wait_semaphores,
+                signal_semaphores,
+                buffer_binds,
+                image_opaque_binds,
+                image_binds,
+                fence,
+            } => {
+                let semaphores = &self.semaphores;
+                let memories = &self.memories;
+                let wait_semaphores = wait_semaphores
+                    .into_iter()
+                    .map(|(id, value)| Ok((get(semaphores, id)?, value)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let signal_semaphores = signal_semaphores
+                    .into_iter()
+                    .map(|(id, value)| Ok((get(semaphores, id)?, value)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let buffers = &self.buffers;
+                let images = &self.images;
+                let buffer_binds = buffer_binds
+                    .iter()
+                    .map(|(id, binds)| {
+                        Ok((get(buffers, *id)?, Self::sparse_binds(memories, binds)?))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let image_opaque_binds = image_opaque_binds
+                    .iter()
+                    .map(|(id, binds)| {
+                        Ok((get(images, *id)?, Self::sparse_binds(memories, binds)?))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let image_binds = image_binds
+                    .iter()
+                    .map(|(id, binds)| {
+                        Ok((
+                            get(images, *id)?,
+                            Self::sparse_image_binds(memories, binds)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let fence = match fence {
+                    Some(id) => Some(get(&self.fences, id)?),
+                    None => None,
+                };
+                get_mut(&mut self.queues, queue)?.bind_sparse(
+                    SparseBinding {
+                        wait_semaphores,
+                        signal_semaphores,
+                        buffer_binds: buffer_binds
+                            .iter()
+                            .map(|(buffer, binds)| (*buffer, &binds[..])),
+                        image_opaque_binds: image_opaque_binds
+                            .iter()
+                            .map(|(image, binds)| (*image, &binds[..])),
+                        image_binds: image_binds
+                            .iter()
+                            .map(|(image, binds)| (*image, &binds[..])),
+                    },
+                    fence,
+                );
+            }
+            Call::Present {
+                queue,
+                swapchains: _,
+                wait_semaphores,
+            } => {
+                self.sync(Some(queue), &wait_semaphores, &[], None)?;
+            }
+            Call::PresentSurface {
+                queue,
+                surface: _,
+                image,
+                wait_semaphore,
+            } => {
+                let wait_semaphores = wait_semaphore.into_iter().collect::<Vec<_>>();
+                self.sync(Some(queue), &wait_semaphores, &[], None)?;
+                let view = take(&mut self.image_views, image)?;
+                self.device
+                    .as_ref()
+                    .ok_or(Error::NoDevice)?
+                    .destroy_image_view(view);
+            }
+            Call::QueueWaitIdle { queue } => {
+                get(&self.queues, queue)?
+                    .wait_idle()
+                    .map_err(failed("CommandQueue::wait_idle"))?;
+            }
+            Call::ConfigureSwapchain { surface, config } => {
+                let (image, memory) = self.create_offscreen(&config)?;
+                let offscreen = Offscreen {
+                    image,
+                    memory,
+                    config,
+                };
+                if let Some(old) = get_mut(&mut self.surfaces, surface)?.replace(offscreen) {
+                    self.destroy_offscreen(old.image, old.memory)?;
+                }
+            }
+            Call::UnconfigureSwapchain { surface } => {
+                if let Some(old) = get_mut(&mut self.surfaces, surface)?.take() {
+                    self.destroy_offscreen(old.image, old.memory)?;
+                }
+            }
+            Call::AcquireImage { surface, image } => {
+                let offscreen = get(&self.surfaces, surface)?
+                    .as_ref()
+                    .ok_or(Error::MissingObject(surface))?;
+                let view = device
+                    .create_image_view(
+                        &offscreen.image,
+                        image::ViewKind::D2,
+                        offscreen.config.format,
+                        format::Swizzle::NO,
+                        image::SubresourceRange {
+                            aspects: format::Aspects::COLOR,
+                            levels: 0 .. 1,
+                            layers: 0 .. 1,
+                        },
+                    )
+                    .map_err(failed("Device::create_image_view"))?;
+                self.image_views.insert(image, view);
+            }
+            Call::AcquireSwapchainImage {
+                swapchain: _,
+                index: _,
+                semaphore,
+                fence,
+            } => {
+                let signal_semaphores = semaphore.into_iter().collect::<Vec<_>>();
+                self.sync(None, &[], &signal_semaphores, fence)?;
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn record(&mut self, id: Id, command: Command) -> Result<(), Error> {
+        // Take the command buffer out, so that the objects it refers to can be borrowed.
+        let mut cmd_buffer = take(&mut self.command_buffers, id)?;
+        let result = self.record_into(&mut cmd_buffer, command);
+        self.command_buffers.insert(id, cmd_buffer);
+        result
+    }
+
+    unsafe fn record_into(
+        &self,
+        cmd_buffer: &mut B::CommandBuffer,
+        command: Command,
+    ) -> Result<(), Error> {
+        match command {
+            Command::Begin { flags, inheritance } => {
+                let subpass = match inheritance.subpass {
+                    Some((render_pass, index)) => Some(pass::Subpass {
+                        index,
+                        main_pass: get(&self.render_passes, render_pass)?,
+                    }),
+                    None => None,
+                };
+                let framebuffer = match inheritance.framebuffer {
+                    Some(id) => Some(get(&self.framebuffers, id)?),
+                    None => None,
+                };
+                cmd_buffer.begin(
+                    flags,
+                    CommandBufferInheritanceInfo {
+                        subpass,
+                        framebuffer,
+                        occlusion_query_enable: inheritance.occlusion_query_enable,
+                        occlusion_query_flags: inheritance.occlusion_query_flags,
+                        pipeline_statistics: inheritance.pipeline_statistics,
+                    },
+                );
+            }
+            Command::Finish => cmd_buffer.finish(),
+            Command::Reset { release_resources } => cmd_buffer.reset(release_resources),
+            Command::PipelineBarrier {
+                stages,
+                dependencies,
+                barriers,
+            } => {
+                cmd_buffer.pipeline_barrier(stages, dependencies, self.barriers(&barriers)?);
+            }
+            Command::FillBuffer {
+                buffer,
+                range,
+                data,
+            } => cmd_buffer.fill_buffer(get(&self.buffers, buffer)?, range, data),
+            Command::UpdateBuffer {
+                buffer,
+                offset,
+                data,
+            } => cmd_buffer.update_buffer(get(&self.buffers, buffer)?, offset, &data),
+            Command::ClearImage {
+                image,
+                layout,
+                value,
+                subresource_ranges,
+            } => cmd_buffer.clear_image(
+                get(&self.images, image)?,
+                layout,
+                value.into(),
+                &subresource_ranges,
+            ),
+            Command::ClearAttachments { clears, rects } => cmd_buffer.clear_attachments(
+                clears.into_iter().map(hal::command::AttachmentClear::from),
+                &rects,
+            ),
+            Command::ResolveImage {
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                regions,
+            } => cmd_buffer.resolve_image(
+                get(&self.images, src)?,
+                src_layout,
+                get(&self.images, dst)?,
+                dst_layout,
+                &regions,
+            ),
+            Command::BlitImage {
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                filter,
+                regions,
+            } => cmd_buffer.blit_image(
+                get(&self.images, src)?,
+                src_layout,
+                get(&self.images, dst)?,
+                dst_layout,
+                filter,
+                &regions,
+            ),
+            Command::BindIndexBuffer {
+                buffer,
+                range,
+                index_type,
+            } => cmd_buffer.bind_index_buffer(buffer::IndexBufferView {
+                buffer: get(&self.buffers, buffer)?,
+                range,
+                index_type,
+            }),
+            Command::BindVertexBuffers {
+                first_binding,
+                buffers,
+            } => {
+                let buffers = buffers
+                    .into_iter()
+                    .map(|(buffer, range)| Ok((get(&self.buffers, buffer)?, range)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                cmd_buffer.bind_vertex_buffers(first_binding, buffers);
+            }
+            Command::BindTransformFeedbackBuffers {
+                first_binding,
+                buffers,
+            } => {
+                let buffers = buffers
+                    .into_iter()
+                    .map(|(buffer, range)| Ok((get(&self.buffers, buffer)?, range)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                cmd_buffer.bind_transform_feedback_buffers(first_binding, buffers);
+            }
+            Command::SetViewports {
+                first_viewport,
+                viewports,
+            } => cmd_buffer.set_viewports(first_viewport, &viewports),
+            Command::SetScissors {
+                first_scissor,
+                rects,
+            } => cmd_buffer.set_scissors(first_scissor, &rects),
+            Command::SetStencilReference { faces, value } => {
+                cmd_buffer.set_stencil_reference(faces, value)
+            }
+            Command::SetStencilReadMask { faces, value } => {
+                cmd_buffer.set_stencil_read_mask(faces, value)
+            }
+            Command::SetStencilWriteMask { faces, value } => {
+                cmd_buffer.set_stencil_write_mask(faces, value)
+            }
+            Command::SetBlendConstants(color) => cmd_buffer.set_blend_constants(color),
+            Command::SetDepthBounds(bounds) => cmd_buffer.set_depth_bounds(bounds),
+            Command::SetLineWidth(width) => cmd_buffer.set_line_width(width),
+            Command::SetDepthBias(depth_bias) => cmd_buffer.set_depth_bias(depth_bias),
+            Command::BeginRenderPass {
+                render_pass,
+                framebuffer,
+                render_area,
+                clear_values,
+                first_subpass,
+            } => cmd_buffer.begin_render_pass(
+                get(&self.render_passes, render_pass)?,
+                get(&self.framebuffers, framebuffer)?,
+                render_area,
+                clear_values.into_iter().map(hal::command::ClearValue::from),
+                first_subpass,
+            ),
+            Command::NextSubpass(contents) => cmd_buffer.next_subpass(contents),
+            Command::EndRenderPass => cmd_buffer.end_render_pass(),
+            Command::BindGraphicsPipeline(pipeline) => {
+                cmd_buffer.bind_graphics_pipeline(get(&self.graphics_pipelines, pipeline)?)
+            }
+            Command::BindGraphicsDescriptorSets {
+                layout,
+                first_set,
+                sets,
+                offsets,
+            } => cmd_buffer.bind_graphics_descriptor_sets(
+                get(&self.pipeline_layouts, layout)?,
+                first_set,
+                Self::ids(&self.descriptor_sets, &sets)?,
+                &offsets,
+            ),
+            Command::PushGraphicsDescriptorSet {
+                layout,
+                set,
+                writes,
+            } => cmd_buffer.push_graphics_descriptor_set(
+                get(&self.pipeline_layouts, layout)?,
+                set,
+                self.push_writes(&writes)?,
+            ),
+            Command::BindComputePipeline(pipeline) => {
+                cmd_buffer.bind_compute_pipeline(get(&self.compute_pipelines, pipeline)?)
+            }
+            Command::BindComputeDescriptorSets {
+                layout,
+                first_set,
+                sets,
+                offsets,
+            } => cmd_buffer.bind_compute_descriptor_sets(
+                get(&self.pipeline_layouts, layout)?,
+                first_set,
+                Self::ids(&self.descriptor_sets, &sets)?,
+                &offsets,
+            ),
+            Command::PushComputeDescriptorSet {
+                layout,
+                set,
+                writes,
+            } => cmd_buffer.push_compute_descriptor_set(
+                get(&self.pipeline_layouts, layout)?,
+                set,
+                self.push_writes(&writes)?,
+            ),
+            Command::Dispatch(count) => cmd_buffer.dispatch(count),
+            Command::DispatchIndirect { buffer, offset } => {
+                cmd_buffer.dispatch_indirect(get(&self.buffers, buffer)?, offset)
+            }
+            Command::CopyBuffer { src, dst, regions } => {
+                cmd_buffer.copy_buffer(get(&self.buffers, src)?, get(&self.buffers, dst)?, &regions)
+            }
+            Command::CopyImage {
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                regions,
+            } => cmd_buffer.copy_image(
+                get(&self.images, src)?,
+                src_layout,
+                get(&self.images, dst)?,
+                dst_layout,
+                &regions,
+            ),
+            Command::CopyBufferToImage {
+                src,
+                dst,
+                dst_layout,
+                regions,
+            } => cmd_buffer.copy_buffer_to_image(
+                get(&self.buffers, src)?,
+                get(&self.images, dst)?,
+                dst_layout,
+                &regions,
+            ),
+            Command::CopyImageToBuffer {
+                src,
+                src_layout,
+                dst,
+                regions,
+            } => cmd_buffer.copy_image_to_buffer(
+                get(&self.images, src)?,
+                src_layout,
+                get(&self.buffers, dst)?,
+                &regions,
+            ),
+            Command::Draw {
+                vertices,
+                instances,
+            } => cmd_buffer.draw(vertices, instances),
+            Command::DrawIndexed {
+                indices,
+                base_vertex,
+                instances,
+            } => cmd_buffer.draw_indexed(indices, base_vertex, instances),
+            Command::DrawIndirect {
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            } => cmd_buffer.draw_indirect(get(&self.buffers, buffer)?, offset, draw_count, stride),
+            Command::DrawIndexedIndirect {
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            } => cmd_buffer.draw_indexed_indirect(
+                get(&self.buffers, buffer)?,
+                offset,
+                draw_count,
+                stride,
+            ),
+            Command::DrawIndirectCount {
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            } => cmd_buffer.draw_indirect_count(
+                get(&self.buffers, buffer)?,
+                offset,
+                get(&self.buffers, count_buffer)?,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            ),
+            Command::DrawIndexedIndirectCount {
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            } => cmd_buffer.draw_indexed_indirect_count(
+                get(&self.buffers, buffer)?,
+                offset,
+                get(&self.buffers, count_buffer)?,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            ),
+            Command::BeginConditionalRendering {
+                buffer,
+                offset,
+                inverted,
+            } => cmd_buffer.begin_conditional_rendering(
+                get(&self.buffers, buffer)?,
+                offset,
+                inverted,
+            ),
+            Command::BeginConditionalRenderingQuery { pool, id, inverted } => cmd_buffer
+                .begin_conditional_rendering_query(get(&self.query_pools, pool)?, id, inverted),
+            Command::EndConditionalRendering => cmd_buffer.end_conditional_rendering(),
+            Command::BeginTransformFeedback => cmd_buffer.begin_transform_feedback(),
+            Command::EndTransformFeedback => cmd_buffer.end_transform_feedback(),
+            Command::SetEvent { event, stages } => {
+                cmd_buffer.set_event(get(&self.events, event)?, stages)
+            }
+            Command::ResetEvent { event, stages } => {
+                cmd_buffer.reset_event(get(&self.events, event)?, stages)
+            }
+            Command::WaitEvents {
+                events,
+                stages,
+                barriers,
+            } => {
+                cmd_buffer.wait_events(
+                    Self::ids(&self.events, &events)?,
+                    stages,
+                    self.barriers(&barriers)?,
+                );
+            }
+            Command::BeginQuery { pool, id, flags } => cmd_buffer.begin_query(
+                hal::query::Query {
+                    pool: get(&self.query_pools, pool)?,
+                    id,
+                },
+                flags,
+            ),
+            Command::EndQuery { pool, id } => cmd_buffer.end_query(hal::query::Query {
+                pool: get(&self.query_pools, pool)?,
+                id,
+            }),
+            Command::ResetQueryPool { pool, queries } => {
+                cmd_buffer.reset_query_pool(get(&self.query_pools, pool)?, queries)
+            }
+            Command::CopyQueryPoolResults {
+                pool,
+                queries,
+                buffer,
+                offset,
+                stride,
+                flags,
+            } => cmd_buffer.copy_query_pool_results(
+                get(&self.query_pools, pool)?,
+                queries,
+                get(&self.buffers, buffer)?,
+                offset,
+                stride,
+                flags,
+            ),
+            Command::WriteTimestamp { stage, pool, id } => cmd_buffer.write_timestamp(
+                stage,
+                hal::query::Query {
+                    pool: get(&self.query_pools, pool)?,
+                    id,
+                },
+            ),
+            Command::PushGraphicsConstants {
+                layout,
+                stages,
+                offset,
+                constants,
+            } => cmd_buffer.push_graphics_constants(
+                get(&self.pipeline_layouts, layout)?,
+                stages,
+                offset,
+                &constants,
+            ),
+            Command::PushComputeConstants {
+                layout,
+                offset,
+                constants,
+            } => cmd_buffer.push_compute_constants(
+                get(&self.pipeline_layouts, layout)?,
+                offset,
+                &constants,
+            ),
+            Command::ExecuteCommands(secondaries) => {
+                cmd_buffer.execute_commands(Self::ids(&self.command_buffers, &secondaries)?)
+            }
+            Command::InsertDebugMarker { name, color } => {
+                cmd_buffer.insert_debug_marker(&name, color)
+            }
+            Command::BeginDebugMarker { name, color } => {
+                cmd_buffer.begin_debug_marker(&name, color)
+            }
+            Command::EndDebugMarker => cmd_buffer.end_debug_marker(),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    format::{Call, Id},
+    native,
+    recorder::Recorder,
+    Device,
+    PhysicalDevice,
+    Traced,
+};
+use hal::{format, window};
+use std::sync::Arc;
+
+/// Swapchain image of the wrapped backend's surface.
+pub(crate) type SwapchainImage<B> =
+    <<B as hal::Backend>::Surface as window::PresentationSurface<B>>::SwapchainImage;
+
+/// Trace surface, wrapping a surface of the backend `B`.
+#[derive(Debug)]
+pub struct Surface<B: hal::Backend> {
+    pub(crate) raw: B::Surface,
+    pub(crate) id: Id,
+    pub(crate) recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> Surface<B> {
+    /// Returns the wrapped surface.
+    pub fn raw(&self) -> &B::Surface {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> window::Surface<Traced<B>> for Surface<B> {
+    fn supports_queue_family(&self, family: &B::QueueFamily) -> bool {
+        self.raw.supports_queue_family(family)
+    }
+
+    fn capabilities(&self, physical_device: &PhysicalDevice<B>) -> window::SurfaceCapabilities {
+        self.raw.capabilities(&physical_device.raw)
+    }
+
+    fn supported_formats(
+        &self,
+        physical_device: &PhysicalDevice<B>,
+    ) -> Option<Vec<format::Format>> {
+        self.raw.supported_formats(&physical_device.raw)
+    }
+}
+
+impl<B: hal::Backend> window::PresentationSurface<Traced<B>> for Surface<B> {
+    type SwapchainImage = native::ImageView<B>;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device<B>,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        self.raw.configure_swapchain(&device.raw, config.clone())?;
+        self.recorder.record(Call::ConfigureSwapchain {
+            surface: self.id,
+            config,
+        });
+        Ok(())
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, device: &Device<B>) {
+        self.raw.unconfigure_swapchain(&device.raw);
+        self.recorder
+            .record(Call::UnconfigureSwapchain { surface: self.id });
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+    ) -> Result<(native::ImageView<B>, Option<window::Suboptimal>), window::AcquireError> {
+        let (image, suboptimal) = self.raw.acquire_image(timeout_ns)?;
+        let id = self.recorder.id();
+        self.recorder.record(Call::AcquireImage {
+            surface: self.id,
+            image: id,
+        });
+        let view = native::ImageView {
+            kind: native::ImageViewKind::Swapchain(image),
+            id,
+        };
+        Ok((view, suboptimal))
+    }
+}
+
+/// Trace swapchain.
+#[derive(Debug)]
+pub struct Swapchain<B: hal::Backend> {
+    pub(crate) raw: B::Swapchain,
+    pub(crate) id: Id,
+    pub(crate) recorder: Arc<Recorder>,
+}
+
+impl<B: hal::Backend> window::Swapchain<Traced<B>> for Swapchain<B> {
+    unsafe fn acquire_image(
+        &mut self,
+        timeout_ns: u64,
+        semaphore: Option<&native::Handle<B::Semaphore>>,
+        fence: Option<&native::Handle<B::Fence>>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        let (index, suboptimal) = self.raw.acquire_image(
+            timeout_ns,
+            semaphore.map(|semaphore| &semaphore.raw),
+            fence.map(|fence| &fence.raw),
+        )?;
+        self.recorder.record(Call::AcquireSwapchainImage {
+            swapchain: self.id,
+            index,
+            semaphore: semaphore.map(|semaphore| semaphore.id),
+            fence: fence.map(|fence| fence.id),
+        });
+        Ok((index, suboptimal))
+    }
+}
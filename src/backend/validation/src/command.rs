@@ -0,0 +1,1190 @@
+use crate::{describe, native, Reporter, Validated};
+use hal::{
+    buffer,
+    command::{self as com, Level},
+    image,
+    memory,
+    pass,
+    pso,
+    query,
+    DrawCount,
+    IndexCount,
+    InstanceCount,
+    VertexCount,
+    VertexOffset,
+    WorkGroupCount,
+};
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Recording state of a command buffer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Initial,
+    Recording,
+    Executable,
+}
+
+/// Render pass instance being recorded.
+#[derive(Debug)]
+struct PassState {
+    subpass: usize,
+    subpasses: usize,
+    /// The current subpass is recorded in secondary command buffers.
+    secondary_contents: bool,
+}
+
+/// Unwraps the descriptors pushed into a command buffer.
+fn push_writes<'a, B, I, J>(writes: I) -> Vec<pso::DescriptorWrite<Vec<pso::Descriptor<'a, B>>>>
+where
+    B: hal::Backend,
+    I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+    J: IntoIterator,
+    J::Item: Borrow<pso::Descriptor<'a, Validated<B>>>,
+{
+    writes
+        .into_iter()
+        .map(|write| pso::DescriptorWrite {
+            binding: write.binding,
+            array_offset: write.array_offset,
+            descriptors: write
+                .descriptors
+                .into_iter()
+                .map(|descriptor| match *descriptor.borrow() {
+                    pso::Descriptor::Sampler(sampler) => pso::Descriptor::Sampler(sampler),
+                    pso::Descriptor::Image(view, layout) => pso::Descriptor::Image(view, layout),
+                    pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+                        pso::Descriptor::CombinedImageSampler(view, layout, sampler)
+                    }
+                    pso::Descriptor::Buffer(buffer, ref range) => {
+                        pso::Descriptor::Buffer(&buffer.raw, range.clone())
+                    }
+                    pso::Descriptor::TexelBuffer(view) => pso::Descriptor::TexelBuffer(view),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Validation command buffer.
+///
+/// Tracks the recording state, the active render pass and the bound pipelines
+/// to validate the commands before recording them into the wrapped command buffer.
+#[derive(Debug)]
+pub struct CommandBuffer<B: hal::Backend> {
+    pub(crate) raw: B::CommandBuffer,
+    level: Level,
+    state: State,
+    pass: Option<PassState>,
+    graphics_pipeline: bool,
+    compute_pipeline: bool,
+    index_buffer: bool,
+    /// Whether conditional rendering is active, and if it began inside a render pass.
+    conditional_rendering: Option<bool>,
+    transform_feedback: bool,
+    pub(crate) name: String,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> CommandBuffer<B> {
+    pub(crate) fn new(raw: B::CommandBuffer, level: Level, reporter: Arc<Reporter>) -> Self {
+        CommandBuffer {
+            raw,
+            level,
+            state: State::Initial,
+            pass: None,
+            graphics_pipeline: false,
+            compute_pipeline: false,
+            index_buffer: false,
+            conditional_rendering: None,
+            transform_feedback: false,
+            name: String::new(),
+            reporter,
+        }
+    }
+
+    /// Returns the wrapped command buffer.
+    pub fn raw(&self) -> &B::CommandBuffer {
+        &self.raw
+    }
+
+    pub(crate) fn level(&self) -> Level {
+        self.level
+    }
+
+    pub(crate) fn is_executable(&self) -> bool {
+        self.state == State::Executable
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        describe("command buffer", &self.name)
+    }
+
+    fn report(&self, call: &'static str, mut objects: Vec<String>, message: &str) {
+        objects.insert(0, self.describe());
+        self.reporter.report(call, objects, message);
+    }
+
+    fn check_recording(&self, call: &'static str) {
+        if self.state != State::Recording {
+            self.report(call, Vec::new(), "command buffer is not recording");
+        }
+    }
+
+    fn check_outside_pass(&self, call: &'static str) {
+        self.check_recording(call);
+        if self.pass.is_some() {
+            self.report(
+                call,
+                Vec::new(),
+                "command is not allowed inside a render pass",
+            );
+        }
+    }
+
+    fn check_inside_pass(&self, call: &'static str) {
+        self.check_recording(call);
+        match self.pass {
+            None => self.report(call, Vec::new(), "command requires an active render pass"),
+            Some(ref pass) if pass.secondary_contents => self.report(
+                call,
+                Vec::new(),
+                "subpass contents are recorded in secondary command buffers",
+            ),
+            Some(_) => {}
+        }
+    }
+
+    fn check_draw(&self, call: &'static str) {
+        self.check_inside_pass(call);
+        if !self.graphics_pipeline {
+            self.report(call, Vec::new(), "no graphics pipeline is bound");
+        }
+    }
+
+    fn check_buffer(&self, call: &'static str, buffer: &native::Buffer<B>, usage: buffer::Usage) {
+        if !buffer.bound {
+            self.report(
+                call,
+                vec![buffer.describe()],
+                "buffer is not bound to memory",
+            );
+        }
+        if !buffer.usage.contains(usage) {
+            self.report(
+                call,
+                vec![buffer.describe()],
+                &format!("buffer usage doesn't include {:?}", usage),
+            );
+        }
+    }
+
+    fn check_image(&self, call: &'static str, image: &native::Image<B>, usage: image::Usage) {
+        if !image.bound {
+            self.report(call, vec![image.describe()], "image is not bound to memory");
+        }
+        if !image.usage.contains(usage) {
+            self.report(
+                call,
+                vec![image.describe()],
+                &format!("image usage doesn't include {:?}", usage),
+            );
+        }
+    }
+
+    fn check_aligned(
+        &self,
+        call: &'static str,
+        buffer: &native::Buffer<B>,
+        value: u64,
+        what: &str,
+    ) {
+        if value & 3 != 0 {
+            self.report(
+                call,
+                vec![buffer.describe()],
+                &format!("{} {} is not a multiple of 4", what, value),
+            );
+        }
+    }
+
+    fn barrier<'a>(
+        &self,
+        call: &'static str,
+        barrier: &memory::Barrier<'a, Validated<B>>,
+    ) -> memory::Barrier<'a, B> {
+        match *barrier {
+            memory::Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+            memory::Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+            memory::Barrier::Buffer {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => {
+                if !target.bound {
+                    self.report(
+                        call,
+                        vec![target.describe()],
+                        "buffer is not bound to memory",
+                    );
+                }
+                memory::Barrier::Buffer {
+                    states: states.clone(),
+                    target: &target.raw,
+                    range: range.clone(),
+                    families: families.clone(),
+                }
+            }
+            memory::Barrier::Image {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => {
+                if !target.bound {
+                    self.report(
+                        call,
+                        vec![target.describe()],
+                        "image is not bound to memory",
+                    );
+                }
+                memory::Barrier::Image {
+                    states: states.clone(),
+                    target: &target.raw,
+                    range: range.clone(),
+                    families: families.clone(),
+                }
+            }
+        }
+    }
+}
+
+impl<B: hal::Backend> com::CommandBuffer<Validated<B>> for CommandBuffer<B> {
+    unsafe fn begin(
+        &mut self,
+        flags: com::CommandBufferFlags,
+        inheritance_info: com::CommandBufferInheritanceInfo<Validated<B>>,
+    ) {
+        if self.state == State::Recording {
+            self.report(
+                "CommandBuffer::begin",
+                Vec::new(),
+                "command buffer is already recording",
+            );
+        }
+        self.state = State::Recording;
+        self.graphics_pipeline = false;
+        self.compute_pipeline = false;
+        self.index_buffer = false;
+        self.pass = match inheritance_info.subpass {
+            Some(ref subpass) if self.level == Level::Secondary => Some(PassState {
+                subpass: subpass.index as usize,
+                subpasses: subpass.main_pass.subpasses,
+                secondary_contents: false,
+            }),
+            _ => None,
+        };
+
+        let info = com::CommandBufferInheritanceInfo {
+            subpass: inheritance_info.subpass.map(|subpass| pass::Subpass {
+                index: subpass.index,
+                main_pass: &subpass.main_pass.raw,
+            }),
+            framebuffer: inheritance_info
+                .framebuffer
+                .map(|framebuffer| &framebuffer.raw),
+            occlusion_query_enable: inheritance_info.occlusion_query_enable,
+            occlusion_query_flags: inheritance_info.occlusion_query_flags,
+            pipeline_statistics: inheritance_info.pipeline_statistics,
+        };
+        self.raw.begin(flags, info)
+    }
+
+    unsafe fn finish(&mut self) {
+        let call = "CommandBuffer::finish";
+        self.check_recording(call);
+        if self.level == Level::Primary && self.pass.is_some() {
+            self.report(call, Vec::new(), "render pass is not ended");
+        }
+        if self.conditional_rendering.is_some() {
+            self.report(call, Vec::new(), "conditional rendering is not ended");
+        }
+        self.state = State::Executable;
+        self.pass = None;
+        self.conditional_rendering = None;
+        self.transform_feedback = false;
+        self.raw.finish()
+    }
+
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.state = State::Initial;
+        self.pass = None;
+        self.conditional_rendering = None;
+        self.transform_feedback = false;
+        self.raw.reset(release_resources)
+    }
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        dependencies: memory::Dependencies,
+        barriers: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Validated<B>>>,
+    {
+        let call = "CommandBuffer::pipeline_barrier";
+        self.check_recording(call);
+        let barriers = barriers
+            .into_iter()
+            .map(|barrier| self.barrier(call, barrier.borrow()))
+            .collect::<Vec<_>>();
+        self.raw.pipeline_barrier(stages, dependencies, barriers)
+    }
+
+    unsafe fn fill_buffer(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        range: buffer::SubRange,
+        data: u32,
+    ) {
+        let call = "CommandBuffer::fill_buffer";
+        self.check_outside_pass(call);
+        self.check_buffer(call, buffer, buffer::Usage::TRANSFER_DST);
+        self.check_aligned(call, buffer, range.offset, "offset");
+        if let Some(size) = range.size {
+            self.check_aligned(call, buffer, size, "size");
+        }
+        self.raw.fill_buffer(&buffer.raw, range, data)
+    }
+
+    unsafe fn update_buffer(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        data: &[u8],
+    ) {
+        let call = "CommandBuffer::update_buffer";
+        self.check_outside_pass(call);
+        self.check_buffer(call, buffer, buffer::Usage::TRANSFER_DST);
+        self.check_aligned(call, buffer, offset, "offset");
+        self.check_aligned(call, buffer, data.len() as u64, "size");
+        if data.len() > 65536 {
+            self.report(
+                call,
+                vec![buffer.describe()],
+                &format!("{} bytes exceed the limit of 65536", data.len()),
+            );
+        }
+        self.raw.update_buffer(&buffer.raw, offset, data)
+    }
+
+    unsafe fn clear_image<T>(
+        &mut self,
+        image: &native::Image<B>,
+        layout: image::Layout,
+        value: com::ClearValue,
+        subresource_ranges: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+        let call = "CommandBuffer::clear_image";
+        self.check_outside_pass(call);
+        self.check_image(call, image, image::Usage::TRANSFER_DST);
+        self.raw
+            .clear_image(&image.raw, layout, value, subresource_ranges)
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<com::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        self.check_inside_pass("CommandBuffer::clear_attachments");
+        self.raw.clear_attachments(clears, rects)
+    }
+
+    unsafe fn resolve_image<T>(
+        &mut self,
+        src: &native::Image<B>,
+        src_layout: image::Layout,
+        dst: &native::Image<B>,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageResolve>,
+    {
+        let call = "CommandBuffer::resolve_image";
+        self.check_outside_pass(call);
+        for image in &[src, dst] {
+            if !image.bound {
+                self.report(call, vec![image.describe()], "image is not bound to memory");
+            }
+        }
+        self.raw
+            .resolve_image(&src.raw, src_layout, &dst.raw, dst_layout, regions)
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        src: &native::Image<B>,
+        src_layout: image::Layout,
+        dst: &native::Image<B>,
+        dst_layout: image::Layout,
+        filter: image::Filter,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageBlit>,
+    {
+        let call = "CommandBuffer::blit_image";
+        self.check_outside_pass(call);
+        self.check_image(call, src, image::Usage::TRANSFER_SRC);
+        self.check_image(call, dst, image::Usage::TRANSFER_DST);
+        self.raw
+            .blit_image(&src.raw, src_layout, &dst.raw, dst_layout, filter, regions)
+    }
+
+    unsafe fn bind_index_buffer(&mut self, view: buffer::IndexBufferView<Validated<B>>) {
+        let call = "CommandBuffer::bind_index_buffer";
+        self.check_recording(call);
+        self.check_buffer(call, view.buffer, buffer::Usage::INDEX);
+        self.index_buffer = true;
+        self.raw.bind_index_buffer(buffer::IndexBufferView {
+            buffer: &view.buffer.raw,
+            range: view.range,
+            index_type: view.index_type,
+        })
+    }
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, first_binding: pso::BufferIndex, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer<B>>,
+    {
+        let call = "CommandBuffer::bind_vertex_buffers";
+        self.check_recording(call);
+        let buffers = buffers.into_iter().collect::<Vec<_>>();
+        for (buffer, _) in &buffers {
+            self.check_buffer(call, buffer.borrow(), buffer::Usage::VERTEX);
+        }
+        self.raw.bind_vertex_buffers(
+            first_binding,
+            buffers
+                .iter()
+                .map(|(buffer, range)| (&buffer.borrow().raw, range.clone())),
+        )
+    }
+
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, first_binding: u32, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<native::Buffer<B>>,
+    {
+        let call = "CommandBuffer::bind_transform_feedback_buffers";
+        self.check_recording(call);
+        if self.transform_feedback {
+            self.report(call, Vec::new(), "transform feedback is active");
+        }
+        let buffers = buffers.into_iter().collect::<Vec<_>>();
+        for (buffer, _) in &buffers {
+            self.check_buffer(call, buffer.borrow(), buffer::Usage::TRANSFORM_FEEDBACK);
+        }
+        self.raw.bind_transform_feedback_buffers(
+            first_binding,
+            buffers
+                .iter()
+                .map(|(buffer, range)| (&buffer.borrow().raw, range.clone())),
+        )
+    }
+
+    unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        self.check_recording("CommandBuffer::set_viewports");
+        self.raw.set_viewports(first_viewport, viewports)
+    }
+
+    unsafe fn set_scissors<T>(&mut self, first_scissor: u32, rects: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        self.check_recording("CommandBuffer::set_scissors");
+        self.raw.set_scissors(first_scissor, rects)
+    }
+
+    unsafe fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.check_recording("CommandBuffer::set_stencil_reference");
+        self.raw.set_stencil_reference(faces, value)
+    }
+
+    unsafe fn set_stencil_read_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.check_recording("CommandBuffer::set_stencil_read_mask");
+        self.raw.set_stencil_read_mask(faces, value)
+    }
+
+    unsafe fn set_stencil_write_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.check_recording("CommandBuffer::set_stencil_write_mask");
+        self.raw.set_stencil_write_mask(faces, value)
+    }
+
+    unsafe fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.check_recording("CommandBuffer::set_blend_constants");
+        self.raw.set_blend_constants(color)
+    }
+
+    unsafe fn set_depth_bounds(&mut self, bounds: Range<f32>) {
+        self.check_recording("CommandBuffer::set_depth_bounds");
+        self.raw.set_depth_bounds(bounds)
+    }
+
+    unsafe fn set_line_width(&mut self, width: f32) {
+        self.check_recording("CommandBuffer::set_line_width");
+        self.raw.set_line_width(width)
+    }
+
+    unsafe fn set_depth_bias(&mut self, depth_bias: pso::DepthBias) {
+        self.check_recording("CommandBuffer::set_depth_bias");
+        self.raw.set_depth_bias(depth_bias)
+    }
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        render_pass: &native::RenderPass<B>,
+        framebuffer: &native::Framebuffer<B>,
+        render_area: pso::Rect,
+        clear_values: T,
+        first_subpass: com::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ClearValue>,
+    {
+        let call = "CommandBuffer::begin_render_pass";
+        self.check_outside_pass(call);
+        if self.level != Level::Primary {
+            self.report(
+                call,
+                vec![render_pass.describe()],
+                "render passes can only begin in primary command buffers",
+            );
+        }
+        if framebuffer.attachments != render_pass.attachments {
+            self.report(
+                call,
+                vec![render_pass.describe(), framebuffer.describe()],
+                &format!(
+                    "framebuffer has {} attachments, but the render pass has {}",
+                    framebuffer.attachments, render_pass.attachments
+                ),
+            );
+        }
+        self.pass = Some(PassState {
+            subpass: 0,
+            subpasses: render_pass.subpasses,
+            secondary_contents: match first_subpass {
+                com::SubpassContents::Inline => false,
+                com::SubpassContents::SecondaryBuffers => true,
+            },
+        });
+        self.raw.begin_render_pass(
+            &render_pass.raw,
+            &framebuffer.raw,
+            render_area,
+            clear_values,
+            first_subpass,
+        )
+    }
+
+    unsafe fn next_subpass(&mut self, contents: com::SubpassContents) {
+        let call = "CommandBuffer::next_subpass";
+        self.check_recording(call);
+        let secondary_contents = match contents {
+            com::SubpassContents::Inline => false,
+            com::SubpassContents::SecondaryBuffers => true,
+        };
+        let message = match self.pass {
+            Some(ref mut pass) if pass.subpass + 1 < pass.subpasses => {
+                pass.subpass += 1;
+                pass.secondary_contents = secondary_contents;
+                None
+            }
+            Some(_) => Some("render pass has no next subpass"),
+            None => Some("command requires an active render pass"),
+        };
+        if let Some(message) = message {
+            self.report(call, Vec::new(), message);
+        }
+        self.raw.next_subpass(contents)
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        let call = "CommandBuffer::end_render_pass";
+        self.check_recording(call);
+        match self.pass.take() {
+            Some(ref pass) if pass.subpass + 1 < pass.subpasses => self.report(
+                call,
+                Vec::new(),
+                &format!(
+                    "render pass ended in subpass {} out of {}",
+                    pass.subpass, pass.subpasses
+                ),
+            ),
+            Some(_) => {}
+            None => self.report(call, Vec::new(), "command requires an active render pass"),
+        }
+        if self.transform_feedback {
+            self.report(call, Vec::new(), "transform feedback is not ended");
+            self.transform_feedback = false;
+        }
+        self.raw.end_render_pass()
+    }
+
+    unsafe fn bind_graphics_pipeline(&mut self, pipeline: &B::GraphicsPipeline) {
+        self.check_recording("CommandBuffer::bind_graphics_pipeline");
+        self.graphics_pipeline = true;
+        self.raw.bind_graphics_pipeline(pipeline)
+    }
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet<B>>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        self.check_recording("CommandBuffer::bind_graphics_descriptor_sets");
+        let sets = sets.into_iter().collect::<Vec<_>>();
+        self.raw.bind_graphics_descriptor_sets(
+            layout,
+            first_set,
+            sets.iter().map(|set| &set.borrow().raw),
+            offsets,
+        )
+    }
+
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Validated<B>>>,
+    {
+        self.check_recording("CommandBuffer::push_graphics_descriptor_set");
+        self.raw
+            .push_graphics_descriptor_set(layout, set, push_writes(writes))
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, pipeline: &B::ComputePipeline) {
+        self.check_recording("CommandBuffer::bind_compute_pipeline");
+        self.compute_pipeline = true;
+        self.raw.bind_compute_pipeline(pipeline)
+    }
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSet<B>>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        self.check_recording("CommandBuffer::bind_compute_descriptor_sets");
+        let sets = sets.into_iter().collect::<Vec<_>>();
+        self.raw.bind_compute_descriptor_sets(
+            layout,
+            first_set,
+            sets.iter().map(|set| &set.borrow().raw),
+            offsets,
+        )
+    }
+
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Validated<B>>>,
+    {
+        self.check_recording("CommandBuffer::push_compute_descriptor_set");
+        self.raw
+            .push_compute_descriptor_set(layout, set, push_writes(writes))
+    }
+
+    unsafe fn dispatch(&mut self, count: WorkGroupCount) {
+        let call = "CommandBuffer::dispatch";
+        self.check_outside_pass(call);
+        if !self.compute_pipeline {
+            self.report(call, Vec::new(), "no compute pipeline is bound");
+        }
+        self.raw.dispatch(count)
+    }
+
+    unsafe fn dispatch_indirect(&mut self, buffer: &native::Buffer<B>, offset: buffer::Offset) {
+        let call = "CommandBuffer::dispatch_indirect";
+        self.check_outside_pass(call);
+        if !self.compute_pipeline {
+            self.report(call, Vec::new(), "no compute pipeline is bound");
+        }
+        self.check_buffer(call, buffer, buffer::Usage::INDIRECT);
+        self.raw.dispatch_indirect(&buffer.raw, offset)
+    }
+
+    unsafe fn copy_buffer<T>(
+        &mut self,
+        src: &native::Buffer<B>,
+        dst: &native::Buffer<B>,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferCopy>,
+    {
+        let call = "CommandBuffer::copy_buffer";
+        self.check_outside_pass(call);
+        self.check_buffer(call, src, buffer::Usage::TRANSFER_SRC);
+        self.check_buffer(call, dst, buffer::Usage::TRANSFER_DST);
+        let regions = regions
+            .into_iter()
+            .map(|region| *region.borrow())
+            .collect::<Vec<_>>();
+        for region in &regions {
+            if region.src + region.size > src.size || region.dst + region.size > dst.size {
+                self.report(
+                    call,
+                    vec![src.describe(), dst.describe()],
+                    "region is out of the buffer bounds",
+                );
+            }
+        }
+        self.raw.copy_buffer(&src.raw, &dst.raw, regions)
+    }
+
+    unsafe fn copy_image<T>(
+        &mut self,
+        src: &native::Image<B>,
+        src_layout: image::Layout,
+        dst: &native::Image<B>,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageCopy>,
+    {
+        let call = "CommandBuffer::copy_image";
+        self.check_outside_pass(call);
+        self.check_image(call, src, image::Usage::TRANSFER_SRC);
+        self.check_image(call, dst, image::Usage::TRANSFER_DST);
+        self.raw
+            .copy_image(&src.raw, src_layout, &dst.raw, dst_layout, regions)
+    }
+
+    unsafe fn copy_buffer_to_image<T>(
+        &mut self,
+        src: &native::Buffer<B>,
+        dst: &native::Image<B>,
+        dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        let call = "CommandBuffer::copy_buffer_to_image";
+        self.check_outside_pass(call);
+        self.check_buffer(call, src, buffer::Usage::TRANSFER_SRC);
+        self.check_image(call, dst, image::Usage::TRANSFER_DST);
+        self.raw
+            .copy_buffer_to_image(&src.raw, &dst.raw, dst_layout, regions)
+    }
+
+    unsafe fn copy_image_to_buffer<T>(
+        &mut self,
+        src: &native::Image<B>,
+        src_layout: image::Layout,
+        dst: &native::Buffer<B>,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        let call = "CommandBuffer::copy_image_to_buffer";
+        self.check_outside_pass(call);
+        self.check_image(call, src, image::Usage::TRANSFER_SRC);
+        self.check_buffer(call, dst, buffer::Usage::TRANSFER_DST);
+        self.raw
+            .copy_image_to_buffer(&src.raw, src_layout, &dst.raw, regions)
+    }
+
+    unsafe fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
+        self.check_draw("CommandBuffer::draw");
+        self.raw.draw(vertices, instances)
+    }
+
+    unsafe fn draw_indexed(
+        &mut self,
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    ) {
+        let call = "CommandBuffer::draw_indexed";
+        self.check_draw(call);
+        if !self.index_buffer {
+            self.report(call, Vec::new(), "no index buffer is bound");
+        }
+        self.raw.draw_indexed(indices, base_vertex, instances)
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        let call = "CommandBuffer::draw_indirect";
+        self.check_draw(call);
+        self.check_buffer(call, buffer, buffer::Usage::INDIRECT);
+        self.check_aligned(call, buffer, offset, "offset");
+        self.raw
+            .draw_indirect(&buffer.raw, offset, draw_count, stride)
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        let call = "CommandBuffer::draw_indexed_indirect";
+        self.check_draw(call);
+        if !self.index_buffer {
+            self.report(call, Vec::new(), "no index buffer is bound");
+        }
+        self.check_buffer(call, buffer, buffer::Usage::INDIRECT);
+        self.check_aligned(call, buffer, offset, "offset");
+        self.raw
+            .draw_indexed_indirect(&buffer.raw, offset, draw_count, stride)
+    }
+
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        count_buffer: &native::Buffer<B>,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        let call = "CommandBuffer::draw_indirect_count";
+        self.check_draw(call);
+        self.check_buffer(call, buffer, buffer::Usage::INDIRECT);
+        self.check_aligned(call, buffer, offset, "offset");
+        self.check_buffer(call, count_buffer, buffer::Usage::INDIRECT);
+        self.check_aligned(
+            call,
+            count_buffer,
+            count_buffer_offset,
+            "count buffer offset",
+        );
+        self.raw.draw_indirect_count(
+            &buffer.raw,
+            offset,
+            &count_buffer.raw,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        )
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        count_buffer: &native::Buffer<B>,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        let call = "CommandBuffer::draw_indexed_indirect_count";
+        self.check_draw(call);
+        if !self.index_buffer {
+            self.report(call, Vec::new(), "no index buffer is bound");
+        }
+        self.check_buffer(call, buffer, buffer::Usage::INDIRECT);
+        self.check_aligned(call, buffer, offset, "offset");
+        self.check_buffer(call, count_buffer, buffer::Usage::INDIRECT);
+        self.check_aligned(
+            call,
+            count_buffer,
+            count_buffer_offset,
+            "count buffer offset",
+        );
+        self.raw.draw_indexed_indirect_count(
+            &buffer.raw,
+            offset,
+            &count_buffer.raw,
+            count_buffer_offset,
+            max_draw_count,
+            stride,
+        )
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        let call = "CommandBuffer::begin_conditional_rendering";
+        self.check_recording(call);
+        if self.conditional_rendering.is_some() {
+            self.report(call, Vec::new(), "conditional rendering is already active");
+        }
+        self.check_buffer(call, buffer, buffer::Usage::CONDITIONAL_RENDERING);
+        self.check_aligned(call, buffer, offset, "offset");
+        self.conditional_rendering = Some(self.pass.is_some());
+        self.raw
+            .begin_conditional_rendering(&buffer.raw, offset, inverted)
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &B::QueryPool,
+        id: query::Id,
+        inverted: bool,
+    ) {
+        let call = "CommandBuffer::begin_conditional_rendering_query";
+        self.check_outside_pass(call);
+        if self.conditional_rendering.is_some() {
+            self.report(call, Vec::new(), "conditional rendering is already active");
+        }
+        self.conditional_rendering = Some(false);
+        self.raw
+            .begin_conditional_rendering_query(pool, id, inverted)
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        let call = "CommandBuffer::end_conditional_rendering";
+        self.check_recording(call);
+        match self.conditional_rendering.take() {
+            Some(inside_pass) if inside_pass != self.pass.is_some() => self.report(
+                call,
+                Vec::new(),
+                "conditional rendering must end in the same render pass scope it began in",
+            ),
+            Some(_) => {}
+            None => self.report(call, Vec::new(), "conditional rendering is not active"),
+        }
+        self.raw.end_conditional_rendering()
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        let call = "CommandBuffer::begin_transform_feedback";
+        self.check_inside_pass(call);
+        if self.transform_feedback {
+            self.report(call, Vec::new(), "transform feedback is already active");
+        }
+        self.transform_feedback = true;
+        self.raw.begin_transform_feedback()
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        let call = "CommandBuffer::end_transform_feedback";
+        self.check_inside_pass(call);
+        if !self.transform_feedback {
+            self.report(call, Vec::new(), "transform feedback is not active");
+        }
+        self.transform_feedback = false;
+        self.raw.end_transform_feedback()
+    }
+
+    unsafe fn set_event(&mut self, event: &B::Event, stages: pso::PipelineStage) {
+        self.check_outside_pass("CommandBuffer::set_event");
+        self.raw.set_event(event, stages)
+    }
+
+    unsafe fn reset_event(&mut self, event: &B::Event, stages: pso::PipelineStage) {
+        self.check_outside_pass("CommandBuffer::reset_event");
+        self.raw.reset_event(event, stages)
+    }
+
+    unsafe fn wait_events<'a, I, J>(
+        &mut self,
+        events: I,
+        stages: Range<pso::PipelineStage>,
+        barriers: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<B::Event>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Validated<B>>>,
+    {
+        let call = "CommandBuffer::wait_events";
+        self.check_recording(call);
+        let barriers = barriers
+            .into_iter()
+            .map(|barrier| self.barrier(call, barrier.borrow()))
+            .collect::<Vec<_>>();
+        self.raw.wait_events(events, stages, barriers)
+    }
+
+    unsafe fn begin_query(
+        &mut self,
+        query: query::Query<Validated<B>>,
+        flags: query::ControlFlags,
+    ) {
+        self.check_recording("CommandBuffer::begin_query");
+        self.raw.begin_query(
+            query::Query {
+                pool: query.pool,
+                id: query.id,
+            },
+            flags,
+        )
+    }
+
+    unsafe fn end_query(&mut self, query: query::Query<Validated<B>>) {
+        self.check_recording("CommandBuffer::end_query");
+        self.raw.end_query(query::Query {
+            pool: query.pool,
+            id: query.id,
+        })
+    }
+
+    unsafe fn reset_query_pool(&mut self, pool: &B::QueryPool, queries: Range<query::Id>) {
+        self.check_outside_pass("CommandBuffer::reset_query_pool");
+        self.raw.reset_query_pool(pool, queries)
+    }
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: Range<query::Id>,
+        buffer: &native::Buffer<B>,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) {
+        let call = "CommandBuffer::copy_query_pool_results";
+        self.check_outside_pass(call);
+        self.check_buffer(call, buffer, buffer::Usage::TRANSFER_DST);
+        self.raw
+            .copy_query_pool_results(pool, queries, &buffer.raw, offset, stride, flags)
+    }
+
+    unsafe fn write_timestamp(
+        &mut self,
+        stage: pso::PipelineStage,
+        query: query::Query<Validated<B>>,
+    ) {
+        self.check_recording("CommandBuffer::write_timestamp");
+        self.raw.write_timestamp(
+            stage,
+            query::Query {
+                pool: query.pool,
+                id: query.id,
+            },
+        )
+    }
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        layout: &B::PipelineLayout,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.check_recording("CommandBuffer::push_graphics_constants");
+        self.raw
+            .push_graphics_constants(layout, stages, offset, constants)
+    }
+
+    unsafe fn push_compute_constants(
+        &mut self,
+        layout: &B::PipelineLayout,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.check_recording("CommandBuffer::push_compute_constants");
+        self.raw.push_compute_constants(layout, offset, constants)
+    }
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, cmd_buffers: I)
+    where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let call = "CommandBuffer::execute_commands";
+        self.check_recording(call);
+        if self.level != Level::Primary {
+            self.report(
+                call,
+                Vec::new(),
+                "secondary command buffers can only be executed by primary ones",
+            );
+        }
+        if let Some(ref pass) = self.pass {
+            if !pass.secondary_contents {
+                self.report(call, Vec::new(), "subpass contents are recorded inline");
+            }
+        }
+
+        let cmd_buffers = cmd_buffers
+            .into_iter()
+            .map(|cmd_buffer| {
+                let cmd_buffer = cmd_buffer.borrow();
+                if cmd_buffer.level != Level::Secondary {
+                    self.report(
+                        call,
+                        vec![cmd_buffer.describe()],
+                        "only secondary command buffers can be executed",
+                    );
+                }
+                if cmd_buffer.state != State::Executable {
+                    self.report(
+                        call,
+                        vec![cmd_buffer.describe()],
+                        "command buffer is not finished",
+                    );
+                }
+                &cmd_buffer.raw
+            })
+            .collect::<Vec<_>>();
+        self.raw.execute_commands(cmd_buffers.iter())
+    }
+
+    unsafe fn insert_debug_marker(&mut self, name: &str, color: u32) {
+        self.raw.insert_debug_marker(name, color)
+    }
+
+    unsafe fn begin_debug_marker(&mut self, name: &str, color: u32) {
+        self.raw.begin_debug_marker(name, color)
+    }
+
+    unsafe fn end_debug_marker(&mut self) {
+        self.raw.end_debug_marker()
+    }
+}
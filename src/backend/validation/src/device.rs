@@ -0,0 +1,1622 @@
+use crate::{
+    command::CommandBuffer,
+    native,
+    window::{Surface, Swapchain},
+    CommandPool,
+    DescriptorPool,
+    Reporter,
+    Validated,
+};
+use hal::{
+    adapter::{MemoryProperties, MemoryType},
+    buffer,
+    device,
+    external,
+    format,
+    image,
+    memory,
+    pass,
+    pool::CommandPoolCreateFlags,
+    pso,
+    query,
+    queue::{self, QueueFamilyId},
+    window,
+    MemoryTypeId,
+};
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Validation device, wrapping a device of the backend `B`.
+#[derive(Debug)]
+pub struct Device<B: hal::Backend> {
+    pub(crate) raw: B::Device,
+    memory_types: Vec<MemoryType>,
+    features: hal::Features,
+    limits: hal::Limits,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> Device<B> {
+    pub(crate) fn new(
+        raw: B::Device,
+        properties: MemoryProperties,
+        features: hal::Features,
+        limits: hal::Limits,
+        reporter: Arc<Reporter>,
+    ) -> Self {
+        Device {
+            raw,
+            memory_types: properties.memory_types,
+            features,
+            limits,
+            reporter,
+        }
+    }
+
+    /// Returns the wrapped device.
+    pub fn raw(&self) -> &B::Device {
+        &self.raw
+    }
+
+    fn check_bind(
+        &self,
+        call: &'static str,
+        object: String,
+        bound: bool,
+        requirements: &memory::Requirements,
+        memory: &native::Memory<B>,
+        offset: u64,
+    ) {
+        let report = |message: &str| self.reporter.report(call, vec![object.clone()], message);
+        if bound {
+            report("resource is already bound to memory");
+        }
+        if requirements.type_mask & (1 << memory.ty.0) == 0 {
+            report(&format!(
+                "memory type {} is not allowed by the requirements (mask {:#x})",
+                memory.ty.0, requirements.type_mask
+            ));
+        }
+        if requirements.alignment != 0 && offset & (requirements.alignment - 1) != 0 {
+            report(&format!(
+                "offset {} is not a multiple of the required alignment {}",
+                offset, requirements.alignment
+            ));
+        }
+        if offset + requirements.size > memory.size {
+            report(&format!(
+                "range {} .. {} is out of the memory bounds (size {})",
+                offset,
+                offset + requirements.size,
+                memory.size
+            ));
+        }
+    }
+
+    fn check_sparse(
+        &self,
+        call: &'static str,
+        sparse: memory::SparseFlags,
+        residency_features: &[(hal::Features, &str)],
+    ) {
+        let report = |message: &str| self.reporter.report(call, Vec::new(), message);
+        if sparse.is_empty() {
+            return;
+        }
+        if !sparse.contains(memory::SparseFlags::SPARSE_BINDING) {
+            report(&format!("{:?} requires SPARSE_BINDING", sparse));
+        }
+        if !self.features.contains(hal::Features::SPARSE_BINDING) {
+            report("resource uses SPARSE_BINDING, but the SPARSE_BINDING feature is not enabled");
+        }
+        if sparse.contains(memory::SparseFlags::SPARSE_RESIDENCY) {
+            for &(feature, name) in residency_features {
+                if !self.features.contains(feature) {
+                    report(&format!(
+                        "resource uses SPARSE_RESIDENCY, but the {} feature is not enabled",
+                        name
+                    ));
+                }
+            }
+        }
+        if sparse.contains(memory::SparseFlags::SPARSE_ALIASED)
+            && !self
+                .features
+                .contains(hal::Features::SPARSE_RESIDENCY_ALIASED)
+        {
+            report(
+                "resource uses SPARSE_ALIASED, but the SPARSE_RESIDENCY_ALIASED feature is not enabled",
+            );
+        }
+    }
+
+    fn check_memory_type(&self, call: &'static str, memory_type: MemoryTypeId) {
+        if memory_type.0 >= self.memory_types.len() {
+            self.reporter.report(
+                call,
+                Vec::new(),
+                &format!(
+                    "memory type {} doesn't exist, the device has {} types",
+                    memory_type.0,
+                    self.memory_types.len()
+                ),
+            );
+        }
+    }
+
+    fn check_memory_handle_types(
+        &self,
+        call: &'static str,
+        handle_types: external::MemoryHandleTypes,
+    ) {
+        let features = [
+            (
+                external::MemoryHandleTypes::OPAQUE_FD,
+                hal::Features::EXTERNAL_MEMORY_FD,
+                "EXTERNAL_MEMORY_FD",
+            ),
+            (
+                external::MemoryHandleTypes::DMA_BUF,
+                hal::Features::EXTERNAL_MEMORY_DMA_BUF,
+                "EXTERNAL_MEMORY_DMA_BUF",
+            ),
+        ];
+        for &(handle_type, feature, name) in features.iter() {
+            if handle_types.contains(handle_type) && !self.features.contains(feature) {
+                self.reporter.report(
+                    call,
+                    Vec::new(),
+                    &format!("{} feature is not enabled", name),
+                );
+            }
+        }
+    }
+
+    fn check_external_semaphore(&self, call: &'static str) {
+        if !self.features.contains(hal::Features::EXTERNAL_SEMAPHORE_FD) {
+            self.reporter.report(
+                call,
+                Vec::new(),
+                "EXTERNAL_SEMAPHORE_FD feature is not enabled",
+            );
+        }
+    }
+
+    fn check_multiview(
+        &self,
+        view_masks: impl Iterator<Item = pass::ViewMask>,
+        correlation_masks: &[pass::ViewMask],
+    ) {
+        let report = |message: &str| {
+            self.reporter
+                .report("Device::create_render_pass", Vec::new(), message)
+        };
+        let view_masks = view_masks.collect::<Vec<_>>();
+        if view_masks.iter().all(|&mask| mask == 0) {
+            if !correlation_masks.is_empty() {
+                report("correlation masks are given, but no subpass uses multiview");
+            }
+            return;
+        }
+        if !self.features.contains(hal::Features::MULTIVIEW) {
+            report("render pass uses multiview, but the MULTIVIEW feature is not enabled");
+        }
+        for (index, &mask) in view_masks.iter().enumerate() {
+            let view_count = 32 - mask.leading_zeros() as usize;
+            if mask == 0 {
+                report(&format!(
+                    "subpass {} has no views, but other subpasses use multiview",
+                    index
+                ));
+            } else if view_count > self.limits.max_multiview_view_count {
+                report(&format!(
+                    "subpass {} renders to view {}, but max_multiview_view_count is {}",
+                    index,
+                    view_count - 1,
+                    self.limits.max_multiview_view_count
+                ));
+            }
+        }
+    }
+
+    fn check_mapped_range(
+        &self,
+        call: &'static str,
+        memory: &native::Memory<B>,
+        segment: &memory::Segment,
+    ) {
+        if !memory.is_mapped() {
+            self.reporter
+                .report(call, Vec::new(), "memory is not mapped");
+        }
+        let end = segment
+            .size
+            .map_or(segment.offset, |size| segment.offset + size);
+        if end > memory.size {
+            self.reporter.report(
+                call,
+                Vec::new(),
+                &format!(
+                    "segment ends at {}, past the memory size {}",
+                    end, memory.size
+                ),
+            );
+        }
+    }
+
+    fn check_descriptor(
+        &self,
+        set: &native::DescriptorSet<B>,
+        binding: &pso::DescriptorSetLayoutBinding,
+        descriptor: &pso::Descriptor<Validated<B>>,
+    ) {
+        use hal::pso::{
+            BufferDescriptorFormat as Bdf,
+            DescriptorType as Dt,
+            ImageDescriptorType as Idt,
+        };
+
+        let call = "Device::write_descriptor_sets";
+        let compatible = match *descriptor {
+            pso::Descriptor::Sampler(_) => {
+                if binding.immutable_samplers {
+                    self.reporter.report(
+                        call,
+                        vec![set.describe()],
+                        &format!("binding {} has immutable samplers", binding.binding),
+                    );
+                }
+                binding.ty == Dt::Sampler
+            }
+            pso::Descriptor::Image(..) => match binding.ty {
+                Dt::Image {
+                    ty: Idt::Sampled { with_sampler },
+                } => !with_sampler || binding.immutable_samplers,
+                Dt::Image {
+                    ty: Idt::Storage { .. },
+                }
+                | Dt::InputAttachment => true,
+                _ => false,
+            },
+            pso::Descriptor::CombinedImageSampler(..) => {
+                binding.ty
+                    == Dt::Image {
+                        ty: Idt::Sampled { with_sampler: true },
+                    }
+            }
+            pso::Descriptor::Buffer(buffer, _) => {
+                if !buffer.bound {
+                    self.reporter.report(
+                        call,
+                        vec![set.describe(), buffer.describe()],
+                        "buffer is not bound to memory",
+                    );
+                }
+                matches!(
+                    binding.ty,
+                    Dt::Buffer {
+                        format: Bdf::Structured { .. },
+                        ..
+                    }
+                )
+            }
+            pso::Descriptor::TexelBuffer(_) => matches!(
+                binding.ty,
+                Dt::Buffer {
+                    format: Bdf::Texel,
+                    ..
+                }
+            ),
+        };
+        if !compatible {
+            self.reporter.report(
+                call,
+                vec![set.describe()],
+                &format!(
+                    "descriptor {} doesn't match the type {:?} of binding {}",
+                    descriptor_kind(descriptor),
+                    binding.ty,
+                    binding.binding
+                ),
+            );
+        }
+    }
+}
+
+/// Descriptor binding flags, with the features they require.
+const BINDING_FLAG_FEATURES: &[(pso::DescriptorBindingFlags, hal::Features, &str)] = &[
+    (
+        pso::DescriptorBindingFlags::UPDATE_AFTER_BIND,
+        hal::Features::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND,
+        "DESCRIPTOR_BINDING_UPDATE_AFTER_BIND",
+    ),
+    (
+        pso::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING,
+        hal::Features::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING,
+        "DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING",
+    ),
+    (
+        pso::DescriptorBindingFlags::PARTIALLY_BOUND,
+        hal::Features::DESCRIPTOR_BINDING_PARTIALLY_BOUND,
+        "DESCRIPTOR_BINDING_PARTIALLY_BOUND",
+    ),
+    (
+        pso::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT,
+        hal::Features::DESCRIPTOR_BINDING_VARIABLE_COUNT,
+        "DESCRIPTOR_BINDING_VARIABLE_COUNT",
+    ),
+];
+
+/// Returns true if descriptors of the type take a dynamic offset.
+fn is_dynamic(ty: pso::DescriptorType) -> bool {
+    matches!(
+        ty,
+        pso::DescriptorType::Buffer {
+            format: pso::BufferDescriptorFormat::Structured {
+                dynamic_offset: true,
+            },
+            ..
+        }
+    )
+}
+
+fn descriptor_kind<B: hal::Backend>(descriptor: &pso::Descriptor<B>) -> &'static str {
+    match *descriptor {
+        pso::Descriptor::Sampler(_) => "Sampler",
+        pso::Descriptor::Image(..) => "Image",
+        pso::Descriptor::CombinedImageSampler(..) => "CombinedImageSampler",
+        pso::Descriptor::Buffer(..) => "Buffer",
+        pso::Descriptor::TexelBuffer(_) => "TexelBuffer",
+    }
+}
+
+fn entry_point<'a, B: hal::Backend>(
+    entry: &pso::EntryPoint<'a, Validated<B>>,
+) -> pso::EntryPoint<'a, B> {
+    pso::EntryPoint {
+        entry: entry.entry,
+        module: entry.module,
+        specialization: entry.specialization.clone(),
+    }
+}
+
+fn base_pipeline<'a, P>(parent: &pso::BasePipeline<'a, P>) -> pso::BasePipeline<'a, P> {
+    match parent {
+        pso::BasePipeline::Pipeline(pipeline) => pso::BasePipeline::Pipeline(*pipeline),
+        pso::BasePipeline::Index(index) => pso::BasePipeline::Index(*index),
+        pso::BasePipeline::None => pso::BasePipeline::None,
+    }
+}
+
+impl<B: hal::Backend> device::Device<Validated<B>> for Device<B> {
+    unsafe fn create_command_pool(
+        &self,
+        family: QueueFamilyId,
+        create_flags: CommandPoolCreateFlags,
+    ) -> Result<CommandPool<B>, device::OutOfMemory> {
+        Ok(CommandPool {
+            raw: self.raw.create_command_pool(family, create_flags)?,
+            reporter: Arc::clone(&self.reporter),
+        })
+    }
+
+    unsafe fn destroy_command_pool(&self, pool: CommandPool<B>) {
+        self.raw.destroy_command_pool(pool.raw)
+    }
+
+    unsafe fn allocate_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+    ) -> Result<native::Memory<B>, device::AllocationError> {
+        self.check_memory_type("Device::allocate_memory", memory_type);
+        let raw = self.raw.allocate_memory(memory_type, size)?;
+        Ok(native::Memory::new(
+            raw,
+            memory_type,
+            size,
+            external::MemoryHandleTypes::empty(),
+        ))
+    }
+
+    unsafe fn free_memory(&self, memory: native::Memory<B>) {
+        if memory.is_mapped() {
+            self.reporter
+                .report("Device::free_memory", Vec::new(), "memory is still mapped");
+        }
+        self.raw.free_memory(memory.raw)
+    }
+
+    unsafe fn allocate_exportable_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Memory<B>, device::AllocationError> {
+        let call = "Device::allocate_exportable_memory";
+        self.check_memory_type(call, memory_type);
+        self.check_memory_handle_types(call, handle_types);
+        let raw = self
+            .raw
+            .allocate_exportable_memory(memory_type, size, handle_types)?;
+        Ok(native::Memory::new(raw, memory_type, size, handle_types))
+    }
+
+    unsafe fn export_memory(
+        &self,
+        memory: &native::Memory<B>,
+        handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        if !memory.exportable.contains(handle_type.into()) {
+            self.reporter.report(
+                "Device::export_memory",
+                Vec::new(),
+                &format!("memory isn't exportable as {:?}", handle_type),
+            );
+        }
+        self.raw.export_memory(&memory.raw, handle_type)
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        let call = "Device::get_memory_fd_type_mask";
+        self.check_memory_handle_types(call, handle_type.into());
+        if handle_type == external::MemoryHandleType::OpaqueFd {
+            self.reporter
+                .report(call, Vec::new(), "OpaqueFd handles can't be queried");
+        }
+        self.raw.get_memory_fd_type_mask(handle_type, fd)
+    }
+
+    unsafe fn import_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<native::Memory<B>, external::ImportError> {
+        let call = "Device::import_memory";
+        self.check_memory_type(call, memory_type);
+        self.check_memory_handle_types(call, handle_type.into());
+        let raw = self.raw.import_memory(memory_type, size, handle_type, fd)?;
+        Ok(native::Memory::new(
+            raw,
+            memory_type,
+            size,
+            external::MemoryHandleTypes::empty(),
+        ))
+    }
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        subpasses: IS,
+        dependencies: ID,
+        correlation_masks: &[pass::ViewMask],
+    ) -> Result<native::RenderPass<B>, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        let attachments = attachments.into_iter().collect::<Vec<_>>();
+        let subpasses = subpasses.into_iter().collect::<Vec<_>>();
+        for (index, subpass) in subpasses.iter().enumerate() {
+            let subpass = subpass.borrow();
+            let refs = subpass
+                .colors
+                .iter()
+                .chain(subpass.depth_stencil)
+                .chain(subpass.inputs)
+                .chain(subpass.resolves);
+            for &(id, _) in refs {
+                if id >= attachments.len() {
+                    self.reporter.report(
+                        "Device::create_render_pass",
+                        Vec::new(),
+                        &format!(
+                            "subpass {} references attachment {}, but there are only {}",
+                            index,
+                            id,
+                            attachments.len()
+                        ),
+                    );
+                }
+            }
+        }
+        self.check_multiview(
+            subpasses.iter().map(|subpass| subpass.borrow().view_mask),
+            correlation_masks,
+        );
+
+        let raw = self.raw.create_render_pass(
+            attachments.iter().map(Borrow::borrow),
+            subpasses.iter().map(Borrow::borrow),
+            dependencies,
+            correlation_masks,
+        )?;
+        Ok(native::RenderPass {
+            raw,
+            attachments: attachments.len(),
+            subpasses: subpasses.len(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn destroy_render_pass(&self, rp: native::RenderPass<B>) {
+        self.raw.destroy_render_pass(rp.raw)
+    }
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        set_layouts: IS,
+        push_constant: IR,
+    ) -> Result<B::PipelineLayout, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<native::DescriptorSetLayout<B>>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        let set_layouts = set_layouts.into_iter().collect::<Vec<_>>();
+        self.raw.create_pipeline_layout(
+            set_layouts.iter().map(|layout| &layout.borrow().raw),
+            push_constant,
+        )
+    }
+
+    unsafe fn destroy_pipeline_layout(&self, layout: B::PipelineLayout) {
+        self.raw.destroy_pipeline_layout(layout)
+    }
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        data: Option<&[u8]>,
+    ) -> Result<B::PipelineCache, device::OutOfMemory> {
+        self.raw.create_pipeline_cache(data)
+    }
+
+    unsafe fn get_pipeline_cache_data(
+        &self,
+        cache: &B::PipelineCache,
+    ) -> Result<Vec<u8>, device::OutOfMemory> {
+        self.raw.get_pipeline_cache_data(cache)
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        target: &B::PipelineCache,
+        sources: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::PipelineCache>,
+    {
+        self.raw.merge_pipeline_caches(target, sources)
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, cache: B::PipelineCache) {
+        self.raw.destroy_pipeline_cache(cache)
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        desc: &pso::GraphicsPipelineDesc<'a, Validated<B>>,
+        cache: Option<&B::PipelineCache>,
+    ) -> Result<B::GraphicsPipeline, pso::CreationError> {
+        let main_pass = desc.subpass.main_pass;
+        if desc.subpass.index as usize >= main_pass.subpasses {
+            self.reporter.report(
+                "Device::create_graphics_pipeline",
+                vec![main_pass.describe()],
+                &format!(
+                    "subpass {} doesn't exist, the render pass has {}",
+                    desc.subpass.index, main_pass.subpasses
+                ),
+            );
+        }
+
+        let shaders = &desc.shaders;
+        let raw_desc = pso::GraphicsPipelineDesc {
+            shaders: pso::GraphicsShaderSet {
+                vertex: entry_point(&shaders.vertex),
+                hull: shaders.hull.as_ref().map(entry_point),
+                domain: shaders.domain.as_ref().map(entry_point),
+                geometry: shaders.geometry.as_ref().map(entry_point),
+                fragment: shaders.fragment.as_ref().map(entry_point),
+            },
+            rasterizer: desc.rasterizer,
+            vertex_buffers: desc.vertex_buffers.clone(),
+            attributes: desc.attributes.clone(),
+            input_assembler: desc.input_assembler.clone(),
+            blender: desc.blender.clone(),
+            depth_stencil: desc.depth_stencil,
+            multisampling: desc.multisampling.clone(),
+            baked_states: desc.baked_states.clone(),
+            layout: desc.layout,
+            subpass: pass::Subpass {
+                index: desc.subpass.index,
+                main_pass: &main_pass.raw,
+            },
+            flags: desc.flags,
+            parent: base_pipeline(&desc.parent),
+        };
+        self.raw.create_graphics_pipeline(&raw_desc, cache)
+    }
+
+    unsafe fn destroy_graphics_pipeline(&self, pipeline: B::GraphicsPipeline) {
+        self.raw.destroy_graphics_pipeline(pipeline)
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        desc: &pso::ComputePipelineDesc<'a, Validated<B>>,
+        cache: Option<&B::PipelineCache>,
+    ) -> Result<B::ComputePipeline, pso::CreationError> {
+        let raw_desc = pso::ComputePipelineDesc {
+            shader: entry_point(&desc.shader),
+            layout: desc.layout,
+            flags: desc.flags,
+            parent: base_pipeline(&desc.parent),
+        };
+        self.raw.create_compute_pipeline(&raw_desc, cache)
+    }
+
+    unsafe fn destroy_compute_pipeline(&self, pipeline: B::ComputePipeline) {
+        self.raw.destroy_compute_pipeline(pipeline)
+    }
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        pass: &native::RenderPass<B>,
+        attachments: I,
+        extent: image::Extent,
+    ) -> Result<native::Framebuffer<B>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::ImageView>,
+    {
+        let attachments = attachments.into_iter().collect::<Vec<_>>();
+        if attachments.len() != pass.attachments {
+            self.reporter.report(
+                "Device::create_framebuffer",
+                vec![pass.describe()],
+                &format!(
+                    "{} attachments are given, but the render pass has {}",
+                    attachments.len(),
+                    pass.attachments
+                ),
+            );
+        }
+        let raw = self.raw.create_framebuffer(
+            &pass.raw,
+            attachments.iter().map(Borrow::borrow),
+            extent,
+        )?;
+        Ok(native::Framebuffer {
+            raw,
+            attachments: attachments.len(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn destroy_framebuffer(&self, buf: native::Framebuffer<B>) {
+        self.raw.destroy_framebuffer(buf.raw)
+    }
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv_data: &[u32],
+    ) -> Result<B::ShaderModule, device::ShaderError> {
+        self.raw.create_shader_module(spirv_data)
+    }
+
+    unsafe fn destroy_shader_module(&self, shader: B::ShaderModule) {
+        self.raw.destroy_shader_module(shader)
+    }
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        sparse: memory::SparseFlags,
+    ) -> Result<native::Buffer<B>, buffer::CreationError> {
+        self.check_sparse(
+            "Device::create_buffer",
+            sparse,
+            &[(
+                hal::Features::SPARSE_RESIDENCY_BUFFER,
+                "SPARSE_RESIDENCY_BUFFER",
+            )],
+        );
+        let raw = self.raw.create_buffer(size, usage, sparse)?;
+        let requirements = self.raw.get_buffer_requirements(&raw);
+        Ok(native::Buffer {
+            raw,
+            size,
+            usage,
+            requirements,
+            sparse,
+            // Sparse buffers are bound to memory by the queue, residency isn't tracked
+            bound: !sparse.is_empty(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Buffer<B>, buffer::CreationError> {
+        self.check_memory_handle_types("Device::create_external_buffer", handle_types);
+        let raw = self.raw.create_external_buffer(size, usage, handle_types)?;
+        let requirements = self.raw.get_buffer_requirements(&raw);
+        Ok(native::Buffer {
+            raw,
+            size,
+            usage,
+            requirements,
+            sparse: memory::SparseFlags::empty(),
+            bound: false,
+            name: String::new(),
+        })
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &native::Buffer<B>) -> memory::Requirements {
+        self.raw.get_buffer_requirements(&buffer.raw)
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &native::Memory<B>,
+        offset: u64,
+        buffer: &mut native::Buffer<B>,
+    ) -> Result<(), device::BindError> {
+        if buffer.sparse.is_empty() {
+            self.check_bind(
+                "Device::bind_buffer_memory",
+                buffer.describe(),
+                buffer.bound,
+                &buffer.requirements,
+                memory,
+                offset,
+            );
+        } else {
+            self.reporter.report(
+                "Device::bind_buffer_memory",
+                vec![buffer.describe()],
+                "sparse buffers are bound to memory with CommandQueue::bind_sparse",
+            );
+        }
+        self.raw
+            .bind_buffer_memory(&memory.raw, offset, &mut buffer.raw)?;
+        buffer.bound = true;
+        Ok(())
+    }
+
+    unsafe fn destroy_buffer(&self, buffer: native::Buffer<B>) {
+        self.raw.destroy_buffer(buffer.raw)
+    }
+
+    unsafe fn create_buffer_view(
+        &self,
+        buffer: &native::Buffer<B>,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    ) -> Result<B::BufferView, buffer::ViewCreationError> {
+        let call = "Device::create_buffer_view";
+        if !buffer.bound {
+            self.reporter.report(
+                call,
+                vec![buffer.describe()],
+                "buffer is not bound to memory",
+            );
+        }
+        if !buffer
+            .usage
+            .intersects(buffer::Usage::UNIFORM_TEXEL | buffer::Usage::STORAGE_TEXEL)
+        {
+            self.reporter.report(
+                call,
+                vec![buffer.describe()],
+                "buffer usage doesn't include UNIFORM_TEXEL or STORAGE_TEXEL",
+            );
+        }
+        if range.offset + range.size.unwrap_or(0) > buffer.size {
+            self.reporter.report(
+                call,
+                vec![buffer.describe()],
+                "range is out of the buffer bounds",
+            );
+        }
+        self.raw.create_buffer_view(&buffer.raw, format, range)
+    }
+
+    unsafe fn destroy_buffer_view(&self, view: B::BufferView) {
+        self.raw.destroy_buffer_view(view)
+    }
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
+    ) -> Result<native::Image<B>, image::CreationError> {
+        let mut residency_features = Vec::new();
+        match kind {
+            image::Kind::D1(..) => {
+                if sparse.contains(memory::SparseFlags::SPARSE_RESIDENCY) {
+                    self.reporter.report(
+                        "Device::create_image",
+                        Vec::new(),
+                        "1D images can't use SPARSE_RESIDENCY",
+                    );
+                }
+            }
+            image::Kind::D2(..) => {
+                residency_features.push((
+                    hal::Features::SPARSE_RESIDENCY_IMAGE_2D,
+                    "SPARSE_RESIDENCY_IMAGE_2D",
+                ));
+            }
+            image::Kind::D3(..) => {
+                residency_features.push((
+                    hal::Features::SPARSE_RESIDENCY_IMAGE_3D,
+                    "SPARSE_RESIDENCY_IMAGE_3D",
+                ));
+            }
+        }
+        match kind.num_samples() {
+            1 => {}
+            2 => residency_features.push((
+                hal::Features::SPARSE_RESIDENCY_2_SAMPLES,
+                "SPARSE_RESIDENCY_2_SAMPLES",
+            )),
+            4 => residency_features.push((
+                hal::Features::SPARSE_RESIDENCY_4_SAMPLES,
+                "SPARSE_RESIDENCY_4_SAMPLES",
+            )),
+            8 => residency_features.push((
+                hal::Features::SPARSE_RESIDENCY_8_SAMPLES,
+                "SPARSE_RESIDENCY_8_SAMPLES",
+            )),
+            _ => residency_features.push((
+                hal::Features::SPARSE_RESIDENCY_16_SAMPLES,
+                "SPARSE_RESIDENCY_16_SAMPLES",
+            )),
+        }
+        self.check_sparse("Device::create_image", sparse, &residency_features);
+        let raw = self
+            .raw
+            .create_image(kind, mip_levels, format, tiling, usage, view_caps, sparse)?;
+        let requirements = self.raw.get_image_requirements(&raw);
+        Ok(native::Image {
+            raw,
+            usage,
+            requirements,
+            sparse,
+            // Sparse images are bound to memory by the queue, residency isn't tracked
+            bound: !sparse.is_empty(),
+            name: String::new(),
+        })
+    }
+
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<native::Image<B>, image::CreationError> {
+        self.check_memory_handle_types("Device::create_external_image", handle_types);
+        let raw = self.raw.create_external_image(
+            kind,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            view_caps,
+            handle_types,
+        )?;
+        let requirements = self.raw.get_image_requirements(&raw);
+        Ok(native::Image {
+            raw,
+            usage,
+            requirements,
+            sparse: memory::SparseFlags::empty(),
+            bound: false,
+            name: String::new(),
+        })
+    }
+
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        modifier: external::DrmFormatModifier,
+    ) -> Result<native::Image<B>, image::CreationError> {
+        let call = "Device::create_drm_format_modifier_image";
+        if !self
+            .features
+            .contains(hal::Features::IMAGE_DRM_FORMAT_MODIFIER)
+        {
+            self.reporter.report(
+                call,
+                Vec::new(),
+                "IMAGE_DRM_FORMAT_MODIFIER feature is not enabled",
+            );
+        }
+        if modifier.planes.is_empty() {
+            self.reporter
+                .report(call, Vec::new(), "no plane layout is specified");
+        }
+        let raw = self
+            .raw
+            .create_drm_format_modifier_image(kind, format, usage, view_caps, modifier)?;
+        let requirements = self.raw.get_image_requirements(&raw);
+        Ok(native::Image {
+            raw,
+            usage,
+            requirements,
+            sparse: memory::SparseFlags::empty(),
+            bound: false,
+            name: String::new(),
+        })
+    }
+
+    unsafe fn get_image_requirements(&self, image: &native::Image<B>) -> memory::Requirements {
+        self.raw.get_image_requirements(&image.raw)
+    }
+
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        image: &native::Image<B>,
+    ) -> Vec<image::SparseRequirements> {
+        self.raw.get_image_sparse_requirements(&image.raw)
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &native::Image<B>,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        self.raw
+            .get_image_subresource_footprint(&image.raw, subresource)
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &native::Memory<B>,
+        offset: u64,
+        image: &mut native::Image<B>,
+    ) -> Result<(), device::BindError> {
+        if image.sparse.is_empty() {
+            self.check_bind(
+                "Device::bind_image_memory",
+                image.describe(),
+                image.bound,
+                &image.requirements,
+                memory,
+                offset,
+            );
+        } else {
+            self.reporter.report(
+                "Device::bind_image_memory",
+                vec![image.describe()],
+                "sparse images are bound to memory with CommandQueue::bind_sparse",
+            );
+        }
+        self.raw
+            .bind_image_memory(&memory.raw, offset, &mut image.raw)?;
+        image.bound = true;
+        Ok(())
+    }
+
+    unsafe fn destroy_image(&self, image: native::Image<B>) {
+        self.raw.destroy_image(image.raw)
+    }
+
+    unsafe fn create_image_view(
+        &self,
+        image: &native::Image<B>,
+        view_kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<B::ImageView, image::ViewCreationError> {
+        if !image.bound {
+            self.reporter.report(
+                "Device::create_image_view",
+                vec![image.describe()],
+                "image is not bound to memory",
+            );
+        }
+        self.raw
+            .create_image_view(&image.raw, view_kind, format, swizzle, range)
+    }
+
+    unsafe fn destroy_image_view(&self, view: B::ImageView) {
+        self.raw.destroy_image_view(view)
+    }
+
+    unsafe fn create_sampler(
+        &self,
+        desc: &image::SamplerDesc,
+    ) -> Result<B::Sampler, device::AllocationError> {
+        self.raw.create_sampler(desc)
+    }
+
+    unsafe fn destroy_sampler(&self, sampler: B::Sampler) {
+        self.raw.destroy_sampler(sampler)
+    }
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        max_sets: usize,
+        descriptor_ranges: I,
+        flags: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool<B>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        if flags.contains(pso::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND)
+            && !self
+                .features
+                .contains(hal::Features::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND)
+        {
+            self.reporter.report(
+                "Device::create_descriptor_pool",
+                Vec::new(),
+                "DESCRIPTOR_BINDING_UPDATE_AFTER_BIND feature is not enabled",
+            );
+        }
+        Ok(DescriptorPool {
+            raw: self
+                .raw
+                .create_descriptor_pool(max_sets, descriptor_ranges, flags)?,
+            flags,
+            reporter: Arc::clone(&self.reporter),
+        })
+    }
+
+    unsafe fn destroy_descriptor_pool(&self, pool: DescriptorPool<B>) {
+        self.raw.destroy_descriptor_pool(pool.raw)
+    }
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        immutable_samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
+    ) -> Result<native::DescriptorSetLayout<B>, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<B::Sampler>,
+    {
+        let bindings = bindings
+            .into_iter()
+            .map(|binding| binding.borrow().clone())
+            .collect::<Vec<_>>();
+        for (i, binding) in bindings.iter().enumerate() {
+            if bindings[.. i].iter().any(|b| b.binding == binding.binding) {
+                self.reporter.report(
+                    "Device::create_descriptor_set_layout",
+                    Vec::new(),
+                    &format!("binding {} is declared more than once", binding.binding),
+                );
+            }
+            for &(flag, feature, name) in BINDING_FLAG_FEATURES {
+                if binding.flags.contains(flag) && !self.features.contains(feature) {
+                    self.reporter.report(
+                        "Device::create_descriptor_set_layout",
+                        Vec::new(),
+                        &format!(
+                            "binding {} uses {:?}, but the {} feature is not enabled",
+                            binding.binding, flag, name
+                        ),
+                    );
+                }
+            }
+            if binding
+                .flags
+                .contains(pso::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+                && bindings.iter().any(|b| b.binding > binding.binding)
+            {
+                self.reporter.report(
+                    "Device::create_descriptor_set_layout",
+                    Vec::new(),
+                    &format!(
+                        "binding {} has a variable descriptor count, but isn't the last binding",
+                        binding.binding
+                    ),
+                );
+            }
+        }
+        if flags.contains(pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR) {
+            if !self.features.contains(hal::Features::PUSH_DESCRIPTOR) {
+                self.reporter.report(
+                    "Device::create_descriptor_set_layout",
+                    Vec::new(),
+                    "layout uses PUSH_DESCRIPTOR, but the PUSH_DESCRIPTOR feature is not enabled",
+                );
+            }
+            if let Some(binding) = bindings.iter().find(|binding| is_dynamic(binding.ty)) {
+                self.reporter.report(
+                    "Device::create_descriptor_set_layout",
+                    Vec::new(),
+                    &format!(
+                        "binding {} has a dynamic offset, which push descriptor layouts don't allow",
+                        binding.binding
+                    ),
+                );
+            }
+        }
+        let raw = self
+            .raw
+            .create_descriptor_set_layout(&bindings, immutable_samplers, flags)?;
+        Ok(native::DescriptorSetLayout {
+            raw,
+            bindings: Arc::new(bindings),
+            flags,
+            name: String::new(),
+        })
+    }
+
+    unsafe fn destroy_descriptor_set_layout(&self, layout: native::DescriptorSetLayout<B>) {
+        self.raw.destroy_descriptor_set_layout(layout.raw)
+    }
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, write_iter: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Validated<B>, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Validated<B>>>,
+    {
+        let mut writes = Vec::new();
+        for write in write_iter {
+            let set = write.set;
+            let mut binding = write.binding;
+            let mut array_offset = write.array_offset;
+            let mut descriptors = Vec::new();
+            for descriptor in write.descriptors {
+                let descriptor = descriptor.borrow();
+                // Writes past the end of a binding continue with the next one.
+                while let Some(layout_binding) = set.binding(binding) {
+                    if array_offset < layout_binding.count {
+                        break;
+                    }
+                    binding += 1;
+                    array_offset = 0;
+                }
+                match set.binding(binding) {
+                    Some(layout_binding) => self.check_descriptor(set, layout_binding, descriptor),
+                    None => self.reporter.report(
+                        "Device::write_descriptor_sets",
+                        vec![set.describe()],
+                        &format!("binding {} doesn't exist in the layout", binding),
+                    ),
+                }
+                array_offset += 1;
+
+                descriptors.push(match *descriptor {
+                    pso::Descriptor::Sampler(sampler) => pso::Descriptor::Sampler(sampler),
+                    pso::Descriptor::Image(view, layout) => pso::Descriptor::Image(view, layout),
+                    pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+                        pso::Descriptor::CombinedImageSampler(view, layout, sampler)
+                    }
+                    pso::Descriptor::Buffer(buffer, ref range) => {
+                        pso::Descriptor::Buffer(&buffer.raw, range.clone())
+                    }
+                    pso::Descriptor::TexelBuffer(view) => pso::Descriptor::TexelBuffer(view),
+                });
+            }
+            writes.push(pso::DescriptorSetWrite {
+                set: &set.raw,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                descriptors,
+            });
+        }
+        self.raw.write_descriptor_sets(writes)
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, copy_iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Validated<B>>>,
+    {
+        let copies = copy_iter
+            .into_iter()
+            .map(|copy| {
+                let copy = copy.borrow();
+                let (src_set, dst_set) = (copy.src_set, copy.dst_set);
+                match (
+                    src_set.binding(copy.src_binding),
+                    dst_set.binding(copy.dst_binding),
+                ) {
+                    (Some(src), Some(dst)) if src.ty != dst.ty => self.reporter.report(
+                        "Device::copy_descriptor_sets",
+                        vec![src_set.describe(), dst_set.describe()],
+                        &format!(
+                            "source type {:?} doesn't match the destination type {:?}",
+                            src.ty, dst.ty
+                        ),
+                    ),
+                    (Some(_), Some(_)) => {}
+                    _ => self.reporter.report(
+                        "Device::copy_descriptor_sets",
+                        vec![src_set.describe(), dst_set.describe()],
+                        "binding doesn't exist in the layout",
+                    ),
+                }
+                pso::DescriptorSetCopy {
+                    src_set: &src_set.raw,
+                    src_binding: copy.src_binding,
+                    src_array_offset: copy.src_array_offset,
+                    dst_set: &dst_set.raw,
+                    dst_binding: copy.dst_binding,
+                    dst_array_offset: copy.dst_array_offset,
+                    count: copy.count,
+                }
+            })
+            .collect::<Vec<_>>();
+        self.raw.copy_descriptor_sets(copies)
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &native::Memory<B>,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        let call = "Device::map_memory";
+        let ty = memory.ty.0;
+        if let Some(memory_type) = self.memory_types.get(ty) {
+            if !memory_type
+                .properties
+                .contains(memory::Properties::CPU_VISIBLE)
+            {
+                self.reporter.report(
+                    call,
+                    Vec::new(),
+                    &format!("memory type {} is not CPU_VISIBLE", ty),
+                );
+            }
+        }
+        if memory.set_mapped(true) {
+            self.reporter
+                .report(call, Vec::new(), "memory is already mapped");
+        }
+        self.check_mapped_range(call, memory, &segment);
+        let result = self.raw.map_memory(&memory.raw, segment);
+        if result.is_err() {
+            memory.set_mapped(false);
+        }
+        result
+    }
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(&self, ranges: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory<B>, memory::Segment)>,
+    {
+        let ranges = ranges
+            .into_iter()
+            .map(|range| {
+                let (memory, ref segment) = *range.borrow();
+                self.check_mapped_range("Device::flush_mapped_memory_ranges", memory, segment);
+                (&memory.raw, segment.clone())
+            })
+            .collect::<Vec<_>>();
+        self.raw.flush_mapped_memory_ranges(ranges)
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(
+        &self,
+        ranges: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a native::Memory<B>, memory::Segment)>,
+    {
+        let ranges = ranges
+            .into_iter()
+            .map(|range| {
+                let (memory, ref segment) = *range.borrow();
+                self.check_mapped_range("Device::invalidate_mapped_memory_ranges", memory, segment);
+                (&memory.raw, segment.clone())
+            })
+            .collect::<Vec<_>>();
+        self.raw.invalidate_mapped_memory_ranges(ranges)
+    }
+
+    unsafe fn unmap_memory(&self, memory: &native::Memory<B>) {
+        if !memory.set_mapped(false) {
+            self.reporter
+                .report("Device::unmap_memory", Vec::new(), "memory is not mapped");
+        }
+        self.raw.unmap_memory(&memory.raw)
+    }
+
+    fn create_semaphore(&self) -> Result<B::Semaphore, device::OutOfMemory> {
+        self.raw.create_semaphore()
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: queue::SemaphoreValue,
+    ) -> Result<B::Semaphore, device::OutOfMemory> {
+        if !self.features.contains(hal::Features::TIMELINE_SEMAPHORE) {
+            self.reporter.report(
+                "Device::create_timeline_semaphore",
+                Vec::new(),
+                "TIMELINE_SEMAPHORE feature is not enabled",
+            );
+        }
+        self.raw.create_timeline_semaphore(initial_value)
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &B::Semaphore,
+    ) -> Result<queue::SemaphoreValue, device::DeviceLost> {
+        self.raw.get_semaphore_value(semaphore)
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &B::Semaphore,
+        value: queue::SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw
+            .wait_for_semaphore_value(semaphore, value, timeout_ns)
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        value: queue::SemaphoreValue,
+    ) -> Result<(), device::OutOfMemory> {
+        self.raw.signal_semaphore(semaphore, value)
+    }
+
+    fn create_exportable_semaphore(
+        &self,
+        handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<B::Semaphore, device::OutOfMemory> {
+        self.check_external_semaphore("Device::create_exportable_semaphore");
+        self.raw.create_exportable_semaphore(handle_types)
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        self.check_external_semaphore("Device::export_semaphore");
+        self.raw.export_semaphore(semaphore, handle_type)
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+        fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        self.check_external_semaphore("Device::import_semaphore");
+        self.raw.import_semaphore(semaphore, handle_type, fd)
+    }
+
+    unsafe fn destroy_semaphore(&self, semaphore: B::Semaphore) {
+        self.raw.destroy_semaphore(semaphore)
+    }
+
+    fn create_fence(&self, signaled: bool) -> Result<B::Fence, device::OutOfMemory> {
+        self.raw.create_fence(signaled)
+    }
+
+    unsafe fn reset_fence(&self, fence: &B::Fence) -> Result<(), device::OutOfMemory> {
+        self.raw.reset_fence(fence)
+    }
+
+    unsafe fn reset_fences<I>(&self, fences: I) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::Fence>,
+    {
+        self.raw.reset_fences(fences)
+    }
+
+    unsafe fn wait_for_fence(
+        &self,
+        fence: &B::Fence,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw.wait_for_fence(fence, timeout_ns)
+    }
+
+    unsafe fn wait_for_fences<I>(
+        &self,
+        fences: I,
+        wait: device::WaitFor,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<B::Fence>,
+    {
+        self.raw.wait_for_fences(fences, wait, timeout_ns)
+    }
+
+    unsafe fn get_fence_status(&self, fence: &B::Fence) -> Result<bool, device::DeviceLost> {
+        self.raw.get_fence_status(fence)
+    }
+
+    unsafe fn destroy_fence(&self, fence: B::Fence) {
+        self.raw.destroy_fence(fence)
+    }
+
+    fn create_event(&self) -> Result<B::Event, device::OutOfMemory> {
+        self.raw.create_event()
+    }
+
+    unsafe fn destroy_event(&self, event: B::Event) {
+        self.raw.destroy_event(event)
+    }
+
+    unsafe fn get_event_status(&self, event: &B::Event) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw.get_event_status(event)
+    }
+
+    unsafe fn set_event(&self, event: &B::Event) -> Result<(), device::OutOfMemory> {
+        self.raw.set_event(event)
+    }
+
+    unsafe fn reset_event(&self, event: &B::Event) -> Result<(), device::OutOfMemory> {
+        self.raw.reset_event(event)
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<B::QueryPool, query::CreationError> {
+        if ty == query::Type::PrimitivesWritten
+            && !self.features.contains(hal::Features::TRANSFORM_FEEDBACK)
+        {
+            self.reporter.report(
+                "Device::create_query_pool",
+                Vec::new(),
+                "query type is PrimitivesWritten, but the TRANSFORM_FEEDBACK feature is not enabled",
+            );
+        }
+        self.raw.create_query_pool(ty, count)
+    }
+
+    unsafe fn destroy_query_pool(&self, pool: B::QueryPool) {
+        self.raw.destroy_query_pool(pool)
+    }
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &B::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        self.raw
+            .get_query_pool_results(pool, queries, data, stride, flags)
+    }
+
+    unsafe fn create_swapchain(
+        &self,
+        surface: &mut Surface<B>,
+        config: window::SwapchainConfig,
+        old_swapchain: Option<Swapchain<B>>,
+    ) -> Result<(Swapchain<B>, Vec<native::Image<B>>), window::CreationError> {
+        let usage = config.image_usage;
+        let (raw, images) = self.raw.create_swapchain(
+            &mut surface.raw,
+            config,
+            old_swapchain.map(|swapchain| swapchain.raw),
+        )?;
+        let images = images
+            .into_iter()
+            .map(|raw| {
+                let requirements = self.raw.get_image_requirements(&raw);
+                native::Image {
+                    raw,
+                    usage,
+                    requirements,
+                    sparse: memory::SparseFlags::empty(),
+                    bound: true,
+                    name: String::new(),
+                }
+            })
+            .collect();
+        Ok((Swapchain { raw }, images))
+    }
+
+    unsafe fn destroy_swapchain(&self, swapchain: Swapchain<B>) {
+        self.raw.destroy_swapchain(swapchain.raw)
+    }
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        self.raw.wait_idle()
+    }
+
+    unsafe fn set_image_name(&self, image: &mut native::Image<B>, name: &str) {
+        image.name = name.to_owned();
+        self.raw.set_image_name(&mut image.raw, name)
+    }
+
+    unsafe fn set_buffer_name(&self, buffer: &mut native::Buffer<B>, name: &str) {
+        buffer.name = name.to_owned();
+        self.raw.set_buffer_name(&mut buffer.raw, name)
+    }
+
+    unsafe fn set_command_buffer_name(&self, command_buffer: &mut CommandBuffer<B>, name: &str) {
+        command_buffer.name = name.to_owned();
+        self.raw
+            .set_command_buffer_name(&mut command_buffer.raw, name)
+    }
+
+    unsafe fn set_semaphore_name(&self, semaphore: &mut B::Semaphore, name: &str) {
+        self.raw.set_semaphore_name(semaphore, name)
+    }
+
+    unsafe fn set_fence_name(&self, fence: &mut B::Fence, name: &str) {
+        self.raw.set_fence_name(fence, name)
+    }
+
+    unsafe fn set_framebuffer_name(&self, framebuffer: &mut native::Framebuffer<B>, name: &str) {
+        framebuffer.name = name.to_owned();
+        self.raw.set_framebuffer_name(&mut framebuffer.raw, name)
+    }
+
+    unsafe fn set_render_pass_name(&self, render_pass: &mut native::RenderPass<B>, name: &str) {
+        render_pass.name = name.to_owned();
+        self.raw.set_render_pass_name(&mut render_pass.raw, name)
+    }
+
+    unsafe fn set_descriptor_set_name(
+        &self,
+        descriptor_set: &mut native::DescriptorSet<B>,
+        name: &str,
+    ) {
+        descriptor_set.name = name.to_owned();
+        self.raw
+            .set_descriptor_set_name(&mut descriptor_set.raw, name)
+    }
+
+    unsafe fn set_descriptor_set_layout_name(
+        &self,
+        descriptor_set_layout: &mut native::DescriptorSetLayout<B>,
+        name: &str,
+    ) {
+        descriptor_set_layout.name = name.to_owned();
+        self.raw
+            .set_descriptor_set_layout_name(&mut descriptor_set_layout.raw, name)
+    }
+}
@@ -0,0 +1,1020 @@
+//! API usage validation backend.
+//!
+//! `Validated<B>` wraps any backend `B` and checks the usage rules of hal, which are
+//! otherwise only stated in the documentation, before forwarding each call to `B`.
+//! Examples are binding buffers to memory before using them, respecting the alignment
+//! of memory requirements, recording draw calls inside of a render pass and writing
+//! descriptors matching the type of their binding.
+//!
+//! Violations are reported through a callback, along with the names of the objects
+//! involved as set by `Device::set_*_name`. The call is forwarded in any case, so the
+//! callback may panic in order to stop before the wrapped backend misbehaves.
+
+#[macro_use]
+extern crate log;
+
+use hal::{
+    adapter,
+    buffer,
+    command::Level,
+    device::{CreationError as DeviceCreationError, OutOfMemory},
+    external,
+    format,
+    image,
+    memory,
+    pool,
+    pso,
+    queue,
+    window::{PresentError, Suboptimal, SwapImageIndex},
+};
+use std::borrow::Borrow;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+mod command;
+mod device;
+pub mod native;
+mod window;
+
+pub use crate::command::CommandBuffer;
+pub use crate::device::Device;
+pub use crate::window::{Surface, Swapchain};
+
+/// Validation backend, wrapping the backend `B`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Validated<B: hal::Backend>(PhantomData<B>);
+impl<B: hal::Backend> hal::Backend for Validated<B> {
+    type Instance = Instance<B>;
+    type PhysicalDevice = PhysicalDevice<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type QueueFamily = B::QueueFamily;
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+
+    type Memory = native::Memory<B>;
+    type CommandPool = CommandPool<B>;
+
+    type ShaderModule = B::ShaderModule;
+    type RenderPass = native::RenderPass<B>;
+    type Framebuffer = native::Framebuffer<B>;
+
+    type Buffer = native::Buffer<B>;
+    type BufferView = B::BufferView;
+    type Image = native::Image<B>;
+    type ImageView = B::ImageView;
+    type Sampler = B::Sampler;
+
+    type ComputePipeline = B::ComputePipeline;
+    type GraphicsPipeline = B::GraphicsPipeline;
+    type PipelineCache = B::PipelineCache;
+    type PipelineLayout = B::PipelineLayout;
+    type DescriptorSetLayout = native::DescriptorSetLayout<B>;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = native::DescriptorSet<B>;
+
+    type Fence = B::Fence;
+    type Semaphore = B::Semaphore;
+    type Event = B::Event;
+    type QueryPool = B::QueryPool;
+}
+
+/// Violation of an API usage rule.
+#[derive(Clone, Debug)]
+pub struct Violation {
+    /// Violating call, e.g. `"CommandBuffer::draw"`.
+    pub call: &'static str,
+    /// Objects involved, described by the names set with `Device::set_*_name`.
+    pub objects: Vec<String>,
+    /// Description of the violated rule.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.call, self.message)?;
+        if !self.objects.is_empty() {
+            write!(f, " ({})", self.objects.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Callback receiving the violations of an instance and of all the objects created from it.
+pub type Callback = dyn Fn(&Violation) + Send + Sync;
+
+/// Shared destination of violations.
+pub(crate) struct Reporter {
+    callback: Box<Callback>,
+}
+
+impl fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Reporter")
+    }
+}
+
+impl Reporter {
+    pub(crate) fn report(&self, call: &'static str, objects: Vec<String>, message: &str) {
+        (self.callback)(&Violation {
+            call,
+            objects,
+            message: message.to_owned(),
+        });
+    }
+}
+
+/// Describes an object by its type and name.
+pub(crate) fn describe(kind: &str, name: &str) -> String {
+    if name.is_empty() {
+        format!("unnamed {}", kind)
+    } else {
+        format!("{} \"{}\"", kind, name)
+    }
+}
+
+/// Validation instance, wrapping an instance of the backend `B`.
+pub struct Instance<B: hal::Backend> {
+    raw: B::Instance,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> Instance<B> {
+    /// Wrap an instance, reporting violations to the callback.
+    pub fn new<F>(raw: B::Instance, callback: F) -> Self
+    where
+        F: Fn(&Violation) + Send + Sync + 'static,
+    {
+        Instance {
+            raw,
+            reporter: Arc::new(Reporter {
+                callback: Box::new(callback),
+            }),
+        }
+    }
+
+    /// Returns the wrapped instance.
+    pub fn raw(&self) -> &B::Instance {
+        &self.raw
+    }
+}
+
+impl<B: hal::Backend> hal::Instance<Validated<B>> for Instance<B> {
+    /// Create an instance of `B`, logging the violations as errors.
+    fn create(name: &str, version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        let raw = B::Instance::create(name, version)?;
+        Ok(Instance::new(raw, |violation| error!("{}", violation)))
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Validated<B>>> {
+        self.raw
+            .enumerate_adapters()
+            .into_iter()
+            .map(|adapter| adapter::Adapter {
+                info: adapter.info,
+                physical_device: PhysicalDevice {
+                    raw: adapter.physical_device,
+                    reporter: Arc::clone(&self.reporter),
+                },
+                queue_families: adapter.queue_families,
+            })
+            .collect()
+    }
+
+    unsafe fn create_surface(
+        &self,
+        has_handle: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface<B>, hal::window::InitError> {
+        self.raw.create_surface(has_handle).map(Surface::new)
+    }
+
+    unsafe fn destroy_surface(&self, surface: Surface<B>) {
+        self.raw.destroy_surface(surface.raw)
+    }
+}
+
+/// Validation physical device.
+#[derive(Debug)]
+pub struct PhysicalDevice<B: hal::Backend> {
+    raw: B::PhysicalDevice,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> adapter::PhysicalDevice<Validated<B>> for PhysicalDevice<B> {
+    unsafe fn open(
+        &self,
+        families: &[(&B::QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Validated<B>>, DeviceCreationError> {
+        let gpu = self.raw.open(families, requested_features)?;
+        let queue_groups = gpu
+            .queue_groups
+            .into_iter()
+            .map(|group| {
+                let mut validated = queue::QueueGroup::new(group.family);
+                for raw in group.queues {
+                    validated.add_queue(CommandQueue {
+                        raw,
+                        reporter: Arc::clone(&self.reporter),
+                    });
+                }
+                validated
+            })
+            .collect();
+
+        Ok(adapter::Gpu {
+            device: Device::new(
+                gpu.device,
+                self.raw.memory_properties(),
+                requested_features,
+                self.raw.limits(),
+                Arc::clone(&self.reporter),
+            ),
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        self.raw.format_properties(format)
+    }
+
+    fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        self.raw
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        self.raw.memory_properties()
+    }
+
+    fn features(&self) -> hal::Features {
+        self.raw.features()
+    }
+
+    fn hints(&self) -> hal::Hints {
+        self.raw.hints()
+    }
+
+    fn limits(&self) -> hal::Limits {
+        self.raw.limits()
+    }
+
+    fn is_valid_cache(&self, cache: &[u8]) -> bool {
+        self.raw.is_valid_cache(cache)
+    }
+
+    fn external_buffer_properties(
+        &self,
+        usage: buffer::Usage,
+        handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        self.raw.external_buffer_properties(usage, handle_type)
+    }
+
+    fn external_image_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        self.raw.external_image_properties(
+            format,
+            dimensions,
+            tiling,
+            usage,
+            view_caps,
+            handle_type,
+        )
+    }
+
+    fn external_semaphore_properties(
+        &self,
+        handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        self.raw.external_semaphore_properties(handle_type)
+    }
+
+    fn drm_format_modifier_properties(
+        &self,
+        format: format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        self.raw.drm_format_modifier_properties(format)
+    }
+}
+
+/// Validation command queue.
+///
+/// Checks that the submitted command buffers have finished recording, and that sparse
+/// memory is bound to sparse resources.
+#[derive(Debug)]
+pub struct CommandQueue<B: hal::Backend> {
+    raw: B::CommandQueue,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> queue::CommandQueue<Validated<B>> for CommandQueue<B> {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&B::Fence>,
+    ) where
+        T: 'a + Borrow<CommandBuffer<B>>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+    {
+        let reporter = &self.reporter;
+        let command_buffers = submission
+            .command_buffers
+            .into_iter()
+            .map(|cmd_buffer| {
+                let cmd_buffer = cmd_buffer.borrow();
+                if cmd_buffer.level() != Level::Primary {
+                    reporter.report(
+                        "CommandQueue::submit",
+                        vec![cmd_buffer.describe()],
+                        "secondary command buffers can't be submitted",
+                    );
+                }
+                if !cmd_buffer.is_executable() {
+                    reporter.report(
+                        "CommandQueue::submit",
+                        vec![cmd_buffer.describe()],
+                        "command buffer is not finished",
+                    );
+                }
+                &cmd_buffer.raw
+            })
+            .collect::<Vec<_>>();
+        self.raw.submit(
+            queue::Submission {
+                command_buffers,
+                wait_semaphores: submission.wait_semaphores,
+                signal_semaphores: submission.signal_semaphores,
+            },
+            fence,
+        )
+    }
+
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&B::Fence>,
+    ) where
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b native::Buffer<B>,
+                &'b [memory::SparseBind<&'b native::Memory<B>>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b native::Image<B>,
+                &'b [memory::SparseBind<&'b native::Memory<B>>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b native::Image<B>,
+                &'b [memory::SparseImageBind<&'b native::Memory<B>>],
+            ),
+        >,
+    {
+        let reporter = &self.reporter;
+        let check_resource = |object: String, sparse: memory::SparseFlags, required| {
+            if !sparse.contains(required) {
+                reporter.report(
+                    "CommandQueue::bind_sparse",
+                    vec![object],
+                    &format!("resource was not created with {:?}", required),
+                );
+            }
+        };
+        let check_ranges = |object: String, size: u64, binds: &[memory::SparseBind<_>]| {
+            for bind in binds {
+                if bind.resource_offset + bind.size > size {
+                    reporter.report(
+                        "CommandQueue::bind_sparse",
+                        vec![object.clone()],
+                        &format!(
+                            "range {} .. {} is out of the resource bounds (size {})",
+                            bind.resource_offset,
+                            bind.resource_offset + bind.size,
+                            size
+                        ),
+                    );
+                }
+            }
+        };
+        let buffer_binds = binding
+            .buffer_binds
+            .into_iter()
+            .map(|(buffer, binds)| {
+                check_resource(
+                    buffer.describe(),
+                    buffer.sparse,
+                    memory::SparseFlags::SPARSE_BINDING,
+                );
+                check_ranges(buffer.describe(), buffer.requirements.size, binds);
+                (&buffer.raw, binds.iter().map(unwrap_sparse_bind).collect())
+            })
+            .collect::<Vec<(_, Vec<_>)>>();
+        let image_opaque_binds = binding
+            .image_opaque_binds
+            .into_iter()
+            .map(|(image, binds)| {
+                check_resource(
+                    image.describe(),
+                    image.sparse,
+                    memory::SparseFlags::SPARSE_BINDING,
+                );
+                check_ranges(image.describe(), image.requirements.size, binds);
+                (&image.raw, binds.iter().map(unwrap_sparse_bind).collect())
+            })
+            .collect::<Vec<(_, Vec<_>)>>();
+        let image_binds = binding
+            .image_binds
+            .into_iter()
+            .map(|(image, binds)| {
+                check_resource(
+                    image.describe(),
+                    image.sparse,
+                    memory::SparseFlags::SPARSE_RESIDENCY,
+                );
+                let binds = binds
+                    .iter()
+                    .map(|bind| memory::SparseImageBind {
+                        subresource: bind.subresource,
+                        offset: bind.offset,
+                        extent: bind.extent,
+                        memory: bind.memory.map(|(memory, offset)| (&memory.raw, offset)),
+                    })
+                    .collect();
+                (&image.raw, binds)
+            })
+            .collect::<Vec<(_, Vec<_>)>>();
+        self.raw.bind_sparse(
+            queue::SparseBinding {
+                wait_semaphores: binding.wait_semaphores,
+                signal_semaphores: binding.signal_semaphores,
+                buffer_binds: buffer_binds
+                    .iter()
+                    .map(|(buffer, binds)| (*buffer, &binds[..])),
+                image_opaque_binds: image_opaque_binds
+                    .iter()
+                    .map(|(image, binds)| (*image, &binds[..])),
+                image_binds: image_binds
+                    .iter()
+                    .map(|(image, binds)| (*image, &binds[..])),
+            },
+            fence,
+        )
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        swapchains: Is,
+        wait_semaphores: Iw,
+    ) -> Result<Option<Suboptimal>, PresentError>
+    where
+        W: 'a + Borrow<Swapchain<B>>,
+        Is: IntoIterator<Item = (&'a W, SwapImageIndex)>,
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        let swapchains = swapchains
+            .into_iter()
+            .map(|(swapchain, index)| (&swapchain.borrow().raw, index));
+        self.raw.present(swapchains, wait_semaphores)
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        surface: &mut Surface<B>,
+        image: window::SwapchainImage<B>,
+        wait_semaphore: Option<&B::Semaphore>,
+    ) -> Result<Option<Suboptimal>, PresentError> {
+        self.raw
+            .present_surface(&mut surface.raw, image, wait_semaphore)
+    }
+
+    fn wait_idle(&self) -> Result<(), OutOfMemory> {
+        self.raw.wait_idle()
+    }
+}
+
+fn unwrap_sparse_bind<'a, B: hal::Backend>(
+    bind: &memory::SparseBind<&'a native::Memory<B>>,
+) -> memory::SparseBind<&'a B::Memory> {
+    memory::SparseBind {
+        resource_offset: bind.resource_offset,
+        size: bind.size,
+        memory: bind.memory.map(|(memory, offset)| (&memory.raw, offset)),
+    }
+}
+
+/// Validation command pool.
+#[derive(Debug)]
+pub struct CommandPool<B: hal::Backend> {
+    raw: B::CommandPool,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> pool::CommandPool<Validated<B>> for CommandPool<B> {
+    unsafe fn reset(&mut self, release_resources: bool) {
+        self.raw.reset(release_resources)
+    }
+
+    unsafe fn allocate_one(&mut self, level: Level) -> CommandBuffer<B> {
+        CommandBuffer::new(
+            self.raw.allocate_one(level),
+            level,
+            Arc::clone(&self.reporter),
+        )
+    }
+
+    unsafe fn allocate<E>(&mut self, num: usize, level: Level, list: &mut E)
+    where
+        E: Extend<CommandBuffer<B>>,
+    {
+        let mut raw = Vec::with_capacity(num);
+        self.raw.allocate(num, level, &mut raw);
+        let reporter = &self.reporter;
+        list.extend(
+            raw.into_iter()
+                .map(|raw| CommandBuffer::new(raw, level, Arc::clone(reporter))),
+        );
+    }
+
+    unsafe fn free<I>(&mut self, cmd_buffers: I)
+    where
+        I: IntoIterator<Item = CommandBuffer<B>>,
+    {
+        self.raw
+            .free(cmd_buffers.into_iter().map(|cmd_buffer| cmd_buffer.raw))
+    }
+}
+
+/// Validation descriptor pool.
+#[derive(Debug)]
+pub struct DescriptorPool<B: hal::Backend> {
+    raw: B::DescriptorPool,
+    flags: pso::DescriptorPoolCreateFlags,
+    reporter: Arc<Reporter>,
+}
+
+impl<B: hal::Backend> DescriptorPool<B> {
+    fn check_layout(&self, call: &'static str, layout: &native::DescriptorSetLayout<B>) {
+        if layout
+            .flags
+            .contains(pso::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR)
+        {
+            self.reporter.report(
+                call,
+                Vec::new(),
+                "layout was created for push descriptors, which can't be allocated",
+            );
+        }
+        if !self
+            .flags
+            .contains(pso::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND)
+            && layout.bindings.iter().any(|binding| {
+                binding
+                    .flags
+                    .contains(pso::DescriptorBindingFlags::UPDATE_AFTER_BIND)
+            })
+        {
+            self.reporter.report(
+                call,
+                Vec::new(),
+                "layout has update-after-bind bindings, but the pool doesn't allow them",
+            );
+        }
+    }
+}
+
+impl<B: hal::Backend> pso::DescriptorPool<Validated<B>> for DescriptorPool<B> {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &native::DescriptorSetLayout<B>,
+    ) -> Result<native::DescriptorSet<B>, pso::AllocationError> {
+        self.check_layout("DescriptorPool::allocate_set", layout);
+        let raw = self.raw.allocate_set(&layout.raw)?;
+        Ok(native::DescriptorSet::new(raw, layout))
+    }
+
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &native::DescriptorSetLayout<B>,
+        count: pso::DescriptorArrayIndex,
+    ) -> Result<native::DescriptorSet<B>, pso::AllocationError> {
+        let call = "DescriptorPool::allocate_variable_set";
+        self.check_layout(call, layout);
+        let variable = layout.bindings.iter().find(|binding| {
+            binding
+                .flags
+                .contains(pso::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT)
+        });
+        if let Some(binding) = variable {
+            if count > binding.count {
+                self.reporter.report(
+                    call,
+                    Vec::new(),
+                    &format!(
+                        "count {} exceeds the {} descriptors of binding {}",
+                        count, binding.count, binding.binding
+                    ),
+                );
+            }
+        }
+        let raw = self.raw.allocate_variable_set(&layout.raw, count)?;
+        Ok(native::DescriptorSet::new(raw, layout))
+    }
+
+    unsafe fn allocate<I, E>(
+        &mut self,
+        layouts: I,
+        list: &mut E,
+    ) -> Result<(), pso::AllocationError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<native::DescriptorSetLayout<B>>,
+        E: Extend<native::DescriptorSet<B>>,
+    {
+        let layouts = layouts.into_iter().collect::<Vec<_>>();
+        for layout in &layouts {
+            self.check_layout("DescriptorPool::allocate", layout.borrow());
+        }
+        let mut raw = Vec::with_capacity(layouts.len());
+        let result = self
+            .raw
+            .allocate(layouts.iter().map(|layout| &layout.borrow().raw), &mut raw);
+        list.extend(
+            raw.into_iter()
+                .zip(&layouts)
+                .map(|(raw, layout)| native::DescriptorSet::new(raw, layout.borrow())),
+        );
+        result
+    }
+
+    unsafe fn free<I>(&mut self, descriptor_sets: I)
+    where
+        I: IntoIterator<Item = native::DescriptorSet<B>>,
+    {
+        self.raw
+            .free(descriptor_sets.into_iter().map(|set| set.raw))
+    }
+
+    unsafe fn reset(&mut self) {
+        self.raw.reset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        command::CommandBuffer as _,
+        device::Device as _,
+        memory,
+        pass,
+        pool::CommandPool as _,
+        pso::DescriptorPool as _,
+        queue::{CommandQueue as _, QueueFamily as _},
+        Instance as _,
+    };
+    use std::iter;
+    use std::sync::Mutex;
+
+    fn instance() -> (
+        Instance<gfx_backend_empty::Backend>,
+        Arc<Mutex<Vec<Violation>>>,
+    ) {
+        let violations = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&violations);
+        let raw = gfx_backend_empty::Instance::create("test", 1).unwrap();
+        let instance = Instance::new(raw, move |violation| {
+            sink.lock().unwrap().push(violation.clone())
+        });
+        (instance, violations)
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn foo<T: Send + Sync>() {}
+        foo::<Device<gfx_backend_empty::Backend>>();
+        foo::<CommandBuffer<gfx_backend_empty::Backend>>();
+        foo::<native::Memory<gfx_backend_empty::Backend>>();
+    }
+
+    #[test]
+    fn report_misuse() {
+        let (instance, violations) = instance();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::empty(),
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let mut buffer = device
+                .create_buffer(
+                    256,
+                    buffer::Usage::TRANSFER_DST,
+                    memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            device.set_buffer_name(&mut buffer, "target");
+            let requirements = device.get_buffer_requirements(&buffer);
+            let memory = device
+                .allocate_memory(hal::MemoryTypeId(1), requirements.size + 1)
+                .unwrap();
+            device.bind_buffer_memory(&memory, 1, &mut buffer).unwrap();
+            {
+                let violations = violations.lock().unwrap();
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].call, "Device::bind_buffer_memory");
+                assert_eq!(violations[0].objects[0], "buffer \"target\"");
+            }
+
+            let mut pool = device
+                .create_command_pool(
+                    adapter.queue_families[0].id(),
+                    pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let mut cmd_buffer = pool.allocate_one(Level::Primary);
+            cmd_buffer.begin_primary(hal::command::CommandBufferFlags::empty());
+            cmd_buffer.fill_buffer(&buffer, buffer::SubRange::WHOLE, 0);
+            cmd_buffer.draw(0 .. 3, 0 .. 1);
+            assert_eq!(violations.lock().unwrap().len(), 3);
+
+            gpu.queue_groups[0].queues[0].submit_without_semaphores(iter::once(&cmd_buffer), None);
+            assert_eq!(
+                violations.lock().unwrap().last().unwrap().message,
+                "command buffer is not finished"
+            );
+
+            let ptr = device.map_memory(&memory, memory::Segment::ALL);
+            assert!(ptr.is_ok());
+            device.unmap_memory(&memory);
+
+            pool.free(iter::once(cmd_buffer));
+            device.destroy_command_pool(pool);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn report_descriptor_binding_flags() {
+        let (instance, violations) = instance();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::DESCRIPTOR_BINDING_VARIABLE_COUNT,
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        let binding = |binding, flags| pso::DescriptorSetLayoutBinding {
+            binding,
+            ty: pso::DescriptorType::Sampler,
+            count: 4,
+            stage_flags: pso::ShaderStageFlags::FRAGMENT,
+            immutable_samplers: false,
+            flags,
+        };
+        unsafe {
+            let layout = device
+                .create_descriptor_set_layout(
+                    &[
+                        binding(0, pso::DescriptorBindingFlags::PARTIALLY_BOUND),
+                        binding(1, pso::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT),
+                    ],
+                    &[],
+                    pso::DescriptorSetLayoutCreateFlags::empty(),
+                )
+                .unwrap();
+            {
+                let violations = violations.lock().unwrap();
+                assert_eq!(violations.len(), 1);
+                assert_eq!(
+                    violations[0].message,
+                    "binding 0 uses PARTIALLY_BOUND, but the DESCRIPTOR_BINDING_PARTIALLY_BOUND feature is not enabled"
+                );
+            }
+
+            let mut pool = device
+                .create_descriptor_pool(
+                    2,
+                    iter::once(pso::DescriptorRangeDesc {
+                        ty: pso::DescriptorType::Sampler,
+                        count: 8,
+                    }),
+                    pso::DescriptorPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let set = pool.allocate_variable_set(&layout, 2).unwrap();
+            assert_eq!(violations.lock().unwrap().len(), 1);
+            let oversized = pool.allocate_variable_set(&layout, 5).unwrap();
+            assert_eq!(
+                violations.lock().unwrap().last().unwrap().message,
+                "count 5 exceeds the 4 descriptors of binding 1"
+            );
+
+            pool.free(vec![set, oversized]);
+            device.destroy_descriptor_pool(pool);
+            device.destroy_descriptor_set_layout(layout);
+        }
+    }
+    #[test]
+    fn report_multiview() {
+        let (instance, violations) = instance();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::MULTIVIEW,
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        let subpass = |view_mask| pass::SubpassDesc {
+            colors: &[],
+            depth_stencil: None,
+            inputs: &[],
+            resolves: &[],
+            preserves: &[],
+            view_mask,
+        };
+        unsafe {
+            let render_pass = device
+                .create_render_pass(&[], &[subpass(0b11), subpass(0b111)], &[], &[0b11])
+                .unwrap();
+            assert!(violations.lock().unwrap().is_empty());
+            device.destroy_render_pass(render_pass);
+
+            let render_pass = device
+                .create_render_pass(&[], &[subpass(0b1000_0000), subpass(0)], &[], &[])
+                .unwrap();
+            {
+                let violations = violations.lock().unwrap();
+                assert_eq!(violations.len(), 2);
+                assert_eq!(
+                    violations[0].message,
+                    "subpass 0 renders to view 7, but max_multiview_view_count is 6"
+                );
+                assert_eq!(
+                    violations[1].message,
+                    "subpass 1 has no views, but other subpasses use multiview"
+                );
+            }
+            device.destroy_render_pass(render_pass);
+        }
+    }
+
+    #[test]
+    fn report_conditional_rendering() {
+        let (instance, violations) = instance();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::CONDITIONAL_RENDERING,
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let mut buffer = device
+                .create_buffer(
+                    16,
+                    buffer::Usage::CONDITIONAL_RENDERING,
+                    memory::SparseFlags::empty(),
+                )
+                .unwrap();
+            let requirements = device.get_buffer_requirements(&buffer);
+            let memory = device
+                .allocate_memory(hal::MemoryTypeId(1), requirements.size)
+                .unwrap();
+            device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+
+            let mut pool = device
+                .create_command_pool(
+                    adapter.queue_families[0].id(),
+                    pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let mut cmd_buffer = pool.allocate_one(Level::Primary);
+            cmd_buffer.begin_primary(hal::command::CommandBufferFlags::empty());
+            cmd_buffer.begin_conditional_rendering(&buffer, 4, true);
+            cmd_buffer.end_conditional_rendering();
+            assert!(violations.lock().unwrap().is_empty());
+
+            cmd_buffer.begin_conditional_rendering(&buffer, 2, false);
+            cmd_buffer.begin_conditional_rendering(&buffer, 0, false);
+            cmd_buffer.finish();
+            {
+                let violations = violations.lock().unwrap();
+                assert_eq!(violations.len(), 3);
+                assert_eq!(violations[0].message, "offset 2 is not a multiple of 4");
+                assert_eq!(
+                    violations[1].message,
+                    "conditional rendering is already active"
+                );
+                assert_eq!(violations[2].message, "conditional rendering is not ended");
+            }
+
+            pool.free(iter::once(cmd_buffer));
+            device.destroy_command_pool(pool);
+            device.destroy_buffer(buffer);
+            device.free_memory(memory);
+        }
+    }
+
+    #[test]
+    fn report_external_memory() {
+        let (instance, violations) = instance();
+        let adapter = instance.enumerate_adapters().remove(0);
+        let gpu = unsafe {
+            adapter
+                .physical_device
+                .open(
+                    &[(&adapter.queue_families[0], &[1.0])],
+                    hal::Features::EXTERNAL_MEMORY_FD,
+                )
+                .unwrap()
+        };
+        let device = &gpu.device;
+
+        unsafe {
+            let exportable = device
+                .allocate_exportable_memory(
+                    hal::MemoryTypeId(1),
+                    256,
+                    external::MemoryHandleTypes::OPAQUE_FD,
+                )
+                .unwrap();
+            assert!(violations.lock().unwrap().is_empty());
+
+            let memory = device
+                .allocate_exportable_memory(
+                    hal::MemoryTypeId(1),
+                    256,
+                    external::MemoryHandleTypes::DMA_BUF,
+                )
+                .unwrap();
+            let _ = device.export_memory(&memory, external::MemoryHandleType::OpaqueFd);
+            {
+                let violations = violations.lock().unwrap();
+                assert_eq!(violations.len(), 2);
+                assert_eq!(violations[0].call, "Device::allocate_exportable_memory");
+                assert_eq!(
+                    violations[0].message,
+                    "EXTERNAL_MEMORY_DMA_BUF feature is not enabled"
+                );
+                assert_eq!(violations[1].message, "memory isn't exportable as OpaqueFd");
+            }
+
+            device.free_memory(memory);
+            device.free_memory(exportable);
+        }
+    }
+}
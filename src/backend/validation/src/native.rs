@@ -0,0 +1,181 @@
+//! Wrappers of the backend resources, holding the state needed for validation.
+
+use crate::describe;
+use hal::{buffer, external, image, memory, pso, MemoryTypeId};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Memory allocation.
+#[derive(Debug)]
+pub struct Memory<B: hal::Backend> {
+    pub(crate) raw: B::Memory,
+    pub(crate) ty: MemoryTypeId,
+    pub(crate) size: u64,
+    pub(crate) exportable: external::MemoryHandleTypes,
+    mapped: AtomicBool,
+}
+
+impl<B: hal::Backend> Memory<B> {
+    pub(crate) fn new(
+        raw: B::Memory,
+        ty: MemoryTypeId,
+        size: u64,
+        exportable: external::MemoryHandleTypes,
+    ) -> Self {
+        Memory {
+            raw,
+            ty,
+            size,
+            exportable,
+            mapped: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the wrapped memory.
+    pub fn raw(&self) -> &B::Memory {
+        &self.raw
+    }
+
+    pub(crate) fn is_mapped(&self) -> bool {
+        self.mapped.load(Ordering::Acquire)
+    }
+
+    /// Marks the memory as mapped or unmapped, returning the previous state.
+    pub(crate) fn set_mapped(&self, mapped: bool) -> bool {
+        self.mapped.swap(mapped, Ordering::AcqRel)
+    }
+}
+
+/// Buffer, with its creation parameters and binding state.
+#[derive(Debug)]
+pub struct Buffer<B: hal::Backend> {
+    pub(crate) raw: B::Buffer,
+    pub(crate) size: u64,
+    pub(crate) usage: buffer::Usage,
+    pub(crate) requirements: memory::Requirements,
+    pub(crate) sparse: memory::SparseFlags,
+    pub(crate) bound: bool,
+    pub(crate) name: String,
+}
+
+impl<B: hal::Backend> Buffer<B> {
+    /// Returns the wrapped buffer.
+    pub fn raw(&self) -> &B::Buffer {
+        &self.raw
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        describe("buffer", &self.name)
+    }
+}
+
+/// Image, with its creation parameters and binding state.
+#[derive(Debug)]
+pub struct Image<B: hal::Backend> {
+    pub(crate) raw: B::Image,
+    pub(crate) usage: image::Usage,
+    pub(crate) requirements: memory::Requirements,
+    pub(crate) sparse: memory::SparseFlags,
+    pub(crate) bound: bool,
+    pub(crate) name: String,
+}
+
+impl<B: hal::Backend> Image<B> {
+    /// Returns the wrapped image.
+    pub fn raw(&self) -> &B::Image {
+        &self.raw
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        describe("image", &self.name)
+    }
+}
+
+/// Render pass, with the shape used to validate framebuffers and subpasses.
+#[derive(Debug)]
+pub struct RenderPass<B: hal::Backend> {
+    pub(crate) raw: B::RenderPass,
+    pub(crate) attachments: usize,
+    pub(crate) subpasses: usize,
+    pub(crate) name: String,
+}
+
+impl<B: hal::Backend> RenderPass<B> {
+    /// Returns the wrapped render pass.
+    pub fn raw(&self) -> &B::RenderPass {
+        &self.raw
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        describe("render pass", &self.name)
+    }
+}
+
+/// Framebuffer.
+#[derive(Debug)]
+pub struct Framebuffer<B: hal::Backend> {
+    pub(crate) raw: B::Framebuffer,
+    pub(crate) attachments: usize,
+    pub(crate) name: String,
+}
+
+impl<B: hal::Backend> Framebuffer<B> {
+    /// Returns the wrapped framebuffer.
+    pub fn raw(&self) -> &B::Framebuffer {
+        &self.raw
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        describe("framebuffer", &self.name)
+    }
+}
+
+/// Descriptor set layout, keeping its bindings for validating descriptor writes.
+#[derive(Debug)]
+pub struct DescriptorSetLayout<B: hal::Backend> {
+    pub(crate) raw: B::DescriptorSetLayout,
+    pub(crate) bindings: Arc<Vec<pso::DescriptorSetLayoutBinding>>,
+    pub(crate) flags: pso::DescriptorSetLayoutCreateFlags,
+    pub(crate) name: String,
+}
+
+impl<B: hal::Backend> DescriptorSetLayout<B> {
+    /// Returns the wrapped layout.
+    pub fn raw(&self) -> &B::DescriptorSetLayout {
+        &self.raw
+    }
+}
+
+/// Descriptor set, sharing the bindings of its layout.
+#[derive(Debug)]
+pub struct DescriptorSet<B: hal::Backend> {
+    pub(crate) raw: B::DescriptorSet,
+    pub(crate) bindings: Arc<Vec<pso::DescriptorSetLayoutBinding>>,
+    pub(crate) name: String,
+}
+
+impl<B: hal::Backend> DescriptorSet<B> {
+    pub(crate) fn new(raw: B::DescriptorSet, layout: &DescriptorSetLayout<B>) -> Self {
+        DescriptorSet {
+            raw,
+            bindings: Arc::clone(&layout.bindings),
+            name: String::new(),
+        }
+    }
+
+    /// Returns the wrapped descriptor set.
+    pub fn raw(&self) -> &B::DescriptorSet {
+        &self.raw
+    }
+
+    pub(crate) fn describe(&self) -> String {
+        describe("descriptor set", &self.name)
+    }
+
+    pub(crate) fn binding(
+        &self,
+        binding: pso::DescriptorBinding,
+    ) -> Option<&pso::DescriptorSetLayoutBinding> {
+        self.bindings.iter().find(|b| b.binding == binding)
+    }
+}
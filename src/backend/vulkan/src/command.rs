@@ -7,6 +7,7 @@ use std::ops::Range;
 use std::sync::Arc;
 use std::{mem, ptr, slice};
 
+use crate::device::DescriptorWriteBuf;
 use crate::{conv, native as n, Backend, DebugMessenger, RawDevice};
 use hal::{
     buffer,
@@ -185,6 +186,71 @@ impl CommandBuffer {
             );
         }
     }
+
+    fn begin_conditional_rendering_raw(
+        &mut self,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        inverted: bool,
+    ) {
+        let info = vk::ConditionalRenderingBeginInfoEXT {
+            s_type: vk::StructureType::CONDITIONAL_RENDERING_BEGIN_INFO_EXT,
+            p_next: ptr::null(),
+            buffer,
+            offset,
+            flags: if inverted {
+                vk::ConditionalRenderingFlagsEXT::INVERTED
+            } else {
+                vk::ConditionalRenderingFlagsEXT::empty()
+            },
+        };
+        unsafe {
+            self.device
+                .conditional_rendering_fn
+                .as_ref()
+                .expect("CONDITIONAL_RENDERING feature is not enabled")
+                .cmd_begin_conditional_rendering_ext(self.raw, &info);
+        }
+    }
+
+    fn push_descriptor_set<'a, I, J>(
+        &mut self,
+        bind_point: vk::PipelineBindPoint,
+        layout: &n::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        let mut buf = DescriptorWriteBuf::default();
+        for write in writes {
+            buf.push(
+                vk::DescriptorSet::null(),
+                &layout.set_bindings[set],
+                write.binding,
+                write.array_offset,
+                write.descriptors,
+            );
+        }
+        let raw_writes = buf.finish();
+
+        unsafe {
+            self.device
+                .push_descriptor_fn
+                .as_ref()
+                .expect("PUSH_DESCRIPTOR feature is not enabled")
+                .cmd_push_descriptor_set_khr(
+                    self.raw,
+                    bind_point,
+                    layout.raw,
+                    set as u32,
+                    raw_writes.len() as u32,
+                    raw_writes.as_ptr(),
+                );
+        }
+    }
 }
 
 impl com::CommandBuffer<Backend> for CommandBuffer {
@@ -540,6 +606,34 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
             .cmd_bind_vertex_buffers(self.raw, first_binding, &buffers, &offsets);
     }
 
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, first_binding: u32, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<n::Buffer>,
+    {
+        let mut raw_buffers = SmallVec::<[vk::Buffer; 4]>::new();
+        let mut offsets = SmallVec::<[vk::DeviceSize; 4]>::new();
+        let mut sizes = SmallVec::<[vk::DeviceSize; 4]>::new();
+        for (buffer, sub) in buffers {
+            raw_buffers.push(buffer.borrow().raw);
+            offsets.push(sub.offset);
+            sizes.push(sub.size.unwrap_or(vk::WHOLE_SIZE));
+        }
+
+        self.device
+            .transform_feedback_fn
+            .as_ref()
+            .expect("TRANSFORM_FEEDBACK feature is not enabled")
+            .cmd_bind_transform_feedback_buffers_ext(
+                self.raw,
+                first_binding,
+                raw_buffers.len() as u32,
+                raw_buffers.as_ptr(),
+                offsets.as_ptr(),
+                sizes.as_ptr(),
+            );
+    }
+
     unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
     where
         T: IntoIterator,
@@ -641,6 +735,19 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(vk::PipelineBindPoint::GRAPHICS, layout, set, writes);
+    }
+
     unsafe fn bind_compute_pipeline(&mut self, pipeline: &n::ComputePipeline) {
         self.device
             .raw
@@ -668,6 +775,19 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         );
     }
 
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &n::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        self.push_descriptor_set(vk::PipelineBindPoint::COMPUTE, layout, set, writes);
+    }
+
     unsafe fn dispatch(&mut self, count: WorkGroupCount) {
         self.device
             .raw
@@ -829,6 +949,131 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
             .cmd_draw_indexed_indirect(self.raw, buffer.raw, offset, draw_count, stride)
     }
 
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &n::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.device
+            .draw_indirect_count_fn
+            .as_ref()
+            .expect("DRAW_INDIRECT_COUNT feature is not enabled")
+            .cmd_draw_indirect_count_khr(
+                self.raw,
+                buffer.raw,
+                offset,
+                count_buffer.raw,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            );
+    }
+
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &n::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.device
+            .draw_indirect_count_fn
+            .as_ref()
+            .expect("DRAW_INDIRECT_COUNT feature is not enabled")
+            .cmd_draw_indexed_indirect_count_khr(
+                self.raw,
+                buffer.raw,
+                offset,
+                count_buffer.raw,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            );
+    }
+
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &n::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    ) {
+        self.begin_conditional_rendering_raw(buffer.raw, offset, inverted);
+    }
+
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &n::QueryPool,
+        id: query::Id,
+        inverted: bool,
+    ) {
+        // The predicate is read from a buffer, so the query result is copied there first.
+        let (buffer, _) = pool
+            .predicates
+            .expect("CONDITIONAL_RENDERING_QUERY feature is not enabled for this query pool");
+        let offset = id as vk::DeviceSize * 4;
+        self.device.raw.fp_v1_0().cmd_copy_query_pool_results(
+            self.raw,
+            pool.raw,
+            id,
+            1,
+            buffer,
+            offset,
+            4,
+            vk::QueryResultFlags::WAIT,
+        );
+        let barrier = vk::BufferMemoryBarrier {
+            s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+            p_next: ptr::null(),
+            src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+            dst_access_mask: vk::AccessFlags::CONDITIONAL_RENDERING_READ_EXT,
+            src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+            buffer,
+            offset,
+            size: 4,
+        };
+        self.device.raw.cmd_pipeline_barrier(
+            self.raw,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::CONDITIONAL_RENDERING_EXT,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[barrier],
+            &[],
+        );
+        self.begin_conditional_rendering_raw(buffer, offset, inverted);
+    }
+
+    unsafe fn end_conditional_rendering(&mut self) {
+        self.device
+            .conditional_rendering_fn
+            .as_ref()
+            .expect("CONDITIONAL_RENDERING feature is not enabled")
+            .cmd_end_conditional_rendering_ext(self.raw);
+    }
+
+    unsafe fn begin_transform_feedback(&mut self) {
+        self.device
+            .transform_feedback_fn
+            .as_ref()
+            .expect("TRANSFORM_FEEDBACK feature is not enabled")
+            .cmd_begin_transform_feedback_ext(self.raw, 0, 0, ptr::null(), ptr::null());
+    }
+
+    unsafe fn end_transform_feedback(&mut self) {
+        self.device
+            .transform_feedback_fn
+            .as_ref()
+            .expect("TRANSFORM_FEEDBACK feature is not enabled")
+            .cmd_end_transform_feedback_ext(self.raw, 0, 0, ptr::null(), ptr::null());
+    }
+
     unsafe fn set_event(&mut self, event: &n::Event, stage_mask: pso::PipelineStage) {
         self.device.raw.cmd_set_event(
             self.raw,
@@ -878,7 +1123,7 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
     unsafe fn begin_query(&mut self, query: query::Query<Backend>, flags: query::ControlFlags) {
         self.device.raw.cmd_begin_query(
             self.raw,
-            query.pool.0,
+            query.pool.raw,
             query.id,
             conv::map_query_control_flags(flags),
         )
@@ -887,13 +1132,13 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
     unsafe fn end_query(&mut self, query: query::Query<Backend>) {
         self.device
             .raw
-            .cmd_end_query(self.raw, query.pool.0, query.id)
+            .cmd_end_query(self.raw, query.pool.raw, query.id)
     }
 
     unsafe fn reset_query_pool(&mut self, pool: &n::QueryPool, queries: Range<query::Id>) {
         self.device.raw.cmd_reset_query_pool(
             self.raw,
-            pool.0,
+            pool.raw,
             queries.start,
             queries.end - queries.start,
         )
@@ -911,7 +1156,7 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         //TODO: use safer wrapper
         self.device.raw.fp_v1_0().cmd_copy_query_pool_results(
             self.raw,
-            pool.0,
+            pool.raw,
             queries.start,
             queries.end - queries.start,
             buffer.raw,
@@ -925,7 +1170,7 @@ impl com::CommandBuffer<Backend> for CommandBuffer {
         self.device.raw.cmd_write_timestamp(
             self.raw,
             conv::map_pipeline_stage(stage),
-            query.pool.0,
+            query.pool.raw,
             query.id,
         )
     }
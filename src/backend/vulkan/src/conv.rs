@@ -5,9 +5,10 @@ use ash::vk;
 use hal::{
     buffer,
     command,
+    external,
     format,
     image,
-    memory::Segment,
+    memory::{Segment, SparseBind, SparseFlags, SparseImageBind},
     pass,
     pso,
     query,
@@ -563,6 +564,166 @@ pub fn map_view_capabilities(caps: image::ViewCapabilities) -> vk::ImageCreateFl
     vk::ImageCreateFlags::from_raw(caps.bits())
 }
 
+pub fn map_buffer_sparse_flags(sparse: SparseFlags) -> vk::BufferCreateFlags {
+    vk::BufferCreateFlags::from_raw(sparse.bits())
+}
+
+pub fn map_image_sparse_flags(sparse: SparseFlags) -> vk::ImageCreateFlags {
+    vk::ImageCreateFlags::from_raw(sparse.bits())
+}
+
+pub fn map_sparse_bind(bind: &SparseBind<&n::Memory>) -> vk::SparseMemoryBind {
+    let (memory, memory_offset) = bind
+        .memory
+        .map_or((vk::DeviceMemory::null(), 0), |(memory, offset)| {
+            (memory.raw, offset)
+        });
+    vk::SparseMemoryBind {
+        resource_offset: bind.resource_offset,
+        size: bind.size,
+        memory,
+        memory_offset,
+        flags: vk::SparseMemoryBindFlags::empty(),
+    }
+}
+
+pub fn map_sparse_image_bind(bind: &SparseImageBind<&n::Memory>) -> vk::SparseImageMemoryBind {
+    let (memory, memory_offset) = bind
+        .memory
+        .map_or((vk::DeviceMemory::null(), 0), |(memory, offset)| {
+            (memory.raw, offset)
+        });
+    vk::SparseImageMemoryBind {
+        subresource: map_subresource(&bind.subresource),
+        offset: map_offset(bind.offset),
+        extent: map_extent(bind.extent),
+        memory,
+        memory_offset,
+        flags: vk::SparseMemoryBindFlags::empty(),
+    }
+}
+
+pub fn map_vk_sparse_requirements(
+    requirements: &vk::SparseImageMemoryRequirements,
+) -> image::SparseRequirements {
+    let properties = &requirements.format_properties;
+    image::SparseRequirements {
+        aspects: format::Aspects::from_bits_truncate(properties.aspect_mask.as_raw() as u8),
+        granularity: image::Extent {
+            width: properties.image_granularity.width,
+            height: properties.image_granularity.height,
+            depth: properties.image_granularity.depth,
+        },
+        flags: image::SparseFormatFlags::from_bits_truncate(properties.flags.as_raw()),
+        mip_tail_first_level: requirements.image_mip_tail_first_lod as _,
+        mip_tail_size: requirements.image_mip_tail_size,
+        mip_tail_offset: requirements.image_mip_tail_offset,
+        mip_tail_stride: requirements.image_mip_tail_stride,
+    }
+}
+
+pub fn map_memory_handle_type(
+    handle_type: external::MemoryHandleType,
+) -> vk::ExternalMemoryHandleTypeFlags {
+    match handle_type {
+        external::MemoryHandleType::OpaqueFd => vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD,
+        external::MemoryHandleType::DmaBuf => vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT,
+    }
+}
+
+pub fn map_memory_handle_types(
+    handle_types: external::MemoryHandleTypes,
+) -> vk::ExternalMemoryHandleTypeFlags {
+    let mut flags = vk::ExternalMemoryHandleTypeFlags::empty();
+    if handle_types.contains(external::MemoryHandleTypes::OPAQUE_FD) {
+        flags |= vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD;
+    }
+    if handle_types.contains(external::MemoryHandleTypes::DMA_BUF) {
+        flags |= vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT;
+    }
+    flags
+}
+
+pub fn map_vk_memory_handle_types(
+    flags: vk::ExternalMemoryHandleTypeFlags,
+) -> external::MemoryHandleTypes {
+    let mut handle_types = external::MemoryHandleTypes::empty();
+    if flags.contains(vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD) {
+        handle_types |= external::MemoryHandleTypes::OPAQUE_FD;
+    }
+    if flags.contains(vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT) {
+        handle_types |= external::MemoryHandleTypes::DMA_BUF;
+    }
+    handle_types
+}
+
+pub fn map_vk_external_memory_properties(
+    properties: &vk::ExternalMemoryProperties,
+) -> external::ExternalMemoryProperties {
+    let flags = properties.external_memory_features;
+    let mut features = external::ExternalFeatures::empty();
+    // Memory requiring a dedicated allocation can't be expressed with the HAL
+    if !flags.contains(vk::ExternalMemoryFeatureFlags::DEDICATED_ONLY) {
+        if flags.contains(vk::ExternalMemoryFeatureFlags::EXPORTABLE) {
+            features |= external::ExternalFeatures::EXPORTABLE;
+        }
+        if flags.contains(vk::ExternalMemoryFeatureFlags::IMPORTABLE) {
+            features |= external::ExternalFeatures::IMPORTABLE;
+        }
+    }
+    external::ExternalMemoryProperties {
+        features,
+        compatible_handle_types: map_vk_memory_handle_types(properties.compatible_handle_types),
+    }
+}
+
+pub fn map_semaphore_handle_type(
+    handle_type: external::SemaphoreHandleType,
+) -> vk::ExternalSemaphoreHandleTypeFlags {
+    match handle_type {
+        external::SemaphoreHandleType::OpaqueFd => vk::ExternalSemaphoreHandleTypeFlags::OPAQUE_FD,
+        external::SemaphoreHandleType::SyncFd => vk::ExternalSemaphoreHandleTypeFlags::SYNC_FD,
+    }
+}
+
+pub fn map_semaphore_handle_types(
+    handle_types: external::SemaphoreHandleTypes,
+) -> vk::ExternalSemaphoreHandleTypeFlags {
+    let mut flags = vk::ExternalSemaphoreHandleTypeFlags::empty();
+    if handle_types.contains(external::SemaphoreHandleTypes::OPAQUE_FD) {
+        flags |= vk::ExternalSemaphoreHandleTypeFlags::OPAQUE_FD;
+    }
+    if handle_types.contains(external::SemaphoreHandleTypes::SYNC_FD) {
+        flags |= vk::ExternalSemaphoreHandleTypeFlags::SYNC_FD;
+    }
+    flags
+}
+
+pub fn map_vk_external_semaphore_properties(
+    properties: &vk::ExternalSemaphoreProperties,
+) -> external::ExternalSemaphoreProperties {
+    let flags = properties.external_semaphore_features;
+    let mut features = external::ExternalFeatures::empty();
+    if flags.contains(vk::ExternalSemaphoreFeatureFlags::EXPORTABLE) {
+        features |= external::ExternalFeatures::EXPORTABLE;
+    }
+    if flags.contains(vk::ExternalSemaphoreFeatureFlags::IMPORTABLE) {
+        features |= external::ExternalFeatures::IMPORTABLE;
+    }
+    let compatible = properties.compatible_handle_types;
+    let mut compatible_handle_types = external::SemaphoreHandleTypes::empty();
+    if compatible.contains(vk::ExternalSemaphoreHandleTypeFlags::OPAQUE_FD) {
+        compatible_handle_types |= external::SemaphoreHandleTypes::OPAQUE_FD;
+    }
+    if compatible.contains(vk::ExternalSemaphoreHandleTypeFlags::SYNC_FD) {
+        compatible_handle_types |= external::SemaphoreHandleTypes::SYNC_FD;
+    }
+    external::ExternalSemaphoreProperties {
+        features,
+        compatible_handle_types,
+    }
+}
+
 pub fn map_present_mode(mode: PresentMode) -> vk::PresentModeKHR {
     if mode == PresentMode::IMMEDIATE {
         vk::PresentModeKHR::IMMEDIATE
@@ -607,3 +768,15 @@ pub fn map_descriptor_pool_create_flags(
 ) -> vk::DescriptorPoolCreateFlags {
     vk::DescriptorPoolCreateFlags::from_raw(flags.bits())
 }
+
+pub fn map_descriptor_set_layout_flags(
+    flags: pso::DescriptorSetLayoutCreateFlags,
+) -> vk::DescriptorSetLayoutCreateFlags {
+    vk::DescriptorSetLayoutCreateFlags::from_raw(flags.bits())
+}
+
+pub fn map_descriptor_binding_flags(
+    flags: pso::DescriptorBindingFlags,
+) -> vk::DescriptorBindingFlagsEXT {
+    vk::DescriptorBindingFlagsEXT::from_raw(flags.bits())
+}
@@ -6,11 +6,11 @@ use ash::vk::Handle;
 use smallvec::SmallVec;
 
 use hal::{
-    memory::{Requirements, Segment},
+    memory::{self, Requirements, Segment},
     pool::CommandPoolCreateFlags,
     pso::VertexInputRate,
     window::SwapchainConfig,
-    {buffer, device as d, format, image, pass, pso, query, queue},
+    {buffer, device as d, external, format, image, pass, pso, query, queue},
     {Features, MemoryTypeId},
 };
 
@@ -457,6 +457,126 @@ impl ComputePipelineInfoBuf {
     }
 }
 
+/// Raw descriptor writes, along with the descriptor infos they point to.
+#[derive(Debug, Default)]
+pub(crate) struct DescriptorWriteBuf {
+    writes: Vec<vk::WriteDescriptorSet>,
+    image_infos: Vec<vk::DescriptorImageInfo>,
+    buffer_infos: Vec<vk::DescriptorBufferInfo>,
+    texel_buffer_views: Vec<vk::BufferView>,
+}
+
+impl DescriptorWriteBuf {
+    /// Adds a write of descriptors into `set`, whose layout has the given bindings.
+    pub(crate) fn push<'a, J>(
+        &mut self,
+        set: vk::DescriptorSet,
+        bindings: &[pso::DescriptorSetLayoutBinding],
+        binding: pso::DescriptorBinding,
+        array_offset: pso::DescriptorArrayIndex,
+        descriptors: J,
+    ) where
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, B>>,
+    {
+        let layout = bindings
+            .iter()
+            .find(|lb| lb.binding == binding)
+            .expect("Descriptor set writes don't match the set layout!");
+        let mut raw = vk::WriteDescriptorSet {
+            s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+            p_next: ptr::null(),
+            dst_set: set,
+            dst_binding: binding,
+            dst_array_element: array_offset as _,
+            descriptor_count: 0,
+            descriptor_type: conv::map_descriptor_type(layout.ty),
+            p_image_info: ptr::null(),
+            p_buffer_info: ptr::null(),
+            p_texel_buffer_view: ptr::null(),
+        };
+
+        for descriptor in descriptors {
+            raw.descriptor_count += 1;
+            match *descriptor.borrow() {
+                pso::Descriptor::Sampler(sampler) => {
+                    self.image_infos.push(vk::DescriptorImageInfo {
+                        sampler: sampler.0,
+                        image_view: vk::ImageView::null(),
+                        image_layout: vk::ImageLayout::GENERAL,
+                    });
+                }
+                pso::Descriptor::Image(view, layout) => {
+                    self.image_infos.push(vk::DescriptorImageInfo {
+                        sampler: vk::Sampler::null(),
+                        image_view: view.view,
+                        image_layout: conv::map_image_layout(layout),
+                    });
+                }
+                pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
+                    self.image_infos.push(vk::DescriptorImageInfo {
+                        sampler: sampler.0,
+                        image_view: view.view,
+                        image_layout: conv::map_image_layout(layout),
+                    });
+                }
+                pso::Descriptor::Buffer(buffer, ref sub) => {
+                    self.buffer_infos.push(vk::DescriptorBufferInfo {
+                        buffer: buffer.raw,
+                        offset: sub.offset,
+                        range: sub.size.unwrap_or(vk::WHOLE_SIZE),
+                    });
+                }
+                pso::Descriptor::TexelBuffer(view) => {
+                    self.texel_buffer_views.push(view.raw);
+                }
+            }
+        }
+
+        raw.p_image_info = self.image_infos.len() as _;
+        raw.p_buffer_info = self.buffer_infos.len() as _;
+        raw.p_texel_buffer_view = self.texel_buffer_views.len() as _;
+        self.writes.push(raw);
+    }
+
+    /// Returns the raw writes, pointing into the descriptor infos.
+    pub(crate) fn finish(&mut self) -> &[vk::WriteDescriptorSet] {
+        // Patch the pointers now that we have all the storage allocated
+        for raw in &mut self.writes {
+            use crate::vk::DescriptorType as Dt;
+            match raw.descriptor_type {
+                Dt::SAMPLER
+                | Dt::SAMPLED_IMAGE
+                | Dt::STORAGE_IMAGE
+                | Dt::COMBINED_IMAGE_SAMPLER
+                | Dt::INPUT_ATTACHMENT => {
+                    raw.p_buffer_info = ptr::null();
+                    raw.p_texel_buffer_view = ptr::null();
+                    let base = raw.p_image_info as usize - raw.descriptor_count as usize;
+                    raw.p_image_info = self.image_infos[base ..].as_ptr();
+                }
+                Dt::UNIFORM_TEXEL_BUFFER | Dt::STORAGE_TEXEL_BUFFER => {
+                    raw.p_buffer_info = ptr::null();
+                    raw.p_image_info = ptr::null();
+                    let base = raw.p_texel_buffer_view as usize - raw.descriptor_count as usize;
+                    raw.p_texel_buffer_view = self.texel_buffer_views[base ..].as_ptr();
+                }
+                Dt::UNIFORM_BUFFER
+                | Dt::STORAGE_BUFFER
+                | Dt::STORAGE_BUFFER_DYNAMIC
+                | Dt::UNIFORM_BUFFER_DYNAMIC => {
+                    raw.p_image_info = ptr::null();
+                    raw.p_texel_buffer_view = ptr::null();
+                    let base = raw.p_buffer_info as usize - raw.descriptor_count as usize;
+                    raw.p_buffer_info = self.buffer_infos[base ..].as_ptr();
+                }
+                _ => panic!("unknown descriptor type"),
+            }
+        }
+        &self.writes
+    }
+}
+
 impl d::Device<B> for Device {
     unsafe fn allocate_memory(
         &self,
@@ -481,6 +601,122 @@ impl d::Device<B> for Device {
         }
     }
 
+    unsafe fn allocate_exportable_memory(
+        &self,
+        mem_type: MemoryTypeId,
+        size: u64,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Memory, d::AllocationError> {
+        let export_info = vk::ExportMemoryAllocateInfo {
+            s_type: vk::StructureType::EXPORT_MEMORY_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            handle_types: conv::map_memory_handle_types(handle_types),
+        };
+        let info = vk::MemoryAllocateInfo {
+            s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+            p_next: &export_info as *const _ as *const _,
+            allocation_size: size,
+            memory_type_index: mem_type.0 as _,
+        };
+
+        let result = self.shared.raw.allocate_memory(&info, None);
+
+        match result {
+            Ok(memory) => Ok(n::Memory { raw: memory }),
+            Err(vk::Result::ERROR_TOO_MANY_OBJECTS) => Err(d::AllocationError::TooManyObjects),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn export_memory(
+        &self,
+        memory: &n::Memory,
+        handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        let info = vk::MemoryGetFdInfoKHR {
+            s_type: vk::StructureType::MEMORY_GET_FD_INFO_KHR,
+            p_next: ptr::null(),
+            memory: memory.raw,
+            handle_type: conv::map_memory_handle_type(handle_type),
+        };
+        let mut fd = -1;
+
+        let result = self.external_memory_fd_fn().get_memory_fd_khr(
+            self.shared.raw.handle(),
+            &info,
+            &mut fd,
+        );
+
+        match result {
+            vk::Result::SUCCESS => Ok(fd),
+            vk::Result::ERROR_TOO_MANY_OBJECTS => Err(external::ExportError::TooManyObjects),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<u64, external::ImportError> {
+        let mut properties = vk::MemoryFdPropertiesKHR {
+            s_type: vk::StructureType::MEMORY_FD_PROPERTIES_KHR,
+            p_next: ptr::null_mut(),
+            memory_type_bits: 0,
+        };
+
+        let result = self.external_memory_fd_fn().get_memory_fd_properties_khr(
+            self.shared.raw.handle(),
+            conv::map_memory_handle_type(handle_type),
+            fd,
+            &mut properties,
+        );
+
+        match result {
+            vk::Result::SUCCESS => Ok(properties.memory_type_bits as _),
+            vk::Result::ERROR_INVALID_EXTERNAL_HANDLE => Err(external::ImportError::InvalidHandle),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn import_memory(
+        &self,
+        mem_type: MemoryTypeId,
+        size: u64,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<n::Memory, external::ImportError> {
+        let import_info = vk::ImportMemoryFdInfoKHR {
+            s_type: vk::StructureType::IMPORT_MEMORY_FD_INFO_KHR,
+            p_next: ptr::null(),
+            handle_type: conv::map_memory_handle_type(handle_type),
+            fd,
+        };
+        let info = vk::MemoryAllocateInfo {
+            s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+            p_next: &import_info as *const _ as *const _,
+            allocation_size: size,
+            memory_type_index: mem_type.0 as _,
+        };
+
+        let result = self.shared.raw.allocate_memory(&info, None);
+
+        match result {
+            Ok(memory) => Ok(n::Memory { raw: memory }),
+            Err(vk::Result::ERROR_INVALID_EXTERNAL_HANDLE) => {
+                Err(external::ImportError::InvalidHandle)
+            }
+            Err(vk::Result::ERROR_TOO_MANY_OBJECTS) => Err(external::ImportError::TooManyObjects),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
     unsafe fn create_command_pool(
         &self,
         family: queue::QueueFamilyId,
@@ -523,6 +759,7 @@ impl d::Device<B> for Device {
         attachments: IA,
         subpasses: IS,
         dependencies: ID,
+        correlation_masks: &[pass::ViewMask],
     ) -> Result<n::RenderPass, d::OutOfMemory>
     where
         IA: IntoIterator,
@@ -588,14 +825,26 @@ impl d::Device<B> for Device {
                     .collect::<Box<[_]>>();
                 let resolves = subpass.resolves.iter().map(make_ref).collect::<Box<[_]>>();
 
-                (colors, depth_stencil, inputs, preserves, resolves)
+                (
+                    colors,
+                    depth_stencil,
+                    inputs,
+                    preserves,
+                    resolves,
+                    subpass.view_mask,
+                )
             })
             .collect::<Box<[_]>>();
 
+        let view_masks = attachment_refs
+            .iter()
+            .map(|&(.., view_mask)| view_mask)
+            .collect::<Vec<_>>();
+
         let subpasses = attachment_refs
             .iter()
             .map(
-                |(colors, depth_stencil, inputs, preserves, resolves)| vk::SubpassDescription {
+                |(colors, depth_stencil, inputs, preserves, resolves, _)| vk::SubpassDescription {
                     flags: vk::SubpassDescriptionFlags::empty(),
                     pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
                     input_attachment_count: inputs.len() as u32,
@@ -637,9 +886,22 @@ impl d::Device<B> for Device {
             })
             .collect::<Vec<_>>();
 
+        // View offsets of the dependencies are left at zero
+        let multiview_info = vk::RenderPassMultiviewCreateInfoKHR {
+            subpass_count: view_masks.len() as u32,
+            p_view_masks: view_masks.as_ptr(),
+            correlation_mask_count: correlation_masks.len() as u32,
+            p_correlation_masks: correlation_masks.as_ptr(),
+            ..Default::default()
+        };
+
         let info = vk::RenderPassCreateInfo {
             s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
-            p_next: ptr::null(),
+            p_next: if view_masks.iter().any(|&mask| mask != 0) {
+                &multiview_info as *const _ as *const _
+            } else {
+                ptr::null()
+            },
             flags: vk::RenderPassCreateFlags::empty(),
             attachment_count: attachments.len() as u32,
             p_attachments: attachments.as_ptr(),
@@ -673,10 +935,8 @@ impl d::Device<B> for Device {
         IR: IntoIterator,
         IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
     {
-        let set_layouts = sets
-            .into_iter()
-            .map(|set| set.borrow().raw)
-            .collect::<Vec<_>>();
+        let sets = sets.into_iter().collect::<Vec<_>>();
+        let set_layouts = sets.iter().map(|set| set.borrow().raw).collect::<Vec<_>>();
 
         debug!("create_pipeline_layout {:?}", set_layouts);
 
@@ -705,7 +965,13 @@ impl d::Device<B> for Device {
         let result = self.shared.raw.create_pipeline_layout(&info, None);
 
         match result {
-            Ok(raw) => Ok(n::PipelineLayout { raw }),
+            Ok(raw) => Ok(n::PipelineLayout {
+                raw,
+                set_bindings: sets
+                    .iter()
+                    .map(|set| Arc::clone(&set.borrow().bindings))
+                    .collect(),
+            }),
             Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
             Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
             _ => unreachable!(),
@@ -1305,11 +1571,44 @@ impl d::Device<B> for Device {
         &self,
         size: u64,
         usage: buffer::Usage,
+        sparse: memory::SparseFlags,
     ) -> Result<n::Buffer, buffer::CreationError> {
         let info = vk::BufferCreateInfo {
             s_type: vk::StructureType::BUFFER_CREATE_INFO,
             p_next: ptr::null(),
-            flags: vk::BufferCreateFlags::empty(), // TODO:
+            flags: conv::map_buffer_sparse_flags(sparse),
+            size,
+            usage: conv::map_buffer_usage(usage),
+            sharing_mode: vk::SharingMode::EXCLUSIVE, // TODO:
+            queue_family_index_count: 0,
+            p_queue_family_indices: ptr::null(),
+        };
+
+        let result = self.shared.raw.create_buffer(&info, None);
+
+        match result {
+            Ok(raw) => Ok(n::Buffer { raw }),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Buffer, buffer::CreationError> {
+        let external_info = vk::ExternalMemoryBufferCreateInfo {
+            s_type: vk::StructureType::EXTERNAL_MEMORY_BUFFER_CREATE_INFO,
+            p_next: ptr::null(),
+            handle_types: conv::map_memory_handle_types(handle_types),
+        };
+        let info = vk::BufferCreateInfo {
+            s_type: vk::StructureType::BUFFER_CREATE_INFO,
+            p_next: &external_info as *const _ as *const _,
+            flags: vk::BufferCreateFlags::empty(),
             size,
             usage: conv::map_buffer_usage(usage),
             sharing_mode: vk::SharingMode::EXCLUSIVE, // TODO:
@@ -1390,48 +1689,87 @@ impl d::Device<B> for Device {
         tiling: image::Tiling,
         usage: image::Usage,
         view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
     ) -> Result<n::Image, image::CreationError> {
-        let flags = conv::map_view_capabilities(view_caps);
-        let extent = conv::map_extent(kind.extent());
-        let array_layers = kind.num_layers();
-        let samples = kind.num_samples() as u32;
-        let image_type = match kind {
-            image::Kind::D1(..) => vk::ImageType::TYPE_1D,
-            image::Kind::D2(..) => vk::ImageType::TYPE_2D,
-            image::Kind::D3(..) => vk::ImageType::TYPE_3D,
-        };
+        self.create_image_raw(
+            kind,
+            mip_levels,
+            format,
+            conv::map_tiling(tiling),
+            usage,
+            conv::map_view_capabilities(view_caps) | conv::map_image_sparse_flags(sparse),
+            ptr::null(),
+        )
+    }
 
-        let info = vk::ImageCreateInfo {
-            s_type: vk::StructureType::IMAGE_CREATE_INFO,
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<n::Image, image::CreationError> {
+        let external_info = vk::ExternalMemoryImageCreateInfo {
+            s_type: vk::StructureType::EXTERNAL_MEMORY_IMAGE_CREATE_INFO,
             p_next: ptr::null(),
-            flags,
-            image_type,
-            format: conv::map_format(format),
-            extent: extent.clone(),
-            mip_levels: mip_levels as u32,
-            array_layers: array_layers as u32,
-            samples: vk::SampleCountFlags::from_raw(samples & vk::SampleCountFlags::all().as_raw()),
-            tiling: conv::map_tiling(tiling),
-            usage: conv::map_image_usage(usage),
-            sharing_mode: vk::SharingMode::EXCLUSIVE, // TODO:
-            queue_family_index_count: 0,
-            p_queue_family_indices: ptr::null(),
-            initial_layout: vk::ImageLayout::UNDEFINED,
+            handle_types: conv::map_memory_handle_types(handle_types),
         };
 
-        let result = self.shared.raw.create_image(&info, None);
+        self.create_image_raw(
+            kind,
+            mip_levels,
+            format,
+            conv::map_tiling(tiling),
+            usage,
+            conv::map_view_capabilities(view_caps),
+            &external_info as *const _ as *const _,
+        )
+    }
 
-        match result {
-            Ok(raw) => Ok(n::Image {
-                raw,
-                ty: image_type,
-                flags,
-                extent,
-            }),
-            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
-            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
-            _ => unreachable!(),
-        }
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        modifier: external::DrmFormatModifier,
+    ) -> Result<n::Image, image::CreationError> {
+        let plane_layouts = modifier
+            .planes
+            .iter()
+            .map(|plane| vk::SubresourceLayout {
+                offset: plane.offset,
+                size: 0,
+                row_pitch: plane.row_pitch,
+                array_pitch: 0,
+                depth_pitch: 0,
+            })
+            .collect::<SmallVec<[_; 4]>>();
+        let modifier_info = vk::ImageDrmFormatModifierExplicitCreateInfoEXT {
+            s_type: vk::StructureType::IMAGE_DRM_FORMAT_MODIFIER_EXPLICIT_CREATE_INFO_EXT,
+            p_next: ptr::null(),
+            drm_format_modifier: modifier.modifier,
+            drm_format_modifier_plane_count: plane_layouts.len() as u32,
+            p_plane_layouts: plane_layouts.as_ptr(),
+        };
+        let external_info = vk::ExternalMemoryImageCreateInfo {
+            s_type: vk::StructureType::EXTERNAL_MEMORY_IMAGE_CREATE_INFO,
+            p_next: &modifier_info as *const _ as *const _,
+            handle_types: vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT,
+        };
+
+        self.create_image_raw(
+            kind,
+            1,
+            format,
+            vk::ImageTiling::DRM_FORMAT_MODIFIER_EXT,
+            usage,
+            conv::map_view_capabilities(view_caps),
+            &external_info as *const _ as *const _,
+        )
     }
 
     unsafe fn get_image_requirements(&self, image: &n::Image) -> Requirements {
@@ -1444,6 +1782,18 @@ impl d::Device<B> for Device {
         }
     }
 
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        image: &n::Image,
+    ) -> Vec<image::SparseRequirements> {
+        self.shared
+            .raw
+            .get_image_sparse_memory_requirements(image.raw)
+            .iter()
+            .map(conv::map_vk_sparse_requirements)
+            .collect()
+    }
+
     unsafe fn get_image_subresource_footprint(
         &self,
         image: &n::Image,
@@ -1566,6 +1916,7 @@ impl d::Device<B> for Device {
         &self,
         binding_iter: I,
         immutable_sampler_iter: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<n::DescriptorSetLayout, d::OutOfMemory>
     where
         I: IntoIterator,
@@ -1605,10 +1956,33 @@ impl d::Device<B> for Device {
 
         debug!("create_descriptor_set_layout {:?}", raw_bindings);
 
+        let raw_binding_flags = bindings
+            .iter()
+            .map(|b| conv::map_descriptor_binding_flags(b.flags))
+            .collect::<Vec<_>>();
+        let binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO_EXT,
+            p_next: ptr::null(),
+            binding_count: raw_binding_flags.len() as _,
+            p_binding_flags: raw_binding_flags.as_ptr(),
+        };
+        let mut raw_flags = conv::map_descriptor_set_layout_flags(flags);
+        if bindings.iter().any(|b| {
+            b.flags
+                .contains(pso::DescriptorBindingFlags::UPDATE_AFTER_BIND)
+        }) {
+            raw_flags |= vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL_EXT;
+        }
+
         let info = vk::DescriptorSetLayoutCreateInfo {
             s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+            // The binding flags are only known with `VK_EXT_descriptor_indexing`
+            p_next: if bindings.iter().any(|b| !b.flags.is_empty()) {
+                &binding_flags_info as *const _ as *const _
+            } else {
+                ptr::null()
+            },
+            flags: raw_flags,
             binding_count: raw_bindings.len() as _,
             p_bindings: raw_bindings.as_ptr(),
         };
@@ -1632,108 +2006,17 @@ impl d::Device<B> for Device {
         J: IntoIterator,
         J::Item: Borrow<pso::Descriptor<'a, B>>,
     {
-        let mut raw_writes = Vec::new();
-        let mut image_infos = Vec::new();
-        let mut buffer_infos = Vec::new();
-        let mut texel_buffer_views = Vec::new();
-
+        let mut buf = DescriptorWriteBuf::default();
         for sw in write_iter {
-            let layout = sw
-                .set
-                .bindings
-                .iter()
-                .find(|lb| lb.binding == sw.binding)
-                .expect("Descriptor set writes don't match the set layout!");
-            let mut raw = vk::WriteDescriptorSet {
-                s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
-                p_next: ptr::null(),
-                dst_set: sw.set.raw,
-                dst_binding: sw.binding,
-                dst_array_element: sw.array_offset as _,
-                descriptor_count: 0,
-                descriptor_type: conv::map_descriptor_type(layout.ty),
-                p_image_info: ptr::null(),
-                p_buffer_info: ptr::null(),
-                p_texel_buffer_view: ptr::null(),
-            };
-
-            for descriptor in sw.descriptors {
-                raw.descriptor_count += 1;
-                match *descriptor.borrow() {
-                    pso::Descriptor::Sampler(sampler) => {
-                        image_infos.push(vk::DescriptorImageInfo {
-                            sampler: sampler.0,
-                            image_view: vk::ImageView::null(),
-                            image_layout: vk::ImageLayout::GENERAL,
-                        });
-                    }
-                    pso::Descriptor::Image(view, layout) => {
-                        image_infos.push(vk::DescriptorImageInfo {
-                            sampler: vk::Sampler::null(),
-                            image_view: view.view,
-                            image_layout: conv::map_image_layout(layout),
-                        });
-                    }
-                    pso::Descriptor::CombinedImageSampler(view, layout, sampler) => {
-                        image_infos.push(vk::DescriptorImageInfo {
-                            sampler: sampler.0,
-                            image_view: view.view,
-                            image_layout: conv::map_image_layout(layout),
-                        });
-                    }
-                    pso::Descriptor::Buffer(buffer, ref sub) => {
-                        buffer_infos.push(vk::DescriptorBufferInfo {
-                            buffer: buffer.raw,
-                            offset: sub.offset,
-                            range: sub.size.unwrap_or(vk::WHOLE_SIZE),
-                        });
-                    }
-                    pso::Descriptor::TexelBuffer(view) => {
-                        texel_buffer_views.push(view.raw);
-                    }
-                }
-            }
-
-            raw.p_image_info = image_infos.len() as _;
-            raw.p_buffer_info = buffer_infos.len() as _;
-            raw.p_texel_buffer_view = texel_buffer_views.len() as _;
-            raw_writes.push(raw);
-        }
-
-        // Patch the pointers now that we have all the storage allocated
-        for raw in &mut raw_writes {
-            use crate::vk::DescriptorType as Dt;
-            match raw.descriptor_type {
-                Dt::SAMPLER
-                | Dt::SAMPLED_IMAGE
-                | Dt::STORAGE_IMAGE
-                | Dt::COMBINED_IMAGE_SAMPLER
-                | Dt::INPUT_ATTACHMENT => {
-                    raw.p_buffer_info = ptr::null();
-                    raw.p_texel_buffer_view = ptr::null();
-                    let base = raw.p_image_info as usize - raw.descriptor_count as usize;
-                    raw.p_image_info = image_infos[base ..].as_ptr();
-                }
-                Dt::UNIFORM_TEXEL_BUFFER | Dt::STORAGE_TEXEL_BUFFER => {
-                    raw.p_buffer_info = ptr::null();
-                    raw.p_image_info = ptr::null();
-                    let base = raw.p_texel_buffer_view as usize - raw.descriptor_count as usize;
-                    raw.p_texel_buffer_view = texel_buffer_views[base ..].as_ptr();
-                }
-                Dt::UNIFORM_BUFFER
-                | Dt::STORAGE_BUFFER
-                | Dt::STORAGE_BUFFER_DYNAMIC
-                | Dt::UNIFORM_BUFFER_DYNAMIC => {
-                    raw.p_image_info = ptr::null();
-                    raw.p_texel_buffer_view = ptr::null();
-                    let base = raw.p_buffer_info as usize - raw.descriptor_count as usize;
-                    raw.p_buffer_info = buffer_infos[base ..].as_ptr();
-                }
-                _ => panic!("unknown descriptor type"),
-            }
+            buf.push(
+                sw.set.raw,
+                &sw.set.bindings,
+                sw.binding,
+                sw.array_offset,
+                sw.descriptors,
+            );
         }
-
-        self.shared.raw.update_descriptor_sets(&raw_writes, &[]);
+        self.shared.raw.update_descriptor_sets(buf.finish(), &[]);
     }
 
     unsafe fn copy_descriptor_sets<'a, I>(&self, copies: I)
@@ -1836,6 +2119,181 @@ impl d::Device<B> for Device {
         }
     }
 
+    fn create_exportable_semaphore(
+        &self,
+        handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        let export_info = vk::ExportSemaphoreCreateInfo {
+            s_type: vk::StructureType::EXPORT_SEMAPHORE_CREATE_INFO,
+            p_next: ptr::null(),
+            handle_types: conv::map_semaphore_handle_types(handle_types),
+        };
+        let info = vk::SemaphoreCreateInfo {
+            s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
+            p_next: &export_info as *const _ as *const _,
+            flags: vk::SemaphoreCreateFlags::empty(),
+        };
+
+        let result = unsafe { self.shared.raw.create_semaphore(&info, None) };
+
+        match result {
+            Ok(semaphore) => Ok(n::Semaphore(semaphore)),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn export_semaphore(
+        &self,
+        semaphore: &n::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError> {
+        let info = vk::SemaphoreGetFdInfoKHR {
+            s_type: vk::StructureType::SEMAPHORE_GET_FD_INFO_KHR,
+            p_next: ptr::null(),
+            semaphore: semaphore.0,
+            handle_type: conv::map_semaphore_handle_type(handle_type),
+        };
+        let mut fd = -1;
+
+        let result = self.external_semaphore_fd_fn().get_semaphore_fd_khr(
+            self.shared.raw.handle(),
+            &info,
+            &mut fd,
+        );
+
+        match result {
+            vk::Result::SUCCESS => Ok(fd),
+            vk::Result::ERROR_TOO_MANY_OBJECTS => Err(external::ExportError::TooManyObjects),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn import_semaphore(
+        &self,
+        semaphore: &n::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+        fd: external::Fd,
+    ) -> Result<(), external::ImportError> {
+        let info = vk::ImportSemaphoreFdInfoKHR {
+            s_type: vk::StructureType::IMPORT_SEMAPHORE_FD_INFO_KHR,
+            p_next: ptr::null(),
+            semaphore: semaphore.0,
+            flags: match handle_type {
+                external::SemaphoreHandleType::OpaqueFd => vk::SemaphoreImportFlags::empty(),
+                // Sync files only carry a single payload, which has to be imported temporarily
+                external::SemaphoreHandleType::SyncFd => vk::SemaphoreImportFlags::TEMPORARY,
+            },
+            handle_type: conv::map_semaphore_handle_type(handle_type),
+            fd,
+        };
+
+        let result = self
+            .external_semaphore_fd_fn()
+            .import_semaphore_fd_khr(self.shared.raw.handle(), &info);
+
+        match result {
+            vk::Result::SUCCESS => Ok(()),
+            vk::Result::ERROR_INVALID_EXTERNAL_HANDLE => Err(external::ImportError::InvalidHandle),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: queue::SemaphoreValue,
+    ) -> Result<n::Semaphore, d::OutOfMemory> {
+        let type_info = vk::SemaphoreTypeCreateInfoKHR {
+            s_type: vk::StructureType::SEMAPHORE_TYPE_CREATE_INFO_KHR,
+            p_next: ptr::null(),
+            semaphore_type: vk::SemaphoreTypeKHR::TIMELINE,
+            initial_value,
+        };
+        let info = vk::SemaphoreCreateInfo {
+            s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
+            p_next: &type_info as *const _ as *const _,
+            flags: vk::SemaphoreCreateFlags::empty(),
+        };
+
+        let result = unsafe { self.shared.raw.create_semaphore(&info, None) };
+
+        match result {
+            Ok(semaphore) => Ok(n::Semaphore(semaphore)),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &n::Semaphore,
+    ) -> Result<queue::SemaphoreValue, d::DeviceLost> {
+        let mut value = 0;
+        let result = self
+            .timeline_semaphore_fn()
+            .get_semaphore_counter_value_khr(self.shared.raw.handle(), semaphore.0, &mut value);
+        match result {
+            vk::Result::SUCCESS => Ok(value),
+            vk::Result::ERROR_DEVICE_LOST => Err(d::DeviceLost),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &n::Semaphore,
+        value: queue::SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, d::OomOrDeviceLost> {
+        let info = vk::SemaphoreWaitInfoKHR {
+            s_type: vk::StructureType::SEMAPHORE_WAIT_INFO_KHR,
+            p_next: ptr::null(),
+            flags: vk::SemaphoreWaitFlagsKHR::empty(),
+            semaphore_count: 1,
+            p_semaphores: &semaphore.0,
+            p_values: &value,
+        };
+        let result = self.timeline_semaphore_fn().wait_semaphores_khr(
+            self.shared.raw.handle(),
+            &info,
+            timeout_ns,
+        );
+        match result {
+            vk::Result::SUCCESS => Ok(true),
+            vk::Result::TIMEOUT => Ok(false),
+            vk::Result::ERROR_DEVICE_LOST => Err(d::DeviceLost.into()),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host.into()),
+            vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &n::Semaphore,
+        value: queue::SemaphoreValue,
+    ) -> Result<(), d::OutOfMemory> {
+        let info = vk::SemaphoreSignalInfoKHR {
+            s_type: vk::StructureType::SEMAPHORE_SIGNAL_INFO_KHR,
+            p_next: ptr::null(),
+            semaphore: semaphore.0,
+            value,
+        };
+        let result = self
+            .timeline_semaphore_fn()
+            .signal_semaphore_khr(self.shared.raw.handle(), &info);
+        match result {
+            vk::Result::SUCCESS => Ok(()),
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(d::OutOfMemory::Host),
+            vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(d::OutOfMemory::Device),
+            _ => unreachable!(),
+        }
+    }
+
     fn create_fence(&self, signaled: bool) -> Result<n::Fence, d::OutOfMemory> {
         let info = vk::FenceCreateInfo {
             s_type: vk::StructureType::FENCE_CREATE_INFO,
@@ -1984,6 +2442,10 @@ impl d::Device<B> for Device {
                 vk::QueryType::TIMESTAMP,
                 vk::QueryPipelineStatisticFlags::empty(),
             ),
+            query::Type::PrimitivesWritten => (
+                vk::QueryType::TRANSFORM_FEEDBACK_STREAM_EXT,
+                vk::QueryPipelineStatisticFlags::empty(),
+            ),
         };
 
         let info = vk::QueryPoolCreateInfo {
@@ -1997,12 +2459,34 @@ impl d::Device<B> for Device {
 
         let result = self.shared.raw.create_query_pool(&info, None);
 
-        match result {
-            Ok(pool) => Ok(n::QueryPool(pool)),
-            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
-            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+        let raw = match result {
+            Ok(pool) => pool,
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => return Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => {
+                return Err(d::OutOfMemory::Device.into())
+            }
             _ => unreachable!(),
-        }
+        };
+
+        let predicates = if query_type == vk::QueryType::OCCLUSION
+            && query_count != 0
+            && self
+                .shared
+                .features
+                .contains(Features::CONDITIONAL_RENDERING_QUERY)
+        {
+            match self.create_query_predicates(query_count) {
+                Ok(predicates) => Some(predicates),
+                Err(err) => {
+                    self.shared.raw.destroy_query_pool(raw, None);
+                    return Err(err.into());
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(n::QueryPool { raw, predicates })
     }
 
     unsafe fn get_query_pool_results(
@@ -2015,7 +2499,7 @@ impl d::Device<B> for Device {
     ) -> Result<bool, d::OomOrDeviceLost> {
         let result = self.shared.raw.fp_v1_0().get_query_pool_results(
             self.shared.raw.handle(),
-            pool.0,
+            pool.raw,
             queries.start,
             queries.end - queries.start,
             data.len(),
@@ -2132,7 +2616,11 @@ impl d::Device<B> for Device {
     }
 
     unsafe fn destroy_query_pool(&self, pool: n::QueryPool) {
-        self.shared.raw.destroy_query_pool(pool.0, None);
+        self.shared.raw.destroy_query_pool(pool.raw, None);
+        if let Some((buffer, memory)) = pool.predicates {
+            self.shared.raw.destroy_buffer(buffer, None);
+            self.shared.raw.free_memory(memory, None);
+        }
     }
 
     unsafe fn destroy_shader_module(&self, module: n::ShaderModule) {
@@ -2271,6 +2759,132 @@ impl d::Device<B> for Device {
 }
 
 impl Device {
+    fn timeline_semaphore_fn(&self) -> &vk::KhrTimelineSemaphoreFn {
+        self.shared
+            .timeline_semaphore_fn
+            .as_ref()
+            .expect("TIMELINE_SEMAPHORE feature is not enabled")
+    }
+
+    fn external_memory_fd_fn(&self) -> &vk::KhrExternalMemoryFdFn {
+        self.shared
+            .external_memory_fd_fn
+            .as_ref()
+            .expect("EXTERNAL_MEMORY_FD feature is not enabled")
+    }
+
+    fn external_semaphore_fd_fn(&self) -> &vk::KhrExternalSemaphoreFdFn {
+        self.shared
+            .external_semaphore_fd_fn
+            .as_ref()
+            .expect("EXTERNAL_SEMAPHORE_FD feature is not enabled")
+    }
+
+    /// Creates the buffer which results of occlusion queries are copied into, to drive
+    /// conditional rendering.
+    unsafe fn create_query_predicates(
+        &self,
+        query_count: query::Id,
+    ) -> Result<(vk::Buffer, vk::DeviceMemory), d::OutOfMemory> {
+        let map_err = |err| match err {
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY => d::OutOfMemory::Host,
+            vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => d::OutOfMemory::Device,
+            _ => unreachable!(),
+        };
+
+        let info = vk::BufferCreateInfo {
+            s_type: vk::StructureType::BUFFER_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::BufferCreateFlags::empty(),
+            size: query_count as vk::DeviceSize * 4,
+            usage: vk::BufferUsageFlags::CONDITIONAL_RENDERING_EXT
+                | vk::BufferUsageFlags::TRANSFER_DST,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_family_index_count: 0,
+            p_queue_family_indices: ptr::null(),
+        };
+        let buffer = self
+            .shared
+            .raw
+            .create_buffer(&info, None)
+            .map_err(map_err)?;
+
+        // The buffer is only accessed by the device, so any supported memory type fits.
+        let requirements = self.shared.raw.get_buffer_memory_requirements(buffer);
+        let info = vk::MemoryAllocateInfo {
+            s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            allocation_size: requirements.size,
+            memory_type_index: requirements.memory_type_bits.trailing_zeros(),
+        };
+        let memory = match self.shared.raw.allocate_memory(&info, None) {
+            Ok(memory) => memory,
+            Err(err) => {
+                self.shared.raw.destroy_buffer(buffer, None);
+                return Err(map_err(err));
+            }
+        };
+        if let Err(err) = self.shared.raw.bind_buffer_memory(buffer, memory, 0) {
+            self.shared.raw.destroy_buffer(buffer, None);
+            self.shared.raw.free_memory(memory, None);
+            return Err(map_err(err));
+        }
+
+        Ok((buffer, memory))
+    }
+
+    unsafe fn create_image_raw(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: vk::ImageTiling,
+        usage: image::Usage,
+        flags: vk::ImageCreateFlags,
+        p_next: *const std::os::raw::c_void,
+    ) -> Result<n::Image, image::CreationError> {
+        let extent = conv::map_extent(kind.extent());
+        let array_layers = kind.num_layers();
+        let samples = kind.num_samples() as u32;
+        let image_type = match kind {
+            image::Kind::D1(..) => vk::ImageType::TYPE_1D,
+            image::Kind::D2(..) => vk::ImageType::TYPE_2D,
+            image::Kind::D3(..) => vk::ImageType::TYPE_3D,
+        };
+
+        let info = vk::ImageCreateInfo {
+            s_type: vk::StructureType::IMAGE_CREATE_INFO,
+            p_next,
+            flags,
+            image_type,
+            format: conv::map_format(format),
+            extent: extent.clone(),
+            mip_levels: mip_levels as u32,
+            array_layers: array_layers as u32,
+            samples: vk::SampleCountFlags::from_raw(samples & vk::SampleCountFlags::all().as_raw()),
+            tiling,
+            usage: conv::map_image_usage(usage),
+            sharing_mode: vk::SharingMode::EXCLUSIVE, // TODO:
+            queue_family_index_count: 0,
+            p_queue_family_indices: ptr::null(),
+            initial_layout: vk::ImageLayout::UNDEFINED,
+        };
+
+        let result = self.shared.raw.create_image(&info, None);
+
+        match result {
+            Ok(raw) => Ok(n::Image {
+                raw,
+                ty: image_type,
+                flags,
+                extent,
+            }),
+            Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY) => Err(d::OutOfMemory::Host.into()),
+            Err(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => Err(d::OutOfMemory::Device.into()),
+            _ => unreachable!(),
+        }
+    }
+
     unsafe fn set_object_name(&self, object_type: vk::ObjectType, object_handle: u64, name: &str) {
         let instance = &self.shared.instance;
         if let Some(DebugMessenger::Utils(ref debug_utils_ext, _)) = instance.1 {
@@ -20,7 +20,9 @@ use ash::{Entry, LoadingError};
 
 use hal::{
     adapter,
+    buffer,
     device::{CreationError as DeviceCreationError, DeviceLost, OutOfMemory, SurfaceLost},
+    external,
     format,
     image,
     memory,
@@ -89,6 +91,8 @@ lazy_static! {
         extensions::khr::Win32Surface::name(),
         #[cfg(target_os = "macos")]
         extensions::mvk::MacOSSurface::name(),
+        // Offscreen presentation, not tied to any platform
+        vk::ExtHeadlessSurfaceFn::name(),
     ];
     static ref AMD_NEGATIVE_VIEWPORT_HEIGHT: &'static CStr =
         CStr::from_bytes_with_nul(b"VK_AMD_negative_viewport_height\0").unwrap();
@@ -96,8 +100,69 @@ lazy_static! {
         CStr::from_bytes_with_nul(b"VK_KHR_maintenance1\0").unwrap();
     static ref KHR_SAMPLER_MIRROR_MIRROR_CLAMP_TO_EDGE : &'static CStr =
         CStr::from_bytes_with_nul(b"VK_KHR_sampler_mirror_clamp_to_edge\0").unwrap();
+    static ref KHR_TIMELINE_SEMAPHORE: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_timeline_semaphore\0").unwrap();
+    static ref KHR_MAINTENANCE3: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_maintenance3\0").unwrap();
+    static ref EXT_DESCRIPTOR_INDEXING: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_EXT_descriptor_indexing\0").unwrap();
+    static ref KHR_PUSH_DESCRIPTOR: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_push_descriptor\0").unwrap();
+    static ref KHR_MULTIVIEW: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_multiview\0").unwrap();
+    static ref KHR_DRAW_INDIRECT_COUNT: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_draw_indirect_count\0").unwrap();
+    static ref EXT_CONDITIONAL_RENDERING: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_EXT_conditional_rendering\0").unwrap();
+    static ref EXT_TRANSFORM_FEEDBACK: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_EXT_transform_feedback\0").unwrap();
+    static ref KHR_EXTERNAL_MEMORY: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_external_memory\0").unwrap();
+    static ref KHR_EXTERNAL_MEMORY_FD: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_external_memory_fd\0").unwrap();
+    static ref EXT_EXTERNAL_MEMORY_DMA_BUF: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_EXT_external_memory_dma_buf\0").unwrap();
+    static ref KHR_EXTERNAL_SEMAPHORE: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_external_semaphore\0").unwrap();
+    static ref KHR_EXTERNAL_SEMAPHORE_FD: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_KHR_external_semaphore_fd\0").unwrap();
+    static ref EXT_IMAGE_DRM_FORMAT_MODIFIER: &'static CStr =
+        CStr::from_bytes_with_nul(b"VK_EXT_image_drm_format_modifier\0").unwrap();
+    /// Device extensions required by `VK_EXT_image_drm_format_modifier`, besides
+    /// `VK_KHR_maintenance1` and the external memory ones.
+    static ref DRM_FORMAT_MODIFIER_DEPENDENCIES: Vec<&'static CStr> = vec![
+        CStr::from_bytes_with_nul(b"VK_KHR_bind_memory2\0").unwrap(),
+        CStr::from_bytes_with_nul(b"VK_KHR_get_memory_requirements2\0").unwrap(),
+        CStr::from_bytes_with_nul(b"VK_KHR_image_format_list\0").unwrap(),
+        CStr::from_bytes_with_nul(b"VK_KHR_sampler_ycbcr_conversion\0").unwrap(),
+    ];
 }
 
+/// Features exposed through `VK_EXT_descriptor_indexing`.
+const DESCRIPTOR_INDEXING_FEATURES: Features = Features::from_bits_truncate(
+    Features::SHADER_UNIFORM_BUFFER_ARRAY_NON_UNIFORM_INDEXING.bits()
+        | Features::SHADER_SAMPLED_IMAGE_ARRAY_NON_UNIFORM_INDEXING.bits()
+        | Features::SHADER_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING.bits()
+        | Features::SHADER_STORAGE_IMAGE_ARRAY_NON_UNIFORM_INDEXING.bits()
+        | Features::UNSIZED_DESCRIPTOR_ARRAY.bits()
+        | Features::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND.bits()
+        | Features::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING.bits()
+        | Features::DESCRIPTOR_BINDING_PARTIALLY_BOUND.bits()
+        | Features::DESCRIPTOR_BINDING_VARIABLE_COUNT.bits(),
+);
+
+/// Features exposed through `VK_EXT_conditional_rendering`.
+const CONDITIONAL_RENDERING_FEATURES: Features = Features::from_bits_truncate(
+    Features::CONDITIONAL_RENDERING.bits() | Features::CONDITIONAL_RENDERING_QUERY.bits(),
+);
+
+/// Features exposed through `VK_KHR_external_memory_fd` and its extensions.
+const EXTERNAL_MEMORY_FEATURES: Features = Features::from_bits_truncate(
+    Features::EXTERNAL_MEMORY_FD.bits()
+        | Features::EXTERNAL_MEMORY_DMA_BUF.bits()
+        | Features::IMAGE_DRM_FORMAT_MODIFIER.bits(),
+);
+
 #[cfg(not(feature = "use-rtld-next"))]
 lazy_static! {
     // Entry function pointers
@@ -150,6 +215,15 @@ pub struct Instance {
 
     /// Supported extensions of this instance.
     pub extensions: Vec<&'static CStr>,
+
+    /// Entry points of `VK_KHR_get_physical_device_properties2`, if supported.
+    get_physical_device_properties2_fn: Option<vk::KhrGetPhysicalDeviceProperties2Fn>,
+
+    /// Entry points of `VK_KHR_external_memory_capabilities`, if supported.
+    external_memory_capabilities_fn: Option<vk::KhrExternalMemoryCapabilitiesFn>,
+
+    /// Entry points of `VK_KHR_external_semaphore_capabilities`, if supported.
+    external_semaphore_capabilities_fn: Option<vk::KhrExternalSemaphoreCapabilitiesFn>,
 }
 
 impl fmt::Debug for Instance {
@@ -369,6 +443,11 @@ impl hal::Instance<Backend> for Instance {
         let extensions = SURFACE_EXTENSIONS
             .iter()
             .chain(EXTENSIONS.iter())
+            // Needed to query the features of device extensions
+            .chain(Some(&vk::KhrGetPhysicalDeviceProperties2Fn::name()))
+            // Needed to query the capabilities of external memory and semaphores
+            .chain(Some(&vk::KhrExternalMemoryCapabilitiesFn::name()))
+            .chain(Some(&vk::KhrExternalSemaphoreCapabilitiesFn::name()))
             .filter_map(|&ext| {
                 instance_extensions
                     .iter()
@@ -464,9 +543,42 @@ impl hal::Instance<Backend> for Instance {
         #[cfg(not(debug_assertions))]
         let debug_messenger = None;
 
+        let get_physical_device_properties2_fn =
+            if extensions.contains(&vk::KhrGetPhysicalDeviceProperties2Fn::name()) {
+                Some(vk::KhrGetPhysicalDeviceProperties2Fn::load(|name| unsafe {
+                    mem::transmute(entry.get_instance_proc_addr(instance.handle(), name.as_ptr()))
+                }))
+            } else {
+                None
+            };
+
+        let external_memory_capabilities_fn =
+            if extensions.contains(&vk::KhrExternalMemoryCapabilitiesFn::name()) {
+                Some(vk::KhrExternalMemoryCapabilitiesFn::load(|name| unsafe {
+                    mem::transmute(entry.get_instance_proc_addr(instance.handle(), name.as_ptr()))
+                }))
+            } else {
+                None
+            };
+
+        let external_semaphore_capabilities_fn = if extensions
+            .contains(&vk::KhrExternalSemaphoreCapabilitiesFn::name())
+        {
+            Some(vk::KhrExternalSemaphoreCapabilitiesFn::load(
+                |name| unsafe {
+                    mem::transmute(entry.get_instance_proc_addr(instance.handle(), name.as_ptr()))
+                },
+            ))
+        } else {
+            None
+        };
+
         Ok(Instance {
             raw: Arc::new(RawInstance(instance, debug_messenger)),
             extensions,
+            get_physical_device_properties2_fn,
+            external_memory_capabilities_fn,
+            external_semaphore_capabilities_fn,
         })
     }
 
@@ -507,11 +619,127 @@ impl hal::Instance<Backend> for Instance {
                         _ => adapter::DeviceType::Other,
                     },
                 };
+                let descriptor_indexing = self
+                    .get_physical_device_properties2_fn
+                    .as_ref()
+                    .filter(|_| {
+                        [*KHR_MAINTENANCE3, *EXT_DESCRIPTOR_INDEXING]
+                            .iter()
+                            .all(|&name| {
+                                extensions.iter().any(|ep| unsafe {
+                                    CStr::from_ptr(ep.extension_name.as_ptr()) == name
+                                })
+                            })
+                    })
+                    .map(|properties2_fn| unsafe {
+                        DescriptorIndexing::query(properties2_fn, device)
+                    });
+                let max_push_descriptors = self
+                    .get_physical_device_properties2_fn
+                    .as_ref()
+                    .filter(|_| {
+                        extensions.iter().any(|ep| unsafe {
+                            CStr::from_ptr(ep.extension_name.as_ptr()) == *KHR_PUSH_DESCRIPTOR
+                        })
+                    })
+                    .map(|properties2_fn| unsafe {
+                        let mut push_descriptor_properties =
+                            vk::PhysicalDevicePushDescriptorPropertiesKHR::default();
+                        let mut properties2 = vk::PhysicalDeviceProperties2KHR {
+                            p_next: &mut push_descriptor_properties as *mut _ as *mut _,
+                            ..Default::default()
+                        };
+                        properties2_fn
+                            .get_physical_device_properties2_khr(device, &mut properties2);
+                        push_descriptor_properties.max_push_descriptors
+                    });
+                let max_multiview_view_count = self
+                    .get_physical_device_properties2_fn
+                    .as_ref()
+                    .filter(|_| {
+                        extensions.iter().any(|ep| unsafe {
+                            CStr::from_ptr(ep.extension_name.as_ptr()) == *KHR_MULTIVIEW
+                        })
+                    })
+                    .and_then(|properties2_fn| unsafe {
+                        let mut multiview_features =
+                            vk::PhysicalDeviceMultiviewFeaturesKHR::default();
+                        let mut features2 = vk::PhysicalDeviceFeatures2KHR {
+                            p_next: &mut multiview_features as *mut _ as *mut _,
+                            ..Default::default()
+                        };
+                        properties2_fn.get_physical_device_features2_khr(device, &mut features2);
+                        let mut multiview_properties =
+                            vk::PhysicalDeviceMultiviewPropertiesKHR::default();
+                        let mut properties2 = vk::PhysicalDeviceProperties2KHR {
+                            p_next: &mut multiview_properties as *mut _ as *mut _,
+                            ..Default::default()
+                        };
+                        properties2_fn
+                            .get_physical_device_properties2_khr(device, &mut properties2);
+                        Some(multiview_properties.max_multiview_view_count)
+                            .filter(|_| multiview_features.multiview != 0)
+                    });
+                let conditional_rendering = self
+                    .get_physical_device_properties2_fn
+                    .as_ref()
+                    .filter(|_| {
+                        extensions.iter().any(|ep| unsafe {
+                            CStr::from_ptr(ep.extension_name.as_ptr()) == *EXT_CONDITIONAL_RENDERING
+                        })
+                    })
+                    .map_or(false, |properties2_fn| unsafe {
+                        let mut conditional_rendering_features =
+                            vk::PhysicalDeviceConditionalRenderingFeaturesEXT::default();
+                        let mut features2 = vk::PhysicalDeviceFeatures2KHR {
+                            p_next: &mut conditional_rendering_features as *mut _ as *mut _,
+                            ..Default::default()
+                        };
+                        properties2_fn.get_physical_device_features2_khr(device, &mut features2);
+                        conditional_rendering_features.conditional_rendering != 0
+                    });
+                let max_transform_feedback_buffers = self
+                    .get_physical_device_properties2_fn
+                    .as_ref()
+                    .filter(|_| {
+                        extensions.iter().any(|ep| unsafe {
+                            CStr::from_ptr(ep.extension_name.as_ptr()) == *EXT_TRANSFORM_FEEDBACK
+                        })
+                    })
+                    .and_then(|properties2_fn| unsafe {
+                        let mut transform_feedback_features =
+                            vk::PhysicalDeviceTransformFeedbackFeaturesEXT::default();
+                        let mut features2 = vk::PhysicalDeviceFeatures2KHR {
+                            p_next: &mut transform_feedback_features as *mut _ as *mut _,
+                            ..Default::default()
+                        };
+                        properties2_fn.get_physical_device_features2_khr(device, &mut features2);
+                        let mut transform_feedback_properties =
+                            vk::PhysicalDeviceTransformFeedbackPropertiesEXT::default();
+                        let mut properties2 = vk::PhysicalDeviceProperties2KHR {
+                            p_next: &mut transform_feedback_properties as *mut _ as *mut _,
+                            ..Default::default()
+                        };
+                        properties2_fn
+                            .get_physical_device_properties2_khr(device, &mut properties2);
+                        Some(transform_feedback_properties.max_transform_feedback_buffers)
+                            .filter(|_| transform_feedback_features.transform_feedback != 0)
+                    });
                 let physical_device = PhysicalDevice {
                     instance: self.raw.clone(),
                     handle: device,
                     extensions,
                     properties,
+                    descriptor_indexing,
+                    max_push_descriptors,
+                    max_multiview_view_count,
+                    conditional_rendering,
+                    max_transform_feedback_buffers,
+                    properties2_fn: self.get_physical_device_properties2_fn.clone(),
+                    external_memory_capabilities_fn: self.external_memory_capabilities_fn.clone(),
+                    external_semaphore_capabilities_fn: self
+                        .external_semaphore_capabilities_fn
+                        .clone(),
                 };
                 let queue_families = unsafe {
                     self.raw
@@ -620,6 +848,117 @@ impl queue::QueueFamily for QueueFamily {
     fn id(&self) -> queue::QueueFamilyId {
         queue::QueueFamilyId(self.index as _)
     }
+    fn timestamp_valid_bits(&self) -> u32 {
+        self.properties.timestamp_valid_bits
+    }
+}
+
+/// Features and limits of `VK_EXT_descriptor_indexing` supported by a physical device.
+#[derive(Debug, Clone, Copy)]
+struct DescriptorIndexing {
+    features: Features,
+    max_update_after_bind_descriptors_in_all_pools: u32,
+    max_per_stage_update_after_bind_resources: u32,
+}
+
+impl DescriptorIndexing {
+    unsafe fn query(
+        properties2_fn: &vk::KhrGetPhysicalDeviceProperties2Fn,
+        device: vk::PhysicalDevice,
+    ) -> Self {
+        let mut indexing_features = vk::PhysicalDeviceDescriptorIndexingFeaturesEXT::default();
+        let mut features2 = vk::PhysicalDeviceFeatures2KHR {
+            p_next: &mut indexing_features as *mut _ as *mut _,
+            ..Default::default()
+        };
+        properties2_fn.get_physical_device_features2_khr(device, &mut features2);
+
+        let mut indexing_properties = vk::PhysicalDeviceDescriptorIndexingPropertiesEXT::default();
+        let mut properties2 = vk::PhysicalDeviceProperties2KHR {
+            p_next: &mut indexing_properties as *mut _ as *mut _,
+            ..Default::default()
+        };
+        properties2_fn.get_physical_device_properties2_khr(device, &mut properties2);
+
+        let f = &indexing_features;
+        let mut features = Features::empty();
+        if f.shader_uniform_buffer_array_non_uniform_indexing != 0 {
+            features |= Features::SHADER_UNIFORM_BUFFER_ARRAY_NON_UNIFORM_INDEXING;
+        }
+        if f.shader_sampled_image_array_non_uniform_indexing != 0 {
+            features |= Features::SHADER_SAMPLED_IMAGE_ARRAY_NON_UNIFORM_INDEXING;
+        }
+        if f.shader_storage_buffer_array_non_uniform_indexing != 0 {
+            features |= Features::SHADER_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING;
+        }
+        if f.shader_storage_image_array_non_uniform_indexing != 0 {
+            features |= Features::SHADER_STORAGE_IMAGE_ARRAY_NON_UNIFORM_INDEXING;
+        }
+        if f.runtime_descriptor_array != 0 {
+            features |= Features::UNSIZED_DESCRIPTOR_ARRAY;
+        }
+        if f.descriptor_binding_sampled_image_update_after_bind != 0
+            && f.descriptor_binding_storage_image_update_after_bind != 0
+            && f.descriptor_binding_storage_buffer_update_after_bind != 0
+        {
+            features |= Features::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND;
+        }
+        if f.descriptor_binding_update_unused_while_pending != 0 {
+            features |= Features::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING;
+        }
+        if f.descriptor_binding_partially_bound != 0 {
+            features |= Features::DESCRIPTOR_BINDING_PARTIALLY_BOUND;
+        }
+        if f.descriptor_binding_variable_descriptor_count != 0 {
+            features |= Features::DESCRIPTOR_BINDING_VARIABLE_COUNT;
+        }
+
+        DescriptorIndexing {
+            features,
+            max_update_after_bind_descriptors_in_all_pools: indexing_properties
+                .max_update_after_bind_descriptors_in_all_pools,
+            max_per_stage_update_after_bind_resources: indexing_properties
+                .max_per_stage_update_after_bind_resources,
+        }
+    }
+
+    /// Returns the structure enabling the requested features at device creation.
+    fn enabled_features(requested: Features) -> vk::PhysicalDeviceDescriptorIndexingFeaturesEXT {
+        let flag = |features| {
+            if requested.contains(features) {
+                vk::TRUE
+            } else {
+                vk::FALSE
+            }
+        };
+        let update_after_bind = flag(Features::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND);
+        vk::PhysicalDeviceDescriptorIndexingFeaturesEXT {
+            shader_uniform_buffer_array_non_uniform_indexing: flag(
+                Features::SHADER_UNIFORM_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+            shader_sampled_image_array_non_uniform_indexing: flag(
+                Features::SHADER_SAMPLED_IMAGE_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+            shader_storage_buffer_array_non_uniform_indexing: flag(
+                Features::SHADER_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+            shader_storage_image_array_non_uniform_indexing: flag(
+                Features::SHADER_STORAGE_IMAGE_ARRAY_NON_UNIFORM_INDEXING,
+            ),
+            runtime_descriptor_array: flag(Features::UNSIZED_DESCRIPTOR_ARRAY),
+            descriptor_binding_sampled_image_update_after_bind: update_after_bind,
+            descriptor_binding_storage_image_update_after_bind: update_after_bind,
+            descriptor_binding_storage_buffer_update_after_bind: update_after_bind,
+            descriptor_binding_update_unused_while_pending: flag(
+                Features::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING,
+            ),
+            descriptor_binding_partially_bound: flag(Features::DESCRIPTOR_BINDING_PARTIALLY_BOUND),
+            descriptor_binding_variable_descriptor_count: flag(
+                Features::DESCRIPTOR_BINDING_VARIABLE_COUNT,
+            ),
+            ..Default::default()
+        }
+    }
 }
 
 pub struct PhysicalDevice {
@@ -627,6 +966,21 @@ pub struct PhysicalDevice {
     handle: vk::PhysicalDevice,
     extensions: Vec<vk::ExtensionProperties>,
     properties: vk::PhysicalDeviceProperties,
+    descriptor_indexing: Option<DescriptorIndexing>,
+    /// Limit of `VK_KHR_push_descriptor`, if supported.
+    max_push_descriptors: Option<u32>,
+    /// Limit of `VK_KHR_multiview`, if multiview rendering is supported.
+    max_multiview_view_count: Option<u32>,
+    /// Whether `VK_EXT_conditional_rendering` is supported.
+    conditional_rendering: bool,
+    /// Limit of `VK_EXT_transform_feedback`, if transform feedback is supported.
+    max_transform_feedback_buffers: Option<u32>,
+    /// Entry points of `VK_KHR_get_physical_device_properties2`, if supported.
+    properties2_fn: Option<vk::KhrGetPhysicalDeviceProperties2Fn>,
+    /// Entry points of `VK_KHR_external_memory_capabilities`, if supported.
+    external_memory_capabilities_fn: Option<vk::KhrExternalMemoryCapabilitiesFn>,
+    /// Entry points of `VK_KHR_external_semaphore_capabilities`, if supported.
+    external_semaphore_capabilities_fn: Option<vk::KhrExternalSemaphoreCapabilitiesFn>,
 }
 
 impl PhysicalDevice {
@@ -635,6 +989,28 @@ impl PhysicalDevice {
             .iter()
             .any(|ep| unsafe { CStr::from_ptr(ep.extension_name.as_ptr()) } == extension)
     }
+
+    fn supports_memory_handle_type(&self, handle_type: external::MemoryHandleType) -> bool {
+        self.external_memory_capabilities_fn.is_some()
+            && self.supports_extension(*KHR_EXTERNAL_MEMORY)
+            && self.supports_extension(*KHR_EXTERNAL_MEMORY_FD)
+            && match handle_type {
+                external::MemoryHandleType::OpaqueFd => true,
+                external::MemoryHandleType::DmaBuf => {
+                    self.supports_extension(*EXT_EXTERNAL_MEMORY_DMA_BUF)
+                }
+            }
+    }
+
+    fn supports_drm_format_modifier(&self) -> bool {
+        self.properties2_fn.is_some()
+            && self.supports_memory_handle_type(external::MemoryHandleType::DmaBuf)
+            && self.supports_extension(*KHR_MAINTENANCE1)
+            && DRM_FORMAT_MODIFIER_DEPENDENCIES
+                .iter()
+                .all(|&name| self.supports_extension(name))
+            && self.supports_extension(*EXT_IMAGE_DRM_FORMAT_MODIFIER)
+    }
 }
 
 impl fmt::Debug for PhysicalDevice {
@@ -683,8 +1059,134 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
                     1 => Some(*KHR_MAINTENANCE1),
                     _ => unreachable!(),
                 }
+            )
+            .chain(
+                if requested_features.contains(Features::TIMELINE_SEMAPHORE) {
+                    Some(*KHR_TIMELINE_SEMAPHORE)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.intersects(DESCRIPTOR_INDEXING_FEATURES) {
+                    vec![*KHR_MAINTENANCE3, *EXT_DESCRIPTOR_INDEXING]
+                } else {
+                    vec![]
+                },
+            )
+            .chain(
+                if requested_features.contains(Features::PUSH_DESCRIPTOR) {
+                    Some(*KHR_PUSH_DESCRIPTOR)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.contains(Features::MULTIVIEW) {
+                    Some(*KHR_MULTIVIEW)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.contains(Features::DRAW_INDIRECT_COUNT) {
+                    Some(*KHR_DRAW_INDIRECT_COUNT)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.intersects(CONDITIONAL_RENDERING_FEATURES) {
+                    Some(*EXT_CONDITIONAL_RENDERING)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.contains(Features::TRANSFORM_FEEDBACK) {
+                    Some(*EXT_TRANSFORM_FEEDBACK)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.intersects(EXTERNAL_MEMORY_FEATURES) {
+                    vec![*KHR_EXTERNAL_MEMORY, *KHR_EXTERNAL_MEMORY_FD]
+                } else {
+                    vec![]
+                },
+            )
+            .chain(
+                if requested_features.intersects(
+                    Features::EXTERNAL_MEMORY_DMA_BUF | Features::IMAGE_DRM_FORMAT_MODIFIER,
+                ) {
+                    Some(*EXT_EXTERNAL_MEMORY_DMA_BUF)
+                } else {
+                    None
+                },
+            )
+            .chain(
+                if requested_features.contains(Features::IMAGE_DRM_FORMAT_MODIFIER) {
+                    DRM_FORMAT_MODIFIER_DEPENDENCIES
+                        .iter()
+                        .cloned()
+                        .chain(Some(*EXT_IMAGE_DRM_FORMAT_MODIFIER))
+                        .collect()
+                } else {
+                    vec![]
+                },
+            )
+            .chain(
+                if requested_features.contains(Features::EXTERNAL_SEMAPHORE_FD) {
+                    vec![*KHR_EXTERNAL_SEMAPHORE, *KHR_EXTERNAL_SEMAPHORE_FD]
+                } else {
+                    vec![]
+                },
             );
 
+        // Chain the feature structures of the enabled extensions
+        let mut p_next: *mut std::os::raw::c_void = ptr::null_mut();
+        let mut timeline_semaphore_features = vk::PhysicalDeviceTimelineSemaphoreFeaturesKHR {
+            s_type: vk::StructureType::PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_FEATURES_KHR,
+            p_next: ptr::null_mut(),
+            timeline_semaphore: vk::TRUE,
+        };
+        if requested_features.contains(Features::TIMELINE_SEMAPHORE) {
+            timeline_semaphore_features.p_next = p_next;
+            p_next = &mut timeline_semaphore_features as *mut _ as *mut _;
+        }
+        let mut descriptor_indexing_features =
+            DescriptorIndexing::enabled_features(requested_features);
+        if requested_features.intersects(DESCRIPTOR_INDEXING_FEATURES) {
+            descriptor_indexing_features.p_next = p_next;
+            p_next = &mut descriptor_indexing_features as *mut _ as *mut _;
+        }
+        let mut multiview_features = vk::PhysicalDeviceMultiviewFeaturesKHR {
+            multiview: vk::TRUE,
+            ..Default::default()
+        };
+        if requested_features.contains(Features::MULTIVIEW) {
+            multiview_features.p_next = p_next;
+            p_next = &mut multiview_features as *mut _ as *mut _;
+        }
+        let mut conditional_rendering_features =
+            vk::PhysicalDeviceConditionalRenderingFeaturesEXT {
+                conditional_rendering: vk::TRUE,
+                ..Default::default()
+            };
+        if requested_features.intersects(CONDITIONAL_RENDERING_FEATURES) {
+            conditional_rendering_features.p_next = p_next;
+            p_next = &mut conditional_rendering_features as *mut _ as *mut _;
+        }
+        let mut transform_feedback_features = vk::PhysicalDeviceTransformFeedbackFeaturesEXT {
+            transform_feedback: vk::TRUE,
+            ..Default::default()
+        };
+        if requested_features.contains(Features::TRANSFORM_FEEDBACK) {
+            transform_feedback_features.p_next = p_next;
+            p_next = &mut transform_feedback_features as *mut _ as *mut _;
+        }
+
         // Create device
         let device_raw = {
             let cstrings = enabled_extensions.map(CString::from).collect::<Vec<_>>();
@@ -693,7 +1195,7 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
 
             let info = vk::DeviceCreateInfo {
                 s_type: vk::StructureType::DEVICE_CREATE_INFO,
-                p_next: ptr::null(),
+                p_next: p_next as *const _,
                 flags: vk::DeviceCreateFlags::empty(),
                 queue_create_info_count: family_infos.len() as u32,
                 p_queue_create_infos: family_infos.as_ptr(),
@@ -733,12 +1235,105 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
             )
         });
 
+        let timeline_semaphore_fn = if requested_features.contains(Features::TIMELINE_SEMAPHORE) {
+            Some(vk::KhrTimelineSemaphoreFn::load(|name| {
+                mem::transmute(
+                    self.instance
+                        .0
+                        .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                )
+            }))
+        } else {
+            None
+        };
+
+        let push_descriptor_fn = if requested_features.contains(Features::PUSH_DESCRIPTOR) {
+            Some(vk::KhrPushDescriptorFn::load(|name| {
+                mem::transmute(
+                    self.instance
+                        .0
+                        .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                )
+            }))
+        } else {
+            None
+        };
+
+        let draw_indirect_count_fn = if requested_features.contains(Features::DRAW_INDIRECT_COUNT) {
+            Some(vk::KhrDrawIndirectCountFn::load(|name| {
+                mem::transmute(
+                    self.instance
+                        .0
+                        .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                )
+            }))
+        } else {
+            None
+        };
+
+        let conditional_rendering_fn =
+            if requested_features.intersects(CONDITIONAL_RENDERING_FEATURES) {
+                Some(vk::ExtConditionalRenderingFn::load(|name| {
+                    mem::transmute(
+                        self.instance
+                            .0
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
+        let transform_feedback_fn = if requested_features.contains(Features::TRANSFORM_FEEDBACK) {
+            Some(vk::ExtTransformFeedbackFn::load(|name| {
+                mem::transmute(
+                    self.instance
+                        .0
+                        .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                )
+            }))
+        } else {
+            None
+        };
+
+        let external_memory_fd_fn = if requested_features.intersects(EXTERNAL_MEMORY_FEATURES) {
+            Some(vk::KhrExternalMemoryFdFn::load(|name| {
+                mem::transmute(
+                    self.instance
+                        .0
+                        .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                )
+            }))
+        } else {
+            None
+        };
+
+        let external_semaphore_fd_fn =
+            if requested_features.contains(Features::EXTERNAL_SEMAPHORE_FD) {
+                Some(vk::KhrExternalSemaphoreFdFn::load(|name| {
+                    mem::transmute(
+                        self.instance
+                            .0
+                            .get_device_proc_addr(device_raw.handle(), name.as_ptr()),
+                    )
+                }))
+            } else {
+                None
+            };
+
         let device = Device {
             shared: Arc::new(RawDevice {
                 raw: device_raw,
                 features: requested_features,
                 instance: Arc::clone(&self.instance),
                 maintenance_level,
+                timeline_semaphore_fn,
+                push_descriptor_fn,
+                draw_indirect_count_fn,
+                conditional_rendering_fn,
+                transform_feedback_fn,
+                external_memory_fd_fn,
+                external_semaphore_fd_fn,
             }),
             vendor_id: self.properties.vendor_id,
         };
@@ -911,6 +1506,42 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
         if self.supports_extension(*KHR_SAMPLER_MIRROR_MIRROR_CLAMP_TO_EDGE) {
             bits |= Features::SAMPLER_MIRROR_CLAMP_EDGE;
         }
+        if self.supports_extension(*KHR_TIMELINE_SEMAPHORE) {
+            bits |= Features::TIMELINE_SEMAPHORE;
+        }
+        if let Some(ref descriptor_indexing) = self.descriptor_indexing {
+            bits |= descriptor_indexing.features;
+        }
+        if self.max_push_descriptors.is_some() {
+            bits |= Features::PUSH_DESCRIPTOR;
+        }
+        if self.max_multiview_view_count.is_some() {
+            bits |= Features::MULTIVIEW;
+        }
+        if self.supports_extension(*KHR_DRAW_INDIRECT_COUNT) {
+            bits |= Features::DRAW_INDIRECT_COUNT;
+        }
+        if self.conditional_rendering {
+            bits |= CONDITIONAL_RENDERING_FEATURES;
+        }
+        if self.max_transform_feedback_buffers.is_some() {
+            bits |= Features::TRANSFORM_FEEDBACK;
+        }
+        if self.supports_memory_handle_type(external::MemoryHandleType::OpaqueFd) {
+            bits |= Features::EXTERNAL_MEMORY_FD;
+        }
+        if self.supports_memory_handle_type(external::MemoryHandleType::DmaBuf) {
+            bits |= Features::EXTERNAL_MEMORY_DMA_BUF;
+        }
+        if self.supports_drm_format_modifier() {
+            bits |= Features::IMAGE_DRM_FORMAT_MODIFIER;
+        }
+        if self.external_semaphore_capabilities_fn.is_some()
+            && self.supports_extension(*KHR_EXTERNAL_SEMAPHORE)
+            && self.supports_extension(*KHR_EXTERNAL_SEMAPHORE_FD)
+        {
+            bits |= Features::EXTERNAL_SEMAPHORE_FD;
+        }
 
         if features.robust_buffer_access != 0 {
             bits |= Features::ROBUST_BUFFER_ACCESS;
@@ -1135,6 +1766,7 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
             non_coherent_atom_size: limits.non_coherent_atom_size as _,
             max_sampler_anisotropy: limits.max_sampler_anisotropy,
             min_vertex_input_binding_stride_alignment: 1,
+            timestamp_period: limits.timestamp_period,
             max_bound_descriptor_sets: limits.max_bound_descriptor_sets as _,
             max_compute_shared_memory_size: limits.max_compute_shared_memory_size as _,
             max_compute_work_group_invocations: limits.max_compute_work_group_invocations as _,
@@ -1186,6 +1818,17 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
             max_uniform_buffer_range: limits.max_uniform_buffer_range as _,
             min_memory_map_alignment: limits.min_memory_map_alignment,
             standard_sample_locations: limits.standard_sample_locations == ash::vk::TRUE,
+            max_update_after_bind_descriptors_in_all_pools: self
+                .descriptor_indexing
+                .map_or(0, |di| {
+                    di.max_update_after_bind_descriptors_in_all_pools as _
+                }),
+            max_per_stage_update_after_bind_resources: self
+                .descriptor_indexing
+                .map_or(0, |di| di.max_per_stage_update_after_bind_resources as _),
+            max_push_descriptors: self.max_push_descriptors.unwrap_or(0) as _,
+            max_multiview_view_count: self.max_multiview_view_count.unwrap_or(0) as _,
+            max_transform_feedback_buffers: self.max_transform_feedback_buffers.unwrap_or(0) as _,
         }
     }
 
@@ -1242,6 +1885,172 @@ impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
         }
         true
     }
+
+    fn external_buffer_properties(
+        &self,
+        usage: buffer::Usage,
+        handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        let capabilities_fn = match self.external_memory_capabilities_fn {
+            Some(ref capabilities_fn) if self.supports_memory_handle_type(handle_type) => {
+                capabilities_fn
+            }
+            _ => return external::ExternalMemoryProperties::default(),
+        };
+        let info = vk::PhysicalDeviceExternalBufferInfo {
+            s_type: vk::StructureType::PHYSICAL_DEVICE_EXTERNAL_BUFFER_INFO,
+            p_next: ptr::null(),
+            flags: vk::BufferCreateFlags::empty(),
+            usage: conv::map_buffer_usage(usage),
+            handle_type: conv::map_memory_handle_type(handle_type),
+        };
+        let mut properties = vk::ExternalBufferProperties::default();
+        unsafe {
+            capabilities_fn.get_physical_device_external_buffer_properties_khr(
+                self.handle,
+                &info,
+                &mut properties,
+            );
+        }
+        conv::map_vk_external_memory_properties(&properties.external_memory_properties)
+    }
+
+    fn external_image_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        let properties2_fn = match self.properties2_fn {
+            Some(ref properties2_fn) if self.supports_memory_handle_type(handle_type) => {
+                properties2_fn
+            }
+            _ => return None,
+        };
+        let external_info = vk::PhysicalDeviceExternalImageFormatInfo {
+            s_type: vk::StructureType::PHYSICAL_DEVICE_EXTERNAL_IMAGE_FORMAT_INFO,
+            p_next: ptr::null(),
+            handle_type: conv::map_memory_handle_type(handle_type),
+        };
+        let info = vk::PhysicalDeviceImageFormatInfo2 {
+            s_type: vk::StructureType::PHYSICAL_DEVICE_IMAGE_FORMAT_INFO_2,
+            p_next: &external_info as *const _ as *const _,
+            format: conv::map_format(format),
+            ty: match dimensions {
+                1 => vk::ImageType::TYPE_1D,
+                2 => vk::ImageType::TYPE_2D,
+                3 => vk::ImageType::TYPE_3D,
+                _ => panic!("Unexpected image dimensionality: {}", dimensions),
+            },
+            tiling: conv::map_tiling(tiling),
+            usage: conv::map_image_usage(usage),
+            flags: conv::map_view_capabilities(view_caps),
+        };
+        let mut external_properties = vk::ExternalImageFormatProperties::default();
+        let mut properties2 = vk::ImageFormatProperties2 {
+            p_next: &mut external_properties as *mut _ as *mut _,
+            ..Default::default()
+        };
+        let result = unsafe {
+            properties2_fn.get_physical_device_image_format_properties2_khr(
+                self.handle,
+                &info,
+                &mut properties2,
+            )
+        };
+
+        match result {
+            vk::Result::SUCCESS => Some(conv::map_vk_external_memory_properties(
+                &external_properties.external_memory_properties,
+            )),
+            vk::Result::ERROR_FORMAT_NOT_SUPPORTED => None,
+            other => {
+                error!(
+                    "Unexpected error in `external_image_properties`: {:?}",
+                    other
+                );
+                None
+            }
+        }
+    }
+
+    fn external_semaphore_properties(
+        &self,
+        handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        if !self.features().contains(Features::EXTERNAL_SEMAPHORE_FD) {
+            return external::ExternalSemaphoreProperties::default();
+        }
+        let info = vk::PhysicalDeviceExternalSemaphoreInfo {
+            s_type: vk::StructureType::PHYSICAL_DEVICE_EXTERNAL_SEMAPHORE_INFO,
+            p_next: ptr::null(),
+            handle_type: conv::map_semaphore_handle_type(handle_type),
+        };
+        let mut properties = vk::ExternalSemaphoreProperties::default();
+        unsafe {
+            self.external_semaphore_capabilities_fn
+                .as_ref()
+                .unwrap()
+                .get_physical_device_external_semaphore_properties_khr(
+                    self.handle,
+                    &info,
+                    &mut properties,
+                );
+        }
+        conv::map_vk_external_semaphore_properties(&properties)
+    }
+
+    fn drm_format_modifier_properties(
+        &self,
+        format: format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        let properties2_fn = match self.properties2_fn {
+            Some(ref properties2_fn) if self.supports_drm_format_modifier() => properties2_fn,
+            _ => return Vec::new(),
+        };
+        let format = conv::map_format(format);
+
+        // Query the modifier count first, then fill the list
+        let mut modifier_list = vk::DrmFormatModifierPropertiesListEXT::default();
+        let mut properties2 = vk::FormatProperties2 {
+            p_next: &mut modifier_list as *mut _ as *mut _,
+            ..Default::default()
+        };
+        unsafe {
+            properties2_fn.get_physical_device_format_properties2_khr(
+                self.handle,
+                format,
+                &mut properties2,
+            );
+        }
+        let mut modifiers = vec![
+            vk::DrmFormatModifierPropertiesEXT::default();
+            modifier_list.drm_format_modifier_count as usize
+        ];
+        modifier_list.p_drm_format_modifier_properties = modifiers.as_mut_ptr();
+        properties2.p_next = &mut modifier_list as *mut _ as *mut _;
+        unsafe {
+            properties2_fn.get_physical_device_format_properties2_khr(
+                self.handle,
+                format,
+                &mut properties2,
+            );
+        }
+
+        modifiers[.. modifier_list.drm_format_modifier_count as usize]
+            .iter()
+            .map(|modifier| external::DrmFormatModifierProperties {
+                modifier: modifier.drm_format_modifier,
+                plane_count: modifier.drm_format_modifier_plane_count,
+                tiling_features: conv::map_image_features(
+                    modifier.drm_format_modifier_tiling_features,
+                ),
+            })
+            .collect()
+    }
 }
 
 #[doc(hidden)]
@@ -1250,6 +2059,20 @@ pub struct RawDevice {
     features: Features,
     instance: Arc<RawInstance>,
     maintenance_level: u8,
+    /// Entry points of `VK_KHR_timeline_semaphore`, if the feature is enabled.
+    timeline_semaphore_fn: Option<vk::KhrTimelineSemaphoreFn>,
+    /// Entry points of `VK_KHR_push_descriptor`, if the feature is enabled.
+    push_descriptor_fn: Option<vk::KhrPushDescriptorFn>,
+    /// Entry points of `VK_KHR_draw_indirect_count`, if the feature is enabled.
+    draw_indirect_count_fn: Option<vk::KhrDrawIndirectCountFn>,
+    /// Entry points of `VK_EXT_conditional_rendering`, if the feature is enabled.
+    conditional_rendering_fn: Option<vk::ExtConditionalRenderingFn>,
+    /// Entry points of `VK_EXT_transform_feedback`, if the feature is enabled.
+    transform_feedback_fn: Option<vk::ExtTransformFeedbackFn>,
+    /// Entry points of `VK_KHR_external_memory_fd`, if an external memory feature is enabled.
+    external_memory_fd_fn: Option<vk::KhrExternalMemoryFdFn>,
+    /// Entry points of `VK_KHR_external_semaphore_fd`, if the feature is enabled.
+    external_semaphore_fd_fn: Option<vk::KhrExternalSemaphoreFdFn>,
 }
 
 impl fmt::Debug for RawDevice {
@@ -1301,31 +2124,48 @@ impl queue::CommandQueue<Backend> for CommandQueue {
         T: 'a + Borrow<command::CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
         S: 'a + Borrow<native::Semaphore>,
-        Iw: IntoIterator<Item = (&'a S, PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue, PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
     {
         //TODO: avoid heap allocations
         let mut waits = Vec::new();
+        let mut wait_values = Vec::new();
         let mut stages = Vec::new();
+        let mut signals = Vec::new();
+        let mut signal_values = Vec::new();
 
         let buffers = submission
             .command_buffers
             .into_iter()
             .map(|cmd| cmd.borrow().raw)
             .collect::<Vec<_>>();
-        for (semaphore, stage) in submission.wait_semaphores {
+        for (semaphore, value, stage) in submission.wait_semaphores {
             waits.push(semaphore.borrow().0);
+            wait_values.push(value);
             stages.push(conv::map_pipeline_stage(stage));
         }
-        let signals = submission
-            .signal_semaphores
-            .into_iter()
-            .map(|semaphore| semaphore.borrow().0)
-            .collect::<Vec<_>>();
+        for (semaphore, value) in submission.signal_semaphores {
+            signals.push(semaphore.borrow().0);
+            signal_values.push(value);
+        }
+
+        // The values of binary semaphores are ignored by the driver.
+        let timeline_info = vk::TimelineSemaphoreSubmitInfoKHR {
+            s_type: vk::StructureType::TIMELINE_SEMAPHORE_SUBMIT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_value_count: wait_values.len() as u32,
+            p_wait_semaphore_values: wait_values.as_ptr(),
+            signal_semaphore_value_count: signal_values.len() as u32,
+            p_signal_semaphore_values: signal_values.as_ptr(),
+        };
 
         let info = vk::SubmitInfo {
             s_type: vk::StructureType::SUBMIT_INFO,
-            p_next: ptr::null(),
+            p_next: if self.device.timeline_semaphore_fn.is_some() {
+                &timeline_info as *const _ as *const _
+            } else {
+                ptr::null()
+            },
             wait_semaphore_count: waits.len() as u32,
             p_wait_semaphores: waits.as_ptr(),
             // If count is zero, AMD driver crashes if nullptr is not set for stage masks
@@ -1346,6 +2186,140 @@ impl queue::CommandQueue<Backend> for CommandQueue {
         assert_eq!(Ok(()), result);
     }
 
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: queue::SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&native::Fence>,
+    ) where
+        S: 'a + Borrow<native::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, queue::SemaphoreValue)>,
+        Ib: IntoIterator<
+            Item = (
+                &'b native::Buffer,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Io: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseBind<&'b native::Memory>],
+            ),
+        >,
+        Ii: IntoIterator<
+            Item = (
+                &'b native::Image,
+                &'b [memory::SparseImageBind<&'b native::Memory>],
+            ),
+        >,
+    {
+        //TODO: avoid heap allocations
+        let mut waits = Vec::new();
+        let mut wait_values = Vec::new();
+        let mut signals = Vec::new();
+        let mut signal_values = Vec::new();
+
+        for (semaphore, value) in binding.wait_semaphores {
+            waits.push(semaphore.borrow().0);
+            wait_values.push(value);
+        }
+        for (semaphore, value) in binding.signal_semaphores {
+            signals.push(semaphore.borrow().0);
+            signal_values.push(value);
+        }
+
+        // The binds of each resource are collected first, so that they outlive the call.
+        let buffer_binds = binding
+            .buffer_binds
+            .into_iter()
+            .map(|(buffer, binds)| {
+                let binds = binds.iter().map(conv::map_sparse_bind).collect::<Vec<_>>();
+                (buffer.raw, binds)
+            })
+            .collect::<Vec<_>>();
+        let image_opaque_binds = binding
+            .image_opaque_binds
+            .into_iter()
+            .map(|(image, binds)| {
+                let binds = binds.iter().map(conv::map_sparse_bind).collect::<Vec<_>>();
+                (image.raw, binds)
+            })
+            .collect::<Vec<_>>();
+        let image_binds = binding
+            .image_binds
+            .into_iter()
+            .map(|(image, binds)| {
+                let binds = binds
+                    .iter()
+                    .map(conv::map_sparse_image_bind)
+                    .collect::<Vec<_>>();
+                (image.raw, binds)
+            })
+            .collect::<Vec<_>>();
+
+        let buffer_infos = buffer_binds
+            .iter()
+            .map(|(buffer, binds)| vk::SparseBufferMemoryBindInfo {
+                buffer: *buffer,
+                bind_count: binds.len() as u32,
+                p_binds: binds.as_ptr(),
+            })
+            .collect::<Vec<_>>();
+        let image_opaque_infos = image_opaque_binds
+            .iter()
+            .map(|(image, binds)| vk::SparseImageOpaqueMemoryBindInfo {
+                image: *image,
+                bind_count: binds.len() as u32,
+                p_binds: binds.as_ptr(),
+            })
+            .collect::<Vec<_>>();
+        let image_infos = image_binds
+            .iter()
+            .map(|(image, binds)| vk::SparseImageMemoryBindInfo {
+                image: *image,
+                bind_count: binds.len() as u32,
+                p_binds: binds.as_ptr(),
+            })
+            .collect::<Vec<_>>();
+
+        // The values of binary semaphores are ignored by the driver.
+        let timeline_info = vk::TimelineSemaphoreSubmitInfoKHR {
+            s_type: vk::StructureType::TIMELINE_SEMAPHORE_SUBMIT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_value_count: wait_values.len() as u32,
+            p_wait_semaphore_values: wait_values.as_ptr(),
+            signal_semaphore_value_count: signal_values.len() as u32,
+            p_signal_semaphore_values: signal_values.as_ptr(),
+        };
+
+        let info = vk::BindSparseInfo {
+            s_type: vk::StructureType::BIND_SPARSE_INFO,
+            p_next: if self.device.timeline_semaphore_fn.is_some() {
+                &timeline_info as *const _ as *const _
+            } else {
+                ptr::null()
+            },
+            wait_semaphore_count: waits.len() as u32,
+            p_wait_semaphores: waits.as_ptr(),
+            buffer_bind_count: buffer_infos.len() as u32,
+            p_buffer_binds: buffer_infos.as_ptr(),
+            image_opaque_bind_count: image_opaque_infos.len() as u32,
+            p_image_opaque_binds: image_opaque_infos.as_ptr(),
+            image_bind_count: image_infos.len() as u32,
+            p_image_binds: image_infos.as_ptr(),
+            signal_semaphore_count: signals.len() as u32,
+            p_signal_semaphores: signals.as_ptr(),
+        };
+
+        let fence_raw = fence.map(|fence| fence.0).unwrap_or(vk::Fence::null());
+
+        let result = self
+            .device
+            .raw
+            .queue_bind_sparse(*self.raw, &[info], fence_raw);
+        assert_eq!(Ok(()), result);
+    }
+
     unsafe fn present<'a, W, Is, S, Iw>(
         &mut self,
         swapchains: Is,
@@ -1,7 +1,11 @@
 use crate::{window::FramebufferCachePtr, Backend, RawDevice};
 use ash::{version::DeviceV1_0, vk};
 use hal::{device::OutOfMemory, image::SubresourceRange, pso};
-use std::{borrow::Borrow, sync::Arc};
+use std::{
+    borrow::Borrow,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 #[derive(Debug, Hash)]
 pub struct Semaphore(pub vk::Semaphore);
@@ -85,9 +89,17 @@ pub struct DescriptorSet {
     pub(crate) bindings: Arc<Vec<pso::DescriptorSetLayoutBinding>>,
 }
 
-#[derive(Debug, Hash)]
+#[derive(Debug)]
 pub struct PipelineLayout {
     pub(crate) raw: vk::PipelineLayout,
+    /// Bindings of the set layouts, needed to push descriptors.
+    pub(crate) set_bindings: Vec<Arc<Vec<pso::DescriptorSetLayoutBinding>>>,
+}
+
+impl Hash for PipelineLayout {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
 }
 
 #[derive(Debug)]
@@ -146,15 +158,39 @@ impl pso::DescriptorPool<Backend> for DescriptorPool {
                         .map(|(raw, bindings)| DescriptorSet { raw, bindings }),
                 )
             })
-            .map_err(|err| match err {
-                vk::Result::ERROR_OUT_OF_HOST_MEMORY =>
-                    pso::AllocationError::OutOfMemory(OutOfMemory::Host),
-                vk::Result::ERROR_OUT_OF_DEVICE_MEMORY =>
-                    pso::AllocationError::OutOfMemory(OutOfMemory::Device),
-                vk::Result::ERROR_OUT_OF_POOL_MEMORY =>
-                    pso::AllocationError::OutOfPoolMemory,
-                _ => pso::AllocationError::FragmentedPool,
+            .map_err(map_allocation_error)
+    }
+
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &DescriptorSetLayout,
+        count: pso::DescriptorArrayIndex,
+    ) -> Result<DescriptorSet, pso::AllocationError> {
+        use std::ptr;
+
+        let count = count as u32;
+        let count_info = vk::DescriptorSetVariableDescriptorCountAllocateInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_SET_VARIABLE_DESCRIPTOR_COUNT_ALLOCATE_INFO_EXT,
+            p_next: ptr::null(),
+            descriptor_set_count: 1,
+            p_descriptor_counts: &count,
+        };
+        let info = vk::DescriptorSetAllocateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
+            p_next: &count_info as *const _ as *const _,
+            descriptor_pool: self.raw,
+            descriptor_set_count: 1,
+            p_set_layouts: &layout.raw,
+        };
+
+        self.device
+            .raw
+            .allocate_descriptor_sets(&info)
+            .map(|sets| DescriptorSet {
+                raw: sets[0],
+                bindings: layout.bindings.clone(),
             })
+            .map_err(map_allocation_error)
     }
 
     unsafe fn free<I>(&mut self, descriptor_sets: I)
@@ -179,5 +215,23 @@ impl pso::DescriptorPool<Backend> for DescriptorPool {
     }
 }
 
+fn map_allocation_error(err: vk::Result) -> pso::AllocationError {
+    match err {
+        vk::Result::ERROR_OUT_OF_HOST_MEMORY => {
+            pso::AllocationError::OutOfMemory(OutOfMemory::Host)
+        }
+        vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => {
+            pso::AllocationError::OutOfMemory(OutOfMemory::Device)
+        }
+        vk::Result::ERROR_OUT_OF_POOL_MEMORY => pso::AllocationError::OutOfPoolMemory,
+        _ => pso::AllocationError::FragmentedPool,
+    }
+}
+
 #[derive(Debug, Hash)]
-pub struct QueryPool(pub vk::QueryPool);
+pub struct QueryPool {
+    pub raw: vk::QueryPool,
+    /// Buffer and memory the results of occlusion queries are copied into, to be read
+    /// by `begin_conditional_rendering_query`.
+    pub(crate) predicates: Option<(vk::Buffer, vk::DeviceMemory)>,
+}
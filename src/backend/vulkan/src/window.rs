@@ -2,13 +2,18 @@ use std::{
     borrow::Borrow,
     fmt,
     hash,
+    mem,
     os::raw::c_void,
     ptr,
     sync::{Arc, Mutex},
     time::Instant,
 };
 
-use ash::{extensions::khr, version::DeviceV1_0 as _, vk};
+use ash::{
+    extensions::khr,
+    version::{DeviceV1_0 as _, EntryV1_0 as _},
+    vk,
+};
 use hal::{format::Format, window as w};
 use smallvec::SmallVec;
 
@@ -304,6 +309,47 @@ impl Instance {
         self.create_surface_from_vk_surface_khr(surface)
     }
 
+    /// Create a surface which isn't backed by any window, using `VK_EXT_headless_surface`.
+    ///
+    /// Presenting to it completes without displaying anything, which allows running
+    /// the whole acquire and present loop without a display server.
+    pub fn create_surface_headless(&self) -> Surface {
+        let entry = VK_ENTRY
+            .as_ref()
+            .expect("Unable to load Vulkan entry points");
+
+        if !self.extensions.contains(&vk::ExtHeadlessSurfaceFn::name()) {
+            panic!("Vulkan driver does not support VK_EXT_HEADLESS_SURFACE");
+        }
+
+        let surface = {
+            let headless_fn = vk::ExtHeadlessSurfaceFn::load(|name| unsafe {
+                mem::transmute(entry.get_instance_proc_addr(self.raw.0.handle(), name.as_ptr()))
+            });
+            let info = vk::HeadlessSurfaceCreateInfoEXT {
+                s_type: vk::StructureType::HEADLESS_SURFACE_CREATE_INFO_EXT,
+                p_next: ptr::null(),
+                flags: vk::HeadlessSurfaceCreateFlagsEXT::empty(),
+            };
+            let mut surface = vk::SurfaceKHR::null();
+
+            let result = unsafe {
+                headless_fn.create_headless_surface_ext(
+                    self.raw.0.handle(),
+                    &info,
+                    ptr::null(),
+                    &mut surface,
+                )
+            };
+            if result != vk::Result::SUCCESS {
+                panic!("Unable to create headless surface: {:?}", result);
+            }
+            surface
+        };
+
+        self.create_surface_from_vk_surface_khr(surface)
+    }
+
     pub fn create_surface_from_vk_surface_khr(&self, surface: vk::SurfaceKHR) -> Surface {
         let entry = VK_ENTRY
             .as_ref()
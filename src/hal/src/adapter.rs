@@ -9,7 +9,9 @@
 use std::{any::Any, fmt};
 
 use crate::{
+    buffer,
     device,
+    external,
     format,
     image,
     memory,
@@ -114,6 +116,47 @@ pub trait PhysicalDevice<B: Backend>: fmt::Debug + Any + Send + Sync {
     fn is_valid_cache(&self, _cache: &[u8]) -> bool {
         false
     }
+
+    /// Fetch the capabilities of an external memory handle type for buffers with the given usage.
+    fn external_buffer_properties(
+        &self,
+        _usage: buffer::Usage,
+        _handle_type: external::MemoryHandleType,
+    ) -> external::ExternalMemoryProperties {
+        external::ExternalMemoryProperties::default()
+    }
+
+    /// Fetch the capabilities of an external memory handle type for images
+    /// with the given description.
+    ///
+    /// Returns `None` if such images are not supported at all.
+    fn external_image_properties(
+        &self,
+        _format: format::Format,
+        _dimensions: u8,
+        _tiling: image::Tiling,
+        _usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+        _handle_type: external::MemoryHandleType,
+    ) -> Option<external::ExternalMemoryProperties> {
+        None
+    }
+
+    /// Fetch the capabilities of an external semaphore handle type.
+    fn external_semaphore_properties(
+        &self,
+        _handle_type: external::SemaphoreHandleType,
+    ) -> external::ExternalSemaphoreProperties {
+        external::ExternalSemaphoreProperties::default()
+    }
+
+    /// Fetch the DRM format modifiers supported with a format.
+    fn drm_format_modifier_properties(
+        &self,
+        _format: format::Format,
+    ) -> Vec<external::DrmFormatModifierProperties> {
+        Vec::new()
+    }
 }
 
 /// Supported physical device types
@@ -145,6 +145,10 @@ bitflags!(
         const VERTEX = 0x80;
         ///
         const INDIRECT = 0x100;
+        /// Predicate of conditional rendering.
+        const CONDITIONAL_RENDERING = 0x200;
+        /// Capture of transform feedback.
+        const TRANSFORM_FEEDBACK = 0x800;
     }
 );
 
@@ -189,6 +193,10 @@ bitflags!(
         const MEMORY_READ = 0x8000;
         ///
         const MEMORY_WRITE = 0x10000;
+        /// Read the predicate of conditional rendering.
+        const CONDITIONAL_RENDERING_READ = 0x100000;
+        /// Write vertex processing output with transform feedback.
+        const TRANSFORM_FEEDBACK_WRITE = 0x2000000;
     }
 );
 
@@ -43,6 +43,7 @@ pub type DescriptorSetOffset = u32;
 bitflags! {
     /// Option flags for various command buffer settings.
     #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct CommandBufferFlags: u32 {
         // TODO: Remove once 'const fn' is stabilized: https://github.com/rust-lang/rust/issues/24111
         /// No flags.
@@ -67,13 +68,15 @@ bitflags! {
 /// and `command::Secondary` do at compile-time.
 #[allow(missing_docs)]
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Level {
     Primary,
     Secondary,
 }
 
 /// Specifies how commands for the following renderpasses will be recorded.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SubpassContents {
     /// Contents of the subpass will be inline in the command buffer,
     /// NOT in secondary command buffers.
@@ -219,6 +222,19 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
         I: IntoIterator<Item = (T, buffer::SubRange)>,
         T: Borrow<B::Buffer>;
 
+    /// Bind buffers capturing the vertex processing output while transform feedback
+    /// is active, starting from the transform feedback binding `first_binding`.
+    ///
+    /// The shader outputs to capture, and the binding they are written to, are declared
+    /// in the last vertex processing shader stage of the graphics pipeline.
+    /// The buffers need `buffer::Usage::TRANSFORM_FEEDBACK`.
+    ///
+    /// Requires `Features::TRANSFORM_FEEDBACK`.
+    unsafe fn bind_transform_feedback_buffers<I, T>(&mut self, first_binding: u32, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<B::Buffer>;
+
     /// Set the viewport parameters for the rasterizer.
     ///
     /// Each viewport passed corresponds to the viewport with the same index,
@@ -338,6 +354,21 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
         J: IntoIterator,
         J::Item: Borrow<DescriptorSetOffset>;
 
+    /// Writes descriptors directly into the command buffer, as the graphics descriptor set
+    /// `set` of `layout`, without allocating them from a `DescriptorPool`.
+    ///
+    /// The set layout must have been created with `DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR`,
+    /// and requires `Features::PUSH_DESCRIPTOR`.
+    unsafe fn push_graphics_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, B>>;
+
     /// Bind a compute pipeline.
     ///
     /// # Errors
@@ -363,6 +394,21 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
         J: IntoIterator,
         J::Item: Borrow<DescriptorSetOffset>;
 
+    /// Writes descriptors directly into the command buffer, as the compute descriptor set
+    /// `set` of `layout`, without allocating them from a `DescriptorPool`.
+    ///
+    /// The set layout must have been created with `DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR`,
+    /// and requires `Features::PUSH_DESCRIPTOR`.
+    unsafe fn push_compute_descriptor_set<'a, I, J>(
+        &mut self,
+        layout: &B::PipelineLayout,
+        set: usize,
+        writes: I,
+    ) where
+        I: IntoIterator<Item = pso::DescriptorWrite<J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, B>>;
+
     /// Execute a workgroup in the compute pipeline. `x`, `y` and `z` are the
     /// number of local workgroups to dispatch along each "axis"; a total of `x`*`y`*`z`
     /// local workgroups will be created.
@@ -480,6 +526,83 @@ pub trait CommandBuffer<B: Backend>: fmt::Debug + Any + Send + Sync {
         stride: u32,
     );
 
+    /// Functions identically to `draw_indirect()`, except the number of draws is read
+    /// as a `u32` from `count_buffer`, starting at `count_buffer_offset`. At most
+    /// `max_draw_count` draws are performed.
+    ///
+    /// Requires `Features::DRAW_INDIRECT_COUNT`.
+    unsafe fn draw_indirect_count(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &B::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    );
+
+    /// Functions identically to `draw_indexed_indirect()`, except the number of draws is
+    /// read as a `u32` from `count_buffer`, starting at `count_buffer_offset`. At most
+    /// `max_draw_count` draws are performed.
+    ///
+    /// Requires `Features::DRAW_INDIRECT_COUNT`.
+    unsafe fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        count_buffer: &B::Buffer,
+        count_buffer_offset: buffer::Offset,
+        max_draw_count: DrawCount,
+        stride: u32,
+    );
+
+    /// Begins a conditionally rendered block. Draws, dispatches and `clear_attachments`
+    /// recorded until the matching `end_conditional_rendering` are discarded during
+    /// execution if the `u32` read from `buffer` at `offset` is zero, or non-zero if
+    /// `inverted` is set.
+    ///
+    /// The buffer needs `buffer::Usage::CONDITIONAL_RENDERING`, and `offset` must be
+    /// a multiple of 4. Results of occlusion queries can be copied into it with
+    /// `copy_query_pool_results` to skip draws without a CPU readback.
+    ///
+    /// Requires `Features::CONDITIONAL_RENDERING`.
+    unsafe fn begin_conditional_rendering(
+        &mut self,
+        buffer: &B::Buffer,
+        offset: buffer::Offset,
+        inverted: bool,
+    );
+
+    /// Begins a conditionally rendered block, like `begin_conditional_rendering`, with
+    /// the predicate taken from the result of the occlusion query `id` of `pool`.
+    /// Execution waits for the result of the query to be available.
+    ///
+    /// This must be called outside of a render pass, and the block is ended with
+    /// `end_conditional_rendering`.
+    ///
+    /// Requires `Features::CONDITIONAL_RENDERING_QUERY`.
+    unsafe fn begin_conditional_rendering_query(
+        &mut self,
+        pool: &B::QueryPool,
+        id: query::Id,
+        inverted: bool,
+    );
+
+    /// Ends the current conditionally rendered block.
+    unsafe fn end_conditional_rendering(&mut self);
+
+    /// Begins capturing the output of the following draws into the bound transform
+    /// feedback buffers. This must be called inside of a render pass.
+    ///
+    /// No counter buffers are used, so capturing always starts at the bound offsets
+    /// and can't be resumed across render passes.
+    ///
+    /// Requires `Features::TRANSFORM_FEEDBACK`.
+    unsafe fn begin_transform_feedback(&mut self);
+
+    /// Ends capturing vertex processing output into transform feedback buffers.
+    unsafe fn end_transform_feedback(&mut self);
+
     /// Signals an event once all specified stages of the shader pipeline have completed.
     unsafe fn set_event(&mut self, event: &B::Event, stages: pso::PipelineStage);
 
@@ -18,15 +18,16 @@ use std::{fmt, iter};
 
 use crate::{
     buffer,
+    external,
     format,
     image,
-    memory::{Requirements, Segment},
+    memory::{self, Requirements, Segment},
     pass,
     pool::CommandPoolCreateFlags,
     pso,
     pso::DescriptorPoolCreateFlags,
     query,
-    queue::QueueFamilyId,
+    queue::{QueueFamilyId, SemaphoreValue},
     window::{self, SwapchainConfig},
     Backend,
     MemoryTypeId,
@@ -411,6 +412,47 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Free device memory
     unsafe fn free_memory(&self, memory: B::Memory);
 
+    /// Allocates a memory segment which can be exported as any of the given handle types.
+    ///
+    /// Requires `Features::EXTERNAL_MEMORY_FD`, and `Features::EXTERNAL_MEMORY_DMA_BUF`
+    /// for dma-buf handles.
+    unsafe fn allocate_exportable_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<B::Memory, AllocationError>;
+
+    /// Exports memory as a new file descriptor, owned by the caller.
+    ///
+    /// The memory must have been allocated as exportable to the given handle type.
+    unsafe fn export_memory(
+        &self,
+        memory: &B::Memory,
+        handle_type: external::MemoryHandleType,
+    ) -> Result<external::Fd, external::ExportError>;
+
+    /// Returns the mask of the memory types a file descriptor can be imported as.
+    ///
+    /// Opaque file descriptors can't be queried, and must be imported with the
+    /// memory type they were exported from.
+    unsafe fn get_memory_fd_type_mask(
+        &self,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<u64, external::ImportError>;
+
+    /// Imports memory from a file descriptor.
+    ///
+    /// On success, the ownership of the file descriptor is transferred to the implementation.
+    unsafe fn import_memory(
+        &self,
+        memory_type: MemoryTypeId,
+        size: u64,
+        handle_type: external::MemoryHandleType,
+        fd: external::Fd,
+    ) -> Result<B::Memory, external::ImportError>;
+
     /// Create a new command pool for a given queue family.
     ///
     /// *Note*: the family has to be associated by one as the `Gpu::queue_groups`.
@@ -428,11 +470,16 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// A *render pass* represents a collection of attachments, subpasses, and dependencies between
     /// the subpasses, and describes how the attachments are used over the course of the subpasses.
     /// The use of a render pass in a command buffer is a *render pass* instance.
+    ///
+    /// `correlation_masks` are only used by multiview render passes. Each mask lists views
+    /// that are likely to be rendered with similar contents, which the implementation
+    /// may exploit to render them concurrently.
     unsafe fn create_render_pass<'a, IA, IS, ID>(
         &self,
         attachments: IA,
         subpasses: IS,
         dependencies: ID,
+        correlation_masks: &[pass::ViewMask],
     ) -> Result<B::RenderPass, OutOfMemory>
     where
         IA: IntoIterator,
@@ -594,11 +641,22 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
 
     /// Create a new buffer (unbound).
     ///
-    /// The created buffer won't have associated memory until `bind_buffer_memory` is called.
+    /// The created buffer won't have associated memory until `bind_buffer_memory` is called,
+    /// or, for sparse buffers, until its ranges are bound with `CommandQueue::bind_sparse`.
     unsafe fn create_buffer(
         &self,
         size: u64,
         usage: buffer::Usage,
+        sparse: memory::SparseFlags,
+    ) -> Result<B::Buffer, buffer::CreationError>;
+
+    /// Create a new buffer (unbound), which can be bound to external memory
+    /// of any of the given handle types.
+    unsafe fn create_external_buffer(
+        &self,
+        size: u64,
+        usage: buffer::Usage,
+        handle_types: external::MemoryHandleTypes,
     ) -> Result<B::Buffer, buffer::CreationError>;
 
     /// Get memory requirements for the buffer
@@ -641,11 +699,48 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
         tiling: image::Tiling,
         usage: image::Usage,
         view_caps: image::ViewCapabilities,
+        sparse: memory::SparseFlags,
+    ) -> Result<B::Image, image::CreationError>;
+
+    /// Create a new image object, which can be bound to external memory
+    /// of any of the given handle types.
+    unsafe fn create_external_image(
+        &self,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        handle_types: external::MemoryHandleTypes,
+    ) -> Result<B::Image, image::CreationError>;
+
+    /// Create a new image object with an explicit DRM format modifier and plane layouts,
+    /// to be bound to imported dma-buf memory.
+    ///
+    /// The image has a single mip level.
+    ///
+    /// Requires `Features::IMAGE_DRM_FORMAT_MODIFIER`.
+    unsafe fn create_drm_format_modifier_image(
+        &self,
+        kind: image::Kind,
+        format: format::Format,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+        modifier: external::DrmFormatModifier,
     ) -> Result<B::Image, image::CreationError>;
 
     /// Get memory requirements for the Image
     unsafe fn get_image_requirements(&self, image: &B::Image) -> Requirements;
 
+    /// Get the sparse memory requirements of each aspect of an image.
+    ///
+    /// The result is empty if the image wasn't created with `SparseFlags::SPARSE_RESIDENCY`.
+    unsafe fn get_image_sparse_requirements(
+        &self,
+        image: &B::Image,
+    ) -> Vec<image::SparseRequirements>;
+
     ///
     unsafe fn get_image_subresource_footprint(
         &self,
@@ -716,10 +811,14 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Each individual descriptor binding is specified by a descriptor type, a count (array size)
     /// of the number of descriptors in the binding, a set of shader stages that **can** access the
     /// binding, and (if using immutable samplers) an array of sampler descriptors.
+    ///
+    /// Layouts created with `DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR` can't be used
+    /// to allocate descriptor sets, their descriptors are pushed into command buffers instead.
     unsafe fn create_descriptor_set_layout<I, J>(
         &self,
         bindings: I,
         immutable_samplers: J,
+        flags: pso::DescriptorSetLayoutCreateFlags,
     ) -> Result<B::DescriptorSetLayout, OutOfMemory>
     where
         I: IntoIterator,
@@ -766,6 +865,74 @@ pub trait Device<B: Backend>: fmt::Debug + Any + Send + Sync {
     /// Create a new semaphore object
     fn create_semaphore(&self) -> Result<B::Semaphore, OutOfMemory>;
 
+    /// Create a new timeline semaphore object, with the given initial counter value.
+    ///
+    /// Unlike binary semaphores, timeline semaphores carry a counter which only increases.
+    /// Submissions wait for it to reach a value and set it once they finish executing,
+    /// and the host can query, wait for and signal values as well.
+    ///
+    /// Requires `Features::TIMELINE_SEMAPHORE`.
+    fn create_timeline_semaphore(
+        &self,
+        initial_value: SemaphoreValue,
+    ) -> Result<B::Semaphore, OutOfMemory>;
+
+    /// Returns the current counter value of a timeline semaphore.
+    unsafe fn get_semaphore_value(
+        &self,
+        semaphore: &B::Semaphore,
+    ) -> Result<SemaphoreValue, DeviceLost>;
+
+    /// Blocks until the counter of a timeline semaphore reaches the given value.
+    /// Returns true if the value was reached before the timeout.
+    unsafe fn wait_for_semaphore_value(
+        &self,
+        semaphore: &B::Semaphore,
+        value: SemaphoreValue,
+        timeout_ns: u64,
+    ) -> Result<bool, OomOrDeviceLost>;
+
+    /// Set the counter of a timeline semaphore from the host.
+    ///
+    /// The value must be greater than the current one, and lower than the values of
+    /// pending signal operations.
+    unsafe fn signal_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        value: SemaphoreValue,
+    ) -> Result<(), OutOfMemory>;
+
+    /// Create a new binary semaphore object, which can be exported as any of the
+    /// given handle types.
+    ///
+    /// Requires `Features::EXTERNAL_SEMAPHORE_FD`.
+    fn create_exportable_semaphore(
+        &self,
+        handle_types: external::SemaphoreHandleTypes,
+    ) -> Result<B::Semaphore, OutOfMemory>;
+
+    /// Exports the payload of a semaphore as a new file descriptor, owned by the caller.
+    ///
+    /// Exporting a `SyncFd` handle requires a pending signal operation on the semaphore,
+    /// and leaves it unsignaled.
+    unsafe fn export_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+    ) -> Result<external::Fd, external::ExportError>;
+
+    /// Imports the payload of a semaphore from a file descriptor.
+    ///
+    /// An `OpaqueFd` payload permanently replaces the one of the semaphore, while
+    /// a `SyncFd` payload only lasts until the next wait operation.
+    /// On success, the ownership of the file descriptor is transferred to the implementation.
+    unsafe fn import_semaphore(
+        &self,
+        semaphore: &B::Semaphore,
+        handle_type: external::SemaphoreHandleType,
+        fd: external::Fd,
+    ) -> Result<(), external::ImportError>;
+
     /// Destroy a semaphore object
     unsafe fn destroy_semaphore(&self, semaphore: B::Semaphore);
 
@@ -0,0 +1,216 @@
+//! External memory and semaphores.
+//!
+//! Memory objects and semaphores can be shared with other devices, APIs or processes
+//! by exporting them as POSIX file descriptors, which are then imported on the other side.
+//! Images can also be created on top of Linux dma-buf memory with an explicit DRM format
+//! modifier, as produced by compositors and video decoders.
+
+use crate::{device::OutOfMemory, format::ImageFeature};
+
+/// A POSIX file descriptor.
+pub type Fd = std::os::raw::c_int;
+
+/// Type of an external memory handle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MemoryHandleType {
+    /// Opaque file descriptor, only meaningful to a device of the same driver.
+    OpaqueFd,
+    /// Linux dma-buf file descriptor.
+    DmaBuf,
+}
+
+bitflags!(
+    /// Set of external memory handle types.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct MemoryHandleTypes: u32 {
+        /// Opaque file descriptor.
+        const OPAQUE_FD = 0x1;
+        /// Linux dma-buf file descriptor.
+        const DMA_BUF = 0x2;
+    }
+);
+
+impl From<MemoryHandleType> for MemoryHandleTypes {
+    fn from(handle_type: MemoryHandleType) -> Self {
+        match handle_type {
+            MemoryHandleType::OpaqueFd => MemoryHandleTypes::OPAQUE_FD,
+            MemoryHandleType::DmaBuf => MemoryHandleTypes::DMA_BUF,
+        }
+    }
+}
+
+/// Type of an external semaphore handle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SemaphoreHandleType {
+    /// Opaque file descriptor, only meaningful to a device of the same driver.
+    OpaqueFd,
+    /// Linux sync file descriptor, carrying a single pending signal.
+    SyncFd,
+}
+
+bitflags!(
+    /// Set of external semaphore handle types.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct SemaphoreHandleTypes: u32 {
+        /// Opaque file descriptor.
+        const OPAQUE_FD = 0x1;
+        /// Linux sync file descriptor.
+        const SYNC_FD = 0x2;
+    }
+);
+
+impl From<SemaphoreHandleType> for SemaphoreHandleTypes {
+    fn from(handle_type: SemaphoreHandleType) -> Self {
+        match handle_type {
+            SemaphoreHandleType::OpaqueFd => SemaphoreHandleTypes::OPAQUE_FD,
+            SemaphoreHandleType::SyncFd => SemaphoreHandleTypes::SYNC_FD,
+        }
+    }
+}
+
+bitflags!(
+    /// Operations supported with an external handle type.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct ExternalFeatures: u32 {
+        /// Objects can be exported as handles of this type.
+        const EXPORTABLE = 0x1;
+        /// Handles of this type can be imported.
+        const IMPORTABLE = 0x2;
+    }
+);
+
+/// Capabilities of an external memory handle type, for a given kind of resource.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExternalMemoryProperties {
+    /// Supported operations.
+    pub features: ExternalFeatures,
+    /// Handle types the memory can also be exported as, or imported from,
+    /// when used with this handle type.
+    pub compatible_handle_types: MemoryHandleTypes,
+}
+
+/// Capabilities of an external semaphore handle type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExternalSemaphoreProperties {
+    /// Supported operations.
+    pub features: ExternalFeatures,
+    /// Handle types the semaphore can also be exported as, or imported from,
+    /// when used with this handle type.
+    pub compatible_handle_types: SemaphoreHandleTypes,
+}
+
+/// Layout of a memory plane of an image with an explicit DRM format modifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlaneLayout {
+    /// Offset of the plane from the start of the memory, in bytes.
+    pub offset: u64,
+    /// Distance between consecutive rows of the plane, in bytes.
+    pub row_pitch: u64,
+}
+
+/// DRM format modifier and explicit plane layouts of an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DrmFormatModifier<'a> {
+    /// The DRM format modifier, as defined in `drm_fourcc.h`.
+    pub modifier: u64,
+    /// Layouts of the memory planes, whose count is given by the modifier properties.
+    pub planes: &'a [PlaneLayout],
+}
+
+/// Properties of a DRM format modifier supported with a format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DrmFormatModifierProperties {
+    /// The DRM format modifier.
+    pub modifier: u64,
+    /// Number of memory planes of images using this modifier.
+    pub plane_count: u32,
+    /// Features supported by images using this modifier.
+    pub tiling_features: ImageFeature,
+}
+
+/// Error exporting an external handle.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExportError {
+    /// Out of either host or device memory.
+    OutOfMemory(OutOfMemory),
+    /// Cannot create any more file descriptors.
+    TooManyObjects,
+    /// The handle type is not supported by the implementation.
+    Unsupported,
+}
+
+impl From<OutOfMemory> for ExportError {
+    fn from(error: OutOfMemory) -> Self {
+        ExportError::OutOfMemory(error)
+    }
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::OutOfMemory(err) => write!(fmt, "Failed to export handle: {}", err),
+            ExportError::TooManyObjects => {
+                write!(fmt, "Failed to export handle: Too many objects")
+            }
+            ExportError::Unsupported => {
+                write!(fmt, "Failed to export handle: Unsupported handle type")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportError::OutOfMemory(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Error importing an external handle.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportError {
+    /// Out of either host or device memory.
+    OutOfMemory(OutOfMemory),
+    /// Cannot create any more objects.
+    TooManyObjects,
+    /// The handle is not valid, or not compatible with the import parameters.
+    InvalidHandle,
+}
+
+impl From<OutOfMemory> for ImportError {
+    fn from(error: OutOfMemory) -> Self {
+        ImportError::OutOfMemory(error)
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::OutOfMemory(err) => write!(fmt, "Failed to import handle: {}", err),
+            ImportError::TooManyObjects => {
+                write!(fmt, "Failed to import handle: Too many objects")
+            }
+            ImportError::InvalidHandle => write!(fmt, "Failed to import handle: Invalid handle"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::OutOfMemory(err) => Some(err),
+            _ => None,
+        }
+    }
+}
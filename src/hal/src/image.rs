@@ -681,6 +681,41 @@ pub struct FormatProperties {
     pub max_resource_size: usize,
 }
 
+bitflags!(
+    /// Properties of the sparse memory layout of an image.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct SparseFormatFlags: u32 {
+        /// The image has a single mip tail shared by all its array layers.
+        const SINGLE_MIP_TAIL = 0x1;
+        /// The first level of the mip tail is the first level with an extent
+        /// that isn't a multiple of the tile shape.
+        const ALIGNED_MIP_SIZE = 0x2;
+        /// The tile shape doesn't match the standard one of the format.
+        const NONSTANDARD_BLOCK_SIZE = 0x4;
+    }
+);
+
+/// Sparse memory requirements of the aspects of an image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SparseRequirements {
+    /// Aspects the requirements apply to.
+    pub aspects: format::Aspects,
+    /// Shape of a tile, the granularity of `SparseImageBind` regions, in texels.
+    pub granularity: Extent,
+    /// Properties of the sparse memory layout.
+    pub flags: SparseFormatFlags,
+    /// First mipmap level of the mip tail, which is bound through opaque `SparseBind` ranges.
+    pub mip_tail_first_level: Level,
+    /// Size of the mip tail, in bytes.
+    pub mip_tail_size: u64,
+    /// Opaque offset of the mip tail, or of the one of the first array layer.
+    pub mip_tail_offset: u64,
+    /// Opaque distance between the mip tails of consecutive array layers, unless
+    /// `SINGLE_MIP_TAIL` is set.
+    pub mip_tail_stride: u64,
+}
+
 /// Footprint of a subresource in memory.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -25,6 +25,7 @@ pub mod adapter;
 pub mod buffer;
 pub mod command;
 pub mod device;
+pub mod external;
 pub mod format;
 pub mod image;
 pub mod memory;
@@ -66,7 +67,7 @@ bitflags! {
     //TODO: add a feature for non-normalized samplers
     //TODO: add a feature for mutable comparison samplers
     /// Features that the device supports.
-    /// These only include features of the core interface and not API extensions.
+    /// Features of API extensions are kept in their own range, see `EXTENSIONS_MASK`.
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Features: u128 {
         /// Bit mask of Vulkan Core features.
@@ -75,6 +76,8 @@ bitflags! {
         const PORTABILITY_MASK  = 0x0000_FFFF_0000_0000_0000_0000;
         /// Bit mask for extra WebGPU features.
         const WEBGPU_MASK = 0xFFFF_0000_0000_0000_0000_0000;
+        /// Bit mask of features from API extensions, such as Vulkan `KHR` and `EXT` ones.
+        const EXTENSIONS_MASK = 0xFFFF_FFFF_0000_0000_0000_0000_0000_0000;
 
         /// Support for robust buffer access.
         /// Buffer access by SPIR-V shaders is checked against the buffer/image boundaries.
@@ -207,6 +210,52 @@ bitflags! {
 
         /// Make the NDC coordinate system pointing Y up, to match D3D and Metal.
         const NDC_Y_UP = 0x01 << 80;
+
+        /// Support timeline semaphores, carrying a counter value instead of a binary state.
+        const TIMELINE_SEMAPHORE = 0x0001 << 96;
+
+        /// Support non-uniform indexing of uniform buffer arrays in shaders.
+        const SHADER_UNIFORM_BUFFER_ARRAY_NON_UNIFORM_INDEXING = 0x0002 << 96;
+        /// Support non-uniform indexing of sampled image arrays in shaders.
+        const SHADER_SAMPLED_IMAGE_ARRAY_NON_UNIFORM_INDEXING = 0x0004 << 96;
+        /// Support non-uniform indexing of storage buffer arrays in shaders.
+        const SHADER_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING = 0x0008 << 96;
+        /// Support non-uniform indexing of storage image arrays in shaders.
+        const SHADER_STORAGE_IMAGE_ARRAY_NON_UNIFORM_INDEXING = 0x0010 << 96;
+        /// Support descriptor arrays declared without a size in shaders.
+        const UNSIZED_DESCRIPTOR_ARRAY = 0x0020 << 96;
+        /// Support `DescriptorBindingFlags::UPDATE_AFTER_BIND` on sampled image,
+        /// storage image and storage buffer bindings.
+        const DESCRIPTOR_BINDING_UPDATE_AFTER_BIND = 0x0040 << 96;
+        /// Support `DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING`.
+        const DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING = 0x0080 << 96;
+        /// Support `DescriptorBindingFlags::PARTIALLY_BOUND`.
+        const DESCRIPTOR_BINDING_PARTIALLY_BOUND = 0x0100 << 96;
+        /// Support `DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT`.
+        const DESCRIPTOR_BINDING_VARIABLE_COUNT = 0x0200 << 96;
+        /// Support pushing descriptors directly into command buffers.
+        const PUSH_DESCRIPTOR = 0x0400 << 96;
+        /// Support multiview render passes, broadcasting the draws of a subpass to several views.
+        const MULTIVIEW = 0x0800 << 96;
+        /// Support indirect draws taking the number of draws from a buffer.
+        const DRAW_INDIRECT_COUNT = 0x1000 << 96;
+        /// Support conditional rendering based on a value in a buffer.
+        const CONDITIONAL_RENDERING = 0x2000 << 96;
+        /// Support capturing vertex processing output into buffers with transform feedback.
+        ///
+        /// Also enables `query::Type::PrimitivesWritten` query pools. Capturing can't be
+        /// resumed across render passes.
+        const TRANSFORM_FEEDBACK = 0x4000 << 96;
+        /// Support exporting and importing memory as opaque file descriptors.
+        const EXTERNAL_MEMORY_FD = 0x8000 << 96;
+        /// Support exporting and importing semaphores as file descriptors.
+        const EXTERNAL_SEMAPHORE_FD = 0x0001_0000 << 96;
+        /// Support exporting and importing memory as Linux dma-buf file descriptors.
+        const EXTERNAL_MEMORY_DMA_BUF = 0x0002_0000 << 96;
+        /// Support images with an explicit DRM format modifier.
+        const IMAGE_DRM_FORMAT_MODIFIER = 0x0004_0000 << 96;
+        /// Support conditional rendering based on the result of an occlusion query.
+        const CONDITIONAL_RENDERING_QUERY = 0x0008_0000 << 96;
     }
 }
 
@@ -369,6 +418,26 @@ pub struct Limits {
 
     /// The alignment of the vertex buffer stride.
     pub min_vertex_input_binding_stride_alignment: buffer::Offset,
+
+    /// Number of nanoseconds it takes for a timestamp query value to be incremented by 1.
+    pub timestamp_period: f32,
+
+    /// Maximum number of descriptors in all the pools created with
+    /// `DescriptorPoolCreateFlags::UPDATE_AFTER_BIND`.
+    pub max_update_after_bind_descriptors_in_all_pools: usize,
+    /// Maximum number of resources accessible to a single shader stage, when counting the
+    /// descriptors of update-after-bind bindings.
+    pub max_per_stage_update_after_bind_resources: usize,
+
+    /// Maximum number of descriptors in a layout created with
+    /// `DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR`.
+    pub max_push_descriptors: usize,
+
+    /// Maximum number of views in the view mask of a multiview subpass.
+    pub max_multiview_view_count: usize,
+
+    /// Maximum number of buffers bound for transform feedback.
+    pub max_transform_feedback_buffers: usize,
 }
 
 /// An enum describing the type of an index value in a slice's index buffer
@@ -401,10 +470,10 @@ pub struct UnsupportedBackend;
 ///
 /// // Create a concrete instance of our backend (this is backend-dependent and may be more
 /// // complicated for some backends).
-/// let instance = backend::Instance;
+/// let instance = backend::Instance::with_config(backend::MockConfig::default());
 /// // We can get a list of the available adapters, which are either physical graphics
-/// // devices, or virtual adapters. Because we are using the dummy `empty` backend,
-/// // there will be nothing in this list.
+/// // devices, or virtual adapters. Because we are using the mock `empty` backend,
+/// // there will be a single fake adapter in this list.
 /// for (idx, adapter) in hal::Instance::enumerate_adapters(&instance).iter().enumerate() {
 ///     println!("Adapter {}: {:?}", idx, adapter.info);
 /// }
@@ -42,6 +42,27 @@ bitflags!(
     }
 );
 
+bitflags!(
+    /// Sparse creation flags of buffers and images.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct SparseFlags: u32 {
+        /// The resource is bound to memory with `CommandQueue::bind_sparse`
+        /// instead of being bound to a single memory object.
+        ///
+        /// Requires `Features::SPARSE_BINDING`.
+        const SPARSE_BINDING = 0x1;
+        /// The resource may be partially bound to memory.
+        ///
+        /// Requires `SPARSE_BINDING`, as well as `Features::SPARSE_RESIDENCY_BUFFER`
+        /// or the `Features::SPARSE_RESIDENCY_IMAGE_*` feature matching the image.
+        const SPARSE_RESIDENCY = 0x2;
+        /// The resource may be bound to memory that is also bound to other resources.
+        ///
+        /// Requires `SPARSE_BINDING` and `Features::SPARSE_RESIDENCY_ALIASED`.
+        const SPARSE_ALIASED = 0x4;
+    }
+);
+
 // DOC TODO: Could be better, but I don't know how to do this without
 // trying to explain the whole synchronization model.
 /// A [memory barrier](https://www.khronos.org/registry/vulkan/specs/1.0/html/vkspec.html#synchronization-memory-barriers)
@@ -102,6 +123,39 @@ pub struct Requirements {
     pub type_mask: u64,
 }
 
+/// Binding of a range of a sparse resource to memory.
+///
+/// For images, the range is an opaque range of the memory of the image, as returned by
+/// `get_image_requirements`, which is used for the mip tail and for non-resident images.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SparseBind<M> {
+    /// Offset into the resource.
+    pub resource_offset: u64,
+    /// Size of the bound range, a multiple of the alignment of the resource.
+    pub size: u64,
+    /// Memory and offset into it that the range is bound to, or `None` to unbind the range.
+    pub memory: Option<(M, u64)>,
+}
+
+/// Binding of a region of a subresource of a sparse image to memory.
+///
+/// The offset and extent of the region are in texels, and must be multiples of the tile
+/// shape returned by `get_image_sparse_requirements`, unless the region reaches the edge
+/// of the subresource.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SparseImageBind<M> {
+    /// The subresource containing the region.
+    pub subresource: image::Subresource,
+    /// Offset of the region within the subresource.
+    pub offset: image::Offset,
+    /// Extent of the region.
+    pub extent: image::Extent,
+    /// Memory and offset into it that the region is bound to, or `None` to unbind the region.
+    pub memory: Option<(M, u64)>,
+}
+
 /// A linear segment within a memory block.
 #[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -112,6 +112,9 @@ pub const ATTACHMENT_UNUSED: AttachmentId = !0;
 /// Index of a subpass.
 pub type SubpassId = u8;
 
+/// Mask of the views rendered by a multiview subpass, the bit `i` standing for the view `i`.
+pub type ViewMask = u32;
+
 /// Expresses a dependency between multiple subpasses. This is used
 /// both to describe a source or destination subpass; data either
 /// explicitly passes from this subpass to the next or from another
@@ -150,6 +153,13 @@ pub struct SubpassDesc<'a> {
     /// Attachments that are not used by the subpass but must be preserved to be
     /// passed on to subsequent passes.
     pub preserves: &'a [AttachmentId],
+    /// Views the subpass renders to, or zero if the subpass doesn't use multiview.
+    ///
+    /// Each draw of a multiview subpass is broadcast to all the views of the mask, which
+    /// map to the array layers of the attachments. Shaders can read the index of the view
+    /// being rendered. The masks of the subpasses of a render pass must either be all zero
+    /// or all non-zero. Requires `Features::MULTIVIEW`.
+    pub view_mask: ViewMask,
 }
 
 /// A sub-pass borrow of a pass.
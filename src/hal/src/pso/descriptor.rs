@@ -123,6 +123,38 @@ pub struct DescriptorSetLayoutBinding {
     pub stage_flags: ShaderStageFlags,
     /// Use the associated list of immutable samplers.
     pub immutable_samplers: bool,
+    /// Behavior of the descriptors of this binding.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flags: DescriptorBindingFlags,
+}
+
+bitflags! {
+    /// Flags controlling the behavior of a descriptor set layout binding.
+    #[derive(Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct DescriptorBindingFlags: u32 {
+        /// Descriptors of the binding can be updated after the set is bound, and until
+        /// the command buffers using it are submitted.
+        ///
+        /// Requires `Features::DESCRIPTOR_BINDING_UPDATE_AFTER_BIND`, and the set must be
+        /// allocated from a pool created with `DescriptorPoolCreateFlags::UPDATE_AFTER_BIND`.
+        const UPDATE_AFTER_BIND = 0x1;
+        /// Descriptors of the binding that aren't dynamically used by the pending command
+        /// buffers can be updated while they are executing.
+        ///
+        /// Requires `Features::DESCRIPTOR_BINDING_UPDATE_UNUSED_WHILE_PENDING`.
+        const UPDATE_UNUSED_WHILE_PENDING = 0x2;
+        /// Descriptors of the binding that aren't dynamically used don't need to be valid.
+        ///
+        /// Requires `Features::DESCRIPTOR_BINDING_PARTIALLY_BOUND`.
+        const PARTIALLY_BOUND = 0x4;
+        /// The binding has a variable number of descriptors, chosen at allocation time
+        /// with `DescriptorPool::allocate_variable_set`, and `count` is an upper bound.
+        /// Only the binding with the largest number in the layout can have this flag.
+        ///
+        /// Requires `Features::DESCRIPTOR_BINDING_VARIABLE_COUNT`.
+        const VARIABLE_DESCRIPTOR_COUNT = 0x8;
+    }
 }
 
 /// Set of descriptors of a specific type.
@@ -202,6 +234,22 @@ pub trait DescriptorPool<B: Backend>: Send + Sync + fmt::Debug {
         Ok(result.0.unwrap())
     }
 
+    /// Allocate a descriptor set from the pool, with `count` descriptors in its binding
+    /// flagged with `DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT`.
+    ///
+    /// The count must not exceed the `count` of that binding in the set layout. For layouts
+    /// without such binding, the count is ignored.
+    ///
+    /// The default implementation allocates the whole upper bound of the variable binding.
+    unsafe fn allocate_variable_set(
+        &mut self,
+        layout: &B::DescriptorSetLayout,
+        count: DescriptorArrayIndex,
+    ) -> Result<B::DescriptorSet, AllocationError> {
+        let _ = count;
+        self.allocate_set(layout)
+    }
+
     /// Allocate multiple descriptor sets from the pool.
     ///
     /// The descriptor set will be allocated from the pool according to the corresponding set layout. However,
@@ -272,6 +320,18 @@ pub enum Descriptor<'a, B: Backend> {
     TexelBuffer(&'a B::BufferView),
 }
 
+/// Writes descriptors into a binding of a set pushed with the `push_*_descriptor_set` methods
+/// of a `CommandBuffer`.
+#[allow(missing_docs)]
+#[derive(Debug)]
+pub struct DescriptorWrite<WI> {
+    /// *Note*: as with `DescriptorSetWrite`, the descriptors
+    /// past the end of the binding are spilled onto the next one.
+    pub binding: DescriptorBinding,
+    pub array_offset: DescriptorArrayIndex,
+    pub descriptors: WI,
+}
+
 /// Copies a range of descriptors to be bound from one descriptor set to another Should be
 /// provided to the `copy_descriptor_sets` method of a `Device`.
 #[allow(missing_docs)]
@@ -286,11 +346,28 @@ pub struct DescriptorSetCopy<'a, B: Backend> {
     pub count: usize,
 }
 
+bitflags! {
+    /// Descriptor set layout creation flags.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct DescriptorSetLayoutCreateFlags: u32 {
+        /// Specifies that the descriptors are pushed directly into command buffers, instead
+        /// of being written into sets allocated from a pool. Dynamic buffer descriptors
+        /// are not allowed in such layouts.
+        ///
+        /// Requires `Features::PUSH_DESCRIPTOR`.
+        const PUSH_DESCRIPTOR = 0x1;
+    }
+}
+
 bitflags! {
     /// Descriptor pool creation flags.
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct DescriptorPoolCreateFlags: u32 {
         /// Specifies that descriptor sets are allowed to be freed from the pool
         /// individually.
         const FREE_DESCRIPTOR_SET = 0x1;
+        /// Specifies that descriptor sets allocated from the pool can contain bindings
+        /// with `DescriptorBindingFlags::UPDATE_AFTER_BIND`.
+        const UPDATE_AFTER_BIND = 0x2;
     }
 }
@@ -100,6 +100,10 @@ bitflags!(
         /// Read/Write access from host.
         /// (Not a real pipeline stage)
         const HOST = 0x4000;
+        /// Reading the predicate of conditional rendering.
+        const CONDITIONAL_RENDERING = 0x40000;
+        /// Writing vertex processing output with transform feedback.
+        const TRANSFORM_FEEDBACK = 0x1000000;
     }
 );
 
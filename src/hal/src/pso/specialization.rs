@@ -13,6 +13,7 @@ use std::{borrow::Cow, ops::Range, slice};
 /// More importantly, they are fast to execute, since the driver
 /// can optimize out the branch on that other PSO creation.
 #[derive(Debug, Clone, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SpecializationConstant {
     /// Constant identifier in shader source.
     pub id: u32,
@@ -3,9 +3,13 @@
 //! or other introspection, providing a mechanism for the command buffer to record data about its
 //! operation as it is running.
 
-use crate::device::OutOfMemory;
+use crate::command::CommandBuffer;
+use crate::device::{Device, OomOrDeviceLost, OutOfMemory};
+use crate::pso::PipelineStage;
 use crate::Backend;
 
+use std::slice;
+
 /// A query identifier.
 pub type Id = u32;
 
@@ -74,6 +78,7 @@ bitflags!(
 
 /// Type of queries in a query pool.
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Type {
     /// Occlusion query. Count the number of drawn samples between
     /// the start and end of the query command.
@@ -85,6 +90,12 @@ pub enum Type {
     /// Timestamp query. Timestamps can be recorded to the
     /// query pool by calling `write_timestamp()`.
     Timestamp,
+    /// Transform feedback query. Each result holds two values: the number of
+    /// primitives written to the transform feedback buffers, and the number of
+    /// primitives that would have been written if the buffers were large enough.
+    ///
+    /// Requires `Features::TRANSFORM_FEEDBACK`.
+    PrimitivesWritten,
 }
 
 bitflags!(
@@ -115,3 +126,158 @@ bitflags!(
         const COMPUTE_SHADER_INVOCATIONS = 0x400;
     }
 );
+
+/// Returns the number of nanoseconds elapsed between two timestamps.
+///
+/// Only the lowest `valid_bits` of the timestamps are meaningful, as reported by the
+/// queue family which wrote them, so the difference accounts for the counter wrapping
+/// around. `period` is the `timestamp_period` of the adapter limits.
+pub fn timestamp_delta_ns(start: u64, end: u64, valid_bits: u32, period: f32) -> u64 {
+    let mask = if valid_bits >= 64 {
+        !0
+    } else {
+        (1 << valid_bits) - 1
+    };
+    let ticks = end.wrapping_sub(start) & mask;
+    (ticks as f64 * period as f64) as u64
+}
+
+/// Duration of a scope recorded by a `Profiler`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopeTiming {
+    /// Name given to the scope.
+    pub name: String,
+    /// Number of scopes the scope is nested in.
+    pub depth: usize,
+    /// Time spent executing the scope, in nanoseconds.
+    pub duration_ns: u64,
+}
+
+#[derive(Debug)]
+struct Scope {
+    name: String,
+    depth: usize,
+    start: Id,
+}
+
+/// GPU profiler measuring named scopes of a command buffer with timestamp queries.
+///
+/// Scopes can be nested, and are recorded in the order they begin. Once the command
+/// buffer finished executing, `resolve` returns how long each of them took.
+#[derive(Debug)]
+pub struct Profiler<B: Backend> {
+    pool: B::QueryPool,
+    max_scopes: Id,
+    timestamp_period: f32,
+    timestamp_valid_bits: u32,
+    scopes: Vec<Scope>,
+    open: Vec<usize>,
+}
+
+impl<B: Backend> Profiler<B> {
+    /// Create a profiler able to record up to `max_scopes` scopes between resets.
+    ///
+    /// `timestamp_period` comes from the adapter limits, and `timestamp_valid_bits` from
+    /// the queue family executing the command buffers.
+    pub fn new(
+        device: &B::Device,
+        max_scopes: Id,
+        timestamp_period: f32,
+        timestamp_valid_bits: u32,
+    ) -> Result<Self, CreationError> {
+        if timestamp_valid_bits == 0 {
+            return Err(CreationError::Unsupported(Type::Timestamp));
+        }
+        let pool = unsafe { device.create_query_pool(Type::Timestamp, 2 * max_scopes)? };
+        Ok(Profiler {
+            pool,
+            max_scopes,
+            timestamp_period,
+            timestamp_valid_bits,
+            scopes: Vec::new(),
+            open: Vec::new(),
+        })
+    }
+
+    /// Forget the recorded scopes, and reset their queries before any new scope begins.
+    pub unsafe fn reset(&mut self, cmd_buffer: &mut B::CommandBuffer) {
+        assert!(self.open.is_empty(), "Resetting with open scopes");
+        cmd_buffer.reset_query_pool(&self.pool, 0 .. 2 * self.max_scopes);
+        self.scopes.clear();
+    }
+
+    /// Begin a named scope, ended by the next `end_scope` call on the same command buffer.
+    pub unsafe fn begin_scope(&mut self, cmd_buffer: &mut B::CommandBuffer, name: &str) {
+        assert!(
+            (self.scopes.len() as Id) < self.max_scopes,
+            "Exceeded the maximum of {} scopes",
+            self.max_scopes
+        );
+        let start = 2 * self.scopes.len() as Id;
+        cmd_buffer.write_timestamp(
+            PipelineStage::TOP_OF_PIPE,
+            Query {
+                pool: &self.pool,
+                id: start,
+            },
+        );
+        self.open.push(self.scopes.len());
+        self.scopes.push(Scope {
+            name: name.to_string(),
+            depth: self.open.len() - 1,
+            start,
+        });
+    }
+
+    /// End the innermost open scope.
+    pub unsafe fn end_scope(&mut self, cmd_buffer: &mut B::CommandBuffer) {
+        let index = self.open.pop().expect("No scope to end");
+        cmd_buffer.write_timestamp(
+            PipelineStage::BOTTOM_OF_PIPE,
+            Query {
+                pool: &self.pool,
+                id: self.scopes[index].start + 1,
+            },
+        );
+    }
+
+    /// Wait for the results of the recorded scopes, and return their durations.
+    pub unsafe fn resolve(&self, device: &B::Device) -> Result<Vec<ScopeTiming>, OomOrDeviceLost> {
+        assert!(self.open.is_empty(), "Resolving with open scopes");
+        let mut results = vec![0u64; 2 * self.scopes.len()];
+        if !results.is_empty() {
+            let data =
+                slice::from_raw_parts_mut(results.as_mut_ptr() as *mut u8, results.len() * 8);
+            device.get_query_pool_results(
+                &self.pool,
+                0 .. results.len() as Id,
+                data,
+                8,
+                ResultFlags::BITS_64 | ResultFlags::WAIT,
+            )?;
+        }
+        Ok(self
+            .scopes
+            .iter()
+            .map(|scope| {
+                let start = results[scope.start as usize];
+                let end = results[scope.start as usize + 1];
+                ScopeTiming {
+                    name: scope.name.clone(),
+                    depth: scope.depth,
+                    duration_ns: timestamp_delta_ns(
+                        start,
+                        end,
+                        self.timestamp_valid_bits,
+                        self.timestamp_period,
+                    ),
+                }
+            })
+            .collect())
+    }
+
+    /// Destroy the query pool of the profiler.
+    pub unsafe fn destroy(self, device: &B::Device) {
+        device.destroy_query_pool(self.pool);
+    }
+}
@@ -16,6 +16,9 @@ pub trait QueueFamily: Debug + Any + Send + Sync {
     fn max_queues(&self) -> usize;
     /// Returns the queue family ID.
     fn id(&self) -> QueueFamilyId;
+    /// Returns the number of meaningful bits in the timestamps written by the queues,
+    /// or 0 if timestamps are not supported.
+    fn timestamp_valid_bits(&self) -> u32;
 }
 
 /// Identifier for a queue family of a physical device.
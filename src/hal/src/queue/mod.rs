@@ -10,6 +10,7 @@ pub mod family;
 
 use crate::{
     device::OutOfMemory,
+    memory::{SparseBind, SparseImageBind},
     pso,
     window::{PresentError, PresentationSurface, Suboptimal, SwapImageIndex},
     Backend,
@@ -57,15 +58,45 @@ impl QueueType {
 /// `1.0` (high).
 pub type QueuePriority = f32;
 
+/// Counter value of a timeline semaphore.
+pub type SemaphoreValue = u64;
+
 /// Submission information for a command queue.
 #[derive(Debug)]
 pub struct Submission<Ic, Iw, Is> {
     /// Command buffers to submit.
     pub command_buffers: Ic,
     /// Semaphores to wait being signalled before submission.
+    ///
+    /// Timeline semaphores are waited on until their counter reaches the paired value,
+    /// which is ignored for binary semaphores.
     pub wait_semaphores: Iw,
     /// Semaphores to signal after all command buffers in the submission have finished execution.
+    ///
+    /// The counter of timeline semaphores is set to the paired value, which is ignored for
+    /// binary semaphores.
+    pub signal_semaphores: Is,
+}
+
+/// Sparse memory binding information for a command queue.
+#[derive(Debug)]
+pub struct SparseBinding<Iw, Is, Ib, Io, Ii> {
+    /// Semaphores to wait being signalled before the memory is bound.
+    ///
+    /// Timeline semaphores are waited on until their counter reaches the paired value,
+    /// which is ignored for binary semaphores.
+    pub wait_semaphores: Iw,
+    /// Semaphores to signal after the memory has been bound.
+    ///
+    /// The counter of timeline semaphores is set to the paired value, which is ignored for
+    /// binary semaphores.
     pub signal_semaphores: Is,
+    /// Ranges of sparse buffers to bind.
+    pub buffer_binds: Ib,
+    /// Opaque ranges of sparse images to bind, such as their mip tails.
+    pub image_opaque_binds: Io,
+    /// Regions of subresources of sparse images to bind.
+    pub image_binds: Ii,
 }
 
 /// `RawCommandQueue` are abstractions to the internal GPU execution engines.
@@ -86,8 +117,8 @@ pub trait CommandQueue<B: Backend>: fmt::Debug + Any + Send + Sync {
         T: 'a + Borrow<B::CommandBuffer>,
         Ic: IntoIterator<Item = &'a T>,
         S: 'a + Borrow<B::Semaphore>,
-        Iw: IntoIterator<Item = (&'a S, pso::PipelineStage)>,
-        Is: IntoIterator<Item = &'a S>;
+        Iw: IntoIterator<Item = (&'a S, SemaphoreValue, pso::PipelineStage)>,
+        Is: IntoIterator<Item = (&'a S, SemaphoreValue)>;
 
     /// Simplified version of `submit` that doesn't expect any semaphores.
     unsafe fn submit_without_semaphores<'a, T, Ic>(
@@ -106,6 +137,25 @@ pub trait CommandQueue<B: Backend>: fmt::Debug + Any + Send + Sync {
         self.submit::<_, _, B::Semaphore, _, _>(submission, fence)
     }
 
+    /// Bind memory to ranges and regions of sparse buffers and images.
+    /// `fence` must be in unsignalled state, and will be signalled after all the memory has
+    /// been bound.
+    ///
+    /// The queue must belong to a family supporting sparse binding, and the resources must
+    /// have been created with `SparseFlags::SPARSE_BINDING`. The memory binds of a resource
+    /// are applied in order, so later binds replace earlier ones.
+    unsafe fn bind_sparse<'a, 'b, S, Iw, Is, Ib, Io, Ii>(
+        &mut self,
+        binding: SparseBinding<Iw, Is, Ib, Io, Ii>,
+        fence: Option<&B::Fence>,
+    ) where
+        S: 'a + Borrow<B::Semaphore>,
+        Iw: IntoIterator<Item = (&'a S, SemaphoreValue)>,
+        Is: IntoIterator<Item = (&'a S, SemaphoreValue)>,
+        Ib: IntoIterator<Item = (&'b B::Buffer, &'b [SparseBind<&'b B::Memory>])>,
+        Io: IntoIterator<Item = (&'b B::Image, &'b [SparseBind<&'b B::Memory>])>,
+        Ii: IntoIterator<Item = (&'b B::Image, &'b [SparseImageBind<&'b B::Memory>])>;
+
     /// Presents the result of the queue to the given swapchains, after waiting on all the
     /// semaphores given in `wait_semaphores`. A given swapchain must not appear in this
     /// list more than once.
@@ -359,6 +359,7 @@ bitflags!(
 /// # }
 /// ```
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SwapchainConfig {
     /// Presentation mode.
     pub present_mode: PresentMode,
@@ -5,6 +5,7 @@
         feature = "dx11",
         feature = "metal",
         feature = "gl",
+        feature = "soft",
     )),
     allow(dead_code)
 )]
@@ -237,6 +238,10 @@ fn main() {
     {
         num_failures += harness.run::<gfx_backend_gl::Backend>("GL", Disabilities::default());
     }
+    #[cfg(feature = "soft")]
+    {
+        num_failures += harness.run::<gfx_backend_soft::Backend>("Soft", Disabilities::default());
+    }
     let _ = harness;
     num_failures += 0; // mark as mutated
     process::exit(num_failures as _);
@@ -60,7 +60,7 @@ impl<'a, B: hal::Backend> Drop for FetchGuard<'a, B> {
 
 pub struct Buffer<B: hal::Backend> {
     handle: B::Buffer,
-    _memory: B::Memory,
+    memory: B::Memory,
     size: usize,
     stable_state: b::State,
 }
@@ -88,7 +88,7 @@ impl<B: hal::Backend> Buffer<B> {
 
 pub struct Image<B: hal::Backend> {
     handle: B::Image,
-    _memory: B::Memory,
+    memory: B::Memory,
     kind: i::Kind,
     format: f::Format,
     range: i::SubresourceRange,
@@ -187,6 +187,7 @@ pub struct Scene<B: hal::Backend> {
     upload_buffers: HashMap<String, (B::Buffer, B::Memory)>,
     download_types: Vec<hal::MemoryTypeId>,
     limits: hal::Limits,
+    timestamp_valid_bits: u32,
 }
 
 fn align(x: u64, y: u64) -> u64 {
@@ -207,6 +208,7 @@ impl<B: hal::Backend> Scene<B> {
         info!("creating Scene from {:?}", data_path);
         let memory_types = adapter.physical_device.memory_properties().memory_types;
         let limits = adapter.physical_device.limits();
+        let timestamp_valid_bits = adapter.queue_families[0].timestamp_valid_bits();
 
         // initialize graphics
         let mut gpu = unsafe {
@@ -254,7 +256,11 @@ impl<B: hal::Backend> Scene<B> {
                 )
                 .unwrap()
         };
-        let query_pool = unsafe { device.create_query_pool(query::Type::Timestamp, 2) };
+        let query_pool = if timestamp_valid_bits != 0 {
+            unsafe { device.create_query_pool(query::Type::Timestamp, 2) }
+        } else {
+            Err(query::CreationError::Unsupported(query::Type::Timestamp))
+        };
 
         // create resources
         let mut resources = Resources::<B> {
@@ -307,7 +313,10 @@ impl<B: hal::Backend> Scene<B> {
                     ref data,
                 } => {
                     // allocate memory
-                    let mut buffer = unsafe { device.create_buffer(size as _, usage) }.unwrap();
+                    let mut buffer = unsafe {
+                        device.create_buffer(size as _, usage, memory::SparseFlags::empty())
+                    }
+                    .unwrap();
                     let requirements = unsafe { device.get_buffer_requirements(&buffer) };
                     let memory_type = memory_types
                         .iter()
@@ -351,9 +360,14 @@ impl<B: hal::Backend> Scene<B> {
                         let upload_size =
                             align(size as _, limits.optimal_buffer_copy_pitch_alignment);
                         // create upload buffer
-                        let mut upload_buffer =
-                            unsafe { device.create_buffer(upload_size, b::Usage::TRANSFER_SRC) }
-                                .unwrap();
+                        let mut upload_buffer = unsafe {
+                            device.create_buffer(
+                                upload_size,
+                                b::Usage::TRANSFER_SRC,
+                                memory::SparseFlags::empty(),
+                            )
+                        }
+                        .unwrap();
                         let upload_req = unsafe { device.get_buffer_requirements(&upload_buffer) };
                         let upload_type = *upload_types
                             .iter()
@@ -417,7 +431,7 @@ impl<B: hal::Backend> Scene<B> {
                         name.clone(),
                         Buffer {
                             handle: buffer,
-                            _memory: gpu_memory,
+                            memory: gpu_memory,
                             size,
                             stable_state,
                         },
@@ -439,6 +453,7 @@ impl<B: hal::Backend> Scene<B> {
                             i::Tiling::Optimal,
                             usage,
                             i::ViewCapabilities::empty(),
+                            memory::SparseFlags::empty(),
                         )
                     }
                     .unwrap();
@@ -509,9 +524,14 @@ impl<B: hal::Backend> Scene<B> {
                         let upload_size =
                             (row_pitch as u64 * h as u64 * d as u64) / block_height as u64;
                         // create upload buffer
-                        let mut upload_buffer =
-                            unsafe { device.create_buffer(upload_size, b::Usage::TRANSFER_SRC) }
-                                .unwrap();
+                        let mut upload_buffer = unsafe {
+                            device.create_buffer(
+                                upload_size,
+                                b::Usage::TRANSFER_SRC,
+                                memory::SparseFlags::empty(),
+                            )
+                        }
+                        .unwrap();
                         let upload_req = unsafe { device.get_buffer_requirements(&upload_buffer) };
                         let upload_type = *upload_types
                             .iter()
@@ -599,7 +619,7 @@ impl<B: hal::Backend> Scene<B> {
                         name.clone(),
                         Image {
                             handle: image,
-                            _memory: gpu_memory,
+                            memory: gpu_memory,
                             kind,
                             format,
                             range: COLOR_RANGE.clone(),
@@ -615,6 +635,7 @@ impl<B: hal::Backend> Scene<B> {
                     ref attachments,
                     ref subpasses,
                     ref dependencies,
+                    ref correlation_masks,
                 } => {
                     let att_ref = |aref: &raw::AttachmentRef| {
                         let id = attachments.keys().position(|s| s == &aref.0).unwrap();
@@ -644,7 +665,7 @@ impl<B: hal::Backend> Scene<B> {
                                 .map(|sp| attachments.keys().position(|s| s == sp).unwrap())
                                 .collect::<Vec<_>>();
                             let resolves = sp.resolves.iter().map(&att_ref).collect::<Vec<_>>();
-                            (colors, ds, inputs, preserves, resolves)
+                            (colors, ds, inputs, preserves, resolves, sp.view_mask)
                         })
                         .collect::<Vec<_>>();
                     let raw_subs = temp
@@ -655,6 +676,7 @@ impl<B: hal::Backend> Scene<B> {
                             inputs: &t.2,
                             preserves: &t.3,
                             resolves: &t.4,
+                            view_mask: t.5,
                         })
                         .collect::<Vec<_>>();
                     let raw_deps = dependencies.iter().map(|dep| hal::pass::SubpassDependency {
@@ -665,8 +687,15 @@ impl<B: hal::Backend> Scene<B> {
                     });
 
                     let rp = RenderPass {
-                        handle: unsafe { device.create_render_pass(raw_atts, raw_subs, raw_deps) }
-                            .expect("Render pass creation failure"),
+                        handle: unsafe {
+                            device.create_render_pass(
+                                raw_atts,
+                                raw_subs,
+                                raw_deps,
+                                correlation_masks,
+                            )
+                        }
+                        .expect("Render pass creation failure"),
                         attachments: attachments
                             .iter()
                             .map(|(key, at)| (key.clone(), at.layouts.clone()))
@@ -705,8 +734,14 @@ impl<B: hal::Backend> Scene<B> {
                     assert!(immutable_samplers.is_empty()); //TODO! requires changing the order,
                     assert!(!bindings.is_empty());
                     // since samples are expect to be all read by this point
-                    let layout = unsafe { device.create_descriptor_set_layout(bindings, &[]) }
-                        .expect("Descriptor set layout creation failure!");
+                    let layout = unsafe {
+                        device.create_descriptor_set_layout(
+                            bindings,
+                            &[],
+                            pso::DescriptorSetLayoutCreateFlags::empty(),
+                        )
+                    }
+                    .expect("Descriptor set layout creation failure!");
                     let binding_indices = bindings.iter().map(|dsb| dsb.binding).collect();
                     resources
                         .desc_set_layouts
@@ -1465,6 +1500,7 @@ impl<B: hal::Backend> Scene<B> {
             upload_buffers,
             download_types,
             limits,
+            timestamp_valid_bits,
         })
     }
 }
@@ -1504,8 +1540,14 @@ impl<B: hal::Backend> Scene<B> {
             limits.optimal_buffer_copy_pitch_alignment,
         );
 
-        let mut down_buffer =
-            unsafe { self.device.create_buffer(down_size, b::Usage::TRANSFER_DST) }.unwrap();
+        let mut down_buffer = unsafe {
+            self.device.create_buffer(
+                down_size,
+                b::Usage::TRANSFER_DST,
+                memory::SparseFlags::empty(),
+            )
+        }
+        .unwrap();
         let down_req = unsafe { self.device.get_buffer_requirements(&down_buffer) };
         let download_type = *self
             .download_types
@@ -1616,8 +1658,14 @@ impl<B: hal::Backend> Scene<B> {
         let row_pitch = align(width_bytes, limits.optimal_buffer_copy_pitch_alignment);
         let down_size = (row_pitch * height * depth as u64) / block_height as u64;
 
-        let mut down_buffer =
-            unsafe { self.device.create_buffer(down_size, b::Usage::TRANSFER_DST) }.unwrap();
+        let mut down_buffer = unsafe {
+            self.device.create_buffer(
+                down_size,
+                b::Usage::TRANSFER_DST,
+                memory::SparseFlags::empty(),
+            )
+        }
+        .unwrap();
         let down_req = unsafe { self.device.get_buffer_requirements(&down_buffer) };
         let download_type = *self
             .download_types
@@ -1721,35 +1769,86 @@ impl<B: hal::Backend> Scene<B> {
         }
     }
 
-    pub fn measure_time(&self) -> u32 {
-        let mut results = vec![0u32; 2];
+    /// Returns the time spent executing the scene in nanoseconds, or 0 if timestamps
+    /// are not supported.
+    pub fn measure_time(&self) -> u64 {
+        let mut results = vec![0u64; 2];
         if let Some(ref pool) = self.query_pool {
             unsafe {
                 self.device.wait_idle().unwrap();
-                let raw_data = slice::from_raw_parts_mut(results.as_mut_ptr() as *mut u8, 4 * 2);
+                let raw_data = slice::from_raw_parts_mut(results.as_mut_ptr() as *mut u8, 8 * 2);
                 self.device
-                    .get_query_pool_results(pool, 0 .. 2, raw_data, 4, query::ResultFlags::empty())
+                    .get_query_pool_results(pool, 0 .. 2, raw_data, 8, query::ResultFlags::BITS_64)
                     .unwrap();
             }
         }
-        results[1] - results[0]
+        query::timestamp_delta_ns(
+            results[0],
+            results[1],
+            self.timestamp_valid_bits,
+            self.limits.timestamp_period,
+        )
     }
 }
 
 impl<B: hal::Backend> Drop for Scene<B> {
     fn drop(&mut self) {
         unsafe {
+            // Submissions may still reference the upload buffers.
+            let _ = self.device.wait_idle();
             for (_, (buffer, memory)) in self.upload_buffers.drain() {
                 self.device.destroy_buffer(buffer);
                 self.device.free_memory(memory);
             }
-            //TODO: free those properly
-            let _ = &self.queue_group;
+
+            // Command buffers are freed along with their pool.
             self.device
                 .destroy_command_pool(self.command_pool.take().unwrap());
             if let Some(pool) = self.query_pool.take() {
                 self.device.destroy_query_pool(pool);
             }
+
+            let resources = &mut self.resources;
+            for (_, pipeline) in resources.graphics_pipelines.drain() {
+                self.device.destroy_graphics_pipeline(pipeline);
+            }
+            for (_, (_, pipeline)) in resources.compute_pipelines.drain() {
+                self.device.destroy_compute_pipeline(pipeline);
+            }
+            for (_, layout) in resources.pipeline_layouts.drain() {
+                self.device.destroy_pipeline_layout(layout);
+            }
+            // Descriptor sets are freed along with their pools.
+            resources.desc_sets.clear();
+            for (_, pool) in resources.desc_pools.drain() {
+                self.device.destroy_descriptor_pool(pool);
+            }
+            for (_, (_, layout)) in resources.desc_set_layouts.drain() {
+                self.device.destroy_descriptor_set_layout(layout);
+            }
+            for (_, shader) in resources.shaders.drain() {
+                self.device.destroy_shader_module(shader);
+            }
+            for (_, framebuffer) in resources.framebuffers.drain() {
+                self.device.destroy_framebuffer(framebuffer.handle);
+            }
+            for (_, render_pass) in resources.render_passes.drain() {
+                self.device.destroy_render_pass(render_pass.handle);
+            }
+            for (_, sampler) in resources.samplers.drain() {
+                self.device.destroy_sampler(sampler);
+            }
+            for (_, view) in resources.image_views.drain() {
+                self.device.destroy_image_view(view.handle);
+            }
+            for (_, image) in resources.images.drain() {
+                self.device.destroy_image(image.handle);
+                self.device.free_memory(image.memory);
+            }
+            for (_, buffer) in resources.buffers.drain() {
+                self.device.destroy_buffer(buffer.handle);
+                self.device.free_memory(buffer.memory);
+            }
         }
     }
 }
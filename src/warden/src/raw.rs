@@ -50,6 +50,8 @@ pub struct Subpass {
     pub preserves: Vec<String>,
     #[serde(default)]
     pub resolves: Vec<AttachmentRef>,
+    #[serde(default)]
+    pub view_mask: hal::pass::ViewMask,
 }
 
 #[derive(Debug, Deserialize)]
@@ -109,6 +111,8 @@ pub enum Resource {
         attachments: HashMap<String, hal::pass::Attachment>,
         subpasses: HashMap<String, Subpass>,
         dependencies: Vec<SubpassDependency>,
+        #[serde(default)]
+        correlation_masks: Vec<hal::pass::ViewMask>,
     },
     Shader(String),
     DescriptorSetLayout {